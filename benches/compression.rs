@@ -0,0 +1,131 @@
+//! Benchmark to measure the improvement of packet compression with a zstd dictionary
+//!
+//! The packets are recorded from the server while it replicates entity updates to a client;
+//! the first half of the packets is used to train the dictionary, and the second half to measure
+//! the compression ratio.
+use std::sync::{Arc, OnceLock};
+
+use bevy::prelude::default;
+use bevy::utils::Duration;
+use divan::counter::BytesCount;
+use divan::Bencher;
+use lightyear::client::sync::SyncConfig;
+use lightyear::prelude::client::{InterpolationConfig, PredictionConfig};
+use lightyear::prelude::server::{NetConfig, Replicate, ServerConfig};
+use lightyear::prelude::{DictionarySampler, SharedConfig, TickConfig};
+use lightyear_benches::local_stepper::{LocalBevyStepper, Step};
+use lightyear_benches::protocol::*;
+
+const NUM_ENTITIES: usize = 100;
+const NUM_FRAMES: usize = 400;
+const DICTIONARY_SIZE: usize = 16 * 1024;
+const LEVEL: i32 = 3;
+
+fn main() {
+    let (train, test) = packets();
+    let dictionary = dictionary();
+    let raw: usize = test.iter().map(Vec::len).sum();
+    let without = compressed_size(test, None);
+    let with = compressed_size(test, Some(dictionary));
+    println!(
+        "{} packets ({} used for training), {raw} bytes: {without} bytes compressed without dictionary ({:.2}), {with} bytes with a {} bytes dictionary ({:.2})",
+        test.len(),
+        train.len(),
+        without as f32 / raw as f32,
+        dictionary.len(),
+        with as f32 / raw as f32,
+    );
+    divan::main();
+}
+
+/// Record the packets sent by the server while replicating entity updates
+fn record_packets() -> Vec<Vec<u8>> {
+    let frame_duration = Duration::from_secs_f64(1.0 / 60.0);
+    let tick_duration = Duration::from_secs_f64(1.0 / 64.0);
+    let shared_config = SharedConfig {
+        tick: TickConfig::new(tick_duration),
+        ..default()
+    };
+    let mut stepper = LocalBevyStepper::new(
+        1,
+        shared_config,
+        SyncConfig::default(),
+        PredictionConfig::default(),
+        InterpolationConfig::default(),
+        frame_duration,
+    );
+    let sampler = DictionarySampler::new(usize::MAX);
+    for net in stepper
+        .server_app
+        .world_mut()
+        .resource_mut::<ServerConfig>()
+        .net
+        .iter_mut()
+    {
+        if let NetConfig::Netcode { io, .. } = net {
+            io.sender_middleware.push(Arc::new(sampler.clone()));
+        }
+    }
+    stepper.init();
+
+    let entities: Vec<_> = stepper
+        .server_app
+        .world_mut()
+        .spawn_batch((0..NUM_ENTITIES).map(|i| (Component1(i as f32), Replicate::default())))
+        .collect();
+    for frame in 0..NUM_FRAMES {
+        // update a few entities every frame
+        for entity in entities.iter().skip(frame % 4).step_by(4) {
+            stepper
+                .server_app
+                .world_mut()
+                .get_mut::<Component1>(*entity)
+                .unwrap()
+                .0 += 1.0;
+        }
+        stepper.frame_step();
+    }
+    sampler.samples()
+}
+
+/// Packets used to train the dictionary, and packets used to measure the compression
+fn packets() -> (&'static [Vec<u8>], &'static [Vec<u8>]) {
+    static PACKETS: OnceLock<Vec<Vec<u8>>> = OnceLock::new();
+    let packets = PACKETS.get_or_init(record_packets);
+    packets.split_at(packets.len() / 2)
+}
+
+fn dictionary() -> &'static [u8] {
+    static DICTIONARY: OnceLock<Vec<u8>> = OnceLock::new();
+    DICTIONARY
+        .get_or_init(|| lightyear::prelude::train_dictionary(packets().0, DICTIONARY_SIZE).unwrap())
+}
+
+fn compressor(dictionary: Option<&[u8]>) -> zstd::bulk::Compressor<'static> {
+    match dictionary {
+        Some(dictionary) => zstd::bulk::Compressor::with_dictionary(LEVEL, dictionary),
+        None => zstd::bulk::Compressor::new(LEVEL),
+    }
+    .unwrap()
+}
+
+fn compressed_size(packets: &[Vec<u8>], dictionary: Option<&[u8]>) -> usize {
+    let mut compressor = compressor(dictionary);
+    packets
+        .iter()
+        .map(|packet| compressor.compress(packet).unwrap().len())
+        .sum()
+}
+
+#[divan::bench(args = [false, true])]
+fn compress(bencher: Bencher, with_dictionary: bool) {
+    let packets = packets().1;
+    let dictionary = with_dictionary.then(dictionary);
+    let bytes: usize = packets.iter().map(Vec::len).sum();
+    let mut compressor = compressor(dictionary);
+    bencher.counter(BytesCount::new(bytes)).bench_local(|| {
+        for packet in packets {
+            divan::black_box(compressor.compress(packet).unwrap());
+        }
+    });
+}
@@ -0,0 +1,71 @@
+//! Benchmark to measure the allocations saved by pooling the replication metadata of despawned entities
+//!
+//! The server spawns and despawns entities every frame (for example projectiles); divan's [`AllocProfiler`]
+//! reports the number of allocations per spawn/despawn cycle with and without
+//! [`ReplicationConfig::entity_pooling`](lightyear::prelude::ReplicationConfig::entity_pooling).
+use bevy::prelude::default;
+use bevy::utils::Duration;
+use divan::{AllocProfiler, Bencher};
+use lightyear::client::sync::SyncConfig;
+use lightyear::prelude::client::{InterpolationConfig, PredictionConfig};
+use lightyear::prelude::server::{Replicate, ServerConfig};
+use lightyear::prelude::{SharedConfig, TickConfig};
+use lightyear_benches::local_stepper::{LocalBevyStepper, Step};
+use lightyear_benches::protocol::*;
+
+#[global_allocator]
+static ALLOC: AllocProfiler = AllocProfiler::system();
+
+const NUM_ENTITIES: usize = 1000;
+
+fn main() {
+    divan::main();
+}
+
+fn stepper(entity_pooling: bool) -> LocalBevyStepper {
+    let frame_duration = Duration::from_secs_f64(1.0 / 60.0);
+    let tick_duration = Duration::from_secs_f64(1.0 / 64.0);
+    let shared_config = SharedConfig {
+        tick: TickConfig::new(tick_duration),
+        ..default()
+    };
+    let mut stepper = LocalBevyStepper::new(
+        1,
+        shared_config,
+        SyncConfig::default(),
+        PredictionConfig::default(),
+        InterpolationConfig::default(),
+        frame_duration,
+    );
+    // the server connections are built from the config when the server starts
+    stepper
+        .server_app
+        .world_mut()
+        .resource_mut::<ServerConfig>()
+        .replication
+        .entity_pooling = entity_pooling;
+    stepper.init();
+    stepper
+}
+
+/// Spawn and despawn the entities, so that the next cycle can reuse the pooled metadata
+fn spawn_despawn_cycle(stepper: &mut LocalBevyStepper) {
+    let entities: Vec<_> = stepper
+        .server_app
+        .world_mut()
+        .spawn_batch(vec![(Component1(0.0), Replicate::default()); NUM_ENTITIES])
+        .collect();
+    stepper.frame_step();
+    for entity in entities {
+        stepper.server_app.world_mut().despawn(entity);
+    }
+    stepper.frame_step();
+}
+
+#[divan::bench(args = [false, true])]
+fn spawn_despawn_churn(bencher: Bencher, entity_pooling: bool) {
+    let mut stepper = stepper(entity_pooling);
+    // fill the pool
+    spawn_despawn_cycle(&mut stepper);
+    bencher.bench_local(|| spawn_despawn_cycle(&mut stepper));
+}
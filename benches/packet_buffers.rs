@@ -0,0 +1,75 @@
+//! Benchmark to measure the allocations saved by reusing the packet buffers
+//!
+//! The server replicates entity updates to several clients; divan's [`AllocProfiler`] reports
+//! the number of allocations per frame with and without the packet buffer pool.
+use bevy::prelude::default;
+use bevy::utils::Duration;
+use divan::{AllocProfiler, Bencher};
+use lightyear::client::sync::SyncConfig;
+use lightyear::prelude::client::{InterpolationConfig, PredictionConfig};
+use lightyear::prelude::server::{Replicate, ServerConfig};
+use lightyear::prelude::{client, SharedConfig, TickConfig};
+use lightyear_benches::local_stepper::{LocalBevyStepper, Step};
+use lightyear_benches::protocol::*;
+
+#[global_allocator]
+static ALLOC: AllocProfiler = AllocProfiler::system();
+
+const NUM_CLIENTS: usize = 4;
+const NUM_ENTITIES: usize = 100;
+
+fn main() {
+    divan::main();
+}
+
+fn stepper(packet_buffer_pool_size: usize) -> LocalBevyStepper {
+    let frame_duration = Duration::from_secs_f64(1.0 / 60.0);
+    let tick_duration = Duration::from_secs_f64(1.0 / 64.0);
+    let shared_config = SharedConfig {
+        tick: TickConfig::new(tick_duration),
+        ..default()
+    };
+    let mut stepper = LocalBevyStepper::new(
+        NUM_CLIENTS,
+        shared_config,
+        SyncConfig::default(),
+        PredictionConfig::default(),
+        InterpolationConfig::default(),
+        frame_duration,
+    );
+    stepper
+        .server_app
+        .world_mut()
+        .resource_mut::<ServerConfig>()
+        .packet
+        .packet_buffer_pool_size = packet_buffer_pool_size;
+    for client_app in stepper.client_apps.values_mut() {
+        client_app
+            .world_mut()
+            .resource_mut::<client::ClientConfig>()
+            .packet
+            .packet_buffer_pool_size = packet_buffer_pool_size;
+    }
+    stepper.init();
+    stepper
+        .server_app
+        .world_mut()
+        .spawn_batch((0..NUM_ENTITIES).map(|i| (Component1(i as f32), Replicate::default())));
+    // replicate the spawns and fill the pools
+    for _ in 0..10 {
+        stepper.frame_step();
+    }
+    stepper
+}
+
+#[divan::bench(args = [0, 8])]
+fn send_updates(bencher: Bencher, packet_buffer_pool_size: usize) {
+    let mut stepper = stepper(packet_buffer_pool_size);
+    bencher.bench_local(|| {
+        let world = stepper.server_app.world_mut();
+        for mut component in world.query::<&mut Component1>().iter_mut(world) {
+            component.0 += 1.0;
+        }
+        stepper.frame_step();
+    });
+}
@@ -0,0 +1,117 @@
+//! Benchmark to measure how the per-tick replication cost of the server scales with the number
+//! of connected clients and the number of replicated entities.
+//!
+//! The clients are connected to the server via in-memory channels, and the time is advanced manually,
+//! so that we only measure the cost of the server update.
+//!
+//! This is useful to catch regressions where the cost becomes O(clients * entities) in a hot path.
+#![allow(unused_imports)]
+
+use bevy::prelude::{default, With};
+use bevy::utils::Duration;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use lightyear::prelude::server::{NetServer, Replicate, ServerConnections};
+use lightyear::prelude::Replicating;
+use lightyear_benches::local_stepper::{LocalBevyStepper, Step as LocalStep};
+use lightyear_benches::profiler::FlamegraphProfiler;
+use lightyear_benches::protocol::*;
+use std::time::Instant;
+
+criterion_group!(
+    name = scaling_benches;
+    config = Criterion::default().with_profiler(FlamegraphProfiler::new(3000));
+    targets = server_tick_update_scaling,
+);
+criterion_main!(scaling_benches);
+
+const NUM_CLIENTS: &[usize] = &[1, 4, 16, 32];
+const NUM_ENTITIES: &[usize] = &[100, 1000, 5000];
+
+/// Total number of bytes sent by the server since it started
+fn server_bytes_sent(stepper: &LocalBevyStepper) -> usize {
+    stepper
+        .server_app
+        .world()
+        .resource::<ServerConnections>()
+        .servers
+        .iter()
+        .filter_map(|server| server.io())
+        .map(|io| io.stats().bytes_sent)
+        .sum()
+}
+
+/// Modify the replicated component of every entity, so that it has to be sent to every client
+fn update_all_entities(stepper: &mut LocalBevyStepper, value: f32) {
+    let world = stepper.server_app.world_mut();
+    let mut query = world.query_filtered::<&mut Component1, With<Replicating>>();
+    for mut component in query.iter_mut(world) {
+        component.0 = value;
+    }
+}
+
+/// Create a stepper with `num_clients` connected clients and `num_entities` replicated entities,
+/// which have already been spawned on every client
+fn setup(num_clients: usize, num_entities: usize) -> LocalBevyStepper {
+    let mut stepper = LocalBevyStepper::default_n_clients(num_clients);
+    let entities = vec![(Component1(0.0), Replicate::default()); num_entities];
+    stepper.server_app.world_mut().spawn_batch(entities);
+    // let the spawns be replicated and acked
+    for _ in 0..10 {
+        stepper.frame_step();
+    }
+    stepper
+}
+
+/// Measure the duration of one server tick where all entities are updated, for
+/// a varying number of clients and entities.
+///
+/// The throughput reported is the number of bytes sent by the server during one tick.
+fn server_tick_update_scaling(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("scaling/server_tick_update");
+    group.warm_up_time(Duration::from_millis(500));
+    group.measurement_time(Duration::from_millis(4000));
+    group.sample_size(20);
+    for num_clients in NUM_CLIENTS.iter().copied() {
+        for num_entities in NUM_ENTITIES.iter().copied() {
+            let mut stepper = setup(num_clients, num_entities);
+            let mut value = 1.0;
+
+            // run a tick outside the measurement to get the bandwidth used per tick
+            update_all_entities(&mut stepper, value);
+            let bytes_before = server_bytes_sent(&stepper);
+            stepper.advance_time(stepper.frame_duration);
+            stepper.server_update();
+            let bytes_per_tick = server_bytes_sent(&stepper) - bytes_before;
+            stepper.client_update();
+            println!(
+                "{num_clients} clients, {num_entities} entities: {bytes_per_tick} bytes sent per tick"
+            );
+            group.throughput(Throughput::Bytes(bytes_per_tick as u64));
+
+            group.bench_with_input(
+                BenchmarkId::new(format!("{num_clients}_clients"), num_entities),
+                &num_entities,
+                |bencher, _| {
+                    bencher.iter_custom(|iter| {
+                        let mut elapsed = Duration::ZERO;
+                        for _ in 0..iter {
+                            value += 1.0;
+                            update_all_entities(&mut stepper, value);
+                            stepper.advance_time(stepper.frame_duration);
+
+                            let instant = Instant::now();
+                            // buffer and send replication messages
+                            stepper.server_update();
+                            elapsed += instant.elapsed();
+
+                            // the clients receive the updates and send back acks
+                            stepper.client_update();
+                        }
+                        elapsed
+                    });
+                },
+            );
+        }
+    }
+    group.finish();
+}
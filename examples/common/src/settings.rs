@@ -76,6 +76,8 @@ impl Conditioner {
             incoming_latency: Duration::from_millis(self.latency_ms as u64),
             incoming_jitter: Duration::from_millis(self.jitter_ms as u64),
             incoming_loss: self.packet_loss,
+            duplicate_chance: 0.0,
+            reorder_chance: 0.0,
         }
     }
 }
@@ -149,6 +151,8 @@ pub(crate) fn build_server_netcode_config(
             incoming_latency: Duration::from_millis(c.latency_ms as u64),
             incoming_jitter: Duration::from_millis(c.jitter_ms as u64),
             incoming_loss: c.packet_loss,
+            duplicate_chance: 0.0,
+            reorder_chance: 0.0,
         })
     });
     let netcode_config = server::NetcodeConfig::default()
@@ -156,8 +160,9 @@ pub(crate) fn build_server_netcode_config(
         .with_key(shared.private_key);
     let io_config = server::IoConfig {
         transport: transport_config,
-        conditioner,
+        incoming_conditioner: conditioner,
         compression: shared.compression,
+        ..default()
     };
     server::NetConfig::Netcode {
         config: netcode_config,
@@ -261,8 +266,9 @@ pub(crate) fn build_client_netcode_config(
     let netcode_config = client::NetcodeConfig::default();
     let io_config = client::IoConfig {
         transport: transport_config,
-        conditioner,
+        incoming_conditioner: conditioner,
         compression: shared.compression,
+        ..default()
     };
     client::NetConfig::Netcode {
         auth,
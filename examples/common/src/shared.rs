@@ -14,5 +14,6 @@ pub fn shared_config(mode: Mode) -> SharedConfig {
             tick_duration: Duration::from_secs_f64(1.0 / FIXED_TIMESTEP_HZ),
         },
         mode,
+        rng_seed: None,
     }
 }
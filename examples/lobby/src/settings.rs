@@ -2,7 +2,7 @@
 use std::net::{Ipv4Addr, SocketAddr};
 
 use async_compat::Compat;
-use bevy::prelude::Resource;
+use bevy::prelude::{default, Resource};
 use bevy::tasks::IoTaskPool;
 use bevy::utils::Duration;
 use serde::{Deserialize, Serialize};
@@ -10,7 +10,9 @@ use serde::{Deserialize, Serialize};
 use lightyear::prelude::client::Authentication;
 #[cfg(not(target_family = "wasm"))]
 use lightyear::prelude::client::SteamConfig;
-use lightyear::prelude::{CompressionConfig, IoConfig, LinkConditionerConfig, TransportConfig};
+use lightyear::prelude::client::ClientTransport;
+use lightyear::prelude::server::ServerTransport;
+use lightyear::prelude::{CompressionConfig, LinkConditionerConfig};
 
 #[cfg(not(target_family = "wasm"))]
 use crate::server::Identity;
@@ -66,6 +68,8 @@ impl Conditioner {
             incoming_latency: Duration::from_millis(self.latency_ms as u64),
             incoming_jitter: Duration::from_millis(self.jitter_ms as u64),
             incoming_loss: self.packet_loss,
+            duplicate_chance: 0.0,
+            reorder_chance: 0.0,
         }
     }
 }
@@ -141,22 +145,25 @@ pub struct Settings {
 pub fn build_server_netcode_config(
     conditioner: Option<&Conditioner>,
     shared: &SharedSettings,
-    transport_config: TransportConfig,
+    transport_config: ServerTransport,
 ) -> server::NetConfig {
     let conditioner = conditioner.map_or(None, |c| {
         Some(LinkConditionerConfig {
             incoming_latency: Duration::from_millis(c.latency_ms as u64),
             incoming_jitter: Duration::from_millis(c.jitter_ms as u64),
             incoming_loss: c.packet_loss,
+            duplicate_chance: 0.0,
+            reorder_chance: 0.0,
         })
     });
     let netcode_config = server::NetcodeConfig::default()
         .with_protocol_id(shared.protocol_id)
         .with_key(shared.private_key);
-    let io_config = IoConfig {
+    let io_config = lightyear::prelude::server::IoConfig {
         transport: transport_config,
-        conditioner,
+        incoming_conditioner: conditioner,
         compression: shared.compression,
+        ..default()
     };
     server::NetConfig::Netcode {
         config: netcode_config,
@@ -176,7 +183,7 @@ pub fn get_server_net_configs(settings: &Settings) -> Vec<server::NetConfig> {
             ServerTransports::Udp { local_port } => build_server_netcode_config(
                 settings.server.conditioner.as_ref(),
                 &settings.shared,
-                TransportConfig::UdpSocket(SocketAddr::new(
+                ServerTransport::UdpSocket(SocketAddr::new(
                     Ipv4Addr::UNSPECIFIED.into(),
                     *local_port,
                 )),
@@ -202,7 +209,7 @@ pub fn get_server_net_configs(settings: &Settings) -> Vec<server::NetConfig> {
                 build_server_netcode_config(
                     settings.server.conditioner.as_ref(),
                     &settings.shared,
-                    TransportConfig::WebTransportServer {
+                    ServerTransport::WebTransportServer {
                         server_addr: SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), *local_port),
                         certificate,
                     },
@@ -211,7 +218,7 @@ pub fn get_server_net_configs(settings: &Settings) -> Vec<server::NetConfig> {
             ServerTransports::WebSocket { local_port } => crate::build_server_netcode_config(
                 settings.server.conditioner.as_ref(),
                 &settings.shared,
-                TransportConfig::WebSocketServer {
+                ServerTransport::WebSocketServer {
                     server_addr: SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), *local_port),
                 },
             ),
@@ -251,7 +258,7 @@ pub fn get_host_server_net_configs(settings: &Settings) -> Vec<server::NetConfig
             ServerTransports::Udp { local_port } => build_server_netcode_config(
                 settings.server.conditioner.as_ref(),
                 &settings.shared,
-                TransportConfig::UdpSocket(SocketAddr::new(
+                ServerTransport::UdpSocket(SocketAddr::new(
                     Ipv4Addr::UNSPECIFIED.into(),
                     *local_port,
                 )),
@@ -277,7 +284,7 @@ pub fn get_host_server_net_configs(settings: &Settings) -> Vec<server::NetConfig
                 build_server_netcode_config(
                     settings.server.conditioner.as_ref(),
                     &settings.shared,
-                    TransportConfig::WebTransportServer {
+                    ServerTransport::WebTransportServer {
                         server_addr: SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), *local_port),
                         certificate,
                     },
@@ -286,7 +293,7 @@ pub fn get_host_server_net_configs(settings: &Settings) -> Vec<server::NetConfig
             ServerTransports::WebSocket { local_port } => crate::build_server_netcode_config(
                 settings.server.conditioner.as_ref(),
                 &settings.shared,
-                TransportConfig::WebSocketServer {
+                ServerTransport::WebSocketServer {
                     server_addr: SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), *local_port),
                 },
             ),
@@ -320,7 +327,7 @@ pub fn build_client_netcode_config(
     server_addr: SocketAddr,
     conditioner: Option<&Conditioner>,
     shared: &SharedSettings,
-    transport_config: TransportConfig,
+    transport_config: ClientTransport,
 ) -> client::NetConfig {
     let conditioner = conditioner.map_or(None, |c| Some(c.build()));
     let auth = Authentication::Manual {
@@ -330,10 +337,11 @@ pub fn build_client_netcode_config(
         protocol_id: shared.protocol_id,
     };
     let netcode_config = client::NetcodeConfig::default();
-    let io_config = IoConfig {
+    let io_config = client::IoConfig {
         transport: transport_config,
-        conditioner,
+        incoming_conditioner: conditioner,
         compression: shared.compression,
+        ..default()
     };
     client::NetConfig::Netcode {
         auth,
@@ -357,14 +365,14 @@ pub fn get_client_net_config(settings: &Settings) -> client::NetConfig {
             server_addr,
             settings.client.conditioner.as_ref(),
             &settings.shared,
-            TransportConfig::UdpSocket(client_addr),
+            ClientTransport::UdpSocket(client_addr),
         ),
         ClientTransports::WebTransport { certificate_digest } => build_client_netcode_config(
             settings.client.client_id,
             server_addr,
             settings.client.conditioner.as_ref(),
             &settings.shared,
-            TransportConfig::WebTransportClient {
+            ClientTransport::WebTransportClient {
                 client_addr,
                 server_addr,
                 #[cfg(target_family = "wasm")]
@@ -376,7 +384,7 @@ pub fn get_client_net_config(settings: &Settings) -> client::NetConfig {
             server_addr,
             settings.client.conditioner.as_ref(),
             &settings.shared,
-            TransportConfig::WebSocketClient { server_addr },
+            ClientTransport::WebSocketClient { server_addr },
         ),
         #[cfg(not(target_family = "wasm"))]
         ClientTransports::Steam { app_id } => client::NetConfig::Steam {
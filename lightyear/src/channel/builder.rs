@@ -46,7 +46,11 @@ pub struct ChannelContainer {
 ///     mode: ChannelMode::UnorderedUnreliable,
 ///     direction: ChannelDirection::Bidirectional,
 ///     priority: 1.0,
+///     fragmentation: true,
 /// });
+///
+/// // or, using one of the presets
+/// app.add_channel::<MyChannel>(ChannelSettings::unordered_unreliable());
 /// ```
 pub trait Channel: 'static {
     fn get_builder(settings: ChannelSettings) -> ChannelBuilder {
@@ -125,6 +129,9 @@ pub struct ChannelSettings {
     pub send_frequency: Duration,
     /// Sets the priority of the channel. The final priority of a message will be `MessagePriority * ChannelPriority`
     pub priority: f32,
+    /// If false, messages that are too big to fit in a single packet cannot be sent on this channel,
+    /// instead of being split into fragments.
+    pub fragmentation: bool,
 }
 
 impl Default for ChannelSettings {
@@ -133,7 +140,79 @@ impl Default for ChannelSettings {
             mode: ChannelMode::UnorderedUnreliable,
             send_frequency: Duration::default(),
             priority: 1.0,
+            fragmentation: true,
+        }
+    }
+}
+
+/// Presets for the most common channel configurations
+impl ChannelSettings {
+    /// Messages may arrive out-of-order, or not at all
+    pub fn unordered_unreliable() -> Self {
+        Self::from_mode(ChannelMode::UnorderedUnreliable)
+    }
+
+    /// Messages may arrive out-of-order, or not at all, but we keep track of which messages got received
+    pub fn unordered_unreliable_with_acks() -> Self {
+        Self::from_mode(ChannelMode::UnorderedUnreliableWithAcks)
+    }
+
+    /// Only the newest message is accepted, and messages can be lost
+    pub fn sequenced_unreliable() -> Self {
+        Self::from_mode(ChannelMode::SequencedUnreliable)
+    }
+
+    /// Messages may arrive out-of-order, but are resent until they are acked
+    pub fn unordered_reliable() -> Self {
+        Self::from_mode(ChannelMode::UnorderedReliable(ReliableSettings::default()))
+    }
+
+    /// Only the newest message is accepted, and messages are resent until they are acked
+    pub fn sequenced_reliable() -> Self {
+        Self::from_mode(ChannelMode::SequencedReliable(ReliableSettings::default()))
+    }
+
+    /// Messages arrive in the order they were sent, and are resent until they are acked
+    pub fn ordered_reliable() -> Self {
+        Self::from_mode(ChannelMode::OrderedReliable(ReliableSettings::default()))
+    }
+
+    fn from_mode(mode: ChannelMode) -> Self {
+        Self {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_mode(mut self, mode: ChannelMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_send_frequency(mut self, send_frequency: Duration) -> Self {
+        self.send_frequency = send_frequency;
+        self
+    }
+
+    pub fn with_priority(mut self, priority: f32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_fragmentation(mut self, fragmentation: bool) -> Self {
+        self.fragmentation = fragmentation;
+        self
+    }
+
+    /// Update the [`ReliableSettings`] of the channel. Has no effect on unreliable channels.
+    pub fn with_reliable_settings(mut self, reliable_settings: ReliableSettings) -> Self {
+        match &mut self.mode {
+            ChannelMode::UnorderedReliable(settings)
+            | ChannelMode::SequencedReliable(settings)
+            | ChannelMode::OrderedReliable(settings) => *settings = reliable_settings,
+            _ => {}
         }
+        self
     }
 }
 
@@ -197,6 +276,12 @@ pub struct ReliableSettings {
     pub rtt_resend_factor: f32,
     /// Minimum duration to wait before resending a packet if it has not been acked
     pub rtt_resend_min_delay: Duration,
+    /// Maximum number of times a message is sent before it is considered lost.
+    /// If None, the message is resent until it is acked.
+    ///
+    /// This cannot be used with [`ChannelMode::OrderedReliable`]: the receiver would wait forever for the lost message
+    /// and never deliver the messages sent after it.
+    pub max_send_attempts: Option<u32>,
 }
 
 impl Default for ReliableSettings {
@@ -204,11 +289,27 @@ impl Default for ReliableSettings {
         Self {
             rtt_resend_factor: 1.5,
             rtt_resend_min_delay: Duration::default(),
+            max_send_attempts: None,
         }
     }
 }
 
 impl ReliableSettings {
+    pub fn with_rtt_resend_factor(mut self, rtt_resend_factor: f32) -> Self {
+        self.rtt_resend_factor = rtt_resend_factor;
+        self
+    }
+
+    pub fn with_rtt_resend_min_delay(mut self, rtt_resend_min_delay: Duration) -> Self {
+        self.rtt_resend_min_delay = rtt_resend_min_delay;
+        self
+    }
+
+    pub fn with_max_send_attempts(mut self, max_send_attempts: Option<u32>) -> Self {
+        self.max_send_attempts = max_send_attempts;
+        self
+    }
+
     pub(crate) fn resend_delay(&self, rtt: Duration) -> Duration {
         let delay = rtt.mul_f32(self.rtt_resend_factor);
         std::cmp::max(delay, self.rtt_resend_min_delay)
@@ -226,6 +327,11 @@ pub struct EntityActionsChannel;
 /// This is a Sequenced Unreliable channel
 pub struct EntityUpdatesChannel;
 
+#[derive(ChannelInternal)]
+/// Default channel to replicate the updates of components registered with [`ReplicationMode::Reliable`](crate::prelude::ReplicationMode::Reliable)
+/// This is an Unordered Reliable channel
+pub struct EntityReliableUpdatesChannel;
+
 /// Default channel to send pings. This is a Sequenced Unreliable channel, because
 /// there is no point in getting older pings.
 #[derive(ChannelInternal)]
@@ -236,11 +342,38 @@ pub struct PingChannel;
 #[derive(ChannelInternal)]
 pub struct PongChannel;
 
+/// Channel used by the server to send [`TimeSync`](crate::shared::ping::message::TimeSync) stamps.
+/// This is a Sequenced Unreliable channel, because only the most recent stamp is useful.
+#[derive(ChannelInternal)]
+pub struct TimeSyncChannel;
+
 #[derive(ChannelInternal)]
 /// Default channel to send inputs from client to server. This is a Sequenced Unreliable channel.
 pub struct InputChannel;
 
+#[derive(ChannelInternal)]
+/// Channel used by the server to acknowledge the inputs it received from the client.
+/// This is a Sequenced Unreliable channel, because only the most recent ack is useful.
+pub struct InputAckChannel;
+
 #[derive(ChannelInternal)]
 /// Channel to send messages related to Authority transfers
 /// This is an Ordered Reliable channel
 pub struct AuthorityChannel;
+
+#[derive(ChannelInternal)]
+/// Channel used by the client to confirm that it applied component updates
+/// (see [`ComponentRegistration::add_application_ack`](crate::protocol::component::ComponentRegistration::add_application_ack)).
+/// This is an Unordered Reliable channel
+pub struct ComponentAppliedChannel;
+
+#[derive(ChannelInternal)]
+/// Channel used by the server to notify a client that its [initial sync](crate::server::initial_sync) is complete.
+/// This is an Unordered Reliable channel
+pub struct InitialSyncChannel;
+
+#[derive(ChannelInternal)]
+/// Channel used by the server to send the seed of the [`DeterministicRng`](crate::prelude::DeterministicRng)
+/// to a client when it connects.
+/// This is an Unordered Reliable channel
+pub struct RngSeedChannel;
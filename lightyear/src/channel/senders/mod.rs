@@ -56,8 +56,25 @@ pub trait ChannelSend {
     /// has been lost by the remote peer
     fn subscribe_nacks(&mut self) -> Receiver<MessageId>;
 
+    /// Create a new receiver that will receive a message id when a sent message is considered lost
+    /// and won't be sent again
+    fn subscribe_lost(&mut self) -> Receiver<MessageId> {
+        crossbeam_channel::never()
+    }
+
     /// Send nacks to the subscribers of nacks
     fn send_nacks(&mut self, nack: MessageId);
+
+    /// Returns true if the channel still has messages that were not sent, or (for reliable channels)
+    /// that were not acked by the remote peer
+    fn has_messages_to_send(&self) -> bool;
+
+    /// Number of messages that were not sent, or (for reliable channels) that were not acked by the remote peer
+    fn num_pending_messages(&self) -> usize;
+
+    /// Returns true if the channel will send its buffered messages during the current frame
+    /// (i.e. if its `send_frequency` interval has elapsed)
+    fn is_ready_to_send(&self) -> bool;
 }
 
 /// Enum dispatch lets us derive ChannelSend on each enum variant
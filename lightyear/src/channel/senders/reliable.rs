@@ -40,6 +40,8 @@ pub struct UnackedMessageWithPriority {
     pub unacked_message: UnackedMessage,
     pub base_priority: f32,
     pub accumulated_priority: f32,
+    /// Number of times the message has been sent
+    pub send_attempts: u32,
 }
 
 /// A sender that makes sure to resend messages until it receives an ack
@@ -69,6 +71,8 @@ pub struct ReliableSender {
     ack_senders: Vec<Sender<MessageId>>,
     /// List of senders that want to be notified when a message is lost
     nack_senders: Vec<Sender<MessageId>>,
+    /// Notified when a message reached the maximum number of send attempts
+    lost_senders: Vec<Sender<MessageId>>,
     current_rtt: Duration,
     current_time: WrappedTime,
     /// Internal timer to determine if the channel is ready to send messages
@@ -95,6 +99,7 @@ impl ReliableSender {
             fragment_sender: FragmentSender::new(),
             ack_senders: vec![],
             nack_senders: vec![],
+            lost_senders: vec![],
             current_rtt: Duration::default(),
             current_time: WrappedTime::default(),
             timer,
@@ -153,6 +158,7 @@ impl ChannelSend for ReliableSender {
             // store with 0.0 accumulated priority because priority gets accumulated when we collect the messages
             // for sending (even the first time the message is sent)
             accumulated_priority: 0.0,
+            send_attempts: 0,
         };
         self.unacked_messages
             .insert(message_id, unacked_message_with_priority);
@@ -164,7 +170,7 @@ impl ChannelSend for ReliableSender {
     /// to be sent
     /// The messages to be sent need to have been collected prior to this point.
     fn send_packet(&mut self) -> (VecDeque<SendMessage>, VecDeque<SendMessage>) {
-        if self.timer.as_ref().is_some_and(|t| !t.finished()) {
+        if !self.is_ready_to_send() {
             return (VecDeque::new(), VecDeque::new());
         }
 
@@ -184,8 +190,31 @@ impl ChannelSend for ReliableSender {
             }
         };
 
+        let mut lost_messages = vec![];
         // Iterate through all unacked messages, oldest message ids first
         for (message_id, unacked_message_with_priority) in self.unacked_messages.iter_mut() {
+            let needs_send = match &unacked_message_with_priority.unacked_message {
+                UnackedMessage::Single { last_sent, .. } => should_send(last_sent),
+                UnackedMessage::Fragmented(fragment_acks) => fragment_acks
+                    .iter()
+                    .any(|f| !f.acked && should_send(&f.last_sent)),
+            };
+            if needs_send {
+                // give up on the message if it has already been sent too many times
+                if self
+                    .reliable_settings
+                    .max_send_attempts
+                    .is_some_and(|max| unacked_message_with_priority.send_attempts >= max)
+                {
+                    trace!(
+                        ?message_id,
+                        "Reliable message reached the maximum number of send attempts"
+                    );
+                    lost_messages.push(*message_id);
+                    continue;
+                }
+                unacked_message_with_priority.send_attempts += 1;
+            }
             // accumulate the priority for all messages (including the ones that were just added, since we set the accumulated priority to 0.0)
             unacked_message_with_priority.accumulated_priority +=
                 unacked_message_with_priority.base_priority * self.priority_multiplier;
@@ -243,6 +272,14 @@ impl ChannelSend for ReliableSender {
             }
         }
 
+        for message_id in lost_messages {
+            self.unacked_messages.remove(&message_id);
+            self.send_nacks(message_id);
+            for sender in &self.lost_senders {
+                sender.send(message_id).unwrap();
+            }
+        }
+
         // TODO: is this message_ids_to_send even useful? in which situation would we send the same message twice?
         // right now, we send everything; so we can reset
         self.message_ids_to_send.clear();
@@ -327,12 +364,30 @@ impl ChannelSend for ReliableSender {
         receiver
     }
 
+    fn subscribe_lost(&mut self) -> Receiver<MessageId> {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        self.lost_senders.push(sender);
+        receiver
+    }
+
     /// Send nacks to the subscribers of nacks
     fn send_nacks(&mut self, nack: MessageId) {
         for sender in &self.nack_senders {
             sender.send(nack).unwrap();
         }
     }
+
+    fn has_messages_to_send(&self) -> bool {
+        !self.unacked_messages.is_empty()
+    }
+
+    fn num_pending_messages(&self) -> usize {
+        self.unacked_messages.len()
+    }
+
+    fn is_ready_to_send(&self) -> bool {
+        !self.timer.as_ref().is_some_and(|t| !t.finished())
+    }
 }
 
 #[cfg(test)]
@@ -351,6 +406,7 @@ mod tests {
             ReliableSettings {
                 rtt_resend_factor: 1.5,
                 rtt_resend_min_delay: Duration::from_millis(100),
+                max_send_attempts: None,
             },
             Duration::default(),
         );
@@ -396,4 +452,31 @@ mod tests {
         let (single, _) = sender.send_packet();
         assert_eq!(single.len(), 0);
     }
+
+    #[test]
+    fn test_reliable_sender_max_send_attempts() {
+        let mut sender = ReliableSender::new(
+            ReliableSettings::default()
+                .with_rtt_resend_min_delay(Duration::from_millis(100))
+                .with_max_send_attempts(Some(2)),
+            Duration::default(),
+        );
+        let nacks = sender.subscribe_nacks();
+        sender.current_time = WrappedTime::new(0);
+        sender.buffer_send(Bytes::from("hello"), 1.0).unwrap();
+
+        // the message is sent, then resent once
+        let (single, _) = sender.send_packet();
+        assert_eq!(single.len(), 1);
+        sender.current_time += Duration::from_millis(200);
+        let (single, _) = sender.send_packet();
+        assert_eq!(single.len(), 1);
+
+        // after that, the message is considered lost
+        sender.current_time += Duration::from_millis(200);
+        let (single, _) = sender.send_packet();
+        assert_eq!(single.len(), 0);
+        assert!(sender.unacked_messages.is_empty());
+        assert_eq!(nacks.try_recv(), Ok(MessageId(0)));
+    }
 }
@@ -90,7 +90,7 @@ impl ChannelSend for UnorderedUnreliableSender {
 
     /// Take messages from the buffer of messages to be sent, and build a list of packets to be sent
     fn send_packet(&mut self) -> (VecDeque<SendMessage>, VecDeque<SendMessage>) {
-        if self.timer.as_ref().is_some_and(|t| !t.finished()) {
+        if !self.is_ready_to_send() {
             return (VecDeque::new(), VecDeque::new());
         }
         (
@@ -123,6 +123,18 @@ impl ChannelSend for UnorderedUnreliableSender {
             sender.send(nack).unwrap();
         }
     }
+
+    fn has_messages_to_send(&self) -> bool {
+        !self.single_messages_to_send.is_empty() || !self.fragmented_messages_to_send.is_empty()
+    }
+
+    fn num_pending_messages(&self) -> usize {
+        self.single_messages_to_send.len() + self.fragmented_messages_to_send.len()
+    }
+
+    fn is_ready_to_send(&self) -> bool {
+        !self.timer.as_ref().is_some_and(|t| !t.finished())
+    }
 }
 
 #[cfg(test)]
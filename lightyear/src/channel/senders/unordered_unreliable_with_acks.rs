@@ -107,7 +107,7 @@ impl ChannelSend for UnorderedUnreliableWithAcksSender {
 
     /// Take messages from the buffer of messages to be sent, and build a list of packets to be sent
     fn send_packet(&mut self) -> (VecDeque<SendMessage>, VecDeque<SendMessage>) {
-        if self.timer.as_ref().is_some_and(|t| !t.finished()) {
+        if !self.is_ready_to_send() {
             return (VecDeque::new(), VecDeque::new());
         }
         (
@@ -157,12 +157,29 @@ impl ChannelSend for UnorderedUnreliableWithAcksSender {
         receiver
     }
 
+    /// Nacked messages are never resent
+    fn subscribe_lost(&mut self) -> Receiver<MessageId> {
+        self.subscribe_nacks()
+    }
+
     /// Send nacks to the subscribers of nacks
     fn send_nacks(&mut self, nack: MessageId) {
         for sender in &self.nack_senders {
             sender.send(nack).unwrap();
         }
     }
+
+    fn has_messages_to_send(&self) -> bool {
+        !self.single_messages_to_send.is_empty() || !self.fragmented_messages_to_send.is_empty()
+    }
+
+    fn num_pending_messages(&self) -> usize {
+        self.single_messages_to_send.len() + self.fragmented_messages_to_send.len()
+    }
+
+    fn is_ready_to_send(&self) -> bool {
+        !self.timer.as_ref().is_some_and(|t| !t.finished())
+    }
 }
 
 #[cfg(test)]
@@ -1,14 +1,19 @@
 //! Defines client-specific configuration options
 use bevy::prelude::Resource;
 use bevy::reflect::Reflect;
+use bevy::utils::Duration;
 use governor::Quota;
 use nonzero_ext::nonzero;
+use std::sync::Arc;
 
 use crate::client::input::native::InputConfig;
 use crate::client::interpolation::plugin::InterpolationConfig;
 use crate::client::prediction::plugin::PredictionConfig;
 use crate::client::sync::SyncConfig;
 use crate::connection::client::NetConfig;
+use crate::packet::congestion::CongestionConfig;
+use crate::packet::inspector::PacketInspector;
+use crate::packet::mtu_discovery::MtuDiscoveryConfig;
 use crate::shared::config::SharedConfig;
 use crate::shared::ping::manager::PingConfig;
 use crate::shared::replication::plugin::ReplicationConfig;
@@ -26,6 +31,11 @@ pub struct NetcodeConfig {
     /// Set the duration in seconds after which the `ConnectToken` generated by the Client
     /// will expire. Set a negative value for the token to never expire.
     pub token_expire_secs: i32,
+    /// Application data (for example the name chosen by the player) that will be sent to the server
+    /// when connecting, and that will be available in the server's `ConnectEvent`.
+    ///
+    /// It must not be larger than [`MAX_CONNECT_PAYLOAD_BYTES`](crate::connection::netcode::MAX_CONNECT_PAYLOAD_BYTES) bytes.
+    pub connect_payload: Vec<u8>,
 }
 
 impl Default for NetcodeConfig {
@@ -35,19 +45,26 @@ impl Default for NetcodeConfig {
             keepalive_packet_send_rate: 1.0 / 10.0,
             client_timeout_secs: -1,
             token_expire_secs: 30,
+            connect_payload: Vec::new(),
         }
     }
 }
 
 impl NetcodeConfig {
+    pub fn with_connect_payload(mut self, connect_payload: Vec<u8>) -> Self {
+        self.connect_payload = connect_payload;
+        self
+    }
+
     pub(crate) fn build(&self) -> crate::connection::netcode::ClientConfig<()> {
         crate::connection::netcode::ClientConfig::default()
             .num_disconnect_packets(self.num_disconnect_packets)
             .packet_send_rate(self.keepalive_packet_send_rate)
+            .connect_payload(self.connect_payload.clone())
     }
 }
 
-#[derive(Clone, Copy, Reflect)]
+#[derive(Clone, Reflect)]
 #[reflect(from_reflect = false)]
 pub struct PacketConfig {
     /// After how many multiples of RTT do we consider a packet to be lost?
@@ -60,6 +77,24 @@ pub struct PacketConfig {
     pub send_bandwidth_cap: Quota,
     /// If false, there is no bandwidth cap and all messages are sent as soon as possible
     pub bandwidth_cap_enabled: bool,
+    /// If set, probe each connection to find the largest packet size that can be used,
+    /// instead of always using [`MAX_PACKET_SIZE`](crate::connection::netcode::MAX_PACKET_SIZE)
+    pub mtu_discovery: Option<MtuDiscoveryConfig>,
+    /// If set, reduce the replication send rate when the packet loss of the connection is high.
+    ///
+    /// This is disabled by default. When it is enabled, the replication groups with a priority lower than
+    /// [`CongestionConfig::priority_threshold`] (which includes the groups with the default priority)
+    /// send their updates less often while the connection is congested.
+    pub congestion_control: Option<CongestionConfig>,
+    /// If set, the inspector is called with the decoded contents of every packet sent to or received from the server
+    #[reflect(ignore)]
+    pub packet_inspector: Option<Arc<dyn PacketInspector>>,
+    /// Number of packet buffers that are kept after the packets are sent, to serialize the
+    /// next packets without allocating.
+    ///
+    /// Each connection has its own pool, so the pools are never shared between systems.
+    /// Set to 0 to allocate a new buffer for every packet.
+    pub packet_buffer_pool_size: usize,
 }
 
 impl Default for PacketConfig {
@@ -69,6 +104,10 @@ impl Default for PacketConfig {
             // 56 KB/s bandwidth cap
             send_bandwidth_cap: Quota::per_second(nonzero!(56000u32)),
             bandwidth_cap_enabled: false,
+            mtu_discovery: None,
+            congestion_control: None,
+            packet_inspector: None,
+            packet_buffer_pool_size: 8,
         }
     }
 }
@@ -89,6 +128,102 @@ impl PacketConfig {
         self.bandwidth_cap_enabled = true;
         self
     }
+
+    pub fn with_mtu_discovery(mut self, mtu_discovery: MtuDiscoveryConfig) -> Self {
+        self.mtu_discovery = Some(mtu_discovery);
+        self
+    }
+
+    pub fn with_congestion_control(mut self, congestion_control: CongestionConfig) -> Self {
+        self.congestion_control = Some(congestion_control);
+        self
+    }
+
+    pub fn disable_congestion_control(mut self) -> Self {
+        self.congestion_control = None;
+        self
+    }
+
+    pub fn with_packet_inspector(mut self, packet_inspector: Arc<dyn PacketInspector>) -> Self {
+        self.packet_inspector = Some(packet_inspector);
+        self
+    }
+
+    pub fn with_packet_buffer_pool_size(mut self, packet_buffer_pool_size: usize) -> Self {
+        self.packet_buffer_pool_size = packet_buffer_pool_size;
+        self
+    }
+}
+
+/// Configuration of the automatic reconnection of the client, see [`ClientConfig::reconnect`]
+///
+/// When the connection is lost, the client waits for a delay and then tries to connect again,
+/// with the latest [`ClientConfig`]. The delay is multiplied by [`backoff_factor`](Self::backoff_factor)
+/// after each failed attempt, up to [`max_delay`](Self::max_delay).
+///
+/// The client does not try to reconnect if it disconnected itself (with
+/// [`ClientCommands::disconnect_client`](crate::client::networking::ClientCommands::disconnect_client)),
+/// if the server denied the connection request, or if the server kicked the client with a non-zero code.
+/// A denial with [`DeniedReason::AlreadyConnected`](crate::connection::server::DeniedReason::AlreadyConnected)
+/// is retried, because the server might not have noticed yet that the previous connection was lost.
+///
+/// The replicated entities are despawned when the connection is lost, and replicated again by the
+/// server once the client is reconnected. A [`ReconnectAttemptEvent`](crate::client::events::ReconnectAttemptEvent)
+/// is emitted at the start of each attempt.
+///
+/// With [`Authentication::Token`](crate::connection::client::Authentication::Token), each attempt
+/// re-uses the same `ConnectToken`, which the server might reject; you can update the token in the
+/// [`ClientConfig`] before the next attempt.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct ReconnectConfig {
+    /// Maximum number of consecutive reconnection attempts before the client gives up
+    pub max_attempts: u32,
+    /// Delay before the first reconnection attempt
+    pub initial_delay: Duration,
+    /// Maximum delay between two reconnection attempts
+    pub max_delay: Duration,
+    /// Factor by which the delay is multiplied after each failed attempt
+    pub backoff_factor: f32,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            backoff_factor: 2.0,
+        }
+    }
+}
+
+impl ReconnectConfig {
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    pub fn with_initial_delay(mut self, initial_delay: Duration) -> Self {
+        self.initial_delay = initial_delay;
+        self
+    }
+
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn with_backoff_factor(mut self, backoff_factor: f32) -> Self {
+        self.backoff_factor = backoff_factor;
+        self
+    }
+
+    /// Delay before the reconnection attempt, given the number of attempts that already failed
+    pub(crate) fn delay(&self, failed_attempts: u32) -> Duration {
+        self.initial_delay
+            .mul_f32(self.backoff_factor.powi(failed_attempts as i32))
+            .min(self.max_delay)
+    }
 }
 
 /// The configuration object that lets you create a `ClientPlugin` with the desired settings.
@@ -124,4 +259,8 @@ pub struct ClientConfig {
     pub replication: ReplicationConfig,
     pub prediction: PredictionConfig,
     pub interpolation: InterpolationConfig,
+    /// If set, the client automatically tries to reconnect when the connection is lost.
+    ///
+    /// This is disabled by default.
+    pub reconnect: Option<ReconnectConfig>,
 }
@@ -7,7 +7,8 @@ use bytes::Bytes;
 use tracing::{debug, trace, trace_span};
 
 use crate::channel::builder::{
-    EntityActionsChannel, EntityUpdatesChannel, PingChannel, PongChannel,
+    ComponentAppliedChannel, EntityActionsChannel, EntityReliableUpdatesChannel,
+    EntityUpdatesChannel, InputAckChannel, PingChannel, PongChannel, TimeSyncChannel,
 };
 
 use crate::channel::receivers::ChannelReceive;
@@ -16,6 +17,10 @@ use crate::client::config::ClientConfig;
 use crate::client::error::ClientError;
 use crate::client::sync::SyncConfig;
 use crate::connection::netcode::MAX_PACKET_SIZE;
+use crate::inputs::ack::{InputAck, InputAckTracker};
+use crate::packet::congestion::CongestionState;
+use crate::packet::inspector::ConnectionInspector;
+use crate::packet::message_ack::MessageHandle;
 use crate::packet::message_manager::MessageManager;
 use crate::packet::packet_builder::{Payload, RecvPayload};
 use crate::packet::priority_manager::PriorityConfig;
@@ -32,7 +37,9 @@ use crate::server::error::ServerError;
 use crate::shared::events::connection::ConnectionEvents;
 use crate::shared::message::MessageSend;
 use crate::shared::ping::manager::{PingConfig, PingManager};
-use crate::shared::ping::message::{Ping, Pong};
+use crate::shared::ping::message::{Ping, Pong, TimeSync};
+use crate::shared::replication::applied::ComponentApplied;
+use crate::shared::replication::components::ReplicationGroupId;
 use crate::shared::replication::delta::DeltaManager;
 use crate::shared::replication::network_target::NetworkTarget;
 use crate::shared::replication::receive::ReplicationReceiver;
@@ -87,7 +94,11 @@ pub struct ConnectionManager {
     /// We use this so that:
     /// - in host server mode, we deserialize the bytes and push them to the server's Message Events queue directly
     /// - in non-host server mode, we buffer the bytes to the message manager as usual
-    pub(crate) messages_to_send: Vec<(Bytes, ChannelKind)>,
+    pub(crate) messages_to_send: Vec<(Bytes, ChannelKind, Option<MessageHandle>)>,
+    /// Tracks the inputs that were not acked by the server yet
+    pub(crate) input_ack: InputAckTracker,
+    /// Groups that still need to be received before the initial sync is complete
+    pub(crate) pending_initial_sync: Option<Vec<ReplicationGroupId>>,
 }
 
 // NOTE: useful when we sometimes need to create a temporary fake ConnectionManager
@@ -97,6 +108,7 @@ impl Default for ConnectionManager {
             crossbeam_channel::unbounded().1,
             crossbeam_channel::unbounded().1,
             crossbeam_channel::unbounded().1,
+            crossbeam_channel::unbounded().1,
             ReplicationConfig::default(),
             false,
         );
@@ -120,6 +132,8 @@ impl Default for ConnectionManager {
             received_messages: HashMap::default(),
             writer: Writer::with_capacity(0),
             messages_to_send: Vec::default(),
+            input_ack: InputAckTracker::default(),
+            pending_initial_sync: None,
         }
     }
 }
@@ -134,11 +148,21 @@ impl ConnectionManager {
     ) -> Self {
         let bandwidth_cap_enabled = client_config.packet.bandwidth_cap_enabled;
         // create the message manager and the channels
-        let mut message_manager = MessageManager::new(
-            channel_registry,
-            client_config.packet.nack_rtt_multiple,
-            client_config.packet.into(),
-        );
+        let mut message_manager =
+            MessageManager::new(
+                channel_registry,
+                client_config.packet.nack_rtt_multiple,
+                (&client_config.packet).into(),
+            )
+            .with_mtu_discovery(client_config.packet.mtu_discovery)
+            .with_congestion_control(client_config.packet.congestion_control)
+            .with_packet_buffer_pool(client_config.packet.packet_buffer_pool_size)
+            .with_packet_inspector(client_config.packet.packet_inspector.clone().map(
+                |inspector| ConnectionInspector {
+                    inspector,
+                    client_id: None,
+                },
+            ));
         // get notified when a replication-update message gets acked/nacked
         let entity_updates_sender = &mut message_manager
             .channels
@@ -147,17 +171,26 @@ impl ConnectionManager {
             .sender;
         let update_nacks_receiver = entity_updates_sender.subscribe_nacks();
         let update_acks_receiver = entity_updates_sender.subscribe_acks();
+        // get notified when a replication-actions message gets acked
+        let actions_acks_receiver = message_manager
+            .channels
+            .get_mut(&ChannelKind::of::<EntityActionsChannel>())
+            .unwrap()
+            .sender
+            .subscribe_acks();
         // get a channel to get notified when a replication update message gets actually send (to update priority)
         let replication_update_send_receiver =
             message_manager.get_replication_update_send_receiver();
         let replication_sender = ReplicationSender::new(
             update_acks_receiver,
             update_nacks_receiver,
+            actions_acks_receiver,
             replication_update_send_receiver,
             client_config.replication,
             bandwidth_cap_enabled,
         );
-        let replication_receiver = ReplicationReceiver::new();
+        let replication_receiver = ReplicationReceiver::new()
+            .with_updates_ordering(client_config.replication.updates_ordering);
         Self {
             component_registry: component_registry.clone(),
             message_registry: message_registry.clone(),
@@ -166,27 +199,147 @@ impl ConnectionManager {
             replication_sender,
             replication_receiver,
             ping_manager: PingManager::new(client_config.ping),
-            sync_manager: SyncManager::new(client_config.sync, client_config.prediction),
+            sync_manager: SyncManager::new(client_config.sync, client_config.prediction.clone())
+                .with_input_delay_ticks(client_config.input.input_delay_ticks)
+                .with_input_send_interval(client_config.input.send_interval)
+                .with_input_jitter_buffer_ticks(client_config.input.jitter_buffer_ticks),
             events: ConnectionEvents::default(),
             #[cfg(feature = "leafwing")]
             received_leafwing_input_messages: HashMap::default(),
             received_messages: HashMap::default(),
             writer: Writer::with_capacity(MAX_PACKET_SIZE),
             messages_to_send: Vec::default(),
+            input_ack: InputAckTracker::default(),
+            pending_initial_sync: None,
         }
     }
 
-    #[doc(hidden)]
     /// Returns true if the connection is synced with the server
+    /// (i.e. the handshake with the server is finalized)
     pub fn is_synced(&self) -> bool {
         self.sync_manager.is_synced()
     }
 
+    /// Returns true if the warmup phase that follows the handshake is over, so that the sync
+    /// with the server is stable
+    pub fn is_ready(&self) -> bool {
+        self.sync_manager.is_ready()
+    }
+
+    /// Returns true if the client still has messages to send to the server, or reliable messages
+    /// that were not acked by the server yet.
+    ///
+    /// This can be used to make sure that all pending messages have been delivered, for example before
+    /// disconnecting or at the end of a test.
+    pub fn has_pending_messages(&self) -> bool {
+        !self.messages_to_send.is_empty() || self.message_manager.has_messages_to_send()
+    }
+
+    /// Maximum size of the packets sent to the server.
+    ///
+    /// This is the default [`MAX_PACKET_SIZE`](crate::connection::netcode::MAX_PACKET_SIZE) unless
+    /// MTU discovery is enabled and found that bigger packets can be used.
+    pub fn mtu(&self) -> usize {
+        self.message_manager.mtu()
+    }
+
+    /// Congestion state of the connection to the server
+    pub fn congestion_state(&self) -> CongestionState {
+        self.message_manager.congestion_state()
+    }
+
+    /// Number of entities whose updates were deferred to a later send during the last send, because of
+    /// [`ReplicationConfig::max_entities_per_tick`](crate::prelude::ReplicationConfig::max_entities_per_tick)
+    pub fn deferred_entities(&self) -> usize {
+        self.replication_sender.deferred_entities
+    }
+
     /// Returns true if we received a new server packet on this frame
     pub(crate) fn received_new_server_tick(&self) -> bool {
         self.sync_manager.duration_since_latest_received_server_tick == Duration::default()
     }
 
+    /// The interpolation delay currently in use, i.e. how far behind the server time estimate
+    /// the interpolation timeline is.
+    ///
+    /// If [`InterpolationDelay::adaptive`](crate::client::interpolation::plugin::InterpolationDelay::adaptive)
+    /// is set, this value adapts to the jitter of the connection.
+    pub fn interpolation_delay(&self) -> Duration {
+        self.sync_manager.interpolation_delay
+    }
+
+    /// Number of ticks of input delay currently applied: inputs buffered at tick `T` are
+    /// executed at tick `T + input_delay_ticks`.
+    ///
+    /// This is the maximum of [`InputConfig::input_delay_ticks`](crate::client::input::native::InputConfig::input_delay_ticks)
+    /// and the input delay computed from the [`PredictionConfig`](crate::client::prediction::plugin::PredictionConfig).
+    pub fn input_delay_ticks(&self) -> u16 {
+        self.sync_manager.input_delay_ticks
+    }
+
+    /// Most recent input tick that the server acknowledged receiving.
+    ///
+    /// Returns None if no input was acked yet.
+    pub fn last_acked_input_tick(&self) -> Option<Tick> {
+        self.input_ack.last_acked_tick
+    }
+
+    /// How far ahead of the server the client's prediction timeline currently is, compared to our
+    /// estimate of the current server time.
+    ///
+    /// The server time estimate is computed from the server tick of each received packet, corrected by the
+    /// [`TimeSync`] stamps that the server sends periodically.
+    ///
+    /// Returns None if the client is not synced yet.
+    pub fn server_time_offset(
+        &self,
+        tick_manager: &TickManager,
+        time_manager: &TimeManager,
+    ) -> Option<chrono::Duration> {
+        if !self.is_synced() {
+            return None;
+        }
+        Some(
+            self.sync_manager
+                .current_prediction_time(tick_manager, time_manager)
+                - self.sync_manager.server_time_estimate(),
+        )
+    }
+
+    /// Our estimate of the current server time, i.e. the time elapsed since the server started.
+    ///
+    /// Returns None if the client is not synced yet.
+    pub fn server_time(&self) -> Option<Duration> {
+        self.is_synced()
+            .then(|| self.sync_manager.server_time_estimate().elapsed)
+    }
+
+    /// Keep the prediction timeline at a fixed offset ahead of the server time estimate (see
+    /// [`Self::server_time_offset`]), instead of the offset computed from the RTT and jitter of the connection.
+    ///
+    /// This is mostly useful for deterministic tests. Set to None to go back to the computed offset.
+    pub fn set_manual_offset(&mut self, offset: Option<chrono::Duration>) {
+        self.sync_manager.set_manual_offset(offset);
+    }
+
+    /// Number of ticks that the client's predicted simulation is running ahead of the server,
+    /// i.e. the current client tick minus our estimate of the current server tick.
+    ///
+    /// This should stay roughly constant once the client is synced; a drifting offset is a sign
+    /// that the sync is struggling (for example because of a lot of jitter).
+    ///
+    /// Returns None if the client is not synced yet.
+    pub fn tick_offset(&self, tick_manager: &TickManager) -> Option<i16> {
+        if !self.is_synced() {
+            return None;
+        }
+        let server_tick = self
+            .sync_manager
+            .server_time_estimate()
+            .to_tick(tick_manager.config.tick_duration);
+        Some(tick_manager.tick() - server_tick)
+    }
+
     /// The latest server tick that we received from the server.
     pub(crate) fn latest_received_server_tick(&self) -> Tick {
         self.sync_manager
@@ -253,12 +406,44 @@ impl ConnectionManager {
         self.erased_send_message_to_target(message, ChannelKind::of::<C>(), target)
     }
 
+    /// Send a [`Message`] to the server using a specific [`Channel`], and get notified when the server
+    /// receives it
+    ///
+    /// A [`MessageAckEvent`](crate::client::events::MessageAckEvent) with the returned [`MessageHandle`]
+    /// is emitted when the server acks the message, or a [`MessageTimeoutEvent`](crate::client::events::MessageTimeoutEvent)
+    /// if the message is lost. See [`message_ack`](crate::packet::message_ack) for more details.
+    ///
+    /// The channel must watch acks (reliable channels, or [`ChannelMode::UnorderedUnreliableWithAcks`](crate::prelude::ChannelMode::UnorderedUnreliableWithAcks)).
+    pub fn send_message_with_ack<C: Channel, M: Message>(
+        &mut self,
+        message: &mut M,
+    ) -> Result<MessageHandle, ClientError> {
+        let channel_kind = ChannelKind::of::<C>();
+        self.message_manager.check_acks_tracked(&channel_kind)?;
+        let handle = self.message_manager.message_acks.new_handle();
+        self.buffer_message(message, channel_kind, NetworkTarget::None, Some(handle))?;
+        Ok(handle)
+    }
+
     /// Serialize a message and buffer it internally so that it can be sent later
     fn erased_send_message_to_target<M: Message>(
         &mut self,
         message: &M,
         channel_kind: ChannelKind,
         target: NetworkTarget,
+    ) -> Result<(), ClientError> {
+        self.buffer_message(message, channel_kind, target, None)
+    }
+
+    /// Serialize a message and buffer it internally so that it can be sent later.
+    ///
+    /// If a `handle` is provided, the delivery of the message is reported with the handle.
+    fn buffer_message<M: Message>(
+        &mut self,
+        message: &M,
+        channel_kind: ChannelKind,
+        target: NetworkTarget,
+        handle: Option<MessageHandle>,
     ) -> Result<(), ClientError> {
         // write the target first
         // NOTE: this is ok to do because most of the time (without rebroadcast, this just adds 1 byte)
@@ -271,8 +456,14 @@ impl ConnectionManager {
         )?;
         let message_bytes = self.writer.split();
 
-        // TODO: emit logs/metrics about the message being buffered?
-        self.messages_to_send.push((message_bytes, channel_kind));
+        #[cfg(feature = "metrics")]
+        {
+            metrics::counter!("message.sent", "message" => std::any::type_name::<M>()).increment(1);
+            metrics::counter!("message.bytes_sent", "message" => std::any::type_name::<M>())
+                .increment(message_bytes.len() as u64);
+        }
+        self.messages_to_send
+            .push((message_bytes, channel_kind, handle));
         Ok(())
     }
 
@@ -318,7 +509,7 @@ impl ConnectionManager {
         // go through messages_to_send, deserialize them and make the server receive them
         self.messages_to_send
             .drain(..)
-            .try_for_each(|(message_bytes, channel_kind)| {
+            .try_for_each(|(message_bytes, channel_kind, handle)| {
                 server_manager
                     .connection_mut(local_client_id)?
                     .receive_message(
@@ -326,7 +517,12 @@ impl ConnectionManager {
                         channel_kind,
                         &self.message_registry,
                     )
-                    .map_err(ServerError::from)
+                    .map_err(ServerError::from)?;
+                // the message is received by the server right away
+                if let Some(handle) = handle {
+                    self.message_manager.message_acks.delivered(handle);
+                }
+                Ok::<(), ServerError>(())
             })?;
         Ok(())
     }
@@ -365,9 +561,18 @@ impl ConnectionManager {
         // buffer the messages into the message manager
         self.messages_to_send
             .drain(..)
-            .try_for_each(|(message_bytes, channel_kind)| {
-                self.message_manager
-                    .buffer_send(message_bytes, channel_kind)?;
+            .try_for_each(|(message_bytes, channel_kind, handle)| {
+                match handle {
+                    Some(handle) => self.message_manager.buffer_send_with_ack(
+                        message_bytes,
+                        channel_kind,
+                        handle,
+                    )?,
+                    None => {
+                        self.message_manager
+                            .buffer_send(message_bytes, channel_kind)?;
+                    }
+                }
                 Ok::<(), ClientError>(())
             })?;
 
@@ -379,6 +584,11 @@ impl ConnectionManager {
         payloads.map_err(Into::into)
     }
 
+    /// Return the payloads of the packets that were sent so that their buffers can be reused
+    pub(crate) fn recycle_payloads(&mut self, payloads: Vec<Payload>) {
+        self.message_manager.recycle_payloads(payloads);
+    }
+
     pub(crate) fn receive(
         &mut self,
         world: &mut World,
@@ -428,12 +638,23 @@ impl ConnectionManager {
                             time = ?pong.pong_sent_time,
                             "Updated server pong generation"
                         )
+                    } else if *channel_kind == ChannelKind::of::<TimeSyncChannel>() {
+                        let time_sync = TimeSync::from_bytes(&mut reader)?;
+                        self.sync_manager
+                            .apply_time_sync(&time_sync, tick_manager.config.tick_duration);
+                    } else if *channel_kind == ChannelKind::of::<InputAckChannel>() {
+                        let input_ack = InputAck::from_bytes(&mut reader)?;
+                        self.input_ack.receive_ack(input_ack);
                     } else if *channel_kind == ChannelKind::of::<EntityActionsChannel>() {
                         let actions = EntityActionsMessage::from_bytes(&mut reader)?;
                         self.replication_receiver.recv_actions(actions, tick);
                     } else if *channel_kind == ChannelKind::of::<EntityUpdatesChannel>() {
                         let updates = EntityUpdatesMessage::from_bytes(&mut reader)?;
                         self.replication_receiver.recv_updates(updates, tick);
+                    } else if *channel_kind == ChannelKind::of::<EntityReliableUpdatesChannel>() {
+                        let updates = EntityUpdatesMessage::from_bytes(&mut reader)?;
+                        self.replication_receiver
+                            .recv_reliable_updates(updates, tick);
                     } else {
                         // TODO: this code is copy-pasted from self.receive_message because of borrow checker limitations
                         // identify the type of message
@@ -471,6 +692,15 @@ impl ConnectionManager {
                 tick_manager.tick(),
                 &mut self.events,
             );
+            // confirm to the server the component updates that were applied
+            let applied_components = std::mem::take(&mut self.events.applied_components);
+            for (entity, component, tick) in applied_components {
+                self.send_message::<ComponentAppliedChannel, _>(&mut ComponentApplied {
+                    entity,
+                    component,
+                    tick,
+                })?;
+            }
         }
         Ok(())
     }
@@ -591,9 +821,19 @@ impl ReplicationSend for ConnectionManager {
 
 #[cfg(test)]
 mod tests {
-    use crate::prelude::{client, server, ClientConnectionManager, RemoteEntityMap};
-    use crate::tests::protocol::EntityMessage;
-    use crate::tests::stepper::BevyStepper;
+    use std::time::Duration;
+
+    use bevy::prelude::Events;
+
+    use crate::client::error::ClientError;
+    use crate::connection::netcode::MAX_PACKET_SIZE;
+    use crate::packet::mtu_discovery::MtuDiscoveryConfig;
+    use crate::prelude::{
+        client, server, ClientConnectionManager, RemoteEntityMap, SharedConfig, TickConfig,
+    };
+    use crate::prelude::{ClientId, PacketError};
+    use crate::tests::protocol::{Channel1, Channel2, EntityMessage, StringMessage};
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
 
     /// Check that we can map entities from the local world to the remote world
     /// using the ConnectionManager
@@ -644,4 +884,145 @@ mod tests {
         assert!(RemoteEntityMap::is_mapped(message.0));
         assert_eq!(RemoteEntityMap::mark_unmapped(message.0), server_entity);
     }
+
+    /// Check that the network is drained once all reliable messages have been acked
+    #[test]
+    fn test_drain_network() {
+        let mut stepper = BevyStepper::default();
+        stepper.drain_network(10);
+
+        // the entity spawn is sent on a reliable channel
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(server::Replicate::default())
+            .id();
+        stepper.advance_time(stepper.frame_duration);
+        stepper.server_app.update();
+        assert!(stepper
+            .server_app
+            .world()
+            .resource::<server::ConnectionManager>()
+            .has_pending_messages());
+
+        stepper.drain_network(10);
+        assert!(!stepper
+            .server_app
+            .world()
+            .resource::<server::ConnectionManager>()
+            .has_pending_messages());
+        assert!(!stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .has_pending_messages());
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .is_some());
+    }
+
+    /// Check that MTU discovery raises the maximum packet size of the connection
+    #[test]
+    fn test_mtu_discovery() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..Default::default()
+        };
+        let mut client_config = client::ClientConfig::default();
+        client_config.packet = client_config.packet.with_mtu_discovery(
+            MtuDiscoveryConfig::default()
+                .with_ceiling(1400)
+                .with_step(64)
+                .with_probe_interval(Duration::ZERO),
+        );
+        let mut stepper = BevyStepper::new(shared_config, client_config, frame_duration);
+        stepper.init();
+
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        // the local channels do not drop big packets, so we reach the ceiling
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .mtu(),
+            1400
+        );
+        // the server did not enable MTU discovery
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .resource::<server::ConnectionManager>()
+                .connection(ClientId::Netcode(TEST_CLIENT_ID))
+                .unwrap()
+                .mtu(),
+            MAX_PACKET_SIZE
+        );
+    }
+    /// Check that the sender is notified when a tracked message is acked
+    #[test]
+    fn test_send_message_with_ack() {
+        let mut stepper = BevyStepper::default();
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+
+        // the acks of unreliable channels are not watched
+        assert!(matches!(
+            stepper
+                .client_app
+                .world_mut()
+                .resource_mut::<client::ConnectionManager>()
+                .send_message_with_ack::<Channel1, _>(&mut StringMessage("a".to_string())),
+            Err(ClientError::Packet(PacketError::AcksNotTracked))
+        ));
+
+        let client_handle = stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<client::ConnectionManager>()
+            .send_message_with_ack::<Channel2, _>(&mut StringMessage("a".to_string()))
+            .unwrap();
+        let server_handle = stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<server::ConnectionManager>()
+            .send_message_with_ack::<Channel2, _>(client_id, &mut StringMessage("b".to_string()))
+            .unwrap();
+        let mut client_acks = vec![];
+        let mut server_acks = vec![];
+        for _ in 0..10 {
+            stepper.frame_step();
+            client_acks.extend(
+                stepper
+                    .client_app
+                    .world_mut()
+                    .resource_mut::<Events<client::MessageAckEvent>>()
+                    .drain()
+                    .map(|event| event.handle),
+            );
+            server_acks.extend(
+                stepper
+                    .server_app
+                    .world_mut()
+                    .resource_mut::<Events<server::MessageAckEvent>>()
+                    .drain()
+                    .map(|event| (event.handle, event.context)),
+            );
+        }
+        assert_eq!(client_acks, vec![client_handle]);
+        assert_eq!(server_acks, vec![(server_handle, client_id)]);
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<Events<client::MessageTimeoutEvent>>()
+            .is_empty());
+    }
 }
@@ -1,15 +1,15 @@
 use crate::client::connection::ConnectionManager;
 use crate::client::prediction::diagnostics::PredictionDiagnosticsPlugin;
 use bevy::app::{App, Plugin, PostUpdate};
-use bevy::diagnostic::Diagnostics;
-use bevy::prelude::{not, Condition, IntoSystemConfigs, Real, Res, ResMut, Time};
+use bevy::diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
+use bevy::prelude::{not, Condition, IntoSystemConfigs, Local, Real, Res, Time};
 use bevy::time::common_conditions::on_timer;
 use bevy::utils::Duration;
 
 use crate::connection::client::{ClientConnection, NetClient};
-use crate::prelude::{client::is_disconnected, is_host_server};
+use crate::prelude::{client::is_disconnected, client::is_synced, is_host_server, TickManager};
 use crate::shared::ping::diagnostics::PingDiagnosticsPlugin;
-use crate::transport::io::IoDiagnosticsPlugin;
+use crate::transport::io::{IoDiagnosticsPlugin, IoStats};
 
 // TODO: ideally make this a plugin group? but nested plugin groups are not supported
 #[derive(Debug)]
@@ -17,6 +17,12 @@ pub struct ClientDiagnosticsPlugin {
     flush_interval: Duration,
 }
 
+impl ClientDiagnosticsPlugin {
+    /// Number of ticks that the client is running ahead of the server
+    /// (see [`ConnectionManager::tick_offset`])
+    pub const TICK_OFFSET: DiagnosticPath = DiagnosticPath::const_new("sync.tick_offset");
+}
+
 impl Default for ClientDiagnosticsPlugin {
     fn default() -> Self {
         Self {
@@ -26,12 +32,18 @@ impl Default for ClientDiagnosticsPlugin {
 }
 
 fn io_diagnostics_system(
-    mut netclient: ResMut<ClientConnection>,
+    netclient: Res<ClientConnection>,
+    mut last_stats: Local<IoStats>,
     time: Res<Time<Real>>,
     mut diagnostics: Diagnostics,
 ) {
-    if let Some(io) = netclient.io_mut() {
-        IoDiagnosticsPlugin::update_diagnostics(&mut io.stats, &time, &mut diagnostics);
+    if let Some(io) = netclient.io() {
+        IoDiagnosticsPlugin::update_diagnostics(
+            &io.stats(),
+            &mut last_stats,
+            &time,
+            &mut diagnostics,
+        );
     }
 }
 
@@ -39,8 +51,29 @@ fn ping_diagnostics_system(connection: Res<ConnectionManager>, diagnostics: Diag
     PingDiagnosticsPlugin::add_measurements(&connection.ping_manager, diagnostics);
 }
 
+fn sync_diagnostics_system(
+    connection: Res<ConnectionManager>,
+    tick_manager: Res<TickManager>,
+    mut diagnostics: Diagnostics,
+) {
+    if let Some(tick_offset) = connection.tick_offset(tick_manager.as_ref()) {
+        #[cfg(feature = "metrics")]
+        metrics::gauge!("sync.tick_offset").set(tick_offset as f64);
+        diagnostics.add_measurement(&ClientDiagnosticsPlugin::TICK_OFFSET, || tick_offset as f64);
+    }
+}
+
 impl Plugin for ClientDiagnosticsPlugin {
     fn build(&self, app: &mut App) {
+        app.register_diagnostic(Diagnostic::new(Self::TICK_OFFSET).with_suffix("ticks"));
+        app.add_systems(
+            PostUpdate,
+            sync_diagnostics_system.run_if(
+                on_timer(self.flush_interval)
+                    .and_then(is_synced)
+                    .and_then(not(is_host_server)),
+            ),
+        );
         {
             let ping_plugin = PingDiagnosticsPlugin::default();
             let flush_interval = ping_plugin.flush_interval;
@@ -13,15 +13,17 @@
 //! }
 //! ```
 
-use bevy::app::{App, Plugin, PreUpdate};
+use bevy::app::{App, Plugin};
 use bevy::prelude::{Component, Event, IntoSystemConfigs};
+use bevy::utils::Duration;
 
 use crate::client::connection::ConnectionManager;
 use crate::connection::client::DisconnectReason;
-use crate::prelude::ClientId;
+use crate::prelude::{ClientId, Tick};
 use crate::shared::events::plugin::EventsPlugin;
 use crate::shared::events::systems::push_component_events;
-use crate::shared::sets::{ClientMarker, InternalMainSet};
+use crate::shared::sets::{ClientMarker, InternalMainSet, NetworkingSchedules};
+use crate::transport::udp::TransportRebindEvent;
 
 /// Plugin that handles generating bevy [`Events`](Event) related to networking and replication
 #[derive(Default)]
@@ -33,17 +35,25 @@ impl Plugin for ClientEventsPlugin {
             // EVENTS
             .add_event::<ConnectEvent>()
             .add_event::<DisconnectEvent>()
+            .add_event::<ReconnectAttemptEvent>()
+            .add_event::<WorldSnapshotComplete>()
+            .add_event::<MessageAckEvent>()
+            .add_event::<MessageTimeoutEvent>()
+            .add_event::<ReadyEvent>()
+            .add_event::<InputNotAckedEvent>()
+            .add_event::<TransportRebindEvent>()
             // PLUGIN
             .add_plugins(EventsPlugin::<ConnectionManager>::default());
     }
 }
 
 pub(crate) fn emit_replication_events<C: Component>(app: &mut App) {
+    let schedules = NetworkingSchedules::of(app);
     app.add_event::<ComponentUpdateEvent<C>>();
     app.add_event::<ComponentInsertEvent<C>>();
     app.add_event::<ComponentRemoveEvent<C>>();
     app.add_systems(
-        PreUpdate,
+        schedules.receive,
         push_component_events::<C, ConnectionManager>
             .in_set(InternalMainSet::<ClientMarker>::EmitEvents),
     );
@@ -65,12 +75,58 @@ impl ConnectEvent {
     }
 }
 
+/// Bevy [`Event`] emitted on the client on the frame where the sync with the server is stable,
+/// i.e. when the warmup phase that follows the handshake is over
+/// (see [`SyncConfig::warmup_duration`](crate::client::sync::SyncConfig::warmup_duration))
+///
+/// This can be used to keep the player on a loading screen until the networking is stable.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadyEvent;
+
+/// Bevy [`Event`] emitted on the client when the server has not acknowledged the inputs we sent for longer than
+/// [`InputConfig::ack_timeout`](crate::client::input::native::InputConfig::ack_timeout)
+///
+/// This means that the inputs don't reach the server even though the connection seems alive; it can be used to warn
+/// the player that their controls are not registering. The event is emitted once, and can be emitted again
+/// after the server acknowledges some inputs.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputNotAckedEvent {
+    /// Most recent input tick acked by the server, if any
+    pub last_acked_tick: Option<Tick>,
+    /// Duration since the server last acknowledged our inputs (or since we started sending inputs)
+    pub unacked_duration: Duration,
+}
+
 /// Bevy [`Event`] emitted on the client on the frame where the connection is disconnected
+///
+/// This is also emitted if the connection could not be established. If the server denied the connection
+/// request, the reason is [`DisconnectReason::Denied`].
+///
+/// The current state of the connection is available with the [`NetworkingState`](crate::client::networking::NetworkingState) state.
 #[derive(Event, Default)]
 pub struct DisconnectEvent {
     pub reason: Option<DisconnectReason>,
 }
 
+/// Bevy [`Event`] emitted on the client when it starts an automatic reconnection attempt
+///
+/// See [`ClientConfig::reconnect`](crate::client::config::ClientConfig::reconnect). If the attempt fails,
+/// a [`DisconnectEvent`] is emitted and the next attempt is scheduled.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectAttemptEvent {
+    /// Number of the attempt, starting at 1
+    pub attempt: u32,
+    /// Maximum number of attempts, from [`ReconnectConfig::max_attempts`](crate::client::config::ReconnectConfig::max_attempts)
+    pub max_attempts: u32,
+}
+
+/// Bevy [`Event`] emitted on the client when all the entities of its [initial sync](crate::server::initial_sync)
+/// have been spawned
+///
+/// This is only emitted if the server sets [`ReplicationConfig::initial_sync_entities_per_tick`](crate::prelude::ReplicationConfig::initial_sync_entities_per_tick).
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorldSnapshotComplete;
+
 /// Bevy [`Event`] emitted on the client to indicate the user input for the tick
 pub type InputEvent<I> = crate::shared::events::components::InputEvent<I, ()>;
 /// Bevy [`Event`] emitted on the client when a EntitySpawn replication message is received
@@ -85,3 +141,9 @@ pub type ComponentInsertEvent<C> = crate::shared::events::components::ComponentI
 pub type ComponentRemoveEvent<C> = crate::shared::events::components::ComponentRemoveEvent<C, ()>;
 /// Bevy [`Event`] emitted on the client when a (non-replication) message is received
 pub type MessageEvent<M> = crate::shared::events::components::MessageEvent<M, ()>;
+/// Bevy [`Event`] emitted on the client when the server acked a message sent with
+/// [`send_message_with_ack`](crate::client::connection::ConnectionManager::send_message_with_ack)
+pub type MessageAckEvent = crate::shared::events::components::MessageAckEvent<()>;
+/// Bevy [`Event`] emitted on the client when a message sent with
+/// [`send_message_with_ack`](crate::client::connection::ConnectionManager::send_message_with_ack) is lost
+pub type MessageTimeoutEvent = crate::shared::events::components::MessageTimeoutEvent<()>;
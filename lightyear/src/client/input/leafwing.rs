@@ -159,6 +159,7 @@ impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A>
 // TODO: will need to generate diffs in FixedPreUpdate schedule once it's fixed in leafwing
 {
     fn build(&self, app: &mut App) {
+        let schedules = NetworkingSchedules::of(app);
         // PLUGINS
         app.add_plugins(InputManagerPlugin::<A>::default());
         // RESOURCES
@@ -173,7 +174,7 @@ impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A>
 
         // SETS
         app.configure_sets(
-            PreUpdate,
+            schedules.receive,
             (
                 InputSystemSet::AddBuffers
                     // TODO: these constraints are only necessary for entities controlled by other players
@@ -194,7 +195,7 @@ impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A>
             InputSystemSet::PrepareInputMessage.run_if(should_run.clone().and_then(is_synced)),
         );
         app.configure_sets(
-            PostUpdate,
+            schedules.send,
             (
                 SyncSet,
                 // run after SyncSet to make sure that the TickEvents are handled
@@ -208,7 +209,7 @@ impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A>
 
         // SYSTEMS
         app.add_systems(
-            PreUpdate,
+            schedules.receive,
             (
                 receive_remote_player_input_messages::<A>
                     .in_set(InputSystemSet::ReceiveInputMessages),
@@ -265,7 +266,7 @@ impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A>
         // if the client tick is updated because of a desync, update the ticks in the input buffers
         app.observe(receive_tick_events::<A>);
         app.add_systems(
-            PostUpdate,
+            schedules.send,
             (
                 send_input_messages::<A>.in_set(InputSystemSet::SendInputMessage),
                 clean_buffers::<A>.in_set(InputSystemSet::CleanUp),
@@ -657,11 +658,10 @@ fn send_input_messages<A: LeafwingUserAction>(
         message_buffer.0.len()
     );
     for mut message in message_buffer.0.drain(..) {
-        connection
-            .send_message::<InputChannel, InputMessage<A>>(&mut message)
-            .unwrap_or_else(|err| {
-                error!("Error while sending input message: {:?}", err);
-            });
+        match connection.send_message::<InputChannel, InputMessage<A>>(&mut message) {
+            Ok(()) => connection.input_ack.record_sent(message.end_tick),
+            Err(err) => error!("Error while sending input message: {:?}", err),
+        }
     }
 }
 
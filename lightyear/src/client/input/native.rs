@@ -60,7 +60,7 @@ use crate::connection::client::NetClientDispatch;
 use crate::inputs::native::input_buffer::InputBuffer;
 use crate::inputs::native::UserAction;
 use crate::prelude::{is_host_server, ChannelKind, ChannelRegistry, Tick, TickManager};
-use crate::shared::sets::{ClientMarker, InternalMainSet};
+use crate::shared::sets::{ClientMarker, InternalMainSet, NetworkingSchedules};
 use crate::shared::tick_manager::TickEvent;
 use crate::{channel::builder::InputChannel, prelude::client::ClientConnection};
 
@@ -73,7 +73,53 @@ pub struct InputConfig {
     pub packet_redundancy: u16,
     /// How often do we send input messages to the server?
     /// Duration::default() means that we will send input messages every frame.
+    ///
+    /// If the interval is longer than the tick duration, the inputs of the ticks between two sends
+    /// are batched in a single message, so the server still receives the input of every tick.
     pub send_interval: Duration,
+    /// Number of ticks of input delay.
+    ///
+    /// An input added for tick `T` with [`InputManager::add_input`] will be executed at tick `T + input_delay_ticks`,
+    /// on both the client and the server. The client timeline is kept that many ticks closer to the server, so that
+    /// inputs still reach the server in time: there is less to predict (and to rollback) at the cost of added latency.
+    ///
+    /// The effective delay is the maximum of this value and the input delay computed from the
+    /// [`PredictionConfig`](crate::client::prediction::plugin::PredictionConfig); it can be read with
+    /// [`ConnectionManager::input_delay_ticks`].
+    pub input_delay_ticks: u16,
+    /// Number of ticks that inputs are held in the server's input buffer before their tick executes.
+    ///
+    /// Inputs arrive on the server with jitter; if they arrive right when their tick executes, a bit of
+    /// jitter is enough for the server to miss the input and fallback to the previous one, which causes
+    /// prediction corrections on the client. With a jitter buffer, the client runs `jitter_buffer_ticks` ticks further ahead
+    /// of the server, so that its inputs wait in the server's buffer and are available when their tick executes.
+    /// This trades a little latency for fewer missed inputs.
+    ///
+    /// Use [`InputBuffers::stats`](crate::server::input::native::InputBuffers::stats) on the server to see how often
+    /// the buffer ran dry, and tune the depth accordingly.
+    pub jitter_buffer_ticks: u16,
+    /// If the server does not acknowledge the inputs that we send for longer than this duration,
+    /// an [`InputNotAckedEvent`](crate::client::events::InputNotAckedEvent) is emitted.
+    ///
+    /// This happens if the inputs don't reach the server even though the connection seems alive.
+    pub ack_timeout: Duration,
+}
+
+impl InputConfig {
+    pub fn with_input_delay_ticks(mut self, input_delay_ticks: u16) -> Self {
+        self.input_delay_ticks = input_delay_ticks;
+        self
+    }
+
+    pub fn with_jitter_buffer_ticks(mut self, jitter_buffer_ticks: u16) -> Self {
+        self.jitter_buffer_ticks = jitter_buffer_ticks;
+        self
+    }
+
+    pub fn with_ack_timeout(mut self, ack_timeout: Duration) -> Self {
+        self.ack_timeout = ack_timeout;
+        self
+    }
 }
 
 /// Resource that handles buffering and sending inputs to the server
@@ -83,12 +129,15 @@ pub struct InputConfig {
 #[derive(Debug, Resource)]
 pub struct InputManager<A> {
     pub(crate) input_buffer: InputBuffer<A>,
+    /// Number of ticks of input delay applied to the inputs that are added
+    pub(crate) input_delay_ticks: u16,
 }
 
 impl<A> Default for InputManager<A> {
     fn default() -> Self {
         Self {
             input_buffer: InputBuffer::default(),
+            input_delay_ticks: 0,
         }
     }
 }
@@ -101,8 +150,16 @@ impl<A: UserAction> InputManager<A> {
     }
 
     /// Buffer a user action for the given tick
+    ///
+    /// If there is input delay, the action will be executed `input_delay_ticks` ticks after `tick`
     pub fn add_input(&mut self, input: A, tick: Tick) {
-        self.input_buffer.set(tick, Some(input));
+        self.input_buffer
+            .set(tick + self.input_delay_ticks as i16, Some(input));
+    }
+
+    /// Number of ticks of input delay applied to the inputs added with [`InputManager::add_input`]
+    pub fn input_delay_ticks(&self) -> u16 {
+        self.input_delay_ticks
     }
 }
 
@@ -111,6 +168,9 @@ impl Default for InputConfig {
         InputConfig {
             packet_redundancy: 10,
             send_interval: Duration::default(),
+            input_delay_ticks: 0,
+            jitter_buffer_ticks: 0,
+            ack_timeout: Duration::from_secs(1),
         }
     }
 }
@@ -144,6 +204,7 @@ pub struct CurrentInput<A: UserAction> {
 
 impl<A: UserAction> Plugin for InputPlugin<A> {
     fn build(&self, app: &mut App) {
+        let schedules = NetworkingSchedules::of(app);
         // REGISTRATION
         app.register_type::<InputConfig>();
         // RESOURCES
@@ -162,7 +223,7 @@ impl<A: UserAction> Plugin for InputPlugin<A> {
         );
         app.configure_sets(FixedPostUpdate, InputSystemSet::ClearInputEvent);
         app.configure_sets(
-            PostUpdate,
+            schedules.send,
             (
                 // create input messages after SyncSet to make sure that the TickEvents are handled
                 SyncSet,
@@ -196,13 +257,13 @@ impl<A: UserAction> Plugin for InputPlugin<A> {
         );
         app.observe(receive_tick_events::<A>);
         app.add_systems(
-            PostUpdate,
+            schedules.send,
             (prepare_input_message::<A>.in_set(InputSystemSet::SendInputMessage),),
         );
 
         // in case the framerate is faster than fixed-update interval, we also write/clear the events at frame limits
         // TODO: should we also write the events at PreUpdate?
-        // app.add_systems(PostUpdate, clear_input_events::);
+        // app.add_systems(schedules.send, clear_input_events::);
     }
 }
 
@@ -284,6 +345,41 @@ fn prepare_input_message<A: UserAction>(
     //  this means we would also want to track packet->message acks for unreliable channels as well, so we can notify
     //  this system what the latest acked input tick is?
 
+    // the inputs with input delay have been buffered for future ticks
+    input_manager.input_delay_ticks = connection.input_delay_ticks();
+    // If the send interval is longer than the tick duration, we only buffer a message when the input
+    // channel is about to send it: the message covers all the ticks since the previous send, so that
+    // the server receives the input of every tick, without buffering one redundant message per frame.
+    if connection
+        .message_manager
+        .is_ready_to_send(&ChannelKind::of::<InputChannel>())
+    {
+        buffer_input_message(
+            connection.as_mut(),
+            channel_registry.as_ref(),
+            input_manager.as_mut(),
+            config.as_ref(),
+            current_tick,
+        );
+    }
+    // NOTE: actually we keep the input values! because they might be needed when we rollback for client prediction
+    // TODO: figure out when we can delete old inputs. Basically when the oldest prediction group tick has passed?
+    //  maybe at interpolation_tick(), since it's before any latest server update we receive?
+
+    // delete old input values
+    let interpolation_tick = connection.sync_manager.interpolation_tick(&tick_manager);
+    input_manager.input_buffer.pop(interpolation_tick);
+    // .pop(current_tick - (message_len + 1));
+}
+
+/// Buffer an input message containing the inputs of the last ticks
+fn buffer_input_message<A: UserAction>(
+    connection: &mut ConnectionManager,
+    channel_registry: &ChannelRegistry,
+    input_manager: &mut InputManager<A>,
+    config: &ClientConfig,
+    current_tick: Tick,
+) {
     // we send redundant inputs, so that if a packet is lost, we can still recover
     let input_send_interval = channel_registry
         .get_builder_from_kind(&ChannelKind::of::<InputChannel>())
@@ -302,31 +398,25 @@ fn prepare_input_message<A: UserAction>(
     //  - buffer an input every frame; and require some redundancy (number of tick per frame)
     //  - or buffer an input only when we are sending, and require more redundancy
     // let message_len = 20 as u16;
+    let end_tick = current_tick + input_manager.input_delay_ticks as i16;
     let mut message = input_manager
         .input_buffer
-        .create_message(tick_manager.tick(), message_len);
+        .create_message(end_tick, message_len);
     // all inputs are absent
     if !message.is_empty() {
         // TODO: should we provide variants of each user-facing function, so that it pushes the error
         //  to the ConnectionEvents?
         debug!(
             ?current_tick,
-            "sending input message: {:?}", message.end_tick
+            ?end_tick,
+            "sending input message: {:?}",
+            message.end_tick
         );
-        connection
-            .send_message::<InputChannel, _>(&mut message)
-            .unwrap_or_else(|err| {
-                error!("Error while sending input message: {:?}", err);
-            })
+        match connection.send_message::<InputChannel, _>(&mut message) {
+            Ok(()) => connection.input_ack.record_sent(message.end_tick),
+            Err(err) => error!("Error while sending input message: {:?}", err),
+        }
     }
-    // NOTE: actually we keep the input values! because they might be needed when we rollback for client prediction
-    // TODO: figure out when we can delete old inputs. Basically when the oldest prediction group tick has passed?
-    //  maybe at interpolation_tick(), since it's before any latest server update we receive?
-
-    // delete old input values
-    let interpolation_tick = connection.sync_manager.interpolation_tick(&tick_manager);
-    input_manager.input_buffer.pop(interpolation_tick);
-    // .pop(current_tick - (message_len + 1));
 }
 
 /// In host server mode, we don't buffer inputs (because there is no rollback) and we don't send
@@ -347,12 +437,22 @@ fn send_input_directly_to_client_events<A: UserAction>(
 
 #[cfg(test)]
 mod tests {
+    use crate::channel::builder::InputChannel;
+    use crate::client::events::InputNotAckedEvent;
     use crate::client::input::native::InputSystemSet;
     use crate::prelude::client::InputManager;
-    use crate::prelude::{server, TickManager};
+    use crate::prelude::{
+        client, server, ChannelKind, ClientId, SharedConfig, Tick, TickConfig, TickManager,
+    };
+    use crate::server::input::native::{
+        InputBufferStats, InputBuffers, InputSystemSet as ServerInputSystemSet, MissingInputPolicy,
+    };
+    use crate::shared::sets::{InternalMainSet, ServerMarker};
     use crate::tests::host_server_stepper::HostServerStepper;
     use crate::tests::protocol::MyInput;
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
     use bevy::prelude::*;
+    use bevy::utils::Duration;
 
     fn press_input(
         mut input_manager: ResMut<InputManager<MyInput>>,
@@ -390,4 +490,315 @@ mod tests {
         stepper.frame_step();
         assert!(stepper.server_app.world().resource::<Counter>().0 > 0);
     }
+
+    /// Check that with input delay, the inputs are buffered for a future tick
+    #[test]
+    fn test_input_delay() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..Default::default()
+        };
+        let mut client_config = client::ClientConfig::default();
+        client_config.input = client_config.input.with_input_delay_ticks(2);
+        let mut stepper = BevyStepper::new(shared_config, client_config, frame_duration);
+        stepper.client_app.add_systems(
+            FixedPreUpdate,
+            press_input.in_set(InputSystemSet::BufferInputs),
+        );
+        stepper.init();
+        stepper.frame_step();
+
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .input_delay_ticks(),
+            2
+        );
+        let tick = stepper.client_tick();
+        let input_manager = stepper
+            .client_app
+            .world()
+            .resource::<InputManager<MyInput>>();
+        assert_eq!(input_manager.input_delay_ticks(), 2);
+        // the input added for the current tick will be executed 2 ticks later
+        assert_eq!(input_manager.get_input(tick + 2), Some(MyInput(2)));
+        assert_eq!(input_manager.get_input(tick + 3), None);
+    }
+
+    fn press_tick_input(
+        mut input_manager: ResMut<InputManager<MyInput>>,
+        tick_manager: Res<TickManager>,
+    ) {
+        let tick = tick_manager.tick();
+        input_manager.add_input(MyInput(tick.0 as i16), tick);
+    }
+
+    #[derive(Resource, Default)]
+    struct ReceivedInputs(Vec<(Tick, Option<MyInput>)>);
+
+    fn record_input(
+        tick_manager: Res<TickManager>,
+        mut received: ResMut<ReceivedInputs>,
+        mut input: EventReader<server::InputEvent<MyInput>>,
+    ) {
+        for input in input.read() {
+            received.0.push((tick_manager.tick(), *input.input()));
+        }
+    }
+
+    /// Check that if inputs are sent less often than every tick, the server still receives
+    /// the input of every tick
+    #[test]
+    fn test_input_send_interval_batching() {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let mut client_config = client::ClientConfig::default();
+        // 1 input message every 5 ticks
+        client_config.input.send_interval = tick_duration * 5;
+        let mut stepper = BevyStepper::new(shared_config, client_config, tick_duration);
+        stepper.client_app.add_systems(
+            FixedPreUpdate,
+            press_tick_input.in_set(InputSystemSet::BufferInputs),
+        );
+        stepper.server_app.init_resource::<ReceivedInputs>();
+        stepper.server_app.add_systems(FixedUpdate, record_input);
+        stepper.init();
+
+        // only 1 input message is buffered per send interval
+        let mut messages_buffered = 0;
+        for _ in 0..10 {
+            let ready = stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .message_manager
+                .is_ready_to_send(&ChannelKind::of::<InputChannel>());
+            if ready {
+                messages_buffered += 1;
+            }
+            stepper.frame_step();
+        }
+        // 10 frames with 1 message every 5 frames
+        assert_eq!(messages_buffered, 2);
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+
+        // the server received the input of every tick of the last 5-tick windows
+        let received = &stepper.server_app.world().resource::<ReceivedInputs>().0;
+        let last_ticks = &received[received.len() - 15..];
+        for (tick, input) in last_ticks {
+            assert_eq!(input, &Some(MyInput(tick.0 as i16)), "tick {tick:?}");
+        }
+    }
+
+    #[derive(Resource, Default)]
+    struct DropInputs(bool);
+
+    /// Simulate inputs that don't reach the server
+    fn drop_inputs(
+        drop: Res<DropInputs>,
+        mut connection_manager: ResMut<server::ConnectionManager>,
+    ) {
+        if drop.0 {
+            connection_manager
+                .connections
+                .values_mut()
+                .for_each(|connection| connection.received_input_messages.clear());
+        }
+    }
+
+    #[derive(Resource, Default)]
+    struct NotAckedEvents(Vec<InputNotAckedEvent>);
+
+    fn record_not_acked(
+        mut events: EventReader<InputNotAckedEvent>,
+        mut recorded: ResMut<NotAckedEvents>,
+    ) {
+        recorded.0.extend(events.read().copied());
+    }
+
+    /// Check that the server acks the inputs, and that we are notified if the inputs are not acked
+    #[test]
+    fn test_input_not_acked() {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let mut client_config = client::ClientConfig::default();
+        client_config.input = client_config
+            .input
+            .with_ack_timeout(Duration::from_millis(100));
+        let mut stepper = BevyStepper::new(shared_config, client_config, tick_duration);
+        stepper.client_app.add_systems(
+            FixedPreUpdate,
+            press_tick_input.in_set(InputSystemSet::BufferInputs),
+        );
+        stepper.client_app.init_resource::<NotAckedEvents>();
+        stepper.client_app.add_systems(Update, record_not_acked);
+        stepper.server_app.init_resource::<DropInputs>();
+        stepper.server_app.add_systems(
+            PreUpdate,
+            drop_inputs
+                .after(InternalMainSet::<ServerMarker>::Receive)
+                .before(ServerInputSystemSet::ReceiveInputMessage),
+        );
+        stepper.init();
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        let last_acked_tick = |stepper: &BevyStepper| {
+            stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .last_acked_input_tick()
+        };
+        assert!(last_acked_tick(&stepper).unwrap() > stepper.server_tick());
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<NotAckedEvents>()
+            .0
+            .is_empty());
+
+        // the inputs stop reaching the server
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<DropInputs>()
+            .0 = true;
+        // let the acks that are in flight arrive
+        stepper.frame_step();
+        stepper.frame_step();
+        let acked = last_acked_tick(&stepper).unwrap();
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        assert_eq!(last_acked_tick(&stepper), Some(acked));
+        let events = &stepper.client_app.world().resource::<NotAckedEvents>().0;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].last_acked_tick, Some(acked));
+        assert!(events[0].unacked_duration > Duration::from_millis(100));
+
+        // the inputs reach the server again
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<DropInputs>()
+            .0 = false;
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert!(last_acked_tick(&stepper).unwrap() > acked);
+    }
+
+    fn buffer_stats(jitter_buffer_ticks: u16) -> InputBufferStats {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let mut client_config = client::ClientConfig::default();
+        client_config.input = client_config
+            .input
+            .with_jitter_buffer_ticks(jitter_buffer_ticks);
+        let mut stepper = BevyStepper::new(shared_config, client_config, tick_duration);
+        stepper.client_app.add_systems(
+            FixedPreUpdate,
+            press_tick_input.in_set(InputSystemSet::BufferInputs),
+        );
+        stepper.init();
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        *stepper
+            .server_app
+            .world()
+            .resource::<InputBuffers<MyInput>>()
+            .stats(ClientId::Netcode(TEST_CLIENT_ID))
+            .unwrap()
+    }
+
+    /// Check that with a jitter buffer, inputs wait on the server before their tick executes
+    #[test]
+    fn test_input_jitter_buffer() {
+        let without_buffer = buffer_stats(0);
+        let with_buffer = buffer_stats(4);
+        assert!(with_buffer.ticks > 0);
+        assert_eq!(with_buffer.missing_inputs, 0);
+        assert!(
+            with_buffer.buffered_ticks >= without_buffer.buffered_ticks + 3,
+            "without buffer: {without_buffer:?}, with buffer: {with_buffer:?}"
+        );
+    }
+
+    /// Inputs used by the server for the last ticks while the inputs of the client don't reach it
+    fn missing_inputs(policy: MissingInputPolicy<MyInput>) -> Vec<(Tick, Option<MyInput>)> {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            client::ClientConfig::default(),
+            tick_duration,
+        );
+        stepper.client_app.add_systems(
+            FixedPreUpdate,
+            press_tick_input.in_set(InputSystemSet::BufferInputs),
+        );
+        stepper.server_app.insert_resource(policy);
+        stepper.server_app.init_resource::<ReceivedInputs>();
+        stepper.server_app.add_systems(FixedUpdate, record_input);
+        stepper.server_app.init_resource::<DropInputs>();
+        stepper.server_app.add_systems(
+            PreUpdate,
+            drop_inputs
+                .after(InternalMainSet::<ServerMarker>::Receive)
+                .before(ServerInputSystemSet::ReceiveInputMessage),
+        );
+        stepper.init();
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<DropInputs>()
+            .0 = true;
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        let received = &stepper.server_app.world().resource::<ReceivedInputs>().0;
+        received[received.len() - 5..].to_vec()
+    }
+
+    /// Check that the server fills the missing inputs according to the MissingInputPolicy
+    #[test]
+    fn test_missing_input_policy() {
+        // the last input received is repeated
+        let inputs = missing_inputs(MissingInputPolicy::RepeatLast);
+        assert!(inputs.iter().all(|(_, input)| input == &inputs[0].1));
+        let last_input = inputs[0].1.unwrap();
+        assert!(last_input.0 < inputs[0].0 .0 as i16);
+
+        let inputs = missing_inputs(MissingInputPolicy::Absent);
+        assert!(inputs.iter().all(|(_, input)| input.is_none()));
+
+        let inputs = missing_inputs(MissingInputPolicy::Custom(|last| {
+            last.map(|input| MyInput(-input.0))
+        }));
+        assert!(inputs
+            .iter()
+            .all(|(_, input)| input.is_some_and(|input| input.0 < 0)));
+    }
 }
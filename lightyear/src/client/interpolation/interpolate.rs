@@ -5,6 +5,7 @@ use crate::client::components::SyncComponent;
 use crate::client::config::ClientConfig;
 use crate::client::connection::ConnectionManager;
 use crate::client::interpolation::interpolation_history::ConfirmedHistory;
+use crate::client::interpolation::plugin::InterpolationSpawnMode;
 use crate::prelude::{ComponentRegistry, TickManager};
 use crate::shared::tick_manager::Tick;
 
@@ -34,6 +35,19 @@ pub struct InterpolateStatus<C: Component> {
 }
 
 impl<C: Component> InterpolateStatus<C> {
+    /// Tick of the server snapshot that we are interpolating from
+    pub fn start_tick(&self) -> Option<Tick> {
+        self.start.as_ref().map(|(tick, _)| *tick)
+    }
+
+    /// Tick of the server snapshot that we are interpolating towards
+    pub fn end_tick(&self) -> Option<Tick> {
+        self.end.as_ref().map(|(tick, _)| *tick)
+    }
+
+    /// Interpolation alpha between the start and end snapshots, in `[0.0, 1.0[`
+    ///
+    /// Returns None if we don't have two snapshots to interpolate between.
     pub fn interpolation_fraction(&self) -> Option<f32> {
         self.start.as_ref().and_then(|(start_tick, _)| {
             self.end.as_ref().map(|(end_tick, _)| {
@@ -51,6 +65,7 @@ impl<C: Component> InterpolateStatus<C> {
 /// At the end of each frame, interpolate the components between the last 2 confirmed server states
 /// Invariant: start_tick <= current_interpolate_tick + overstep < end_tick
 pub(crate) fn update_interpolate_status<C: SyncComponent>(
+    component_registry: Res<ComponentRegistry>,
     config: Res<ClientConfig>,
     connection: Res<ConnectionManager>,
     tick_manager: Res<TickManager>,
@@ -75,7 +90,7 @@ pub(crate) fn update_interpolate_status<C: SyncComponent>(
     let current_interpolate_overstep = connection
         .sync_manager
         .interpolation_overstep(tick_manager.as_ref());
-    for (entity, component, mut status, mut history) in query.iter_mut() {
+    for (entity, mut component, mut status, mut history) in query.iter_mut() {
         let mut start = status.start.take();
         let mut end = status.end.take();
 
@@ -91,8 +106,8 @@ pub(crate) fn update_interpolate_status<C: SyncComponent>(
                 );
                 start.clone_from(&end);
                 // TODO: this clone should be avoidable
-                if let Some(mut component) = component {
-                    *component = end_value.clone();
+                if let Some(component) = component.as_mut() {
+                    **component = end_value.clone();
                 }
                 end = None;
             }
@@ -127,6 +142,21 @@ pub(crate) fn update_interpolate_status<C: SyncComponent>(
             }
         }
 
+        // if the next snapshot is discontinuous (teleport), we don't interpolate towards it:
+        // the component keeps its current value until we reach the end tick, and then jumps to the end value
+        if let Some((end_tick, end_value)) = &end {
+            if start.as_ref().is_some_and(|(_, start_value)| {
+                history.is_snap(*end_tick) || component_registry.should_snap(start_value, end_value)
+            }) {
+                trace!(
+                    ?entity,
+                    ?end_tick,
+                    "snapping to the next snapshot instead of interpolating"
+                );
+                start = None;
+            }
+        }
+
         // // NOTE: if we took enough margin, we should always have server snapshots (end tick) to interpolate towards,
         // //  lets consider that this is the case.
 
@@ -169,7 +199,9 @@ pub(crate) fn update_interpolate_status<C: SyncComponent>(
         // otherwise the interpolation will seem weird because the start tick is very old
         // Only do this when end_tick is None, otherwise it could affect the currently running
         // interpolation
-        if end.is_none() {
+        // We also keep the start value if the component hasn't been inserted yet on the interpolated
+        // entity, since we need it to insert the component.
+        if end.is_none() && component.is_some() {
             let temp_start = std::mem::take(&mut start);
             if let Some((start_tick, _)) = temp_start {
                 if current_interpolate_tick - start_tick < send_interval_delta_tick {
@@ -211,14 +243,14 @@ pub(crate) fn update_interpolate_status<C: SyncComponent>(
 /// - or at least SEND_INTERVAL_TICK_FACTOR * send_interval has passed. (this is to deal with the case where we only receive
 ///   one update; for example if we spawn the player and then they don't move. If we didn't do this
 ///   the interpolated entity would simply not appear)
+///
+/// If [`InterpolationSpawnMode::Immediate`] is used, the component is inserted as soon as we receive the first update.
 pub(crate) fn insert_interpolated_component<C: SyncComponent>(
     component_registry: Res<ComponentRegistry>,
     config: Res<ClientConfig>,
-    tick_manager: Res<TickManager>,
     mut commands: Commands,
     mut query: Query<(Entity, &InterpolateStatus<C>), Without<C>>,
 ) {
-    let tick = tick_manager.tick();
     // how many ticks between each interpolation update (add 1 to roughly take the ceil)
     // TODO: use something more precise, with the interpolation overstep?
     let send_interval_delta_tick = (SEND_INTERVAL_TICK_FACTOR
@@ -241,7 +273,9 @@ pub(crate) fn insert_interpolated_component<C: SyncComponent>(
                 entity_commands.insert(value);
             } else {
                 // we only have one update, but enough time has passed that we should add the component anyway
-                if tick - *start_tick >= send_interval_delta_tick {
+                if config.interpolation.spawn_mode == InterpolationSpawnMode::Immediate
+                    || status.current_tick - *start_tick >= send_interval_delta_tick
+                {
                     trace!("insert interpolated comp value because enough time has passed");
                     entity_commands.insert(start_value.clone());
                 }
@@ -274,6 +308,209 @@ pub(crate) fn interpolate<C: Component + Clone>(
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{default, Entity};
+    use bevy::utils::Duration;
+
+    use crate::client::interpolation::interpolate::InterpolateStatus;
+    use crate::client::interpolation::plugin::{InterpolationConfig, InterpolationSpawnMode};
+    use crate::prelude::client::{ClientConfig, Confirmed};
+    use crate::prelude::server::{Replicate, SyncTarget};
+    use crate::prelude::{
+        client, ComponentRegistry, InterpolationSnap, NetworkTarget, SharedConfig, TickConfig,
+    };
+    use crate::tests::protocol::*;
+    use crate::tests::stepper::BevyStepper;
+
+    /// Spawn an interpolated entity on the server, and return the server entity and the client's interpolated entity
+    fn setup(spawn_mode: InterpolationSpawnMode) -> (BevyStepper, Entity, Entity) {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            server_replication_send_interval: Duration::from_millis(100),
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let client_config = ClientConfig {
+            interpolation: InterpolationConfig::default().with_spawn_mode(spawn_mode),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, tick_duration);
+        stepper.init();
+
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                Replicate {
+                    sync: SyncTarget {
+                        interpolation: NetworkTarget::All,
+                        ..default()
+                    },
+                    ..default()
+                },
+                ComponentSyncModeFull(1.0),
+                InterpolationSnap::default(),
+            ))
+            .id();
+        let mut interpolated = None;
+        for _ in 0..20 {
+            stepper.frame_step();
+            interpolated = stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .and_then(|confirmed| {
+                    stepper
+                        .client_app
+                        .world()
+                        .get::<Confirmed>(confirmed)
+                        .and_then(|c| c.interpolated)
+                });
+            if interpolated.is_some() {
+                break;
+            }
+        }
+        let interpolated = interpolated.expect("interpolated entity was not spawned");
+        // let the interpolation systems run
+        stepper.frame_step();
+        (stepper, server_entity, interpolated)
+    }
+
+    /// Update the component on the server and return all the values taken by the interpolated component
+    fn interpolated_values(
+        stepper: &mut BevyStepper,
+        server_entity: Entity,
+        interpolated: Entity,
+        snap: bool,
+    ) -> Vec<f32> {
+        let mut entity_mut = stepper.server_app.world_mut().entity_mut(server_entity);
+        entity_mut.get_mut::<ComponentSyncModeFull>().unwrap().0 = 100.0;
+        if snap {
+            entity_mut.get_mut::<InterpolationSnap>().unwrap().snap();
+        }
+        let mut values = vec![];
+        for _ in 0..60 {
+            stepper.frame_step();
+            values.push(
+                stepper
+                    .client_app
+                    .world()
+                    .get::<ComponentSyncModeFull>(interpolated)
+                    .unwrap()
+                    .0,
+            );
+        }
+        values
+    }
+
+    #[test]
+    fn test_spawn_mode_wait_for_two_snapshots() {
+        let (mut stepper, _, interpolated) = setup(InterpolationSpawnMode::WaitForTwoSnapshots);
+        // we only have one snapshot, so the component is not inserted yet
+        assert!(stepper
+            .client_app
+            .world()
+            .get::<ComponentSyncModeFull>(interpolated)
+            .is_none());
+
+        // after enough time has passed, the component is inserted even without a second snapshot
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(interpolated),
+            Some(&ComponentSyncModeFull(1.0))
+        );
+    }
+
+    /// The interpolation status exposes the ticks of the two snapshots and the alpha between them
+    #[test]
+    fn test_interpolate_status() {
+        let (mut stepper, server_entity, interpolated) = setup(InterpolationSpawnMode::Immediate);
+        stepper
+            .server_app
+            .world_mut()
+            .get_mut::<ComponentSyncModeFull>(server_entity)
+            .unwrap()
+            .0 = 100.0;
+        let mut fractions = vec![];
+        for _ in 0..60 {
+            stepper.frame_step();
+            let status = stepper
+                .client_app
+                .world()
+                .get::<InterpolateStatus<ComponentSyncModeFull>>(interpolated)
+                .unwrap();
+            let (Some(start_tick), Some(end_tick), Some(fraction)) = (
+                status.start_tick(),
+                status.end_tick(),
+                status.interpolation_fraction(),
+            ) else {
+                continue;
+            };
+            assert!(start_tick < end_tick);
+            assert!(status.current_tick >= start_tick && status.current_tick < end_tick);
+            assert!((0.0..1.0).contains(&fraction));
+            fractions.push(fraction);
+        }
+        // we interpolated between the two snapshots
+        assert!(fractions.len() > 1);
+        assert!(fractions.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_spawn_mode_immediate() {
+        let (stepper, _, interpolated) = setup(InterpolationSpawnMode::Immediate);
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(interpolated),
+            Some(&ComponentSyncModeFull(1.0))
+        );
+    }
+
+    #[test]
+    fn test_interpolation_without_snap() {
+        let (mut stepper, server_entity, interpolated) = setup(InterpolationSpawnMode::Immediate);
+        let values = interpolated_values(&mut stepper, server_entity, interpolated, false);
+        assert!(values.iter().any(|v| *v > 1.0 && *v < 100.0));
+        assert_eq!(values.last(), Some(&100.0));
+    }
+
+    /// A teleport flagged by the server is not interpolated
+    #[test]
+    fn test_interpolation_snap_flag() {
+        let (mut stepper, server_entity, interpolated) = setup(InterpolationSpawnMode::Immediate);
+        let values = interpolated_values(&mut stepper, server_entity, interpolated, true);
+        assert!(values.iter().all(|v| *v == 1.0 || *v == 100.0));
+        assert_eq!(values.last(), Some(&100.0));
+    }
+
+    /// A teleport detected by the snap function is not interpolated
+    #[test]
+    fn test_interpolation_snap_fn() {
+        let (mut stepper, server_entity, interpolated) = setup(InterpolationSpawnMode::Immediate);
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ComponentRegistry>()
+            .set_interpolation_snap::<ComponentSyncModeFull>(|previous, next| {
+                (next.0 - previous.0).abs() > 10.0
+            });
+        let values = interpolated_values(&mut stepper, server_entity, interpolated, false);
+        assert!(values.iter().all(|v| *v == 1.0 || *v == 100.0));
+        assert_eq!(values.last(), Some(&100.0));
+    }
+}
+
 // #[cfg(test)]
 // mod tests {
 //     #![allow(unused_imports)]
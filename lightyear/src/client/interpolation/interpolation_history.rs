@@ -11,7 +11,7 @@ use crate::client::connection::ConnectionManager;
 use crate::client::interpolation::interpolate::InterpolateStatus;
 use crate::client::interpolation::resource::InterpolationManager;
 use crate::client::interpolation::Interpolated;
-use crate::prelude::{ComponentRegistry, HasAuthority, TickManager};
+use crate::prelude::{ComponentRegistry, HasAuthority, InterpolationSnap, TickManager};
 use crate::shared::tick_manager::Tick;
 use crate::utils::ready_buffer::ReadyBuffer;
 
@@ -27,6 +27,8 @@ pub struct ConfirmedHistory<C: SyncComponent> {
 
     // We will only store the history for the ticks where the component got updated
     pub buffer: ReadyBuffer<Tick, C>,
+    /// Ticks of the updates that are discontinuous: we won't interpolate towards them
+    pub(crate) snap_ticks: Vec<Tick>,
 }
 
 impl<C: SyncComponent> Default for ConfirmedHistory<C> {
@@ -46,12 +48,26 @@ impl<C: SyncComponent> ConfirmedHistory<C> {
     pub fn new() -> Self {
         Self {
             buffer: ReadyBuffer::new(),
+            snap_ticks: Vec::new(),
         }
     }
 
     /// Reset the history for this component
     pub(crate) fn clear(&mut self) {
         self.buffer = ReadyBuffer::new();
+        self.snap_ticks.clear();
+    }
+
+    /// Add a discontinuous update to the history: the interpolation will jump directly to this value
+    /// instead of interpolating towards it
+    pub(crate) fn push_snap(&mut self, tick: Tick, value: C) {
+        self.buffer.push(tick, value);
+        self.snap_ticks.push(tick);
+    }
+
+    /// Returns true if the update at this tick is discontinuous
+    pub(crate) fn is_snap(&self, tick: Tick) -> bool {
+        self.snap_ticks.contains(&tick)
     }
 
     pub(crate) fn peek(&mut self) -> Option<(Tick, &C)> {
@@ -70,6 +86,7 @@ impl<C: SyncComponent> ConfirmedHistory<C> {
     /// contains gaps. Therefore, we need to always leave a value in the history buffer so that we can
     /// get the values for the future ticks
     pub(crate) fn pop_until_tick(&mut self, tick: Tick) -> Option<(Tick, C)> {
+        self.snap_ticks.retain(|snap_tick| *snap_tick > tick);
         self.buffer.pop_until(&tick)
     }
 }
@@ -142,10 +159,16 @@ pub(crate) fn apply_confirmed_update_mode_full<C: SyncComponent>(
         &mut ConfirmedHistory<C>,
         (With<Interpolated>, Without<Confirmed>),
     >,
-    confirmed_entities: Query<(Entity, &Confirmed, Ref<C>, Has<HasAuthority>)>,
+    confirmed_entities: Query<(
+        Entity,
+        &Confirmed,
+        Ref<C>,
+        Has<HasAuthority>,
+        Option<Ref<InterpolationSnap>>,
+    )>,
 ) {
     let kind = std::any::type_name::<C>();
-    for (confirmed_entity, confirmed, confirmed_component, has_authority) in
+    for (confirmed_entity, confirmed, confirmed_component, has_authority, snap) in
         confirmed_entities.iter()
     {
         if let Some(p) = confirmed.interpolated {
@@ -177,7 +200,13 @@ pub(crate) fn apply_confirmed_update_mode_full<C: SyncComponent>(
                     let _ = manager.map_entities(&mut component, component_registry.as_ref());
                     trace!(?kind, tick = ?tick, "adding confirmed update to history");
                     // update the history at the value that the entity currently is
-                    history.buffer.push(tick, component);
+                    // (if the server marked the update as a teleport, we won't interpolate towards it)
+                    if snap.is_some_and(|snap| snap.is_changed()) {
+                        trace!(?kind, tick = ?tick, "confirmed update is a snap");
+                        history.push_snap(tick, component);
+                    } else {
+                        history.buffer.push(tick, component);
+                    }
 
                     // TODO: here we do not want to update directly the component, that will be done during interpolation
                 }
@@ -9,7 +9,7 @@ use crate::client::interpolation::interpolate::{
 use crate::client::interpolation::resource::InterpolationManager;
 use crate::client::interpolation::spawn::spawn_interpolated_entity;
 use crate::client::interpolation::Interpolated;
-use crate::client::run_conditions::is_synced;
+use crate::client::run_conditions::{is_synced, is_warmup_done};
 use crate::prelude::is_host_server;
 
 use super::interpolation_history::{
@@ -24,17 +24,55 @@ pub struct InterpolationDelay {
     /// to interpolate towards.
     /// Set to 0.0 if you want to only use the Ratio
     pub min_delay: Duration,
+    /// The minimum delay expressed as a number of ticks; it is converted to a duration with the tick duration.
+    ///
+    /// The minimum delay is the max of `min_delay` and `min_delay_ticks`.
+    /// Set to 0 if you want to only use the Delay or the Ratio
+    pub min_delay_ticks: u16,
     /// The interpolation delay is a ratio of the update-rate from the server
     /// The higher the server update_rate (i.e. smaller send_interval), the smaller the interpolation delay
     /// Set to 0.0 if you want to only use the Delay
     pub send_interval_ratio: f32,
+    /// If set, the delay is increased by a multiple of the jitter of the connection
+    pub adaptive: Option<AdaptiveInterpolationDelay>,
 }
 
 impl Default for InterpolationDelay {
     fn default() -> Self {
         Self {
             min_delay: Duration::from_millis(0),
+            min_delay_ticks: 0,
             send_interval_ratio: 2.0,
+            adaptive: None,
+        }
+    }
+}
+
+/// Adapt the interpolation delay to the jitter of the connection:
+/// a higher jitter requires a bigger delay so that we always have a server snapshot to interpolate towards,
+/// while a lower jitter allows for a smaller delay.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct AdaptiveInterpolationDelay {
+    /// How many multiples of the jitter estimate are added to the delay
+    pub jitter_multiple: f32,
+    /// The interpolation delay will never be smaller than this
+    pub min_delay: Duration,
+    /// The interpolation delay will never be bigger than this
+    pub max_delay: Duration,
+    /// Fraction of the difference between the current delay and the target delay that is applied
+    /// on every update (between 0.0 and 1.0).
+    ///
+    /// Lower values make the delay change more gradually, to avoid oscillations.
+    pub smoothing: f32,
+}
+
+impl Default for AdaptiveInterpolationDelay {
+    fn default() -> Self {
+        Self {
+            jitter_multiple: 3.0,
+            min_delay: Duration::from_millis(0),
+            max_delay: Duration::from_millis(500),
+            smoothing: 0.05,
         }
     }
 }
@@ -45,23 +83,77 @@ impl InterpolationDelay {
         self
     }
 
+    /// Set the minimum delay as a number of ticks
+    pub fn with_min_delay_ticks(mut self, min_delay_ticks: u16) -> Self {
+        self.min_delay_ticks = min_delay_ticks;
+        self
+    }
+
     pub fn with_send_interval_ratio(mut self, send_interval_ratio: f32) -> Self {
         self.send_interval_ratio = send_interval_ratio;
         self
     }
 
+    /// Adapt the delay to the jitter of the connection
+    pub fn with_adaptive(mut self, adaptive: AdaptiveInterpolationDelay) -> Self {
+        self.adaptive = Some(adaptive);
+        self
+    }
+
     /// How much behind the latest server update we want the interpolation time to be
-    pub(crate) fn to_duration(&self, server_send_interval: Duration) -> Duration {
+    pub(crate) fn to_duration(
+        &self,
+        server_send_interval: Duration,
+        tick_duration: Duration,
+    ) -> Duration {
         // TODO: deal with server_send_interval = 0 (set to frame rate)
         let ratio_value = server_send_interval.mul_f32(self.send_interval_ratio);
-        std::cmp::max(ratio_value, self.min_delay)
+        let min_delay = self
+            .min_delay
+            .max(tick_duration * self.min_delay_ticks as u32);
+        std::cmp::max(ratio_value, min_delay)
+    }
+
+    /// The delay that we want to reach, taking into account the jitter of the connection
+    /// if the delay is adaptive
+    pub(crate) fn target_duration(
+        &self,
+        server_send_interval: Duration,
+        tick_duration: Duration,
+        jitter: Duration,
+    ) -> Duration {
+        let base = self.to_duration(server_send_interval, tick_duration);
+        match &self.adaptive {
+            None => base,
+            Some(adaptive) => (base + jitter.mul_f32(adaptive.jitter_multiple)).clamp(
+                adaptive.min_delay,
+                adaptive.max_delay.max(adaptive.min_delay),
+            ),
+        }
     }
 }
 
+/// How the interpolated components of a newly-spawned `Interpolated` entity are inserted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Reflect)]
+pub enum InterpolationSpawnMode {
+    /// Wait until we have received two snapshots, so that the entity starts interpolating as soon as it appears.
+    ///
+    /// If no second snapshot arrives within roughly one server send_interval (for example because the entity
+    /// is not moving), the component is inserted anyway with the first received value.
+    #[default]
+    WaitForTwoSnapshots,
+    /// Insert the component as soon as the first snapshot is received.
+    ///
+    /// The entity will appear earlier but will stay in place until the next snapshot is received.
+    Immediate,
+}
+
 /// Config to specify how the snapshot interpolation should behave
 #[derive(Clone, Copy, Reflect)]
 pub struct InterpolationConfig {
     pub delay: InterpolationDelay,
+    /// How to handle the interpolated components of newly-spawned entities
+    pub spawn_mode: InterpolationSpawnMode,
     // How long are we keeping the history of the confirmed entities so we can interpolate between them?
     // pub(crate) interpolation_buffer_size: Duration,
 }
@@ -71,6 +163,7 @@ impl Default for InterpolationConfig {
     fn default() -> Self {
         Self {
             delay: InterpolationDelay::default(),
+            spawn_mode: InterpolationSpawnMode::default(),
             // interpolation_buffer_size: Duration::from_millis(100),
         }
     }
@@ -81,6 +174,11 @@ impl InterpolationConfig {
         self.delay = delay;
         self
     }
+
+    pub fn with_spawn_mode(mut self, spawn_mode: InterpolationSpawnMode) -> Self {
+        self.spawn_mode = spawn_mode;
+        self
+    }
 }
 
 #[derive(Default)]
@@ -169,11 +267,15 @@ pub fn add_interpolation_systems<C: SyncComponent>(app: &mut App) {
 
 impl Plugin for InterpolationPlugin {
     fn build(&self, app: &mut App) {
-        let should_run_interpolation = not(is_host_server).and_then(is_synced);
+        let should_run_interpolation = not(is_host_server)
+            .and_then(is_synced)
+            .and_then(is_warmup_done);
 
         // REFLECT
         app.register_type::<InterpolationConfig>()
             .register_type::<InterpolationDelay>()
+            .register_type::<AdaptiveInterpolationDelay>()
+            .register_type::<InterpolationSpawnMode>()
             .register_type::<Interpolated>();
 
         // RESOURCES
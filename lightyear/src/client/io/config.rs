@@ -1,17 +1,27 @@
 use crate::client::io::transport::{ClientTransportBuilder, ClientTransportBuilderEnum};
 use crate::client::io::{Io, IoContext};
 use crate::prelude::CompressionConfig;
-use crate::transport::config::SharedIoConfig;
+use crate::shared::time_source::RealTimeSource;
+use crate::transport::config::{SharedIoConfig, SocketConfig};
+use crate::transport::custom::{CustomTransport, CustomTransportBuilder};
 use crate::transport::dummy::DummyIo;
 use crate::transport::error::Result;
-use crate::transport::io::{BaseIo, IoStats};
+use crate::transport::io::{BaseIo, IoCounters};
 use crate::transport::local::LocalChannelBuilder;
+use crate::transport::middleware::bandwidth::BandwidthLimitedSender;
+use crate::transport::middleware::checksum::{ChecksumReceiver, ChecksumSender};
+#[cfg(any(feature = "zstd", feature = "lz4"))]
+use crate::transport::middleware::compression::packet::{
+    CompressedPacketReceiver, CompressedPacketSender,
+};
 #[cfg(feature = "zstd")]
 use crate::transport::middleware::compression::zstd::compression::ZstdCompressor;
 #[cfg(feature = "zstd")]
 use crate::transport::middleware::compression::zstd::decompression::ZstdDecompressor;
-use crate::transport::middleware::conditioner::LinkConditioner;
-use crate::transport::middleware::PacketReceiverWrapper;
+use crate::transport::middleware::conditioner::{ConditionedPacketSender, LinkConditioner};
+use crate::transport::middleware::{
+    apply_receiver_middleware, apply_sender_middleware, PacketReceiverWrapper,
+};
 #[cfg(not(target_family = "wasm"))]
 use crate::transport::udp::UdpSocketBuilder;
 #[cfg(feature = "websocket")]
@@ -22,6 +32,7 @@ use crate::transport::{BoxedReceiver, Transport, LOCAL_SOCKET};
 use bevy::prelude::TypePath;
 use crossbeam_channel::{Receiver, Sender};
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 /// Use this to configure the [`Transport`] that will be used to establish a connection with the
 /// server.
@@ -48,16 +59,22 @@ pub enum ClientTransport {
         recv: Receiver<Vec<u8>>,
         send: Sender<Vec<u8>>,
     },
+    /// Use a transport provided by the user
+    Custom(Arc<dyn CustomTransport>),
     /// Dummy transport if the connection handles its own io (for example steam sockets)
     Dummy,
 }
 
 impl ClientTransport {
-    pub(super) fn build(self) -> ClientTransportBuilderEnum {
+    #[cfg_attr(target_family = "wasm", allow(unused_variables))]
+    pub(super) fn build(self, socket_config: SocketConfig) -> ClientTransportBuilderEnum {
         match self {
             #[cfg(not(target_family = "wasm"))]
             ClientTransport::UdpSocket(addr) => {
-                ClientTransportBuilderEnum::UdpSocket(UdpSocketBuilder { local_addr: addr })
+                ClientTransportBuilderEnum::UdpSocket(UdpSocketBuilder {
+                    local_addr: addr,
+                    socket_config,
+                })
             }
             #[cfg(all(feature = "webtransport", not(target_family = "wasm")))]
             ClientTransport::WebTransportClient {
@@ -77,7 +94,14 @@ impl ClientTransport {
                 server_addr,
                 certificate_digest,
             }),
-            #[cfg(feature = "websocket")]
+            #[cfg(all(feature = "websocket", not(target_family = "wasm")))]
+            ClientTransport::WebSocketClient { server_addr } => {
+                ClientTransportBuilderEnum::WebSocketClient(WebSocketClientSocketBuilder {
+                    server_addr,
+                    tcp_nodelay: socket_config.tcp_nodelay,
+                })
+            }
+            #[cfg(all(feature = "websocket", target_family = "wasm"))]
             ClientTransport::WebSocketClient { server_addr } => {
                 ClientTransportBuilderEnum::WebSocketClient(WebSocketClientSocketBuilder {
                     server_addr,
@@ -86,6 +110,9 @@ impl ClientTransport {
             ClientTransport::LocalChannel { recv, send } => {
                 ClientTransportBuilderEnum::LocalChannel(LocalChannelBuilder { recv, send })
             }
+            ClientTransport::Custom(transport) => {
+                ClientTransportBuilderEnum::Custom(CustomTransportBuilder(transport))
+            }
             ClientTransport::Dummy => ClientTransportBuilderEnum::Dummy(DummyIo),
         }
     }
@@ -106,36 +133,74 @@ impl Default for ClientTransport {
 
 impl SharedIoConfig<ClientTransport> {
     pub fn connect(self) -> Result<Io> {
-        let (transport, state, io_rx, network_tx) = self.transport.build().connect()?;
+        let (transport, state, io_rx, network_tx) = self.transport.build(self.socket).connect()?;
         let local_addr = transport.local_addr();
-        #[allow(unused_mut)]
-        let (mut sender, receiver) = transport.split();
-        #[allow(unused_mut)]
-        let mut receiver: BoxedReceiver = if let Some(conditioner_config) = self.conditioner {
-            let conditioner = LinkConditioner::new(conditioner_config);
-            Box::new(conditioner.wrap(receiver))
-        } else {
-            Box::new(receiver)
-        };
+        let (sender, receiver) = transport.split();
+        let mut sender = apply_sender_middleware(sender, &self.sender_middleware);
+        if let Some(conditioner_config) = self.outgoing_conditioner {
+            let mut conditioner = LinkConditioner::new(conditioner_config);
+            if let Some(time_source) = &self.time_source {
+                conditioner = conditioner.with_time_source(time_source.clone());
+            }
+            sender = Box::new(ConditionedPacketSender::new(sender, conditioner));
+        }
+        if let Some(max_bandwidth) = self.max_send_bandwidth {
+            let time_source = self
+                .time_source
+                .clone()
+                .unwrap_or_else(|| Arc::new(RealTimeSource));
+            sender = Box::new(BandwidthLimitedSender::new(
+                sender,
+                max_bandwidth,
+                time_source,
+            ));
+        }
+        let receiver = apply_receiver_middleware(receiver, &self.receiver_middleware);
+        let mut receiver: BoxedReceiver =
+            if let Some(conditioner_config) = self.incoming_conditioner {
+                let mut conditioner = LinkConditioner::new(conditioner_config);
+                if let Some(time_source) = &self.time_source {
+                    conditioner = conditioner.with_time_source(time_source.clone());
+                }
+                Box::new(conditioner.wrap(receiver))
+            } else {
+                Box::new(receiver)
+            };
+        let stats = Arc::new(IoCounters::new(
+            self.bandwidth_window,
+            self.time_source.clone(),
+        ));
+        if self.checksum {
+            sender = Box::new(ChecksumSender::new(sender));
+            receiver = Box::new(ChecksumReceiver::new(receiver, stats.clone()));
+        }
         match self.compression {
             CompressionConfig::None => {}
             #[cfg(feature = "zstd")]
             CompressionConfig::Zstd { level } => {
-                use crate::transport::middleware::PacketSenderWrapper;
-                let compressor = ZstdCompressor::new(level);
-                sender = Box::new(compressor.wrap(sender));
-                let decompressor = ZstdDecompressor::new();
-                receiver = Box::new(decompressor.wrap(receiver));
+                let dictionary = self.compression_dictionary.as_deref();
+                sender = Box::new(CompressedPacketSender::new(
+                    sender,
+                    ZstdCompressor::new(level, dictionary),
+                    self.compression_min_size,
+                ));
+                receiver = Box::new(CompressedPacketReceiver::new(
+                    receiver,
+                    ZstdDecompressor::new(dictionary),
+                ));
             }
             #[cfg(feature = "lz4")]
             CompressionConfig::Lz4 => {
-                use crate::transport::middleware::PacketSenderWrapper;
-                let compressor =
-                    crate::transport::middleware::compression::lz4::Compressor::default();
-                sender = Box::new(compressor.wrap(sender));
-                let decompressor =
-                    crate::transport::middleware::compression::lz4::Decompressor::default();
-                receiver = Box::new(decompressor.wrap(receiver));
+                use crate::transport::middleware::compression::lz4::{Compressor, Decompressor};
+                sender = Box::new(CompressedPacketSender::new(
+                    sender,
+                    Compressor::default(),
+                    self.compression_min_size,
+                ));
+                receiver = Box::new(CompressedPacketReceiver::new(
+                    receiver,
+                    Decompressor::default(),
+                ));
             }
         }
         Ok(BaseIo {
@@ -143,7 +208,7 @@ impl SharedIoConfig<ClientTransport> {
             sender,
             receiver,
             state,
-            stats: IoStats::default(),
+            stats,
             context: IoContext {
                 event_sender: network_tx,
                 event_receiver: io_rx,
@@ -3,6 +3,9 @@
 pub(crate) mod config;
 pub(crate) mod transport;
 
+use std::net::SocketAddr;
+
+use crate::connection::client::IoConfig;
 use crate::transport::error::{Error, Result};
 use crate::transport::io::{BaseIo, IoState};
 use async_channel::{Receiver, Sender};
@@ -17,8 +20,10 @@ pub struct IoContext {
 pub type Io = BaseIo<IoContext>;
 
 impl Io {
+    /// Close the io: the transport is released immediately, even if the io is not dropped
     pub fn close(&mut self) -> Result<()> {
         self.state = IoState::Disconnected;
+        self.close_transport();
         if let Some(event_sender) = self.context.event_sender.as_mut() {
             event_sender
                 .try_send(ClientIoEvent::Disconnected(
@@ -28,6 +33,22 @@ impl Io {
         }
         Ok(())
     }
+
+    /// Replace the transport of the io with the one built from `config` (for example to migrate
+    /// from a [`LocalChannel`](crate::client::io::config::ClientTransport::LocalChannel) to a UDP socket).
+    ///
+    /// The packets that are in flight on the previous transport are dropped, but the connection
+    /// (netcode session, channels, etc.) is kept. The io stats are reset.
+    ///
+    /// With netcode, the server migrates the client to its new address when it receives the first
+    /// authenticated keep-alive or payload packet from it.
+    /// If the new transport cannot be built, the current transport is kept.
+    pub fn set_transport(&mut self, config: &IoConfig) -> Result<()> {
+        let io = config.clone().connect()?;
+        // drop the previous transport
+        *self = io;
+        Ok(())
+    }
 }
 
 /// Events that will be sent from the io thread to the main thread
@@ -35,6 +56,8 @@ impl Io {
 pub(crate) enum ClientIoEvent {
     Connected,
     Disconnected(Error),
+    /// The socket became invalid and was bound again to this local address
+    Rebound(SocketAddr),
 }
 
 #[derive(Deref, DerefMut)]
@@ -42,3 +65,38 @@ pub(crate) struct ClientIoEventReceiver(pub(crate) Receiver<ClientIoEvent>);
 
 #[derive(Deref, DerefMut)]
 pub(crate) struct ClientNetworkEventSender(pub(crate) Sender<ClientIoEvent>);
+
+#[cfg(test)]
+mod tests {
+    use crate::client::io::config::ClientTransport;
+    use crate::transport::{PacketReceiver, PacketSender, LOCAL_SOCKET};
+
+    use super::*;
+
+    #[test]
+    fn test_set_transport() {
+        let (send, recv) = crossbeam_channel::unbounded();
+        let mut io = IoConfig::from_transport(ClientTransport::LocalChannel { send, recv })
+            .connect()
+            .unwrap();
+        assert_eq!(io.local_addr(), LOCAL_SOCKET);
+
+        let (send, recv) = crossbeam_channel::unbounded();
+        io.set_transport(&IoConfig::from_transport(ClientTransport::LocalChannel {
+            send: send.clone(),
+            recv: recv.clone(),
+        }))
+        .unwrap();
+        io.send(b"hello", &LOCAL_SOCKET).unwrap();
+        assert_eq!(recv.try_recv().unwrap(), b"hello");
+        send.send(b"world".to_vec()).unwrap();
+        assert_eq!(io.recv().unwrap().unwrap().0, b"world");
+
+        // migrate to a UDP socket
+        io.set_transport(&IoConfig::from_transport(ClientTransport::UdpSocket(
+            "127.0.0.1:0".parse().unwrap(),
+        )))
+        .unwrap();
+        assert_ne!(io.local_addr().port(), 0);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::client::io::{ClientIoEventReceiver, ClientNetworkEventSender};
+use crate::transport::custom::{CustomTransportBuilder, CustomTransportSocket};
 use crate::transport::dummy::DummyIo;
 use crate::transport::error::Error as TransportError;
 use crate::transport::io::IoState;
@@ -42,6 +43,7 @@ pub(crate) enum ClientTransportBuilderEnum {
     #[cfg(feature = "websocket")]
     WebSocketClient(WebSocketClientSocketBuilder),
     LocalChannel(LocalChannelBuilder),
+    Custom(CustomTransportBuilder),
     Dummy(DummyIo),
 }
 
@@ -55,5 +57,6 @@ pub(crate) enum ClientTransportEnum {
     #[cfg(feature = "websocket")]
     WebSocketClient(WebSocketClientSocket),
     LocalChannel(LocalChannel),
+    Custom(CustomTransportSocket),
     Dummy(DummyIo),
 }
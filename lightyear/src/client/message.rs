@@ -1,6 +1,7 @@
 //! Defines the [`ClientMessage`] enum used to send messages from the client to the server
 
-use bevy::prelude::{App, EventWriter, IntoSystemConfigs, PreUpdate, Res, ResMut};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::{App, EventReader, EventWriter, IntoSystemConfigs, Res, ResMut};
 use byteorder::WriteBytesExt;
 use bytes::Bytes;
 use tracing::error;
@@ -12,7 +13,7 @@ use crate::protocol::message::{MessageKind, MessageRegistry};
 use crate::serialize::reader::Reader;
 use crate::serialize::{SerializationError, ToBytes};
 use crate::shared::replication::network_target::NetworkTarget;
-use crate::shared::sets::{ClientMarker, InternalMainSet};
+use crate::shared::sets::{ClientMarker, InternalMainSet, NetworkingSchedules};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct ClientMessage {
@@ -78,11 +79,34 @@ fn read_message<M: Message>(
     }
 }
 
+/// [`SystemParam`] to read the messages of type `M` received from the server.
+///
+/// This is a typed wrapper around `EventReader<MessageEvent<M>>`:
+/// ```rust,ignore
+/// fn handle_chat(mut receiver: MessageReceiver<ChatMessage>) {
+///     for message in receiver.receive() {
+///         // do something with the message
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct MessageReceiver<'w, 's, M: Message> {
+    events: EventReader<'w, 's, MessageEvent<M>>,
+}
+
+impl<'w, 's, M: Message> MessageReceiver<'w, 's, M> {
+    /// Iterate through the messages received since the last time this system ran
+    pub fn receive(&mut self) -> impl Iterator<Item = &M> + '_ {
+        self.events.read().map(|event| event.message())
+    }
+}
+
 /// Register a message that can be sent from server to client
 pub(crate) fn add_client_receive_message_from_server<M: Message>(app: &mut App) {
+    let schedules = NetworkingSchedules::of(app);
     app.add_event::<MessageEvent<M>>();
     app.add_systems(
-        PreUpdate,
+        schedules.receive,
         read_message::<M>
             .in_set(InternalMainSet::<ClientMarker>::EmitEvents)
             .run_if(is_connected),
@@ -196,7 +220,7 @@ mod tests {
     use crate::serialize::writer::Writer;
     use crate::tests::host_server_stepper::HostServerStepper;
     use crate::tests::protocol::{Channel1, StringMessage};
-    use bevy::prelude::{EventReader, Resource, Update};
+    use bevy::prelude::{Resource, Update};
 
     #[test]
     fn client_message_serde() {
@@ -250,4 +274,42 @@ mod tests {
         // verify that the server received the message
         assert_eq!(stepper.server_app.world().resource::<Counter>().0, 1);
     }
+
+    #[derive(Resource, Default)]
+    struct Received(Vec<String>);
+
+    fn receive_messages(
+        mut received: ResMut<Received>,
+        mut receiver: MessageReceiver<StringMessage>,
+    ) {
+        received
+            .0
+            .extend(receiver.receive().map(|message| message.0.clone()));
+    }
+
+    #[test]
+    fn client_message_receiver() {
+        use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+
+        let mut stepper = BevyStepper::default();
+        stepper.client_app.init_resource::<Received>();
+        stepper.client_app.add_systems(Update, receive_messages);
+
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<crate::prelude::server::ConnectionManager>()
+            .send_message::<Channel1, StringMessage>(
+                crate::prelude::ClientId::Netcode(TEST_CLIENT_ID),
+                &mut StringMessage("a".to_string()),
+            )
+            .unwrap();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        assert_eq!(
+            stepper.client_app.world().resource::<Received>().0,
+            vec!["a".to_string()]
+        );
+    }
 }
@@ -5,11 +5,14 @@ use async_channel::TryRecvError;
 use bevy::ecs::system::{RunSystemOnce, SystemChangeTick};
 use bevy::prelude::ResMut;
 use bevy::prelude::*;
-use tracing::{error, trace};
+use tracing::{debug, error, trace};
 
 use crate::client::config::ClientConfig;
 use crate::client::connection::ConnectionManager;
-use crate::client::events::{ConnectEvent, DisconnectEvent};
+use crate::client::events::{
+    ConnectEvent, DisconnectEvent, InputNotAckedEvent, MessageAckEvent, MessageTimeoutEvent,
+    ReadyEvent, ReconnectAttemptEvent,
+};
 use crate::client::interpolation::Interpolated;
 use crate::client::io::ClientIoEvent;
 use crate::client::networking::utils::AppStateExt;
@@ -19,6 +22,8 @@ use crate::client::run_conditions::is_disconnected;
 use crate::client::sync::SyncSet;
 use crate::connection::client::{ClientConnection, ConnectionState, DisconnectReason, NetClient};
 use crate::connection::server::IoConfig;
+use crate::connection::server::{DeniedReason, DisconnectReason as ServerDisconnectReason};
+use crate::packet::message_ack::MessageDelivery;
 use crate::prelude::{
     is_host_server, ChannelRegistry, MainSet, MessageRegistry, TickManager, TimeManager,
 };
@@ -26,14 +31,17 @@ use crate::protocol::component::ComponentRegistry;
 use crate::server::clients::ControlledEntities;
 use crate::shared::config::Mode;
 use crate::shared::replication::components::Replicated;
-use crate::shared::sets::{ClientMarker, InternalMainSet};
+use crate::shared::sets::{ClientMarker, InternalMainSet, NetworkingSchedules};
 use crate::transport::io::IoState;
+use crate::transport::udp::TransportRebindEvent;
+use crate::transport::PacketSender;
 
 #[derive(Default)]
 pub(crate) struct ClientNetworkingPlugin;
 
 impl Plugin for ClientNetworkingPlugin {
     fn build(&self, app: &mut App) {
+        let schedules = NetworkingSchedules::of(app);
         app
             // REFLECTION
             .register_type::<HostServerMetadata>()
@@ -42,9 +50,10 @@ impl Plugin for ClientNetworkingPlugin {
             .init_state_without_entering(NetworkingState::Disconnected)
             // RESOURCE
             .init_resource::<HostServerMetadata>()
+            .init_resource::<Reconnection>()
             // SYSTEM SETS
             .configure_sets(
-                PreUpdate,
+                schedules.receive,
                 (
                     InternalMainSet::<ClientMarker>::Receive
                         .in_set(MainSet::Receive)
@@ -57,7 +66,11 @@ impl Plugin for ClientNetworkingPlugin {
                     .run_if(not(is_disconnected)),
             )
             .configure_sets(
-                PostUpdate,
+                schedules.receive,
+                MainSet::Simulation.after(MainSet::EmitEvents),
+            )
+            .configure_sets(
+                schedules.send,
                 // run sync before send because some send systems need to know if the client is synced
                 // we don't send packets every frame, but on a timer instead
                 (
@@ -69,7 +82,7 @@ impl Plugin for ClientNetworkingPlugin {
             )
             // SYSTEMS
             .add_systems(
-                PreUpdate,
+                schedules.receive,
                 listen_io_state
                     // we are running the listen_io_state in a different set because it can impact the run_condition for the
                     // Receive system set
@@ -77,13 +90,27 @@ impl Plugin for ClientNetworkingPlugin {
                     .run_if(not(is_host_server.or_else(is_disconnected))),
             )
             .add_systems(
-                PreUpdate,
+                schedules.receive,
                 (listen_io_state, (receive_packets, receive).chain())
                     .in_set(InternalMainSet::<ClientMarker>::Receive),
             )
+            .add_systems(
+                schedules.receive,
+                reconnect.run_if(is_disconnected.and_then(not(is_host_server))),
+            )
+            .add_systems(
+                schedules.receive,
+                check_input_acks
+                    .in_set(InternalMainSet::<ClientMarker>::EmitEvents)
+                    .run_if(not(is_host_server)),
+            )
+            .add_systems(
+                schedules.receive,
+                emit_message_ack_events.in_set(InternalMainSet::<ClientMarker>::EmitEvents),
+            )
             // TODO: make HostServer a computed state?
             .add_systems(
-                PostUpdate,
+                schedules.send,
                 (
                     (
                         send.run_if(not(is_host_server)),
@@ -216,10 +243,22 @@ pub(crate) fn send(
     let packet_bytes = connection
         .send_packets(time_manager.as_ref(), tick_manager.as_ref())
         .unwrap();
-    for packet_byte in packet_bytes {
-        let _ = netcode.send(packet_byte.as_slice()).map_err(|e| {
-            error!("Error sending packet: {}", e);
-        });
+    for packet_byte in packet_bytes.iter() {
+        if let Err(e) = netcode.send(packet_byte.as_slice()) {
+            // recoverable errors (for example when the bandwidth limit is exceeded) can happen every frame
+            if e.is_recoverable() {
+                debug!("Could not send packet: {}", e);
+            } else {
+                error!("Error sending packet: {}", e);
+            }
+        }
+    }
+    connection.recycle_payloads(packet_bytes);
+    // send the packets that were buffered by the io (for example with UDP GSO)
+    if let Some(io) = netcode.io_mut() {
+        let _ = io
+            .flush()
+            .inspect_err(|e| error!("Error flushing io: {}", e));
     }
 
     // no need to clear the connection, because we already std::mem::take it
@@ -258,6 +297,7 @@ pub(crate) fn sync_update(
     mut time_manager: ResMut<TimeManager>,
     mut tick_manager: ResMut<TickManager>,
     mut virtual_time: ResMut<Time<Virtual>>,
+    mut ready_events: EventWriter<ReadyEvent>,
 ) {
     let connection = connection.into_inner();
     // NOTE: this triggers change detection
@@ -286,6 +326,65 @@ pub(crate) fn sync_update(
         let relative_speed = time_manager.get_relative_speed();
         virtual_time.set_relative_speed(relative_speed);
     }
+
+    let pongs_recv = connection.ping_manager.pongs_recv;
+    if connection
+        .sync_manager
+        .update_warmup(time_manager.delta(), pongs_recv)
+    {
+        ready_events.send(ReadyEvent);
+        commands.trigger(ReadyEvent);
+    }
+}
+
+/// Emit an [`InputNotAckedEvent`] if the server has not acked our inputs for too long
+pub(crate) fn check_input_acks(
+    mut commands: Commands,
+    config: Res<ClientConfig>,
+    mut connection: ResMut<ConnectionManager>,
+    time_manager: Res<TimeManager>,
+    mut events: EventWriter<InputNotAckedEvent>,
+) {
+    if let Some(unacked_duration) = connection
+        .input_ack
+        .update(time_manager.delta(), config.input.ack_timeout)
+    {
+        warn!(
+            ?unacked_duration,
+            "the server has not acknowledged our inputs; are they reaching the server?"
+        );
+        let event = InputNotAckedEvent {
+            last_acked_tick: connection.input_ack.last_acked_tick,
+            unacked_duration,
+        };
+        events.send(event);
+        commands.trigger(event);
+    }
+}
+
+/// Emit a [`MessageAckEvent`] or a [`MessageTimeoutEvent`] for the messages sent with
+/// [`send_message_with_ack`](ConnectionManager::send_message_with_ack)
+pub(crate) fn emit_message_ack_events(
+    mut connection: ResMut<ConnectionManager>,
+    mut ack_events: EventWriter<MessageAckEvent>,
+    mut timeout_events: EventWriter<MessageTimeoutEvent>,
+) {
+    for (handle, delivery) in connection.message_manager.message_acks.drain() {
+        match delivery {
+            MessageDelivery::Acked => {
+                ack_events.send(MessageAckEvent {
+                    handle,
+                    context: (),
+                });
+            }
+            MessageDelivery::Lost => {
+                timeout_events.send(MessageTimeoutEvent {
+                    handle,
+                    context: (),
+                });
+            }
+        }
+    }
 }
 
 /// Bevy [`State`] representing the networking state of the client.
@@ -302,8 +401,10 @@ pub enum NetworkingState {
 
 /// Listen to [`ClientIoEvent`]s and update the [`IoState`] and [`NetworkingState`] accordingly
 fn listen_io_state(
+    mut commands: Commands,
     mut next_state: ResMut<NextState<NetworkingState>>,
     mut netclient: ResMut<ClientConnection>,
+    mut rebind_events: EventWriter<TransportRebindEvent>,
 ) {
     let mut disconnect = false;
     if let Some(io) = netclient.io_mut() {
@@ -313,6 +414,11 @@ fn listen_io_state(
                     debug!("Io is connected!");
                     io.state = IoState::Connected;
                 }
+                Ok(ClientIoEvent::Rebound(local_addr)) => {
+                    let event = TransportRebindEvent { local_addr };
+                    rebind_events.send(event);
+                    commands.trigger(event);
+                }
                 Ok(ClientIoEvent::Disconnected(e)) => {
                     error!("Error from io: {}", e);
                     io.state = IoState::Disconnected;
@@ -356,8 +462,10 @@ fn on_connect(
     mut connect_event_writer: EventWriter<ConnectEvent>,
     mut commands: Commands,
     netcode: Res<ClientConnection>,
+    mut reconnection: ResMut<Reconnection>,
     mut query: Query<&mut ReplicateToServer>,
 ) {
+    *reconnection = Reconnection::default();
     // Set all the ReplicateToServer ticks to changed, so that we replicate existing entities to the server
     for mut replicate in query.iter_mut() {
         // TODO: ideally set is_added instead of simply changed
@@ -383,7 +491,7 @@ fn on_connect_host_server(
     // spawn an entity for the client
     let client_entity = commands.spawn(ControlledEntities::default()).id();
     // start a server connection for that client (which will also send a ConnectEvent on the server)
-    server_manager.add(netcode.id(), client_entity);
+    server_manager.add(netcode.id(), client_entity, Vec::new(), None);
     server_manager
         .connection_mut(netcode.id())
         .unwrap()
@@ -394,9 +502,59 @@ fn on_connect_host_server(
     commands.trigger(ConnectEvent::new(netcode.id()));
 }
 
+/// Automatic reconnection of the client, see [`ReconnectConfig`](crate::client::config::ReconnectConfig)
+#[derive(Resource, Default, Debug)]
+pub(crate) struct Reconnection {
+    /// Number of consecutive attempts since the connection was lost
+    attempts: u32,
+    /// Delay before the next attempt, if one is scheduled
+    timer: Option<Timer>,
+}
+
+/// Returns true if the client should try to reconnect after being disconnected for this reason
+fn should_reconnect(reason: &DisconnectReason) -> bool {
+    !matches!(
+        reason,
+        // the server might still have the previous session if it hasn't noticed that the connection was lost,
+        // so we retry if the connection is denied because we are already connected
+        DisconnectReason::Denied(reason) if *reason != DeniedReason::AlreadyConnected
+    ) && !matches!(
+        reason,
+            | DisconnectReason::Server(
+                ServerDisconnectReason::ServerKicked(1..) | ServerDisconnectReason::Reconnected
+            )
+    )
+}
+
+/// Start the reconnection attempt once its delay has elapsed
+fn reconnect(
+    time: Res<Time<Real>>,
+    config: Res<ClientConfig>,
+    mut reconnection: ResMut<Reconnection>,
+    mut next_state: ResMut<NextState<NetworkingState>>,
+    mut reconnect_event_writer: EventWriter<ReconnectAttemptEvent>,
+) {
+    let Some(timer) = reconnection.timer.as_mut() else {
+        return;
+    };
+    if !timer.tick(time.delta()).finished() {
+        return;
+    }
+    reconnection.timer = None;
+    reconnection.attempts += 1;
+    debug!(attempt = reconnection.attempts, "Trying to reconnect");
+    reconnect_event_writer.send(ReconnectAttemptEvent {
+        attempt: reconnection.attempts,
+        max_attempts: config.reconnect.map_or(0, |c| c.max_attempts),
+    });
+    next_state.set(NetworkingState::Connecting);
+}
+
 /// System that runs when we enter the Disconnected state
 /// Updates the DisconnectEvent events
 fn on_disconnect(
+    config: Res<ClientConfig>,
+    mut reconnection: ResMut<Reconnection>,
     mut connection_manager: ResMut<ConnectionManager>,
     mut disconnect_event_writer: EventWriter<DisconnectEvent>,
     mut netclient: ResMut<ClientConnection>,
@@ -420,6 +578,20 @@ fn on_disconnect(
     // no need to update the io state, because we will recreate a new `ClientConnection`
     // for the next connection attempt
     let reason = std::mem::take(&mut netclient.disconnect_reason);
+
+    // schedule a reconnection attempt if the connection was lost
+    // (the reason is None if the client disconnected itself)
+    match config.reconnect {
+        Some(reconnect_config)
+            if reason.as_ref().is_some_and(should_reconnect)
+                && reconnection.attempts < reconnect_config.max_attempts =>
+        {
+            let delay = reconnect_config.delay(reconnection.attempts);
+            info!(?delay, "Connection lost, scheduling a reconnection attempt");
+            reconnection.timer = Some(Timer::new(delay, TimerMode::Once));
+        }
+        _ => *reconnection = Reconnection::default(),
+    }
     disconnect_event_writer.send(DisconnectEvent { reason });
     // commands.trigger(DisconnectEvent { reason });
     // TODO: remove ClientConnection and ConnectionManager resources?
@@ -435,6 +607,8 @@ fn on_disconnect_host_server(
         server_disconnect_event_writer.send(crate::server::events::DisconnectEvent {
             client_id,
             entity: client_entity,
+            reason: crate::connection::server::DisconnectReason::ClientRequested,
+            addr: None,
         });
     }
 }
@@ -521,10 +695,13 @@ pub trait ClientCommands {
 
 impl ClientCommands for Commands<'_, '_> {
     fn connect_client(&mut self) {
+        self.insert_resource(Reconnection::default());
         self.insert_resource(NextState::Pending(NetworkingState::Connecting));
     }
 
     fn disconnect_client(&mut self) {
+        // cancel any pending reconnection attempt
+        self.insert_resource(Reconnection::default());
         self.insert_resource(NextState::Pending(NetworkingState::Disconnected));
     }
 }
@@ -560,11 +737,18 @@ mod tests {
 
     use bevy::prelude::*;
 
+    use crate::client::networking::NetworkingState;
+    use crate::connection::client::DisconnectReason;
+    use crate::connection::client::NetConfig;
+    use crate::prelude::client::{ReconnectAttemptEvent, ReconnectConfig};
+    use crate::tests::protocol::ComponentSyncModeFull;
     use crate::{
         client::config::ClientConfig,
         prelude::{client::ClientCommands, server::*, SharedConfig, TickConfig},
         tests::host_server_stepper::HostServerStepper,
+        tests::stepper::BevyStepper,
     };
+    use bevy::ecs::system::RunSystemOnce;
 
     #[derive(Resource, Default)]
     struct CheckCounter(usize);
@@ -623,4 +807,180 @@ mod tests {
         stepper.frame_step();
         assert_eq!(stepper.server_app.world().resource::<CheckCounter>().0, 2); // 2 because local client as well as external client disconnect
     }
+
+    /// The netcode timeouts are driven by the bevy `Time`, so we can check that the client
+    /// times out without having to sleep
+    #[test]
+    fn test_client_timeout() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..Default::default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, ClientConfig::default(), frame_duration);
+        if let NetConfig::Netcode { config, .. } = &mut stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ClientConfig>()
+            .net
+        {
+            config.client_timeout_secs = 3;
+        }
+        stepper.init();
+        stepper
+            .client_app
+            .init_resource::<CheckCounter>()
+            .add_systems(
+                Update,
+                |mut reader: EventReader<crate::client::events::DisconnectEvent>,
+                 mut res: ResMut<CheckCounter>| {
+                    for event in reader.read() {
+                        assert!(matches!(event.reason, Some(DisconnectReason::Timeout)));
+                        res.0 += 1;
+                    }
+                },
+            );
+
+        // only update the client, so that it doesn't receive any packets from the server
+        // for longer than the timeout (3 seconds)
+        for _ in 0..40 {
+            stepper.advance_time(Duration::from_millis(100));
+            stepper.client_app.update();
+        }
+        assert_eq!(stepper.client_app.world().resource::<CheckCounter>().0, 1);
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<State<NetworkingState>>()
+                .get(),
+            &NetworkingState::Disconnected
+        );
+    }
+
+    fn reconnect_stepper(client_timeout_secs: i32) -> BevyStepper {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..Default::default()
+        };
+        let client_config = ClientConfig {
+            reconnect: Some(
+                ReconnectConfig::default()
+                    .with_max_attempts(2)
+                    .with_initial_delay(Duration::from_millis(500)),
+            ),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, frame_duration);
+        if let NetConfig::Netcode { config, .. } = &mut stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ClientConfig>()
+            .net
+        {
+            config.client_timeout_secs = client_timeout_secs;
+        }
+        stepper.init();
+        stepper
+            .client_app
+            .init_resource::<CheckCounter>()
+            .add_systems(
+                Update,
+                |mut reader: EventReader<ReconnectAttemptEvent>, mut res: ResMut<CheckCounter>| {
+                    for event in reader.read() {
+                        res.0 += 1;
+                        assert_eq!(event.attempt as usize, res.0);
+                    }
+                },
+            );
+        stepper
+    }
+
+    #[test]
+    fn test_reconnect() {
+        let mut stepper = reconnect_stepper(3);
+        stepper
+            .server_app
+            .world_mut()
+            .spawn((Replicate::default(), ComponentSyncModeFull(1.0)));
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let replicated = |stepper: &mut BevyStepper| {
+            stepper
+                .client_app
+                .world_mut()
+                .query_filtered::<(), With<ComponentSyncModeFull>>()
+                .iter(stepper.client_app.world())
+                .count()
+        };
+        assert_eq!(replicated(&mut stepper), 1);
+
+        // restart the server: the client loses the connection
+        stepper
+            .server_app
+            .world_mut()
+            .run_system_once(|mut commands: Commands| commands.stop_server());
+        stepper.frame_step();
+        stepper.frame_step();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<State<NetworkingState>>()
+                .get(),
+            &NetworkingState::Disconnected
+        );
+        // the replicated entities are despawned
+        assert_eq!(replicated(&mut stepper), 0);
+        stepper
+            .server_app
+            .world_mut()
+            .run_system_once(|mut commands: Commands| commands.start_server());
+
+        // the client reconnects after the delay, and the entities are replicated again
+        for _ in 0..100 {
+            stepper.frame_step();
+        }
+        assert_eq!(stepper.client_app.world().resource::<CheckCounter>().0, 1);
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<State<NetworkingState>>()
+                .get(),
+            &NetworkingState::Connected
+        );
+        assert_eq!(replicated(&mut stepper), 1);
+    }
+
+    #[test]
+    fn test_reconnect_max_attempts() {
+        let mut stepper = reconnect_stepper(1);
+
+        // only update the client, so that the connection and the reconnection attempts time out
+        for _ in 0..100 {
+            stepper.advance_time(Duration::from_millis(100));
+            stepper.client_app.update();
+        }
+        // the client gives up after 2 attempts
+        assert_eq!(stepper.client_app.world().resource::<CheckCounter>().0, 2);
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<State<NetworkingState>>()
+                .get(),
+            &NetworkingState::Disconnected
+        );
+    }
+
+    #[test]
+    fn test_reconnect_delay() {
+        let config = ReconnectConfig::default();
+        assert_eq!(config.delay(0), Duration::from_secs(1));
+        assert_eq!(config.delay(2), Duration::from_secs(4));
+        assert_eq!(config.delay(10), Duration::from_secs(30));
+    }
 }
@@ -28,6 +28,10 @@ impl PredictionDiagnosticsPlugin {
     pub const ROLLBACKS: DiagnosticPath =
         DiagnosticPath::const_new("replication.prediction.rollbacks");
 
+    /// Number of rollbacks per second
+    pub const ROLLBACKS_PER_SECOND: DiagnosticPath =
+        DiagnosticPath::const_new("replication.prediction.rollbacks_per_second");
+
     /// Total number of ticks resimulated as part of rollbacks
     pub const ROLLBACK_TICKS: DiagnosticPath =
         DiagnosticPath::const_new("replication.prediction.rollback_ticks");
@@ -36,8 +40,32 @@ impl PredictionDiagnosticsPlugin {
     pub const ROLLBACK_DEPTH: DiagnosticPath =
         DiagnosticPath::const_new("replication.prediction.rollback_depth");
 
-    fn flush_measurements(metrics: ResMut<PredictionMetrics>, mut diagnostics: Diagnostics) {
+    /// Largest number of states stored in a prediction history buffer
+    pub const HISTORY_LENGTH: DiagnosticPath =
+        DiagnosticPath::const_new("replication.prediction.history_length");
+
+    /// Number of server updates that were older than the prediction history
+    pub const HISTORY_SNAPS: DiagnosticPath =
+        DiagnosticPath::const_new("replication.prediction.history_snaps");
+
+    fn flush_measurements(
+        metrics: ResMut<PredictionMetrics>,
+        mut diagnostics: Diagnostics,
+        time: Res<Time<Real>>,
+        // time and number of rollbacks at the previous flush
+        mut last_flush: Local<Option<(Duration, u32)>>,
+    ) {
         diagnostics.add_measurement(&Self::ROLLBACKS, || metrics.rollbacks as f64);
+        let now = time.elapsed();
+        if let Some((last_time, last_rollbacks)) = *last_flush {
+            let elapsed = (now - last_time).as_secs_f64();
+            if elapsed > 0.0 {
+                diagnostics.add_measurement(&Self::ROLLBACKS_PER_SECOND, || {
+                    metrics.rollbacks.saturating_sub(last_rollbacks) as f64 / elapsed
+                });
+            }
+        }
+        *last_flush = Some((now, metrics.rollbacks));
         diagnostics.add_measurement(&Self::ROLLBACK_TICKS, || metrics.rollback_ticks as f64);
         diagnostics.add_measurement(&Self::ROLLBACK_DEPTH, || {
             if metrics.rollbacks == 0 {
@@ -46,6 +74,8 @@ impl PredictionDiagnosticsPlugin {
                 metrics.rollback_ticks as f64 / metrics.rollbacks as f64
             }
         });
+        diagnostics.add_measurement(&Self::HISTORY_LENGTH, || metrics.max_history_length as f64);
+        diagnostics.add_measurement(&Self::HISTORY_SNAPS, || metrics.history_snaps as f64);
     }
 }
 
@@ -57,6 +87,13 @@ pub struct PredictionMetrics {
     pub rollbacks: u32,
     /// Per rollback, incremented by the number of ticks the rollback window contains
     pub rollback_ticks: u32,
+    /// Largest number of states stored in a single prediction history buffer.
+    ///
+    /// This is bounded by [`PredictionConfig::history_ticks`](crate::client::prediction::plugin::PredictionConfig::history_ticks) + 1
+    pub max_history_length: u32,
+    /// Incremented every time a server update was older than the prediction history, so that
+    /// the predicted entity had to be snapped to the server state instead of rolled back
+    pub history_snaps: u32,
 }
 
 impl Plugin for PredictionDiagnosticsPlugin {
@@ -73,6 +110,11 @@ impl Plugin for PredictionDiagnosticsPlugin {
                 .with_suffix("rollbacks")
                 .with_max_history_length(self.history_length),
         );
+        app.register_diagnostic(
+            Diagnostic::new(Self::ROLLBACKS_PER_SECOND)
+                .with_suffix("rollbacks/s")
+                .with_max_history_length(self.history_length),
+        );
         app.register_diagnostic(
             Diagnostic::new(Self::ROLLBACK_TICKS)
                 .with_suffix("ticks resimulated during rollback")
@@ -83,5 +125,15 @@ impl Plugin for PredictionDiagnosticsPlugin {
                 .with_suffix("Average rollback depth")
                 .with_max_history_length(self.history_length),
         );
+        app.register_diagnostic(
+            Diagnostic::new(Self::HISTORY_LENGTH)
+                .with_suffix("states in the largest prediction history")
+                .with_max_history_length(self.history_length),
+        );
+        app.register_diagnostic(
+            Diagnostic::new(Self::HISTORY_SNAPS)
+                .with_suffix("updates older than the prediction history")
+                .with_max_history_length(self.history_length),
+        );
     }
 }
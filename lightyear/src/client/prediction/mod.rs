@@ -12,6 +12,7 @@ pub mod prespawn;
 pub(crate) mod resource;
 pub mod rollback;
 pub mod spawn;
+pub mod transition;
 
 /// Marks an entity that is being predicted by the client
 #[derive(Component, Debug, Reflect)]
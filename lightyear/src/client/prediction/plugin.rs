@@ -1,12 +1,16 @@
+use bevy::app::FixedMainScheduleOrder;
+use bevy::ecs::schedule::{LogLevel, ScheduleBuildSettings, ScheduleLabel};
 use bevy::prelude::{
-    not, App, Component, Condition, FixedPostUpdate, IntoSystemConfigs, IntoSystemSetConfigs,
-    Plugin, PostUpdate, PreUpdate, Res, SystemSet,
+    not, App, Component, Condition, FixedPostUpdate, FixedUpdate, IntoSystemConfigs,
+    IntoSystemSetConfigs, Plugin, PostUpdate, Res, Schedule, SystemSet,
 };
 use bevy::reflect::Reflect;
 use bevy::transform::TransformSystem;
+use bevy::utils::HashSet;
 use std::time::Duration;
 
 use crate::client::components::{ComponentSyncMode, Confirmed, SyncComponent};
+use crate::client::config::ClientConfig;
 use crate::client::prediction::correction::{
     get_visually_corrected_state, restore_corrected_state,
 };
@@ -24,8 +28,10 @@ use crate::client::prediction::prespawn::{
 };
 use crate::client::prediction::resource::PredictionManager;
 use crate::client::prediction::Predicted;
+use crate::client::run_conditions::is_warmup_done;
 use crate::prelude::{client::is_synced, is_host_server, PreSpawnedPlayerObject};
-use crate::shared::sets::{ClientMarker, InternalMainSet};
+use crate::protocol::component::{ComponentKind, ComponentRegistry};
+use crate::shared::sets::{ClientMarker, InternalMainSet, MainSet, NetworkingSchedules};
 
 use super::pre_prediction::PrePredictionPlugin;
 use super::predicted_history::{add_component_history, apply_confirmed_update};
@@ -34,9 +40,12 @@ use super::rollback::{
     prepare_rollback_prespawn, run_rollback, Rollback, RollbackState,
 };
 use super::spawn::spawn_predicted_entity;
+use super::transition::{
+    apply_sync_mode_transition, restore_sync_mode_transition, start_sync_mode_transition,
+};
 
 /// Configuration to specify how the prediction plugin should behave
-#[derive(Debug, Clone, Copy, Reflect)]
+#[derive(Debug, Clone, Reflect)]
 pub struct PredictionConfig {
     /// If true, we always rollback whenever we receive a server update, instead of checking
     /// ff the confirmed state matches the predicted state history
@@ -75,6 +84,54 @@ pub struct PredictionConfig {
     /// (i.e. if the client is 10 ticks head and correction_ticks is 1.0, then the correction will be done over 10 ticks)
     // Number of ticks it will take to visually update the Predicted state to the new Corrected state
     pub correction_ticks_factor: f32,
+    /// Number of ticks over which the visual state is eased from the interpolated entity to the predicted
+    /// entity (or the reverse) when an entity switches between interpolation and prediction,
+    /// for example when the client gains or loses authority over it.
+    ///
+    /// Only components that have an interpolation function are eased.
+    /// Set to 0 to switch instantly.
+    pub sync_mode_transition_ticks: u16,
+    /// Number of ticks of component history that are kept for each predicted component, to be able to
+    /// compare the predicted state with the server state and roll back.
+    ///
+    /// This needs to cover the worst-case RTT (plus jitter) of the connection: if a server update arrives for a
+    /// tick that is older than the history, we cannot roll back correctly and the predicted entity is snapped
+    /// to the server state instead (see [`PredictionMetrics::history_snaps`](crate::client::prediction::diagnostics::PredictionMetrics::history_snaps)).
+    /// This also caps the number of ticks that are re-simulated during a rollback.
+    ///
+    /// The history only stores the ticks where the component changed, so the worst-case memory cost is
+    /// `predicted entities * predicted components * history_ticks * size_of::<C>()`.
+    pub history_ticks: u16,
+    /// If set, only these components participate in the predicted simulation.
+    ///
+    /// The other components registered with [`ComponentSyncMode::Full`] are synced with [`ComponentSyncMode::Simple`]
+    /// instead: they are copied from the confirmed entity whenever the server updates them, without keeping a
+    /// prediction history or checking for rollbacks. This is useful for cosmetic components (for example an
+    /// animation state) where mispredictions don't matter.
+    ///
+    /// This is applied when the [`PredictionPlugin`] is finished, so it can be set at any point before the app runs.
+    #[reflect(ignore)]
+    pub predicted_components: Option<HashSet<ComponentKind>>,
+    /// How long a [`PreSpawnedPlayerObject`] entity that doesn't match any server entity is kept on the client
+    /// before being despawned, for example when the server rejected the action that spawned it.
+    ///
+    /// A [`PredictionRejected`](crate::client::prediction::prespawn::PredictionRejected) event is triggered
+    /// for the entity before it is despawned (or, with [`PrespawnTimeout::Never`], before it becomes a
+    /// client-only entity).
+    pub prespawn_timeout: PrespawnTimeout,
+}
+
+/// How long an unmatched [`PreSpawnedPlayerObject`] entity lives on the client
+#[derive(Clone, Copy, Debug, Default, PartialEq, Reflect)]
+pub enum PrespawnTimeout {
+    /// Despawn the entity once the server state of its spawn tick should have been received, i.e. after
+    /// twice the difference between the current tick and the interpolation tick
+    #[default]
+    InterpolationDelay,
+    /// Despawn the entity after this number of ticks
+    Ticks(u16),
+    /// Never despawn the entity: it becomes a normal client-only entity
+    Never,
 }
 
 impl Default for PredictionConfig {
@@ -93,6 +150,10 @@ impl Default for PredictionConfig {
             maximum_input_delay_before_prediction: 0,
             maximum_predicted_ticks: 100,
             correction_ticks_factor: 1.0,
+            sync_mode_transition_ticks: 0,
+            history_ticks: 100,
+            predicted_components: None,
+            prespawn_timeout: PrespawnTimeout::default(),
         }
     }
 }
@@ -115,6 +176,49 @@ impl PredictionConfig {
         self
     }
 
+    /// Update the number of ticks used to transition between the interpolated and predicted entities
+    pub fn with_sync_mode_transition_ticks(mut self, ticks: u16) -> Self {
+        self.sync_mode_transition_ticks = ticks;
+        self
+    }
+
+    /// Update the number of ticks of history kept for each predicted component
+    pub fn with_history_ticks(mut self, ticks: u16) -> Self {
+        self.history_ticks = ticks;
+        self
+    }
+
+    /// Update how long an unmatched [`PreSpawnedPlayerObject`] entity lives on the client
+    pub fn with_prespawn_timeout(mut self, timeout: PrespawnTimeout) -> Self {
+        self.prespawn_timeout = timeout;
+        self
+    }
+
+    /// Add a component to the list of components that participate in the predicted simulation.
+    ///
+    /// See [`PredictionConfig::predicted_components`]
+    pub fn with_predicted_component<C: Component>(mut self) -> Self {
+        self.predicted_components
+            .get_or_insert_with(HashSet::default)
+            .insert(ComponentKind::of::<C>());
+        self
+    }
+
+    /// Returns the [`ComponentSyncMode`] that is actually used on the client for the component `kind`,
+    /// given the mode it was registered with
+    pub(crate) fn prediction_mode(
+        &self,
+        kind: ComponentKind,
+        mode: ComponentSyncMode,
+    ) -> ComponentSyncMode {
+        match &self.predicted_components {
+            Some(components) if mode == ComponentSyncMode::Full && !components.contains(&kind) => {
+                ComponentSyncMode::Simple
+            }
+            _ => mode,
+        }
+    }
+
     /// Compute the amount of input delay that should be applied, considering the current RTT
     pub fn input_delay_ticks(&self, rtt: Duration, tick_interval: Duration) -> u16 {
         let rtt_ticks = rtt.as_nanos() as f32 / tick_interval.as_nanos() as f32;
@@ -178,6 +282,35 @@ pub enum PredictionSet {
     All,
 }
 
+/// Schedule that contains the predicted simulation.
+///
+/// It runs in the `FixedMain` schedule right after `FixedUpdate`, and it is replayed verbatim for each tick
+/// that is re-simulated during a rollback.
+///
+/// The systems of the predicted simulation must run in the same relative order during the original tick and
+/// during the rollback, otherwise the replay diverges from the original simulation. The contract is that
+/// every pair of systems in this schedule that access the same data (with at least one of them mutating it)
+/// must be explicitly ordered (with `.chain()`, `.before()`, `.after()` or system sets).
+/// Systems that don't conflict can still run in parallel, since their order doesn't affect the result.
+///
+/// In debug builds, the contract is enforced: the schedule fails to build (and the app panics) if it contains
+/// ambiguously ordered systems.
+#[derive(ScheduleLabel, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PredictedFixedUpdate;
+
+impl PredictedFixedUpdate {
+    fn build_settings() -> ScheduleBuildSettings {
+        ScheduleBuildSettings {
+            ambiguity_detection: if cfg!(debug_assertions) {
+                LogLevel::Error
+            } else {
+                LogLevel::Ignore
+            },
+            ..Default::default()
+        }
+    }
+}
+
 /// Returns true if we are doing rollback
 pub fn is_in_rollback(rollback: Option<Res<Rollback>>) -> bool {
     rollback.is_some_and(|rollback| rollback.is_rollback())
@@ -185,9 +318,10 @@ pub fn is_in_rollback(rollback: Option<Res<Rollback>>) -> bool {
 
 /// Enable rollbacking a component even if the component is not networked
 pub fn add_non_networked_rollback_systems<C: Component + PartialEq + Clone>(app: &mut App) {
+    let schedules = NetworkingSchedules::of(app);
     app.observe(apply_component_removal_predicted::<C>);
     app.add_systems(
-        PreUpdate,
+        schedules.receive,
         (
             add_non_networked_component_history::<C>.in_set(PredictionSet::SpawnHistory),
             prepare_rollback_non_networked::<C>.in_set(PredictionSet::PrepareRollback),
@@ -200,8 +334,9 @@ pub fn add_non_networked_rollback_systems<C: Component + PartialEq + Clone>(app:
 }
 
 pub fn add_prediction_systems<C: SyncComponent>(app: &mut App, prediction_mode: ComponentSyncMode) {
+    let schedules = NetworkingSchedules::of(app);
     app.add_systems(
-        PreUpdate,
+        schedules.receive,
         (
             // handle components being added
             add_component_history::<C>.in_set(PredictionSet::SpawnHistory),
@@ -211,13 +346,17 @@ pub fn add_prediction_systems<C: SyncComponent>(app: &mut App, prediction_mode:
         ComponentSyncMode::Full => {
             app.observe(apply_component_removal_predicted::<C>);
             app.add_systems(
-                PreUpdate,
+                schedules.receive,
                 // restore to the corrected state (as the visual state might be interpolating
                 // between the predicted and corrected state)
-                restore_corrected_state::<C>.in_set(PredictionSet::RestoreVisualCorrection),
+                (
+                    restore_corrected_state::<C>,
+                    restore_sync_mode_transition::<C>,
+                )
+                    .in_set(PredictionSet::RestoreVisualCorrection),
             );
             app.add_systems(
-                PreUpdate,
+                schedules.receive,
                 (
                     // for SyncMode::Full, we need to check if we need to rollback.
                     // TODO: for mode=simple/once, we still need to re-add the component if the entity ends up not being despawned!
@@ -236,13 +375,22 @@ pub fn add_prediction_systems<C: SyncComponent>(app: &mut App, prediction_mode:
             );
             app.add_systems(
                 PostUpdate,
-                get_visually_corrected_state::<C>.in_set(PredictionSet::VisualCorrection),
+                (
+                    get_visually_corrected_state::<C>,
+                    // ease the transition between the interpolated and predicted entities
+                    (
+                        start_sync_mode_transition::<C>,
+                        apply_sync_mode_transition::<C>,
+                    )
+                        .chain(),
+                )
+                    .in_set(PredictionSet::VisualCorrection),
             );
         }
         ComponentSyncMode::Simple => {
             app.observe(apply_component_removal_confirmed::<C>);
             app.add_systems(
-                PreUpdate,
+                schedules.receive,
                 (
                     // for SyncMode::Simple, just copy the confirmed components
                     apply_confirmed_update::<C>.in_set(PredictionSet::CheckRollback),
@@ -255,7 +403,7 @@ pub fn add_prediction_systems<C: SyncComponent>(app: &mut App, prediction_mode:
         }
         ComponentSyncMode::Once => {
             app.add_systems(
-                PreUpdate,
+                schedules.receive,
                 // if we are rolling back (maybe because the predicted entity despawn is getting cancelled, restore components)
                 restore_components_if_despawn_rolled_back::<C>
                     // .before(run_rollback::)
@@ -272,10 +420,13 @@ pub fn add_prediction_systems<C: SyncComponent>(app: &mut App, prediction_mode:
 
 impl Plugin for PredictionPlugin {
     fn build(&self, app: &mut App) {
+        let schedules = NetworkingSchedules::of(app);
         // we only run prediction:
         // - if we're not in host-server mode
         // - after the client is synced
-        let should_prediction_run = not(is_host_server).and_then(is_synced);
+        let should_prediction_run = not(is_host_server)
+            .and_then(is_synced)
+            .and_then(is_warmup_done);
 
         // REFLECTION
         app.register_type::<Predicted>()
@@ -286,6 +437,14 @@ impl Plugin for PredictionPlugin {
             .register_type::<PredictionDespawnMarker>()
             .register_type::<PredictionConfig>();
 
+        // SCHEDULES
+        let mut predicted_schedule = Schedule::new(PredictedFixedUpdate);
+        predicted_schedule.set_build_settings(PredictedFixedUpdate::build_settings());
+        app.add_schedule(predicted_schedule);
+        app.world_mut()
+            .resource_mut::<FixedMainScheduleOrder>()
+            .insert_after(FixedUpdate, PredictedFixedUpdate);
+
         // RESOURCES
         app.init_resource::<PredictionManager>();
         app.insert_resource(Rollback::new(RollbackState::Default));
@@ -296,7 +455,7 @@ impl Plugin for PredictionPlugin {
         // 3. (in prediction_systems) Check if we should do rollback, clear histories and snap prediction's history to server-state
         // 4. Potentially do rollback
         app.configure_sets(
-            PreUpdate,
+            schedules.receive,
             (
                 InternalMainSet::<ClientMarker>::EmitEvents,
                 (
@@ -313,11 +472,14 @@ impl Plugin for PredictionPlugin {
                 .chain(),
         )
         .configure_sets(
-            PreUpdate,
-            PredictionSet::All.run_if(should_prediction_run.clone()),
+            schedules.receive,
+            (
+                PredictionSet::All.run_if(should_prediction_run.clone()),
+                MainSet::Simulation.after(PredictionSet::All),
+            ),
         );
         app.add_systems(
-            PreUpdate,
+            schedules.receive,
             (
                 // - we first check if the entity has a matching PreSpawnedPlayerObject. If match, remove PrePredicted/ShouldBePredicted
                 // - then we check if it is a PrePredicted entity. If match, remove ShouldBePredicted
@@ -373,6 +535,18 @@ impl Plugin for PredictionPlugin {
         // PLUGINS
         app.add_plugins((PrePredictionPlugin, PreSpawnedPlayerObjectPlugin));
     }
+
+    /// Add the prediction systems of the components, now that the protocol and the [`ClientConfig`] are complete
+    fn finish(&self, app: &mut App) {
+        let config = app.world().resource::<ClientConfig>().prediction.clone();
+        let add_systems = app
+            .world_mut()
+            .resource_mut::<ComponentRegistry>()
+            .apply_prediction_config(&config);
+        for (add_fn, prediction_mode) in add_systems {
+            add_fn(app, prediction_mode);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -387,6 +561,10 @@ mod tests {
             maximum_input_delay_before_prediction: 3,
             maximum_predicted_ticks: 7,
             correction_ticks_factor: 0.0,
+            sync_mode_transition_ticks: 0,
+            history_ticks: 100,
+            predicted_components: None,
+            prespawn_timeout: PrespawnTimeout::default(),
         };
         // 1. Test the minimum input delay
         assert_eq!(
@@ -14,7 +14,7 @@ use crate::prelude::{
     ReplicationTarget, ShouldBePredicted, TickManager,
 };
 use crate::shared::replication::components::PrePredicted;
-use crate::shared::sets::{ClientMarker, InternalReplicationSet};
+use crate::shared::sets::{ClientMarker, InternalReplicationSet, NetworkingSchedules};
 
 #[derive(Default)]
 pub(crate) struct PrePredictionPlugin;
@@ -28,8 +28,9 @@ pub enum PrePredictionSet {
 
 impl Plugin for PrePredictionPlugin {
     fn build(&self, app: &mut App) {
+        let schedules = NetworkingSchedules::of(app);
         app.configure_sets(
-            PostUpdate,
+            schedules.send,
             (
                 InternalReplicationSet::<ClientMarker>::Buffer,
                 PrePredictionSet::Clean
@@ -39,7 +40,7 @@ impl Plugin for PrePredictionPlugin {
                 .run_if(is_synced),
         );
         app.add_systems(
-            PostUpdate,
+            schedules.send,
             (
                 // clean-up the ShouldBePredicted components after we've sent them
                 Self::clean_pre_predicted_entity.in_set(PrePredictionSet::Clean),
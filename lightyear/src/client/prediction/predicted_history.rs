@@ -3,12 +3,14 @@
 use std::ops::Deref;
 
 use bevy::prelude::{
-    Added, Commands, Component, DetectChanges, Entity, OnRemove, Or, Query, Ref, Res, Trigger,
-    With, Without,
+    Added, Commands, Component, DetectChanges, Entity, OnRemove, Or, Query, Ref, Res, ResMut,
+    Trigger, With, Without,
 };
 use tracing::{debug, trace};
 
 use crate::client::components::{ComponentSyncMode, Confirmed, SyncComponent};
+use crate::client::config::ClientConfig;
+use crate::client::prediction::diagnostics::PredictionMetrics;
 use crate::client::prediction::resource::PredictionManager;
 use crate::client::prediction::rollback::Rollback;
 use crate::client::prediction::Predicted;
@@ -28,7 +30,6 @@ pub(crate) enum ComponentState<C> {
 /// To know if we need to do rollback, we need to compare the predicted entity's history with the server's state updates
 #[derive(Component, Debug)]
 pub(crate) struct PredictionHistory<C> {
-    // TODO: add a max size for the buffer
     // We want to avoid using a SequenceBuffer for optimization (we don't want to store a copy of the component for each history tick)
     // We can afford to use a ReadyBuffer because we will get server updates with monotonically increasing ticks
     // therefore we can get rid of the old ticks before the server update
@@ -71,6 +72,12 @@ impl<C: PartialEq + Clone> PredictionHistory<C> {
         self.buffer.push(tick, ComponentState::Removed);
     }
 
+    /// Drop the values that are older than `tick`, keeping the most recent of them
+    /// so that we still know the value of the component at `tick`
+    pub(crate) fn trim(&mut self, tick: Tick) {
+        self.pop_until_tick(tick);
+    }
+
     // TODO: check if this logic is necessary/correct?
     /// Clear the history of values strictly older than the specified tick,
     /// and return the most recent value that is older or equal to the specified tick.
@@ -249,17 +256,28 @@ fn add_history<C: SyncComponent>(
 /// This system only handles changes, removals are handled in `apply_component_removal`
 pub(crate) fn update_prediction_history<T: Component + PartialEq + Clone>(
     mut query: Query<(Ref<T>, &mut PredictionHistory<T>)>,
+    config: Res<ClientConfig>,
     tick_manager: Res<TickManager>,
     rollback: Res<Rollback>,
+    metrics: Option<ResMut<PredictionMetrics>>,
 ) {
     // tick for which we will record the history (either the current client tick or the current rollback tick)
     let tick = tick_manager.tick_or_rollback_tick(rollback.as_ref());
+    let oldest_tick = tick - config.prediction.history_ticks;
 
     // update history if the predicted component changed
+    let mut max_history_length = 0;
     for (component, mut history) in query.iter_mut() {
         // change detection works even when running the schedule for rollback
         if component.is_changed() {
             history.add_update(tick, component.deref().clone());
+            history.trim(oldest_tick);
+            max_history_length = max_history_length.max(history.buffer.len() as u32);
+        }
+    }
+    if let Some(mut metrics) = metrics {
+        if max_history_length > metrics.max_history_length {
+            metrics.max_history_length = max_history_length;
         }
     }
 }
@@ -390,6 +408,26 @@ mod tests {
         assert_eq!(component_history.buffer.len(), 0);
     }
 
+    /// Test the component history when the ticks wrap around
+    #[test]
+    fn test_component_history_tick_wrap() {
+        let mut component_history = PredictionHistory::<ComponentSyncModeFull>::default();
+        component_history.add_update(Tick(u16::MAX - 1), ComponentSyncModeFull(1.0));
+        component_history.add_update(Tick(u16::MAX), ComponentSyncModeFull(2.0));
+        component_history.add_update(Tick(1), ComponentSyncModeFull(3.0));
+
+        // Tick(0) comes after Tick(u16::MAX)
+        assert_eq!(
+            component_history.pop_until_tick(Tick(0)),
+            Some(ComponentState::Updated(ComponentSyncModeFull(2.0)))
+        );
+        assert!(component_history.buffer.has_item(&Tick(1)));
+        assert_eq!(
+            component_history.pop_until_tick(Tick(1)),
+            Some(ComponentState::Updated(ComponentSyncModeFull(3.0)))
+        );
+    }
+
     /// Test adding the component history to the predicted entity
     /// 1. Add the history for ComponentSyncMode::Full that was added to the confirmed entity
     /// 2. Add the history for ComponentSyncMode::Full that was added to the predicted entity
@@ -712,4 +750,87 @@ mod tests {
             "Expected component value to be removed from prediction history"
         );
     }
+
+    /// Only the components in the `predicted_components` allow-list get a prediction history;
+    /// the other components are copied from the confirmed entity
+    #[test]
+    fn test_predicted_components_allow_list() {
+        let frame_duration = std::time::Duration::from_millis(10);
+        let shared_config = crate::prelude::SharedConfig {
+            tick: crate::prelude::TickConfig::new(frame_duration),
+            ..Default::default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, Default::default(), frame_duration);
+        // the config is applied when the plugin is finished, after the protocol was registered
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ClientConfig>()
+            .prediction = crate::prelude::client::PredictionConfig::default()
+            .with_predicted_component::<ComponentSyncModeFull>();
+        stepper.init();
+
+        let confirmed = stepper
+            .client_app
+            .world_mut()
+            .spawn(Confirmed::default())
+            .id();
+        let predicted = stepper
+            .client_app
+            .world_mut()
+            .spawn(Predicted {
+                confirmed_entity: Some(confirmed),
+            })
+            .id();
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(confirmed)
+            .get_mut::<Confirmed>()
+            .unwrap()
+            .predicted = Some(predicted);
+
+        // the position is predicted, the animation state is only replicated
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(confirmed)
+            .insert((ComponentSyncModeFull(1.0), ComponentSyncModeFull2(1.0)));
+        stepper.frame_step();
+        let world = stepper.client_app.world();
+        assert!(world
+            .get::<PredictionHistory<ComponentSyncModeFull>>(predicted)
+            .is_some());
+        assert!(world
+            .get::<PredictionHistory<ComponentSyncModeFull2>>(predicted)
+            .is_none());
+        assert_eq!(
+            world.get::<ComponentSyncModeFull2>(predicted),
+            Some(&ComponentSyncModeFull2(1.0))
+        );
+
+        // server updates of the non-predicted component are applied directly to the predicted entity
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<ComponentSyncModeFull2>(confirmed)
+            .unwrap()
+            .0 = 2.0;
+        stepper.frame_step();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull2>(predicted),
+            Some(&ComponentSyncModeFull2(2.0))
+        );
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<ComponentRegistry>()
+                .prediction_mode::<ComponentSyncModeFull2>(),
+            ComponentSyncMode::Simple
+        );
+    }
 }
@@ -5,16 +5,17 @@ use serde::{Deserialize, Serialize};
 use tracing::{debug, trace};
 
 use crate::client::components::Confirmed;
+use crate::client::config::ClientConfig;
 use crate::client::connection::ConnectionManager;
 use crate::client::events::ComponentInsertEvent;
 use crate::client::prediction::resource::PredictionManager;
 use crate::client::prediction::rollback::Rollback;
 use crate::client::prediction::Predicted;
-use crate::prelude::client::PredictionSet;
-use crate::prelude::{ComponentRegistry, Replicated, ShouldBePredicted, TickManager};
+use crate::prelude::client::{PredictionSet, PrespawnTimeout};
+use crate::prelude::{ComponentRegistry, Replicated, ShouldBePredicted, Tick, TickManager};
 
 use crate::shared::replication::prespawn::compute_default_hash;
-use crate::shared::sets::{ClientMarker, InternalReplicationSet};
+use crate::shared::sets::{ClientMarker, InternalReplicationSet, NetworkingSchedules};
 
 #[derive(Default)]
 pub(crate) struct PreSpawnedPlayerObjectPlugin;
@@ -34,8 +35,9 @@ pub enum PreSpawnedPlayerObjectSet {
 
 impl Plugin for PreSpawnedPlayerObjectPlugin {
     fn build(&self, app: &mut App) {
+        let schedules = NetworkingSchedules::of(app);
         app.configure_sets(
-            PreUpdate,
+            schedules.receive,
             PreSpawnedPlayerObjectSet::Spawn.in_set(PredictionSet::SpawnPrediction),
         );
         app.configure_sets(
@@ -55,7 +57,7 @@ impl Plugin for PreSpawnedPlayerObjectPlugin {
         );
 
         app.add_systems(
-            PreUpdate,
+            schedules.receive,
             // we first try to see if the entity was a PreSpawnedPlayerObject
             // if we couldn't match it then the component gets removed and then should we try the normal spawn-prediction flow
             // TODO: or should we just consider that there was an error, and not go through the normal prediction flow?
@@ -266,11 +268,13 @@ impl PreSpawnedPlayerObjectPlugin {
     /// Cleanup the client prespawned entities for which we couldn't find a mapped server entity
     pub(crate) fn pre_spawned_player_object_cleanup(
         mut commands: Commands,
+        config: Res<ClientConfig>,
         tick_manager: Res<TickManager>,
         connection: Res<ConnectionManager>,
         mut manager: ResMut<PredictionManager>,
     ) {
         let tick = tick_manager.tick();
+        let timeout = config.prediction.prespawn_timeout;
         // TODO: why is interpolation tick not good enough and we need to use an earlier tick?
         // TODO: for some reason at interpolation_tick we often haven't received the update from the server yet!
         //  use a tick that it's even more in the past
@@ -287,30 +291,66 @@ impl PreSpawnedPlayerObjectPlugin {
         //     tick,
         //     interpolation_tick
         // );
-        let tick_diff = (tick - interpolation_tick).saturating_mul(2) as u16;
+        let tick_diff = match timeout {
+            PrespawnTimeout::Ticks(ticks) => ticks,
+            // with `Never`, we still stop trying to match the entities after the interpolation delay
+            PrespawnTimeout::InterpolationDelay | PrespawnTimeout::Never => {
+                (tick - interpolation_tick).saturating_mul(2) as u16
+            }
+        };
         let past_tick = tick - tick_diff;
         // remove all the prespawned entities that have not been matched with a server entity
-        for (_, hash) in manager.prespawn_tick_to_hash.drain_until(&past_tick) {
+        for (spawn_tick, hash) in manager.prespawn_tick_to_hash.drain_until(&past_tick) {
             manager
                 .prespawn_hash_to_entities
                 .remove(&hash)
                 .iter()
                 .flatten()
                 .for_each(|entity| {
-                    if let Some(entity_commands) = commands.get_entity(*entity) {
+                    if let Some(mut entity_commands) = commands.get_entity(*entity) {
                         trace!(
                             ?tick,
                             ?entity,
                             "Cleaning up prespawned player object up to past tick: {:?}",
                             past_tick
                         );
-                        entity_commands.despawn_recursive();
+                        // the observers run before the despawn, so they can still access the entity
+                        entity_commands
+                            .commands()
+                            .trigger_targets(PredictionRejected { tick: spawn_tick }, *entity);
+                        if timeout == PrespawnTimeout::Never {
+                            entity_commands.remove::<PreSpawnedPlayerObject>();
+                        } else {
+                            entity_commands.despawn_recursive();
+                        }
                     }
                 });
         }
     }
 }
 
+/// Triggered for a [`PreSpawnedPlayerObject`] entity that didn't match any entity spawned by the server
+/// before the [`PredictionConfig::prespawn_timeout`](crate::prelude::client::PredictionConfig::prespawn_timeout),
+/// for example because the server rejected the action that spawned it.
+///
+/// The event targets the entity, and is triggered right before the entity is despawned.
+/// With [`PrespawnTimeout::Never`](crate::prelude::client::PrespawnTimeout::Never), the event is triggered
+/// once the client stops trying to match the entity, right before the [`PreSpawnedPlayerObject`] component
+/// is removed; the entity itself is kept.
+///
+/// ```rust,ignore
+/// app.observe(|trigger: Trigger<PredictionRejected>, query: Query<&Transform>| {
+///     if let Ok(transform) = query.get(trigger.entity()) {
+///         // play a "miss" effect at the position of the entity
+///     }
+/// });
+/// ```
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PredictionRejected {
+    /// Tick at which the entity was spawned
+    pub tick: Tick,
+}
+
 #[derive(
     Component, Serialize, Deserialize, Default, Debug, Copy, Clone, PartialEq, Eq, Reflect,
 )]
@@ -447,6 +487,8 @@ impl PreSpawnedPlayerObject {
 
 #[cfg(test)]
 mod tests {
+    use bevy::prelude::*;
+
     use crate::client::prediction::predicted_history::{ComponentState, PredictionHistory};
     use crate::client::prediction::resource::PredictionManager;
 
@@ -514,4 +556,84 @@ mod tests {
             })
         );
     }
+
+    /// Entities for which a `PredictionRejected` was triggered, and their component at that time
+    #[derive(Resource, Default)]
+    struct Rejected(Vec<(Entity, Option<ComponentSyncModeFull>)>);
+
+    fn prespawn_stepper(timeout: client::PrespawnTimeout) -> BevyStepper {
+        let tick_duration = bevy::utils::Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let client_config = client::ClientConfig {
+            prediction: client::PredictionConfig::default().with_prespawn_timeout(timeout),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, tick_duration);
+        stepper.client_app.init_resource::<Rejected>().observe(
+            |trigger: Trigger<client::PredictionRejected>,
+             query: Query<&ComponentSyncModeFull>,
+             mut rejected: ResMut<Rejected>| {
+                rejected
+                    .0
+                    .push((trigger.entity(), query.get(trigger.entity()).ok().cloned()))
+            },
+        );
+        stepper.init();
+        stepper
+    }
+
+    #[test]
+    fn test_prespawn_timeout() {
+        let mut stepper = prespawn_stepper(client::PrespawnTimeout::Ticks(5));
+        let entity = stepper
+            .client_app
+            .world_mut()
+            .spawn((
+                ComponentSyncModeFull(1.0),
+                PreSpawnedPlayerObject::default(),
+            ))
+            .id();
+        for _ in 0..4 {
+            stepper.frame_step();
+        }
+        assert!(stepper.client_app.world().get_entity(entity).is_some());
+        for _ in 0..3 {
+            stepper.frame_step();
+        }
+        // the server never spawned a matching entity
+        assert!(stepper.client_app.world().get_entity(entity).is_none());
+        assert_eq!(
+            stepper.client_app.world().resource::<Rejected>().0,
+            vec![(entity, Some(ComponentSyncModeFull(1.0)))]
+        );
+    }
+
+    #[test]
+    fn test_prespawn_timeout_never() {
+        let mut stepper = prespawn_stepper(client::PrespawnTimeout::Never);
+        let entity = stepper
+            .client_app
+            .world_mut()
+            .spawn((
+                ComponentSyncModeFull(1.0),
+                PreSpawnedPlayerObject::default(),
+            ))
+            .id();
+        for _ in 0..50 {
+            stepper.frame_step();
+        }
+        // the entity is kept as a client-only entity
+        assert!(stepper
+            .client_app
+            .world()
+            .get::<PreSpawnedPlayerObject>(entity)
+            .is_none());
+        assert_eq!(
+            stepper.client_app.world().resource::<Rejected>().0,
+            vec![(entity, Some(ComponentSyncModeFull(1.0)))]
+        );
+    }
 }
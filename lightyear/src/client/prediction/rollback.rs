@@ -10,7 +10,7 @@ use bevy::prelude::{
 };
 use bevy::reflect::Reflect;
 use parking_lot::RwLock;
-use tracing::{debug, error, trace, trace_span};
+use tracing::{debug, error, trace, trace_span, warn};
 
 use crate::client::components::{Confirmed, SyncComponent};
 use crate::client::config::ClientConfig;
@@ -25,6 +25,10 @@ use super::predicted_history::PredictionHistory;
 use super::Predicted;
 
 /// Resource that indicates whether we are in a rollback state or not
+///
+/// You can use [`Rollback::is_replaying`] in your `FixedMain` systems (`FixedPreUpdate`, `FixedUpdate`, `FixedPostUpdate`, etc.)
+/// to know if the current tick is being re-simulated during a rollback, for example to avoid
+/// playing a sound or spawning VFX multiple times for the same tick.
 #[derive(Default, Resource, Reflect)]
 #[reflect(Resource)]
 pub struct Rollback {
@@ -33,6 +37,8 @@ pub struct Rollback {
     /// We use a RwLock because we want to be able to update this value from multiple systems
     /// in parallel.
     pub state: RwLock<RollbackState>,
+    /// True while we are re-running the `FixedMain` schedule to re-simulate the rollback ticks
+    replaying: bool,
     // pub rollback_groups: EntityHashMap<ReplicationGroupId, RollbackState>,
 }
 
@@ -56,6 +62,7 @@ impl Rollback {
     pub(crate) fn new(state: RollbackState) -> Self {
         Self {
             state: RwLock::new(state),
+            replaying: false,
         }
     }
 
@@ -67,6 +74,17 @@ impl Rollback {
         }
     }
 
+    /// Returns true if the `FixedMain` schedule is currently being re-run to re-simulate
+    /// a tick during rollback, and false if it is running for a new tick.
+    ///
+    /// This is only meaningful in the `FixedMain` schedules (`FixedPreUpdate`, `FixedUpdate`, `FixedPostUpdate`, etc.).
+    /// In other schedules it always returns false, because the rollback re-simulation happens entirely
+    /// within the `PreUpdate` schedule. Use [`Rollback::is_rollback`] to check if a rollback has been
+    /// detected.
+    pub fn is_replaying(&self) -> bool {
+        self.replaying
+    }
+
     /// Get the current rollback tick
     pub fn get_rollback_tick(&self) -> Option<Tick> {
         match *self.state.read().deref() {
@@ -102,7 +120,9 @@ impl Rollback {
 #[allow(clippy::type_complexity)]
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn check_rollback<C: SyncComponent>(
+    mut commands: Commands,
     component_registry: Res<ComponentRegistry>,
+    config: Res<ClientConfig>,
     // TODO: have a way to only get the updates of entities that are predicted?
     tick_manager: Res<TickManager>,
     connection: Res<ConnectionManager>,
@@ -111,6 +131,7 @@ pub(crate) fn check_rollback<C: SyncComponent>(
     // We use Option<> because the predicted component could have been removed while it still exists in Confirmed
     confirmed_query: Query<(Entity, Option<&C>, Ref<Confirmed>)>,
     rollback: Res<Rollback>,
+    metrics: Option<ResMut<PredictionMetrics>>,
 ) {
     // TODO: can just enable bevy spans?
     let _span = trace_span!("client rollback check");
@@ -125,6 +146,7 @@ pub(crate) fn check_rollback<C: SyncComponent>(
     }
 
     let current_tick = tick_manager.tick();
+    let mut history_snaps = 0;
     for (confirmed_entity, confirmed_component, confirmed) in confirmed_query.iter() {
         // NOTE: it is not enough to check if we received any ComponentRemoveEvent<C>, ComponentUpdateEvent<C> and ComponentInsertEvent<C>
         //  because we could have entity A and B in the same ReplicationGroup.
@@ -171,7 +193,32 @@ pub(crate) fn check_rollback<C: SyncComponent>(
             continue;
         }
 
-        // 3.a We are still not sure if we should do rollback. Compare history against confirmed
+        // 3. If the server update is older than the history, we cannot check for rollback correctly:
+        // snap the predicted entity to the server state instead
+        if (current_tick - tick) as i32 > config.prediction.history_ticks as i32 {
+            warn!(
+                ?confirmed_entity,
+                ?tick,
+                ?current_tick,
+                "Server update for {:?} is older than the prediction history, snapping the predicted entity to the server state. Consider increasing `PredictionConfig::history_ticks`",
+                kind
+            );
+            history_snaps += 1;
+            predicted_history.clear();
+            match confirmed_component {
+                None => {
+                    predicted_history.add_remove(current_tick);
+                    commands.entity(p).remove::<C>();
+                }
+                Some(c) => {
+                    predicted_history.add_update(current_tick, c.clone());
+                    commands.entity(p).insert(c.clone());
+                }
+            }
+            continue;
+        }
+
+        // 4.a We are still not sure if we should do rollback. Compare history against confirmed
         // We rollback if there's no history (newly added predicted entity, or if there is a mismatch)
         if !rollback.is_rollback() {
             let history_value = predicted_history.pop_until_tick(tick);
@@ -202,13 +249,16 @@ pub(crate) fn check_rollback<C: SyncComponent>(
                 rollback.set_rollback_tick(tick + 1);
             }
         } else {
-            // 3.b We already know we should do rollback (because of another entity/component), start the rollback
+            // 4.b We already know we should do rollback (because of another entity/component), start the rollback
             trace!(
                    "Rollback check: should roll back for component between predicted and confirmed on tick {:?} for component {:?}. Current tick: {:?}",
                    tick, kind, current_tick
                    );
         }
     }
+    if let Some(mut metrics) = metrics.filter(|_| history_snaps > 0) {
+        metrics.history_snaps += history_snaps;
+    }
 }
 
 /// If there is a mismatch, prepare rollback for all components
@@ -272,6 +322,12 @@ pub(crate) fn prepare_rollback<C: SyncComponent>(
             continue;
         };
 
+        // the value that we predicted for the rollback tick, to measure the prediction error
+        let predicted_at_rollback_tick = match predicted_history.pop_until_tick(rollback_tick) {
+            Some(ComponentState::Updated(c)) => Some(c),
+            _ => None,
+        };
+
         // 2. we need to clear the history so we can write a new one
         predicted_history.clear();
         // SAFETY: we know the predicted entity exists
@@ -309,8 +365,22 @@ pub(crate) fn prepare_rollback<C: SyncComponent>(
                             * config.prediction.correction_ticks_factor)
                             .round() as i16;
 
-                        // no need to add the Correction if the correction is instant
-                        if correction_ticks != 0 && component_registry.has_correction::<C>() {
+                        // no need to add the Correction if the correction is instant, or if the
+                        // prediction error is too big and we snap to the corrected value.
+                        // The error is measured at the rollback tick, where the confirmed value is known
+                        // (the predicted entity might have moved since then)
+                        if component_registry.should_snap_correction(
+                            predicted_at_rollback_tick
+                                .as_ref()
+                                .unwrap_or(predicted_component.as_ref()),
+                            c,
+                        ) {
+                            debug!("snapping to the corrected value");
+                            if correction.is_some() {
+                                entity_mut.remove::<Correction<C>>();
+                            }
+                        } else if correction_ticks != 0 && component_registry.has_correction::<C>()
+                        {
                             let final_correction_tick = current_tick + correction_ticks;
                             if let Some(correction) = correction.as_mut() {
                                 debug!("updating existing correction");
@@ -538,11 +608,17 @@ pub(crate) fn run_rollback(world: &mut World) {
         current_rollback_tick, current_tick
     );
 
+    #[cfg(feature = "metrics")]
+    let rollback_start = bevy::utils::Instant::now();
+
+    world.resource_mut::<Rollback>().replaying = true;
     // run the physics fixed update schedule (which should contain ALL predicted/rollback components)
     for i in 0..num_rollback_ticks {
         debug!("Rollback tick: {:?}", current_rollback_tick + i);
         // TODO: if we are in rollback, there are some FixedUpdate systems that we don't want to re-run ??
         //  for example we only want to run the physics on non-confirmed entities
+        // NOTE: this also replays the `PredictedFixedUpdate` schedule, whose systems are guaranteed to run
+        //  in the same order as during the original tick
         world.run_schedule(FixedMain)
     }
     debug!("Finished rollback. Current tick: {:?}", current_tick);
@@ -551,8 +627,17 @@ pub(crate) fn run_rollback(world: &mut World) {
     metrics.rollbacks += 1;
     metrics.rollback_ticks += num_rollback_ticks as u32;
 
+    // record the cost of each rollback, to detect deep rollbacks that could exceed the frame budget
+    #[cfg(feature = "metrics")]
+    {
+        metrics::histogram!("prediction.rollback_ticks").record(num_rollback_ticks as f64);
+        metrics::histogram!("prediction.rollback_duration_ms")
+            .record(rollback_start.elapsed().as_secs_f64() * 1000.0);
+    }
+
     // revert the state of Rollback for the next frame
-    let rollback = world.get_resource_mut::<Rollback>().unwrap();
+    let mut rollback = world.get_resource_mut::<Rollback>().unwrap();
+    rollback.replaying = false;
     rollback.set_non_rollback();
 }
 
@@ -565,11 +650,37 @@ pub(crate) fn increment_rollback_tick(rollback: Res<Rollback>) {
 pub(super) mod test_utils {
     use crate::client::components::Confirmed;
     use crate::client::connection::ConnectionManager;
+    use crate::client::prediction::Predicted;
     use crate::prelude::Tick;
     use crate::tests::stepper::BevyStepper;
     use bevy::prelude::Entity;
     use std::time::Duration;
 
+    /// Spawn a confirmed entity and the corresponding predicted entity
+    pub(super) fn predicted_setup(stepper: &mut BevyStepper) -> (Entity, Entity) {
+        let confirmed = stepper
+            .client_app
+            .world_mut()
+            .spawn(Confirmed::default())
+            .id();
+        let predicted = stepper
+            .client_app
+            .world_mut()
+            .spawn(Predicted {
+                confirmed_entity: Some(confirmed),
+            })
+            .id();
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(confirmed)
+            .get_mut::<Confirmed>()
+            .unwrap()
+            .predicted = Some(predicted);
+        stepper.frame_step();
+        (confirmed, predicted)
+    }
+
     /// Helper function to simulate that we received a server message
     pub(super) fn received_confirmed_update(
         stepper: &mut BevyStepper,
@@ -748,6 +859,182 @@ mod unit_tests {
             .resource::<Rollback>()
             .is_rollback());
     }
+
+    /// Check that if the confirmed update is older than the prediction history, we snap the
+    /// predicted entity to the server state instead of rolling back
+    #[test]
+    fn test_check_rollback_older_than_history() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ClientConfig>()
+            .prediction
+            .history_ticks = 5;
+        let confirmed = stepper
+            .client_app
+            .world_mut()
+            .spawn(Confirmed::default())
+            .id();
+        let predicted = stepper
+            .client_app
+            .world_mut()
+            .spawn(Predicted {
+                confirmed_entity: Some(confirmed),
+            })
+            .id();
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(confirmed)
+            .get_mut::<Confirmed>()
+            .unwrap()
+            .predicted = Some(predicted);
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(confirmed)
+            .insert(ComponentSyncModeFull(1.0));
+        let tick = stepper.client_tick();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+
+        // the history is bounded
+        for i in 0..10 {
+            stepper
+                .client_app
+                .world_mut()
+                .get_mut::<ComponentSyncModeFull>(predicted)
+                .unwrap()
+                .0 = 10.0 + i as f32;
+            stepper.frame_step();
+        }
+        let history_length = stepper
+            .client_app
+            .world()
+            .get::<PredictionHistory<ComponentSyncModeFull>>(predicted)
+            .unwrap()
+            .buffer
+            .len();
+        assert_eq!(history_length, 6);
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<PredictionMetrics>()
+                .max_history_length,
+            6
+        );
+
+        // receive a server update that is older than the history
+        let history_snaps = stepper
+            .client_app
+            .world()
+            .resource::<PredictionMetrics>()
+            .history_snaps;
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<ComponentSyncModeFull>(confirmed)
+            .unwrap()
+            .0 = 2.0;
+        received_confirmed_update(&mut stepper, confirmed, tick);
+        stepper
+            .client_app
+            .world_mut()
+            .run_system_once(check_rollback::<ComponentSyncModeFull>);
+        assert!(!stepper
+            .client_app
+            .world()
+            .resource::<Rollback>()
+            .is_rollback());
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(predicted),
+            Some(&ComponentSyncModeFull(2.0))
+        );
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<PredictionMetrics>()
+                .history_snaps,
+            history_snaps + 1
+        );
+    }
+
+    /// Check that the correction is skipped when the prediction error is above the snap threshold
+    #[test]
+    fn test_correction_snap() {
+        let mut stepper = BevyStepper::default();
+        {
+            let mut registry = stepper
+                .client_app
+                .world_mut()
+                .resource_mut::<ComponentRegistry>();
+            registry.set_correction::<ComponentSyncModeFull>(|start, other, t| {
+                ComponentSyncModeFull(start.0 + (other.0 - start.0) * t)
+            });
+            registry.set_correction_snap::<ComponentSyncModeFull>(|predicted, corrected| {
+                (predicted.0 - corrected.0).abs() > 10.0
+            });
+        }
+        let (confirmed, predicted) = predicted_setup(&mut stepper);
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(confirmed)
+            .insert(ComponentSyncModeFull(1.0));
+        stepper.frame_step();
+        stepper.frame_step();
+        let rollback_tick = stepper.client_tick() - 1;
+        // set the value predicted at the rollback tick, and the current predicted value
+        let predict = |stepper: &mut BevyStepper, at_rollback_tick: f32, current: f32| {
+            let mut entity_mut = stepper.client_app.world_mut().entity_mut(predicted);
+            let mut history = entity_mut
+                .get_mut::<PredictionHistory<ComponentSyncModeFull>>()
+                .unwrap();
+            history.clear();
+            history.add_update(rollback_tick, ComponentSyncModeFull(at_rollback_tick));
+            entity_mut.insert(ComponentSyncModeFull(current));
+        };
+
+        // the entity kept moving after the rollback tick, but the prediction error at the rollback tick
+        // is small: the correction is applied
+        predict(&mut stepper, 5.0, 100.0);
+        received_confirmed_update(&mut stepper, confirmed, rollback_tick);
+        stepper
+            .client_app
+            .world_mut()
+            .run_system_once(prepare_rollback::<ComponentSyncModeFull>);
+        assert!(stepper
+            .client_app
+            .world()
+            .get::<Correction<ComponentSyncModeFull>>(predicted)
+            .is_some());
+
+        // the prediction error at the rollback tick is too big: the predicted entity snaps to the corrected value
+        predict(&mut stepper, 100.0, 100.0);
+        stepper
+            .client_app
+            .world_mut()
+            .run_system_once(prepare_rollback::<ComponentSyncModeFull>);
+        assert!(stepper
+            .client_app
+            .world()
+            .get::<Correction<ComponentSyncModeFull>>(predicted)
+            .is_none());
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(predicted),
+            Some(&ComponentSyncModeFull(1.0))
+        );
+    }
 }
 
 /// More general integration tests for rollback
@@ -801,6 +1088,138 @@ mod integration_tests {
         (stepper, confirmed, predicted)
     }
 
+    #[derive(Resource, Default)]
+    struct SimulatedTicks {
+        fresh: usize,
+        replayed: usize,
+    }
+
+    fn count_simulated_ticks(rollback: Res<Rollback>, mut ticks: ResMut<SimulatedTicks>) {
+        if rollback.is_replaying() {
+            ticks.replayed += 1;
+        } else {
+            ticks.fresh += 1;
+        }
+    }
+
+    /// Test that `is_replaying` is only true while the rollback ticks are being re-simulated
+    #[test]
+    fn test_rollback_is_replaying() {
+        let (mut stepper, confirmed, _predicted) = setup();
+        stepper
+            .client_app
+            .init_resource::<SimulatedTicks>()
+            .add_systems(FixedUpdate, count_simulated_ticks);
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(confirmed)
+            .insert(ComponentSyncModeFull(0.0));
+        stepper.frame_step();
+        assert!(!stepper
+            .client_app
+            .world()
+            .resource::<Rollback>()
+            .is_replaying());
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<SimulatedTicks>()
+                .replayed,
+            0
+        );
+
+        // trigger a rollback of 3 ticks
+        let fresh_before = stepper
+            .client_app
+            .world()
+            .resource::<SimulatedTicks>()
+            .fresh;
+        let tick = stepper.client_tick();
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<ComponentSyncModeFull>(confirmed)
+            .unwrap()
+            .0 = -10.0;
+        received_confirmed_update(&mut stepper, confirmed, tick - 3);
+        stepper.frame_step();
+
+        let ticks = stepper.client_app.world().resource::<SimulatedTicks>();
+        assert_eq!(ticks.replayed, 3);
+        assert_eq!(ticks.fresh, fresh_before + 1);
+        assert!(!stepper
+            .client_app
+            .world()
+            .resource::<Rollback>()
+            .is_replaying());
+    }
+
+    fn add_one(mut query: Query<&mut ComponentSyncModeFull, With<Predicted>>) {
+        for mut component in query.iter_mut() {
+            component.0 += 1.0;
+        }
+    }
+
+    fn double(mut query: Query<&mut ComponentSyncModeFull, With<Predicted>>) {
+        for mut component in query.iter_mut() {
+            component.0 *= 2.0;
+        }
+    }
+
+    /// Test that the order-dependent systems of the `PredictedFixedUpdate` schedule run
+    /// in the same order when the ticks are re-simulated during a rollback
+    #[test]
+    fn test_predicted_schedule_rollback_order() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .client_app
+            .add_systems(PredictedFixedUpdate, (add_one, double).chain());
+        let (confirmed, predicted) = predicted_setup(&mut stepper);
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(confirmed)
+            .insert(ComponentSyncModeFull(0.0));
+        stepper.frame_step();
+
+        // trigger a rollback of 3 ticks
+        let tick = stepper.client_tick();
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<ComponentSyncModeFull>(confirmed)
+            .unwrap()
+            .0 = 1.0;
+        received_confirmed_update(&mut stepper, confirmed, tick - 3);
+        stepper.frame_step();
+
+        // 3 replayed ticks and 1 new tick, each one running `add_one` and then `double`:
+        // 1 -> 4 -> 10 -> 22 -> 46
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(predicted)
+                .unwrap(),
+            &ComponentSyncModeFull(46.0)
+        );
+    }
+
+    /// Test that the `PredictedFixedUpdate` schedule rejects systems that are order-dependent
+    /// (they mutate the same component) but could run in any order
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "PredictedFixedUpdate")]
+    fn test_predicted_schedule_ambiguous_order() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .client_app
+            .add_systems(PredictedFixedUpdate, (add_one, double));
+        stepper.frame_step();
+    }
+
     /// Test that:
     /// - we remove a component from the predicted entity
     /// - rolling back before the remove should re-add it
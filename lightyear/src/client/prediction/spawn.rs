@@ -1,5 +1,5 @@
 //! Logic to handle spawning Predicted entities
-use bevy::prelude::{Added, Commands, Entity, Query, Res, ResMut};
+use bevy::prelude::{Added, Commands, Entity, Query, Res, ResMut, With};
 use tracing::debug;
 
 use crate::client::components::Confirmed;
@@ -28,8 +28,24 @@ pub(crate) fn spawn_predicted_entity(
     // only handle predicted that have ShouldBePredicted
     // (if the entity was handled by prespawn or prepredicted before, ShouldBePredicted gets removed)
     mut confirmed_entities: Query<(Entity, Option<&mut Confirmed>), Added<ShouldBePredicted>>,
+    predicted: Query<(), With<Predicted>>,
 ) {
     for (confirmed_entity, confirmed) in confirmed_entities.iter_mut() {
+        // the server resynced an entity that is already predicted: keep the existing predicted entity,
+        // which will be rolled back if it diverged from the resynced state
+        if let Some(predicted_entity) = confirmed.as_ref().and_then(|c| c.predicted) {
+            if predicted.get(predicted_entity).is_ok() {
+                debug!(
+                    ?confirmed_entity,
+                    ?predicted_entity,
+                    "Received ShouldBePredicted for an entity that is already predicted"
+                );
+                commands
+                    .entity(confirmed_entity)
+                    .remove::<ShouldBePredicted>();
+                continue;
+            }
+        }
         debug!("Received entity with ShouldBePredicted from server: {confirmed_entity:?}");
         // we need to spawn a predicted entity for this confirmed entity
         let predicted_entity = commands
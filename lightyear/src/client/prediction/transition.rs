@@ -0,0 +1,224 @@
+//! Smooth the visual change when an entity switches between interpolation and prediction
+//!
+//! The interpolated entity is displayed in the past, while the predicted entity is displayed in the future;
+//! when the client starts predicting an entity that was interpolated (for example when it gains authority
+//! over it), or starts interpolating an entity that was predicted, the entity would visually jump
+//! between the two timelines.
+//!
+//! Instead, for a number of ticks after the switch, the visual value of the new entity is eased from the
+//! current value of the previous entity, using the component's interpolation function.
+use bevy::prelude::{Added, Commands, Component, DetectChangesMut, Entity, Query, Res, With};
+use tracing::debug;
+
+use crate::client::components::{Confirmed, SyncComponent};
+use crate::client::config::ClientConfig;
+use crate::client::easings::ease_out_quad;
+use crate::client::interpolation::Interpolated;
+use crate::client::prediction::Predicted;
+use crate::prelude::{ComponentRegistry, Tick, TickManager};
+
+/// Eases the visual value of a component from the value of another entity that represents the same
+/// confirmed entity.
+///
+/// This is added automatically when an entity switches between the interpolated and the predicted timeline
+/// (see [`PredictionConfig::sync_mode_transition_ticks`](crate::client::prediction::plugin::PredictionConfig::sync_mode_transition_ticks)),
+/// but it can also be inserted manually to transition between two entities.
+#[derive(Component, Debug)]
+pub struct SyncModeTransition<C: Component> {
+    /// The entity that was previously used to display the confirmed entity
+    pub source: Entity,
+    /// This is the tick at which the transition started
+    pub start_tick: Tick,
+    /// This is the tick at which the transition will be over
+    pub end_tick: Tick,
+    /// This is the current actual value, which we restore at the start of the next frame
+    pub current_value: Option<C>,
+}
+
+impl<C: Component> SyncModeTransition<C> {
+    pub fn new(source: Entity, start_tick: Tick, ticks: u16) -> Self {
+        Self {
+            source,
+            start_tick,
+            end_tick: start_tick + ticks as i16,
+            current_value: None,
+        }
+    }
+}
+
+/// Start a transition when a component is added to the predicted entity of a confirmed entity that was
+/// already interpolated, or to the interpolated entity of a confirmed entity that was already predicted.
+pub(crate) fn start_sync_mode_transition<C: SyncComponent>(
+    config: Res<ClientConfig>,
+    component_registry: Res<ComponentRegistry>,
+    tick_manager: Res<TickManager>,
+    mut commands: Commands,
+    confirmed: Query<&Confirmed>,
+    components: Query<(), With<C>>,
+    added_predicted: Query<(Entity, &Predicted), Added<C>>,
+    added_interpolated: Query<(Entity, &Interpolated), Added<C>>,
+) {
+    let ticks = config.prediction.sync_mode_transition_ticks;
+    if ticks == 0 || !component_registry.has_interpolation::<C>() {
+        return;
+    }
+    let start_tick = tick_manager.tick();
+    let predicted_sources = added_predicted.iter().filter_map(|(entity, predicted)| {
+        let confirmed = confirmed.get(predicted.confirmed_entity?).ok()?;
+        Some((entity, confirmed.interpolated?))
+    });
+    let interpolated_sources = added_interpolated
+        .iter()
+        .filter_map(|(entity, interpolated)| {
+            let confirmed = confirmed.get(interpolated.confirmed_entity).ok()?;
+            Some((entity, confirmed.predicted?))
+        });
+    for (entity, source) in predicted_sources.chain(interpolated_sources) {
+        if components.get(source).is_err() {
+            continue;
+        }
+        debug!(
+            ?entity,
+            ?source,
+            "Starting sync mode transition for {:?}",
+            std::any::type_name::<C>()
+        );
+        commands
+            .entity(entity)
+            .insert(SyncModeTransition::<C>::new(source, start_tick, ticks));
+    }
+}
+
+/// Visually update the component to a value that is interpolated between the value of the source entity
+/// and the actual value
+pub(crate) fn apply_sync_mode_transition<C: SyncComponent>(
+    component_registry: Res<ComponentRegistry>,
+    tick_manager: Res<TickManager>,
+    mut commands: Commands,
+    mut transitions: Query<(Entity, &mut SyncModeTransition<C>)>,
+    mut components: Query<&mut C>,
+) {
+    let current_tick = tick_manager.tick();
+    for (entity, mut transition) in transitions.iter_mut() {
+        let t = (current_tick - transition.start_tick) as f32
+            / (transition.end_tick - transition.start_tick) as f32;
+        let t = ease_out_quad(t.clamp(0.0, 1.0));
+        // the source entity might have been despawned in the meantime
+        let source = components.get(transition.source).ok().cloned();
+        let (Some(source), true) = (source, t < 1.0) else {
+            debug!(?entity, "Sync mode transition is over");
+            commands.entity(entity).remove::<SyncModeTransition<C>>();
+            continue;
+        };
+        let Ok(mut component) = components.get_mut(entity) else {
+            continue;
+        };
+        // store the actual value so that we can restore it at the start of the next frame
+        transition.current_value = Some(component.clone());
+        let visual = component_registry.interpolate(&source, component.as_ref(), t);
+        *component.bypass_change_detection() = visual;
+    }
+}
+
+/// At the start of the next frame, restore the actual value of the component
+pub(crate) fn restore_sync_mode_transition<C: SyncComponent>(
+    mut query: Query<(&mut C, &mut SyncModeTransition<C>)>,
+) {
+    for (mut component, mut transition) in query.iter_mut() {
+        if let Some(value) = std::mem::take(&mut transition.current_value) {
+            *component.bypass_change_detection() = value;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::default;
+
+    use crate::client::components::Confirmed;
+    use crate::client::prediction::plugin::PredictionConfig;
+    use crate::prelude::client::ClientConfig;
+    use crate::prelude::{SharedConfig, TickConfig};
+    use crate::tests::protocol::ComponentSyncModeFull;
+    use crate::tests::stepper::BevyStepper;
+
+    use super::*;
+
+    #[test]
+    fn test_interpolated_to_predicted_transition() {
+        let frame_duration = std::time::Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..default()
+        };
+        let client_config = ClientConfig {
+            prediction: PredictionConfig::default().with_sync_mode_transition_ticks(4),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, frame_duration);
+        stepper.init();
+
+        let world = stepper.client_app.world_mut();
+        let confirmed = world.spawn(ComponentSyncModeFull(10.0)).id();
+        let interpolated = world
+            .spawn((
+                Interpolated {
+                    confirmed_entity: confirmed,
+                },
+                ComponentSyncModeFull(0.0),
+            ))
+            .id();
+        // the entity starts being predicted
+        let predicted = world
+            .spawn((
+                Predicted {
+                    confirmed_entity: Some(confirmed),
+                },
+                ComponentSyncModeFull(10.0),
+            ))
+            .id();
+        let tick = stepper.client_tick();
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(confirmed)
+            .insert(Confirmed {
+                predicted: Some(predicted),
+                interpolated: Some(interpolated),
+                tick,
+            });
+
+        // the visual value starts from the interpolated value
+        stepper.frame_step();
+        let value = stepper
+            .client_app
+            .world()
+            .get::<ComponentSyncModeFull>(predicted)
+            .unwrap()
+            .0;
+        assert!(value < 10.0, "value: {value}");
+        assert!(stepper
+            .client_app
+            .world()
+            .get::<SyncModeTransition<ComponentSyncModeFull>>(predicted)
+            .is_some());
+
+        // the transition ends on the actual predicted value
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(predicted)
+                .unwrap(),
+            &ComponentSyncModeFull(10.0)
+        );
+        assert!(stepper
+            .client_app
+            .world()
+            .get::<SyncModeTransition<ComponentSyncModeFull>>(predicted)
+            .is_none());
+    }
+}
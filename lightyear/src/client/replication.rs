@@ -9,13 +9,15 @@ use crate::shared::sets::{ClientMarker, InternalReplicationSet};
 
 pub(crate) mod receive {
     use super::*;
+    use crate::client::events::WorldSnapshotComplete;
     use crate::prelude::client::MessageEvent;
     use crate::prelude::{
         client::{is_connected, is_synced},
         is_host_server,
     };
+    use crate::server::initial_sync::InitialSyncComplete;
     use crate::shared::replication::authority::{AuthorityChange, HasAuthority};
-    use crate::shared::sets::InternalMainSet;
+    use crate::shared::sets::{InternalMainSet, NetworkingSchedules};
 
     #[derive(Default)]
     pub struct ClientReplicationReceivePlugin {
@@ -24,13 +26,14 @@ pub(crate) mod receive {
 
     impl Plugin for ClientReplicationReceivePlugin {
         fn build(&self, app: &mut App) {
+            let schedules = NetworkingSchedules::of(app);
             // PLUGIN
             app.add_plugins(ReplicationReceivePlugin::<ConnectionManager>::new(
                 self.tick_interval,
             ));
 
             app.configure_sets(
-                PostUpdate,
+                schedules.send,
                 // only replicate entities once client is synced
                 // NOTE: we need is_synced, and not connected. Otherwise the ticks associated with the messages might be incorrect
                 //  and the message might be ignored by the server
@@ -44,8 +47,12 @@ pub(crate) mod receive {
             );
 
             app.add_systems(
-                PreUpdate,
-                handle_authority_change.after(InternalMainSet::<ClientMarker>::EmitEvents),
+                schedules.receive,
+                (
+                    handle_authority_change,
+                    handle_initial_sync_complete.run_if(is_connected),
+                )
+                    .after(InternalMainSet::<ClientMarker>::EmitEvents),
             );
         }
     }
@@ -67,6 +74,34 @@ pub(crate) mod receive {
             }
         }
     }
+
+    /// Emit a [`WorldSnapshotComplete`] event once all the groups of the initial sync have been received
+    ///
+    /// The end of the initial sync is sent on a different channel than the entity spawns, so it
+    /// can be received before the last spawns.
+    fn handle_initial_sync_complete(
+        mut connection: ResMut<ConnectionManager>,
+        mut messages: ResMut<Events<MessageEvent<InitialSyncComplete>>>,
+        mut events: EventWriter<WorldSnapshotComplete>,
+    ) {
+        for message in messages.drain() {
+            connection.pending_initial_sync = Some(message.message.groups);
+        }
+        let connection = connection.as_mut();
+        let Some(pending) = connection.pending_initial_sync.as_mut() else {
+            return;
+        };
+        let group_channels = &connection.replication_receiver.group_channels;
+        pending.retain(|group_id| {
+            group_channels
+                .get(group_id)
+                .map_or(true, |channel| channel.latest_tick.is_none())
+        });
+        if pending.is_empty() {
+            connection.pending_initial_sync = None;
+            events.send(WorldSnapshotComplete);
+        }
+    }
 }
 
 pub(crate) mod send {
@@ -82,15 +117,16 @@ pub(crate) mod send {
         is_host_server, ComponentRegistry, DisabledComponent, ReplicateHierarchy, Replicated,
         ReplicationGroup, TargetEntity, Tick, TickManager, TimeManager,
     };
-    use crate::protocol::component::ComponentKind;
+    use crate::protocol::component::{ComponentKind, ReplicationMode};
 
-    use crate::shared::replication::components::{Replicating, ReplicationGroupId};
+    use crate::shared::replication::components::{DontReplicate, Replicating, ReplicationGroupId};
 
     use crate::shared::replication::archetypes::{
         get_erased_component, ClientReplicatedArchetypes,
     };
     use crate::shared::replication::authority::HasAuthority;
     use crate::shared::replication::error::ReplicationError;
+    use crate::shared::sets::NetworkingSchedules;
     use bevy::ecs::system::SystemChangeTick;
     use bevy::ptr::Ptr;
 
@@ -101,11 +137,8 @@ pub(crate) mod send {
 
     impl Plugin for ClientReplicationSendPlugin {
         fn build(&self, app: &mut App) {
-            let send_interval = app
-                .world()
-                .resource::<ClientConfig>()
-                .replication
-                .send_interval;
+            let schedules = NetworkingSchedules::of(app);
+            let replication_config = app.world().resource::<ClientConfig>().replication;
 
             app
                 // REFLECTION
@@ -113,11 +146,12 @@ pub(crate) mod send {
                 // PLUGIN
                 .add_plugins(ReplicationSendPlugin::<ConnectionManager>::new(
                     self.tick_interval,
-                    send_interval,
+                    replication_config.send_interval,
+                    replication_config.send_interval_ticks,
                 ))
                 // SETS
                 .configure_sets(
-                    PostUpdate,
+                    schedules.send,
                     // only replicate entities once client is synced
                     // NOTE: we need is_synced, and not connected. Otherwise the ticks associated with the messages might be incorrect
                     //  and the message might be ignored by the server
@@ -131,7 +165,7 @@ pub(crate) mod send {
                 )
                 // SYSTEMS
                 .add_systems(
-                    PostUpdate,
+                    schedules.send,
                     (
                         replicate
                             .in_set(InternalReplicationSet::<ClientMarker>::BufferEntityUpdates)
@@ -406,7 +440,7 @@ pub(crate) mod send {
         //  in which case we don't want to replicate the despawn.
         //  i.e. if a user wants to despawn an entity without replicating the despawn
         //  I guess we can provide a command that first removes Replicating, and then despawns the entity.
-        query: Query<&ReplicationGroup, With<Replicating>>,
+        query: Query<&ReplicationGroup, (With<Replicating>, Without<DontReplicate>)>,
         mut sender: ResMut<ConnectionManager>,
     ) {
         let mut entity = trigger.entity();
@@ -514,12 +548,20 @@ pub(crate) mod send {
                     .prepare_component_insert(entity, group_id, raw_data);
             } else {
                 trace!(?entity, "send update");
-                let send_tick = sender
+                // delta-compressed components are always sent unreliably, since the diffs are computed from the last acked update
+                let reliable = !delta_compression
+                    && component_registry.replication_mode(component_kind)
+                        == ReplicationMode::Reliable;
+                let group_channel = sender
                     .replication_sender
                     .group_channels
                     .entry(group_id)
-                    .or_default()
-                    .send_tick;
+                    .or_default();
+                let send_tick = if reliable {
+                    group_channel.reliable_send_tick
+                } else {
+                    group_channel.send_tick
+                };
 
                 // send the update for all changes newer than the last send bevy tick for the group
                 if send_tick.map_or(true, |c| {
@@ -564,9 +606,15 @@ pub(crate) mod send {
                             ),
                         )?;
                         let raw_data = writer.split();
-                        sender
-                            .replication_sender
-                            .prepare_component_update(entity, group_id, raw_data);
+                        if reliable {
+                            sender
+                                .replication_sender
+                                .prepare_reliable_component_update(entity, group_id, raw_data);
+                        } else {
+                            sender
+                                .replication_sender
+                                .prepare_component_update(entity, group_id, raw_data);
+                        }
                     }
                 }
             }
@@ -582,7 +630,11 @@ pub(crate) mod send {
         // only remove the component for entities that are being actively replicated
         query: Query<
             (&ReplicationGroup, Has<DisabledComponent<C>>),
-            (With<Replicating>, With<ReplicateToServer>),
+            (
+                With<Replicating>,
+                With<ReplicateToServer>,
+                Without<DontReplicate>,
+            ),
         >,
     ) {
         let mut entity = trigger.entity();
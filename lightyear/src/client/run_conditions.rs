@@ -1,4 +1,5 @@
 //! Common client-related run conditions
+use crate::client::config::ClientConfig;
 use crate::client::connection::ConnectionManager;
 use crate::connection::client::{ClientConnection, ConnectionState, NetClient};
 use bevy::prelude::Res;
@@ -31,3 +32,21 @@ pub fn is_synced(
         // TODO: check if this correct; in host-server mode, the client is always synced
         connection.map_or(false, |c| c.sync_manager.is_synced())
 }
+
+/// Run condition if the client is synced and the warmup phase that follows the handshake is over
+pub fn is_ready(
+    netclient: Option<Res<ClientConnection>>,
+    connection: Option<Res<ConnectionManager>>,
+) -> bool {
+    netclient.is_some_and(|c| matches!(c.state(), ConnectionState::Connected))
+        && connection.is_some_and(|c| c.sync_manager.is_ready())
+}
+
+/// Run condition for the prediction and interpolation systems: if [`SyncConfig::wait_for_warmup`](crate::client::sync::SyncConfig::wait_for_warmup)
+/// is enabled, they only run once the warmup is over
+pub(crate) fn is_warmup_done(
+    config: Res<ClientConfig>,
+    connection: Option<Res<ConnectionManager>>,
+) -> bool {
+    !config.sync.wait_for_warmup || connection.is_some_and(|c| c.sync_manager.is_ready())
+}
@@ -9,6 +9,7 @@ use crate::client::interpolation::plugin::InterpolationDelay;
 use crate::packet::packet::PacketId;
 use crate::prelude::client::PredictionConfig;
 use crate::shared::ping::manager::PingManager;
+use crate::shared::ping::message::TimeSync;
 use crate::shared::tick_manager::TickManager;
 use crate::shared::tick_manager::{Tick, TickEvent};
 use crate::shared::time_manager::{TimeManager, WrappedTime};
@@ -38,6 +39,19 @@ pub struct SyncConfig {
     pub tick_margin: u8,
     /// Number of pings to exchange with the server before finalizing the handshake
     pub handshake_pings: u8,
+    /// Duration of the warmup phase that follows the handshake.
+    ///
+    /// Right after the handshake, the estimate of the server time is still noisy. During the warmup
+    /// the client keeps exchanging pings with the server to stabilize its sync, and a
+    /// [`ReadyEvent`](crate::client::events::ReadyEvent) is emitted once it is over.
+    /// (see [`ConnectionManager::is_ready`](crate::client::connection::ConnectionManager::is_ready))
+    pub warmup_duration: Duration,
+    /// Minimum number of pongs to receive from the server during the warmup phase.
+    ///
+    /// The warmup is over once both `warmup_duration` has elapsed and `warmup_pings` pongs have been received.
+    pub warmup_pings: u8,
+    /// If true, prediction and interpolation only start running once the warmup is over
+    pub wait_for_warmup: bool,
     /// Error margin for upstream throttle (in multiple of ticks)
     pub error_margin: f32,
     /// If the error margin is too big, we snap the prediction/interpolation time to the objective value
@@ -56,6 +70,9 @@ impl Default for SyncConfig {
             jitter_multiple_margin: 3,
             tick_margin: 1,
             handshake_pings: 3,
+            warmup_duration: Duration::ZERO,
+            warmup_pings: 0,
+            wait_for_warmup: false,
             error_margin: 0.5,
             max_error_margin: 5.0,
             speedup_factor: 1.05,
@@ -70,6 +87,20 @@ impl SyncConfig {
         self.speedup_factor = speedup_factor;
         self
     }
+
+    /// Set the duration of the warmup phase that follows the handshake, and whether prediction
+    /// and interpolation should wait for the end of the warmup
+    pub fn with_warmup(mut self, warmup_duration: Duration, wait_for_warmup: bool) -> Self {
+        self.warmup_duration = warmup_duration;
+        self.wait_for_warmup = wait_for_warmup;
+        self
+    }
+
+    /// Set the minimum number of pongs to receive from the server during the warmup phase
+    pub fn with_warmup_pings(mut self, warmup_pings: u8) -> Self {
+        self.warmup_pings = warmup_pings;
+        self
+    }
 }
 
 #[derive(Default)]
@@ -93,6 +124,12 @@ pub struct SyncManager {
     prediction_config: PredictionConfig,
     /// whether the handshake is finalized
     pub(crate) synced: bool,
+    /// whether the warmup that follows the handshake is over
+    ready: bool,
+    /// time elapsed since the handshake was finalized
+    warmup_elapsed: Duration,
+    /// number of pongs received when the warmup started
+    warmup_start_pongs: Option<u32>,
 
     // time
     server_time_estimate: WrappedTime,
@@ -111,8 +148,29 @@ pub struct SyncManager {
     /// The Tick associated with the 'server_tick_generation' (it might not be the same as latest_received_server_tick
     /// because we update the generation only from pong messages)
     pub(crate) server_pong_tick: Tick,
+    /// Correction applied to the server time estimate from the latest [`TimeSync`] stamp.
+    ///
+    /// It is kept until the next stamp, so that the packets received in between (which don't contain
+    /// a stamp) don't move the estimate back to the start of their tick.
+    pub(crate) time_sync_correction: Duration,
+    /// The interpolation delay currently in use
+    pub(crate) interpolation_delay: Duration,
+    /// Fixed number of ticks of input delay requested by the [`InputConfig`](crate::client::input::native::InputConfig)
+    minimum_input_delay_ticks: u16,
+    /// The input delay (in ticks) currently in use
+    pub(crate) input_delay_ticks: u16,
+    /// How often the client sends input messages; the inputs of a tick can wait that long before being sent
+    input_send_interval: Duration,
+    /// Number of ticks that the inputs should be buffered on the server before their tick executes
+    input_jitter_buffer_ticks: u16,
+    /// If set, the prediction time is kept at this offset from the server time estimate, instead of
+    /// the offset computed from the RTT and jitter
+    manual_offset: Option<ChronoDuration>,
 }
 
+/// Weight of the previous correction in the moving average of the [`TimeSync`] stamps
+const TIME_SYNC_SMOOTHING: f32 = 0.8;
+
 // TODO: split into PredictionTime Manager, InterpolationTime Manager
 impl SyncManager {
     pub fn new(config: SyncConfig, prediction_config: PredictionConfig) -> Self {
@@ -120,6 +178,9 @@ impl SyncManager {
             config,
             prediction_config,
             synced: false,
+            ready: false,
+            warmup_elapsed: Duration::default(),
+            warmup_start_pongs: None,
             // time
             server_time_estimate: WrappedTime::default(),
             interpolation_time: WrappedTime::default(),
@@ -130,9 +191,44 @@ impl SyncManager {
             new_latest_received_server_tick: false,
             server_pong_generation: 0,
             server_pong_tick: Tick(0),
+            time_sync_correction: Duration::default(),
+            interpolation_delay: Duration::default(),
+            minimum_input_delay_ticks: 0,
+            input_delay_ticks: 0,
+            input_send_interval: Duration::default(),
+            input_jitter_buffer_ticks: 0,
+            manual_offset: None,
         }
     }
 
+    /// Keep the client far enough ahead of the server that inputs still arrive in time if they are
+    /// only sent every `input_send_interval`
+    pub(crate) fn with_input_send_interval(mut self, input_send_interval: Duration) -> Self {
+        self.input_send_interval = input_send_interval;
+        self
+    }
+
+    /// Run ahead of the server by `input_jitter_buffer_ticks` additional ticks, so that inputs
+    /// are buffered on the server for that many ticks
+    pub(crate) fn with_input_jitter_buffer_ticks(mut self, input_jitter_buffer_ticks: u16) -> Self {
+        self.input_jitter_buffer_ticks = input_jitter_buffer_ticks;
+        self
+    }
+
+    /// Always apply at least `input_delay_ticks` ticks of input delay
+    pub(crate) fn with_input_delay_ticks(mut self, input_delay_ticks: u16) -> Self {
+        self.minimum_input_delay_ticks = input_delay_ticks;
+        self.input_delay_ticks = input_delay_ticks;
+        self
+    }
+
+    /// Compute the input delay to apply, from the [`PredictionConfig`] and the fixed input delay
+    fn compute_input_delay_ticks(&self, rtt: Duration, tick_duration: Duration) -> u16 {
+        self.prediction_config
+            .input_delay_ticks(rtt, tick_duration)
+            .max(self.minimum_input_delay_ticks)
+    }
+
     /// We want to run this update at PostUpdate, after both ticks/time have been updated
     /// (because we need to compare the client tick with the server tick when the server sends packets,
     /// i.e. after both ticks/time have been updated)
@@ -153,11 +249,12 @@ impl SyncManager {
         // check if we are ready to finalize the handshake
         if !self.synced && ping_manager.sync_stats.len() >= self.config.handshake_pings as usize {
             self.synced = true;
-            self.interpolation_time = self.interpolation_objective(
-                interpolation_delay,
+            self.interpolation_delay = interpolation_delay.target_duration(
                 server_send_interval,
-                tick_manager,
+                tick_manager.config.tick_duration,
+                ping_manager.jitter(),
             );
+            self.interpolation_time = self.interpolation_objective(tick_manager);
             debug!(
                 interpolation_tick = ?self.interpolation_tick(tick_manager),
                 "Client is synced!"
@@ -166,15 +263,72 @@ impl SyncManager {
         }
 
         if self.synced {
-            self.update_interpolation_time(interpolation_delay, server_send_interval, tick_manager);
+            self.update_interpolation_delay(
+                interpolation_delay,
+                server_send_interval,
+                tick_manager.config.tick_duration,
+                ping_manager,
+            );
+            self.update_interpolation_time(tick_manager);
         }
         None
     }
 
+    /// Move the interpolation delay towards the target delay.
+    ///
+    /// If the delay is adaptive, the target depends on the jitter of the connection, and the delay
+    /// changes gradually to avoid oscillations.
+    pub(crate) fn update_interpolation_delay(
+        &mut self,
+        interpolation_delay: &InterpolationDelay,
+        server_send_interval: Duration,
+        tick_duration: Duration,
+        ping_manager: &PingManager,
+    ) {
+        let target = interpolation_delay.target_duration(
+            server_send_interval,
+            tick_duration,
+            ping_manager.jitter(),
+        );
+        self.interpolation_delay = match &interpolation_delay.adaptive {
+            None => target,
+            Some(adaptive) => {
+                let current = self.interpolation_delay.as_secs_f32();
+                let smoothing = adaptive.smoothing.clamp(0.0, 1.0);
+                Duration::from_secs_f32(current + (target.as_secs_f32() - current) * smoothing)
+            }
+        };
+    }
+
     pub(crate) fn is_synced(&self) -> bool {
         self.synced
     }
 
+    pub(crate) fn is_ready(&self) -> bool {
+        self.ready
+    }
+
+    /// Advance the warmup phase that follows the handshake.
+    ///
+    /// `pongs_recv` is the total number of pongs received from the server.
+    ///
+    /// Returns true on the frame where the warmup is over
+    pub(crate) fn update_warmup(&mut self, delta: Duration, pongs_recv: u32) -> bool {
+        if !self.synced || self.ready {
+            return false;
+        }
+        self.warmup_elapsed += delta;
+        let warmup_pongs = pongs_recv - *self.warmup_start_pongs.get_or_insert(pongs_recv);
+        if self.warmup_elapsed >= self.config.warmup_duration
+            && warmup_pongs >= self.config.warmup_pings as u32
+        {
+            debug!("Client warmup is over");
+            self.ready = true;
+            return true;
+        }
+        false
+    }
+
     /// Compute the current client time; we will make sure that the client tick is ahead of the server tick
     /// Even if it is wrapped around.
     /// (i.e. if client tick is 1, and server tick is 65535, we act as if the client tick was 65537)
@@ -193,12 +347,14 @@ impl SyncManager {
         // client can only be this behind server if it wrapped around... if that's the case, we need to update
         // the generation to compute the time correctly
         // SAFETY: we only call this when we are synced, so we know that the latest_received_server_tick is not None
-        // NOTE: we only call this when we are synced, so we know that the client tick is ahead of the server tick
-        let generation = if (client_tick_raw - self.latest_received_server_tick.unwrap().0 as i32)
-            < (i16::MIN as i32)
-        {
+        let raw_diff = client_tick_raw - self.latest_received_server_tick.unwrap().0 as i32;
+        let generation = if raw_diff < (i16::MIN as i32) {
             debug!("client tick is one generation ahead of server tick");
             self.server_latest_tick_generation() + 1
+        } else if raw_diff > (i16::MAX as i32) {
+            // the server tick wrapped around but the client tick didn't (the client fell behind the server)
+            debug!("client tick is one generation behind server tick");
+            self.server_latest_tick_generation().saturating_sub(1)
         } else {
             self.server_latest_tick_generation()
         };
@@ -224,11 +380,23 @@ impl SyncManager {
         self.server_time_estimate
     }
 
+    /// Pin the offset between the prediction time and the server time estimate, or go back to the
+    /// computed offset with None
+    pub(crate) fn set_manual_offset(&mut self, offset: Option<ChronoDuration>) {
+        self.manual_offset = offset;
+    }
+
     fn server_latest_tick_generation(&self) -> u16 {
         // check if the latest_server_tick has crossed a generation compared to the latest pong tick
-        if self.latest_received_server_tick.unwrap().0 < self.server_pong_tick.0 {
+        // (the comparison between ticks is wrapping-aware, the comparison between the raw ticks is not)
+        let latest = self.latest_received_server_tick.unwrap();
+        let pong = self.server_pong_tick;
+        if latest.0 < pong.0 && latest > pong {
             debug!("latest server tick is a generation compared to the server pong tick");
             self.server_pong_generation + 1
+        } else if latest.0 > pong.0 && latest < pong {
+            debug!("server pong tick is a generation compared to the latest server tick");
+            self.server_pong_generation.saturating_sub(1)
         } else {
             self.server_pong_generation
         }
@@ -244,7 +412,8 @@ impl SyncManager {
             self.latest_received_server_tick.unwrap(),
             self.server_latest_tick_generation(),
             tick_duration,
-        ) + self.duration_since_latest_received_server_tick;
+        ) + self.duration_since_latest_received_server_tick
+            + self.time_sync_correction;
 
         // instead of just using the latest_received_server_tick, we apply some smoothing
         // (in case the latest server tick is wildly off-base)
@@ -267,6 +436,45 @@ impl SyncManager {
         );
     }
 
+    /// Correct the server time estimate using a [`TimeSync`] stamp sent by the server.
+    ///
+    /// The estimate computed in [`Self::update_server_time_estimate`] assumes that the packet was sent
+    /// at the start of the server tick contained in the packet header, plus the correction from the previous stamp;
+    /// the stamp tells us how far into that tick the server actually was.
+    pub(crate) fn apply_time_sync(&mut self, time_sync: &TimeSync, tick_duration: Duration) {
+        // the stamp can only correct the estimate computed from the same packet, i.e. from a packet
+        // received this frame with the same tick
+        if self.latest_received_server_tick != Some(time_sync.tick)
+            || self.duration_since_latest_received_server_tick != Duration::default()
+        {
+            trace!(?time_sync, "ignoring outdated time sync stamp");
+            return;
+        }
+        // the overstep is different for each packet, so we keep a moving average of the stamps
+        // to correct the packets that don't contain a stamp
+        let correction = self.time_sync_correction.mul_f32(TIME_SYNC_SMOOTHING)
+            + tick_duration.mul_f32(time_sync.overstep * (1.0 - TIME_SYNC_SMOOTHING));
+        // apply the same smoothing as in `update_server_time_estimate` (which is linear in the new estimate)
+        let weight = if self.is_synced() {
+            1.0 - self.config.server_time_estimate_smoothing
+        } else {
+            1.0
+        };
+        // the estimate already contains the previous correction, so we only apply the difference
+        if correction >= self.time_sync_correction {
+            self.server_time_estimate += (correction - self.time_sync_correction).mul_f32(weight);
+        } else {
+            self.server_time_estimate -= (self.time_sync_correction - correction).mul_f32(weight);
+        }
+        self.time_sync_correction = correction;
+        trace!(
+            ?time_sync,
+            correction = ?self.time_sync_correction,
+            updated_server_time_estimate = ?self.server_time_estimate,
+            "applied time sync stamp"
+        );
+    }
+
     /// time (from server's scale) at which the server would receive a packet we send now
     fn predicted_server_receive_time(&self, rtt: Duration) -> WrappedTime {
         self.server_time_estimate() + rtt
@@ -289,10 +497,11 @@ impl SyncManager {
         let input_delay = tick_duration * input_delay_ticks as u32;
         ChronoDuration::nanoseconds(
             jitter.as_nanos() as i64 * self.config.jitter_multiple_margin as i64
-                // TODO: this should actually be `n * client_input_send_interval`
-                //  in our case we send input messages in FixedUpdate, so roughly every tick_duration
-                //  so this should be fine
                 + tick_duration.as_nanos() as i64 * self.config.tick_margin as i64
+                // the input for a tick can be buffered for up to `input_send_interval` before being sent
+                + self.input_send_interval.as_nanos() as i64
+                // inputs should wait in the server's jitter buffer before their tick executes
+                + tick_duration.as_nanos() as i64 * self.input_jitter_buffer_ticks as i64
                 - input_delay.as_nanos() as i64,
         )
     }
@@ -306,6 +515,9 @@ impl SyncManager {
         jitter: Duration,
         input_delay_ticks: u16,
     ) -> WrappedTime {
+        if let Some(offset) = self.manual_offset {
+            return self.server_time_estimate() + offset;
+        }
         let ideal_time = self.predicted_server_receive_time(rtt)
             + self.client_ahead_minimum(tick_duration, jitter, input_delay_ticks);
 
@@ -323,14 +535,7 @@ impl SyncManager {
         )
     }
 
-    pub(crate) fn interpolation_objective(
-        &self,
-        // TODO: make interpolation delay part of SyncConfig?
-        interpolation_delay: &InterpolationDelay,
-        // TODO: should we get this via an estimate?
-        server_send_interval: Duration,
-        tick_manager: &TickManager,
-    ) -> WrappedTime {
+    pub(crate) fn interpolation_objective(&self, tick_manager: &TickManager) -> WrappedTime {
         // // TODO: maybe integrate because of jitter?
         // let objective_time = WrappedTime::from_duration(
         //     self.latest_received_server_tick.0 as u32 * tick_manager.config.tick_duration
@@ -339,9 +544,7 @@ impl SyncManager {
         // let objective_time = self.server_time_estimate();
         // how much we want interpolation time to be behind the latest received server tick?
         // TODO: use a specified config margin + add std of time_between_server_updates?
-        let objective_delta =
-            chrono::Duration::from_std(interpolation_delay.to_duration(server_send_interval))
-                .unwrap();
+        let objective_delta = chrono::Duration::from_std(self.interpolation_delay).unwrap();
         // info!("objective_delta: {:?}", objective_delta);
         self.server_time_estimate() - objective_delta
     }
@@ -358,18 +561,10 @@ impl SyncManager {
 
     // TODO: only run when there's a change? (new server tick received or new ping received)
     // TODO: change name to make it clear that we might modify speed
-    pub(crate) fn update_interpolation_time(
-        &mut self,
-        // TODO: make interpolation delay part of SyncConfig?
-        interpolation_delay: &InterpolationDelay,
-        // TODO: should we get this via an estimate?
-        server_update_rate: Duration,
-        tick_manager: &TickManager,
-    ) {
+    pub(crate) fn update_interpolation_time(&mut self, tick_manager: &TickManager) {
         // for interpolation time, we don't need to use ticks (because we only need interpolation at the end
         // of the frame, not during the FixedUpdate schedule)
-        let objective_time =
-            self.interpolation_objective(interpolation_delay, server_update_rate, tick_manager);
+        let objective_time = self.interpolation_objective(tick_manager);
         let delta = objective_time - self.interpolation_time;
         trace!(
             ?objective_time,
@@ -423,9 +618,9 @@ impl SyncManager {
         let current_prediction_time = self.current_prediction_time(tick_manager, time_manager);
 
         // client ideal time
-        let input_delay_ticks = self
-            .prediction_config
-            .input_delay_ticks(rtt, tick_manager.config.tick_duration);
+        let input_delay_ticks =
+            self.compute_input_delay_ticks(rtt, tick_manager.config.tick_duration);
+        self.input_delay_ticks = input_delay_ticks;
         let client_ideal_time = self.client_ideal_time(
             rtt,
             tick_manager.config.tick_duration,
@@ -518,9 +713,9 @@ impl SyncManager {
         self.update_server_time_estimate(tick_duration, rtt);
 
         // Compute how many ticks the client must be compared to server
-        let input_delay_ticks = self
-            .prediction_config
-            .input_delay_ticks(rtt, tick_manager.config.tick_duration);
+        let input_delay_ticks =
+            self.compute_input_delay_ticks(rtt, tick_manager.config.tick_duration);
+        self.input_delay_ticks = input_delay_ticks;
         let client_ideal_time =
             self.client_ideal_time(rtt, tick_duration, jitter, input_delay_ticks);
 
@@ -569,6 +764,8 @@ mod tests {
     use crate::tests::stepper::BevyStepper;
 
     use super::*;
+    use crate::client::interpolation::plugin::AdaptiveInterpolationDelay;
+    use crate::shared::ping::manager::PingConfig;
 
     fn press_input(
         mut input_manager: ResMut<InputManager<MyInput>>,
@@ -653,4 +850,407 @@ mod tests {
             &ComponentSyncModeFull(1.0)
         );
     }
+
+    /// Check that the connection keeps working when the ticks wrap around
+    #[test]
+    fn test_connection_across_tick_wrap() {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            client::ClientConfig::default(),
+            tick_duration,
+        );
+
+        // the server is close to the end of the tick range when the client connects
+        let new_tick = Tick(u16::MAX - 100);
+        let new_time = WrappedTime::from_duration(tick_duration * (new_tick.0 as u32));
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<TimeManager>()
+            .set_current_time(new_time);
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<TickManager>()
+            .set_tick_to(new_tick);
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((ComponentSyncModeFull(0.0), Replicate::default()))
+            .id();
+        stepper.init();
+
+        // cross the wrap boundary
+        let start_tick = stepper.server_tick();
+        for _ in 0..200 {
+            stepper.frame_step();
+            let connection = stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>();
+            assert!(connection.is_synced());
+            // the client stays ahead of the server
+            assert!(stepper.client_tick() > stepper.server_tick());
+        }
+        assert!(stepper.server_tick().0 < start_tick.0);
+        assert!(stepper.server_tick() > start_tick);
+
+        // replication still works after the wrap
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(server_entity)
+            .insert(ComponentSyncModeFull(1.0));
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .unwrap();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(client_entity)
+                .unwrap(),
+            &ComponentSyncModeFull(1.0)
+        );
+    }
+
+    /// Check that the generation of the server tick is computed correctly around the wrap boundary
+    #[test]
+    fn test_server_tick_generation_wrap() {
+        let mut sync_manager = SyncManager::new(SyncConfig::default(), PredictionConfig::default());
+        sync_manager.server_pong_generation = 3;
+
+        // the latest tick wrapped around after the pong
+        sync_manager.server_pong_tick = Tick(u16::MAX - 2);
+        sync_manager.latest_received_server_tick = Some(Tick(2));
+        assert_eq!(sync_manager.server_latest_tick_generation(), 4);
+
+        // the latest tick is slightly older than the pong, but in the same generation
+        sync_manager.server_pong_tick = Tick(10);
+        sync_manager.latest_received_server_tick = Some(Tick(8));
+        assert_eq!(sync_manager.server_latest_tick_generation(), 3);
+
+        // the pong wrapped around, but not the latest tick
+        sync_manager.server_pong_tick = Tick(1);
+        sync_manager.latest_received_server_tick = Some(Tick(u16::MAX - 1));
+        assert_eq!(sync_manager.server_latest_tick_generation(), 2);
+    }
+
+    /// Error (in seconds) between the server time estimate of the client and the server time,
+    /// for each frame after the client is synced
+    fn server_time_estimate_errors(time_sync_interval: Option<Duration>) -> Vec<f32> {
+        let tick_duration = Duration::from_millis(10);
+        // frames are not aligned with ticks, so the server sends packets in the middle of a tick
+        let frame_duration = Duration::from_millis(15);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            client::ClientConfig::default(),
+            frame_duration,
+        );
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<server::ServerConfig>()
+            .ping
+            .time_sync_interval = time_sync_interval;
+        stepper.init();
+
+        let errors = (0..100)
+            .map(|_| {
+                stepper.frame_step();
+                let estimate = stepper
+                    .client_app
+                    .world()
+                    .resource::<client::ConnectionManager>()
+                    .sync_manager
+                    .server_time_estimate();
+                let server_time = stepper
+                    .server_app
+                    .world()
+                    .resource::<TimeManager>()
+                    .current_time();
+                (estimate - server_time).num_microseconds().unwrap() as f32 / 1_000_000.0
+            })
+            .collect();
+        let world = stepper.client_app.world();
+        assert!(world
+            .resource::<client::ConnectionManager>()
+            .server_time_offset(
+                world.resource::<TickManager>(),
+                world.resource::<TimeManager>()
+            )
+            .is_some());
+        errors
+    }
+
+    /// Check that the client corrects its server time estimate using the time sync
+    /// stamps sent by the server
+    #[test]
+    fn test_time_sync_correction() {
+        let errors = server_time_estimate_errors(Some(Duration::from_millis(100)));
+        let errors_without_sync = server_time_estimate_errors(None);
+        let mean = |errors: &[f32]| errors.iter().sum::<f32>() / errors.len() as f32;
+        let max = |errors: &[f32]| errors.iter().fold(0.0f32, |m, e| m.max(e.abs()));
+        // without the stamps, the estimate assumes that the packets are sent at the start of their tick,
+        // so it lags behind the server time
+        assert!(mean(&errors_without_sync[50..]) < -0.002);
+        // with the stamps, the estimate converges to the server time, and stays there (the correction
+        // is kept between two stamps)
+        assert!(mean(&errors[50..]).abs() < 0.001);
+        assert!(mean(&errors[75..]).abs() < 0.001);
+        assert!(max(&errors[50..]) < max(&errors_without_sync[50..]));
+    }
+
+    #[test]
+    fn test_tick_offset() {
+        use bevy::diagnostic::DiagnosticsStore;
+
+        use crate::client::diagnostics::ClientDiagnosticsPlugin;
+
+        let mut stepper = BevyStepper::default();
+        for _ in 0..50 {
+            stepper.frame_step();
+        }
+        let world = stepper.client_app.world();
+        let tick_offset = world
+            .resource::<client::ConnectionManager>()
+            .tick_offset(world.resource::<TickManager>())
+            .unwrap();
+        // the client runs ahead of the server
+        assert!(tick_offset > 0);
+        // the offset is published as a diagnostic
+        assert!(world
+            .resource::<DiagnosticsStore>()
+            .get(&ClientDiagnosticsPlugin::TICK_OFFSET)
+            .and_then(|diagnostic| diagnostic.value())
+            .is_some_and(|value| value > 0.0));
+    }
+
+    #[test]
+    fn test_manual_offset() {
+        let mut stepper = BevyStepper::default();
+        for _ in 0..50 {
+            stepper.frame_step();
+        }
+        let tick_duration = stepper
+            .client_app
+            .world()
+            .resource::<TickManager>()
+            .config
+            .tick_duration;
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .server_time()
+            .is_some());
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<client::ConnectionManager>()
+            .set_manual_offset(Some(
+                chrono::Duration::from_std(tick_duration * 20).unwrap(),
+            ));
+        for _ in 0..50 {
+            stepper.frame_step();
+        }
+        let world = stepper.client_app.world();
+        let tick_offset = world
+            .resource::<client::ConnectionManager>()
+            .tick_offset(world.resource::<TickManager>())
+            .unwrap();
+        assert!((19..=21).contains(&tick_offset), "{tick_offset}");
+    }
+
+    #[test]
+    fn test_interpolation_delay_ticks() {
+        let send_interval = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(15);
+        let interpolation_delay = InterpolationDelay::default().with_min_delay_ticks(3);
+        assert_eq!(
+            interpolation_delay.to_duration(send_interval, tick_duration),
+            Duration::from_millis(45)
+        );
+        // the delay is the max of the minimum delays and the send interval ratio
+        let interpolation_delay = interpolation_delay.with_min_delay(Duration::from_millis(60));
+        assert_eq!(
+            interpolation_delay.to_duration(send_interval, tick_duration),
+            Duration::from_millis(60)
+        );
+        let interpolation_delay = interpolation_delay.with_send_interval_ratio(8.0);
+        assert!(
+            interpolation_delay
+                .to_duration(send_interval, tick_duration)
+                .abs_diff(Duration::from_millis(80))
+                < Duration::from_millis(1)
+        );
+    }
+
+    #[test]
+    fn test_adaptive_interpolation_delay() {
+        let send_interval = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let mut sync_manager = SyncManager::new(SyncConfig::default(), PredictionConfig::default());
+        let mut ping_manager = PingManager::new(PingConfig::default());
+        ping_manager.final_stats.jitter = Duration::from_millis(20);
+
+        // without the adaptive mode, the delay only depends on the send interval
+        let interpolation_delay = InterpolationDelay::default();
+        sync_manager.update_interpolation_delay(
+            &interpolation_delay,
+            send_interval,
+            tick_duration,
+            &ping_manager,
+        );
+        assert_eq!(sync_manager.interpolation_delay, Duration::from_millis(20));
+
+        // target is 2 * send_interval + 3 * jitter = 80ms; the delay moves gradually towards it
+        let interpolation_delay =
+            InterpolationDelay::default().with_adaptive(AdaptiveInterpolationDelay {
+                jitter_multiple: 3.0,
+                min_delay: Duration::default(),
+                max_delay: Duration::from_millis(100),
+                smoothing: 0.5,
+            });
+        sync_manager.update_interpolation_delay(
+            &interpolation_delay,
+            send_interval,
+            tick_duration,
+            &ping_manager,
+        );
+        assert!(sync_manager.interpolation_delay > Duration::from_millis(45));
+        assert!(sync_manager.interpolation_delay < Duration::from_millis(55));
+        for _ in 0..20 {
+            sync_manager.update_interpolation_delay(
+                &interpolation_delay,
+                send_interval,
+                tick_duration,
+                &ping_manager,
+            );
+        }
+        assert!(
+            sync_manager
+                .interpolation_delay
+                .abs_diff(Duration::from_millis(80))
+                < Duration::from_millis(1)
+        );
+
+        // the delay cannot go above the max delay
+        ping_manager.final_stats.jitter = Duration::from_millis(50);
+        for _ in 0..20 {
+            sync_manager.update_interpolation_delay(
+                &interpolation_delay,
+                send_interval,
+                tick_duration,
+                &ping_manager,
+            );
+        }
+        assert!(
+            sync_manager
+                .interpolation_delay
+                .abs_diff(Duration::from_millis(100))
+                < Duration::from_millis(1)
+        );
+    }
+
+    /// Check that the ReadyEvent is emitted once the warmup that follows the handshake is over
+    #[test]
+    fn test_sync_warmup() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..default()
+        };
+        let client_config = client::ClientConfig {
+            sync: SyncConfig::default().with_warmup(Duration::from_millis(200), true),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, frame_duration);
+        stepper.init();
+
+        let ready_events = |stepper: &mut BevyStepper| {
+            stepper
+                .client_app
+                .world_mut()
+                .resource_mut::<Events<client::ReadyEvent>>()
+                .drain()
+                .count()
+        };
+        let connection = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>();
+        assert!(connection.is_synced());
+        assert!(!connection.is_ready());
+        assert_eq!(ready_events(&mut stepper), 0);
+
+        let mut events = 0;
+        for _ in 0..25 {
+            stepper.frame_step();
+            events += ready_events(&mut stepper);
+        }
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .is_ready());
+        assert_eq!(events, 1);
+    }
+
+    /// Check that the warmup waits until enough pongs have been received from the server
+    #[test]
+    fn test_sync_warmup_pings() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..default()
+        };
+        let client_config = client::ClientConfig {
+            sync: SyncConfig::default().with_warmup_pings(20),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, frame_duration);
+        stepper.init();
+        let start_pongs = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .ping_manager
+            .pongs_recv;
+
+        // the client pings the server every frame
+        for _ in 0..25 {
+            let connection = stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>();
+            let pongs = connection.ping_manager.pongs_recv - start_pongs;
+            if pongs < 20 {
+                assert!(!connection.is_ready());
+            }
+            stepper.frame_step();
+        }
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .is_ready());
+    }
 }
@@ -61,6 +61,9 @@ pub trait NetClient: Send + Sync {
     fn id(&self) -> ClientId;
 
     /// Get the local address of the client
+    ///
+    /// The io is built when the client starts connecting: after that, this is the address the io is actually
+    /// bound to (for example the ephemeral port chosen by the OS if the transport binds to the port 0).
     fn local_addr(&self) -> SocketAddr;
 
     /// Get immutable access to the inner io
@@ -91,6 +94,12 @@ pub struct ClientConnection {
 pub enum DisconnectReason {
     Transport(crate::transport::error::Error),
     Netcode(super::netcode::ClientState),
+    /// We did not receive any packet from the server for too long
+    Timeout,
+    /// The server disconnected us, and sent the reason for the disconnection
+    Server(super::server::DisconnectReason),
+    /// The server denied our connection request, and sent the reason for the denial
+    Denied(super::server::DeniedReason),
     #[cfg(all(feature = "steam", not(target_family = "wasm")))]
     Steam(steamworks::networking_types::NetConnectionEnd),
 }
@@ -333,3 +342,17 @@ pub enum ConnectionError {
     #[cfg(all(feature = "steam", not(target_family = "wasm")))]
     SteamError(#[from] steamworks::SteamError),
 }
+
+impl ConnectionError {
+    /// Returns true if the error is temporary (for example the bandwidth limit was exceeded),
+    /// so that the connection can keep being used
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            ConnectionError::Transport(e)
+            | ConnectionError::Netcode(super::netcode::error::Error::Transport(e)) => {
+                e.is_recoverable()
+            }
+            _ => false,
+        }
+    }
+}
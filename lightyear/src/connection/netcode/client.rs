@@ -8,6 +8,7 @@ use crate::connection::client::{
     ConnectionError, ConnectionState, DisconnectReason, IoConfig, NetClient,
 };
 use crate::connection::id;
+use crate::connection::server::{DeniedReason, DisconnectReason as ServerDisconnectReason};
 use crate::packet::packet_builder::RecvPayload;
 use crate::transport::io::IoState;
 use crate::transport::{PacketReceiver, PacketSender, LOCAL_SOCKET};
@@ -21,7 +22,8 @@ use super::{
     },
     replay::ReplayProtection,
     token::{ChallengeToken, ConnectToken},
-    utils, ClientId, MAX_PACKET_SIZE, MAX_PKT_BUF_SIZE, PACKET_SEND_RATE_SEC,
+    utils, ClientId, MAX_CONNECT_PAYLOAD_BYTES, MAX_PAYLOAD_SIZE, MAX_PKT_BUF_SIZE,
+    PACKET_SEND_RATE_SEC,
 };
 
 type Callback<Ctx> = Box<dyn FnMut(ClientState, ClientState, &mut Ctx) + Send + Sync + 'static>;
@@ -31,6 +33,7 @@ type Callback<Ctx> = Box<dyn FnMut(ClientState, ClientState, &mut Ctx) + Send +
 /// * `num_disconnect_packets` - The number of redundant disconnect packets that will be sent to a server when the clients wants to disconnect.
 /// * `packet_send_rate` - The rate at which periodic packets will be sent to the server.
 /// * `on_state_change` - A callback that will be called when the client changes states.
+/// * `connect_payload` - Application data that will be sent to the server when connecting.
 ///
 /// # Example
 /// ```
@@ -58,6 +61,7 @@ pub struct ClientConfig<Ctx> {
     packet_send_rate: f64,
     context: Ctx,
     on_state_change: Option<Callback<Ctx>>,
+    connect_payload: Vec<u8>,
 }
 
 impl Default for ClientConfig<()> {
@@ -67,6 +71,7 @@ impl Default for ClientConfig<()> {
             packet_send_rate: PACKET_SEND_RATE_SEC,
             context: (),
             on_state_change: None,
+            connect_payload: Vec::new(),
         }
     }
 }
@@ -83,6 +88,7 @@ impl<Ctx> ClientConfig<Ctx> {
             packet_send_rate: PACKET_SEND_RATE_SEC,
             context: ctx,
             on_state_change: None,
+            connect_payload: Vec::new(),
         }
     }
     /// Set the number of redundant disconnect packets that will be sent to a server when the clients wants to disconnect.
@@ -97,6 +103,14 @@ impl<Ctx> ClientConfig<Ctx> {
         self.packet_send_rate = rate_seconds;
         self
     }
+    /// Set the application data (for example the name chosen by the player) that will be sent to the server
+    /// in the connection response packet. The server can read it as soon as the client is connected.
+    ///
+    /// The payload must not be larger than [`MAX_CONNECT_PAYLOAD_BYTES`].
+    pub fn connect_payload(mut self, connect_payload: Vec<u8>) -> Self {
+        self.connect_payload = connect_payload;
+        self
+    }
     /// Set a callback that will be called when the client changes states.
     pub fn on_state_change<F>(mut self, cb: F) -> Self
     where
@@ -192,6 +206,8 @@ pub struct NetcodeClient<Ctx = ()> {
     replay_protection: ReplayProtection,
     should_disconnect: bool,
     should_disconnect_state: ClientState,
+    server_disconnect_reason: Option<ServerDisconnectReason>,
+    denied_reason: Option<DeniedReason>,
     packet_queue: VecDeque<RecvPayload>,
     buffer_pool: Pool<Vec<u8>>,
     cfg: ClientConfig<Ctx>,
@@ -199,6 +215,12 @@ pub struct NetcodeClient<Ctx = ()> {
 
 impl<Ctx> NetcodeClient<Ctx> {
     fn from_token(token_bytes: &[u8], cfg: ClientConfig<Ctx>) -> Result<Self> {
+        if cfg.connect_payload.len() > MAX_CONNECT_PAYLOAD_BYTES {
+            return Err(Error::ConnectPayloadTooLarge(
+                cfg.connect_payload.len(),
+                MAX_CONNECT_PAYLOAD_BYTES,
+            ));
+        }
         if token_bytes.len() != ConnectToken::SIZE {
             return Err(Error::SizeMismatch(ConnectToken::SIZE, token_bytes.len()));
         }
@@ -227,6 +249,8 @@ impl<Ctx> NetcodeClient<Ctx> {
             replay_protection: ReplayProtection::new(),
             should_disconnect: false,
             should_disconnect_state: ClientState::Disconnected,
+            server_disconnect_reason: None,
+            denied_reason: None,
             packet_queue: VecDeque::new(),
             buffer_pool: Pool::new(10, || vec![0u8; MAX_PKT_BUF_SIZE]),
             cfg,
@@ -332,7 +356,11 @@ impl<Ctx> NetcodeClient<Ctx> {
             }
             ClientState::SendingChallengeResponse => {
                 debug!("client sending connection response packet to server");
-                ResponsePacket::create(self.challenge_token_sequence, self.challenge_token_data)
+                ResponsePacket::create(
+                    self.challenge_token_sequence,
+                    self.challenge_token_data,
+                    self.cfg.connect_payload.clone(),
+                )
             }
             ClientState::Connected => {
                 trace!("client sending connection keep-alive packet to server");
@@ -382,6 +410,7 @@ impl<Ctx> NetcodeClient<Ctx> {
                     "client connection denied by server. Reason: {:?}",
                     pkt.reason
                 );
+                self.denied_reason = Some(pkt.reason);
                 self.should_disconnect = true;
                 self.should_disconnect_state = ClientState::ConnectionDenied;
             }
@@ -423,8 +452,12 @@ impl<Ctx> NetcodeClient<Ctx> {
                 // TODO: control the size/memory of the packet queue?
                 self.packet_queue.push_back(buf);
             }
-            (Packet::Disconnect(_), ClientState::Connected) => {
-                debug!("client received disconnect packet from server");
+            (Packet::Disconnect(pkt), ClientState::Connected) => {
+                debug!(
+                    "client received disconnect packet from server: {:?}",
+                    pkt.reason
+                );
+                self.server_disconnect_reason = Some(pkt.reason);
                 self.should_disconnect = true;
                 self.should_disconnect_state = ClientState::Disconnected;
             }
@@ -523,6 +556,8 @@ impl<Ctx> NetcodeClient<Ctx> {
     /// This function does not perform any IO, it only readies the client to send/receive packets on the next call to [`update`](NetcodeClient::update). <br>
     pub fn connect(&mut self) {
         self.reset_connection();
+        self.server_disconnect_reason = None;
+        self.denied_reason = None;
         self.set_state(ClientState::SendingConnectionRequest);
         info!(
             "client connecting to server {} [{}/{}]",
@@ -598,14 +633,14 @@ impl<Ctx> NetcodeClient<Ctx> {
 
     /// Sends a packet to the server.
     ///
-    /// The provided buffer must be smaller than [`MAX_PACKET_SIZE`].
+    /// The provided buffer must be smaller than [`MAX_PAYLOAD_SIZE`].
     pub fn send(&mut self, buf: &[u8], io: &mut Io) -> Result<()> {
         if self.state != ClientState::Connected {
             trace!("tried to send but not connected");
             return Ok(());
         }
-        if buf.len() > MAX_PACKET_SIZE {
-            return Err(Error::SizeMismatch(MAX_PACKET_SIZE, buf.len()));
+        if buf.len() > MAX_PAYLOAD_SIZE {
+            return Err(Error::SizeMismatch(MAX_PAYLOAD_SIZE, buf.len()));
         }
         self.send_packet(PayloadPacket::create(buf), io)?;
         Ok(())
@@ -620,7 +655,10 @@ impl<Ctx> NetcodeClient<Ctx> {
         );
         if io.state == IoState::Connected {
             for _ in 0..self.cfg.num_disconnect_packets {
-                self.send_packet(DisconnectPacket::create(), io)?;
+                self.send_packet(
+                    DisconnectPacket::create(ServerDisconnectReason::ClientRequested),
+                    io,
+                )?;
             }
         }
         self.reset(ClientState::Disconnected);
@@ -631,6 +669,14 @@ impl<Ctx> NetcodeClient<Ctx> {
     pub fn state(&self) -> ClientState {
         self.state
     }
+    /// Returns the reason sent by the server when it disconnected the client, if any.
+    pub fn server_disconnect_reason(&self) -> Option<ServerDisconnectReason> {
+        self.server_disconnect_reason
+    }
+    /// Returns the reason sent by the server when it denied the connection request, if any.
+    pub fn denied_reason(&self) -> Option<&DeniedReason> {
+        self.denied_reason.as_ref()
+    }
     /// Returns true if the client is in an error state.
     pub fn is_error(&self) -> bool {
         self.state < ClientState::Disconnected
@@ -690,8 +736,20 @@ pub(crate) mod connection {
                     ConnectionState::Connecting
                 }
                 ClientState::Connected => ConnectionState::Connected,
+                ClientState::ConnectionTimedOut => ConnectionState::Disconnected {
+                    reason: Some(DisconnectReason::Timeout),
+                },
+                ClientState::ConnectionDenied => ConnectionState::Disconnected {
+                    reason: Some(self.client.denied_reason().cloned().map_or(
+                        DisconnectReason::Netcode(self.client.state),
+                        DisconnectReason::Denied,
+                    )),
+                },
                 _ => ConnectionState::Disconnected {
-                    reason: Some(DisconnectReason::Netcode(self.client.state)),
+                    reason: Some(self.client.server_disconnect_reason().map_or(
+                        DisconnectReason::Netcode(self.client.state),
+                        DisconnectReason::Server,
+                    )),
                 },
             }
         }
@@ -15,6 +15,8 @@ pub enum Error {
     ClientNotConnected,
     #[error("clock went backwards (did you invent a time machine?): {0}")]
     SystemTime(#[from] std::time::SystemTimeError),
+    #[error("connect payload of {0} bytes is larger than the maximum of {1} bytes")]
+    ConnectPayloadTooLarge(usize, usize),
     #[error("invalid connect token: {0}")]
     InvalidToken(super::token::InvalidTokenError),
     #[error(transparent)]
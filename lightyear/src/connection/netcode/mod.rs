@@ -124,7 +124,9 @@ loop {
 pub use client::{connection::Client, ClientConfig, ClientState, NetcodeClient};
 pub use crypto::{generate_key, try_generate_key, Key};
 pub use error::{Error, Result};
-pub use server::{connection::Server, Callback, ClientId, NetcodeServer, ServerConfig};
+pub use server::{
+    connection::Server, Callback, ClientId, NetcodeServer, ServerConfig, MAX_CLIENTS,
+};
 pub use token::{ConnectToken, ConnectTokenBuilder, InvalidTokenError};
 
 mod bytes;
@@ -138,7 +140,7 @@ mod token;
 mod utils;
 
 pub(crate) const MAC_BYTES: usize = 16;
-pub(crate) const MAX_PKT_BUF_SIZE: usize = 1300;
+pub(crate) const MAX_PKT_BUF_SIZE: usize = 1472;
 pub(crate) const CONNECTION_TIMEOUT_SEC: i32 = 15;
 pub(crate) const PACKET_SEND_RATE_SEC: f64 = 1.0 / 10.0;
 
@@ -146,9 +148,22 @@ pub(crate) const PACKET_SEND_RATE_SEC: f64 = 1.0 / 10.0;
 pub const PRIVATE_KEY_BYTES: usize = 32;
 /// The size of the user data in a connect token in bytes.
 pub const USER_DATA_BYTES: usize = 256;
+/// The maximum size of the connect payload that a client can send to the server in bytes.
+pub const MAX_CONNECT_PAYLOAD_BYTES: usize = 256;
 /// The size of the connect token in bytes.
 pub const CONNECT_TOKEN_BYTES: usize = 2048;
-/// The maximum size of a packet in bytes.
+/// The default maximum size of a packet in bytes.
 pub const MAX_PACKET_SIZE: usize = 1200;
+/// The largest payload that can be sent in a single packet, once the netcode overhead
+/// (prefix byte, sequence number and MAC) is accounted for.
+///
+/// Connections only send packets larger than [`MAX_PACKET_SIZE`] if MTU discovery is enabled.
+pub const MAX_PAYLOAD_SIZE: usize = MAX_PKT_BUF_SIZE - 1 - 8 - MAC_BYTES;
 /// The version of the netcode protocol implemented by this crate.
-pub const NETCODE_VERSION: &[u8; 13] = b"NETCODE 1.02\0";
+///
+/// It differs from the reference netcode 1.02 protocol:
+/// - the disconnect packets contain the [`DisconnectReason`](crate::connection::server::DisconnectReason) of the disconnection
+/// - the connection response packets contain the connect payload of the client (at most [`MAX_CONNECT_PAYLOAD_BYTES`] bytes)
+///
+/// Peers that use a different version cannot connect to each other.
+pub const NETCODE_VERSION: &[u8; 13] = b"NETCODE 1.03\0";
@@ -8,7 +8,7 @@ use chacha20poly1305::XNonce;
 use tracing::debug;
 
 use crate::connection::netcode::ClientId;
-use crate::connection::server::DeniedReason;
+use crate::connection::server::{DeniedReason, DisconnectReason};
 
 use super::{
     bytes::Bytes,
@@ -16,7 +16,7 @@ use super::{
     error::Error as NetcodeError,
     replay::ReplayProtection,
     token::{ChallengeToken, ConnectTokenPrivate},
-    MAC_BYTES, MAX_PKT_BUF_SIZE, NETCODE_VERSION,
+    MAC_BYTES, MAX_CONNECT_PAYLOAD_BYTES, MAX_PKT_BUF_SIZE, NETCODE_VERSION,
 };
 
 #[derive(thiserror::Error, Debug)]
@@ -290,13 +290,20 @@ impl Bytes for ChallengePacket {
 pub struct ResponsePacket {
     pub sequence: u64,
     pub token: [u8; ChallengeToken::SIZE],
+    /// Application data provided by the client, that the server will receive on connection
+    pub connect_payload: Vec<u8>,
 }
 
 impl ResponsePacket {
-    pub fn create(sequence: u64, token_bytes: [u8; ChallengeToken::SIZE]) -> Packet<'static> {
+    pub fn create(
+        sequence: u64,
+        token_bytes: [u8; ChallengeToken::SIZE],
+        connect_payload: Vec<u8>,
+    ) -> Packet<'static> {
         Packet::Response(ResponsePacket {
             sequence,
             token: token_bytes,
+            connect_payload,
         })
     }
 }
@@ -306,6 +313,14 @@ impl Bytes for ResponsePacket {
     fn write_to(&self, writer: &mut impl WriteBytesExt) -> Result<(), Self::Error> {
         writer.write_u64::<LittleEndian>(self.sequence)?;
         writer.write_all(&self.token)?;
+        if self.connect_payload.len() > MAX_CONNECT_PAYLOAD_BYTES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "connect payload is too large",
+            ));
+        }
+        writer.write_u16::<LittleEndian>(self.connect_payload.len() as u16)?;
+        writer.write_all(&self.connect_payload)?;
         Ok(())
     }
 
@@ -313,7 +328,20 @@ impl Bytes for ResponsePacket {
         let sequence = reader.read_u64::<LittleEndian>()?;
         let mut token = [0; ChallengeToken::SIZE];
         reader.read_exact(&mut token)?;
-        Ok(Self { sequence, token })
+        let payload_len = reader.read_u16::<LittleEndian>()? as usize;
+        if payload_len > MAX_CONNECT_PAYLOAD_BYTES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "connect payload is too large",
+            ));
+        }
+        let mut connect_payload = vec![0; payload_len];
+        reader.read_exact(&mut connect_payload)?;
+        Ok(Self {
+            sequence,
+            token,
+            connect_payload,
+        })
     }
 }
 
@@ -350,22 +378,81 @@ impl PayloadPacket<'_> {
     }
 }
 
-pub struct DisconnectPacket {}
+pub struct DisconnectPacket {
+    pub reason: DisconnectReason,
+}
 
 impl DisconnectPacket {
-    pub fn create() -> Packet<'static> {
-        Packet::Disconnect(Self {})
+    pub fn create(reason: DisconnectReason) -> Packet<'static> {
+        Packet::Disconnect(Self { reason })
+    }
+}
+
+impl Bytes for DisconnectReason {
+    type Error = io::Error;
+
+    fn write_to(&self, writer: &mut impl WriteBytesExt) -> Result<(), Self::Error> {
+        match self {
+            DisconnectReason::ClientRequested => {
+                writer.write_u8(0)?;
+            }
+            DisconnectReason::Timeout => {
+                writer.write_u8(1)?;
+            }
+            DisconnectReason::ServerKicked(code) => {
+                writer.write_u8(2)?;
+                writer.write_u16::<LittleEndian>(*code)?;
+            }
+            DisconnectReason::TransportError => {
+                writer.write_u8(3)?;
+            }
+            DisconnectReason::ProtocolMismatch => {
+                writer.write_u8(4)?;
+            }
+            DisconnectReason::RateLimitExceeded => {
+                writer.write_u8(5)?;
+            }
+            DisconnectReason::Reconnected => {
+                writer.write_u8(6)?;
+            }
+            DisconnectReason::ServerStopped => {
+                writer.write_u8(7)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_from(reader: &mut impl ReadBytesExt) -> Result<Self, Self::Error> {
+        let variant = reader.read_u8()?;
+        match variant {
+            0 => Ok(DisconnectReason::ClientRequested),
+            1 => Ok(DisconnectReason::Timeout),
+            2 => Ok(DisconnectReason::ServerKicked(
+                reader.read_u16::<LittleEndian>()?,
+            )),
+            3 => Ok(DisconnectReason::TransportError),
+            4 => Ok(DisconnectReason::ProtocolMismatch),
+            5 => Ok(DisconnectReason::RateLimitExceeded),
+            6 => Ok(DisconnectReason::Reconnected),
+            7 => Ok(DisconnectReason::ServerStopped),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid disconnect reason",
+            )),
+        }
     }
 }
 
 impl Bytes for DisconnectPacket {
     type Error = io::Error;
-    fn write_to(&self, _writer: &mut impl WriteBytesExt) -> Result<(), Self::Error> {
+    fn write_to(&self, writer: &mut impl WriteBytesExt) -> Result<(), Self::Error> {
+        self.reason.write_to(writer)?;
         Ok(())
     }
 
-    fn read_from(_reader: &mut impl byteorder::ReadBytesExt) -> Result<Self, io::Error> {
-        Ok(Self {})
+    fn read_from(reader: &mut impl byteorder::ReadBytesExt) -> Result<Self, io::Error> {
+        let reason = DisconnectReason::read_from(reader)?;
+        Ok(Self { reason })
     }
 }
 
@@ -755,6 +842,37 @@ mod tests {
         assert_eq!(challenge_pkt.sequence, sequence);
     }
 
+    #[test]
+    pub fn response_packet_connect_payload() {
+        let packet_key = generate_key();
+        let protocol_id = 0x1234_5678_9abc_def0;
+        let sequence = 0u64;
+        let token = [0x11u8; ChallengeToken::SIZE];
+
+        let packet = ResponsePacket::create(sequence, token, b"player_name".to_vec());
+        let mut buf = [0u8; MAX_PKT_BUF_SIZE];
+        let size = packet
+            .write(&mut buf, sequence, &packet_key, protocol_id)
+            .unwrap();
+        let packet =
+            Packet::read(&mut buf[..size], protocol_id, 0, packet_key, None, 0xff).unwrap();
+        let Packet::Response(response_pkt) = packet else {
+            panic!("wrong packet type");
+        };
+        assert_eq!(response_pkt.token, token);
+        assert_eq!(response_pkt.connect_payload, b"player_name".to_vec());
+
+        // payloads that are too large are rejected
+        let packet = ResponsePacket::create(
+            sequence + 1,
+            token,
+            vec![0u8; MAX_CONNECT_PAYLOAD_BYTES + 1],
+        );
+        assert!(packet
+            .write(&mut buf, sequence + 1, &packet_key, protocol_id)
+            .is_err());
+    }
+
     #[test]
     pub fn keep_alive_packet() {
         let packet_key = generate_key();
@@ -794,7 +912,9 @@ mod tests {
         let sequence = 0u64;
         let mut replay_protection = ReplayProtection::new();
 
-        let packet = Packet::Disconnect(DisconnectPacket {});
+        let packet = Packet::Disconnect(DisconnectPacket {
+            reason: DisconnectReason::ServerKicked(7),
+        });
 
         let mut buf = [0u8; MAX_PKT_BUF_SIZE];
         let size = packet
@@ -811,9 +931,11 @@ mod tests {
         )
         .unwrap();
 
-        let Packet::Disconnect(_disconnect_pkt) = packet else {
+        let Packet::Disconnect(disconnect_pkt) = packet else {
             panic!("wrong packet type");
         };
+
+        assert_eq!(disconnect_pkt.reason, DisconnectReason::ServerKicked(7));
     }
 
     #[test]
@@ -847,4 +969,26 @@ mod tests {
 
         assert_eq!(data_pkt.buf.len(), 100);
     }
+
+    /// The largest MTU probe still fits in the transport MTU once the io middleware overhead is added
+    #[test]
+    fn payload_packet_mtu_ceiling() {
+        use crate::packet::mtu_discovery::MAX_CEILING;
+        use crate::transport::middleware::MIDDLEWARE_OVERHEAD;
+        use crate::transport::MTU;
+
+        let packet_key = generate_key();
+        let protocol_id = 0x1234_5678_9abc_def0;
+        // the largest sequence number uses 8 bytes
+        let sequence = u64::MAX;
+
+        let payload = vec![0u8; MAX_CEILING];
+        let packet = Packet::Payload(PayloadPacket { buf: &payload });
+
+        let mut buf = [0u8; MAX_PKT_BUF_SIZE];
+        let size = packet
+            .write(&mut buf, sequence, &packet_key, protocol_id)
+            .unwrap();
+        assert!(size + MIDDLEWARE_OVERHEAD <= MTU);
+    }
 }
@@ -1,10 +1,10 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use bevy::prelude::Resource;
-use tracing::{debug, error, trace};
+use tracing::{debug, error, info, trace};
 
 #[cfg(feature = "trace")]
 use tracing::{instrument, Level};
@@ -12,7 +12,8 @@ use tracing::{instrument, Level};
 use crate::connection::id;
 use crate::connection::netcode::token::TOKEN_EXPIRE_SEC;
 use crate::connection::server::{
-    ConnectionRequestHandler, DefaultConnectionRequestHandler, DeniedReason, IoConfig, NetServer,
+    ConnectionRequestHandler, DefaultConnectionRequestHandler, DeniedReason, DisconnectReason,
+    DuplicateConnectPolicy, IoConfig, NetServer,
 };
 use crate::packet::packet_builder::RecvPayload;
 use crate::server::config::NetcodeConfig;
@@ -29,7 +30,7 @@ use super::{
     },
     replay::ReplayProtection,
     token::{ChallengeToken, ConnectToken, ConnectTokenBuilder, ConnectTokenPrivate},
-    MAC_BYTES, MAX_PACKET_SIZE, MAX_PKT_BUF_SIZE, PACKET_SEND_RATE_SEC,
+    MAC_BYTES, MAX_PAYLOAD_SIZE, MAX_PKT_BUF_SIZE, PACKET_SEND_RATE_SEC,
 };
 
 pub const MAX_CLIENTS: usize = 256;
@@ -80,7 +81,7 @@ impl TokenEntries {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 struct Connection {
     confirmed: bool,
     connected: bool,
@@ -93,6 +94,10 @@ struct Connection {
     send_key: Key,
     receive_key: Key,
     sequence: u64,
+    /// Application data sent by the client when connecting
+    connect_payload: Vec<u8>,
+    /// MAC of the connect token used to create this session
+    token_mac: [u8; 16],
 }
 
 impl Connection {
@@ -148,12 +153,14 @@ impl ConnectionCache {
         timeout: i32,
         send_key: Key,
         receive_key: Key,
+        token_mac: [u8; 16],
     ) {
         if let Some((_, ref mut existing)) = self.find_by_addr(&addr) {
             existing.client_id = client_id;
             existing.timeout = timeout;
             existing.send_key = send_key;
             existing.receive_key = receive_key;
+            existing.token_mac = token_mac;
             existing.last_access_time = self.time;
             return;
         }
@@ -169,6 +176,8 @@ impl ConnectionCache {
             send_key,
             receive_key,
             sequence: 0,
+            connect_payload: Vec::new(),
+            token_mac,
         };
         self.clients.insert(client_id, conn);
         self.replay_protection
@@ -195,7 +204,7 @@ impl ConnectionCache {
     fn find_by_addr(&self, addr: &SocketAddr) -> Option<(ClientId, Connection)> {
         self.client_id_map
             .get(addr)
-            .and_then(|id| self.clients.get(id).map(|conn| (*id, *conn)))
+            .and_then(|id| self.clients.get(id).map(|conn| (*id, conn.clone())))
     }
     fn find_by_id(&self, client_id: ClientId) -> Option<Connection> {
         self.clients.get(&client_id).cloned()
@@ -215,6 +224,8 @@ impl ConnectionCache {
 }
 
 pub type Callback<Ctx> = Box<dyn FnMut(ClientId, SocketAddr, &mut Ctx) + Send + Sync + 'static>;
+pub type DisconnectCallback<Ctx> =
+    Box<dyn FnMut(ClientId, SocketAddr, DisconnectReason, &mut Ctx) + Send + Sync + 'static>;
 
 /// Configuration for a server.
 ///
@@ -222,6 +233,7 @@ pub type Callback<Ctx> = Box<dyn FnMut(ClientId, SocketAddr, &mut Ctx) + Send +
 /// * `keep_alive_send_rate` - The rate at which keep-alive packets will be sent to clients.
 /// * `on_connect` - A callback that will be called when a client is connected to the server.
 /// * `on_disconnect` - A callback that will be called when a client is disconnected from the server.
+/// * `on_reconnect` - A callback that will be called when a client is connected by replacing its existing session.
 ///
 /// # Example
 /// ```
@@ -246,9 +258,12 @@ pub struct ServerConfig<Ctx> {
     client_timeout_secs: i32,
     connection_request_handler: Arc<dyn ConnectionRequestHandler>,
     server_addr: SocketAddr,
+    duplicate_connect_policy: DuplicateConnectPolicy,
+    max_clients: usize,
     context: Ctx,
     on_connect: Option<Callback<Ctx>>,
-    on_disconnect: Option<Callback<Ctx>>,
+    on_disconnect: Option<DisconnectCallback<Ctx>>,
+    on_reconnect: Option<Callback<Ctx>>,
 }
 
 impl Default for ServerConfig<()> {
@@ -260,9 +275,12 @@ impl Default for ServerConfig<()> {
             client_timeout_secs: CLIENT_TIMEOUT_SECS,
             connection_request_handler: Arc::new(DefaultConnectionRequestHandler),
             server_addr: SocketAddr::from(([0, 0, 0, 0], 0)),
+            duplicate_connect_policy: DuplicateConnectPolicy::default(),
+            max_clients: MAX_CLIENTS,
             context: (),
             on_connect: None,
             on_disconnect: None,
+            on_reconnect: None,
         }
     }
 }
@@ -281,9 +299,12 @@ impl<Ctx> ServerConfig<Ctx> {
             client_timeout_secs: CLIENT_TIMEOUT_SECS,
             connection_request_handler: Arc::new(DefaultConnectionRequestHandler),
             server_addr: SocketAddr::from(([0, 0, 0, 0], 0)),
+            duplicate_connect_policy: DuplicateConnectPolicy::default(),
+            max_clients: MAX_CLIENTS,
             context: ctx,
             on_connect: None,
             on_disconnect: None,
+            on_reconnect: None,
         }
     }
     /// Set the number of redundant disconnect packets that will be sent to a client when the server is disconnecting it. <br>
@@ -316,6 +337,19 @@ impl<Ctx> ServerConfig<Ctx> {
         self.server_addr = server_addr;
         self
     }
+    /// Set how the server handles a connection request from a client that already has a connected session. <br>
+    /// The default is [`DuplicateConnectPolicy::Reject`].
+    pub fn duplicate_connect_policy(mut self, policy: DuplicateConnectPolicy) -> Self {
+        self.duplicate_connect_policy = policy;
+        self
+    }
+    /// Set the maximum number of clients that can be connected at the same time.
+    /// Connection requests are denied with [`DeniedReason::ServerFull`] when the server is full. <br>
+    /// The default (and maximum) is [`MAX_CLIENTS`].
+    pub fn max_clients(mut self, max_clients: usize) -> Self {
+        self.max_clients = max_clients.min(MAX_CLIENTS);
+        self
+    }
     /// Provide a callback that will be called when a client is connected to the server. <br>
     /// The callback will be called with the client index and the context that was provided (provide a `None` context if you don't need one).
     ///
@@ -328,16 +362,26 @@ impl<Ctx> ServerConfig<Ctx> {
         self
     }
     /// Provide a callback that will be called when a client is disconnected from the server. <br>
-    /// The callback will be called with the client index and the context that was provided (provide a `None` context if you don't need one).
+    /// The callback will be called with the client index, the reason for the disconnection and the context that was provided (provide a `None` context if you don't need one).
     ///
     /// See [`ServerConfig`] for an example.
     pub fn on_disconnect<F>(mut self, cb: F) -> Self
     where
-        F: FnMut(ClientId, SocketAddr, &mut Ctx) + Send + Sync + 'static,
+        F: FnMut(ClientId, SocketAddr, DisconnectReason, &mut Ctx) + Send + Sync + 'static,
     {
         self.on_disconnect = Some(Box::new(cb));
         self
     }
+    /// Provide a callback that will be called when a client is connected by replacing its existing session
+    /// (see [`DuplicateConnectPolicy::Reconnect`]). <br>
+    /// The callback will be called after the `on_connect` callback.
+    pub fn on_reconnect<F>(mut self, cb: F) -> Self
+    where
+        F: FnMut(ClientId, SocketAddr, &mut Ctx) + Send + Sync + 'static,
+    {
+        self.on_reconnect = Some(Box::new(cb));
+        self
+    }
 }
 
 /// The `netcode` server.
@@ -383,6 +427,8 @@ pub struct NetcodeServer<Ctx = ()> {
     protocol_id: u64,
     conn_cache: ConnectionCache,
     token_entries: TokenEntries,
+    /// Clients whose previous session was replaced by a connection that is still being established
+    reconnecting: HashSet<ClientId>,
     cfg: ServerConfig<Ctx>,
 }
 
@@ -401,6 +447,7 @@ impl NetcodeServer {
             challenge_key: crypto::generate_key(),
             conn_cache: ConnectionCache::new(0.0),
             token_entries: TokenEntries::new(),
+            reconnecting: HashSet::new(),
             cfg: ServerConfig::default(),
         };
         // info!("server started on {}", server.io.local_addr());
@@ -435,6 +482,7 @@ impl<Ctx> NetcodeServer<Ctx> {
             challenge_key: crypto::generate_key(),
             conn_cache: ConnectionCache::new(0.0),
             token_entries: TokenEntries::new(),
+            reconnecting: HashSet::new(),
             cfg,
         };
         // info!("server started on {}", server.addr());
@@ -453,8 +501,13 @@ impl<Ctx> NetcodeServer<Ctx> {
             cb(client_id, addr, &mut self.cfg.context)
         }
     }
-    fn on_disconnect(&mut self, client_id: ClientId, addr: SocketAddr) {
+    fn on_disconnect(&mut self, client_id: ClientId, addr: SocketAddr, reason: DisconnectReason) {
         if let Some(cb) = self.cfg.on_disconnect.as_mut() {
+            cb(client_id, addr, reason, &mut self.cfg.context)
+        }
+    }
+    fn on_reconnect(&mut self, client_id: ClientId, addr: SocketAddr) {
+        if let Some(cb) = self.cfg.on_reconnect.as_mut() {
             cb(client_id, addr, &mut self.cfg.context)
         }
     }
@@ -509,7 +562,7 @@ impl<Ctx> NetcodeServer<Ctx> {
             Packet::Disconnect(_) => {
                 if let Some(idx) = client_id {
                     debug!("server disconnected client {idx}");
-                    self.on_disconnect(idx, addr);
+                    self.on_disconnect(idx, addr, DisconnectReason::ClientRequested);
                     self.conn_cache.remove(idx);
                 }
                 Ok(())
@@ -578,35 +631,46 @@ impl<Ctx> NetcodeServer<Ctx> {
         //     );
         //     return Ok(());
         // };
-        if self
+        let mac: [u8; 16] = packet.token_data
+            [ConnectTokenPrivate::SIZE - MAC_BYTES..ConnectTokenPrivate::SIZE]
+            .try_into()
+            .expect("valid MAC size");
+        // sessions that are already connected with the same address or the same client id
+        let mut duplicates: Vec<Connection> = self
             .conn_cache
             .find_by_addr(&from_addr)
-            .is_some_and(|(_, conn)| conn.is_connected())
-        {
-            debug!("server ignored connection request. a client with this address is already connected");
+            .map(|(_, conn)| conn)
+            .into_iter()
+            .chain(self.conn_cache.find_by_id(token.client_id))
+            .filter(Connection::is_connected)
+            .collect();
+        duplicates.dedup_by_key(|conn| conn.client_id);
+        if duplicates.iter().any(|conn| conn.token_mac == mac) {
+            debug!("server ignored connection request. the connect token is already used by a connected client");
             return Ok(());
-        };
-        if self
-            .conn_cache
-            .find_by_id(token.client_id)
-            .is_some_and(|conn| conn.is_connected())
+        }
+        if !duplicates.is_empty()
+            && self.cfg.duplicate_connect_policy == DuplicateConnectPolicy::Reject
         {
-            debug!("server ignored connection request. a client with this id is already connected");
+            debug!("server denied connection request. a client with this address or id is already connected");
+            self.send_to_addr(
+                DeniedPacket::create(DeniedReason::AlreadyConnected),
+                from_addr,
+                token.server_to_client_key,
+                sender,
+            )?;
             return Ok(());
-        };
+        }
         let entry = TokenEntry {
             time: self.time,
             addr: from_addr,
-            mac: packet.token_data
-                [ConnectTokenPrivate::SIZE - MAC_BYTES..ConnectTokenPrivate::SIZE]
-                .try_into()
-                .expect("valid MAC size"),
+            mac,
         };
         if !self.token_entries.find_or_insert(entry) {
             debug!("server ignored connection request. connect token has already been used");
             return Ok(());
         };
-        if self.num_connected_clients() >= MAX_CLIENTS {
+        if self.num_connected_clients() >= self.cfg.max_clients {
             debug!("server denied connection request. server is full");
             self.send_to_addr(
                 DeniedPacket::create(DeniedReason::ServerFull),
@@ -619,7 +683,10 @@ impl<Ctx> NetcodeServer<Ctx> {
         if let Some(denied_reason) = self
             .cfg
             .connection_request_handler
-            .handle_request(crate::prelude::ClientId::Netcode(token.client_id))
+            .handle_request_with_user_data(
+                crate::prelude::ClientId::Netcode(token.client_id),
+                &token.user_data,
+            )
         {
             debug!("server denied connection request. handle_connection_request_fn returned false");
             self.send_to_addr(
@@ -630,12 +697,25 @@ impl<Ctx> NetcodeServer<Ctx> {
             )?;
             return Ok(());
         }
+        // DuplicateConnectPolicy::Reconnect: the new connection replaces the existing sessions
+        for conn in duplicates {
+            debug!(
+                "server replaced the session of client {} with a new connection",
+                conn.client_id
+            );
+            if conn.client_id == token.client_id {
+                self.reconnecting.insert(conn.client_id);
+            }
+            self.on_disconnect(conn.client_id, conn.addr, DisconnectReason::Reconnected);
+            self.conn_cache.remove(conn.client_id);
+        }
         self.conn_cache.add(
             token.client_id,
             from_addr,
             token.timeout_seconds,
             token.server_to_client_key,
             token.client_to_server_key,
+            mac,
         );
         let Ok(challenge_token_encrypted) = ChallengeToken {
             client_id: token.client_id,
@@ -677,7 +757,7 @@ impl<Ctx> NetcodeServer<Ctx> {
             return Ok(());
         };
 
-        if self.num_connected_clients() >= MAX_CLIENTS {
+        if self.num_connected_clients() >= self.cfg.max_clients {
             debug!("server denied connection response. server is full");
             self.send_to_addr(
                 DeniedPacket::create(DeniedReason::ServerFull),
@@ -697,6 +777,7 @@ impl<Ctx> NetcodeServer<Ctx> {
             .get_mut(&id)
             .expect("invalid client id");
         client.connect();
+        client.connect_payload = std::mem::take(&mut packet.connect_payload);
         client.last_send_time = self.time;
         client.last_receive_time = self.time;
         debug!(
@@ -705,9 +786,19 @@ impl<Ctx> NetcodeServer<Ctx> {
         );
         self.send_to_client(KeepAlivePacket::create(id), id, sender)?;
         self.on_connect(id, from_addr);
+        if self.reconnecting.remove(&id) {
+            self.on_reconnect(id, from_addr);
+        }
         Ok(())
     }
     fn check_for_timeouts(&mut self) {
+        // forget the replaced sessions whose new connection was never established
+        let (time, clients) = (self.time, &self.conn_cache.clients);
+        self.reconnecting.retain(|id| {
+            clients.get(id).is_some_and(|conn| {
+                !conn.timeout.is_positive() || conn.last_access_time + (conn.timeout as f64) >= time
+            })
+        });
         for id in self.conn_cache.ids() {
             let Some(client) = self.conn_cache.clients.get_mut(&id) else {
                 continue;
@@ -720,7 +811,7 @@ impl<Ctx> NetcodeServer<Ctx> {
                 && client.last_receive_time + (client.timeout as f64) < self.time
             {
                 debug!("server timed out client {id}");
-                self.on_disconnect(id, addr);
+                self.on_disconnect(id, addr, DisconnectReason::Timeout);
                 self.conn_cache.remove(id);
             }
         }
@@ -757,6 +848,9 @@ impl<Ctx> NetcodeServer<Ctx> {
             // Too small to be a packet
             return Ok(());
         }
+        if buf[0] != Packet::REQUEST && self.conn_cache.find_by_addr(&addr).is_none() {
+            self.migrate_client_addr(buf, now, addr);
+        }
         let (key, replay_protection) = match self.conn_cache.find_by_addr(&addr) {
             // Regardless of whether an entry in the connection cache exists for the client or not,
             // if the packet is a connection request we need to use the server's private key to decrypt it.
@@ -797,6 +891,55 @@ impl<Ctx> NetcodeServer<Ctx> {
         self.process_packet(addr, packet, sender)
     }
 
+    /// A connected client might send packets from a new address, for example because it switched
+    /// its transport (see [`Io::set_transport`](crate::client::io::Io::set_transport)) or because of a NAT rebinding.
+    ///
+    /// If the packet is a keep-alive or a payload packet that is authenticated by the key of a connected client
+    /// (and is not a replayed packet), the address of that client is updated.
+    fn migrate_client_addr(&mut self, buf: &[u8], now: u64, addr: SocketAddr) {
+        let (_, kind) = Packet::get_prefix(buf[0]);
+        if kind != Packet::KEEP_ALIVE && kind != Packet::PAYLOAD {
+            return;
+        }
+        let Some(client_id) = self
+            .conn_cache
+            .clients
+            .iter()
+            .filter(|(_, conn)| conn.is_connected())
+            .find_map(|(client_id, conn)| {
+                // the packet is decrypted in place, and the replay protection is only updated
+                // when the packet is actually processed
+                let mut buf = buf.to_vec();
+                let mut replay_protection =
+                    self.conn_cache.replay_protection.get(client_id)?.clone();
+                Packet::read(
+                    &mut buf,
+                    self.protocol_id,
+                    now,
+                    conn.receive_key,
+                    Some(&mut replay_protection),
+                    Self::ALLOWED_PACKETS,
+                )
+                .ok()
+                .map(|_| *client_id)
+            })
+        else {
+            return;
+        };
+        let conn = self
+            .conn_cache
+            .clients
+            .get_mut(&client_id)
+            .expect("client id not found");
+        info!(
+            "client {client_id} migrated from address {} to {addr}",
+            conn.addr
+        );
+        self.conn_cache.client_id_map.remove(&conn.addr);
+        conn.addr = addr;
+        self.conn_cache.client_id_map.insert(addr, client_id);
+    }
+
     fn recv_packets(
         &mut self,
         sender: &mut impl PacketSender,
@@ -830,9 +973,9 @@ impl<Ctx> NetcodeServer<Ctx> {
     pub fn try_update(&mut self, delta_ms: f64, io: &mut Io) -> Result<()> {
         self.time += delta_ms;
         self.conn_cache.update(delta_ms);
-        let (sender, receiver) = io.split();
+        let (mut sender, mut receiver) = io.split();
         self.check_for_timeouts();
-        self.recv_packets(sender, receiver)?;
+        self.recv_packets(&mut sender, &mut receiver)?;
         self.send_packets(io)?;
         Ok(())
     }
@@ -869,11 +1012,11 @@ impl<Ctx> NetcodeServer<Ctx> {
     }
     /// Sends a packet to a client.
     ///
-    /// The provided buffer must be smaller than [`MAX_PACKET_SIZE`].
+    /// The provided buffer must be smaller than [`MAX_PAYLOAD_SIZE`].
     #[cfg_attr(feature = "trace", instrument(level = Level::INFO, skip_all))]
     pub fn send(&mut self, buf: &[u8], client_id: ClientId, io: &mut Io) -> Result<()> {
-        if buf.len() > MAX_PACKET_SIZE {
-            return Err(Error::SizeMismatch(MAX_PACKET_SIZE, buf.len()));
+        if buf.len() > MAX_PAYLOAD_SIZE {
+            return Err(Error::SizeMismatch(MAX_PAYLOAD_SIZE, buf.len()));
         }
         let Some(conn) = self.conn_cache.clients.get_mut(&client_id) else {
             return Err(Error::ClientNotFound);
@@ -894,7 +1037,7 @@ impl<Ctx> NetcodeServer<Ctx> {
 
     /// Sends a packet to all connected clients.
     ///
-    /// The provided buffer must be smaller than [`MAX_PACKET_SIZE`].
+    /// The provided buffer must be smaller than [`MAX_PAYLOAD_SIZE`].
     pub fn send_all(&mut self, buf: &[u8], io: &mut Io) -> Result<()> {
         for id in self.conn_cache.ids() {
             match self.send(buf, id, io) {
@@ -944,6 +1087,18 @@ impl<Ctx> NetcodeServer<Ctx> {
     ///
     /// The server will send a number of redundant disconnect packets to the client, and then remove its connection info.
     pub fn disconnect(&mut self, client_id: ClientId, io: &mut Io) -> Result<()> {
+        self.disconnect_with_reason(client_id, DisconnectReason::ServerKicked(0), io)
+    }
+
+    /// Disconnects a client, providing the reason for the disconnection.
+    ///
+    /// The reason is sent to the client in the disconnect packets.
+    pub fn disconnect_with_reason(
+        &mut self,
+        client_id: ClientId,
+        reason: DisconnectReason,
+        io: &mut Io,
+    ) -> Result<()> {
         let Some(conn) = self.conn_cache.clients.get_mut(&client_id) else {
             return Ok(());
         };
@@ -952,13 +1107,13 @@ impl<Ctx> NetcodeServer<Ctx> {
         }
         let addr = conn.addr;
         debug!("server disconnecting client {client_id}");
-        self.on_disconnect(client_id, addr);
+        self.on_disconnect(client_id, addr, reason);
         for _ in 0..self.cfg.num_disconnect_packets {
             // self.send_to_client(DisconnectPacket::create(), client_id, io)?;
 
             // we do not use ? here because we want to continue even if the send fails
             let _ = self
-                .send_to_client(DisconnectPacket::create(), client_id, io)
+                .send_to_client(DisconnectPacket::create(reason), client_id, io)
                 .inspect_err(|e| {
                     error!("server failed to send disconnect packet: {e}");
                 });
@@ -974,7 +1129,7 @@ impl<Ctx> NetcodeServer<Ctx> {
         let Some(client_id) = self.conn_cache.client_id_map.get(&addr) else {
             return Err(Error::ClientNotFound);
         };
-        self.disconnect(*client_id, io)
+        self.disconnect_with_reason(*client_id, DisconnectReason::TransportError, io)
     }
 
     /// Disconnects all clients.
@@ -985,7 +1140,7 @@ impl<Ctx> NetcodeServer<Ctx> {
                 continue;
             };
             if conn.is_connected() {
-                self.disconnect(id, io)?;
+                self.disconnect_with_reason(id, DisconnectReason::ServerStopped, io)?;
             }
         }
         Ok(())
@@ -1002,6 +1157,13 @@ impl<Ctx> NetcodeServer<Ctx> {
         self.conn_cache.clients.keys().copied()
     }
 
+    /// Sets the maximum number of clients that can be connected at the same time.
+    ///
+    /// Clients that are already connected are not disconnected if the limit is lowered.
+    pub(crate) fn set_max_clients(&mut self, max_clients: usize) {
+        self.cfg.max_clients = max_clients.min(MAX_CLIENTS);
+    }
+
     /// Gets the number of connected clients.
     pub fn num_connected_clients(&self) -> usize {
         self.conn_cache
@@ -1016,6 +1178,14 @@ impl<Ctx> NetcodeServer<Ctx> {
         self.conn_cache.clients.get(&client_id).map(|c| c.addr)
     }
 
+    /// Gets the connect payload that a client sent when connecting.
+    pub fn connect_payload(&self, client_id: ClientId) -> Option<&[u8]> {
+        self.conn_cache
+            .clients
+            .get(&client_id)
+            .map(|c| c.connect_payload.as_slice())
+    }
+
     /// Gets the address of the server
     pub fn local_addr(&self) -> SocketAddr {
         self.cfg.server_addr
@@ -1029,10 +1199,26 @@ pub(crate) mod connection {
     #[derive(Default)]
     pub(crate) struct NetcodeServerContext {
         pub(crate) connections: Vec<id::ClientId>,
-        pub(crate) disconnections: Vec<id::ClientId>,
+        pub(crate) reconnections: Vec<id::ClientId>,
+        pub(crate) disconnections: Vec<(id::ClientId, DisconnectReason)>,
+        /// Disconnections that were triggered outside of [`NetServer::try_update`], which will be reported
+        /// during the next update
+        pending_disconnections: Vec<(id::ClientId, DisconnectReason)>,
+        /// Addresses of the sessions that were replaced by a new connection during the current update
+        replaced_addrs: Vec<SocketAddr>,
         sender: Option<ServerNetworkEventSender>,
     }
 
+    /// Notify the io that a client got disconnected, so that we can stop the corresponding task
+    fn notify_client_disconnected(sender: &mut Option<ServerNetworkEventSender>, addr: SocketAddr) {
+        if let Some(sender) = sender.as_mut() {
+            debug!("Notify the io that client {addr:?} got disconnected, so that we can stop the corresponding task");
+            let _ = sender
+                .try_send(ServerIoEvent::ClientDisconnected(addr))
+                .inspect_err(|e| error!("Error sending 'ClientDisconnected' event to io: {:?}", e));
+        }
+    }
+
     #[derive(Resource)]
     pub struct Server {
         pub(crate) server: NetcodeServer<NetcodeServerContext>,
@@ -1072,11 +1258,21 @@ pub(crate) mod connection {
 
         /// Disconnect a client from the server
         /// (also adds the client_id to the list of newly disconnected clients)
-        fn disconnect(&mut self, client_id: id::ClientId) -> Result<(), ConnectionError> {
+        fn disconnect(
+            &mut self,
+            client_id: id::ClientId,
+            reason: DisconnectReason,
+        ) -> Result<(), ConnectionError> {
             match client_id {
                 id::ClientId::Netcode(id) => {
                     if let Some(io) = self.io.as_mut() {
-                        self.server.disconnect(id, io)?
+                        let context = &mut self.server.cfg.context;
+                        let num_disconnections = context.disconnections.len();
+                        self.server.disconnect_with_reason(id, reason, io)?;
+                        let context = &mut self.server.cfg.context;
+                        let new_disconnections =
+                            context.disconnections.split_off(num_disconnections);
+                        context.pending_disconnections.extend(new_disconnections);
                     }
                     Ok(())
                 }
@@ -1091,13 +1287,30 @@ pub(crate) mod connection {
                 .collect()
         }
 
+        fn set_max_clients(&mut self, max_clients: usize) {
+            self.server.set_max_clients(max_clients);
+        }
+
         fn try_update(&mut self, delta_ms: f64) -> Result<(), ConnectionError> {
             let io = self.io.as_mut().ok_or(ConnectionError::IoNotInitialized)?;
             // reset the new connections/disconnections
             self.server.cfg.context.connections.clear();
-            self.server.cfg.context.disconnections.clear();
+            self.server.cfg.context.reconnections.clear();
+            let context = &mut self.server.cfg.context;
+            context.disconnections.clear();
+            context
+                .disconnections
+                .append(&mut context.pending_disconnections);
 
             self.server.try_update(delta_ms, io)?;
+
+            // notify the io about the sessions that got replaced, unless the new connection uses the same address
+            let replaced_addrs = std::mem::take(&mut self.server.cfg.context.replaced_addrs);
+            for addr in replaced_addrs {
+                if self.server.conn_cache.find_by_addr(&addr).is_none() {
+                    notify_client_disconnected(&mut self.server.cfg.context.sender, addr);
+                }
+            }
             Ok(())
         }
 
@@ -1120,10 +1333,28 @@ pub(crate) mod connection {
             self.server.cfg.context.connections.clone()
         }
 
-        fn new_disconnections(&self) -> Vec<id::ClientId> {
+        fn new_reconnections(&self) -> Vec<id::ClientId> {
+            self.server.cfg.context.reconnections.clone()
+        }
+
+        fn new_disconnections(&self) -> Vec<(id::ClientId, DisconnectReason)> {
             self.server.cfg.context.disconnections.clone()
         }
 
+        fn connect_payload(&self, client_id: id::ClientId) -> Option<&[u8]> {
+            let id::ClientId::Netcode(id) = client_id else {
+                return None;
+            };
+            self.server.connect_payload(id)
+        }
+
+        fn client_addr(&self, client_id: id::ClientId) -> Option<SocketAddr> {
+            let id::ClientId::Netcode(id) = client_id else {
+                return None;
+            };
+            self.server.client_addr(id)
+        }
+
         fn io(&self) -> Option<&Io> {
             self.io.as_ref()
         }
@@ -1133,28 +1364,30 @@ pub(crate) mod connection {
     }
 
     impl Server {
-        pub(crate) fn new(config: NetcodeConfig, io_config: IoConfig) -> Self {
+        pub(crate) fn new(config: NetcodeConfig, io_config: IoConfig, max_clients: usize) -> Self {
             // create context
             let context = NetcodeServerContext::default();
             let mut cfg = ServerConfig::with_context(context)
                 .on_connect(|id, addr, ctx| {
                     ctx.connections.push(id::ClientId::Netcode(id));
                 })
-                .on_disconnect(|id, addr, ctx| {
-                    // notify the io that a client got disconnected
-                    if let Some(sender) = &mut ctx.sender {
-                        debug!("Notify the io that client {id:?} got disconnected, so that we can stop the corresponding task");
-                        let _ = sender
-                            .try_send(ServerIoEvent::ClientDisconnected(addr))
-                            .inspect_err(|e| {
-                                error!("Error sending 'ClientDisconnected' event to io: {:?}", e)
-                            });
+                .on_reconnect(|id, addr, ctx| {
+                    ctx.reconnections.push(id::ClientId::Netcode(id));
+                })
+                .on_disconnect(|id, addr, reason, ctx| {
+                    if reason == DisconnectReason::Reconnected {
+                        // the new connection could be using the same address, we will check it at the end of the update
+                        ctx.replaced_addrs.push(addr);
+                    } else {
+                        notify_client_disconnected(&mut ctx.sender, addr);
                     }
-                    ctx.disconnections.push(id::ClientId::Netcode(id));
+                    ctx.disconnections.push((id::ClientId::Netcode(id), reason));
                 });
             cfg = cfg.keep_alive_send_rate(config.keep_alive_send_rate);
             cfg = cfg.num_disconnect_packets(config.num_disconnect_packets);
             cfg = cfg.client_timeout_secs(config.client_timeout_secs);
+            cfg = cfg.duplicate_connect_policy(config.duplicate_connect_policy);
+            cfg = cfg.max_clients(max_clients);
             cfg.connection_request_handler = config.connection_request_handler;
             let server = NetcodeServer::with_config(config.protocol_id, config.private_key, cfg)
                 .expect("Could not create server netcode");
@@ -1177,5 +1410,11 @@ pub(crate) mod connection {
             }
             Ok(())
         }
+
+        /// Replace the sender used to notify the io about the disconnected clients
+        #[cfg(test)]
+        pub(crate) fn set_io_event_sender(&mut self, sender: ServerNetworkEventSender) {
+            self.server.cfg.context.sender = Some(sender);
+        }
     }
 }
@@ -5,6 +5,7 @@ use enum_dispatch::enum_dispatch;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 use crate::connection::id::ClientId;
@@ -12,7 +13,6 @@ use crate::connection::id::ClientId;
 use crate::connection::steam::{server::SteamConfig, steamworks_client::SteamworksClient};
 use crate::packet::packet_builder::RecvPayload;
 use crate::prelude::server::ServerTransport;
-#[cfg(all(feature = "steam", not(target_family = "wasm")))]
 use crate::prelude::LinkConditionerConfig;
 use crate::server::config::NetcodeConfig;
 use crate::server::io::Io;
@@ -30,12 +30,76 @@ pub enum DeniedReason {
     Custom(String),
 }
 
+/// Reasons for a client to be disconnected from the server
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum DisconnectReason {
+    /// We did not receive any packet from the client for too long
+    Timeout,
+    /// The client requested to be disconnected
+    ClientRequested,
+    /// The server disconnected the client, with an application-specific code.
+    ///
+    /// The code `0` is used when the server disconnects a client without providing a code.
+    ServerKicked(u16),
+    /// The server was stopped
+    ServerStopped,
+    /// The transport used by the client's connection failed
+    TransportError,
+    /// The client sent packets that don't match the server's protocol (for example an unknown channel)
+    ProtocolMismatch,
+    /// The client sent messages well above the rate limits of the server
+    RateLimitExceeded,
+    /// The client started a new connection, which replaced this one
+    ///
+    /// See [`DuplicateConnectPolicy::Reconnect`]
+    Reconnected,
+}
+
+/// How the server handles a connection request from an address or a client id that already
+/// has a connected session.
+///
+/// Requests that re-send the connect token of the existing session (for example retransmitted
+/// connect packets) are always ignored.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum DuplicateConnectPolicy {
+    /// Keep the existing session and deny the new connection with [`DeniedReason::AlreadyConnected`]
+    #[default]
+    Reject,
+    /// Replace the existing session with the new connection.
+    ///
+    /// The existing session is disconnected with [`DisconnectReason::Reconnected`]. If the new
+    /// connection uses the same [`ClientId`], a [`ReconnectEvent`](crate::server::events::ReconnectEvent)
+    /// is emitted once it is established.
+    Reconnect,
+}
+
 /// Trait for handling connection requests from clients.
 pub trait ConnectionRequestHandler: Debug + Send + Sync {
     /// Handle a connection request from a client.
     /// Returns None if the connection is accepted,
     /// Returns Some(reason) if the connection is denied.
-    fn handle_request(&self, client_id: ClientId) -> Option<DeniedReason>;
+    fn handle_request(&self, client_id: ClientId) -> Option<DeniedReason> {
+        None
+    }
+
+    /// Handle a connection request from a client, with the user data of its `ConnectToken`.
+    ///
+    /// The user data is opaque data that your backend (for example a matchmaker) can put in the token
+    /// with [`ConnectTokenBuilder::user_data`](crate::connection::netcode::ConnectTokenBuilder::user_data),
+    /// for example a session ticket that the server can validate before accepting the client.
+    /// The user data is encrypted with the private key, so it cannot be read or modified by the client.
+    ///
+    /// A denied client receives the [`DeniedReason`], and no connection is created
+    /// (no [`ConnectEvent`](crate::server::events::ConnectEvent) is emitted).
+    ///
+    /// By default, this calls [`handle_request`](ConnectionRequestHandler::handle_request).
+    fn handle_request_with_user_data(
+        &self,
+        client_id: ClientId,
+        user_data: &[u8],
+    ) -> Option<DeniedReason> {
+        self.handle_request(client_id)
+    }
 }
 
 /// By default, all connection requests are accepted by the server.
@@ -62,11 +126,22 @@ pub trait NetServer: Send + Sync {
     //  and we decide whether to accept it or not
     /// Disconnect a specific client
     /// Is also responsible for adding the client to the list of new disconnections.
-    fn disconnect(&mut self, client_id: ClientId) -> Result<(), ConnectionError>;
+    fn disconnect(
+        &mut self,
+        client_id: ClientId,
+        reason: DisconnectReason,
+    ) -> Result<(), ConnectionError>;
 
     /// Return the list of connected clients
     fn connected_client_ids(&self) -> Vec<ClientId>;
 
+    /// Set the maximum number of clients that can be connected to this server at the same time
+    ///
+    /// [`ServerConnections`] sets it before every update, so that the
+    /// [`ServerConfig::max_clients`](crate::server::config::ServerConfig::max_clients) limit applies
+    /// to the total number of clients connected to all the servers.
+    fn set_max_clients(&mut self, max_clients: usize);
+
     /// Update the connection states + internal bookkeeping (keep-alives, etc.)
     fn try_update(&mut self, delta_ms: f64) -> Result<(), ConnectionError>;
 
@@ -78,11 +153,35 @@ pub trait NetServer: Send + Sync {
 
     fn new_connections(&self) -> Vec<ClientId>;
 
-    fn new_disconnections(&self) -> Vec<ClientId>;
+    /// Return the list of clients that connected during the last update by replacing an
+    /// existing session with the same [`ClientId`]
+    ///
+    /// These clients are also part of [`new_connections`](NetServer::new_connections)
+    fn new_reconnections(&self) -> Vec<ClientId> {
+        Vec::new()
+    }
+
+    /// Return the list of clients that got disconnected during the last update, along with
+    /// the reason for the disconnection
+    fn new_disconnections(&self) -> Vec<(ClientId, DisconnectReason)>;
+
+    /// Return the application data that the client sent when connecting, if any
+    fn connect_payload(&self, client_id: ClientId) -> Option<&[u8]>;
+
+    /// Return the address of the client, if the connection uses the Io of the server
+    fn client_addr(&self, client_id: ClientId) -> Option<SocketAddr>;
 
     fn io(&self) -> Option<&Io>;
 
     fn io_mut(&mut self) -> Option<&mut Io>;
+
+    /// Return the address that the server's Io is actually bound to.
+    ///
+    /// This is None if the server is not started or doesn't use an Io (for example Steam).
+    /// If the [`IoConfig`] uses the port 0, this returns the ephemeral port chosen by the OS.
+    fn local_addr(&self) -> Option<SocketAddr> {
+        self.io().map(|io| io.local_addr())
+    }
 }
 
 #[enum_dispatch(NetServer)]
@@ -137,10 +236,11 @@ impl Default for NetConfig {
 }
 
 impl NetConfig {
-    pub fn build_server(self) -> ServerConnection {
+    /// Build the server connection, which accepts at most `max_clients` connected clients
+    pub fn build_server(self, max_clients: usize) -> ServerConnection {
         match self {
             NetConfig::Netcode { config, io } => {
-                let server = super::netcode::Server::new(config, io);
+                let server = super::netcode::Server::new(config, io, max_clients);
                 ServerConnection::Netcode(server)
             }
             // TODO: might want to distinguish between steam with direct ip connections
@@ -148,9 +248,10 @@ impl NetConfig {
             #[cfg(all(feature = "steam", not(target_family = "wasm")))]
             NetConfig::Steam {
                 steamworks_client,
-                config,
+                mut config,
                 conditioner,
             } => {
+                config.max_clients = config.max_clients.min(max_clients);
                 // TODO: handle errors
                 let server = super::steam::server::Server::new(
                     steamworks_client.unwrap_or_else(|| {
@@ -177,20 +278,23 @@ pub struct ServerConnections {
     pub servers: Vec<ServerConnection>,
     /// Mapping from the connection's [`ClientId`] into the index of the [`ServerConnection`] in the `servers` list
     pub(crate) client_server_map: HashMap<ClientId, ServerConnectionIdx>,
+    /// Maximum number of clients that can be connected to all the servers combined
+    pub(crate) max_clients: usize,
     /// Track whether the server is ready to listen to incoming connections
     is_listening: bool,
 }
 
 impl ServerConnections {
-    pub fn new(config: Vec<NetConfig>) -> Self {
+    pub fn new(config: Vec<NetConfig>, max_clients: usize) -> Self {
         let mut servers = vec![];
         for config in config {
-            let server = config.build_server();
+            let server = config.build_server(max_clients);
             servers.push(server);
         }
         ServerConnections {
             servers,
             client_server_map: HashMap::default(),
+            max_clients,
             is_listening: false,
         }
     }
@@ -215,10 +319,24 @@ impl ServerConnections {
 
     /// Disconnect a specific client
     pub fn disconnect(&mut self, client_id: ClientId) -> Result<(), ConnectionError> {
+        self.kick(client_id, 0)
+    }
+
+    /// Disconnect a specific client, with an application-specific code that will be
+    /// available in the [`DisconnectReason::ServerKicked`] of the disconnect events on both the server and the client
+    pub fn kick(&mut self, client_id: ClientId, code: u16) -> Result<(), ConnectionError> {
+        self.disconnect_with_reason(client_id, DisconnectReason::ServerKicked(code))
+    }
+
+    pub(crate) fn disconnect_with_reason(
+        &mut self,
+        client_id: ClientId,
+        reason: DisconnectReason,
+    ) -> Result<(), ConnectionError> {
         self.client_server_map.get(&client_id).map_or(
             Err(ConnectionError::ConnectionNotFound),
             |&server_idx| {
-                self.servers[server_idx].disconnect(client_id)?;
+                self.servers[server_idx].disconnect(client_id, reason)?;
                 // NOTE: we don't remove the client from the map here because it is done
                 //  in the server's `receive` method
                 // self.client_server_map.remove(&client_id);
@@ -227,6 +345,45 @@ impl ServerConnections {
         )
     }
 
+    /// Simulate specific network conditions (latency, jitter, packet loss) for the packets
+    /// received from a given client, instead of using the conditioner of the server's [`IoConfig`].
+    ///
+    /// If `config` is None, the client uses the default conditioner of the server again.
+    ///
+    /// This is useful for chaos testing, to simulate a mix of good and bad connections against a single server.
+    pub fn set_client_conditioner(
+        &mut self,
+        client_id: ClientId,
+        config: Option<LinkConditionerConfig>,
+    ) -> Result<(), ConnectionError> {
+        let server_idx = *self
+            .client_server_map
+            .get(&client_id)
+            .ok_or(ConnectionError::ConnectionNotFound)?;
+        let server = &mut self.servers[server_idx];
+        let addr = server
+            .client_addr(client_id)
+            .ok_or(ConnectionError::InvalidConnectionType)?;
+        server
+            .io_mut()
+            .ok_or(ConnectionError::IoNotInitialized)?
+            .set_conditioner(addr, config);
+        Ok(())
+    }
+
+    /// Returns the address that each server is bound to, in the same order as the [`NetConfig`]s
+    /// of the [`ServerConfig`](crate::server::config::ServerConfig).
+    ///
+    /// If the transport binds to the port 0, this is the ephemeral port chosen by the OS, which can be
+    /// advertised to a lobby or a matchmaker once the server is started.
+    /// See [`NetServer::local_addr`].
+    pub fn local_addrs(&self) -> Vec<Option<SocketAddr>> {
+        self.servers
+            .iter()
+            .map(|server| server.local_addr())
+            .collect()
+    }
+
     /// Returns true if the server is currently listening for client packets
     pub(crate) fn is_listening(&self) -> bool {
         self.is_listening
@@ -256,3 +413,59 @@ pub enum ConnectionError {
     #[cfg(all(feature = "steam", not(target_family = "wasm")))]
     SteamError(#[from] steamworks::SteamError),
 }
+
+impl ConnectionError {
+    /// Returns true if the error is temporary (for example the bandwidth limit was exceeded),
+    /// so that the connection can keep being used
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            ConnectionError::Transport(e)
+            | ConnectionError::Netcode(super::netcode::error::Error::Transport(e)) => {
+                e.is_recoverable()
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::server::io::config::ServerTransport;
+
+    use super::*;
+
+    #[test]
+    fn test_connection_error_is_recoverable() {
+        use crate::connection::netcode::error::Error as NetcodeError;
+        use crate::transport::error::Error as TransportError;
+
+        assert!(ConnectionError::Transport(TransportError::BandwidthExceeded).is_recoverable());
+        assert!(ConnectionError::Netcode(NetcodeError::Transport(
+            TransportError::BandwidthExceeded
+        ))
+        .is_recoverable());
+        assert!(!ConnectionError::Transport(TransportError::NotConnected).is_recoverable());
+        assert!(!ConnectionError::Netcode(NetcodeError::ClientNotFound).is_recoverable());
+    }
+
+    #[test]
+    fn test_local_addrs() {
+        let mut servers = ServerConnections::new(
+            vec![NetConfig::Netcode {
+                config: NetcodeConfig::default(),
+                io: IoConfig::from_transport(ServerTransport::UdpSocket(
+                    "127.0.0.1:0".parse().unwrap(),
+                )),
+            }],
+            crate::connection::netcode::MAX_CLIENTS,
+        );
+        assert_eq!(servers.local_addrs(), vec![None]);
+
+        servers.start().unwrap();
+        let addr = servers.local_addrs()[0].unwrap();
+        assert_eq!(addr.ip(), std::net::Ipv4Addr::LOCALHOST);
+        // the OS picked an ephemeral port
+        assert_ne!(addr.port(), 0);
+        servers.stop().unwrap();
+    }
+}
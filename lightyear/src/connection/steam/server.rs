@@ -1,7 +1,8 @@
 use crate::connection::id::ClientId;
 use crate::connection::netcode::MAX_PACKET_SIZE;
 use crate::connection::server::{
-    ConnectionError, ConnectionRequestHandler, DefaultConnectionRequestHandler, NetServer,
+    ConnectionError, ConnectionRequestHandler, DefaultConnectionRequestHandler, DisconnectReason,
+    NetServer,
 };
 use crate::packet::packet_builder::RecvPayload;
 use crate::prelude::LinkConditionerConfig;
@@ -76,8 +77,13 @@ pub struct Server {
     connections: HashMap<ClientId, NetConnection<ClientManager>>,
     packet_queue: VecDeque<(RecvPayload, ClientId)>,
     new_connections: Vec<ClientId>,
-    new_disconnections: Vec<ClientId>,
+    new_disconnections: Vec<(ClientId, DisconnectReason)>,
+    /// Disconnections that were triggered outside of [`NetServer::try_update`], which will be reported
+    /// during the next update
+    pending_disconnections: Vec<(ClientId, DisconnectReason)>,
     conditioner: Option<LinkConditionerConfig>,
+    /// Maximum number of connected clients, at most [`SteamConfig::max_clients`]
+    max_clients: usize,
 }
 
 impl Server {
@@ -107,12 +113,14 @@ impl Server {
         Ok(Self {
             steamworks_client,
             server,
+            max_clients: config.max_clients,
             config,
             listen_socket: None,
             connections: HashMap::new(),
             packet_queue: VecDeque::new(),
             new_connections: Vec::new(),
             new_disconnections: Vec::new(),
+            pending_disconnections: Vec::new(),
             conditioner,
         })
     }
@@ -162,18 +170,23 @@ impl NetServer for Server {
         self.listen_socket = None;
         for (client_id, connection) in self.connections.drain() {
             let _ = connection.close(NetConnectionEnd::AppGeneric, None, true);
-            self.new_disconnections.push(client_id);
+            self.new_disconnections
+                .push((client_id, DisconnectReason::ServerStopped));
         }
         info!("Steam socket has been closed.");
         Ok(())
     }
 
-    fn disconnect(&mut self, client_id: ClientId) -> Result<(), ConnectionError> {
+    fn disconnect(
+        &mut self,
+        client_id: ClientId,
+        reason: DisconnectReason,
+    ) -> Result<(), ConnectionError> {
         match client_id {
             ClientId::Steam(id) => {
                 if let Some(connection) = self.connections.remove(&client_id) {
                     let _ = connection.close(NetConnectionEnd::AppGeneric, None, true);
-                    self.new_disconnections.push(client_id);
+                    self.pending_disconnections.push((client_id, reason));
                 }
                 Ok(())
             }
@@ -185,6 +198,10 @@ impl NetServer for Server {
         self.connections.keys().cloned().collect()
     }
 
+    fn set_max_clients(&mut self, max_clients: usize) {
+        self.max_clients = max_clients.min(self.config.max_clients);
+    }
+
     fn try_update(&mut self, delta_ms: f64) -> Result<(), ConnectionError> {
         self.steamworks_client
             .try_write()
@@ -195,6 +212,8 @@ impl NetServer for Server {
         // reset connection events
         self.new_connections.clear();
         self.new_disconnections.clear();
+        self.new_disconnections
+            .append(&mut self.pending_disconnections);
 
         // process connection events
         let Some(listen_socket) = self.listen_socket.as_mut() else {
@@ -222,14 +241,15 @@ impl NetServer for Server {
                         );
                         if let Some(connection) = self.connections.remove(&client_id) {
                             let _ = connection.close(NetConnectionEnd::AppGeneric, None, true);
-                            self.new_disconnections.push(client_id);
+                            self.new_disconnections
+                                .push((client_id, DisconnectReason::ClientRequested));
                         }
                     } else {
                         error!("Received disconnection attempt from invalid steam id");
                     }
                 }
                 ListenSocketEvent::Connecting(event) => {
-                    if self.connections.len() >= self.config.max_clients {
+                    if self.connections.len() >= self.max_clients {
                         event.reject(NetConnectionEnd::AppGeneric, Some("Too many clients"));
                         continue;
                     }
@@ -292,10 +312,20 @@ impl NetServer for Server {
         self.new_connections.clone()
     }
 
-    fn new_disconnections(&self) -> Vec<ClientId> {
+    fn new_disconnections(&self) -> Vec<(ClientId, DisconnectReason)> {
         self.new_disconnections.clone()
     }
 
+    /// Steam connections cannot carry a connect payload
+    fn connect_payload(&self, _: ClientId) -> Option<&[u8]> {
+        None
+    }
+
+    /// The steamworks bindings do not expose the remote address of a connection
+    fn client_addr(&self, _: ClientId) -> Option<SocketAddr> {
+        None
+    }
+
     fn io(&self) -> Option<&Io> {
         None
     }
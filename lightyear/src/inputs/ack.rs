@@ -0,0 +1,136 @@
+//! Acknowledgements of the inputs received by the server
+//!
+//! Input messages are sent on an unreliable channel, so the client cannot rely on packet acks to know
+//! if its inputs reach the server. Instead, the server sends back an [`InputAck`] with the most recent
+//! input tick that it received from the client.
+//!
+//! If the client keeps sending inputs without receiving any ack for longer than
+//! [`InputConfig::ack_timeout`](crate::client::input::native::InputConfig::ack_timeout), an
+//! [`InputNotAckedEvent`](crate::client::events::InputNotAckedEvent) is emitted: the connection
+//! could still be alive (packets from the server are received), but the inputs don't reach the server.
+use std::time::Duration;
+
+use byteorder::WriteBytesExt;
+
+use crate::prelude::Tick;
+use crate::serialize::reader::Reader;
+use crate::serialize::{SerializationError, ToBytes};
+
+/// Message sent by the server with the most recent input tick received from the client
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct InputAck {
+    pub end_tick: Tick,
+}
+
+impl ToBytes for InputAck {
+    fn len(&self) -> usize {
+        self.end_tick.len()
+    }
+
+    fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
+        self.end_tick.to_bytes(buffer)
+    }
+
+    fn from_bytes(buffer: &mut Reader) -> Result<Self, SerializationError>
+    where
+        Self: Sized,
+    {
+        Ok(InputAck {
+            end_tick: Tick::from_bytes(buffer)?,
+        })
+    }
+}
+
+/// Client-side tracker of the inputs that are waiting for an ack from the server
+#[derive(Debug, Default)]
+pub(crate) struct InputAckTracker {
+    /// Most recent input tick that was acked by the server
+    pub(crate) last_acked_tick: Option<Tick>,
+    /// Most recent input tick that was sent to the server
+    last_sent_tick: Option<Tick>,
+    /// Time elapsed since we started waiting for an ack, if some inputs are not acked
+    unacked_duration: Option<Duration>,
+    /// True if we already notified that the inputs are not acked
+    notified: bool,
+}
+
+impl InputAckTracker {
+    /// Record that an input message with inputs up to `end_tick` was sent
+    pub(crate) fn record_sent(&mut self, end_tick: Tick) {
+        if self.last_sent_tick.map_or(true, |tick| end_tick > tick) {
+            self.last_sent_tick = Some(end_tick);
+        }
+        if self.unacked_duration.is_none() && !self.is_acked() {
+            self.unacked_duration = Some(Duration::ZERO);
+        }
+    }
+
+    /// Handle an ack received from the server
+    pub(crate) fn receive_ack(&mut self, ack: InputAck) {
+        if self
+            .last_acked_tick
+            .map_or(true, |tick| ack.end_tick > tick)
+        {
+            self.last_acked_tick = Some(ack.end_tick);
+        }
+        // the server is receiving our inputs, restart the timer if some inputs are still in flight
+        self.unacked_duration = (!self.is_acked()).then_some(Duration::ZERO);
+        self.notified = false;
+    }
+
+    fn is_acked(&self) -> bool {
+        match (self.last_sent_tick, self.last_acked_tick) {
+            (Some(sent), Some(acked)) => acked >= sent,
+            (Some(_), None) => false,
+            (None, _) => true,
+        }
+    }
+
+    /// Advance the timer; returns the duration for which the inputs have not been acked
+    /// the first time it exceeds `timeout`
+    pub(crate) fn update(&mut self, delta: Duration, timeout: Duration) -> Option<Duration> {
+        let unacked_duration = self.unacked_duration.as_mut()?;
+        *unacked_duration += delta;
+        if self.notified || *unacked_duration <= timeout {
+            return None;
+        }
+        self.notified = true;
+        Some(*unacked_duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_ack_tracker() {
+        let timeout = Duration::from_millis(100);
+        let delta = Duration::from_millis(60);
+        let mut tracker = InputAckTracker::default();
+        // no inputs sent
+        assert_eq!(tracker.update(delta, timeout), None);
+        assert_eq!(tracker.update(delta, timeout), None);
+
+        tracker.record_sent(Tick(1));
+        assert_eq!(tracker.update(delta, timeout), None);
+        tracker.record_sent(Tick(2));
+        assert_eq!(tracker.update(delta, timeout), Some(delta * 2));
+        // we only notify once
+        assert_eq!(tracker.update(delta, timeout), None);
+
+        // an ack for an older input restarts the timer
+        tracker.receive_ack(InputAck { end_tick: Tick(1) });
+        assert_eq!(tracker.last_acked_tick, Some(Tick(1)));
+        assert_eq!(tracker.update(delta, timeout), None);
+        assert_eq!(tracker.update(delta, timeout), Some(delta * 2));
+
+        // all inputs are acked
+        tracker.receive_ack(InputAck { end_tick: Tick(2) });
+        assert_eq!(tracker.update(delta, timeout), None);
+        assert_eq!(tracker.update(delta, timeout), None);
+        // acks that arrive out of order are ignored
+        tracker.receive_ack(InputAck { end_tick: Tick(1) });
+        assert_eq!(tracker.last_acked_tick, Some(Tick(2)));
+    }
+}
@@ -5,4 +5,5 @@
 #[cfg_attr(docsrs, doc(cfg(feature = "leafwing")))]
 pub mod leafwing;
 
+pub(crate) mod ack;
 pub mod native;
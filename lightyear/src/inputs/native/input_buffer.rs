@@ -89,6 +89,12 @@ impl<T: UserAction> InputBuffer<T> {
         self.buffer.pop_front().unwrap()
     }
 
+    /// Most recent tick for which the buffer contains an entry
+    pub(crate) fn end_tick(&self) -> Option<Tick> {
+        let start_tick = self.start_tick?;
+        Some(start_tick + (self.buffer.len() as i16 - 1))
+    }
+
     pub(crate) fn get(&self, tick: Tick) -> Option<&T> {
         let start_tick = self.start_tick?;
         if self.buffer.is_empty() {
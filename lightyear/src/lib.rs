@@ -121,6 +121,11 @@ If you update the target to exclude a given client, the entity will get despawne
 
 On the receiver side, entities that are replicated from a remote peer will have the [`Replicated`] marker component.
 
+Replication is opt-in: entities are never replicated just because they have components that are registered in the protocol,
+they need to have the [`Replicate`](prelude::server::Replicate) bundle. Some entities can still be replicated implicitly,
+for example the children of a replicated entity when [`ReplicateHierarchy`] is recursive. You can add the [`DontReplicate`]
+marker component to make sure that an entity (for example a server-internal entity) never gets replicated.
+
 
 ### Reacting to replication events
 
@@ -161,6 +166,8 @@ fn component_inserted(query: Query<Entity, (With<Replicated>, Added<MyComponent>
 [`Replicated`]: prelude::Replicated
 [`ReplicationTarget`]: prelude::ReplicationTarget
 [`Replicating`]: prelude::Replicating
+[`DontReplicate`]: prelude::DontReplicate
+[`ReplicateHierarchy`]: prelude::ReplicateHierarchy
 [`SharedConfig`]: prelude::SharedConfig
  */
 #![allow(clippy::missing_transmute_annotations)]
@@ -194,39 +201,63 @@ pub mod prelude {
     #[cfg(feature = "leafwing")]
     pub use crate::inputs::leafwing::{input_message::InputMessage, LeafwingUserAction};
     pub use crate::inputs::native::UserAction;
+    pub use crate::packet::congestion::{CongestionConfig, CongestionState};
     pub use crate::packet::error::PacketError;
+    pub use crate::packet::inspector::{InspectedPacket, PacketInspector};
     pub use crate::packet::message::Message;
+    pub use crate::packet::message_ack::MessageHandle;
+    pub use crate::packet::mtu_discovery::MtuDiscoveryConfig;
     pub use crate::protocol::channel::{AppChannelExt, ChannelKind, ChannelRegistry};
-    pub use crate::protocol::component::{AppComponentExt, ComponentRegistry, Linear};
+    pub use crate::protocol::component::{
+        AppComponentExt, ComponentRegistry, Linear, ReplicationMode,
+    };
     pub use crate::protocol::message::{AppMessageExt, MessageRegistry};
-    pub use crate::protocol::serialize::AppSerializeExt;
+    #[cfg(feature = "serialization_stats")]
+    pub use crate::protocol::serialization_stats::SerializationStats;
+    pub use crate::protocol::serialize::{
+        AppSerializeExt, Bincode, SerializationBackend, SerializeFns,
+    };
     pub use crate::shared::config::{Mode, SharedConfig};
+    pub use crate::shared::drain::{drain_network, has_pending_messages};
     #[cfg(feature = "leafwing")]
     pub use crate::shared::input::leafwing::LeafwingInputPlugin;
     pub use crate::shared::input::native::InputPlugin;
-    pub use crate::shared::ping::manager::PingConfig;
+    pub use crate::shared::ping::manager::{PingConfig, RttEstimator};
     pub use crate::shared::plugin::{NetworkIdentity, SharedPlugin};
     pub use crate::shared::replication::authority::HasAuthority;
     pub use crate::shared::replication::components::{
-        DeltaCompression, DisabledComponent, NetworkRelevanceMode, OverrideTargetComponent,
-        PrePredicted, ReplicateHierarchy, ReplicateOnceComponent, Replicated, Replicating,
-        ReplicationGroup, ReplicationTarget, ShouldBePredicted, TargetEntity,
+        ComponentClientFilter, DeltaCompression, DisabledComponent, DontReplicate,
+        InterpolationSnap, NetworkRelevanceMode, OverrideTargetComponent, PrePredicted,
+        ReplicateHierarchy, ReplicateOnceComponent, Replicated, Replicating, ReplicationGroup,
+        ReplicationTarget, ShouldBePredicted, TargetEntity,
+    };
+    pub use crate::shared::replication::entity_map::{
+        EntityNamespace, NetworkEntityId, RemoteEntityMap,
     };
-    pub use crate::shared::replication::entity_map::RemoteEntityMap;
     pub use crate::shared::replication::hierarchy::ParentSync;
     pub use crate::shared::replication::network_target::NetworkTarget;
     pub use crate::shared::replication::plugin::ReplicationConfig;
-    pub use crate::shared::replication::plugin::SendUpdatesMode;
+    pub use crate::shared::replication::plugin::{SendUpdatesMode, UpdatesOrdering};
     pub use crate::shared::replication::resources::{
         ReplicateResourceExt, ReplicateResourceMetadata, StopReplicateResourceExt,
     };
+    pub use crate::shared::replication::send::SpawnAckState;
+    pub use crate::shared::rng::DeterministicRng;
     pub use crate::shared::run_conditions::*;
-    pub use crate::shared::sets::{FixedUpdateSet, MainSet};
+    pub use crate::shared::sets::{FixedUpdateSet, MainSet, NetworkingSchedules};
     pub use crate::shared::tick_manager::TickManager;
     pub use crate::shared::tick_manager::{Tick, TickConfig};
     pub use crate::shared::time_manager::TimeManager;
-    pub use crate::transport::middleware::compression::CompressionConfig;
+    pub use crate::shared::time_source::{MockTimeSource, RealTimeSource, TimeSource};
+    pub use crate::transport::config::SocketConfig;
+    pub use crate::transport::custom::{CustomTransport, PeerAddrMap};
+    pub use crate::transport::error::TransportError;
+    #[cfg(feature = "zstd")]
+    pub use crate::transport::middleware::compression::{train_dictionary, DictionarySampler};
+    pub use crate::transport::middleware::compression::{CompressionConfig, TypeCompressionConfig};
     pub use crate::transport::middleware::conditioner::LinkConditionerConfig;
+    pub use crate::transport::middleware::{ReceiverMiddleware, SenderMiddleware};
+    pub use crate::transport::udp::TransportRebindEvent;
 
     mod rename {
         pub use crate::client::events::ComponentInsertEvent as ClientComponentInsertEvent;
@@ -257,36 +288,47 @@ pub mod prelude {
         pub use crate::client::components::{
             ComponentSyncMode, Confirmed, LerpFn, SyncComponent, SyncMetadata,
         };
-        pub use crate::client::config::{ClientConfig, NetcodeConfig, PacketConfig};
+        pub use crate::client::config::{
+            ClientConfig, NetcodeConfig, PacketConfig, ReconnectConfig,
+        };
         pub use crate::client::connection::ConnectionManager;
         pub use crate::client::error::ClientError;
         pub use crate::client::events::{
             ComponentInsertEvent, ComponentRemoveEvent, ComponentUpdateEvent, ConnectEvent,
-            DisconnectEvent, EntityDespawnEvent, EntitySpawnEvent, InputEvent, MessageEvent,
+            DisconnectEvent, EntityDespawnEvent, EntitySpawnEvent, InputEvent, InputNotAckedEvent,
+            MessageAckEvent, MessageEvent, MessageTimeoutEvent, ReadyEvent, ReconnectAttemptEvent,
+            WorldSnapshotComplete,
         };
         #[cfg(feature = "leafwing")]
         pub use crate::client::input::leafwing::LeafwingInputConfig;
         pub use crate::client::input::native::{InputConfig, InputManager};
         pub use crate::client::interpolation::interpolation_history::ConfirmedHistory;
         pub use crate::client::interpolation::plugin::{
-            InterpolationConfig, InterpolationDelay, InterpolationSet,
+            AdaptiveInterpolationDelay, InterpolationConfig, InterpolationDelay, InterpolationSet,
+            InterpolationSpawnMode,
         };
         pub use crate::client::interpolation::{
             InterpolateStatus, Interpolated, VisualInterpolateStatus, VisualInterpolationPlugin,
         };
         pub use crate::client::io::config::ClientTransport;
         pub use crate::client::io::Io;
+        pub use crate::client::message::MessageReceiver;
         pub use crate::client::networking::{ClientCommands, NetworkingState};
         pub use crate::client::plugin::ClientPlugins;
         pub use crate::client::prediction::correction::Correction;
         pub use crate::client::prediction::despawn::PredictionDespawnCommandsExt;
         pub use crate::client::prediction::plugin::is_in_rollback;
-        pub use crate::client::prediction::plugin::{PredictionConfig, PredictionSet};
+        pub use crate::client::prediction::plugin::{
+            PredictedFixedUpdate, PredictionConfig, PredictionSet, PrespawnTimeout,
+        };
+        pub use crate::client::prediction::prespawn::PredictionRejected;
         pub use crate::client::prediction::rollback::{Rollback, RollbackState};
         pub use crate::client::prediction::Predicted;
         pub use crate::client::replication::commands::DespawnReplicationCommandExt;
         pub use crate::client::replication::send::Replicate;
-        pub use crate::client::run_conditions::{is_connected, is_disconnected, is_synced};
+        pub use crate::client::run_conditions::{
+            is_connected, is_disconnected, is_ready, is_synced,
+        };
         pub use crate::client::sync::SyncConfig;
         pub use crate::connection::client::{
             Authentication, ClientConnection, IoConfig, NetClient, NetConfig,
@@ -299,7 +341,8 @@ pub mod prelude {
         pub use wtransport::tls::Identity;
 
         pub use crate::connection::server::{
-            IoConfig, NetConfig, NetServer, ServerConnection, ServerConnections,
+            DisconnectReason, DuplicateConnectPolicy, IoConfig, NetConfig, NetServer,
+            ServerConnection, ServerConnections,
         };
         #[cfg(all(feature = "steam", not(target_family = "wasm")))]
         pub use crate::connection::steam::server::{SocketConfig, SteamConfig};
@@ -309,12 +352,16 @@ pub mod prelude {
         pub use crate::server::error::ServerError;
         pub use crate::server::events::{
             ComponentInsertEvent, ComponentRemoveEvent, ComponentUpdateEvent, ConnectEvent,
-            DisconnectEvent, EntityDespawnEvent, EntitySpawnEvent, InputEvent, MessageEvent,
+            ConnectionHealthEvent, DisconnectEvent, EntityDespawnEvent, EntitySpawnEvent,
+            InputEvent, MessageAckEvent, MessageEvent, MessageTimeoutEvent, ReconnectEvent,
         };
         pub use crate::server::io::config::ServerTransport;
         pub use crate::server::io::Io;
+        pub use crate::server::message::MessageReceiver;
         pub use crate::server::networking::{NetworkingState, ServerCommands};
         pub use crate::server::plugin::ServerPlugins;
+        pub use crate::server::rate_limit::{ChannelRateLimit, ChannelRates, RateLimitConfig};
+        pub use crate::server::relevance::distance::{DistanceUpdateRate, ReplicationFocus};
         pub use crate::server::relevance::immediate::RelevanceManager;
         pub use crate::server::relevance::room::{RoomId, RoomManager};
         pub use crate::server::replication::commands::AuthorityCommandExt;
@@ -324,6 +371,7 @@ pub mod prelude {
             ReplicationSet, ServerReplicationSet,
         };
         pub use crate::server::run_conditions::{is_started, is_stopped};
+        pub use crate::shared::replication::applied::ComponentAppliedEvent;
         pub use crate::shared::replication::authority::AuthorityPeer;
     }
 
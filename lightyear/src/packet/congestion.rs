@@ -0,0 +1,420 @@
+//! Reduce the replication send rate when the connection is congested
+//!
+//! Sending at the configured rate on a lossy link tends to make things worse: the lost updates are resent
+//! on top of the new ones, which adds even more load on the link.
+//!
+//! We estimate the packet loss from the acks and nacks of the packets we send. When the loss goes above
+//! [`CongestionConfig::congestion_loss`], the connection becomes [`CongestionState::Congested`]:
+//! - the updates of the low-priority replication groups are only sent every
+//!   [`CongestionConfig::update_interval_multiplier`] send intervals; the changes in between are coalesced
+//!   so that only the latest value is sent
+//! - the bandwidth cap (if it is enabled) is reduced by [`CongestionConfig::bandwidth_factor`], so that
+//!   the priority manager sheds the low-priority messages first
+//!
+//! The connection recovers once the loss goes back below [`CongestionConfig::recovery_loss`].
+//!
+//! Congestion control is disabled by default, it can be enabled with the `congestion_control` field
+//! of the client or server `PacketConfig`.
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bevy::prelude::Reflect;
+use bevy::time::Stopwatch;
+use tracing::debug;
+
+use crate::packet::packet::PacketId;
+
+/// Number of packets over which the packet loss is computed
+const LOSS_WINDOW: usize = 64;
+/// Minimum number of packets needed to estimate the packet loss
+const MIN_LOSS_SAMPLES: usize = 32;
+
+/// Configuration for the per-connection congestion control
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct CongestionConfig {
+    /// Packet loss (between 0.0 and 1.0) above which the connection is considered congested
+    pub congestion_loss: f32,
+    /// Packet loss (between 0.0 and 1.0) below which a congested connection is considered recovered
+    pub recovery_loss: f32,
+    /// Minimum duration during which the connection stays congested, to avoid switching back and forth
+    /// between the two states
+    pub min_congestion_duration: Duration,
+    /// While congested, the updates of low-priority replication groups are only sent once every
+    /// `update_interval_multiplier` send intervals
+    pub update_interval_multiplier: u32,
+    /// While congested, the replication groups with a priority higher or equal to this value
+    /// keep sending their updates every send interval
+    pub priority_threshold: f32,
+    /// While congested, the bandwidth cap is multiplied by this factor (between 0.0 and 1.0).
+    ///
+    /// This only applies if the bandwidth cap is enabled.
+    pub bandwidth_factor: f32,
+}
+
+impl Default for CongestionConfig {
+    fn default() -> Self {
+        Self {
+            congestion_loss: 0.1,
+            recovery_loss: 0.02,
+            min_congestion_duration: Duration::from_secs(1),
+            update_interval_multiplier: 3,
+            priority_threshold: 10.0,
+            bandwidth_factor: 0.5,
+        }
+    }
+}
+
+impl CongestionConfig {
+    pub fn with_congestion_loss(mut self, congestion_loss: f32) -> Self {
+        self.congestion_loss = congestion_loss;
+        self
+    }
+
+    pub fn with_recovery_loss(mut self, recovery_loss: f32) -> Self {
+        self.recovery_loss = recovery_loss;
+        self
+    }
+
+    pub fn with_min_congestion_duration(mut self, min_congestion_duration: Duration) -> Self {
+        self.min_congestion_duration = min_congestion_duration;
+        self
+    }
+
+    pub fn with_update_interval_multiplier(mut self, update_interval_multiplier: u32) -> Self {
+        self.update_interval_multiplier = update_interval_multiplier;
+        self
+    }
+
+    pub fn with_priority_threshold(mut self, priority_threshold: f32) -> Self {
+        self.priority_threshold = priority_threshold;
+        self
+    }
+
+    pub fn with_bandwidth_factor(mut self, bandwidth_factor: f32) -> Self {
+        self.bandwidth_factor = bandwidth_factor;
+        self
+    }
+}
+
+/// Congestion state of a connection
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum CongestionState {
+    /// Messages are sent at the configured rate
+    #[default]
+    Normal,
+    /// The packet loss is high: low-priority replication updates are sent less often
+    Congested,
+}
+
+/// Keeps track of the congestion state of a connection
+#[derive(Debug)]
+pub(crate) struct CongestionController {
+    config: CongestionConfig,
+    state: CongestionState,
+    /// Whether each of the last [`LOSS_WINDOW`] packets was lost
+    outcomes: VecDeque<bool>,
+    /// Number of lost packets in `outcomes`
+    lost: usize,
+    /// First packet that was acked by the remote.
+    /// The packets sent before it are ignored, since the remote might not have been listening yet
+    first_acked: Option<PacketId>,
+    /// Time spent in the congested state
+    congestion_timer: Stopwatch,
+    /// Number of sends since the low-priority updates were last sent
+    skipped_sends: u32,
+}
+
+impl CongestionController {
+    pub(crate) fn new(config: CongestionConfig) -> Self {
+        Self {
+            config,
+            state: CongestionState::Normal,
+            outcomes: VecDeque::with_capacity(LOSS_WINDOW),
+            lost: 0,
+            first_acked: None,
+            congestion_timer: Stopwatch::new(),
+            skipped_sends: 0,
+        }
+    }
+
+    pub(crate) fn state(&self) -> CongestionState {
+        self.state
+    }
+
+    /// Estimated packet loss (between 0.0 and 1.0)
+    pub(crate) fn packet_loss(&self) -> f32 {
+        if self.outcomes.len() < MIN_LOSS_SAMPLES {
+            return 0.0;
+        }
+        self.lost as f32 / self.outcomes.len() as f32
+    }
+
+    pub(crate) fn packet_acked(&mut self, packet_id: PacketId) {
+        if self.first_acked.is_none() {
+            self.first_acked = Some(packet_id);
+        }
+        self.push_outcome(false);
+    }
+
+    pub(crate) fn packet_lost(&mut self, packet_id: PacketId) {
+        if self.first_acked.map_or(true, |first| packet_id < first) {
+            return;
+        }
+        self.push_outcome(true);
+    }
+
+    fn push_outcome(&mut self, lost: bool) {
+        if self.outcomes.len() == LOSS_WINDOW && self.outcomes.pop_front() == Some(true) {
+            self.lost -= 1;
+        }
+        self.outcomes.push_back(lost);
+        if lost {
+            self.lost += 1;
+        }
+    }
+
+    /// Update the congestion state from the packet loss estimate.
+    ///
+    /// Returns true if the congestion state changed.
+    pub(crate) fn update(&mut self, delta: Duration) -> bool {
+        let packet_loss = self.packet_loss();
+        match self.state {
+            CongestionState::Normal => {
+                if packet_loss > self.config.congestion_loss {
+                    debug!(?packet_loss, "connection is congested");
+                    self.state = CongestionState::Congested;
+                    self.congestion_timer.reset();
+                    self.skipped_sends = 0;
+                    return true;
+                }
+            }
+            CongestionState::Congested => {
+                self.congestion_timer.tick(delta);
+                if packet_loss < self.config.recovery_loss
+                    && self.congestion_timer.elapsed() >= self.config.min_congestion_duration
+                {
+                    debug!(?packet_loss, "connection recovered from congestion");
+                    self.state = CongestionState::Normal;
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Called every time we send replication updates.
+    ///
+    /// Returns the priority below which the replication groups should not send their updates
+    /// this time, or None if every group can send its updates.
+    pub(crate) fn updates_priority_threshold(&mut self) -> Option<f32> {
+        if self.state == CongestionState::Normal {
+            return None;
+        }
+        self.skipped_sends += 1;
+        if self.skipped_sends >= self.config.update_interval_multiplier {
+            self.skipped_sends = 0;
+            return None;
+        }
+        Some(self.config.priority_threshold)
+    }
+
+    /// Factor by which the bandwidth cap is multiplied in the current state
+    pub(crate) fn bandwidth_factor(&self) -> f32 {
+        match self.state {
+            CongestionState::Normal => 1.0,
+            CongestionState::Congested => self.config.bandwidth_factor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::connection::server::ServerConnections;
+    use crate::prelude::server::{ConnectionManager, Replicate, ServerConfig};
+    use crate::prelude::{client, ClientId, LinkConditionerConfig, ReplicationGroup, SharedConfig};
+    use crate::tests::protocol::ComponentSyncModeFull;
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+
+    use super::*;
+
+    /// Simulate the outcome of `lost + acked` packets
+    fn update_with_loss(
+        controller: &mut CongestionController,
+        packet_id: &mut PacketId,
+        lost: u16,
+        acked: u16,
+    ) -> bool {
+        for i in 0..lost + acked {
+            if i < lost {
+                controller.packet_lost(*packet_id);
+            } else {
+                controller.packet_acked(*packet_id);
+            }
+            *packet_id += 1;
+        }
+        controller.update(Duration::from_millis(100))
+    }
+
+    #[test]
+    fn test_congestion_state() {
+        let config = CongestionConfig::default()
+            .with_min_congestion_duration(Duration::from_millis(100))
+            .with_update_interval_multiplier(2);
+        let mut controller = CongestionController::new(config);
+        let mut packet_id = PacketId(0);
+        // the packets lost before the first ack are ignored
+        assert!(!update_with_loss(&mut controller, &mut packet_id, 10, 0));
+        assert!(!update_with_loss(&mut controller, &mut packet_id, 0, 40));
+        assert_eq!(controller.packet_loss(), 0.0);
+        assert_eq!(controller.state(), CongestionState::Normal);
+        assert_eq!(controller.updates_priority_threshold(), None);
+
+        // the loss spikes
+        assert!(update_with_loss(&mut controller, &mut packet_id, 10, 10));
+        assert_eq!(controller.state(), CongestionState::Congested);
+        assert_eq!(controller.bandwidth_factor(), config.bandwidth_factor);
+        // low-priority updates are only sent every 2 sends
+        assert_eq!(
+            controller.updates_priority_threshold(),
+            Some(config.priority_threshold)
+        );
+        assert_eq!(controller.updates_priority_threshold(), None);
+        assert_eq!(
+            controller.updates_priority_threshold(),
+            Some(config.priority_threshold)
+        );
+
+        // the loss clears, we recover once the lost packets are out of the window
+        assert!(!update_with_loss(&mut controller, &mut packet_id, 0, 40));
+        assert_eq!(controller.state(), CongestionState::Congested);
+        assert!(update_with_loss(&mut controller, &mut packet_id, 0, 40));
+        assert_eq!(controller.state(), CongestionState::Normal);
+        assert_eq!(controller.bandwidth_factor(), 1.0);
+        assert_eq!(controller.updates_priority_threshold(), None);
+    }
+
+    #[test]
+    fn test_congestion_min_duration() {
+        let config =
+            CongestionConfig::default().with_min_congestion_duration(Duration::from_secs(10));
+        let mut controller = CongestionController::new(config);
+        let mut packet_id = PacketId(0);
+        update_with_loss(&mut controller, &mut packet_id, 0, 1);
+        update_with_loss(&mut controller, &mut packet_id, 40, 0);
+        assert_eq!(controller.state(), CongestionState::Congested);
+        // even if the loss clears, we stay congested for the minimum duration
+        for _ in 0..50 {
+            update_with_loss(&mut controller, &mut packet_id, 0, 10);
+        }
+        assert_eq!(controller.state(), CongestionState::Congested);
+        for _ in 0..50 {
+            update_with_loss(&mut controller, &mut packet_id, 0, 10);
+        }
+        assert_eq!(controller.state(), CongestionState::Normal);
+    }
+
+    /// Check that the updates of low-priority groups are delayed when the connection is congested,
+    /// while the high-priority groups keep sending their updates
+    #[test]
+    fn test_congestion_sheds_low_priority_updates() {
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        let mut stepper = BevyStepper::new(
+            SharedConfig::default(),
+            client::ClientConfig::default(),
+            Duration::from_millis(10),
+        );
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .packet
+            .congestion_control = Some(CongestionConfig::default());
+        stepper.init();
+        let spawn = |stepper: &mut BevyStepper, priority: f32| {
+            stepper
+                .server_app
+                .world_mut()
+                .spawn((
+                    Replicate {
+                        group: ReplicationGroup::default().set_priority(priority),
+                        ..Default::default()
+                    },
+                    ComponentSyncModeFull(0.0),
+                ))
+                .id()
+        };
+        let low = spawn(&mut stepper, 1.0);
+        let high = spawn(&mut stepper, 20.0);
+        stepper.frame_step();
+        stepper.frame_step();
+        let set_loss = |stepper: &mut BevyStepper, loss: f32| {
+            stepper
+                .server_app
+                .world_mut()
+                .resource_mut::<ServerConnections>()
+                .set_client_conditioner(
+                    client_id,
+                    Some(LinkConditionerConfig::new(
+                        Duration::default(),
+                        Duration::default(),
+                        loss,
+                    )),
+                )
+                .unwrap();
+        };
+        let congestion_state = |stepper: &BevyStepper| {
+            stepper
+                .server_app
+                .world()
+                .resource::<ConnectionManager>()
+                .connection(client_id)
+                .unwrap()
+                .congestion_state()
+        };
+        assert_eq!(congestion_state(&stepper), CongestionState::Normal);
+
+        // the server does not receive any acks from the client, so its packets are considered lost
+        set_loss(&mut stepper, 1.0);
+        for _ in 0..50 {
+            stepper.frame_step();
+        }
+        assert_eq!(congestion_state(&stepper), CongestionState::Congested);
+        set_loss(&mut stepper, 0.0);
+        stepper.frame_step();
+
+        let client_value = |stepper: &BevyStepper, server_entity| {
+            let client_entity = stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .unwrap();
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(client_entity)
+                .unwrap()
+                .0
+        };
+        let mut low_delayed = false;
+        for i in 1..=3 {
+            for entity in [low, high] {
+                stepper
+                    .server_app
+                    .world_mut()
+                    .get_mut::<ComponentSyncModeFull>(entity)
+                    .unwrap()
+                    .0 = i as f32;
+            }
+            // the update is received by the client on the next frame
+            stepper.frame_step();
+            stepper.frame_step();
+            assert_eq!(client_value(&stepper, high), i as f32);
+            low_delayed |= client_value(&stepper, low) != i as f32;
+        }
+        assert_eq!(congestion_state(&stepper), CongestionState::Congested);
+        assert!(low_delayed);
+    }
+}
@@ -10,6 +10,10 @@ pub enum PacketError {
     Serialization(#[from] SerializationError),
     #[error("channel was not found")]
     ChannelNotFound,
+    #[error("the message is too big ({0} bytes) to be sent on a channel without fragmentation")]
+    FragmentationDisabled(usize),
+    #[error("the acks of the messages sent on this channel are not tracked")]
+    AcksNotTracked,
     #[error("receiver channel error: {0}")]
     ChannelReceiveError(#[from] ChannelReceiveError),
 }
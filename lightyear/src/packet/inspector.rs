@@ -0,0 +1,400 @@
+//! Inspect the decoded contents of the packets that are sent and received
+//!
+//! A [`PacketInspector`] can be registered in the client's or server's `PacketConfig`
+//! (see [`client::PacketConfig::with_packet_inspector`](crate::client::config::PacketConfig::with_packet_inspector)
+//! and [`server::PacketConfig::with_packet_inspector`](crate::server::config::PacketConfig::with_packet_inspector)).
+//! It is called with an [`InspectedPacket`] for every packet sent or received by a connection, which contains
+//! the parsed header (including the acks) and a summary of every message in the packet.
+//!
+//! The contents are parsed only if an inspector is registered; otherwise the packets are not inspected at all.
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use bevy::prelude::Entity;
+use byteorder::ReadBytesExt;
+use bytes::Bytes;
+
+use crate::channel::builder::{
+    EntityActionsChannel, EntityReliableUpdatesChannel, EntityUpdatesChannel, InputAckChannel,
+    PingChannel, PongChannel, TimeSyncChannel,
+};
+use crate::connection::id::ClientId;
+use crate::inputs::ack::InputAck;
+use crate::packet::error::PacketError;
+use crate::packet::header::PacketHeader;
+use crate::packet::message::{FragmentData, FragmentIndex, MessageId, SingleData};
+use crate::packet::packet::PacketId;
+use crate::packet::packet_type::PacketType;
+use crate::prelude::Tick;
+use crate::protocol::channel::{ChannelId, ChannelKind, ChannelRegistry};
+use crate::protocol::component::ComponentNetId;
+use crate::protocol::registry::NetId;
+use crate::serialize::reader::Reader;
+use crate::serialize::{SerializationError, ToBytes};
+use crate::shared::replication::components::ReplicationGroupId;
+use crate::shared::replication::{EntityActionsMessage, EntityUpdatesMessage, SpawnAction};
+
+/// Hook that is called with the contents of every packet sent or received
+pub trait PacketInspector: Debug + Send + Sync {
+    fn inspect(&self, packet: &InspectedPacket);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketDirection {
+    Send,
+    Receive,
+}
+
+/// Parsed contents of a packet
+#[derive(Debug, Clone, PartialEq)]
+pub struct InspectedPacket {
+    pub direction: PacketDirection,
+    /// On the server, the client that the packet is sent to or received from. `None` on the client.
+    pub client_id: Option<ClientId>,
+    pub packet_type: PacketType,
+    /// Packet id from the sender's perspective
+    pub packet_id: PacketId,
+    /// Tick of the sender when the packet was sent
+    pub tick: Tick,
+    /// Most recent packet id that the sender received from the remote
+    pub last_ack_packet_id: PacketId,
+    /// Bitfield of the 32 packet ids before `last_ack_packet_id` that the sender received from the remote
+    pub ack_bitfield: u32,
+    /// Size of the packet in bytes
+    pub size: usize,
+    pub messages: Vec<InspectedMessage>,
+}
+
+/// Summary of a message included in a packet
+#[derive(Debug, Clone, PartialEq)]
+pub struct InspectedMessage {
+    pub channel: ChannelKind,
+    pub channel_name: String,
+    pub message_id: Option<MessageId>,
+    /// Size of the message in bytes
+    pub size: usize,
+    pub contents: MessageContents,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageContents {
+    Ping,
+    Pong,
+    TimeSync,
+    /// Ack of the inputs received by the server
+    InputAck {
+        end_tick: Tick,
+    },
+    EntityActions {
+        group_id: ReplicationGroupId,
+        actions: Vec<InspectedEntityActions>,
+    },
+    EntityUpdates {
+        group_id: ReplicationGroupId,
+        /// For each entity, the net ids of the components that were updated
+        updates: Vec<(Entity, Vec<ComponentNetId>)>,
+    },
+    /// A message sent with `send_message`, identified by the net id of its type in the `MessageRegistry`
+    Message {
+        net_id: NetId,
+    },
+    /// A fragment of a message that is too big to fit in a single packet.
+    ///
+    /// The contents of the message are not available until all the fragments are received.
+    Fragment {
+        fragment_id: FragmentIndex,
+        num_fragments: FragmentIndex,
+    },
+    /// The contents of the message could not be parsed
+    Unknown,
+}
+
+/// Replication actions for a single entity
+#[derive(Debug, Clone, PartialEq)]
+pub struct InspectedEntityActions {
+    pub entity: Entity,
+    pub spawn: bool,
+    pub despawn: bool,
+    /// Net ids of the components that were inserted
+    pub insert: Vec<ComponentNetId>,
+    /// Net ids of the components that were removed
+    pub remove: Vec<ComponentNetId>,
+    /// Net ids of the components that were updated
+    pub updates: Vec<ComponentNetId>,
+}
+
+/// Inspector registered on a connection
+#[derive(Debug, Clone)]
+pub(crate) struct ConnectionInspector {
+    pub(crate) inspector: Arc<dyn PacketInspector>,
+    pub(crate) client_id: Option<ClientId>,
+}
+
+impl ConnectionInspector {
+    /// Parse the packet and call the inspector. Packets that cannot be parsed are skipped.
+    pub(crate) fn inspect(
+        &self,
+        payload: &[u8],
+        direction: PacketDirection,
+        channel_registry: &ChannelRegistry,
+    ) {
+        match InspectedPacket::parse(
+            Bytes::copy_from_slice(payload),
+            direction,
+            self.client_id,
+            channel_registry,
+        ) {
+            Ok(packet) => self.inspector.inspect(&packet),
+            Err(e) => tracing::debug!(?e, "could not inspect packet"),
+        }
+    }
+}
+
+impl InspectedPacket {
+    pub(crate) fn parse(
+        payload: Bytes,
+        direction: PacketDirection,
+        client_id: Option<ClientId>,
+        channel_registry: &ChannelRegistry,
+    ) -> Result<Self, PacketError> {
+        let size = payload.len();
+        let mut reader = Reader::from(payload);
+        let header = PacketHeader::from_bytes(&mut reader)?;
+        let packet_type = header.get_packet_type();
+        let mut messages = vec![];
+        if packet_type == PacketType::DataFragment {
+            let channel_id = ChannelId::from_bytes(&mut reader)?;
+            let fragment = FragmentData::from_bytes(&mut reader)?;
+            messages.push(Self::message(
+                channel_id,
+                Some(fragment.message_id),
+                fragment.bytes.len(),
+                MessageContents::Fragment {
+                    fragment_id: fragment.fragment_id,
+                    num_fragments: fragment.num_fragments,
+                },
+                channel_registry,
+            )?);
+        }
+        // MTU probes only contain padding after the header
+        while packet_type != PacketType::MtuProbe && reader.has_remaining() {
+            let channel_id = ChannelId::from_bytes(&mut reader)?;
+            let num_messages = reader.read_u8().map_err(SerializationError::from)?;
+            let channel_kind = channel_registry
+                .get_kind_from_net_id(channel_id)
+                .ok_or(PacketError::ChannelNotFound)?;
+            for _ in 0..num_messages {
+                let single_data = SingleData::from_bytes(&mut reader)?;
+                let contents = MessageContents::parse(channel_kind, single_data.bytes.clone());
+                messages.push(Self::message(
+                    channel_id,
+                    single_data.id,
+                    single_data.bytes.len(),
+                    contents,
+                    channel_registry,
+                )?);
+            }
+        }
+        Ok(Self {
+            direction,
+            client_id,
+            packet_type,
+            packet_id: header.packet_id,
+            tick: header.tick,
+            last_ack_packet_id: header.last_ack_packet_id,
+            ack_bitfield: header.ack_bitfield,
+            size,
+            messages,
+        })
+    }
+
+    fn message(
+        channel_id: ChannelId,
+        message_id: Option<MessageId>,
+        size: usize,
+        contents: MessageContents,
+        channel_registry: &ChannelRegistry,
+    ) -> Result<InspectedMessage, PacketError> {
+        let channel = *channel_registry
+            .get_kind_from_net_id(channel_id)
+            .ok_or(PacketError::ChannelNotFound)?;
+        Ok(InspectedMessage {
+            channel,
+            channel_name: channel_registry
+                .name(&channel)
+                .ok_or(PacketError::ChannelNotFound)?
+                .to_string(),
+            message_id,
+            size,
+            contents,
+        })
+    }
+}
+
+impl MessageContents {
+    fn parse(channel_kind: &ChannelKind, bytes: Bytes) -> Self {
+        Self::try_parse(channel_kind, bytes).unwrap_or(MessageContents::Unknown)
+    }
+
+    fn try_parse(channel_kind: &ChannelKind, bytes: Bytes) -> Result<Self, SerializationError> {
+        let mut reader = Reader::from(bytes);
+        Ok(if *channel_kind == ChannelKind::of::<PingChannel>() {
+            MessageContents::Ping
+        } else if *channel_kind == ChannelKind::of::<PongChannel>() {
+            MessageContents::Pong
+        } else if *channel_kind == ChannelKind::of::<TimeSyncChannel>() {
+            MessageContents::TimeSync
+        } else if *channel_kind == ChannelKind::of::<InputAckChannel>() {
+            MessageContents::InputAck {
+                end_tick: InputAck::from_bytes(&mut reader)?.end_tick,
+            }
+        } else if *channel_kind == ChannelKind::of::<EntityActionsChannel>() {
+            let message = EntityActionsMessage::from_bytes(&mut reader)?;
+            MessageContents::EntityActions {
+                group_id: message.group_id,
+                actions: message
+                    .actions
+                    .into_iter()
+                    .map(|(entity, actions)| {
+                        Ok(InspectedEntityActions {
+                            entity,
+                            spawn: matches!(
+                                actions.spawn,
+                                SpawnAction::Spawn | SpawnAction::Reuse(_)
+                            ),
+                            despawn: actions.spawn == SpawnAction::Despawn,
+                            insert: component_net_ids(actions.insert)?,
+                            remove: actions.remove,
+                            updates: component_net_ids(actions.updates)?,
+                        })
+                    })
+                    .collect::<Result<_, SerializationError>>()?,
+            }
+        } else if *channel_kind == ChannelKind::of::<EntityUpdatesChannel>()
+            || *channel_kind == ChannelKind::of::<EntityReliableUpdatesChannel>()
+        {
+            let message = EntityUpdatesMessage::from_bytes(&mut reader)?;
+            MessageContents::EntityUpdates {
+                group_id: message.group_id,
+                updates: message
+                    .updates
+                    .into_iter()
+                    .map(|(entity, updates)| Ok((entity, component_net_ids(updates)?)))
+                    .collect::<Result<_, SerializationError>>()?,
+            }
+        } else {
+            MessageContents::Message {
+                net_id: NetId::from_bytes(&mut reader)?,
+            }
+        })
+    }
+}
+
+/// Serialized components are prefixed with the net id of the component
+fn component_net_ids(components: Vec<Bytes>) -> Result<Vec<ComponentNetId>, SerializationError> {
+    components
+        .into_iter()
+        .map(|bytes| ComponentNetId::from_bytes(&mut Reader::from(bytes)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use crate::prelude::client::ClientConfig;
+    use crate::prelude::server::{Replicate, ServerConfig};
+    use crate::prelude::{ComponentRegistry, SharedConfig, TickConfig};
+    use crate::tests::protocol::ComponentSyncModeFull;
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct RecordInspector(Mutex<Vec<InspectedPacket>>);
+
+    impl PacketInspector for RecordInspector {
+        fn inspect(&self, packet: &InspectedPacket) {
+            self.0.lock().unwrap().push(packet.clone());
+        }
+    }
+
+    #[test]
+    fn test_packet_inspector() {
+        let frame_duration = std::time::Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..Default::default()
+        };
+        let client_inspector = Arc::new(RecordInspector::default());
+        let mut client_config = ClientConfig::default();
+        client_config.packet = client_config
+            .packet
+            .with_packet_inspector(client_inspector.clone());
+        let mut stepper = BevyStepper::new(shared_config, client_config, frame_duration);
+        let server_inspector = Arc::new(RecordInspector::default());
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .packet
+            .packet_inspector = Some(server_inspector.clone());
+        stepper.init();
+
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((Replicate::default(), ComponentSyncModeFull(1.0)))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let net_id = stepper
+            .server_app
+            .world()
+            .resource::<ComponentRegistry>()
+            .net_id::<ComponentSyncModeFull>();
+
+        // the server sent the entity spawn to the client
+        let server_packets = server_inspector.0.lock().unwrap();
+        let spawn = server_packets
+            .iter()
+            .filter(|packet| packet.direction == PacketDirection::Send)
+            .flat_map(|packet| {
+                assert_eq!(packet.client_id, Some(ClientId::Netcode(TEST_CLIENT_ID)));
+                packet.messages.iter()
+            })
+            .find_map(|message| match &message.contents {
+                MessageContents::EntityActions { actions, .. } => actions.first(),
+                _ => None,
+            })
+            .expect("the server did not send the entity spawn");
+        assert_eq!(spawn.entity, server_entity);
+        assert!(spawn.spawn);
+        assert_eq!(spawn.insert, vec![net_id]);
+
+        // the client received the same packets that were sent by the server
+        let client_packets = client_inspector.0.lock().unwrap();
+        let received = client_packets
+            .iter()
+            .filter(|packet| packet.direction == PacketDirection::Receive)
+            .collect::<Vec<_>>();
+        assert!(!received.is_empty());
+        assert!(received.iter().all(|packet| packet.client_id.is_none()));
+        let sent_by_server = server_packets
+            .iter()
+            .find(|packet| {
+                packet.direction == PacketDirection::Send
+                    && packet.packet_id == received[0].packet_id
+            })
+            .unwrap();
+        assert_eq!(sent_by_server.messages, received[0].messages);
+        // the client also sends packets to the server (pings, acks, etc.)
+        assert!(client_packets
+            .iter()
+            .any(|packet| packet.direction == PacketDirection::Send
+                && packet
+                    .messages
+                    .iter()
+                    .any(|m| m.contents == MessageContents::Ping)));
+    }
+}
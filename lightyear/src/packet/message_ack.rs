@@ -0,0 +1,161 @@
+//! Report to the user when a message was acked by the remote peer
+//!
+//! A message sent with `send_message_with_ack` (on the client or on the server) returns a [`MessageHandle`].
+//! When the remote peer acks the message, a `MessageAckEvent` with the same handle is emitted.
+//! If the message is lost instead, a `MessageTimeoutEvent` is emitted:
+//! - on a reliable channel, when the message reached the
+//!   [`max_send_attempts`](crate::prelude::ReliableSettings::max_send_attempts) (reliable messages are
+//!   resent until they are acked if `max_send_attempts` is not set, which is always the case on
+//!   `OrderedReliable` channels)
+//! - on an unreliable channel with acks, when the packet containing the message was lost
+//!
+//! Only the channels that watch acks can be used; sending a tracked message on another channel returns
+//! a [`PacketError::AcksNotTracked`](crate::prelude::PacketError::AcksNotTracked) error.
+use bevy::utils::HashMap;
+use crossbeam_channel::Receiver;
+
+use crate::channel::builder::ChannelContainer;
+use crate::channel::senders::ChannelSend;
+use crate::packet::message::MessageId;
+use crate::protocol::channel::ChannelKind;
+
+/// Identifies a message sent with `send_message_with_ack`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MessageHandle(pub(crate) u64);
+
+/// What happened to a tracked message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MessageDelivery {
+    Acked,
+    Lost,
+}
+
+#[derive(Debug)]
+struct TrackedChannel {
+    acks: Receiver<MessageId>,
+    lost: Receiver<MessageId>,
+    messages: HashMap<MessageId, MessageHandle>,
+}
+
+/// Keeps track of the messages whose delivery is reported to the user
+#[derive(Debug, Default)]
+pub(crate) struct MessageAckTracker {
+    next_handle: u64,
+    channels: HashMap<ChannelKind, TrackedChannel>,
+    /// Messages that were delivered without going through a channel (for example to the local client
+    /// in host-server mode)
+    delivered: Vec<MessageHandle>,
+}
+
+impl MessageAckTracker {
+    pub(crate) fn new_handle(&mut self) -> MessageHandle {
+        let handle = MessageHandle(self.next_handle);
+        self.next_handle += 1;
+        handle
+    }
+
+    /// Start tracking the message `message_id` that was buffered on the channel
+    pub(crate) fn track(
+        &mut self,
+        channel: &mut ChannelContainer,
+        channel_kind: ChannelKind,
+        message_id: MessageId,
+        handle: MessageHandle,
+    ) {
+        self.channels
+            .entry(channel_kind)
+            .or_insert_with(|| TrackedChannel {
+                acks: channel.sender.subscribe_acks(),
+                lost: channel.sender.subscribe_lost(),
+                messages: HashMap::default(),
+            })
+            .messages
+            .insert(message_id, handle);
+    }
+
+    /// The message was delivered directly
+    pub(crate) fn delivered(&mut self, handle: MessageHandle) {
+        self.delivered.push(handle);
+    }
+
+    /// Return the tracked messages that were acked or lost since the last call
+    pub(crate) fn drain(&mut self) -> Vec<(MessageHandle, MessageDelivery)> {
+        let mut result: Vec<_> = self
+            .delivered
+            .drain(..)
+            .map(|handle| (handle, MessageDelivery::Acked))
+            .collect();
+        for channel in self.channels.values_mut() {
+            for (message_id, delivery) in channel
+                .acks
+                .try_iter()
+                .map(|id| (id, MessageDelivery::Acked))
+                .chain(
+                    channel
+                        .lost
+                        .try_iter()
+                        .map(|id| (id, MessageDelivery::Lost)),
+                )
+            {
+                if let Some(handle) = channel.messages.remove(&message_id) {
+                    result.push((handle, delivery));
+                }
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::utils::Duration;
+    use bytes::Bytes;
+
+    use crate::channel::builder::{
+        ChannelContainer, ChannelSettings, EntityActionsChannel, ReliableSettings,
+    };
+    use crate::packet::message::MessageAck;
+    use crate::prelude::ChannelMode;
+
+    use super::*;
+
+    fn channel(mode: ChannelMode) -> ChannelContainer {
+        ChannelContainer::new(ChannelSettings {
+            mode,
+            send_frequency: Duration::default(),
+            priority: 1.0,
+            fragmentation: true,
+        })
+    }
+
+    #[test]
+    fn test_message_ack_tracker() {
+        let kind = ChannelKind::of::<EntityActionsChannel>();
+        let mut channel = channel(ChannelMode::OrderedReliable(ReliableSettings::default()));
+        let mut tracker = MessageAckTracker::default();
+
+        let untracked = channel
+            .sender
+            .buffer_send(Bytes::from_static(b"a"), 1.0)
+            .unwrap()
+            .unwrap();
+        let message_id = channel
+            .sender
+            .buffer_send(Bytes::from_static(b"b"), 1.0)
+            .unwrap()
+            .unwrap();
+        let handle = tracker.new_handle();
+        tracker.track(&mut channel, kind, message_id, handle);
+        assert!(tracker.drain().is_empty());
+
+        // only the tracked messages are reported
+        for message_id in [untracked, message_id] {
+            channel.sender.receive_ack(&MessageAck {
+                message_id,
+                fragment_id: None,
+            });
+        }
+        assert_eq!(tracker.drain(), vec![(handle, MessageDelivery::Acked)]);
+        assert!(tracker.drain().is_empty());
+    }
+}
@@ -11,12 +11,16 @@ use crate::channel::receivers::ChannelReceive;
 use crate::channel::senders::ChannelSend;
 #[cfg(feature = "trace")]
 use crate::channel::stats::send::ChannelSendStats;
+use crate::packet::congestion::{CongestionConfig, CongestionController, CongestionState};
 use crate::packet::error::PacketError;
 use crate::packet::header::PacketHeader;
+use crate::packet::inspector::{ConnectionInspector, PacketDirection};
 use crate::packet::message::{
     FragmentData, MessageAck, MessageId, ReceiveMessage, SendMessage, SingleData,
 };
-use crate::packet::packet::PacketId;
+use crate::packet::message_ack::{MessageAckTracker, MessageHandle};
+use crate::packet::mtu_discovery::{MtuDiscovery, MtuDiscoveryConfig};
+use crate::packet::packet::{PacketId, FRAGMENT_SIZE};
 use crate::packet::packet_builder::{PacketBuilder, Payload, RecvPayload};
 use crate::packet::packet_type::PacketType;
 use crate::packet::priority_manager::{PriorityConfig, PriorityManager};
@@ -25,6 +29,7 @@ use crate::protocol::registry::NetId;
 use crate::serialize::reader::Reader;
 use crate::serialize::{SerializationError, ToBytes};
 use crate::shared::ping::manager::PingManager;
+use crate::shared::replication::debug::ChannelDebugInfo;
 use crate::shared::tick_manager::Tick;
 use crate::shared::tick_manager::TickManager;
 use crate::shared::time_manager::TimeManager;
@@ -50,6 +55,10 @@ pub struct MessageManager {
     /// reliable senders can stop trying to send a message that has already been received
     packet_to_message_ack_map: HashMap<PacketId, Vec<(ChannelKind, MessageAck)>>,
     nack_senders: Vec<Sender<MessageId>>,
+    mtu_discovery: Option<MtuDiscovery>,
+    congestion: Option<CongestionController>,
+    inspector: Option<ConnectionInspector>,
+    pub(crate) message_acks: MessageAckTracker,
 }
 
 impl MessageManager {
@@ -65,9 +74,72 @@ impl MessageManager {
             channel_registry: channel_registry.clone(),
             packet_to_message_ack_map: HashMap::new(),
             nack_senders: vec![],
+            mtu_discovery: None,
+            congestion: None,
+            inspector: None,
+            message_acks: MessageAckTracker::default(),
         }
     }
 
+    /// Probe the connection to find the largest packet size that can be used
+    pub(crate) fn with_mtu_discovery(mut self, config: Option<MtuDiscoveryConfig>) -> Self {
+        self.mtu_discovery = config.map(MtuDiscovery::new);
+        self
+    }
+
+    /// Reduce the replication send rate when the packet loss of the connection is high
+    pub(crate) fn with_congestion_control(mut self, config: Option<CongestionConfig>) -> Self {
+        self.congestion = config.map(CongestionController::new);
+        self
+    }
+
+    /// Current [`CongestionState`] of the connection.
+    ///
+    /// This is always [`CongestionState::Normal`] if congestion control is disabled.
+    pub fn congestion_state(&self) -> CongestionState {
+        self.congestion
+            .as_ref()
+            .map_or(CongestionState::Normal, CongestionController::state)
+    }
+
+    /// Called every time we send replication updates.
+    ///
+    /// Returns the priority below which the replication groups should not send their updates this time
+    /// (because the connection is congested), or None if every group can send its updates.
+    pub(crate) fn updates_priority_threshold(&mut self) -> Option<f32> {
+        self.congestion
+            .as_mut()
+            .and_then(CongestionController::updates_priority_threshold)
+    }
+
+    /// Keep up to `size` buffers of sent packets to build the next packets without allocating
+    pub(crate) fn with_packet_buffer_pool(mut self, size: usize) -> Self {
+        self.packet_manager.max_pooled_buffers = size;
+        self
+    }
+
+    /// Return the payloads returned by [`send_packets`](Self::send_packets) once they have been sent,
+    /// so that their buffers can be reused
+    pub(crate) fn recycle_payloads(&mut self, payloads: impl IntoIterator<Item = Payload>) {
+        payloads
+            .into_iter()
+            .for_each(|payload| self.packet_manager.recycle_buffer(payload));
+    }
+
+    /// Call the inspector with the contents of every packet sent or received on this connection
+    pub(crate) fn with_packet_inspector(mut self, inspector: Option<ConnectionInspector>) -> Self {
+        self.inspector = inspector;
+        self
+    }
+
+    /// Maximum size of the packets sent on this connection.
+    ///
+    /// This is the default [`MAX_PACKET_SIZE`](crate::connection::netcode::MAX_PACKET_SIZE) unless
+    /// MTU discovery found that bigger packets can be used.
+    pub fn mtu(&self) -> usize {
+        self.packet_manager.max_packet_size
+    }
+
     pub(crate) fn get_replication_update_send_receiver(&mut self) -> Receiver<MessageId> {
         self.priority_manager
             .subscribe_replication_update_sent_messages()
@@ -88,6 +160,15 @@ impl MessageManager {
             .update(time_manager, ping_manager);
         // notify that some messages have been lost
         for lost_packet in lost_packets {
+            // the probes are expected to be lost when they are larger than the MTU,
+            // so they are not counted in the packet loss estimate
+            let is_probe = self
+                .mtu_discovery
+                .as_mut()
+                .is_some_and(|mtu_discovery| mtu_discovery.receive_nack(lost_packet));
+            if let Some(congestion) = self.congestion.as_mut().filter(|_| !is_probe) {
+                congestion.packet_lost(lost_packet);
+            }
             if let Some(message_map) = self.packet_to_message_ack_map.remove(&lost_packet) {
                 for (channel_kind, message_ack) in message_map {
                     let channel = self
@@ -105,6 +186,15 @@ impl MessageManager {
                 }
             }
         }
+        if let Some(mtu_discovery) = &mut self.mtu_discovery {
+            mtu_discovery.update(time_manager);
+        }
+        if let Some(congestion) = &mut self.congestion {
+            if congestion.update(time_manager.delta()) {
+                self.priority_manager
+                    .set_bandwidth_factor(congestion.bandwidth_factor());
+            }
+        }
         for channel in self.channels.values_mut() {
             channel
                 .sender
@@ -141,13 +231,58 @@ impl MessageManager {
         channel_kind: ChannelKind,
         priority: f32,
     ) -> Result<Option<MessageId>, PacketError> {
+        #[cfg(feature = "metrics")]
+        if let Some(name) = self.channel_registry.name(&channel_kind) {
+            metrics::counter!("channel.messages_sent", "channel" => name.to_string()).increment(1);
+            metrics::counter!("channel.bytes_sent", "channel" => name.to_string())
+                .increment(message.len() as u64);
+        }
         let channel = self
             .channels
             .get_mut(&channel_kind)
             .ok_or(PacketError::ChannelNotFound)?;
+        if !channel.setting.fragmentation && message.len() > FRAGMENT_SIZE {
+            return Err(PacketError::FragmentationDisabled(message.len()));
+        }
         Ok(channel.sender.buffer_send(message, priority)?)
     }
 
+    /// Returns an error if the acks of the messages sent on the channel are not tracked
+    pub(crate) fn check_acks_tracked(&self, channel_kind: &ChannelKind) -> Result<(), PacketError> {
+        let channel = self
+            .channels
+            .get(channel_kind)
+            .ok_or(PacketError::ChannelNotFound)?;
+        if !channel.setting.mode.is_watching_acks() {
+            return Err(PacketError::AcksNotTracked);
+        }
+        Ok(())
+    }
+
+    /// Buffer a message and report when it is acked or lost, with the `handle`
+    pub(crate) fn buffer_send_with_ack(
+        &mut self,
+        message: Bytes,
+        channel_kind: ChannelKind,
+        handle: MessageHandle,
+    ) -> Result<(), PacketError> {
+        self.check_acks_tracked(&channel_kind)?;
+        let message_id = self
+            .buffer_send(message, channel_kind)?
+            .ok_or(PacketError::AcksNotTracked)?;
+        let channel = self.channels.get_mut(&channel_kind).unwrap();
+        self.message_acks
+            .track(channel, channel_kind, message_id, handle);
+        Ok(())
+    }
+
+    /// Returns true if the channel will send its buffered messages during the current frame
+    pub(crate) fn is_ready_to_send(&self, channel_kind: &ChannelKind) -> bool {
+        self.channels
+            .get(channel_kind)
+            .is_some_and(|channel| channel.sender.is_ready_to_send())
+    }
+
     /// Prepare buckets from the internal send buffers, and return the bytes to send
     // TODO: maybe pass TickManager instead of Tick? Find a more elegant way to pass extra data that might not be used?
     //  (ticks are not purely necessary without client prediction)
@@ -173,8 +308,11 @@ impl MessageManager {
             }
         }
         // return early if there are no messages to send
-        if !has_data_to_send {
-            return Ok(vec![]);
+        if !has_data_to_send && !self.priority_manager.has_buffered_messages() {
+            let mut bytes = vec![];
+            self.send_mtu_probe(current_tick, &mut bytes)?;
+            self.inspect_sent_packets(&bytes);
+            return Ok(bytes);
         }
 
         // priority manager: get the list of messages we can send according to the rate limiter
@@ -270,15 +408,50 @@ impl MessageManager {
             }
         }
 
+        self.send_mtu_probe(current_tick, &mut bytes)?;
+        self.inspect_sent_packets(&bytes);
         Ok(bytes)
     }
 
+    fn inspect_sent_packets(&self, payloads: &[Payload]) {
+        if let Some(inspector) = &self.inspector {
+            for payload in payloads {
+                inspector.inspect(payload, PacketDirection::Send, &self.channel_registry);
+            }
+        }
+    }
+
+    /// If MTU discovery is enabled, add a probe packet at the end of the packets to send.
+    ///
+    /// The probe is sent last so that a failure to send it does not affect the other packets.
+    fn send_mtu_probe(
+        &mut self,
+        current_tick: Tick,
+        bytes: &mut Vec<Payload>,
+    ) -> Result<(), PacketError> {
+        let Some(mtu_discovery) = &mut self.mtu_discovery else {
+            return Ok(());
+        };
+        let Some(size) = mtu_discovery.probe_to_send() else {
+            return Ok(());
+        };
+        let packet = self
+            .packet_manager
+            .build_mtu_probe_packet(size, current_tick)?;
+        mtu_discovery.probe_sent(packet.packet_id, size);
+        bytes.push(packet.payload);
+        Ok(())
+    }
+
     /// Process packet received over the network as raw bytes
     /// Update the acks, and put the messages from the packets in internal buffers
     /// Returns the tick of the packet
     #[cfg_attr(feature = "trace", instrument(level = Level::INFO, skip_all))]
     pub fn recv_packet(&mut self, packet: RecvPayload) -> Result<Tick, PacketError> {
         trace!(packet = ?packet.as_ref(), "Received packet");
+        if let Some(inspector) = &self.inspector {
+            inspector.inspect(&packet, PacketDirection::Receive, &self.channel_registry);
+        }
         let mut cursor = Reader::from(packet);
 
         // Step 1. Parse the packet
@@ -292,6 +465,10 @@ impl MessageManager {
         // TODO: an option is to have an async task that is on the receiving side of the
         //  cross-beam channel which tell which packets have been received
 
+        if let Some(mtu_discovery) = &mut self.mtu_discovery {
+            mtu_discovery.start();
+        }
+
         // Step 2. Update the packet acks (which packets have we received, and which of our packets
         // have been acked)
         let acked_packets = self
@@ -302,6 +479,14 @@ impl MessageManager {
         // Step 3. Update the list of messages that have been acked
         for acked_packet in acked_packets {
             trace!("Acked packet {:?}", acked_packet);
+            let mut is_probe = false;
+            if let Some(mtu_discovery) = &mut self.mtu_discovery {
+                is_probe = mtu_discovery.receive_ack(acked_packet);
+                self.packet_manager.max_packet_size = mtu_discovery.mtu();
+            }
+            if let Some(congestion) = self.congestion.as_mut().filter(|_| !is_probe) {
+                congestion.packet_acked(acked_packet);
+            }
             if let Some(message_acks) = self.packet_to_message_ack_map.remove(&acked_packet) {
                 for (channel_kind, message_ack) in message_acks {
                     let channel_name = self
@@ -322,6 +507,11 @@ impl MessageManager {
             }
         }
 
+        // MTU probes only contain padding after the header
+        if header.get_packet_type() == PacketType::MtuProbe {
+            return Ok(tick);
+        }
+
         // Step 4. Parse the payload into messages, put them in the internal buffers for each channel
         // we read directly from the packet and don't create intermediary datastructures to avoid allocations
         // TODO: maybe do this in a helper function?
@@ -343,6 +533,17 @@ impl MessageManager {
             trace!(?channel_id, ?num_messages);
             for i in 0..num_messages {
                 let single_data = SingleData::from_bytes(&mut cursor)?;
+                #[cfg(feature = "metrics")]
+                if let Some(name) = self
+                    .channel_registry
+                    .get_kind_from_net_id(channel_id)
+                    .and_then(|kind| self.channel_registry.name(kind))
+                {
+                    metrics::counter!("channel.messages_received", "channel" => name.to_string())
+                        .increment(1);
+                    metrics::counter!("channel.bytes_received", "channel" => name.to_string())
+                        .increment(single_data.bytes.len() as u64);
+                }
                 self.get_channel_mut(channel_id)?
                     .receiver
                     .buffer_recv(ReceiveMessage {
@@ -418,6 +619,39 @@ impl MessageManager {
             .ok_or(PacketError::ChannelNotFound)
     }
 
+    /// Returns true if any channel still has messages that were not sent (or not acked, for reliable channels)
+    pub fn has_messages_to_send(&self) -> bool {
+        self.priority_manager.has_buffered_messages()
+            || self
+                .channels
+                .values()
+                .any(|channel| channel.sender.has_messages_to_send())
+    }
+
+    /// Returns true if some unreliable messages were dropped during the last send because the
+    /// outgoing buffer was full
+    pub(crate) fn outgoing_buffer_overflowed(&self) -> bool {
+        self.priority_manager.overflowed()
+    }
+
+    /// Number of pending messages in each channel, identified by the channel name
+    pub(crate) fn channel_queue_depths(&self) -> Vec<ChannelDebugInfo> {
+        let mut depths = self
+            .channels
+            .iter()
+            .map(|(kind, channel)| ChannelDebugInfo {
+                name: self
+                    .channel_registry
+                    .name(kind)
+                    .unwrap_or_default()
+                    .to_string(),
+                pending_messages: channel.sender.num_pending_messages(),
+            })
+            .collect::<Vec<_>>();
+        depths.sort_by(|a, b| a.name.cmp(&b.name));
+        depths
+    }
+
     /// Get the ChannelSendStats of a given channel
     #[cfg(feature = "trace")]
     pub fn channel_send_stats<C: crate::prelude::Channel>(&self) -> Option<&ChannelSendStats> {
@@ -463,6 +697,26 @@ mod tests {
         (client_message_manager, server_message_manager)
     }
 
+    #[test]
+    fn test_message_manager_fragmentation_disabled() {
+        let mut channel_registry = ChannelRegistry::default();
+        channel_registry
+            .add_channel::<Channel1>(ChannelSettings::ordered_reliable().with_fragmentation(false));
+        let mut message_manager =
+            MessageManager::new(&channel_registry, 1.5, PriorityConfig::default());
+        let channel_kind = ChannelKind::of::<Channel1>();
+
+        // small messages can still be sent
+        message_manager
+            .buffer_send(vec![0, 1].into(), channel_kind)
+            .unwrap();
+        let message: Bytes = vec![1u8; FRAGMENT_SIZE + 1].into();
+        assert!(matches!(
+            message_manager.buffer_send(message, channel_kind),
+            Err(PacketError::FragmentationDisabled(_))
+        ));
+    }
+
     #[test]
     /// We want to test that we can send/receive messages over a connection
     fn test_message_manager_single_message() -> Result<(), PacketError> {
@@ -686,4 +940,63 @@ mod tests {
         assert_eq!(update_acks_tracker.try_recv().unwrap(), message_id);
         Ok(())
     }
+
+    #[test]
+    fn test_outgoing_buffer() -> Result<(), PacketError> {
+        use governor::{DefaultDirectRateLimiter, Quota};
+        use nonzero_ext::nonzero;
+
+        let (_, mut server_message_manager) = setup();
+        let mut channel_registry = ChannelRegistry::default();
+        channel_registry.add_channel::<Channel1>(ChannelSettings {
+            mode: ChannelMode::UnorderedUnreliable,
+            ..default()
+        });
+        // only one message fits in the bandwidth quota
+        let quota = Quota::per_hour(nonzero!(12u32));
+        let mut client_message_manager = MessageManager::new(
+            &channel_registry,
+            1.5,
+            PriorityConfig {
+                bandwidth_quota: quota,
+                enabled: true,
+                buffer_size: 1,
+            },
+        );
+        for i in 0..3 {
+            client_message_manager.buffer_send_with_priority(
+                vec![i; 6].into(),
+                Channel1::kind(),
+                1.0 + i as f32,
+            )?;
+        }
+        let payloads = client_message_manager.send_packets(Tick(0))?;
+        // the message with the highest priority is sent, the next one is buffered
+        // and the last one is dropped
+        assert!(client_message_manager.has_messages_to_send());
+        assert!(client_message_manager.outgoing_buffer_overflowed());
+        for payload in payloads {
+            server_message_manager.recv_packet(payload.into())?;
+        }
+        let data = MessageManager::collect_messages(server_message_manager.read_messages());
+        assert_eq!(
+            data.get(&Channel1::kind()).unwrap(),
+            &vec![(Tick(0), vec![2; 6].into())]
+        );
+
+        // the buffered message is sent when there is bandwidth available again
+        client_message_manager.priority_manager.limiter = DefaultDirectRateLimiter::direct(quota);
+        let payloads = client_message_manager.send_packets(Tick(1))?;
+        assert!(!client_message_manager.has_messages_to_send());
+        assert!(!client_message_manager.outgoing_buffer_overflowed());
+        for payload in payloads {
+            server_message_manager.recv_packet(payload.into())?;
+        }
+        let data = MessageManager::collect_messages(server_message_manager.read_messages());
+        assert_eq!(
+            data.get(&Channel1::kind()).unwrap(),
+            &vec![(Tick(1), vec![1; 6].into())]
+        );
+        Ok(())
+    }
 }
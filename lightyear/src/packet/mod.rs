@@ -22,17 +22,29 @@ Packets that are over the maximum packet size will be fragmented into multiple [
 /// Manages the [`PacketHeader`](header::PacketHeader) which includes important packet information
 pub(crate) mod header;
 
+/// Reduces the replication send rate when the connection is congested
+pub mod congestion;
+
+/// Inspect the decoded contents of the packets that are sent and received
+pub mod inspector;
+
 pub(crate) mod message;
 
+/// Reports when the messages sent with `send_message_with_ack` are acked by the remote peer
+pub mod message_ack;
+
 /// Manages sending and receiving [`Packets`](packet::Packet) over the network
 pub mod message_manager;
 
+/// Discovers the largest packet size that can be used on a connection
+pub mod mtu_discovery;
+
 pub mod packet;
 
 pub(crate) mod error;
 /// Manages building a single [`Packet`](packet::Packet) from multiple [`Messages`](message::Message)
 pub(crate) mod packet_builder;
 /// Defines the [`PacketType`](packet_type::PacketType) enum
-mod packet_type;
+pub mod packet_type;
 pub(crate) mod priority_manager;
 pub(crate) mod stats_manager;
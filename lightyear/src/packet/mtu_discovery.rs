@@ -0,0 +1,260 @@
+//! Discover the largest packet size that can be sent over a connection without being dropped
+//!
+//! We send probe packets of increasing size (a packet header followed by padding); the remote acks
+//! them through the normal packet-ack mechanism. The largest probe that gets acked becomes the
+//! connection's maximum packet size.
+use std::time::Duration;
+
+use bevy::prelude::Reflect;
+use bevy::time::Stopwatch;
+use tracing::{debug, trace};
+
+use crate::connection::netcode::{MAX_PACKET_SIZE, MAX_PAYLOAD_SIZE, MAX_PKT_BUF_SIZE};
+use crate::packet::packet::PacketId;
+use crate::shared::time_manager::TimeManager;
+use crate::transport::middleware::MIDDLEWARE_OVERHEAD;
+use crate::transport::MTU;
+
+/// Largest packet size that can be probed: once the netcode overhead (prefix byte, sequence number and MAC)
+/// and the io middleware overhead (compression flag, checksum) are added, the packet must still fit in
+/// the [`MTU`] of the transports.
+pub(crate) const MAX_CEILING: usize =
+    MTU - (MAX_PKT_BUF_SIZE - MAX_PAYLOAD_SIZE) - MIDDLEWARE_OVERHEAD;
+
+/// Configuration for the per-connection MTU discovery
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct MtuDiscoveryConfig {
+    /// Largest packet size (in bytes) that we will try to use.
+    ///
+    /// It is capped so that the packets still fit in the MTU of the transports once the netcode and
+    /// io middleware (compression, checksum) overhead is added.
+    pub ceiling: usize,
+    /// Number of bytes added to the probe size after each successful probe
+    pub step: usize,
+    /// Minimum duration between two probes
+    pub probe_interval: Duration,
+    /// Number of probes of a given size that can be lost before we consider that this size
+    /// cannot be used on the connection
+    pub max_attempts: u8,
+}
+
+impl Default for MtuDiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            ceiling: 1400,
+            step: 64,
+            probe_interval: Duration::from_millis(200),
+            max_attempts: 3,
+        }
+    }
+}
+
+impl MtuDiscoveryConfig {
+    pub fn with_ceiling(mut self, ceiling: usize) -> Self {
+        self.ceiling = ceiling;
+        self
+    }
+
+    pub fn with_step(mut self, step: usize) -> Self {
+        self.step = step;
+        self
+    }
+
+    pub fn with_probe_interval(mut self, probe_interval: Duration) -> Self {
+        self.probe_interval = probe_interval;
+        self
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: u8) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+}
+
+/// Keeps track of the MTU probes sent on a connection
+#[derive(Debug)]
+pub(crate) struct MtuDiscovery {
+    config: MtuDiscoveryConfig,
+    /// Largest packet size that has been confirmed to go through.
+    ///
+    /// Starts at the default [`MAX_PACKET_SIZE`], which is used if discovery is inconclusive.
+    mtu: usize,
+    /// Size of the next probe to send
+    probe_size: usize,
+    /// Probe that is currently waiting for an ack
+    in_flight: Option<(PacketId, usize)>,
+    /// Number of probes of size `probe_size` that were lost
+    attempts: u8,
+    probe_timer: Stopwatch,
+    /// We only start probing once we know that the remote is reachable
+    started: bool,
+    finished: bool,
+}
+
+impl MtuDiscovery {
+    pub(crate) fn new(mut config: MtuDiscoveryConfig) -> Self {
+        config.ceiling = config.ceiling.clamp(MAX_PACKET_SIZE, MAX_CEILING);
+        config.step = config.step.max(1);
+        let mut discovery = Self {
+            config,
+            mtu: MAX_PACKET_SIZE,
+            probe_size: MAX_PACKET_SIZE,
+            in_flight: None,
+            attempts: 0,
+            probe_timer: Stopwatch::new(),
+            started: false,
+            finished: false,
+        };
+        discovery.next_probe_size();
+        discovery
+    }
+
+    /// Largest packet size that can be used on the connection
+    pub(crate) fn mtu(&self) -> usize {
+        self.mtu
+    }
+
+    pub(crate) fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub(crate) fn update(&mut self, time_manager: &TimeManager) {
+        self.probe_timer.tick(time_manager.delta());
+    }
+
+    /// Start probing; called when we receive a packet from the remote
+    pub(crate) fn start(&mut self) {
+        self.started = true;
+    }
+
+    /// Returns the size of the probe to send, if we should send one now
+    pub(crate) fn probe_to_send(&self) -> Option<usize> {
+        if !self.started
+            || self.finished
+            || self.in_flight.is_some()
+            || self.probe_timer.elapsed() < self.config.probe_interval
+        {
+            return None;
+        }
+        Some(self.probe_size)
+    }
+
+    /// Register that a probe has been sent in the packet `packet_id`
+    pub(crate) fn probe_sent(&mut self, packet_id: PacketId, size: usize) {
+        trace!(?packet_id, ?size, "sent mtu probe");
+        self.in_flight = Some((packet_id, size));
+        self.probe_timer.reset();
+    }
+
+    /// The packet `packet_id` has been acked by the remote
+    ///
+    /// Returns true if the packet was the probe
+    pub(crate) fn receive_ack(&mut self, packet_id: PacketId) -> bool {
+        let Some((probe_id, size)) = self.in_flight else {
+            return false;
+        };
+        if probe_id != packet_id {
+            return false;
+        }
+        self.in_flight = None;
+        self.mtu = size;
+        self.attempts = 0;
+        debug!(mtu = ?self.mtu, "mtu probe acked");
+        self.next_probe_size();
+        true
+    }
+
+    /// The packet `packet_id` has been considered lost
+    ///
+    /// Returns true if the packet was the probe
+    pub(crate) fn receive_nack(&mut self, packet_id: PacketId) -> bool {
+        let Some((probe_id, size)) = self.in_flight else {
+            return false;
+        };
+        if probe_id != packet_id {
+            return false;
+        }
+        self.in_flight = None;
+        self.attempts += 1;
+        trace!(?size, attempts = ?self.attempts, "mtu probe lost");
+        if self.attempts >= self.config.max_attempts {
+            debug!(mtu = ?self.mtu, "mtu discovery finished");
+            self.finished = true;
+        }
+        true
+    }
+
+    fn next_probe_size(&mut self) {
+        if self.mtu >= self.config.ceiling {
+            debug!(mtu = ?self.mtu, "mtu discovery reached the ceiling");
+            self.finished = true;
+            return;
+        }
+        self.probe_size = (self.mtu + self.config.step).min(self.config.ceiling);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn send_probe(discovery: &mut MtuDiscovery, id: u16) -> usize {
+        let size = discovery.probe_to_send().unwrap();
+        discovery.probe_sent(PacketId(id), size);
+        assert!(discovery.probe_to_send().is_none());
+        size
+    }
+
+    #[test]
+    fn test_mtu_discovery() {
+        let config = MtuDiscoveryConfig::default()
+            .with_ceiling(1300)
+            .with_step(64)
+            .with_probe_interval(Duration::ZERO)
+            .with_max_attempts(2);
+        let mut discovery = MtuDiscovery::new(config);
+        assert_eq!(discovery.mtu(), MAX_PACKET_SIZE);
+        // no probes until the remote is reachable
+        assert!(discovery.probe_to_send().is_none());
+        discovery.start();
+
+        // the probe gets acked
+        assert_eq!(send_probe(&mut discovery, 0), 1264);
+        // acks for other packets are ignored
+        discovery.receive_ack(PacketId(5));
+        assert_eq!(discovery.mtu(), MAX_PACKET_SIZE);
+        discovery.receive_ack(PacketId(0));
+        assert_eq!(discovery.mtu(), 1264);
+
+        // the next probe is capped to the ceiling, and gets lost until we give up
+        assert_eq!(send_probe(&mut discovery, 1), 1300);
+        discovery.receive_nack(PacketId(1));
+        assert!(!discovery.is_finished());
+        assert_eq!(send_probe(&mut discovery, 2), 1300);
+        discovery.receive_nack(PacketId(2));
+        assert!(discovery.is_finished());
+        assert!(discovery.probe_to_send().is_none());
+        assert_eq!(discovery.mtu(), 1264);
+    }
+
+    #[test]
+    fn test_mtu_discovery_inconclusive() {
+        let config = MtuDiscoveryConfig::default()
+            .with_probe_interval(Duration::ZERO)
+            .with_max_attempts(1);
+        let mut discovery = MtuDiscovery::new(config);
+        discovery.start();
+        send_probe(&mut discovery, 0);
+        discovery.receive_nack(PacketId(0));
+        assert!(discovery.is_finished());
+        // fall back to the default packet size
+        assert_eq!(discovery.mtu(), MAX_PACKET_SIZE);
+    }
+
+    #[test]
+    fn test_mtu_discovery_ceiling() {
+        let config = MtuDiscoveryConfig::default().with_ceiling(usize::MAX);
+        let discovery = MtuDiscovery::new(config);
+        assert_eq!(discovery.config.ceiling, MAX_CEILING);
+    }
+}
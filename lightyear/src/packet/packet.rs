@@ -41,12 +41,14 @@ pub(crate) struct Packet {
     pub(crate) packet_id: PacketId,
     // How many bytes we know we are going to have to write in the packet, but haven't written yet
     pub(crate) prewritten_size: usize,
+    /// Maximum number of bytes that the packet can contain
+    pub(crate) max_packet_size: usize,
 }
 
 impl Packet {
     /// Check that we can still fit some data in the buffer
     pub(crate) fn can_fit(&self, size: usize) -> bool {
-        self.payload.len() + size + self.prewritten_size <= MAX_PACKET_SIZE
+        self.payload.len() + size + self.prewritten_size <= self.max_packet_size
     }
 
     /// Check if we can write a channel_id + the number of messages in the packet.
@@ -31,6 +31,12 @@ pub type RecvPayload = Bytes;
 pub(crate) struct PacketBuilder {
     pub(crate) header_manager: PacketHeaderManager,
     current_packet: Option<Packet>,
+    /// Maximum number of bytes in a packet; can be raised by MTU discovery
+    pub(crate) max_packet_size: usize,
+    /// Buffers of packets that were already sent, that can be reused to build new packets
+    buffer_pool: Vec<Payload>,
+    /// Maximum number of buffers kept in the pool. If 0, a new buffer is allocated for each packet
+    pub(crate) max_pooled_buffers: usize,
     // Pre-allocated buffer to encode/decode without allocation.
     // TODO: should this be associated with Packet?
     // cursor: Vec<u8>,
@@ -45,6 +51,9 @@ impl PacketBuilder {
         Self {
             header_manager: PacketHeaderManager::new(nack_rtt_multiple),
             current_packet: None,
+            max_packet_size: MAX_PACKET_SIZE,
+            buffer_pool: Vec::new(),
+            max_pooled_buffers: 0,
             // cursor: Vec::with_capacity(PACKET_BUFFER_CAPACITY),
             // acks: Vec::new(),
 
@@ -55,9 +64,46 @@ impl PacketBuilder {
         }
     }
 
-    // TODO: get the vec from a pool of preallocated buffers
-    fn get_new_buffer(&self) -> Payload {
-        Vec::with_capacity(MAX_PACKET_SIZE)
+    /// Get an empty buffer from the pool, or allocate a new one if the pool is empty
+    fn get_new_buffer(&mut self) -> Payload {
+        match self.buffer_pool.pop() {
+            Some(mut buffer) => {
+                // the buffer could be smaller than the packets if the MTU was raised
+                buffer.reserve(self.max_packet_size);
+                buffer
+            }
+            None => Vec::with_capacity(self.max_packet_size),
+        }
+    }
+
+    /// Return the buffer of a packet that was sent to the pool, so that it can be reused for the next packets
+    pub(crate) fn recycle_buffer(&mut self, mut buffer: Payload) {
+        if self.buffer_pool.len() < self.max_pooled_buffers {
+            buffer.clear();
+            self.buffer_pool.push(buffer);
+        }
+    }
+
+    /// Build a MTU probe packet of exactly `size` bytes: the header followed by padding.
+    pub(crate) fn build_mtu_probe_packet(
+        &mut self,
+        size: usize,
+        current_tick: Tick,
+    ) -> Result<Packet, SerializationError> {
+        let mut cursor = self.get_new_buffer();
+        let mut header = self
+            .header_manager
+            .prepare_send_packet_header(PacketType::MtuProbe);
+        header.tick = current_tick;
+        header.to_bytes(&mut cursor)?;
+        cursor.resize(size.max(cursor.len()), 0);
+        Ok(Packet {
+            payload: cursor,
+            message_acks: vec![],
+            packet_id: header.packet_id,
+            prewritten_size: 0,
+            max_packet_size: size,
+        })
     }
 
     /// Start building new packet, we start with an empty packet
@@ -80,6 +126,7 @@ impl PacketBuilder {
             message_acks: vec![],
             packet_id: header.packet_id,
             prewritten_size: 0,
+            max_packet_size: self.max_packet_size,
         });
         Ok(())
     }
@@ -112,6 +159,7 @@ impl PacketBuilder {
             )],
             packet_id: header.packet_id,
             prewritten_size: 0,
+            max_packet_size: self.max_packet_size,
         });
         Ok(())
 
@@ -139,7 +187,10 @@ impl PacketBuilder {
 
     pub fn finish_packet(&mut self) -> Packet {
         let mut packet = self.current_packet.take().unwrap();
-        packet.payload.shrink_to_fit();
+        // pooled buffers keep their capacity to be reused for the next packets
+        if self.max_pooled_buffers == 0 {
+            packet.payload.shrink_to_fit();
+        }
         // TODO: should we use bytes so this clone is cheap?
         packet
     }
@@ -687,5 +738,36 @@ mod tests {
         Ok(())
     }
 
+    /// The buffers of the packets that were sent are reused to build the next packets
+    #[test]
+    fn test_buffer_pool() -> Result<(), PacketError> {
+        let channel_registry = get_channel_registry();
+        let mut manager = PacketBuilder::new(1.5);
+        manager.max_pooled_buffers = 1;
+        let channel_id = *channel_registry
+            .get_net_from_kind(&ChannelKind::of::<Channel1>())
+            .unwrap();
+        let message = SingleData::new(None, Bytes::from(vec![7u8; 10]));
+        let single_data = || vec![(channel_id, VecDeque::from(vec![message.clone()]))];
+
+        let mut packets = manager.build_packets(Tick(0), single_data(), vec![])?;
+        let payload = packets.pop().unwrap().payload;
+        let expected = payload.clone();
+        let ptr = payload.as_ptr();
+        manager.recycle_buffer(payload);
+
+        let mut packets = manager.build_packets(Tick(0), single_data(), vec![])?;
+        let packet = packets.pop().unwrap();
+        assert_eq!(packet.payload.as_ptr(), ptr);
+        // only the packet id in the header changes
+        assert_eq!(packet.payload.len(), expected.len());
+
+        // the pool doesn't grow above its maximum size
+        manager.recycle_buffer(packet.payload);
+        manager.recycle_buffer(Vec::new());
+        assert_eq!(manager.buffer_pool.len(), 1);
+        Ok(())
+    }
+
     // TODO: ADD MORE TESTS
 }
@@ -15,6 +15,8 @@ pub enum PacketType {
     /// - channel_id = 0 = indication of end of packet
     Data = 0,
     DataFragment = 1,
+    /// A packet used for MTU discovery: the header is followed by padding that should be ignored
+    MtuProbe = 2,
 }
 
 impl From<PacketType> for u8 {
@@ -30,6 +32,7 @@ impl TryFrom<u8> for PacketType {
         match value {
             0 => Ok(PacketType::Data),
             1 => Ok(PacketType::DataFragment),
+            2 => Ok(PacketType::MtuProbe),
             _ => Err(crate::serialize::SerializationError::InvalidPacketType),
         }
     }
@@ -29,6 +29,8 @@ pub struct PriorityConfig {
     pub bandwidth_quota: Quota,
     /// If false, there is no bandwidth cap and all messages are sent as soon as possible
     pub enabled: bool,
+    /// Maximum number of unreliable messages that are kept for the next send when the bandwidth quota is reached
+    pub buffer_size: usize,
 }
 
 // this is mostly for testing
@@ -38,24 +40,27 @@ impl Default for PriorityConfig {
             // 56 KB/s bandwidth cap
             bandwidth_quota: Quota::per_second(nonzero!(56000u32)),
             enabled: false,
+            buffer_size: 0,
         }
     }
 }
 
-impl From<crate::client::config::PacketConfig> for PriorityConfig {
-    fn from(value: crate::client::config::PacketConfig) -> Self {
+impl From<&crate::client::config::PacketConfig> for PriorityConfig {
+    fn from(value: &crate::client::config::PacketConfig) -> Self {
         Self {
             bandwidth_quota: value.send_bandwidth_cap,
             enabled: value.bandwidth_cap_enabled,
+            buffer_size: 0,
         }
     }
 }
 
-impl From<crate::server::config::PacketConfig> for PriorityConfig {
-    fn from(value: crate::server::config::PacketConfig) -> Self {
+impl From<&crate::server::config::PacketConfig> for PriorityConfig {
+    fn from(value: &crate::server::config::PacketConfig) -> Self {
         Self {
             bandwidth_quota: value.per_client_send_bandwidth_cap,
             enabled: value.bandwidth_cap_enabled,
+            buffer_size: value.outgoing_buffer_size,
         }
     }
 }
@@ -68,8 +73,10 @@ pub(crate) struct PriorityManager {
     // // Internal buffer of data that we want to send
     // // Reuse allocation across frames
     // data_to_send: BTreeMap<ChannelId, (VecDeque<SendMessage>, VecDeque<SendMessage>)>,
-    // Messages that could not be sent because of the bandwidth quota
-    // buffered_data: Vec<BufferedMessage>,
+    /// Unreliable messages that could not be sent because of the bandwidth quota
+    buffered_data: Vec<BufferedMessage>,
+    /// True if some unreliable messages were dropped during the last send because the buffer was full
+    overflowed: bool,
     /// List of senders to notify when a replication update message is actually sent (included in packet)
     replication_update_senders: Vec<Sender<MessageId>>,
 }
@@ -80,11 +87,36 @@ impl PriorityManager {
             config: config.clone(),
             limiter: DefaultDirectRateLimiter::direct(config.bandwidth_quota),
             // data_to_send: BTreeMap::new(),
-            // buffered_data: Vec::new(),
+            buffered_data: Vec::new(),
+            overflowed: false,
             replication_update_senders: Vec::new(),
         }
     }
 
+    /// Scale the bandwidth quota by `factor` (between 0.0 and 1.0), for example when the connection is congested
+    pub(crate) fn set_bandwidth_factor(&mut self, factor: f32) {
+        let quota = self.config.bandwidth_quota;
+        let quota = Quota::with_period(
+            quota
+                .replenish_interval()
+                .div_f32(factor.clamp(f32::EPSILON, 1.0)),
+        )
+        .map_or(quota, |scaled| scaled.allow_burst(quota.burst_size()));
+        debug!(?factor, ?quota, "updating the bandwidth quota");
+        self.limiter = DefaultDirectRateLimiter::direct(quota);
+    }
+
+    /// Returns true if some messages are waiting for the bandwidth quota to be sent
+    pub(crate) fn has_buffered_messages(&self) -> bool {
+        !self.buffered_data.is_empty()
+    }
+
+    /// Returns true if some unreliable messages were dropped during the last send because
+    /// the buffer was full
+    pub(crate) fn overflowed(&self) -> bool {
+        self.overflowed
+    }
+
     /// Create a channel to notify when a replication update message is actually sent (included in packet)
     /// (as opposed to dropped because of the bandwidth quota)
     pub(crate) fn subscribe_replication_update_sent_messages(&mut self) -> Receiver<MessageId> {
@@ -170,6 +202,8 @@ impl PriorityManager {
                     }))
             })
             .collect::<Vec<_>>();
+        // the messages that could not be sent during the previous send
+        all_messages.append(&mut self.buffered_data);
 
         // sort from highest priority to lower
         all_messages.sort_by(|a, b| a.priority.partial_cmp(&b.priority).unwrap());
@@ -196,6 +230,7 @@ impl PriorityManager {
             if buffered_message.priority < BYPASS_QUOTA_PRIORITY {
                 let Ok(()) = result else {
                     debug!("Bandwidth quota reached, no more messages can be sent this tick");
+                    all_messages.push(buffered_message);
                     break;
                 };
             }
@@ -237,18 +272,40 @@ impl PriorityManager {
         }
 
         // all the other messages that don't make the cut, we just drop
-        // - unreliable messages: they are unreliable so it's ok
+        // - unreliable messages: the ones with the highest priority are kept for the next send, up to the buffer size
         // - reliable messages: they will be retried later, maybe with higher priority?
         // - unreliable entity updates: the replication sender keeps track for each entity of when we were able to send an update
         //   - PROBLEM: we could have the entity action not get sent (bandwidth), and then the priority still drops because the entity update
         //     was sent right after...
         // - reliable entity actions:
+        let mut num_messages_overflowed = 0;
+        self.overflowed = false;
+        // iterate from highest priority to lower
+        for buffered_message in all_messages.drain(..).rev() {
+            let settings = &channel_registry
+                .get_builder_from_net_id(buffered_message.channel_net_id)
+                .unwrap()
+                .settings;
+            // the replication sender already handles the entity updates that were not sent
+            if settings.mode.is_reliable()
+                || channel_registry.is_replication_update_channel(buffered_message.channel_net_id)
+            {
+                continue;
+            }
+            if self.buffered_data.len() < self.config.buffer_size {
+                self.buffered_data.push(buffered_message);
+            } else {
+                num_messages_overflowed += 1;
+                self.overflowed = true;
+            }
+        }
         let num_messages_sent = single_data.values().map(|data| data.len()).sum::<usize>()
             + fragment_data.values().map(|data| data.len()).sum::<usize>();
         debug!(
             bytes_sent = ?bytes_used,
             ?num_messages_sent,
-            num_messages_discarded = ?all_messages.len(),
+            num_messages_buffered = ?self.buffered_data.len(),
+            ?num_messages_overflowed,
             "priority filter done.");
 
         (
@@ -2,13 +2,15 @@ use bevy::app::App;
 use bevy::prelude::{Resource, TypePath};
 use bevy::utils::Duration;
 use std::any::TypeId;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::channel::builder::{
-    AuthorityChannel, Channel, ChannelBuilder, ChannelSettings, PongChannel,
+    AuthorityChannel, Channel, ChannelBuilder, ChannelSettings, ComponentAppliedChannel,
+    InitialSyncChannel, PongChannel, RngSeedChannel, TimeSyncChannel,
 };
 use crate::channel::builder::{
-    ChannelContainer, EntityActionsChannel, EntityUpdatesChannel, InputChannel, PingChannel,
+    ChannelContainer, EntityActionsChannel, EntityReliableUpdatesChannel, EntityUpdatesChannel,
+    InputAckChannel, InputChannel, PingChannel,
 };
 use crate::prelude::{ChannelMode, ReliableSettings};
 use crate::protocol::registry::{NetId, TypeKind, TypeMapper};
@@ -64,6 +66,8 @@ pub struct ChannelRegistry {
     pub(in crate::protocol) builder_map: HashMap<ChannelKind, ChannelBuilder>,
     pub(in crate::protocol) kind_map: TypeMapper<ChannelKind>,
     pub(in crate::protocol) name_map: HashMap<ChannelKind, String>,
+    /// Channels used internally by lightyear (replication, sync, inputs, ...)
+    internal_channels: HashSet<ChannelKind>,
     built: bool,
 }
 
@@ -73,6 +77,7 @@ impl ChannelRegistry {
             builder_map: HashMap::new(),
             kind_map: TypeMapper::new(),
             name_map: HashMap::new(),
+            internal_channels: HashSet::new(),
             built: false,
         };
         registry.add_channel::<EntityUpdatesChannel>(ChannelSettings {
@@ -83,6 +88,7 @@ impl ChannelRegistry {
             // directly on the replication_sender
             send_frequency: Duration::default(),
             priority: 1.0,
+            fragmentation: true,
         });
         registry.add_channel::<EntityActionsChannel>(ChannelSettings {
             mode: ChannelMode::UnorderedReliable(ReliableSettings::default()),
@@ -93,38 +99,90 @@ impl ChannelRegistry {
             send_frequency: Duration::default(),
             // we want to send the entity actions as soon as possible
             priority: 10.0,
+            fragmentation: true,
         });
         registry.add_channel::<PingChannel>(ChannelSettings {
             mode: ChannelMode::SequencedUnreliable,
             send_frequency: Duration::default(),
             // we always want to include the ping in the packet
             priority: f32::INFINITY,
+            fragmentation: true,
         });
         registry.add_channel::<PongChannel>(ChannelSettings {
             mode: ChannelMode::SequencedUnreliable,
             send_frequency: Duration::default(),
             // we always want to include the pong in the packet
             priority: f32::INFINITY,
+            fragmentation: true,
         });
         registry.add_channel::<InputChannel>(ChannelSettings {
             mode: ChannelMode::UnorderedUnreliable,
             send_frequency: input_send_interval,
             // we always want to include the inputs in the packet
             priority: f32::INFINITY,
+            fragmentation: true,
         });
         registry.add_channel::<AuthorityChannel>(ChannelSettings {
             mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
             send_frequency: Duration::default(),
             // we want to send the authority transfers as soon as possible
             priority: 10.0,
+            fragmentation: true,
         });
+        // new internal channels are registered after the existing ones, so that the net ids of the
+        // existing channels stay the same
+        registry.add_channel::<TimeSyncChannel>(ChannelSettings {
+            mode: ChannelMode::SequencedUnreliable,
+            send_frequency: Duration::default(),
+            // the stamp must be sent in the packet for which it was computed
+            priority: f32::INFINITY,
+            fragmentation: true,
+        });
+        registry.add_channel::<ComponentAppliedChannel>(ChannelSettings {
+            mode: ChannelMode::UnorderedReliable(ReliableSettings::default()),
+            send_frequency: Duration::default(),
+            priority: 1.0,
+            fragmentation: true,
+        });
+        registry.add_channel::<EntityReliableUpdatesChannel>(ChannelSettings {
+            mode: ChannelMode::UnorderedReliable(ReliableSettings::default()),
+            // same as the EntityUpdatesChannel, the send frequency is handled by the replication_sender
+            send_frequency: Duration::default(),
+            priority: 1.0,
+            fragmentation: true,
+        });
+        registry.add_channel::<InputAckChannel>(ChannelSettings {
+            mode: ChannelMode::SequencedUnreliable,
+            send_frequency: Duration::default(),
+            priority: 10.0,
+            fragmentation: true,
+        });
+        registry.add_channel::<InitialSyncChannel>(ChannelSettings {
+            mode: ChannelMode::UnorderedReliable(ReliableSettings::default()),
+            send_frequency: Duration::default(),
+            priority: 1.0,
+            fragmentation: true,
+        });
+        registry.add_channel::<RngSeedChannel>(ChannelSettings {
+            mode: ChannelMode::UnorderedReliable(ReliableSettings::default()),
+            send_frequency: Duration::default(),
+            priority: 1.0,
+            fragmentation: true,
+        });
+        registry.internal_channels = registry.builder_map.keys().copied().collect();
         registry
     }
 
+    /// Returns true if the channel is used internally by lightyear, instead of being registered by the user
+    pub(crate) fn is_internal_channel(&self, kind: &ChannelKind) -> bool {
+        self.internal_channels.contains(kind)
+    }
+
     /// Returns true if the net_id corresponds to a channel that is used for replication
     pub(crate) fn is_replication_channel(&self, net_id: NetId) -> bool {
         self.kind_map.kind(net_id).map_or(false, |kind| {
             *kind == ChannelKind::of::<EntityUpdatesChannel>()
+                || *kind == ChannelKind::of::<EntityReliableUpdatesChannel>()
                 || *kind == ChannelKind::of::<EntityActionsChannel>()
         })
     }
@@ -150,7 +208,18 @@ impl ChannelRegistry {
     }
 
     /// Register a new type
+    ///
+    /// Panics if the channel is [`ChannelMode::OrderedReliable`] with a
+    /// [`max_send_attempts`](crate::prelude::ReliableSettings::max_send_attempts).
     pub fn add_channel<C: Channel>(&mut self, settings: ChannelSettings) {
+        if let ChannelMode::OrderedReliable(reliable_settings) = &settings.mode {
+            assert!(
+                reliable_settings.max_send_attempts.is_none(),
+                "channel {}: max_send_attempts cannot be used with an OrderedReliable channel, \
+                the messages sent after a lost message would never be delivered",
+                C::name()
+            );
+        }
         let kind = self.kind_map.add::<C>();
         self.builder_map.insert(kind, C::get_builder(settings));
         let name = C::name();
@@ -227,4 +296,34 @@ mod tests {
             ChannelMode::UnorderedUnreliable
         );
     }
+
+    #[test]
+    #[should_panic(expected = "max_send_attempts cannot be used with an OrderedReliable channel")]
+    fn test_ordered_channel_max_send_attempts() {
+        let mut registry = ChannelRegistry::default();
+        registry.add_channel::<MyChannel>(
+            ChannelSettings::ordered_reliable().with_reliable_settings(
+                ReliableSettings::default().with_max_send_attempts(Some(2)),
+            ),
+        );
+    }
+
+    /// The internal channels always get the same net ids
+    #[test]
+    fn test_internal_channel_net_ids() {
+        let registry = ChannelRegistry::new(Duration::default());
+        let net_id = |kind: ChannelKind| *registry.kind_map.net_id(&kind).unwrap();
+        assert_eq!(net_id(ChannelKind::of::<EntityUpdatesChannel>()), 0);
+        assert_eq!(net_id(ChannelKind::of::<EntityActionsChannel>()), 1);
+        assert_eq!(net_id(ChannelKind::of::<PingChannel>()), 2);
+        assert_eq!(net_id(ChannelKind::of::<PongChannel>()), 3);
+        assert_eq!(net_id(ChannelKind::of::<InputChannel>()), 4);
+        assert_eq!(net_id(ChannelKind::of::<AuthorityChannel>()), 5);
+        assert_eq!(net_id(ChannelKind::of::<TimeSyncChannel>()), 6);
+        assert_eq!(net_id(ChannelKind::of::<ComponentAppliedChannel>()), 7);
+        assert_eq!(net_id(ChannelKind::of::<EntityReliableUpdatesChannel>()), 8);
+        assert_eq!(net_id(ChannelKind::of::<InputAckChannel>()), 9);
+        assert_eq!(net_id(ChannelKind::of::<InitialSyncChannel>()), 10);
+        assert_eq!(net_id(ChannelKind::of::<RngSeedChannel>()), 11);
+    }
 }
@@ -7,7 +7,7 @@ use std::ops::{Add, Mul};
 
 use bevy::prelude::{App, Component, EntityWorldMut, Mut, Resource, TypePath, World};
 use bevy::ptr::Ptr;
-use bevy::utils::HashMap;
+use bevy::utils::{HashMap, HashSet};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
@@ -17,7 +17,7 @@ use crate::client::components::ComponentSyncMode;
 use crate::client::config::ClientConfig;
 use crate::client::interpolation::{add_interpolation_systems, add_prepare_interpolation_systems};
 use crate::client::prediction::plugin::{
-    add_non_networked_rollback_systems, add_prediction_systems,
+    add_non_networked_rollback_systems, add_prediction_systems, PredictionConfig,
 };
 use crate::prelude::client::SyncComponent;
 use crate::prelude::server::ServerConfig;
@@ -28,8 +28,10 @@ use crate::protocol::serialize::{ErasedSerializeFns, SerializeFns};
 use crate::serialize::reader::Reader;
 use crate::serialize::SerializationError;
 use crate::shared::events::connection::ConnectionEvents;
+use crate::shared::replication::authority::AuthorityPeer;
 use crate::shared::replication::delta::{DeltaMessage, Diffable};
 use crate::shared::replication::entity_map::{EntityMap, ReceiveEntityMap};
+use crate::transport::middleware::compression::TypeCompressionConfig;
 
 pub type ComponentNetId = NetId;
 
@@ -72,6 +74,14 @@ pub enum ComponentError {
 /// }
 /// ```
 ///
+/// #### Protocol upgrades
+/// By default, components are identified over the network by the order in which they were registered,
+/// so the client and the server must register the same components in the same order.
+///
+/// To let clients that run an older version of the protocol connect to an updated server, give each component a fixed id
+/// with [`with_net_id`](ComponentRegistration::with_net_id). Every serialized component is length-prefixed,
+/// so a receiver skips the components whose id it doesn't know instead of failing to read the rest of the message.
+///
 /// ### Customizing Component behaviour
 ///
 /// There are some cases where you might want to define additional behaviour for a component.
@@ -101,6 +111,10 @@ pub enum ComponentError {
 /// If your component implements the [`Linear`] trait, you can use the [`add_linear_correction_fn`](ComponentRegistration::add_linear_correction_fn) method,
 /// which provides linear interpolation.
 ///
+/// When the prediction error is large, smoothing the correction would make the entity visibly slide across the map.
+/// With [`add_correction_snap_fn`](ComponentRegistration::add_correction_snap_fn) you can provide a threshold
+/// above which the Predicted entity snaps directly to the corrected state.
+///
 /// #### Interpolation
 /// Similarly to client-prediction, we create two distinct entities on the client when the server replicates an entity: a Confirmed entity and an Interpolated entity.
 /// The Confirmed entity will just get updated when the client receives the server updates, while the Interpolated entity will be updated by the client's interpolation system,
@@ -143,7 +157,40 @@ pub struct ComponentRegistry {
     prediction_map: HashMap<ComponentKind, PredictionMetadata>,
     serialize_fns_map: HashMap<ComponentKind, ErasedSerializeFns>,
     delta_fns_map: HashMap<ComponentKind, ErasedDeltaFns>,
+    /// For each component, the list of components that must be applied before it
+    /// when they are received in the same replication message
+    apply_dependencies: HashMap<ComponentKind, Vec<ComponentKind>>,
+    /// Rank of each component in the application order (components with a lower rank are applied first)
+    apply_ranks: HashMap<ComponentKind, usize>,
+    /// Components for which the receiver confirms that the updates were applied
+    application_acks: HashSet<ComponentKind>,
+    /// Components whose updates are sent on a reliable channel
+    reliable_components: HashSet<ComponentKind>,
+    /// Functions used to reject the invalid values of a component received from a remote peer
+    validation_map: HashMap<ComponentKind, unsafe fn()>,
     pub(crate) kind_map: TypeMapper<ComponentKind>,
+    #[cfg(feature = "serialization_stats")]
+    pub(crate) serialized_sizes: crate::protocol::serialization_stats::SizeRecorder,
+}
+
+/// Channel on which the updates of a component are replicated
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ReplicationMode {
+    /// Updates are sent on the [`EntityUpdatesChannel`](crate::channel::builder::EntityUpdatesChannel).
+    ///
+    /// Lost updates are only resent if no more recent update was sent in the meantime, which is
+    /// the best fit for components that change frequently (for example the position of an entity).
+    #[default]
+    Unreliable,
+    /// Updates are sent on the [`EntityReliableUpdatesChannel`](crate::channel::builder::EntityReliableUpdatesChannel).
+    ///
+    /// Every update is retransmitted until it is acked, independently of the unreliable updates of the
+    /// replication group. This is the best fit for components that rarely change but whose changes
+    /// must not be lost (for example the name of a player).
+    ///
+    /// Components that are delta-compressed are always sent unreliably, because their diffs are computed
+    /// from the last acked unreliable update.
+    Reliable,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -152,12 +199,13 @@ pub struct ReplicationMetadata {
     pub delta_compression_id: ComponentId,
     pub replicate_once_id: ComponentId,
     pub override_target_id: ComponentId,
+    pub client_filter_id: ComponentId,
     pub disabled_id: ComponentId,
     pub write: RawWriteFn,
     pub remove: Option<RawRemoveFn>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct PredictionMetadata {
     pub prediction_mode: ComponentSyncMode,
     pub correction: Option<unsafe fn()>,
@@ -165,6 +213,25 @@ pub struct PredictionMetadata {
     /// to determine if a rollback is needed. Returns true if we should do a rollback.
     /// Will default to a PartialEq::ne implementation, but can be overriden.
     pub should_rollback: unsafe fn(),
+    /// Function used to check if the predicted component should snap directly to the corrected
+    /// value instead of being visually corrected over multiple ticks.
+    pub correction_snap: Option<unsafe fn()>,
+    /// Function that adds the client prediction systems of the component for a given mode.
+    ///
+    /// The systems are added when the [`PredictionPlugin`](crate::client::prediction::plugin::PredictionPlugin)
+    /// is finished, once the [`PredictionConfig`] is known.
+    pub add_systems: Option<fn(&mut App, ComponentSyncMode)>,
+}
+
+// the functions are compared by address: the metadata of a component is equal if it was registered
+// with the same functions
+impl PartialEq for PredictionMetadata {
+    fn eq(&self, other: &Self) -> bool {
+        self.prediction_mode == other.prediction_mode
+            && self.correction.map(|f| f as usize) == other.correction.map(|f| f as usize)
+            && self.should_rollback as usize == other.should_rollback as usize
+            && self.correction_snap.map(|f| f as usize) == other.correction_snap.map(|f| f as usize)
+    }
 }
 
 impl PredictionMetadata {
@@ -178,6 +245,8 @@ impl PredictionMetadata {
                     should_rollback,
                 )
             },
+            correction_snap: None,
+            add_systems: None,
         }
     }
 }
@@ -187,6 +256,7 @@ pub struct InterpolationMetadata {
     pub interpolation_mode: ComponentSyncMode,
     pub interpolation: Option<unsafe fn()>,
     pub custom_interpolation: bool,
+    pub should_snap: Option<unsafe fn()>,
 }
 
 type RawRemoveFn = fn(&ComponentRegistry, &mut EntityWorldMut);
@@ -208,6 +278,18 @@ pub type LerpFn<C> = fn(start: &C, other: &C, t: f32) -> C;
 /// Defaults to PartialEq::ne
 type ShouldRollbackFn<C> = fn(this: &C, that: &C) -> bool;
 
+/// Function that returns true if the interpolation should snap directly to the `next` server snapshot
+/// instead of interpolating from the `previous` one. (for example if the distance between them is too big)
+type ShouldSnapFn<C> = fn(previous: &C, next: &C) -> bool;
+
+/// Function that returns true if the `predicted` value should snap directly to the `corrected` value
+/// after a rollback instead of being visually corrected. (for example if the prediction error is too big)
+type ShouldSnapCorrectionFn<C> = fn(predicted: &C, corrected: &C) -> bool;
+
+/// Function that returns true if a value of the component received from a client is valid.
+/// (for example if a client-authoritative position is inside the bounds of the map)
+pub type ValidateFn<C> = fn(value: &C) -> bool;
+
 pub trait Linear {
     fn lerp(start: &Self, other: &Self, t: f32) -> Self;
 }
@@ -313,6 +395,17 @@ mod serialize {
             erased_fns.add_map_entities::<C>();
         }
 
+        pub(crate) fn set_compression<C: 'static>(&mut self, compression: TypeCompressionConfig) {
+            let kind = ComponentKind::of::<C>();
+            let erased_fns = self.serialize_fns_map.get_mut(&kind).unwrap_or_else(|| {
+                panic!(
+                    "Component {} is not part of the protocol",
+                    std::any::type_name::<C>()
+                )
+            });
+            erased_fns.compression = Some(compression);
+        }
+
         /// Returns true if we have a registered `map_entities` function for this component type
         pub(crate) fn is_map_entities<C: 'static>(&self) -> bool {
             let kind = ComponentKind::of::<C>();
@@ -324,6 +417,12 @@ mod serialize {
         }
 
         /// Returns true if we have a registered `map_entities` function for this component type
+        /// Type names of all the components that can be serialized
+        #[cfg(feature = "serialization_stats")]
+        pub(crate) fn type_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+            self.serialize_fns_map.values().map(|fns| fns.type_name)
+        }
+
         pub(crate) fn erased_is_map_entities(&self, kind: ComponentKind) -> bool {
             let erased_fns = self
                 .serialize_fns_map
@@ -344,12 +443,20 @@ mod serialize {
                 .get(&kind)
                 .ok_or(ComponentError::MissingSerializationFns)?;
             let net_id = self.kind_map.net_id(&kind).unwrap();
+            #[cfg(any(feature = "metrics", feature = "serialization_stats"))]
+            let start = writer.len();
 
             net_id.to_bytes(writer)?;
             // SAFETY: the ErasedFns corresponds to type C
             unsafe {
                 erased_fns.serialize(component, writer, entity_map)?;
             }
+            #[cfg(feature = "metrics")]
+            metrics::counter!("replication.component_bytes", "component" => erased_fns.type_name)
+                .increment((writer.len() - start) as u64);
+            #[cfg(feature = "serialization_stats")]
+            self.serialized_sizes
+                .record(erased_fns.type_name, writer.len() - start);
             Ok(())
         }
 
@@ -366,11 +473,19 @@ mod serialize {
                 .get(&kind)
                 .ok_or(ComponentError::MissingSerializationFns)?;
             let net_id = self.kind_map.net_id(&kind).unwrap();
+            #[cfg(any(feature = "metrics", feature = "serialization_stats"))]
+            let start = writer.len();
             net_id.to_bytes(writer)?;
             // SAFETY: the ErasedSerializeFns corresponds to type C
             unsafe {
                 (erased_fns.erased_serialize)(erased_fns, component, writer, entity_map)?;
             }
+            #[cfg(feature = "metrics")]
+            metrics::counter!("replication.component_bytes", "component" => erased_fns.type_name)
+                .increment((writer.len() - start) as u64);
+            #[cfg(feature = "serialization_stats")]
+            self.serialized_sizes
+                .record(erased_fns.type_name, writer.len() - start);
             Ok(())
         }
 
@@ -463,6 +578,50 @@ mod prediction {
                 )
             });
         }
+        pub(crate) fn set_correction_snap<C: Component + PartialEq>(
+            &mut self,
+            should_snap: ShouldSnapCorrectionFn<C>,
+        ) {
+            let kind = ComponentKind::of::<C>();
+            self.prediction_map
+                .entry(kind)
+                .or_insert_with(|| PredictionMetadata::default_from::<C>(ComponentSyncMode::Full))
+                .correction_snap = Some(unsafe {
+                std::mem::transmute::<for<'a, 'b> fn(&'a C, &'b C) -> bool, unsafe fn()>(
+                    should_snap,
+                )
+            });
+        }
+
+        pub(crate) fn set_prediction_systems<C: SyncComponent>(
+            &mut self,
+            add_systems: fn(&mut App, ComponentSyncMode),
+        ) {
+            let kind = ComponentKind::of::<C>();
+            self.prediction_map
+                .entry(kind)
+                .or_insert_with(|| PredictionMetadata::default_from::<C>(ComponentSyncMode::Full))
+                .add_systems = Some(add_systems);
+        }
+
+        /// Apply the client's [`PredictionConfig`] to the prediction modes of the components.
+        ///
+        /// Returns the functions that add the prediction systems of each component, with the mode to use.
+        pub(crate) fn apply_prediction_config(
+            &mut self,
+            config: &PredictionConfig,
+        ) -> Vec<(fn(&mut App, ComponentSyncMode), ComponentSyncMode)> {
+            let mut add_systems = vec![];
+            for (kind, metadata) in self.prediction_map.iter_mut() {
+                // the client can choose to only predict some of the components
+                metadata.prediction_mode = config.prediction_mode(*kind, metadata.prediction_mode);
+                if let Some(add_fn) = metadata.add_systems {
+                    add_systems.push((add_fn, metadata.prediction_mode));
+                }
+            }
+            add_systems
+        }
+
         pub(crate) fn prediction_mode<C: Component>(&self) -> ComponentSyncMode {
             let kind = ComponentKind::of::<C>();
             self.prediction_map
@@ -489,6 +648,24 @@ mod prediction {
             should_rollback_fn(this, that)
         }
 
+        /// Returns true if the predicted component should snap to the corrected value instead of being
+        /// visually corrected
+        pub(crate) fn should_snap_correction<C: Component>(
+            &self,
+            predicted: &C,
+            corrected: &C,
+        ) -> bool {
+            let kind = ComponentKind::of::<C>();
+            self.prediction_map
+                .get(&kind)
+                .and_then(|metadata| metadata.correction_snap)
+                .is_some_and(|should_snap| {
+                    let should_snap: ShouldSnapCorrectionFn<C> =
+                        unsafe { std::mem::transmute(should_snap) };
+                    should_snap(predicted, corrected)
+                })
+        }
+
         pub(crate) fn correct<C: Component>(&self, predicted: &C, corrected: &C, t: f32) -> C {
             let kind = ComponentKind::of::<C>();
             let prediction_metadata = self
@@ -514,6 +691,7 @@ mod interpolation {
                     interpolation_mode: mode,
                     interpolation: None,
                     custom_interpolation: false,
+                    should_snap: None,
                 })
                 .interpolation_mode = mode;
         }
@@ -530,6 +708,7 @@ mod interpolation {
                     interpolation_mode: ComponentSyncMode::Full,
                     interpolation: None,
                     custom_interpolation: false,
+                    should_snap: None,
                 })
                 .interpolation = Some(unsafe {
                 std::mem::transmute::<for<'a, 'b> fn(&'a C, &'b C, f32) -> C, unsafe fn()>(
@@ -537,6 +716,38 @@ mod interpolation {
                 )
             });
         }
+
+        pub(crate) fn set_interpolation_snap<C: Component>(
+            &mut self,
+            should_snap: ShouldSnapFn<C>,
+        ) {
+            let kind = ComponentKind::of::<C>();
+            self.interpolation_map
+                .entry(kind)
+                .or_insert_with(|| InterpolationMetadata {
+                    interpolation_mode: ComponentSyncMode::Full,
+                    interpolation: None,
+                    custom_interpolation: false,
+                    should_snap: None,
+                })
+                .should_snap = Some(unsafe {
+                std::mem::transmute::<for<'a, 'b> fn(&'a C, &'b C) -> bool, unsafe fn()>(
+                    should_snap,
+                )
+            });
+        }
+
+        /// Returns true if the interpolation should jump directly from `previous` to `next`
+        pub(crate) fn should_snap<C: Component>(&self, previous: &C, next: &C) -> bool {
+            let kind = ComponentKind::of::<C>();
+            self.interpolation_map
+                .get(&kind)
+                .and_then(|metadata| metadata.should_snap)
+                .is_some_and(|should_snap| {
+                    let should_snap: ShouldSnapFn<C> = unsafe { std::mem::transmute(should_snap) };
+                    should_snap(previous, next)
+                })
+        }
         pub(crate) fn interpolation_mode<C: Component>(&self) -> ComponentSyncMode {
             let kind = ComponentKind::of::<C>();
             self.interpolation_map
@@ -545,6 +756,13 @@ mod interpolation {
                     metadata.interpolation_mode
                 })
         }
+        pub(crate) fn has_interpolation<C: Component>(&self) -> bool {
+            let kind = ComponentKind::of::<C>();
+            self.interpolation_map
+                .get(&kind)
+                .is_some_and(|metadata| metadata.interpolation.is_some())
+        }
+
         pub(crate) fn interpolate<C: Component>(&self, start: &C, end: &C, t: f32) -> C {
             let kind = ComponentKind::of::<C>();
             let interpolation_metadata = self
@@ -561,11 +779,13 @@ mod interpolation {
 mod replication {
     use super::*;
     use crate::prelude::{
-        DeltaCompression, DisabledComponent, OverrideTargetComponent, ReplicateOnceComponent,
+        ComponentClientFilter, DeltaCompression, DisabledComponent, OverrideTargetComponent,
+        ReplicateOnceComponent,
     };
     use crate::serialize::reader::Reader;
     use crate::serialize::ToBytes;
     use crate::shared::replication::entity_map::ReceiveEntityMap;
+    use bytes::Bytes;
 
     impl ComponentRegistry {
         pub(crate) fn set_replication_fns<C: Component + PartialEq>(&mut self, world: &mut World) {
@@ -579,6 +799,7 @@ mod replication {
                     delta_compression_id: world.init_component::<DeltaCompression<C>>(),
                     replicate_once_id: world.init_component::<ReplicateOnceComponent<C>>(),
                     override_target_id: world.init_component::<OverrideTargetComponent<C>>(),
+                    client_filter_id: world.init_component::<ComponentClientFilter<C>>(),
                     disabled_id: world.init_component::<DisabledComponent<C>>(),
                     write,
                     remove: Some(remove),
@@ -596,10 +817,12 @@ mod replication {
             events: &mut ConnectionEvents,
         ) -> Result<(), ComponentError> {
             let net_id = ComponentNetId::from_bytes(reader).map_err(SerializationError::from)?;
-            let kind = self
-                .kind_map
-                .kind(net_id)
-                .ok_or(ComponentError::NotRegistered)?;
+            let Some(kind) = self.kind_map.kind(net_id) else {
+                // the remote could be using a newer version of the protocol: every component
+                // is length-prefixed, so we can skip the components that we don't know about
+                debug!(?net_id, "skipping unknown component");
+                return Ok(());
+            };
             let replication_metadata = self
                 .replication_map
                 .get(kind)
@@ -612,9 +835,67 @@ mod replication {
                 entity_world_mut,
                 entity_map,
                 events,
+            )?;
+            if self.application_acks.contains(kind) {
+                events.push_applied_component(entity_world_mut.id(), net_id, tick);
+            }
+            Ok(())
+        }
+
+        pub(crate) fn add_validation<C: 'static>(&mut self, validate: ValidateFn<C>) {
+            self.validation_map
+                .insert(ComponentKind::of::<C>(), unsafe {
+                    std::mem::transmute::<for<'a> fn(&'a C) -> bool, unsafe fn()>(validate)
+                });
+        }
+
+        /// Returns false if the value of the component received from a client must be rejected.
+        ///
+        /// `from_client` is true if the entity is controlled by a client (see [`AuthorityPeer`]); only
+        /// these values are validated, so the values that the clients receive from the server are always accepted.
+        pub(crate) fn validate<C: 'static>(&self, value: &C, from_client: bool) -> bool {
+            if !from_client {
+                return true;
+            }
+            self.validation_map
+                .get(&ComponentKind::of::<C>())
+                .map_or(true, |validate| {
+                    // SAFETY: the function was registered for the type C
+                    let validate: ValidateFn<C> = unsafe { std::mem::transmute(*validate) };
+                    validate(value)
+                })
+        }
+
+        /// Returns true if the entity is controlled by a client, so the values received for it must be validated
+        pub(crate) fn is_from_client(entity_world_mut: &EntityWorldMut) -> bool {
+            matches!(
+                entity_world_mut.get::<AuthorityPeer>(),
+                Some(AuthorityPeer::Client(_))
             )
         }
 
+        /// Specify that the receiver must confirm that the updates of `C` were applied
+        pub(crate) fn add_application_ack<C: 'static>(&mut self) {
+            self.application_acks.insert(ComponentKind::of::<C>());
+        }
+
+        pub(crate) fn set_replication_mode<C: 'static>(&mut self, mode: ReplicationMode) {
+            let kind = ComponentKind::of::<C>();
+            match mode {
+                ReplicationMode::Unreliable => self.reliable_components.remove(&kind),
+                ReplicationMode::Reliable => self.reliable_components.insert(kind),
+            };
+        }
+
+        /// Get the [`ReplicationMode`] of the component
+        pub(crate) fn replication_mode(&self, kind: ComponentKind) -> ReplicationMode {
+            if self.reliable_components.contains(&kind) {
+                ReplicationMode::Reliable
+            } else {
+                ReplicationMode::Unreliable
+            }
+        }
+
         pub(crate) fn write<C: Component + PartialEq>(
             &self,
             reader: &mut Reader,
@@ -627,6 +908,14 @@ mod replication {
             trace!("Writing component {} to entity", std::any::type_name::<C>());
             let component = self.raw_deserialize::<C>(reader, net_id, entity_map)?;
             let entity = entity_world_mut.id();
+            if !self.validate(&component, Self::is_from_client(entity_world_mut)) {
+                debug!(
+                    ?entity,
+                    "rejecting invalid value of component {}",
+                    std::any::type_name::<C>()
+                );
+                return Ok(());
+            }
             // TODO: should we send the event based on on the message type (Insert/Update) or based on whether the component was actually inserted?
             if let Some(mut c) = entity_world_mut.get_mut::<C>() {
                 // only apply the update if the component is different, to not trigger change detection
@@ -641,12 +930,84 @@ mod replication {
             Ok(())
         }
 
+        /// Specify that `D` must be applied before `C` when both are received for an entity
+        /// in the same replication message.
+        ///
+        /// Panics if this creates a cycle in the component application order.
+        pub(crate) fn add_apply_dependency<C: 'static, D: 'static>(&mut self) {
+            let kind = ComponentKind::of::<C>();
+            let dependency = ComponentKind::of::<D>();
+            let dependencies = self.apply_dependencies.entry(kind).or_default();
+            if !dependencies.contains(&dependency) {
+                dependencies.push(dependency);
+            }
+            self.apply_ranks = HashMap::default();
+            let dependencies = self.apply_dependencies.keys().copied().collect::<Vec<_>>();
+            for kind in dependencies {
+                self.compute_apply_rank(kind, &mut vec![]);
+            }
+        }
+
+        /// Compute the rank of the component in the application order: 0 if it does not have any
+        /// dependency, otherwise one more than the highest rank of its dependencies
+        fn compute_apply_rank(
+            &mut self,
+            kind: ComponentKind,
+            visiting: &mut Vec<ComponentKind>,
+        ) -> usize {
+            if let Some(rank) = self.apply_ranks.get(&kind) {
+                return *rank;
+            }
+            if visiting.contains(&kind) {
+                panic!(
+                    "Cycle detected in the component application order involving {}",
+                    self.serialize_fns_map
+                        .get(&kind)
+                        .map_or("an unregistered component", |fns| fns.type_name)
+                );
+            }
+            visiting.push(kind);
+            let dependencies = self
+                .apply_dependencies
+                .get(&kind)
+                .cloned()
+                .unwrap_or_default();
+            let rank = dependencies
+                .into_iter()
+                .map(|dependency| self.compute_apply_rank(dependency, visiting) + 1)
+                .max()
+                .unwrap_or(0);
+            visiting.pop();
+            self.apply_ranks.insert(kind, rank);
+            rank
+        }
+
+        /// Sort serialized components so that they are applied in the declared application order.
+        ///
+        /// The sort is stable, so components with the same rank keep the order in which they were received.
+        pub(crate) fn sort_by_apply_order(&self, components: &mut [Bytes]) {
+            if self.apply_ranks.is_empty() {
+                return;
+            }
+            components.sort_by_cached_key(|component| {
+                ComponentNetId::from_bytes(&mut Reader::from(component.clone()))
+                    .ok()
+                    .and_then(|net_id| self.kind_map.kind(net_id))
+                    .and_then(|kind| self.apply_ranks.get(kind))
+                    .copied()
+                    .unwrap_or(0)
+            });
+        }
+
         pub(crate) fn raw_remove(
             &self,
             net_id: ComponentNetId,
             entity_world_mut: &mut EntityWorldMut,
         ) {
-            let kind = self.kind_map.kind(net_id).expect("unknown component kind");
+            let Some(kind) = self.kind_map.kind(net_id) else {
+                debug!(?net_id, "skipping removal of unknown component");
+                return;
+            };
             let replication_metadata = self
                 .replication_map
                 .get(kind)
@@ -696,6 +1057,7 @@ mod delta {
                     delta_compression_id: ComponentId::new(0),
                     replicate_once_id: ComponentId::new(0),
                     override_target_id: ComponentId::new(0),
+                    client_filter_id: ComponentId::new(0),
                     disabled_id: ComponentId::new(0),
                     write,
                     remove: None,
@@ -791,6 +1153,7 @@ mod delta {
             let delta =
                 self.raw_deserialize::<DeltaMessage<C::Delta>>(reader, delta_net_id, entity_map)?;
             let entity = entity_world_mut.id();
+            let from_client = Self::is_from_client(entity_world_mut);
             // TODO: should we send the event based on on the message type (Insert/Update) or based on whether the component was actually inserted?
             match delta.delta_type {
                 DeltaType::Normal { previous_tick } => {
@@ -815,6 +1178,15 @@ mod delta {
                     history.buffer = history.buffer.split_off(&previous_tick);
                     // store the new value in the history
                     history.buffer.insert(tick, new_value.clone());
+                    // the history still stores the value, because the next diffs are computed from it
+                    if !self.validate(&new_value, from_client) {
+                        debug!(
+                            ?entity,
+                            "rejecting invalid value of component {}",
+                            std::any::type_name::<C>()
+                        );
+                        return Ok(());
+                    }
                     let Some(mut c) = entity_world_mut.get_mut::<C>() else {
                         return Err(ComponentError::DeltaCompressionError(
                             format!("Entity {entity:?} does not have a {} component, but we received a diff for delta-compression",
@@ -828,7 +1200,13 @@ mod delta {
                     let mut new_value = C::base_value();
                     new_value.apply_diff(&delta.delta);
                     let value = new_value.clone();
-                    if let Some(mut c) = entity_world_mut.get_mut::<C>() {
+                    if !self.validate(&new_value, from_client) {
+                        debug!(
+                            ?entity,
+                            "rejecting invalid value of component {}",
+                            std::any::type_name::<C>()
+                        );
+                    } else if let Some(mut c) = entity_world_mut.get_mut::<C>() {
                         // only apply the update if the component is different, to not trigger change detection
                         if c.as_ref() != &new_value {
                             *c = new_value;
@@ -923,6 +1301,12 @@ pub trait AppComponentExt {
     /// Add a `Correction` behaviour to this component.
     fn add_correction_fn<C: SyncComponent>(&mut self, correction_fn: LerpFn<C>);
 
+    /// Add a function to decide if the correction should be skipped after a rollback.
+    ///
+    /// When the function returns true (for example if the prediction error is above a threshold),
+    /// the predicted entity snaps directly to the corrected value instead of being visually corrected.
+    fn add_correction_snap_fn<C: SyncComponent>(&mut self, should_snap: ShouldSnapCorrectionFn<C>);
+
     /// Add a custom function to use for checking if a rollback is needed.
     ///
     /// (By default we use the PartialEq::ne function, but you can use this to override the
@@ -943,6 +1327,12 @@ pub trait AppComponentExt {
     /// Add a `Interpolation` behaviour to this component.
     fn add_interpolation_fn<C: SyncComponent>(&mut self, interpolation_fn: LerpFn<C>);
 
+    /// Add a function to detect discontinuities (teleports) between two consecutive server snapshots.
+    ///
+    /// When the function returns true, the interpolated entity jumps directly to the new snapshot
+    /// instead of interpolating towards it.
+    fn add_interpolation_snap_fn<C: SyncComponent>(&mut self, should_snap: ShouldSnapFn<C>);
+
     /// Enable delta compression when serializing this component
     fn add_delta_compression<C: Component + PartialEq + Diffable>(&mut self)
     where
@@ -955,6 +1345,25 @@ pub struct ComponentRegistration<'a, C> {
 }
 
 impl<C> ComponentRegistration<'_, C> {
+    /// Use a fixed network id for this component, instead of the id derived from the order of registration.
+    ///
+    /// Using fixed ids lets a server and clients that run different versions of the protocol keep
+    /// talking to each other: components that the receiver doesn't know are skipped, and the
+    /// other components keep the same id even if components are added or removed from the protocol.
+    ///
+    /// If the id was automatically assigned to a component registered earlier, that component gets
+    /// another id, so fixed ids don't depend on the order of registration.
+    ///
+    /// Panics if the id is already fixed for another component.
+    pub fn with_net_id(self, net_id: ComponentNetId) -> Self
+    where
+        C: 'static,
+    {
+        let mut registry = self.app.world_mut().resource_mut::<ComponentRegistry>();
+        registry.kind_map.set_net_id::<C>(net_id);
+        self
+    }
+
     /// Specify that the component contains entities which should be mapped from the remote world to the local world
     /// upon deserialization
     pub fn add_map_entities(self) -> Self
@@ -966,6 +1375,18 @@ impl<C> ComponentRegistration<'_, C> {
         self
     }
 
+    /// Compress the serialized bytes of this component type.
+    ///
+    /// This must be set identically on the client and the server.
+    pub fn add_compression(self, compression: TypeCompressionConfig) -> Self
+    where
+        C: 'static,
+    {
+        let mut registry = self.app.world_mut().resource_mut::<ComponentRegistry>();
+        registry.set_compression::<C>(compression);
+        self
+    }
+
     /// Enable prediction systems for this component.
     /// You can specify the prediction [`ComponentSyncMode`]
     pub fn add_prediction(self, prediction_mode: ComponentSyncMode) -> Self
@@ -994,6 +1415,18 @@ impl<C> ComponentRegistration<'_, C> {
         self
     }
 
+    /// Add a function to decide if the correction should be skipped after a rollback.
+    ///
+    /// When the function returns true (for example if the prediction error is above a threshold),
+    /// the predicted entity snaps directly to the corrected value instead of being visually corrected.
+    pub fn add_correction_snap_fn(self, should_snap: ShouldSnapCorrectionFn<C>) -> Self
+    where
+        C: SyncComponent,
+    {
+        self.app.add_correction_snap_fn::<C>(should_snap);
+        self
+    }
+
     /// Add a custom function to use for checking if a rollback is needed.
     ///
     /// (By default we use the PartialEq::ne function, but you can use this to override the
@@ -1044,6 +1477,18 @@ impl<C> ComponentRegistration<'_, C> {
         self
     }
 
+    /// Add a function to detect discontinuities (teleports) between two consecutive server snapshots.
+    ///
+    /// When the function returns true, the interpolated entity jumps directly to the new snapshot
+    /// instead of interpolating towards it.
+    pub fn add_interpolation_snap_fn(self, should_snap: ShouldSnapFn<C>) -> Self
+    where
+        C: SyncComponent,
+    {
+        self.app.add_interpolation_snap_fn::<C>(should_snap);
+        self
+    }
+
     /// Enable delta compression when serializing this component
     pub fn add_delta_compression(self) -> Self
     where
@@ -1053,6 +1498,76 @@ impl<C> ComponentRegistration<'_, C> {
         self.app.add_delta_compression::<C>();
         self
     }
+
+    /// Ask the client to confirm every update of this component that it applied to its world.
+    ///
+    /// Packet acks only tell that the update was received: the update could still be discarded
+    /// (for example if it is older than the current state, or if the entity was not spawned yet).
+    /// With application acks, the server emits a [`ComponentAppliedEvent`](crate::prelude::server::ComponentAppliedEvent)
+    /// when the client actually applied the update.
+    ///
+    /// This sends an extra reliable message for every applied update, so it should only be used
+    /// for components that need it (for example to confirm a teleport).
+    pub fn add_application_ack(self) -> Self
+    where
+        C: 'static,
+    {
+        let mut registry = self.app.world_mut().resource_mut::<ComponentRegistry>();
+        registry.add_application_ack::<C>();
+        self
+    }
+
+    /// Validate the values of this component that the server receives from the clients.
+    ///
+    /// This is useful for client-authoritative components (for example the position of the entity
+    /// controlled by the client): the server rejects the values for which `validate` returns false
+    /// (for example a position outside of the map), so the entity keeps its previous value, which is
+    /// also the value replicated to the other clients.
+    ///
+    /// The validation only runs on the server, for the entities that are controlled by a client
+    /// (the entities replicated from a client, or whose authority was transferred to a client).
+    ///
+    /// Authority is tracked per entity (see [`HasAuthority`](crate::prelude::HasAuthority)): all the replicated
+    /// components of an entity are owned by the same peer. Making only some components of a
+    /// server-authoritative entity client-authoritative is not supported: instead, put the
+    /// client-owned components on a separate entity and give its authority to the client with
+    /// [`transfer_authority`](crate::prelude::server::AuthorityCommandExt::transfer_authority).
+    pub fn add_validation(self, validate: ValidateFn<C>) -> Self
+    where
+        C: 'static,
+    {
+        let mut registry = self.app.world_mut().resource_mut::<ComponentRegistry>();
+        registry.add_validation::<C>(validate);
+        self
+    }
+
+    /// Choose the channel on which the updates of this component are replicated.
+    ///
+    /// See [`ReplicationMode`] for more details.
+    pub fn with_replication_mode(self, mode: ReplicationMode) -> Self
+    where
+        C: 'static,
+    {
+        let mut registry = self.app.world_mut().resource_mut::<ComponentRegistry>();
+        registry.set_replication_mode::<C>(mode);
+        self
+    }
+
+    /// Specify that the component `D` must be applied before this component when both are
+    /// received for an entity in the same replication message.
+    ///
+    /// This is useful if an observer or hook that reacts to the insertion of this component
+    /// expects `D` to already be present on the entity.
+    ///
+    /// Panics if this creates a cycle in the component application order.
+    pub fn add_dependency<D: Component>(self) -> Self
+    where
+        C: 'static,
+    {
+        let mut registry = self.app.world_mut().resource_mut::<ComponentRegistry>();
+        registry.add_apply_dependency::<C, D>();
+        self
+    }
 }
 
 impl AppComponentExt for App {
@@ -1107,12 +1622,8 @@ impl AppComponentExt for App {
     fn add_prediction<C: SyncComponent>(&mut self, prediction_mode: ComponentSyncMode) {
         let mut registry = self.world_mut().resource_mut::<ComponentRegistry>();
         registry.set_prediction_mode::<C>(prediction_mode);
-
-        // TODO: make prediction/interpolation possible on server?
-        let is_client = self.world().get_resource::<ClientConfig>().is_some();
-        if is_client {
-            add_prediction_systems::<C>(self, prediction_mode);
-        }
+        // the PredictionPlugin adds the systems, after applying the PredictionConfig to the mode
+        registry.set_prediction_systems::<C>(add_prediction_systems::<C>);
     }
 
     fn add_linear_correction_fn<C: SyncComponent + Linear>(&mut self) {
@@ -1126,6 +1637,11 @@ impl AppComponentExt for App {
         registry.set_correction::<C>(correction_fn);
     }
 
+    fn add_correction_snap_fn<C: SyncComponent>(&mut self, should_snap: ShouldSnapCorrectionFn<C>) {
+        let mut registry = self.world_mut().resource_mut::<ComponentRegistry>();
+        registry.set_correction_snap::<C>(should_snap);
+    }
+
     fn add_should_rollback_fn<C: SyncComponent>(&mut self, rollback_check: ShouldRollbackFn<C>) {
         let mut registry = self.world_mut().resource_mut::<ComponentRegistry>();
         registry.set_should_rollback::<C>(rollback_check);
@@ -1175,6 +1691,11 @@ impl AppComponentExt for App {
         registry.set_interpolation::<C>(interpolation_fn);
     }
 
+    fn add_interpolation_snap_fn<C: SyncComponent>(&mut self, should_snap: ShouldSnapFn<C>) {
+        let mut registry = self.world_mut().resource_mut::<ComponentRegistry>();
+        registry.set_interpolation_snap::<C>(should_snap);
+    }
+
     fn add_delta_compression<C: Component + PartialEq + Diffable>(&mut self)
     where
         C::Delta: Serialize + DeserializeOwned,
@@ -1205,8 +1726,12 @@ impl From<TypeId> for ComponentKind {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::prelude::{client, server, ClientId, SharedConfig, TickConfig};
     use crate::serialize::writer::Writer;
     use crate::tests::protocol::*;
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+    use bevy::prelude::{OnAdd, Query, ResMut, Trigger, With};
+    use bevy::utils::Duration;
 
     #[test]
     fn test_custom_serde() {
@@ -1229,4 +1754,266 @@ mod tests {
             .unwrap();
         assert_eq!(component, read);
     }
+
+    /// Check that the server rejects the values sent by the client that don't pass the validation
+    #[test]
+    fn test_component_validation() {
+        let mut stepper = BevyStepper::default();
+
+        let client_entity = stepper
+            .client_app
+            .world_mut()
+            .spawn((client::Replicate::default(), ComponentValidated(1.0)))
+            .id();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let server_entity = stepper
+            .server_app
+            .world()
+            .resource::<server::ConnectionManager>()
+            .connection(ClientId::Netcode(TEST_CLIENT_ID))
+            .unwrap()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(client_entity)
+            .expect("entity was not replicated to server");
+        let server_value = |stepper: &BevyStepper| {
+            stepper
+                .server_app
+                .world()
+                .get::<ComponentValidated>(server_entity)
+                .unwrap()
+                .0
+        };
+        assert_eq!(server_value(&stepper), 1.0);
+
+        // the invalid value is rejected
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<ComponentValidated>(client_entity)
+            .unwrap()
+            .0 = -1.0;
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        assert_eq!(server_value(&stepper), 1.0);
+
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<ComponentValidated>(client_entity)
+            .unwrap()
+            .0 = 2.0;
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        assert_eq!(server_value(&stepper), 2.0);
+    }
+
+    /// Check that the clients accept all the values sent by the server, even if the validation
+    /// is registered on the client
+    #[test]
+    fn test_component_validation_server_values() {
+        let mut stepper = BevyStepper::default();
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((server::Replicate::default(), ComponentValidated(-1.0)))
+            .id();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentValidated>(client_entity),
+            Some(&ComponentValidated(-1.0))
+        );
+    }
+
+    #[derive(Resource, Default)]
+    struct DependencyPresent(Vec<bool>);
+
+    /// Check that a component is applied after the components it depends on, when they are
+    /// received in the same message
+    #[test]
+    fn test_component_apply_order() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..Default::default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, ClientConfig::default(), frame_duration);
+        // the dependency has to be declared before the client's ConnectionManager is built
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ComponentRegistry>()
+            .add_apply_dependency::<ComponentSyncModeFull, ComponentSyncModeOnce>();
+        stepper.client_app.init_resource::<DependencyPresent>();
+        stepper.client_app.observe(
+            |trigger: Trigger<OnAdd, ComponentSyncModeFull>,
+             query: Query<(), With<ComponentSyncModeOnce>>,
+             mut present: ResMut<DependencyPresent>| {
+                present.0.push(query.get(trigger.entity()).is_ok());
+            },
+        );
+
+        stepper.init();
+
+        stepper.server_app.world_mut().spawn((
+            ComponentSyncModeOnce(1.0),
+            ComponentSyncModeFull(1.0),
+            server::Replicate::default(),
+        ));
+        stepper.frame_step();
+        stepper.frame_step();
+        assert_eq!(
+            stepper.client_app.world().resource::<DependencyPresent>().0,
+            vec![true]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_component_apply_order_cycle() {
+        let mut registry = ComponentRegistry::default();
+        registry.register_component::<ComponentSyncModeOnce>();
+        registry.register_component::<ComponentSyncModeFull>();
+        registry.add_apply_dependency::<ComponentSyncModeOnce, ComponentSyncModeFull>();
+        registry.add_apply_dependency::<ComponentSyncModeFull, ComponentSyncModeOnce>();
+    }
+
+    #[test]
+    fn test_fixed_net_id() {
+        let mut registry = ComponentRegistry::default();
+        registry.register_component::<ComponentSyncModeOnce>();
+        registry.register_component::<ComponentSyncModeFull>();
+        registry.kind_map.set_net_id::<ComponentSyncModeFull>(10);
+        registry.register_component::<ComponentSyncModeFull2>();
+        assert_eq!(registry.net_id::<ComponentSyncModeOnce>(), 0);
+        assert_eq!(registry.net_id::<ComponentSyncModeFull>(), 10);
+        assert_eq!(registry.net_id::<ComponentSyncModeFull2>(), 2);
+        assert_eq!(
+            registry.kind_map.kind(10),
+            Some(&ComponentKind::of::<ComponentSyncModeFull>())
+        );
+        assert_eq!(registry.kind_map.kind(1), None);
+
+        // automatic ids skip the ids that are already used
+        registry.kind_map.set_net_id::<ComponentSyncModeFull2>(3);
+        registry.register_component::<ComponentMapEntities>();
+        assert_eq!(registry.net_id::<ComponentMapEntities>(), 4);
+    }
+
+    /// A fixed id takes precedence over an id assigned automatically to a component registered earlier
+    #[test]
+    fn test_fixed_net_id_reassigns_automatic_id() {
+        let mut registry = ComponentRegistry::default();
+        registry.register_component::<ComponentSyncModeOnce>();
+        registry.register_component::<ComponentSyncModeFull>();
+        registry.kind_map.set_net_id::<ComponentSyncModeFull>(0);
+        assert_eq!(registry.net_id::<ComponentSyncModeFull>(), 0);
+        assert_eq!(registry.net_id::<ComponentSyncModeOnce>(), 2);
+        assert_eq!(
+            registry.kind_map.kind(2),
+            Some(&ComponentKind::of::<ComponentSyncModeOnce>())
+        );
+        assert_eq!(registry.kind_map.kind(1), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_fixed_net_id_conflict() {
+        let mut registry = ComponentRegistry::default();
+        registry.register_component::<ComponentSyncModeOnce>();
+        registry.register_component::<ComponentSyncModeFull>();
+        registry.kind_map.set_net_id::<ComponentSyncModeOnce>(5);
+        registry.kind_map.set_net_id::<ComponentSyncModeFull>(5);
+    }
+
+    /// Check that components that the receiver doesn't know about (for example because the remote
+    /// uses a newer version of the protocol) are skipped
+    #[test]
+    fn test_skip_unknown_component() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..Default::default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, ClientConfig::default(), frame_duration);
+        // the client doesn't know about the net id that the server uses for ComponentSyncModeOnce
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ComponentRegistry>()
+            .kind_map
+            .set_net_id::<ComponentSyncModeOnce>(1000);
+        stepper.init();
+
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                ComponentSyncModeOnce(1.0),
+                ComponentSyncModeFull(1.0),
+                server::Replicate::default(),
+            ))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<crate::prelude::client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(client_entity),
+            Some(&ComponentSyncModeFull(1.0))
+        );
+        assert!(stepper
+            .client_app
+            .world()
+            .get::<ComponentSyncModeOnce>(client_entity)
+            .is_none());
+
+        // removals of unknown components are skipped as well
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(server_entity)
+            .remove::<ComponentSyncModeOnce>();
+        stepper
+            .server_app
+            .world_mut()
+            .get_mut::<ComponentSyncModeFull>(server_entity)
+            .unwrap()
+            .0 = 2.0;
+        stepper.frame_step();
+        stepper.frame_step();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(client_entity),
+            Some(&ComponentSyncModeFull(2.0))
+        );
+    }
 }
@@ -22,6 +22,7 @@ use crate::serialize::ToBytes;
 use crate::server::message::add_server_receive_message_from_client;
 use crate::shared::replication::entity_map::{ReceiveEntityMap, SendEntityMap};
 use crate::shared::replication::resources::DespawnResource;
+use crate::transport::middleware::compression::TypeCompressionConfig;
 
 #[derive(thiserror::Error, Debug)]
 pub enum MessageError {
@@ -100,6 +101,8 @@ pub struct MessageRegistry {
     typed_map: HashMap<MessageKind, MessageType>,
     serialize_fns_map: HashMap<MessageKind, ErasedSerializeFns>,
     pub(crate) kind_map: TypeMapper<MessageKind>,
+    #[cfg(feature = "serialization_stats")]
+    pub(crate) serialized_sizes: crate::protocol::serialization_stats::SizeRecorder,
 }
 
 fn register_message_send<M: Message>(app: &mut App, direction: ChannelDirection) {
@@ -198,6 +201,18 @@ impl<M> MessageRegistration<'_, M> {
         registry.add_map_entities::<M>();
         self
     }
+
+    /// Compress the serialized bytes of this message type.
+    ///
+    /// This must be set identically on the client and the server.
+    pub fn add_compression(self, compression: TypeCompressionConfig) -> Self
+    where
+        M: 'static,
+    {
+        let mut registry = self.app.world_mut().resource_mut::<MessageRegistry>();
+        registry.set_compression::<M>(compression);
+        self
+    }
 }
 
 pub(crate) trait AppMessageInternalExt {
@@ -381,6 +396,23 @@ impl MessageRegistry {
         erased_fns.add_map_entities::<M>();
     }
 
+    pub(crate) fn set_compression<M: 'static>(&mut self, compression: TypeCompressionConfig) {
+        let kind = MessageKind::of::<M>();
+        let erased_fns = self.serialize_fns_map.get_mut(&kind).unwrap_or_else(|| {
+            panic!(
+                "Message {} is not part of the protocol",
+                std::any::type_name::<M>()
+            )
+        });
+        erased_fns.compression = Some(compression);
+    }
+
+    /// Type names of all the messages that can be serialized
+    #[cfg(feature = "serialization_stats")]
+    pub(crate) fn type_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.serialize_fns_map.values().map(|fns| fns.type_name)
+    }
+
     /// Returns true if we have a registered `map_entities` function for this message type
     pub(crate) fn is_map_entities<M: 'static>(&self) -> bool {
         let kind = MessageKind::of::<M>();
@@ -403,11 +435,16 @@ impl MessageRegistry {
             .get(&kind)
             .ok_or(MessageError::MissingSerializationFns)?;
         let net_id = self.kind_map.net_id(&kind).unwrap();
+        #[cfg(feature = "serialization_stats")]
+        let start = writer.len();
         net_id.to_bytes(writer)?;
         // SAFETY: the ErasedSerializeFns was created for the type M
         unsafe {
             erased_fns.serialize(message, writer, entity_map)?;
         }
+        #[cfg(feature = "serialization_stats")]
+        self.serialized_sizes
+            .record(erased_fns.type_name, writer.len() - start);
         Ok(())
     }
 
@@ -24,6 +24,9 @@ pub(crate) mod delta;
 pub(crate) mod registry;
 pub(crate) mod serialize;
 
+#[cfg(feature = "serialization_stats")]
+pub mod serialization_stats;
+
 /// Data that can be used in an Event
 /// Same as `Event`, but we implement it automatically for all compatible types
 pub trait EventContext: Send + Sync + 'static {}
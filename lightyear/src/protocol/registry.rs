@@ -1,7 +1,7 @@
 use crate::serialize::reader::Reader;
 use crate::serialize::varint::{varint_len, VarIntReadExt, VarIntWriteExt};
 use crate::serialize::{SerializationError, ToBytes};
-use bevy::utils::HashMap;
+use bevy::utils::{HashMap, HashSet};
 use byteorder::WriteBytesExt;
 use std::any::TypeId;
 use std::hash::Hash;
@@ -35,6 +35,8 @@ pub struct TypeMapper<K: TypeKind> {
     pub(crate) next_net_id: NetId,
     pub(crate) kind_map: HashMap<K, NetId>,
     pub(crate) id_map: HashMap<NetId, K>,
+    /// Net ids that were fixed with [`TypeMapper::set_net_id`]
+    pub(crate) fixed_net_ids: HashSet<NetId>,
 }
 
 impl<K: TypeKind> Default for TypeMapper<K> {
@@ -49,22 +51,68 @@ impl<K: TypeKind> TypeMapper<K> {
             next_net_id: 0,
             kind_map: HashMap::new(),
             id_map: HashMap::new(),
+            fixed_net_ids: HashSet::new(),
         }
     }
 
     /// Register a new type
+    ///
+    /// The type gets the next net id that is not already used.
     pub fn add<T: 'static>(&mut self) -> K {
         let kind = K::from(TypeId::of::<T>());
         if self.kind_map.contains_key(&kind) {
             panic!("Type {:?} already registered", std::any::type_name::<T>());
         }
-        let net_id = self.next_net_id;
+        let net_id = self.next_free_net_id();
         self.kind_map.insert(kind, net_id);
         self.id_map.insert(net_id, kind);
-        self.next_net_id += 1;
         kind
     }
 
+    /// Returns the next net id that is not already used
+    fn next_free_net_id(&mut self) -> NetId {
+        while self.id_map.contains_key(&self.next_net_id) {
+            self.next_net_id += 1;
+        }
+        let net_id = self.next_net_id;
+        self.next_net_id += 1;
+        net_id
+    }
+
+    /// Use a fixed net id for a type that is already registered, instead of the id derived from the registration order
+    ///
+    /// Fixed ids take precedence over the ids derived from the registration order: if the net id was
+    /// automatically assigned to another type, that type gets the next free net id instead.
+    ///
+    /// Panics if the net id is already fixed for another type.
+    pub(crate) fn set_net_id<T: 'static>(&mut self, net_id: NetId) {
+        let kind = K::from(TypeId::of::<T>());
+        let previous = *self
+            .kind_map
+            .get(&kind)
+            .unwrap_or_else(|| panic!("Type {:?} is not registered", std::any::type_name::<T>()));
+        if previous == net_id {
+            self.fixed_net_ids.insert(net_id);
+            return;
+        }
+        if self.fixed_net_ids.contains(&net_id) {
+            panic!(
+                "Cannot use net id {net_id} for type {:?}: it is already used by another type",
+                std::any::type_name::<T>()
+            );
+        }
+        self.fixed_net_ids.remove(&previous);
+        self.id_map.remove(&previous);
+        self.kind_map.insert(kind, net_id);
+        // move the type that had this id automatically assigned to a free id
+        if let Some(other) = self.id_map.insert(net_id, kind) {
+            let other_net_id = self.next_free_net_id();
+            self.kind_map.insert(other, other_net_id);
+            self.id_map.insert(other_net_id, other);
+        }
+        self.fixed_net_ids.insert(net_id);
+    }
+
     pub fn kind(&self, net_id: NetId) -> Option<&K> {
         self.id_map.get(&net_id)
     }
@@ -0,0 +1,177 @@
+//! Record the number of bytes that each component and message type serializes to
+//!
+//! Requires the `serialization_stats` feature.
+//!
+//! Every frame, after the packets are sent, the [`SerializationStats`] resource is updated with the number of bytes
+//! serialized by each type during the frame (the size includes the network id of the type).
+//! The values are also available as [`Diagnostic`]s, with the paths `serialization.component.<type name>` and
+//! `serialization.message.<type name>`, so that they can be displayed with the `LogDiagnosticsPlugin`:
+//! ```rust,ignore
+//! app.add_plugins(LogDiagnosticsPlugin::default());
+//! ```
+use std::sync::{Arc, Mutex};
+
+use bevy::diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::prelude::{ComponentRegistry, MainSet, MessageRegistry};
+use crate::shared::sets::NetworkingSchedules;
+
+/// Number of bytes serialized by each component and message type during the last frame
+///
+/// The types that were not serialized during the frame are not present.
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct SerializationStats {
+    /// Bytes serialized for each component type (by type name)
+    pub components: HashMap<&'static str, usize>,
+    /// Bytes serialized for each message type (by type name)
+    pub messages: HashMap<&'static str, usize>,
+}
+
+impl SerializationStats {
+    /// Total number of bytes serialized during the last frame
+    pub fn total(&self) -> usize {
+        self.components.values().sum::<usize>() + self.messages.values().sum::<usize>()
+    }
+}
+
+/// Accumulates the serialized sizes inside a registry, which is only accessed immutably when serializing
+///
+/// The clones of a registry (for example the `MessageRegistry` of the `ConnectionManager`) share the same recorder.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct SizeRecorder(Arc<Mutex<HashMap<&'static str, usize>>>);
+
+impl SizeRecorder {
+    pub(crate) fn record(&self, type_name: &'static str, bytes: usize) {
+        *self.0.lock().unwrap().entry(type_name).or_default() += bytes;
+    }
+
+    fn drain(&self) -> HashMap<&'static str, usize> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+// the recorded sizes are not part of the protocol
+impl PartialEq for SizeRecorder {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+fn component_path(type_name: &str) -> DiagnosticPath {
+    DiagnosticPath::new(format!("serialization.component.{type_name}"))
+}
+
+fn message_path(type_name: &str) -> DiagnosticPath {
+    DiagnosticPath::new(format!("serialization.message.{type_name}"))
+}
+
+#[derive(Default)]
+pub(crate) struct SerializationStatsPlugin;
+
+impl SerializationStatsPlugin {
+    fn flush(
+        component_registry: Res<ComponentRegistry>,
+        message_registry: Res<MessageRegistry>,
+        mut stats: ResMut<SerializationStats>,
+        mut diagnostics: Diagnostics,
+    ) {
+        stats.components = component_registry.serialized_sizes.drain();
+        stats.messages = message_registry.serialized_sizes.drain();
+        for type_name in component_registry.type_names() {
+            diagnostics.add_measurement(&component_path(type_name), || {
+                stats.components.get(type_name).copied().unwrap_or_default() as f64
+            });
+        }
+        for type_name in message_registry.type_names() {
+            diagnostics.add_measurement(&message_path(type_name), || {
+                stats.messages.get(type_name).copied().unwrap_or_default() as f64
+            });
+        }
+    }
+}
+
+impl Plugin for SerializationStatsPlugin {
+    fn build(&self, app: &mut App) {
+        let schedules = NetworkingSchedules::of(app);
+        app.init_resource::<SerializationStats>();
+        app.add_systems(schedules.send, Self::flush.after(MainSet::Send));
+    }
+
+    // the protocol is complete once all the plugins have been built
+    fn finish(&self, app: &mut App) {
+        let components: Vec<_> = app
+            .world()
+            .resource::<ComponentRegistry>()
+            .type_names()
+            .collect();
+        let messages: Vec<_> = app
+            .world()
+            .resource::<MessageRegistry>()
+            .type_names()
+            .collect();
+        for type_name in components {
+            app.register_diagnostic(
+                Diagnostic::new(component_path(type_name)).with_suffix("bytes"),
+            );
+        }
+        for type_name in messages {
+            app.register_diagnostic(Diagnostic::new(message_path(type_name)).with_suffix("bytes"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::diagnostic::DiagnosticsStore;
+
+    use crate::prelude::server::{ConnectionManager, Replicate};
+    use crate::prelude::ClientId;
+    use crate::tests::protocol::{Channel1, ComponentSyncModeFull, StringMessage};
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+
+    use super::*;
+
+    #[test]
+    fn test_serialization_stats() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .server_app
+            .world_mut()
+            .spawn((Replicate::default(), ComponentSyncModeFull(1.0)));
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ConnectionManager>()
+            .send_message::<Channel1, _>(
+                ClientId::Netcode(TEST_CLIENT_ID),
+                &mut StringMessage("a".to_string()),
+            )
+            .unwrap();
+        stepper.frame_step();
+
+        let component = std::any::type_name::<ComponentSyncModeFull>();
+        let message = std::any::type_name::<StringMessage>();
+        let stats = stepper.server_app.world().resource::<SerializationStats>();
+        // net id + f32
+        assert_eq!(stats.components.get(component), Some(&5));
+        // net id + string length + string
+        assert_eq!(stats.messages.get(message), Some(&3));
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .resource::<DiagnosticsStore>()
+                .get(&component_path(component))
+                .and_then(|diagnostic| diagnostic.value()),
+            Some(5.0)
+        );
+
+        // the stats only contain the data serialized during the last frame
+        stepper.frame_step();
+        let stats = stepper.server_app.world().resource::<SerializationStats>();
+        assert!(stats.messages.is_empty());
+        assert!(stats.components.is_empty());
+    }
+}
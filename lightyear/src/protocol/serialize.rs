@@ -1,12 +1,18 @@
 use crate::prelude::{ComponentRegistry, Message, MessageRegistry};
+use crate::serialize::varint::{VarIntReadExt, VarIntWriteExt};
 use crate::serialize::{reader::Reader, writer::Writer, SerializationError};
 use crate::shared::replication::entity_map::{EntityMap, ReceiveEntityMap, SendEntityMap};
+use crate::transport::middleware::compression::{
+    compress, decompress, TypeCompressionConfig, MAX_DECOMPRESSED_SIZE,
+};
 use bevy::app::App;
 use bevy::ecs::entity::MapEntities;
 use bevy::ptr::{Ptr, PtrMut};
+use byteorder::{ReadBytesExt, WriteBytesExt};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::any::TypeId;
+use std::io::Write;
 
 /// Stores function pointers related to serialization and deserialization
 #[derive(Clone, Debug, PartialEq)]
@@ -21,6 +27,8 @@ pub struct ErasedSerializeFns {
     pub map_entities: Option<ErasedMapEntitiesFn>,
     pub send_map_entities: Option<ErasedSendMapEntitiesFn>,
     pub receive_map_entities: Option<ErasedReceiveMapEntitiesFn>,
+    /// If set, the serialized bytes of the type are compressed
+    pub compression: Option<TypeCompressionConfig>,
 }
 
 pub struct SerializeFns<M> {
@@ -68,39 +76,67 @@ unsafe fn erased_serialize_fn<M: Message>(
     writer: &mut Writer,
     entity_map: Option<&mut SendEntityMap>,
 ) -> Result<(), SerializationError> {
-    let typed_serialize_fns = erased_serialize_fn.typed::<M>();
-    if let Some(map_entities) = erased_serialize_fn.send_map_entities {
-        let serialize_map_entities = typed_serialize_fns.serialize_map_entities.unwrap();
-        serialize_map_entities(
-            message.deref::<M>(),
-            writer,
-            entity_map.expect("EntityMap is required to serialize this message"),
-            std::mem::transmute(erased_serialize_fn.erased_clone.unwrap()),
-            map_entities,
-            typed_serialize_fns.serialize,
-        )
-    } else {
-        // SAFETY: the Ptr was created for the message of type M
-        let message = message.deref::<M>();
-        (typed_serialize_fns.serialize)(message, writer)
+    // SAFETY: the Ptr was created for the message of type M
+    erased_serialize_fn.serialize(message.deref::<M>(), writer, entity_map)
+}
+
+/// Codec used to serialize the types that implement [`Serialize`] and [`DeserializeOwned`]
+///
+/// The messages and components registered with `register_message`/`register_component` use the [`Bincode`] backend.
+/// To use another codec (for example postcard, or a format that a non-Rust peer can read), register the type with
+/// `register_message_custom_serde`/`register_component_custom_serde` and [`SerializeFns::with_backend`].
+///
+/// Multiple values are written one after the other in the same packet, so the encoding must be self-delimiting:
+/// `deserialize` must only read the bytes that were written by `serialize`.
+pub trait SerializationBackend: 'static {
+    fn serialize<M: Serialize>(message: &M, writer: &mut Writer) -> Result<(), SerializationError>;
+
+    fn deserialize<M: DeserializeOwned>(reader: &mut Reader) -> Result<M, SerializationError>;
+}
+
+/// Default [`SerializationBackend`], using bincode
+///
+/// The standard bincode config uses variable-length integers, so enums are serialized compactly:
+/// only the discriminant of the active variant (a single byte for less than 251 variants) and its fields
+/// are written, regardless of the size of the other variants.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Bincode;
+
+impl SerializationBackend for Bincode {
+    fn serialize<M: Serialize>(message: &M, writer: &mut Writer) -> Result<(), SerializationError> {
+        let _ =
+            bincode::serde::encode_into_std_write(message, writer, bincode::config::standard())?;
+        Ok(())
+    }
+
+    fn deserialize<M: DeserializeOwned>(reader: &mut Reader) -> Result<M, SerializationError> {
+        let data = bincode::serde::decode_from_std_read(reader, bincode::config::standard())?;
+        Ok(data)
     }
 }
 
-/// Default serialize function using bincode
-fn default_serialize<M: Message + Serialize>(
+fn backend_serialize<B: SerializationBackend, M: Message + Serialize>(
     message: &M,
-    buffer: &mut Writer,
+    writer: &mut Writer,
 ) -> Result<(), SerializationError> {
-    let _ = bincode::serde::encode_into_std_write(message, buffer, bincode::config::standard())?;
-    Ok(())
+    B::serialize(message, writer)
 }
 
-/// Default deserialize function using bincode
-fn default_deserialize<M: Message + DeserializeOwned>(
-    buffer: &mut Reader,
+fn backend_deserialize<B: SerializationBackend, M: Message + DeserializeOwned>(
+    reader: &mut Reader,
 ) -> Result<M, SerializationError> {
-    let data = bincode::serde::decode_from_std_read(buffer, bincode::config::standard())?;
-    Ok(data)
+    B::deserialize(reader)
+}
+
+impl<M: Message + Serialize + DeserializeOwned> SerializeFns<M> {
+    /// Serialization functions that use the [`SerializationBackend`] `B`
+    pub fn with_backend<B: SerializationBackend>() -> Self {
+        Self {
+            serialize: backend_serialize::<B, M>,
+            deserialize: backend_deserialize::<B, M>,
+            serialize_map_entities: None,
+        }
+    }
 }
 
 pub(crate) fn serialize_map_entities<M>(
@@ -151,22 +187,7 @@ unsafe fn erased_receive_map_entities<M: MapEntities + 'static>(
 
 impl ErasedSerializeFns {
     pub(crate) fn new<M: Message + Serialize + DeserializeOwned>() -> Self {
-        let serialize_fns = SerializeFns {
-            serialize: default_serialize::<M>,
-            deserialize: default_deserialize::<M>,
-            serialize_map_entities: None,
-        };
-        Self {
-            type_id: TypeId::of::<M>(),
-            type_name: std::any::type_name::<M>(),
-            erased_serialize: erased_serialize_fn::<M>,
-            serialize: unsafe { std::mem::transmute(serialize_fns.serialize) },
-            deserialize: unsafe { std::mem::transmute(serialize_fns.deserialize) },
-            erased_clone: None,
-            map_entities: None,
-            send_map_entities: None,
-            receive_map_entities: None,
-        }
+        Self::new_custom_serde::<M>(SerializeFns::with_backend::<Bincode>())
     }
 
     pub(crate) fn new_custom_serde<M: Message>(serialize_fns: SerializeFns<M>) -> Self {
@@ -180,6 +201,7 @@ impl ErasedSerializeFns {
             map_entities: None,
             send_map_entities: None,
             receive_map_entities: None,
+            compression: None,
         }
     }
 
@@ -241,19 +263,55 @@ impl ErasedSerializeFns {
         entity_map: Option<&mut SendEntityMap>,
     ) -> Result<(), SerializationError> {
         let fns = unsafe { self.typed::<M>() };
-        if let Some(map_entities) = self.send_map_entities {
-            let serialize_map_entities = fns.serialize_map_entities.unwrap();
-            serialize_map_entities(
-                message,
-                writer,
-                entity_map.expect("EntityMap is required to serialize this message"),
-                std::mem::transmute(self.erased_clone.unwrap()),
-                map_entities,
-                fns.serialize,
-            )
-        } else {
-            (fns.serialize)(message, writer)
+        self.maybe_compress(writer, |writer| {
+            if let Some(map_entities) = self.send_map_entities {
+                let serialize_map_entities = fns.serialize_map_entities.unwrap();
+                serialize_map_entities(
+                    message,
+                    writer,
+                    entity_map.expect("EntityMap is required to serialize this message"),
+                    std::mem::transmute(self.erased_clone.unwrap()),
+                    map_entities,
+                    fns.serialize,
+                )
+            } else {
+                (fns.serialize)(message, writer)
+            }
+        })
+    }
+
+    /// Write the output of `serialize` into the writer, compressing it if compression is enabled for this type.
+    ///
+    /// If compression is enabled, the value is prefixed by a byte indicating if it is compressed.
+    /// A compressed value is also prefixed by its length.
+    fn maybe_compress(
+        &self,
+        writer: &mut Writer,
+        serialize: impl FnOnce(&mut Writer) -> Result<(), SerializationError>,
+    ) -> Result<(), SerializationError> {
+        let Some(config) = self.compression else {
+            return serialize(writer);
+        };
+        let mut raw = Writer::default();
+        serialize(&mut raw)?;
+        let raw = raw.to_bytes();
+        // values that are too large to be decompressed by the receiver are sent raw
+        if raw.len() >= config.min_size && raw.len() <= MAX_DECOMPRESSED_SIZE {
+            let compressed = compress(config.compression, &raw)?;
+            #[cfg(feature = "metrics")]
+            metrics::histogram!("serialize.compression_ratio", "kind" => self.type_name)
+                .record(compressed.len() as f64 / raw.len().max(1) as f64);
+            // only use the compressed value if it is actually smaller
+            if compressed.len() < raw.len() {
+                writer.write_u8(1)?;
+                writer.write_varint(compressed.len() as u64)?;
+                writer.write_all(&compressed)?;
+                return Ok(());
+            }
         }
+        writer.write_u8(0)?;
+        writer.write_all(&raw)?;
+        Ok(())
     }
 
     /// Deserialize the message value from the reader
@@ -265,7 +323,19 @@ impl ErasedSerializeFns {
         entity_map: &mut ReceiveEntityMap,
     ) -> Result<M, SerializationError> {
         let fns = unsafe { self.typed::<M>() };
-        let mut message = (fns.deserialize)(reader)?;
+        let mut message = match self.compression {
+            Some(config) if reader.read_u8()? == 1 => {
+                let len = reader.read_varint()? as usize;
+                if len > reader.remaining() {
+                    return Err(SerializationError::InvalidValue);
+                }
+                let compressed = reader.split_len(len);
+                let mut decompressed = Reader::from(decompress(config.compression, &compressed)?);
+                (fns.deserialize)(&mut decompressed)?
+            }
+            // the value was not compressed
+            _ => (fns.deserialize)(reader)?,
+        };
         if let Some(map_entities) = self.receive_map_entities {
             map_entities(PtrMut::from(&mut message), entity_map);
         }
@@ -294,13 +364,25 @@ impl AppSerializeExt for App {
 
 #[cfg(test)]
 mod tests {
-    use crate::protocol::serialize::{erased_serialize_fn, ErasedSerializeFns};
+    use crate::prelude::MessageRegistry;
+    use crate::protocol::message::MessageType;
+    use crate::protocol::serialize::{
+        erased_serialize_fn, ErasedSerializeFns, SerializationBackend, SerializeFns,
+    };
     use crate::serialize::reader::Reader;
+    use crate::serialize::varint::{VarIntReadExt, VarIntWriteExt};
     use crate::serialize::writer::Writer;
+    use crate::serialize::SerializationError;
     use crate::shared::replication::authority::AuthorityChange;
     use crate::shared::replication::entity_map::{ReceiveEntityMap, SendEntityMap};
+    use crate::tests::protocol::StringMessage;
+    use crate::transport::middleware::compression::{CompressionConfig, TypeCompressionConfig};
     use bevy::prelude::Entity;
     use bevy::ptr::Ptr;
+    use byteorder::{ReadBytesExt, WriteBytesExt};
+    use serde::de::DeserializeOwned;
+    use serde::{Deserialize, Serialize};
+    use std::io::Write;
 
     #[test]
     fn test_erased_serde() {
@@ -330,6 +412,122 @@ mod tests {
         assert_eq!(new_message, message);
     }
 
+    // the large variant is intentional: it should not affect the serialized size of the other variants
+    #[allow(clippy::large_enum_variant)]
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    enum TaggedComponent {
+        Empty,
+        Small(u8),
+        Large([u64; 32]),
+    }
+
+    fn roundtrip_len(message: &TaggedComponent) -> usize {
+        let registry = ErasedSerializeFns::new::<TaggedComponent>();
+        let mut writer = Writer::default();
+        unsafe { registry.serialize(message, &mut writer, None) }.unwrap();
+        let data = writer.to_bytes();
+        let len = data.len();
+        let mut reader = Reader::from(data);
+        let new_message = unsafe {
+            registry.deserialize::<TaggedComponent>(&mut reader, &mut ReceiveEntityMap::default())
+        }
+        .unwrap();
+        assert_eq!(&new_message, message);
+        len
+    }
+
+    /// Only the active variant of an enum is serialized, with a 1-byte discriminant
+    #[test]
+    fn test_erased_serde_enum_size() {
+        assert!(std::mem::size_of::<TaggedComponent>() > 32 * 8);
+        assert_eq!(roundtrip_len(&TaggedComponent::Empty), 1);
+        assert_eq!(roundtrip_len(&TaggedComponent::Small(3)), 2);
+        assert!(roundtrip_len(&TaggedComponent::Large([u64::MAX; 32])) > 32 * 8);
+    }
+
+    fn roundtrip_compressed(compression: TypeCompressionConfig, message: &StringMessage) -> usize {
+        let mut registry = ErasedSerializeFns::new::<StringMessage>();
+        registry.compression = Some(compression);
+
+        let mut writer = Writer::default();
+        unsafe { registry.serialize(message, &mut writer, None) }.unwrap();
+        // write some other data after the message, to check that we only read the message bytes
+        writer.write_u8(7).unwrap();
+        let data = writer.to_bytes();
+        let len = data.len();
+
+        let mut reader = Reader::from(data);
+        let new_message = unsafe {
+            registry.deserialize::<StringMessage>(&mut reader, &mut ReceiveEntityMap::default())
+        }
+        .unwrap();
+        assert_eq!(&new_message, message);
+        assert_eq!(reader.read_u8().unwrap(), 7);
+        len
+    }
+
+    #[test]
+    fn test_erased_serde_compression_below_threshold() {
+        let message = StringMessage("a".repeat(100));
+        let len = roundtrip_compressed(
+            TypeCompressionConfig::new(CompressionConfig::None).with_min_size(1000),
+            &message,
+        );
+        // 1 byte for the compression flag, and 1 byte for the trailing data
+        let mut writer = Writer::default();
+        unsafe {
+            ErasedSerializeFns::new::<StringMessage>().serialize(&message, &mut writer, None)
+        }
+        .unwrap();
+        assert_eq!(len, writer.to_bytes().len() + 2);
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn test_erased_serde_compression_lz4() {
+        let message = StringMessage("a".repeat(1000));
+        let len = roundtrip_compressed(
+            TypeCompressionConfig::new(CompressionConfig::Lz4).with_min_size(10),
+            &message,
+        );
+        assert!(len < 100);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_erased_serde_compression_zstd() {
+        let message = StringMessage("a".repeat(1000));
+        let len = roundtrip_compressed(
+            TypeCompressionConfig::new(CompressionConfig::Zstd { level: 3 }).with_min_size(10),
+            &message,
+        );
+        assert!(len < 100);
+    }
+
+    /// A compressed value that would be larger than [`MAX_DECOMPRESSED_SIZE`] once decompressed is rejected
+    #[cfg(any(feature = "zstd", feature = "lz4"))]
+    #[test]
+    fn test_decompress_too_large() {
+        use crate::transport::middleware::compression::{
+            compress, decompress, MAX_DECOMPRESSED_SIZE,
+        };
+        let data = vec![0; MAX_DECOMPRESSED_SIZE + 1];
+        let mut compressions = vec![];
+        #[cfg(feature = "zstd")]
+        compressions.push(CompressionConfig::Zstd { level: 3 });
+        #[cfg(feature = "lz4")]
+        compressions.push(CompressionConfig::Lz4);
+        for compression in compressions {
+            let compressed = compress(compression, &data).unwrap();
+            assert!(decompress(compression, &compressed).is_err());
+            let compressed = compress(compression, &data[..MAX_DECOMPRESSED_SIZE]).unwrap();
+            assert_eq!(
+                decompress(compression, &compressed).unwrap().len(),
+                MAX_DECOMPRESSED_SIZE
+            );
+        }
+    }
+
     #[test]
     fn test_erased_serde_map_entities() {
         let mut registry = ErasedSerializeFns::new::<AuthorityChange>();
@@ -365,4 +563,53 @@ mod tests {
             }
         );
     }
+
+    /// Backend that writes the values as length-prefixed JSON
+    struct Json;
+
+    impl SerializationBackend for Json {
+        fn serialize<M: Serialize>(
+            message: &M,
+            writer: &mut Writer,
+        ) -> Result<(), SerializationError> {
+            let data = serde_json::to_vec(message).map_err(std::io::Error::other)?;
+            writer.write_varint(data.len() as u64)?;
+            writer.write_all(&data)?;
+            Ok(())
+        }
+
+        fn deserialize<M: DeserializeOwned>(reader: &mut Reader) -> Result<M, SerializationError> {
+            let len = reader.read_varint()? as usize;
+            let mut data = vec![0; len];
+            std::io::Read::read_exact(reader, &mut data)?;
+            Ok(serde_json::from_slice(&data).map_err(std::io::Error::other)?)
+        }
+    }
+
+    #[test]
+    fn test_serialization_backend() {
+        let mut registry = MessageRegistry::default();
+        registry.add_message_custom_serde::<StringMessage>(
+            MessageType::Normal,
+            SerializeFns::with_backend::<Json>(),
+        );
+
+        let mut writer = Writer::default();
+        for message in ["hello", "world"] {
+            registry
+                .serialize(&StringMessage(message.to_string()), &mut writer, None)
+                .unwrap();
+        }
+        let data = writer.to_bytes();
+        // the messages are encoded with the backend
+        assert!(data.windows(7).any(|w| w == b"\"hello\""));
+
+        let mut reader = Reader::from(data);
+        for message in ["hello", "world"] {
+            let read: StringMessage = registry
+                .deserialize(&mut reader, &mut ReceiveEntityMap::default())
+                .unwrap();
+            assert_eq!(read, StringMessage(message.to_string()));
+        }
+    }
 }
@@ -49,6 +49,12 @@ impl Writer {
         self.0.get_mut().clear();
     }
 
+    /// Number of bytes written
+    #[cfg(any(feature = "metrics", feature = "serialization_stats"))]
+    pub(crate) fn len(&self) -> usize {
+        self.0.get_ref().len()
+    }
+
     /// Consume the writer to get the RawData
     pub(crate) fn to_bytes(self) -> Bytes {
         self.0.into_inner().into()
@@ -3,7 +3,7 @@
 //! This module contains components and systems to manage the metadata on client entities.
 use crate::server::clients::systems::handle_controlled_by_remove;
 use crate::server::replication::send::Lifetime;
-use crate::shared::sets::{InternalReplicationSet, ServerMarker};
+use crate::shared::sets::{InternalReplicationSet, NetworkingSchedules, ServerMarker};
 use bevy::ecs::entity::EntityHashMap;
 use bevy::prelude::*;
 
@@ -138,8 +138,9 @@ mod systems {
 
 impl Plugin for ClientsMetadataPlugin {
     fn build(&self, app: &mut App) {
+        let schedules = NetworkingSchedules::of(app);
         app.add_systems(
-            PostUpdate,
+            schedules.send,
             systems::handle_controlled_by_update
                 .in_set(InternalReplicationSet::<ServerMarker>::BeforeBuffer),
         );
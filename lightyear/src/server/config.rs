@@ -4,11 +4,15 @@ use governor::Quota;
 use nonzero_ext::nonzero;
 use std::sync::Arc;
 
-use crate::connection::netcode::{Key, PRIVATE_KEY_BYTES};
+use crate::connection::netcode::{Key, MAX_CLIENTS, PRIVATE_KEY_BYTES};
 use crate::connection::server::{
-    ConnectionRequestHandler, DefaultConnectionRequestHandler, NetConfig,
+    ConnectionRequestHandler, DefaultConnectionRequestHandler, DuplicateConnectPolicy, NetConfig,
 };
+use crate::packet::congestion::CongestionConfig;
+use crate::packet::inspector::PacketInspector;
+use crate::packet::mtu_discovery::MtuDiscoveryConfig;
 use crate::prelude::ReplicationConfig;
+use crate::server::rate_limit::RateLimitConfig;
 use crate::shared::config::SharedConfig;
 use crate::shared::ping::manager::PingConfig;
 
@@ -24,6 +28,11 @@ pub struct NetcodeConfig {
     pub private_key: Key,
     /// A closure that will be used to accept or reject incoming connections
     pub connection_request_handler: Arc<dyn ConnectionRequestHandler>,
+    /// How to handle a connection request from a client that is already connected
+    /// (for example a client that restarted, or whose address changed).
+    ///
+    /// The default is [`DuplicateConnectPolicy::Reject`].
+    pub duplicate_connect_policy: DuplicateConnectPolicy,
 }
 
 impl Default for NetcodeConfig {
@@ -35,6 +44,7 @@ impl Default for NetcodeConfig {
             protocol_id: 0,
             private_key: [0; PRIVATE_KEY_BYTES],
             connection_request_handler: Arc::new(DefaultConnectionRequestHandler),
+            duplicate_connect_policy: DuplicateConnectPolicy::default(),
         }
     }
 }
@@ -53,10 +63,15 @@ impl NetcodeConfig {
         self.client_timeout_secs = client_timeout_secs;
         self
     }
+
+    pub fn with_duplicate_connect_policy(mut self, policy: DuplicateConnectPolicy) -> Self {
+        self.duplicate_connect_policy = policy;
+        self
+    }
 }
 
 /// Configuration related to sending packets
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct PacketConfig {
     /// After how many multiples of RTT do we consider a packet to be lost?
     ///
@@ -67,6 +82,31 @@ pub struct PacketConfig {
     pub per_client_send_bandwidth_cap: Quota,
     /// If false, there is no bandwidth cap and all messages are sent as soon as possible
     pub bandwidth_cap_enabled: bool,
+    /// If set, probe each connection to find the largest packet size that can be used,
+    /// instead of always using [`MAX_PACKET_SIZE`](crate::connection::netcode::MAX_PACKET_SIZE)
+    pub mtu_discovery: Option<MtuDiscoveryConfig>,
+    /// If set, reduce the replication send rate when the packet loss of the connection is high.
+    ///
+    /// This is disabled by default. When it is enabled, the replication groups with a priority lower than
+    /// [`CongestionConfig::priority_threshold`] (which includes the groups with the default priority)
+    /// send their updates less often while the connection is congested.
+    pub congestion_control: Option<CongestionConfig>,
+    /// If set, the inspector is called with the decoded contents of every packet sent to or received from each client
+    pub packet_inspector: Option<Arc<dyn PacketInspector>>,
+    /// Limits on the rate of messages received from each client
+    pub rate_limit: Option<RateLimitConfig>,
+    /// Number of packet buffers that are kept after the packets are sent, to serialize the
+    /// next packets without allocating.
+    ///
+    /// Each connection has its own pool, so the pools are never shared between systems.
+    /// Set to 0 to allocate a new buffer for every packet.
+    pub packet_buffer_pool_size: usize,
+    /// When the bandwidth cap is reached, the unreliable messages that could not be sent are kept
+    /// in a buffer (up to this number of messages) and sent during the next sends, instead of being dropped.
+    ///
+    /// The messages with the lowest priority are dropped when the buffer is full.
+    /// Set to 0 to drop all the messages that don't fit in the bandwidth cap.
+    pub outgoing_buffer_size: usize,
 }
 
 impl Default for PacketConfig {
@@ -76,6 +116,12 @@ impl Default for PacketConfig {
             // 56 KB/s bandwidth cap
             per_client_send_bandwidth_cap: Quota::per_second(nonzero!(56000u32)),
             bandwidth_cap_enabled: false,
+            mtu_discovery: None,
+            congestion_control: None,
+            packet_inspector: None,
+            rate_limit: None,
+            packet_buffer_pool_size: 8,
+            outgoing_buffer_size: 0,
         }
     }
 }
@@ -96,6 +142,41 @@ impl PacketConfig {
         self.bandwidth_cap_enabled = true;
         self
     }
+
+    pub fn with_mtu_discovery(mut self, mtu_discovery: MtuDiscoveryConfig) -> Self {
+        self.mtu_discovery = Some(mtu_discovery);
+        self
+    }
+
+    pub fn with_congestion_control(mut self, congestion_control: CongestionConfig) -> Self {
+        self.congestion_control = Some(congestion_control);
+        self
+    }
+
+    pub fn disable_congestion_control(mut self) -> Self {
+        self.congestion_control = None;
+        self
+    }
+
+    pub fn with_packet_inspector(mut self, packet_inspector: Arc<dyn PacketInspector>) -> Self {
+        self.packet_inspector = Some(packet_inspector);
+        self
+    }
+
+    pub fn with_packet_buffer_pool_size(mut self, packet_buffer_pool_size: usize) -> Self {
+        self.packet_buffer_pool_size = packet_buffer_pool_size;
+        self
+    }
+
+    pub fn with_outgoing_buffer_size(mut self, outgoing_buffer_size: usize) -> Self {
+        self.outgoing_buffer_size = outgoing_buffer_size;
+        self
+    }
+
+    pub fn with_rate_limit(mut self, rate_limit: RateLimitConfig) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
 }
 
 /// Configuration for the server plugin.
@@ -104,7 +185,7 @@ impl PacketConfig {
 ///
 /// You can also modify it while the app is running, and the new values will be used on the next
 /// time that the server is started. This can be useful to change some configuration values at runtime.
-#[derive(Clone, Debug, Default, Resource)]
+#[derive(Clone, Debug, Resource)]
 pub struct ServerConfig {
     pub shared: SharedConfig,
     /// The server can support multiple transport at the same time (e.g. UDP and WebTransport) so that
@@ -113,17 +194,46 @@ pub struct ServerConfig {
     pub packet: PacketConfig,
     pub replication: ReplicationConfig,
     pub ping: PingConfig,
+    /// Maximum number of clients that can be connected at the same time, in total across all the [`NetConfig`]s.
+    ///
+    /// The connection requests received when the server is full are denied with
+    /// [`DeniedReason::ServerFull`](crate::connection::server::DeniedReason::ServerFull), and no [`ConnectEvent`](crate::server::events::ConnectEvent) is emitted.
+    /// The netcode server supports at most [`MAX_CLIENTS`] clients.
+    pub max_clients: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            shared: SharedConfig::default(),
+            net: vec![],
+            packet: PacketConfig::default(),
+            replication: ReplicationConfig::default(),
+            ping: PingConfig::default(),
+            max_clients: MAX_CLIENTS,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::client::config::ClientConfig;
+    use crate::client::events as client_events;
     use crate::client::networking::NetworkingState;
+    use crate::connection::client::{self, Authentication};
+    use crate::connection::netcode::{ConnectToken, USER_DATA_BYTES};
     use crate::connection::server::DeniedReason;
+    use crate::prelude::client::ClientCommands;
+    use crate::prelude::server::ServerCommands;
     use crate::prelude::ClientId;
+    use crate::server::connection::ConnectionManager;
+    use crate::server::events::ConnectEvent;
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::prelude::Commands;
 
     use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
-    use bevy::prelude::State;
+    use bevy::prelude::{Events, State};
     use std::fmt::Debug;
     use std::sync::Arc;
 
@@ -171,4 +281,256 @@ mod tests {
             &NetworkingState::Disconnected
         );
     }
+
+    #[test]
+    fn test_denied_reason() {
+        let mut stepper = BevyStepper::default();
+        stepper.stop();
+        for netconfig in &mut stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .net
+        {
+            netconfig.set_connection_request_handler(Arc::new(CustomConnectionRequestHandler));
+        }
+        stepper
+            .server_app
+            .world_mut()
+            .run_system_once(|mut commands: Commands| commands.start_server());
+        stepper
+            .client_app
+            .world_mut()
+            .run_system_once(|mut commands: Commands| commands.connect_client());
+
+        // the client receives the reason of the denial
+        let mut reasons = vec![];
+        for _ in 0..20 {
+            stepper.frame_step();
+            let mut events = stepper
+                .client_app
+                .world_mut()
+                .resource_mut::<Events<client_events::DisconnectEvent>>();
+            reasons.extend(events.drain().map(|event| event.reason));
+        }
+        assert_eq!(reasons.len(), 1);
+        assert!(matches!(
+            &reasons[0],
+            Some(client::DisconnectReason::Denied(DeniedReason::Custom(reason)))
+                if reason == "Test client is not allowed to connect"
+        ));
+    }
+
+    #[test]
+    fn test_max_clients() {
+        let mut stepper = BevyStepper::default();
+        stepper.stop();
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .max_clients = 0;
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<Events<client_events::DisconnectEvent>>()
+            .clear();
+        stepper
+            .server_app
+            .world_mut()
+            .run_system_once(|mut commands: Commands| commands.start_server());
+        stepper
+            .client_app
+            .world_mut()
+            .run_system_once(|mut commands: Commands| commands.connect_client());
+
+        // the server is full
+        let mut reasons = vec![];
+        for _ in 0..20 {
+            stepper.frame_step();
+            let mut events = stepper
+                .client_app
+                .world_mut()
+                .resource_mut::<Events<client_events::DisconnectEvent>>();
+            reasons.extend(events.drain().map(|event| event.reason));
+        }
+        assert_eq!(reasons.len(), 1);
+        assert!(matches!(
+            &reasons[0],
+            Some(client::DisconnectReason::Denied(DeniedReason::ServerFull))
+        ));
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<State<NetworkingState>>()
+                .get(),
+            &NetworkingState::Disconnected
+        );
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .resource::<ConnectionManager>()
+                .client_count(),
+            0
+        );
+        assert!(stepper
+            .server_app
+            .world()
+            .resource::<Events<ConnectEvent>>()
+            .is_empty());
+    }
+
+    /// The max_clients limit applies to the total number of clients of all the NetConfigs
+    #[test]
+    fn test_max_clients_across_net_configs() {
+        use crate::prelude::client::{InterpolationConfig, PredictionConfig, SyncConfig};
+        use crate::prelude::{SharedConfig, TickConfig};
+        use crate::tests::multi_stepper::MultiBevyStepper;
+        use bevy::utils::Duration;
+
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let mut stepper = MultiBevyStepper::new(
+            shared_config,
+            SyncConfig::default().speedup_factor(1.0),
+            PredictionConfig::default(),
+            InterpolationConfig::default(),
+            tick_duration,
+        );
+        // each client connects through a different NetConfig
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .max_clients = 1;
+        stepper.init();
+
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .resource::<ConnectionManager>()
+                .client_count(),
+            1
+        );
+        let disconnected = [&stepper.client_app_1, &stepper.client_app_2]
+            .into_iter()
+            .filter(|app| {
+                app.world().resource::<State<NetworkingState>>().get()
+                    == &NetworkingState::Disconnected
+            })
+            .count();
+        assert_eq!(disconnected, 1);
+    }
+
+    /// Only accepts the clients whose connect token contains a valid ticket
+    #[derive(Debug, Clone)]
+    struct TicketConnectionRequestHandler;
+
+    impl ConnectionRequestHandler for TicketConnectionRequestHandler {
+        fn handle_request_with_user_data(
+            &self,
+            _: ClientId,
+            user_data: &[u8],
+        ) -> Option<DeniedReason> {
+            (!user_data.starts_with(b"valid"))
+                .then(|| DeniedReason::Custom("invalid ticket".into()))
+        }
+    }
+
+    fn client_auth(stepper: &mut BevyStepper) -> &mut Authentication {
+        let client_config = stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ClientConfig>()
+            .into_inner();
+        let client::NetConfig::Netcode { auth, .. } = &mut client_config.net else {
+            unreachable!()
+        };
+        auth
+    }
+
+    /// Replace the authentication of the client with a connect token that contains the ticket
+    fn set_ticket(stepper: &mut BevyStepper, manual_auth: &Authentication, ticket: &[u8]) {
+        let Authentication::Manual {
+            server_addr,
+            client_id,
+            private_key,
+            protocol_id,
+        } = manual_auth.clone()
+        else {
+            unreachable!()
+        };
+        let mut user_data = [0; USER_DATA_BYTES];
+        user_data[..ticket.len()].copy_from_slice(ticket);
+        let token = ConnectToken::build(server_addr, protocol_id, client_id, private_key)
+            .user_data(user_data)
+            .generate()
+            .unwrap();
+        *client_auth(stepper) = Authentication::Token(token);
+    }
+
+    #[test]
+    fn test_connection_request_user_data() {
+        let mut stepper = BevyStepper::default();
+        stepper.stop();
+        for netconfig in &mut stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .net
+        {
+            netconfig.set_connection_request_handler(Arc::new(TicketConnectionRequestHandler));
+        }
+
+        let manual_auth = client_auth(&mut stepper).clone();
+
+        // the ticket is rejected
+        set_ticket(&mut stepper, &manual_auth, b"invalid");
+        stepper.start();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<State<NetworkingState>>()
+                .get(),
+            &NetworkingState::Disconnected
+        );
+        assert!(stepper
+            .server_app
+            .world()
+            .resource::<Events<ConnectEvent>>()
+            .is_empty());
+
+        // the ticket is accepted
+        set_ticket(&mut stepper, &manual_auth, b"valid");
+        stepper
+            .client_app
+            .world_mut()
+            .run_system_once(|mut commands: Commands| commands.connect_client());
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<State<NetworkingState>>()
+                .get(),
+            &NetworkingState::Connected
+        );
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .resource::<ConnectionManager>()
+                .client_count(),
+            1
+        );
+    }
 }
@@ -1,9 +1,11 @@
 //! Specify how a Server sends/receives messages with a Client
+use std::net::SocketAddr;
+
 use bevy::ecs::component::Tick as BevyTick;
 use bevy::ecs::entity::{EntityHash, MapEntities};
 use bevy::prelude::{Component, Entity, Resource, World};
 use bevy::ptr::Ptr;
-use bevy::utils::{Duration, HashMap};
+use bevy::utils::{Duration, HashMap, HashSet};
 use bytes::Bytes;
 use hashbrown::hash_map::Entry;
 use tracing::{debug, info, info_span, trace, trace_span};
@@ -11,7 +13,8 @@ use tracing::{debug, info, info_span, trace, trace_span};
 use tracing::{instrument, Level};
 
 use crate::channel::builder::{
-    EntityActionsChannel, EntityUpdatesChannel, PingChannel, PongChannel,
+    EntityActionsChannel, EntityReliableUpdatesChannel, EntityUpdatesChannel, InputAckChannel,
+    PingChannel, PongChannel, TimeSyncChannel,
 };
 
 use crate::channel::receivers::ChannelReceive;
@@ -19,16 +22,21 @@ use crate::channel::senders::ChannelSend;
 use crate::client::message::ClientMessage;
 use crate::connection::id::ClientId;
 use crate::connection::netcode::MAX_PACKET_SIZE;
+use crate::connection::server::DisconnectReason;
+use crate::inputs::ack::InputAck;
+use crate::packet::congestion::CongestionState;
+use crate::packet::inspector::ConnectionInspector;
+use crate::packet::message_ack::MessageHandle;
 use crate::packet::message_manager::MessageManager;
 use crate::packet::packet_builder::{Payload, RecvPayload};
 use crate::prelude::server::{DisconnectEvent, RoomId, RoomManager};
 use crate::prelude::{
-    Channel, ChannelKind, Message, PreSpawnedPlayerObject, ReplicationConfig, ReplicationGroup,
+    Channel, ChannelKind, Message, PreSpawnedPlayerObject, Replicating, ReplicationConfig,
     ShouldBePredicted,
 };
 use crate::protocol::channel::ChannelRegistry;
 use crate::protocol::component::{
-    ComponentError, ComponentKind, ComponentNetId, ComponentRegistry,
+    ComponentError, ComponentKind, ComponentNetId, ComponentRegistry, ReplicationMode,
 };
 use crate::protocol::message::{MessageError, MessageRegistry, MessageType};
 use crate::protocol::registry::NetId;
@@ -37,23 +45,34 @@ use crate::serialize::writer::Writer;
 use crate::serialize::{SerializationError, ToBytes};
 use crate::server::config::PacketConfig;
 use crate::server::error::ServerError;
-use crate::server::events::{ConnectEvent, ServerEvents};
+use crate::server::events::{ConnectEvent, MessageEvent, ServerEvents};
+use crate::server::initial_sync::InitialSync;
+use crate::server::rate_limit::{ChannelRates, RateLimitDecision, RateLimiter};
 use crate::server::relevance::error::RelevanceError;
+use crate::server::relevance::immediate::{CachedNetworkRelevance, ClientRelevance};
 use crate::shared::events::connection::ConnectionEvents;
 use crate::shared::message::MessageSend;
 use crate::shared::ping::manager::{PingConfig, PingManager};
-use crate::shared::ping::message::{Ping, Pong};
-use crate::shared::replication::components::ReplicationGroupId;
+use crate::shared::ping::message::{Ping, Pong, TimeSync};
+use crate::shared::replication::components::{
+    ReplicationGroup, ReplicationGroupId, ReplicationTarget,
+};
+use crate::shared::replication::debug::{EntityDebugInfo, ReplicationDebugInfo};
 use crate::shared::replication::delta::DeltaManager;
+use crate::shared::replication::error::ReplicationError;
 use crate::shared::replication::network_target::NetworkTarget;
 use crate::shared::replication::receive::ReplicationReceiver;
-use crate::shared::replication::send::ReplicationSender;
-use crate::shared::replication::{EntityActionsMessage, EntityUpdatesMessage, ReplicationPeer};
+use crate::shared::replication::send::{ReplicationSender, SpawnAckState};
+use crate::shared::replication::snapshot::{ReplicationSnapshot, REPLICATION_SNAPSHOT_VERSION};
+use crate::shared::replication::{
+    EntityActionsMessage, EntityUpdatesMessage, ReplicationPeer, SpawnAction,
+};
 use crate::shared::replication::{ReplicationReceive, ReplicationSend};
 use crate::shared::sets::ServerMarker;
 use crate::shared::tick_manager::Tick;
 use crate::shared::tick_manager::TickManager;
 use crate::shared::time_manager::TimeManager;
+use crate::shared::time_manager::WrappedTime;
 
 type EntityHashMap<K, V> = hashbrown::HashMap<K, V, EntityHash>;
 
@@ -68,10 +87,15 @@ pub struct ConnectionManager {
     // list of clients that connected since the last time we sent replication messages
     // (we want to keep track of them because we need to replicate the entire world state to them)
     pub(crate) new_clients: Vec<ClientId>,
+    /// Clients for which the initial replication of the world is spread over multiple sends
+    pub(crate) initial_syncs: HashMap<ClientId, InitialSync>,
+    /// For each entity, the clients from which a component is currently hidden because of a
+    /// [`ComponentClientFilter`](crate::prelude::ComponentClientFilter)
+    pub(crate) hidden_components: EntityHashMap<Entity, HashMap<ComponentKind, HashSet<ClientId>>>,
     pub(crate) writer: Writer,
 
     // CONFIG
-    replication_config: ReplicationConfig,
+    pub(crate) replication_config: ReplicationConfig,
     packet_config: PacketConfig,
     ping_config: PingConfig,
 }
@@ -104,6 +128,8 @@ impl ConnectionManager {
             events: ServerEvents::new(),
             delta_manager: DeltaManager::default(),
             new_clients: vec![],
+            initial_syncs: HashMap::default(),
+            hidden_components: EntityHashMap::default(),
             writer: Writer::with_capacity(MAX_PACKET_SIZE),
             replication_config,
             packet_config,
@@ -116,11 +142,95 @@ impl ConnectionManager {
         self.connection(client_id).map(|c| c.entity)
     }
 
+    /// Returns true if the client with the given [`ClientId`] is currently connected
+    pub fn is_connected(&self, client_id: ClientId) -> bool {
+        self.connections.contains_key(&client_id)
+    }
+
     /// Return the list of connected [`ClientId`]s
+    ///
+    /// The connections and disconnections are processed in [`MainSet::Receive`](crate::prelude::MainSet::Receive)
+    /// (in the `PreUpdate` schedule), at the same time as the [`ConnectEvent`](crate::server::events::ConnectEvent)s
+    /// and [`DisconnectEvent`](crate::server::events::DisconnectEvent)s are emitted. Systems that run after that
+    /// set (for example in `FixedUpdate` or `Update`) see the state of the current frame.
     pub fn connected_clients(&self) -> impl Iterator<Item = ClientId> + '_ {
         self.connections.keys().copied()
     }
 
+    /// Return the number of connected clients
+    ///
+    /// See [`connected_clients`](Self::connected_clients) for when it is updated.
+    pub fn client_count(&self) -> usize {
+        self.connections.len()
+    }
+
+    /// Progress of the initial replication of the world to the client, in percent.
+    ///
+    /// This is the percentage of the entities whose spawn has been sent to the client. It is 100 once the
+    /// initial sync is complete, or if [`ReplicationConfig::initial_sync_entities_per_tick`] is not set.
+    pub fn initial_sync_percent(&self, client_id: ClientId) -> Result<f32, ServerError> {
+        self.connection(client_id)?;
+        if let Some(sync) = self.initial_syncs.get(&client_id) {
+            return Ok(sync.percent());
+        }
+        // the initial sync starts on the next send
+        if self.new_clients.contains(&client_id)
+            && self
+                .replication_config
+                .initial_sync_entities_per_tick
+                .is_some()
+        {
+            return Ok(0.0);
+        }
+        Ok(100.0)
+    }
+
+    /// Returns true if the whole state of the group must be sent to the client during this send:
+    /// either the client just connected, or the group is part of the current batch of its initial sync
+    pub(crate) fn is_initial_sync_target(
+        &self,
+        client_id: ClientId,
+        group_id: ReplicationGroupId,
+    ) -> bool {
+        match self.initial_syncs.get(&client_id) {
+            Some(sync) => sync.in_batch(group_id),
+            None => self.new_clients.contains(&client_id),
+        }
+    }
+
+    /// Clients to which the whole state of the group must be sent during this send
+    pub(crate) fn initial_sync_targets(&self, group_id: ReplicationGroupId) -> Vec<ClientId> {
+        self.new_clients
+            .iter()
+            .filter(|client_id| !self.initial_syncs.contains_key(*client_id))
+            .chain(
+                self.initial_syncs
+                    .iter()
+                    .filter(|(_, sync)| sync.in_batch(group_id))
+                    .map(|(client_id, _)| client_id),
+            )
+            .copied()
+            .collect()
+    }
+
+    /// Remove from `target` the clients whose initial sync hasn't reached the group yet:
+    /// nothing must be sent to them for this group
+    pub(crate) fn exclude_initial_sync_pending(
+        &self,
+        group_id: ReplicationGroupId,
+        target: &mut NetworkTarget,
+    ) {
+        let pending: Vec<_> = self
+            .initial_syncs
+            .iter()
+            .filter(|(_, sync)| sync.is_pending(group_id))
+            .map(|(client_id, _)| *client_id)
+            .collect();
+        if !pending.is_empty() {
+            target.exclude(&NetworkTarget::Only(pending));
+        }
+    }
+
     // TODO: we need `&mut self` because MapEntities requires `&mut EntityMapper` even though it's not needed here
     /// Convert entities in the message to be compatible with the remote world of the provided client
     pub fn map_entities_to_remote<M: Message + MapEntities>(
@@ -160,15 +270,115 @@ impl ConnectionManager {
         self.send_message_to_target::<C, M>(message, target)
     }
 
+    /// Relay a message received from a client to other clients.
+    ///
+    /// The message is forwarded with the bytes that were received, without serializing it again,
+    /// on the same channel that the client used to send it (so it keeps the same reliability and ordering).
+    /// The relayed copies are never sent back to the client that sent the message.
+    ///
+    /// Messages that contain entities (registered with `add_map_entities`) are serialized again for each client,
+    /// so that their entities are mapped correctly.
+    ///
+    /// Use [`relay_message`](Self::relay_message) to relay a message that was modified, or on a different channel.
+    pub fn relay<M: Message>(
+        &mut self,
+        event: &MessageEvent<M>,
+        target: NetworkTarget,
+    ) -> Result<(), ServerError> {
+        let Some(raw) = event.raw().cloned() else {
+            return Err(ServerError::MessageNotRelayable);
+        };
+        let target = Self::relay_target(*event.context(), target);
+        if self.message_registry.is_map_entities::<M>() {
+            return self.buffer_map_entities_message(event.message(), raw.channel, target);
+        }
+        self.buffer_message_bytes(raw.bytes, raw.channel, target)
+    }
+
+    /// Relay a message from the client `from` to other clients, on the channel `C`.
+    ///
+    /// Unlike [`relay`](Self::relay), the message is serialized again. The relayed copies are never
+    /// sent back to the client `from`.
+    pub fn relay_message<C: Channel, M: Message>(
+        &mut self,
+        from: ClientId,
+        message: &M,
+        target: NetworkTarget,
+    ) -> Result<(), ServerError> {
+        let target = Self::relay_target(from, target);
+        self.erased_send_message_to_target(message, ChannelKind::of::<C>(), target)
+    }
+
+    fn relay_target(from: ClientId, mut target: NetworkTarget) -> NetworkTarget {
+        target.exclude(&NetworkTarget::Single(from));
+        target
+    }
+
     /// Queues up a message to be sent to a client
+    ///
+    /// Returns [`ServerError::ClientIdNotFound`] if the client is not connected (for example
+    /// if it disconnected since the client id was captured)
     pub fn send_message<C: Channel, M: Message>(
         &mut self,
         client_id: ClientId,
         message: &mut M,
     ) -> Result<(), ServerError> {
+        if !self.is_connected(client_id) {
+            return Err(ServerError::ClientIdNotFound(client_id));
+        }
         self.send_message_to_target::<C, M>(message, NetworkTarget::Single(client_id))
     }
 
+    /// Send a [`Message`] to a client using a specific [`Channel`], and get notified when the client
+    /// receives it
+    ///
+    /// A [`MessageAckEvent`](crate::server::events::MessageAckEvent) with the returned [`MessageHandle`]
+    /// is emitted when the client acks the message, or a [`MessageTimeoutEvent`](crate::server::events::MessageTimeoutEvent)
+    /// if the message is lost. See [`message_ack`](crate::packet::message_ack) for more details.
+    ///
+    /// The handles are unique per client. The channel must watch acks (reliable channels, or
+    /// [`ChannelMode::UnorderedUnreliableWithAcks`](crate::prelude::ChannelMode::UnorderedUnreliableWithAcks)).
+    pub fn send_message_with_ack<C: Channel, M: Message>(
+        &mut self,
+        client_id: ClientId,
+        message: &mut M,
+    ) -> Result<MessageHandle, ServerError> {
+        let channel_kind = ChannelKind::of::<C>();
+        let connection = self
+            .connections
+            .get_mut(&client_id)
+            .ok_or(ServerError::ClientIdNotFound(client_id))?;
+        connection
+            .message_manager
+            .check_acks_tracked(&channel_kind)?;
+        let entity_map = self.message_registry.is_map_entities::<M>().then_some(
+            &mut connection
+                .replication_receiver
+                .remote_entity_map
+                .local_to_remote,
+        );
+        self.message_registry
+            .serialize(message, &mut self.writer, entity_map)?;
+        let message_bytes = self.writer.split();
+        #[cfg(feature = "metrics")]
+        {
+            metrics::counter!("message.sent", "message" => std::any::type_name::<M>()).increment(1);
+            metrics::counter!("message.bytes_sent", "message" => std::any::type_name::<M>())
+                .increment(message_bytes.len() as u64);
+        }
+        let handle = connection.message_manager.message_acks.new_handle();
+        if connection.is_local_client() {
+            // the local client receives the message right away
+            connection.local_messages_to_send.push(message_bytes);
+            connection.message_manager.message_acks.delivered(handle);
+        } else {
+            connection
+                .message_manager
+                .buffer_send_with_ack(message_bytes, channel_kind, handle)?;
+        }
+        Ok(handle)
+    }
+
     /// Update the priority of a `ReplicationGroup` that is replicated to a given client
     pub fn update_priority(
         &mut self,
@@ -188,6 +398,84 @@ impl ConnectionManager {
         Ok(())
     }
 
+    /// Force a full replication resync for a given client, without disconnecting them.
+    ///
+    /// On the next replication send, the server will re-send entity spawns and the full component state
+    /// of every entity that is replicated to this client, as if the client had just connected.
+    /// The delta-compression baselines for that client are also reset, so the next updates are computed
+    /// from the resynced state.
+    ///
+    /// The client applies the re-sent spawns of the entities that it already knows about as updates:
+    /// the received state is authoritative for the `Confirmed` entities, the existing `Predicted`
+    /// and `Interpolated` entities are kept, and any predicted entity that diverged is rolled back.
+    ///
+    /// This can be used to recover from a desync without requiring the client to reconnect.
+    pub fn resync(&mut self, client_id: ClientId) -> Result<(), ServerError> {
+        self.connection_mut(client_id)?
+            .replication_sender
+            .reset_group_channels();
+        if !self.new_clients.contains(&client_id) {
+            self.new_clients.push(client_id);
+        }
+        debug!(?client_id, "Resyncing client replication state");
+        Ok(())
+    }
+
+    /// Pause or resume the replication to a given client, without disconnecting them.
+    ///
+    /// While the replication is paused, no entity spawns or component updates are sent to the client
+    /// (for example during a loading screen); only the despawns of the entities that the client already knows
+    /// about are sent. The connection itself stays alive, and messages can still be sent.
+    ///
+    /// When the replication is resumed, the client is [resynced](Self::resync): the state of every
+    /// entity replicated to the client is sent again, as if the client had just connected.
+    /// The components that were removed from the entities that the client knows about while the replication
+    /// was paused are removed on the client.
+    pub fn set_replication_paused(
+        &mut self,
+        client_id: ClientId,
+        paused: bool,
+    ) -> Result<(), ServerError> {
+        let connection = self.connection_mut(client_id)?;
+        if connection.replication_paused == paused {
+            return Ok(());
+        }
+        connection.replication_paused = paused;
+        debug!(?client_id, ?paused, "Setting replication paused");
+        if !paused {
+            connection.paused_spawns.clear();
+            for (entity, (group_id, kinds)) in connection.paused_removals.drain() {
+                for kind in kinds {
+                    connection
+                        .replication_sender
+                        .prepare_component_remove(entity, group_id, kind);
+                }
+            }
+            self.resync(client_id)?;
+        }
+        Ok(())
+    }
+
+    /// Returns true if the replication to the client is paused
+    pub fn is_replication_paused(&self, client_id: ClientId) -> Result<bool, ServerError> {
+        Ok(self.connection(client_id)?.replication_paused)
+    }
+
+    /// Get the [`SpawnAckState`] of an entity for a given client.
+    ///
+    /// Returns `None` if [`ReplicationConfig::wait_for_spawn_ack`](crate::prelude::ReplicationConfig::wait_for_spawn_ack)
+    /// is disabled or if the entity was never spawned on that client.
+    pub fn spawn_ack_state(
+        &self,
+        client_id: ClientId,
+        entity: Entity,
+    ) -> Result<Option<SpawnAckState>, ServerError> {
+        Ok(self
+            .connection(client_id)?
+            .replication_sender
+            .spawn_ack_state(entity))
+    }
+
     /// Find the list of connected clients that match the provided [`NetworkTarget`]
     pub(crate) fn connected_targets(
         &self,
@@ -235,6 +523,120 @@ impl ConnectionManager {
         }
     }
 
+    /// Returns true if the server still has messages to send to any client, or reliable messages
+    /// that were not acked by the clients yet.
+    ///
+    /// This can be used to make sure that all pending messages have been delivered, for example before
+    /// stopping the server or at the end of a test.
+    pub fn has_pending_messages(&self) -> bool {
+        self.connections
+            .values()
+            .any(|connection| connection.has_pending_messages())
+    }
+
+    /// Export the replication state of a client, so that it can be imported on another server
+    /// with [`ConnectionManager::import_replication_state`].
+    ///
+    /// See [`snapshot`](crate::shared::replication::snapshot) for what is included in the snapshot.
+    pub fn export_replication_state(
+        &self,
+        client_id: ClientId,
+    ) -> Result<ReplicationSnapshot, ServerError> {
+        let connection = self.connection(client_id)?;
+        let (receive_groups, entity_map) = connection.replication_receiver.snapshot();
+        Ok(ReplicationSnapshot {
+            version: REPLICATION_SNAPSHOT_VERSION,
+            send_groups: connection.replication_sender.snapshot(),
+            receive_groups,
+            entity_map,
+        })
+    }
+
+    /// Dump the replication state of a client, for debugging.
+    ///
+    /// This only reads the replication state and does not change the behaviour of the server.
+    /// See [`debug`](crate::shared::replication::debug) for what is included in the dump.
+    pub fn debug_dump(
+        &self,
+        client_id: ClientId,
+        world: &World,
+    ) -> Result<ReplicationDebugInfo, ServerError> {
+        let connection = self.connection(client_id)?;
+        let sender = &connection.replication_sender;
+        let mut visible_entities = world
+            .iter_entities()
+            .filter(|entity_ref| {
+                entity_ref.contains::<Replicating>()
+                    && entity_ref
+                        .get::<ReplicationTarget>()
+                        .is_some_and(|target| target.target.targets(&client_id))
+                    && entity_ref
+                        .get::<CachedNetworkRelevance>()
+                        .map_or(true, |relevance| {
+                            relevance
+                                .clients_cache
+                                .get(&client_id)
+                                .is_some_and(|r| *r != ClientRelevance::Lost)
+                        })
+            })
+            .filter_map(|entity_ref| {
+                let entity = entity_ref.id();
+                let group_id = entity_ref.get::<ReplicationGroup>()?.group_id(Some(entity));
+                Some(EntityDebugInfo {
+                    entity,
+                    group_id,
+                    spawn_ack_state: sender
+                        .group_channels
+                        .get(&group_id)
+                        .and_then(|channel| channel.spawn_ack_states.get(&entity).copied()),
+                })
+            })
+            .collect::<Vec<_>>();
+        visible_entities.sort_by_key(|info| info.entity);
+        let mut groups = sender.debug_groups();
+        groups.sort_by_key(|group| group.group_id.0);
+        let last_acked_tick = groups.iter().filter_map(|group| group.ack_tick).max();
+        Ok(ReplicationDebugInfo {
+            client_id,
+            visible_entities,
+            groups,
+            channels: connection.message_manager.channel_queue_depths(),
+            last_acked_tick,
+        })
+    }
+
+    /// Import the replication state of a client that was exported by another server.
+    ///
+    /// `map_local_entity` converts the entities of the exporting server into the entities of this server.
+    /// The entities that the client already received are not spawned again: they are replicated as
+    /// updates to the client's existing entities.
+    pub fn import_replication_state(
+        &mut self,
+        client_id: ClientId,
+        snapshot: ReplicationSnapshot,
+        mut map_local_entity: impl FnMut(Entity) -> Option<Entity>,
+    ) -> Result<(), ServerError> {
+        if snapshot.version != REPLICATION_SNAPSHOT_VERSION {
+            return Err(ReplicationError::SnapshotVersionMismatch {
+                expected: REPLICATION_SNAPSHOT_VERSION,
+                found: snapshot.version,
+            }
+            .into());
+        }
+        let connection = self.connection_mut(client_id)?;
+        connection.replication_sender.restore(
+            snapshot.send_groups,
+            &mut connection.replication_receiver.remote_entity_map,
+            &mut map_local_entity,
+        );
+        connection.replication_receiver.restore(
+            snapshot.receive_groups,
+            snapshot.entity_map,
+            map_local_entity,
+        );
+        Ok(())
+    }
+
     pub fn connection(&self, client_id: ClientId) -> Result<&Connection, ServerError> {
         self.connections
             .get(&client_id)
@@ -259,23 +661,32 @@ impl ConnectionManager {
     }
 
     /// Add a new [`Connection`] to the list of connections with the given [`ClientId`]
-    pub(crate) fn add(&mut self, client_id: ClientId, client_entity: Entity) {
+    pub(crate) fn add(
+        &mut self,
+        client_id: ClientId,
+        client_entity: Entity,
+        connect_payload: Vec<u8>,
+        addr: Option<SocketAddr>,
+    ) {
         if let Entry::Vacant(e) = self.connections.entry(client_id) {
             #[cfg(feature = "metrics")]
             metrics::gauge!("connected_clients").increment(1.0);
 
             info!("New connection from id: {}", client_id);
-            let connection = Connection::new(
+            let mut connection = Connection::new(
                 client_id,
                 client_entity,
                 &self.channel_registry,
                 self.replication_config,
-                self.packet_config,
+                self.packet_config.clone(),
                 self.ping_config,
             );
+            connection.addr = addr;
             self.events.add_connect_event(ConnectEvent {
                 client_id,
                 entity: client_entity,
+                connect_payload,
+                addr,
             });
             self.new_clients.push(client_id);
             e.insert(connection);
@@ -286,17 +697,31 @@ impl ConnectionManager {
 
     /// Remove the connection associated with the given [`ClientId`],
     /// and returns the [`Entity`] associated with the client
-    pub(crate) fn remove(&mut self, client_id: ClientId) -> Entity {
+    pub(crate) fn remove(&mut self, client_id: ClientId, reason: DisconnectReason) -> Entity {
         #[cfg(feature = "metrics")]
         metrics::gauge!("connected_clients").decrement(1.0);
 
-        info!("Client {} disconnected", client_id);
+        info!("Client {} disconnected: {:?}", client_id, reason);
         let entity = self
             .client_entity(client_id)
             .expect("client entity not found");
-        self.events
-            .add_disconnect_event(DisconnectEvent { client_id, entity });
+        let addr = self
+            .connections
+            .get(&client_id)
+            .and_then(|connection| connection.addr);
+        self.events.add_disconnect_event(DisconnectEvent {
+            client_id,
+            entity,
+            reason,
+            addr,
+        });
         self.connections.remove(&client_id);
+        self.initial_syncs.remove(&client_id);
+        self.hidden_components.values_mut().for_each(|components| {
+            components.values_mut().for_each(|clients| {
+                clients.remove(&client_id);
+            })
+        });
         entity
     }
 
@@ -341,6 +766,9 @@ impl ConnectionManager {
                     Some(&mut c.replication_receiver.remote_entity_map.local_to_remote),
                 )?;
                 let message_bytes = self.writer.split();
+                #[cfg(feature = "metrics")]
+                metrics::counter!("message.bytes_sent", "message" => std::any::type_name::<M>())
+                    .increment(message_bytes.len() as u64);
                 // for local clients, we don't want to buffer messages in the MessageManager since
                 // there is no io
                 if c.is_local_client() {
@@ -369,8 +797,13 @@ impl ConnectionManager {
             self.message_registry
                 .serialize(message, &mut self.writer, None)?;
             let message_bytes = self.writer.split();
+            #[cfg(feature = "metrics")]
+            metrics::counter!("message.bytes_sent", "message" => std::any::type_name::<M>())
+                .increment(message_bytes.len() as u64);
             self.buffer_message_bytes(message_bytes, channel_kind, target)?;
         }
+        #[cfg(feature = "metrics")]
+        metrics::counter!("message.sent", "message" => std::any::type_name::<M>()).increment(1);
         Ok(())
     }
 
@@ -463,6 +896,8 @@ pub struct Connection {
     pub replication_receiver: ReplicationReceiver,
     pub(crate) events: ConnectionEvents,
     pub(crate) ping_manager: PingManager,
+    /// Last time we received replication or message data from the client (excluding pings/pongs)
+    pub(crate) last_data_receive_time: Option<WrappedTime>,
 
     // TODO: maybe don't do any replication until connection is synced?
     /// Used to transfer raw bytes to a system that can convert the bytes to the actual type
@@ -476,8 +911,24 @@ pub struct Connection {
     pub(crate) messages_to_rebroadcast: Vec<(Bytes, NetworkTarget, ChannelKind)>,
     /// True if this connection corresponds to a local client when running in host-server mode
     is_local_client: bool,
+    /// Address of the client, if the connection uses socket addresses
+    pub(crate) addr: Option<SocketAddr>,
     /// Messages to send to the local client (we don't buffer them in the MessageManager because there is no io)
     pub(crate) local_messages_to_send: Vec<Bytes>,
+    rate_limiter: RateLimiter,
+    /// True if the client exceeded the hard rate limit and should be disconnected
+    pub(crate) rate_limit_exceeded: bool,
+    /// Most recent input tick received from the client
+    last_input_tick: Option<Tick>,
+    /// True if an input message was received since the last [`InputAck`] was sent
+    input_ack_pending: bool,
+    /// If true, no replication messages are sent to the client (except the despawns of entities that it knows about)
+    replication_paused: bool,
+    /// Entities whose spawn was not sent because the replication was paused
+    paused_spawns: HashSet<Entity>,
+    /// Components removed from the entities known by the client while the replication was paused;
+    /// the removals are sent when the replication is resumed
+    paused_removals: HashMap<Entity, (ReplicationGroupId, Vec<ComponentNetId>)>,
 }
 
 impl Connection {
@@ -490,12 +941,22 @@ impl Connection {
         ping_config: PingConfig,
     ) -> Self {
         let bandwidth_cap_enabled = packet_config.bandwidth_cap_enabled;
+        let rate_limiter = RateLimiter::new(packet_config.rate_limit.clone());
         // create the message manager and the channels
         let mut message_manager = MessageManager::new(
             channel_registry,
             packet_config.nack_rtt_multiple,
-            packet_config.into(),
-        );
+            (&packet_config).into(),
+        )
+        .with_mtu_discovery(packet_config.mtu_discovery)
+        .with_congestion_control(packet_config.congestion_control)
+        .with_packet_buffer_pool(packet_config.packet_buffer_pool_size)
+        .with_packet_inspector(packet_config.packet_inspector.map(|inspector| {
+            ConnectionInspector {
+                inspector,
+                client_id: Some(client_id),
+            }
+        }));
         // get notified about acks/nacks for replication-update messages
         let entity_updates_sender = &mut message_manager
             .channels
@@ -504,17 +965,26 @@ impl Connection {
             .sender;
         let update_nacks_receiver = entity_updates_sender.subscribe_nacks();
         let update_acks_receiver = entity_updates_sender.subscribe_acks();
+        // get notified about acks for replication-actions messages
+        let actions_acks_receiver = message_manager
+            .channels
+            .get_mut(&ChannelKind::of::<EntityActionsChannel>())
+            .unwrap()
+            .sender
+            .subscribe_acks();
         // get a channel to get notified when a replication update message gets actually send (to update priority)
         let replication_update_send_receiver =
             message_manager.get_replication_update_send_receiver();
         let replication_sender = ReplicationSender::new(
             update_acks_receiver,
             update_nacks_receiver,
+            actions_acks_receiver,
             replication_update_send_receiver,
             replication_config,
             bandwidth_cap_enabled,
         );
-        let replication_receiver = ReplicationReceiver::new();
+        let replication_receiver =
+            ReplicationReceiver::new().with_updates_ordering(replication_config.updates_ordering);
         Self {
             client_id,
             entity,
@@ -522,6 +992,7 @@ impl Connection {
             replication_sender,
             replication_receiver,
             ping_manager: PingManager::new(ping_config),
+            last_data_receive_time: None,
             events: ConnectionEvents::default(),
             received_messages: HashMap::default(),
             received_input_messages: HashMap::default(),
@@ -530,7 +1001,15 @@ impl Connection {
             writer: Writer::with_capacity(MAX_PACKET_SIZE),
             messages_to_rebroadcast: vec![],
             is_local_client: false,
+            addr: None,
             local_messages_to_send: vec![],
+            rate_limiter,
+            rate_limit_exceeded: false,
+            last_input_tick: None,
+            input_ack_pending: false,
+            replication_paused: false,
+            paused_spawns: HashSet::default(),
+            paused_removals: HashMap::default(),
         }
     }
 
@@ -544,6 +1023,67 @@ impl Connection {
         self.is_local_client
     }
 
+    /// Address of the client, if the connection uses socket addresses
+    pub fn addr(&self) -> Option<SocketAddr> {
+        self.addr
+    }
+
+    /// Returns true if there are still messages to send to this client, or reliable messages
+    /// that were not acked by the client yet
+    pub fn has_pending_messages(&self) -> bool {
+        !self.local_messages_to_send.is_empty() || self.message_manager.has_messages_to_send()
+    }
+
+    /// Returns true if some unreliable messages to this client were dropped during the last send,
+    /// because the bandwidth cap was reached and the
+    /// [`PacketConfig::outgoing_buffer_size`](crate::server::config::PacketConfig::outgoing_buffer_size) buffer was full
+    pub fn outgoing_buffer_overflowed(&self) -> bool {
+        self.message_manager.outgoing_buffer_overflowed()
+    }
+
+    /// Maximum size of the packets sent to this client.
+    ///
+    /// This is the default [`MAX_PACKET_SIZE`] unless MTU discovery is enabled and found that
+    /// bigger packets can be used.
+    pub fn mtu(&self) -> usize {
+        self.message_manager.mtu()
+    }
+
+    /// Congestion state of the connection to this client
+    pub fn congestion_state(&self) -> CongestionState {
+        self.message_manager.congestion_state()
+    }
+
+    /// Number of entities whose updates were deferred to a later send during the last send, because of
+    /// [`ReplicationConfig::max_entities_per_tick`](crate::prelude::ReplicationConfig::max_entities_per_tick)
+    pub fn deferred_entities(&self) -> usize {
+        self.replication_sender.deferred_entities
+    }
+
+    /// Entity spawned on the server for this client
+    pub(crate) fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Record that an input message with inputs up to `end_tick` was received, so that it gets acked
+    pub(crate) fn record_input_tick(&mut self, end_tick: Tick) {
+        if self.last_input_tick.map_or(true, |tick| end_tick > tick) {
+            self.last_input_tick = Some(end_tick);
+        }
+        self.input_ack_pending = true;
+    }
+
+    /// Most recent input tick received from the client
+    pub fn last_input_tick(&self) -> Option<Tick> {
+        self.last_input_tick
+    }
+
+    /// Rates at which the client sent messages on each channel, measured over the previous
+    /// [`RateLimitConfig::window`](crate::server::rate_limit::RateLimitConfig::window)
+    pub fn received_rates(&self) -> &HashMap<ChannelKind, ChannelRates> {
+        self.rate_limiter.rates()
+    }
+
     /// Return the latest estimate of rtt
     pub fn rtt(&self) -> Duration {
         self.ping_manager.rtt()
@@ -554,6 +1094,11 @@ impl Connection {
         self.ping_manager.jitter()
     }
 
+    /// Return the latest estimate of packet loss (between 0.0 and 1.0), computed from the pings
+    pub fn packet_loss(&self) -> f32 {
+        self.ping_manager.packet_loss()
+    }
+
     pub(crate) fn update(
         &mut self,
         world_tick: BevyTick,
@@ -567,6 +1112,7 @@ impl Connection {
             .update(time_manager, &self.ping_manager, tick_manager);
         self.replication_sender.update(world_tick);
         self.ping_manager.update(time_manager);
+        self.rate_limiter.update(time_manager.delta());
     }
 
     pub(crate) fn buffer_message(
@@ -593,6 +1139,9 @@ impl Connection {
         bevy_tick: BevyTick,
         time_manager: &TimeManager,
     ) -> Result<(), ServerError> {
+        if self.replication_paused {
+            self.drop_paused_replication();
+        }
         self.replication_sender.accumulate_priority(time_manager);
         self.replication_sender.send_actions_messages(
             tick,
@@ -609,6 +1158,62 @@ impl Connection {
         Ok(())
     }
 
+    /// Drop the replication messages prepared while the replication is paused.
+    ///
+    /// The despawns of the entities that the client knows about are still sent, so that the client doesn't
+    /// keep entities that don't exist anymore when the replication is resumed. The component removals
+    /// are recorded and sent when the replication is resumed.
+    fn drop_paused_replication(&mut self) {
+        let paused_spawns = &mut self.paused_spawns;
+        let paused_removals = &mut self.paused_removals;
+        for (group_id, channel) in self.replication_sender.group_channels.iter_mut() {
+            channel.pending_updates.clear();
+            channel
+                .pending_actions
+                .retain(|entity, actions| match actions.spawn {
+                    SpawnAction::Spawn | SpawnAction::Reuse(_) => {
+                        paused_spawns.insert(*entity);
+                        false
+                    }
+                    SpawnAction::Despawn => {
+                        paused_removals.remove(entity);
+                        if paused_spawns.remove(entity) {
+                            // the client never received the spawn
+                            return false;
+                        }
+                        actions.insert.clear();
+                        actions.remove.clear();
+                        actions.updates.clear();
+                        true
+                    }
+                    SpawnAction::None => {
+                        if paused_spawns.contains(entity) {
+                            return false;
+                        }
+                        let (_, removed) = paused_removals
+                            .entry(*entity)
+                            .or_insert_with(|| (*group_id, Vec::new()));
+                        // a component that is inserted again is sent by the resync
+                        for component in &actions.insert {
+                            if let Ok(kind) =
+                                ComponentNetId::from_bytes(&mut Reader::from(component.clone()))
+                            {
+                                removed.retain(|removed_kind| *removed_kind != kind);
+                            }
+                        }
+                        for kind in &actions.remove {
+                            if !removed.contains(kind) {
+                                removed.push(*kind);
+                            }
+                        }
+                        false
+                    }
+                });
+        }
+        self.paused_removals
+            .retain(|_, (_, removed)| !removed.is_empty());
+    }
+
     fn send_ping(&mut self, ping: Ping) -> Result<(), ServerError> {
         trace!("Sending ping {:?}", ping);
         ping.to_bytes(&mut self.writer)?;
@@ -627,6 +1232,24 @@ impl Connection {
         Ok(())
     }
 
+    fn send_time_sync(&mut self, time_sync: TimeSync) -> Result<(), ServerError> {
+        trace!("Sending time sync {:?}", time_sync);
+        time_sync.to_bytes(&mut self.writer)?;
+        let message_bytes = self.writer.split();
+        self.message_manager
+            .buffer_send(message_bytes, ChannelKind::of::<TimeSyncChannel>())?;
+        Ok(())
+    }
+
+    fn send_input_ack(&mut self, input_ack: InputAck) -> Result<(), ServerError> {
+        trace!("Sending input ack {:?}", input_ack);
+        input_ack.to_bytes(&mut self.writer)?;
+        let message_bytes = self.writer.split();
+        self.message_manager
+            .buffer_send(message_bytes, ChannelKind::of::<InputAckChannel>())?;
+        Ok(())
+    }
+
     /// Send packets that are ready to be sent
     pub fn send_packets(
         &mut self,
@@ -656,6 +1279,20 @@ impl Connection {
                 self.send_pong(pong)?;
                 Ok::<(), ServerError>(())
             })?;
+
+        // stamp the packet with the precise server time so that the client can correct its clock
+        if let Some(time_sync) = self
+            .ping_manager
+            .maybe_prepare_time_sync(time_manager, tick_manager)
+        {
+            self.send_time_sync(time_sync)?;
+        }
+        // ack the inputs every time we receive inputs (even if they are redundant), so that
+        // a lost ack is quickly replaced
+        if let Some(end_tick) = self.last_input_tick.filter(|_| self.input_ack_pending) {
+            self.input_ack_pending = false;
+            self.send_input_ack(InputAck { end_tick })?;
+        }
         let payloads = self.message_manager.send_packets(tick_manager.tick())?;
 
         // update the replication sender about which messages were actually sent, and accumulate priority
@@ -663,6 +1300,11 @@ impl Connection {
         Ok(payloads)
     }
 
+    /// Return the payloads of the packets that were sent so that their buffers can be reused
+    pub(crate) fn recycle_payloads(&mut self, payloads: Vec<Payload>) {
+        self.message_manager.recycle_payloads(payloads);
+    }
+
     pub fn receive(
         &mut self,
         world: &mut World,
@@ -676,7 +1318,22 @@ impl Connection {
             .channels
             .iter_mut()
             .try_for_each(|(channel_kind, channel)| {
+                let internal = self
+                    .message_manager
+                    .channel_registry
+                    .is_internal_channel(channel_kind);
                 while let Some((tick, single_data)) = channel.receiver.read_message() {
+                    match self
+                        .rate_limiter
+                        .check(*channel_kind, single_data.len(), internal)
+                    {
+                        RateLimitDecision::Accept => {}
+                        RateLimitDecision::Drop => continue,
+                        RateLimitDecision::Disconnect => {
+                            self.rate_limit_exceeded = true;
+                            continue;
+                        }
+                    }
                     // let channel_name = self
                     //     .message_manager
                     //     .channel_registry
@@ -702,13 +1359,25 @@ impl Connection {
                     } else if channel_kind == &ChannelKind::of::<EntityActionsChannel>() {
                         let actions = EntityActionsMessage::from_bytes(&mut reader)?;
                         trace!(?tick, ?actions, "received replication actions message");
+                        self.last_data_receive_time = Some(time_manager.current_time());
                         // buffer the replication message
                         self.replication_receiver.recv_actions(actions, tick);
                     } else if channel_kind == &ChannelKind::of::<EntityUpdatesChannel>() {
                         let updates = EntityUpdatesMessage::from_bytes(&mut reader)?;
                         trace!(?tick, ?updates, "received replication updates message");
+                        self.last_data_receive_time = Some(time_manager.current_time());
                         // buffer the replication message
                         self.replication_receiver.recv_updates(updates, tick);
+                    } else if channel_kind == &ChannelKind::of::<EntityReliableUpdatesChannel>() {
+                        let updates = EntityUpdatesMessage::from_bytes(&mut reader)?;
+                        trace!(
+                            ?tick,
+                            ?updates,
+                            "received reliable replication updates message"
+                        );
+                        self.last_data_receive_time = Some(time_manager.current_time());
+                        self.replication_receiver
+                            .recv_reliable_updates(updates, tick);
                     } else {
                         // TODO: THIS IS DUPLICATED FROM THE `receive_message` FUNCTION BUT THERE ARE BORROW CHECKER
                         //  BECAUSE SPLIT BORROWS ARE NOT WELL HANDLED!
@@ -717,6 +1386,7 @@ impl Connection {
                         //  instead just read the bytes for the target!!
                         let ClientMessage { message, target } =
                             ClientMessage::from_bytes(&mut reader)?;
+                        self.last_data_receive_time = Some(time_manager.current_time());
 
                         let mut reader = Reader::from(message);
                         let net_id = NetId::from_bytes(&mut reader)?;
@@ -830,8 +1500,10 @@ impl ConnectionManager {
         &mut self,
         mut entity: Entity,
         group_id: ReplicationGroupId,
-        target: NetworkTarget,
+        mut target: NetworkTarget,
     ) -> Result<(), ServerError> {
+        // the clients whose initial sync hasn't reached the group never received the entity
+        self.exclude_initial_sync_pending(group_id, &mut target);
         self.connected_targets(target).try_for_each(|client_id| {
             // trace!(
             //     ?entity,
@@ -841,11 +1513,14 @@ impl ConnectionManager {
             // );
 
             // convert the entity to a network entity (possibly mapped)
-            entity = self
+            let remote_entity_map = &mut self
                 .connection_mut(client_id)?
                 .replication_receiver
-                .remote_entity_map
-                .to_remote(entity);
+                .remote_entity_map;
+            let local_entity = entity;
+            entity = remote_entity_map.to_remote(entity);
+            // if the entity becomes visible again, it will be spawned as a new entity
+            remote_entity_map.remove_alias(local_entity);
 
             self.connection_mut(client_id)?
                 .replication_sender
@@ -858,11 +1533,11 @@ impl ConnectionManager {
         &mut self,
         mut entity: Entity,
         kind: ComponentNetId,
-        group: &ReplicationGroup,
-        target: NetworkTarget,
+        group_id: ReplicationGroupId,
+        mut target: NetworkTarget,
     ) -> Result<(), ServerError> {
-        let group_id = group.group_id(Some(entity));
         debug!(?entity, ?kind, "Sending RemoveComponent");
+        self.exclude_initial_sync_pending(group_id, &mut target);
         self.connected_targets(target).try_for_each(|client_id| {
             entity = self
                 .connection_mut(client_id)?
@@ -1048,14 +1723,21 @@ impl ConnectionManager {
     ) -> Result<(), ServerError> {
         let mut num_targets = 0;
         let mut existing_bytes: Option<Bytes> = None;
+        // delta-compressed components are always sent unreliably, since the diffs are computed from the last acked update
+        let reliable =
+            !delta_compression && registry.replication_mode(kind) == ReplicationMode::Reliable;
         self.connected_targets(target).try_for_each(|client_id| {
             let connection = self.connections.get_mut(&client_id).ok_or(ServerError::ClientIdNotFound(client_id))?;
-            let send_tick = connection
+            let group_channel = connection
                 .replication_sender
                 .group_channels
                 .entry(group_id)
-                .or_default()
-                .send_tick;
+                .or_default();
+            let send_tick = if reliable {
+                group_channel.reliable_send_tick
+            } else {
+                group_channel.send_tick
+            };
             // send the update for all changes newer than the last send_tick for the group
             debug!(
                 ?kind,
@@ -1093,7 +1775,11 @@ impl ConnectionManager {
                         .replication_receiver
                         .remote_entity_map
                         .to_remote(entity);
-                    connection.replication_sender.prepare_component_update(entity, group_id, raw_data);
+                    if reliable {
+                        connection.replication_sender.prepare_reliable_component_update(entity, group_id, raw_data);
+                    } else {
+                        connection.replication_sender.prepare_component_update(entity, group_id, raw_data);
+                    }
                 }
             }
             Ok::<(), ServerError>(())
@@ -24,4 +24,6 @@ pub enum ServerError {
     RelevanceError(#[from] crate::server::relevance::error::RelevanceError),
     #[error(transparent)]
     ReplicationError(#[from] crate::shared::replication::error::ReplicationError),
+    #[error("the message was not received from a client, so it cannot be relayed")]
+    MessageNotRelayable,
 }
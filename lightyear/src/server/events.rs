@@ -1,9 +1,13 @@
 //! Wrapper around [`ConnectionEvents`] that adds server-specific functionality
+use std::net::SocketAddr;
+
 use bevy::ecs::entity::EntityHash;
 use bevy::prelude::*;
-use bevy::utils::HashMap;
+use bevy::utils::{Duration, HashMap};
 
 use crate::connection::id::ClientId;
+use crate::connection::server::DisconnectReason;
+use crate::packet::message_ack::MessageDelivery;
 use crate::prelude::ComponentRegistry;
 use crate::server::connection::ConnectionManager;
 use crate::shared::events::connection::{
@@ -12,7 +16,9 @@ use crate::shared::events::connection::{
 };
 use crate::shared::events::plugin::EventsPlugin;
 use crate::shared::events::systems::push_component_events;
-use crate::shared::sets::{InternalMainSet, ServerMarker};
+use crate::shared::sets::{InternalMainSet, NetworkingSchedules, ServerMarker};
+use crate::shared::time_manager::TimeManager;
+use crate::transport::udp::TransportRebindEvent;
 
 type EntityHashMap<K, V> = hashbrown::HashMap<K, V, EntityHash>;
 
@@ -22,17 +28,28 @@ pub struct ServerEventsPlugin;
 
 impl Plugin for ServerEventsPlugin {
     fn build(&self, app: &mut App) {
+        let schedules = NetworkingSchedules::of(app);
         app
             // EVENTS
             .add_event::<ConnectEvent>()
             .add_event::<DisconnectEvent>()
+            .add_event::<ReconnectEvent>()
+            .add_event::<TransportRebindEvent>()
+            .add_event::<ConnectionHealthEvent>()
+            .add_event::<MessageAckEvent>()
+            .add_event::<MessageTimeoutEvent>()
             // PLUGIN
             .add_plugins(EventsPlugin::<ConnectionManager>::default())
             // SYSTEMS
             .add_systems(
-                PreUpdate,
+                schedules.receive,
                 // TODO: check if this should be between Receive and EmitEvents
-                emit_connect_events.in_set(InternalMainSet::<ServerMarker>::EmitEvents),
+                (
+                    emit_connect_events,
+                    emit_connection_health_events,
+                    emit_message_ack_events,
+                )
+                    .in_set(InternalMainSet::<ServerMarker>::EmitEvents),
             );
     }
 }
@@ -42,6 +59,7 @@ fn emit_connect_events(
     mut commands: Commands,
     mut connect_events: EventWriter<ConnectEvent>,
     mut disconnect_events: EventWriter<DisconnectEvent>,
+    mut reconnect_events: EventWriter<ReconnectEvent>,
     mut connection_manager: ResMut<ConnectionManager>,
 ) {
     // EVENTS: Write the received events into bevy events
@@ -50,7 +68,7 @@ fn emit_connect_events(
         if connection_manager.events.has_connections() {
             for connect_event in connection_manager.events.iter_connections() {
                 debug!("Client connected event: {}", connect_event.client_id);
-                connect_events.send(connect_event);
+                connect_events.send(connect_event.clone());
                 // TODO: trigger all events in batch? https://github.com/bevyengine/bevy/pull/13953
                 // NOTE: we don't trigger the event immediately because we're inside world.resource_scope
                 //  so a bunch of Resources have been removed from the World
@@ -70,6 +88,63 @@ fn emit_connect_events(
                 // world.trigger(disconnect_event);
             }
         }
+
+        if connection_manager.events.has_reconnections() {
+            for reconnect_event in connection_manager.events.iter_reconnections() {
+                debug!("Client reconnected event: {}", reconnect_event.client_id);
+                reconnect_events.send(reconnect_event);
+                commands.trigger(reconnect_event);
+            }
+        }
+    }
+}
+
+/// Regularly report the health of each connection, at the interval specified by
+/// [`PingConfig::health_event_interval`](crate::prelude::PingConfig::health_event_interval)
+fn emit_connection_health_events(
+    time_manager: Res<TimeManager>,
+    mut connection_manager: ResMut<ConnectionManager>,
+    mut health_events: EventWriter<ConnectionHealthEvent>,
+) {
+    let current_time = time_manager.current_time();
+    for (client_id, connection) in connection_manager.connections.iter_mut() {
+        if connection.is_local_client() || !connection.ping_manager.maybe_report_health() {
+            continue;
+        }
+        health_events.send(ConnectionHealthEvent {
+            client_id: *client_id,
+            rtt: connection.ping_manager.rtt(),
+            last_recv_ago: connection
+                .last_data_receive_time
+                .map(|time| (current_time - time).to_std().unwrap_or_default()),
+        });
+    }
+}
+
+/// Emit a [`MessageAckEvent`] or a [`MessageTimeoutEvent`] for the messages sent with
+/// [`send_message_with_ack`](ConnectionManager::send_message_with_ack)
+fn emit_message_ack_events(
+    mut connection_manager: ResMut<ConnectionManager>,
+    mut ack_events: EventWriter<MessageAckEvent>,
+    mut timeout_events: EventWriter<MessageTimeoutEvent>,
+) {
+    for (client_id, connection) in connection_manager.connections.iter_mut() {
+        for (handle, delivery) in connection.message_manager.message_acks.drain() {
+            match delivery {
+                MessageDelivery::Acked => {
+                    ack_events.send(MessageAckEvent {
+                        handle,
+                        context: *client_id,
+                    });
+                }
+                MessageDelivery::Lost => {
+                    timeout_events.send(MessageTimeoutEvent {
+                        handle,
+                        context: *client_id,
+                    });
+                }
+            }
+        }
     }
 }
 
@@ -77,16 +152,18 @@ fn emit_connect_events(
 pub struct ServerEvents {
     pub connections: Vec<ConnectEvent>,
     pub disconnections: Vec<DisconnectEvent>,
+    pub reconnections: Vec<ReconnectEvent>,
     pub events: HashMap<ClientId, ConnectionEvents>,
     pub empty: bool,
 }
 
 pub(crate) fn emit_replication_events<C: Component>(app: &mut App) {
+    let schedules = NetworkingSchedules::of(app);
     app.add_event::<ComponentUpdateEvent<C>>();
     app.add_event::<ComponentInsertEvent<C>>();
     app.add_event::<ComponentRemoveEvent<C>>();
     app.add_systems(
-        PreUpdate,
+        schedules.receive,
         push_component_events::<C, ConnectionManager>
             .in_set(InternalMainSet::<ServerMarker>::EmitEvents),
     );
@@ -96,6 +173,7 @@ impl crate::shared::events::connection::ClearEvents for ServerEvents {
     fn clear(&mut self) {
         self.connections = Vec::new();
         self.disconnections = Vec::new();
+        self.reconnections = Vec::new();
         self.empty = true;
         self.events = HashMap::default();
     }
@@ -106,6 +184,7 @@ impl ServerEvents {
         Self {
             connections: Vec::new(),
             disconnections: Vec::new(),
+            reconnections: Vec::new(),
             events: HashMap::default(),
             empty: true,
         }
@@ -153,6 +232,14 @@ impl ServerEvents {
         !self.disconnections.is_empty()
     }
 
+    pub fn iter_reconnections(&mut self) -> Vec<ReconnectEvent> {
+        std::mem::take(&mut self.reconnections)
+    }
+
+    pub fn has_reconnections(&self) -> bool {
+        !self.reconnections.is_empty()
+    }
+
     pub(crate) fn add_connect_event(&mut self, connect_event: ConnectEvent) {
         self.connections.push(connect_event);
         self.empty = false;
@@ -164,6 +251,11 @@ impl ServerEvents {
         self.empty = false;
     }
 
+    pub(crate) fn add_reconnect_event(&mut self, reconnect_event: ReconnectEvent) {
+        self.reconnections.push(reconnect_event);
+        self.empty = false;
+    }
+
     pub(crate) fn push_events(&mut self, client_id: ClientId, events: ConnectionEvents) {
         if !events.is_empty() {
             self.events.insert(client_id, events);
@@ -250,10 +342,20 @@ impl IterComponentInsertEvent<ClientId> for ServerEvents {
 }
 
 /// Bevy [`Event`] emitted on the server on the frame where a client is connected
-#[derive(Event, Debug, Copy, Clone)]
+#[derive(Event, Debug, Clone)]
 pub struct ConnectEvent {
     pub client_id: ClientId,
     pub entity: Entity,
+    /// Application data that the client sent when connecting.
+    ///
+    /// This is empty if the client didn't provide any, or if the connection doesn't support it
+    /// (for example for the local client in host-server mode).
+    pub connect_payload: Vec<u8>,
+    /// Address of the client, for example to check it against a ban list.
+    ///
+    /// This is None if the connection doesn't use socket addresses
+    /// (for example for the local client in host-server mode, or with Steam).
+    pub addr: Option<SocketAddr>,
 }
 
 /// Bevy [`Event`] emitted on the server on the frame where a client is disconnected
@@ -261,6 +363,36 @@ pub struct ConnectEvent {
 pub struct DisconnectEvent {
     pub client_id: ClientId,
     pub entity: Entity,
+    pub reason: DisconnectReason,
+    /// Address of the client (see [`ConnectEvent::addr`])
+    pub addr: Option<SocketAddr>,
+}
+
+/// Bevy [`Event`] emitted on the server when a client connected by replacing its previous session
+/// (see [`DuplicateConnectPolicy::Reconnect`](crate::connection::server::DuplicateConnectPolicy::Reconnect)).
+///
+/// The [`DisconnectEvent`] of the previous session and the [`ConnectEvent`] of the new connection
+/// are emitted as usual.
+#[derive(Event, Debug, Copy, Clone, PartialEq)]
+pub struct ReconnectEvent {
+    pub client_id: ClientId,
+    /// Entity of the new connection
+    pub entity: Entity,
+}
+
+/// Bevy [`Event`] emitted regularly on the server for each connected client, to monitor idle connections.
+///
+/// The event is derived from the existing ping/pong exchange, so no additional packets are sent.
+/// It is only emitted if [`PingConfig::health_event_interval`](crate::prelude::PingConfig::health_event_interval) is set.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct ConnectionHealthEvent {
+    pub client_id: ClientId,
+    /// Current estimate of the round-trip time to the client
+    pub rtt: Duration,
+    /// Time elapsed since we last received replication or message data from the client.
+    ///
+    /// This is `None` if the client hasn't sent any data yet.
+    pub last_recv_ago: Option<Duration>,
 }
 
 /// Bevy [`Event`] emitted on the server on the frame where an input message from a client is received
@@ -281,6 +413,12 @@ pub type ComponentRemoveEvent<C> =
 
 /// Bevy [`Event`] emitted on the server on the frame where a (non-replication) message is received
 pub type MessageEvent<M> = crate::shared::events::components::MessageEvent<M, ClientId>;
+/// Bevy [`Event`] emitted on the server when a client acked a message sent with
+/// [`send_message_with_ack`](crate::server::connection::ConnectionManager::send_message_with_ack)
+pub type MessageAckEvent = crate::shared::events::components::MessageAckEvent<ClientId>;
+/// Bevy [`Event`] emitted on the server when a message sent with
+/// [`send_message_with_ack`](crate::server::connection::ConnectionManager::send_message_with_ack) is lost
+pub type MessageTimeoutEvent = crate::shared::events::components::MessageTimeoutEvent<ClientId>;
 
 #[cfg(test)]
 mod tests {
@@ -333,4 +471,56 @@ mod tests {
         assert!(data.contains(&(entity_1, client_1)));
         assert!(data.contains(&(entity_2, client_2)));
     }
+
+    #[test]
+    fn test_connection_health_events() {
+        use crate::prelude::{client, server::ServerConfig};
+        use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+
+        let mut stepper = BevyStepper::new(
+            Default::default(),
+            Default::default(),
+            Duration::from_millis(10),
+        );
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .ping
+            .health_event_interval = Some(Duration::from_millis(50));
+        stepper.init();
+
+        let health_events = |stepper: &mut BevyStepper| {
+            let mut events = vec![];
+            for _ in 0..10 {
+                stepper.frame_step();
+                events.extend(
+                    stepper
+                        .server_app
+                        .world_mut()
+                        .resource_mut::<Events<ConnectionHealthEvent>>()
+                        .drain(),
+                );
+            }
+            events
+        };
+        // the events are emitted at the configured interval, not every frame
+        let events = health_events(&mut stepper);
+        assert!(!events.is_empty() && events.len() < 5, "{events:?}");
+        assert!(events
+            .iter()
+            .all(|event| event.client_id == ClientId::Netcode(TEST_CLIENT_ID)));
+        // pings do not count as data received from the client
+        assert!(events.iter().all(|event| event.last_recv_ago.is_none()));
+
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<client::ConnectionManager>()
+            .send_message::<Channel1, StringMessage>(&mut StringMessage("a".to_string()))
+            .unwrap();
+        let events = health_events(&mut stepper);
+        let last_recv_ago = events.last().unwrap().last_recv_ago.unwrap();
+        assert!(last_recv_ago < Duration::from_millis(100));
+    }
 }
@@ -0,0 +1,375 @@
+//! Spread the initial replication of the world to a newly connected client over multiple sends
+//!
+//! When a client connects, every entity that is replicated to it has to be spawned on the client.
+//! If the world contains thousands of entities, sending them all at once results in a huge burst of packets.
+//!
+//! With [`ReplicationConfig::initial_sync_entities_per_tick`](crate::prelude::ReplicationConfig::initial_sync_entities_per_tick),
+//! the replication groups are instead sent in batches of at most that many entities, one batch per send.
+//! If the client entity has a [`ReplicationFocus`](crate::prelude::server::ReplicationFocus), the groups
+//! that are closest to the focus (using the [`Transform`](bevy::prelude::Transform) of their entities) are sent first.
+//! Otherwise, or for the same distance, the groups with the highest [`ReplicationGroup`](crate::prelude::ReplicationGroup)
+//! priority are sent first.
+//! The spawns are still sent reliably, and the groups that are not sent yet don't receive any update.
+//!
+//! The progress is available with [`ConnectionManager::initial_sync_percent`](crate::server::connection::ConnectionManager::initial_sync_percent).
+//! On the client, a [`WorldSnapshotComplete`](crate::client::events::WorldSnapshotComplete) event is emitted
+//! once all the entities of the initial sync have been spawned, for example to hide a loading screen.
+use bevy::utils::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::shared::replication::components::ReplicationGroupId;
+
+/// Initial replication of the world to a client, that is spread over multiple sends
+#[derive(Debug, Default)]
+pub(crate) struct InitialSync {
+    /// Groups that still need to be sent (with their number of entities), sorted so that the group
+    /// with the highest priority is last
+    queue: Vec<(ReplicationGroupId, usize)>,
+    /// Groups that are in the queue
+    pending: HashSet<ReplicationGroupId>,
+    /// Groups that are sent during the current send
+    batch: HashSet<ReplicationGroupId>,
+    /// All the groups of the initial sync
+    groups: Vec<ReplicationGroupId>,
+    total_entities: usize,
+    sent_entities: usize,
+}
+
+impl InitialSync {
+    /// Create the initial sync from the `(group, priority, distance to the focus)` of every entity
+    /// replicated to the client
+    pub(crate) fn new(
+        entities: impl IntoIterator<Item = (ReplicationGroupId, f32, Option<f32>)>,
+    ) -> Self {
+        let mut groups: HashMap<ReplicationGroupId, GroupOrder> = HashMap::default();
+        let mut total_entities = 0;
+        for (group_id, priority, distance) in entities {
+            total_entities += 1;
+            let group = groups.entry(group_id).or_insert(GroupOrder {
+                distance,
+                priority,
+                count: 0,
+            });
+            // a group is as close as its closest entity
+            group.distance = match (group.distance, distance) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            group.priority = group.priority.max(priority);
+            group.count += 1;
+        }
+        let mut groups: Vec<_> = groups.into_iter().collect();
+        // closest (then highest priority) last, and lowest group id first for the same order
+        groups.sort_by(|(id_a, a), (id_b, b)| {
+            b.distance
+                .unwrap_or(f32::INFINITY)
+                .total_cmp(&a.distance.unwrap_or(f32::INFINITY))
+                .then(a.priority.total_cmp(&b.priority))
+                .then(id_b.0.cmp(&id_a.0))
+        });
+        Self {
+            pending: groups.iter().map(|(id, _)| *id).collect(),
+            groups: groups.iter().map(|(id, _)| *id).collect(),
+            queue: groups
+                .into_iter()
+                .map(|(id, group)| (id, group.count))
+                .collect(),
+            batch: HashSet::default(),
+            total_entities,
+            sent_entities: 0,
+        }
+    }
+
+    /// Select the groups that are sent during the current send.
+    ///
+    /// A group is never split: a group bigger than `max_entities` is sent on its own.
+    pub(crate) fn next_batch(&mut self, max_entities: usize) {
+        self.batch.clear();
+        let mut num_entities = 0;
+        while let Some(&(group_id, count)) = self.queue.last() {
+            if !self.batch.is_empty() && num_entities + count > max_entities {
+                break;
+            }
+            self.queue.pop();
+            self.pending.remove(&group_id);
+            self.batch.insert(group_id);
+            num_entities += count;
+        }
+        self.sent_entities += num_entities;
+    }
+
+    /// Returns true if the spawn of the group must be sent during the current send
+    pub(crate) fn in_batch(&self, group_id: ReplicationGroupId) -> bool {
+        self.batch.contains(&group_id)
+    }
+
+    /// Returns true if the group hasn't been sent yet
+    pub(crate) fn is_pending(&self, group_id: ReplicationGroupId) -> bool {
+        self.pending.contains(&group_id)
+    }
+
+    pub(crate) fn is_finished(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// All the groups of the initial sync
+    pub(crate) fn groups(&self) -> &[ReplicationGroupId] {
+        &self.groups
+    }
+
+    /// Percentage of the entities whose spawn has been sent
+    pub(crate) fn percent(&self) -> f32 {
+        if self.total_entities == 0 {
+            return 100.0;
+        }
+        self.sent_entities as f32 * 100.0 / self.total_entities as f32
+    }
+}
+
+/// Order in which a group is sent during the initial sync
+#[derive(Debug)]
+struct GroupOrder {
+    distance: Option<f32>,
+    priority: f32,
+    count: usize,
+}
+
+/// Message sent to the client when its initial sync is complete
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct InitialSyncComplete {
+    /// Groups whose spawn was sent to the client. The initial sync is complete on the client once
+    /// it has received all of them.
+    pub groups: Vec<ReplicationGroupId>,
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::prelude::{default, Commands, Events, With};
+    use bevy::utils::Duration;
+
+    use crate::prelude::client::WorldSnapshotComplete;
+    use crate::prelude::server::{ConnectionManager, Replicate, ServerCommands};
+    use crate::prelude::{
+        client::ClientCommands, ClientId, ReplicationGroup, SharedConfig, TickConfig,
+    };
+    use crate::server::config::ServerConfig;
+    use crate::shared::replication::components::Replicated;
+    use crate::tests::protocol::ComponentSyncModeFull;
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+
+    use super::*;
+
+    #[test]
+    fn test_initial_sync_batches() {
+        let group = |id| ReplicationGroupId(id);
+        let mut sync = InitialSync::new([
+            (group(0), 1.0, None),
+            (group(1), 1.0, None),
+            (group(1), 1.0, None),
+            (group(2), 5.0, None),
+            (group(3), 1.0, None),
+        ]);
+        assert_eq!(sync.percent(), 0.0);
+
+        // the group with the highest priority is sent first
+        sync.next_batch(2);
+        assert!(sync.in_batch(group(2)));
+        assert!(sync.in_batch(group(0)));
+        assert!(!sync.is_pending(group(0)));
+        assert!(sync.is_pending(group(1)));
+        assert_eq!(sync.percent(), 40.0);
+
+        // groups are never split
+        sync.next_batch(1);
+        assert!(sync.in_batch(group(1)));
+        assert!(!sync.in_batch(group(0)));
+        assert_eq!(sync.percent(), 80.0);
+
+        sync.next_batch(2);
+        assert!(sync.in_batch(group(3)));
+        assert!(sync.is_finished());
+        assert_eq!(sync.percent(), 100.0);
+    }
+
+    #[test]
+    fn test_initial_sync_distance() {
+        let group = |id| ReplicationGroupId(id);
+        let mut sync = InitialSync::new([
+            (group(0), 10.0, Some(50.0)),
+            (group(1), 1.0, Some(30.0)),
+            // a group is as close as its closest entity
+            (group(2), 1.0, Some(100.0)),
+            (group(2), 1.0, Some(10.0)),
+            (group(3), 1.0, Some(30.0)),
+        ]);
+        let mut order = vec![];
+        while !sync.is_finished() {
+            sync.next_batch(1);
+            order.extend((0..4).filter(|id| sync.in_batch(group(*id))));
+        }
+        // the closest groups are sent first, whatever their priority
+        assert_eq!(order, vec![2, 1, 3, 0]);
+    }
+
+    #[test]
+    fn test_initial_sync_pacing() {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, default(), tick_duration);
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .replication
+            .initial_sync_entities_per_tick = Some(2);
+        stepper.build();
+        // the entities exist before the client connects
+        for i in 0..5 {
+            stepper.server_app.world_mut().spawn((
+                ComponentSyncModeFull(i as f32),
+                Replicate {
+                    group: ReplicationGroup::new_id(i),
+                    ..default()
+                },
+            ));
+        }
+        stepper
+            .server_app
+            .world_mut()
+            .run_system_once(|mut commands: Commands| commands.start_server());
+        stepper
+            .client_app
+            .world_mut()
+            .run_system_once(|mut commands: Commands| commands.connect_client());
+
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        let mut progress = vec![];
+        // number of entities spawned on the client when the snapshot is complete
+        let mut snapshot_complete = vec![];
+        for _ in 0..100 {
+            stepper.frame_step();
+            let events = stepper
+                .client_app
+                .world_mut()
+                .resource_mut::<Events<WorldSnapshotComplete>>()
+                .drain()
+                .count();
+            if events > 0 {
+                let spawned = stepper
+                    .client_app
+                    .world_mut()
+                    .query_filtered::<(), With<Replicated>>()
+                    .iter(stepper.client_app.world())
+                    .count();
+                snapshot_complete.push((events, spawned));
+            }
+            let manager = stepper.server_app.world().resource::<ConnectionManager>();
+            if let Ok(percent) = manager.initial_sync_percent(client_id) {
+                // number of groups whose spawn was sent to the client
+                let groups = manager
+                    .connection(client_id)
+                    .unwrap()
+                    .replication_sender
+                    .group_channels
+                    .len();
+                progress.push((percent, groups));
+            }
+        }
+        // the entities are sent in batches of 2
+        assert_eq!(
+            progress[..4],
+            [(40.0, 2), (80.0, 4), (100.0, 5), (100.0, 5)]
+        );
+        // the event is emitted once, after all the entities are spawned
+        assert_eq!(snapshot_complete, vec![(1, 5)]);
+        // all the entities are spawned on the client with their components
+        let mut values: Vec<_> = stepper
+            .client_app
+            .world_mut()
+            .query_filtered::<&ComponentSyncModeFull, With<Replicated>>()
+            .iter(stepper.client_app.world())
+            .map(|component| component.0)
+            .collect();
+        values.sort_by(f32::total_cmp);
+        assert_eq!(values, vec![0.0, 1.0, 2.0, 3.0, 4.0]);
+    }
+
+    /// An entity that is despawned before its spawn was sent is never sent to the client
+    #[test]
+    fn test_initial_sync_despawn_pending() {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, default(), tick_duration);
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .replication
+            .initial_sync_entities_per_tick = Some(1);
+        stepper.build();
+        let entities: Vec<_> = (0..3)
+            .map(|i| {
+                stepper
+                    .server_app
+                    .world_mut()
+                    .spawn((
+                        ComponentSyncModeFull(i as f32),
+                        Replicate {
+                            group: ReplicationGroup::new_id(i),
+                            ..default()
+                        },
+                    ))
+                    .id()
+            })
+            .collect();
+        stepper
+            .server_app
+            .world_mut()
+            .run_system_once(|mut commands: Commands| commands.start_server());
+        stepper
+            .client_app
+            .world_mut()
+            .run_system_once(|mut commands: Commands| commands.connect_client());
+
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        // wait until the first group is sent
+        for _ in 0..100 {
+            stepper.frame_step();
+            let manager = stepper.server_app.world().resource::<ConnectionManager>();
+            if manager
+                .initial_sync_percent(client_id)
+                .is_ok_and(|percent| percent > 0.0)
+            {
+                break;
+            }
+        }
+        // the last group is still pending
+        stepper.server_app.world_mut().despawn(entities[2]);
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        let manager = stepper.server_app.world().resource::<ConnectionManager>();
+        assert!(!manager
+            .connection(client_id)
+            .unwrap()
+            .replication_sender
+            .group_channels
+            .contains_key(&ReplicationGroupId(2)));
+        let mut values: Vec<_> = stepper
+            .client_app
+            .world_mut()
+            .query_filtered::<&ComponentSyncModeFull, With<Replicated>>()
+            .iter(stepper.client_app.world())
+            .map(|component| component.0)
+            .collect();
+        values.sort_by(f32::total_cmp);
+        assert_eq!(values, vec![0.0, 1.0]);
+    }
+}
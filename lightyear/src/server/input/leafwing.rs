@@ -40,12 +40,13 @@ pub enum InputSystemSet {
 
 impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A> {
     fn build(&self, app: &mut App) {
+        let schedules = NetworkingSchedules::of(app);
         // RESOURCES
         // app.init_resource::<GlobalActions<A>>();
         // TODO: (global action states) add a resource tracking the action-state of all clients
         // SETS
         app.configure_sets(
-            PreUpdate,
+            schedules.receive,
             (
                 InternalMainSet::<ServerMarker>::Receive,
                 InputSystemSet::AddBuffers,
@@ -57,7 +58,7 @@ impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A> {
         app.configure_sets(FixedPreUpdate, InputSystemSet::Update.run_if(is_started));
         // SYSTEMS
         app.add_systems(
-            PreUpdate,
+            schedules.receive,
             (
                 // TODO: ideally we have a Flush between add_action_diff_buffer and Tick?
                 add_action_diff_buffer::<A>.in_set(InputSystemSet::AddBuffers),
@@ -122,6 +123,7 @@ fn receive_input_message<A: LeafwingUserAction>(
                 ) {
                     Ok(message) => {
                         debug!(?client_id, action = ?A::short_type_path(), ?message.end_tick, ?message.diffs, "received input message");
+                        connection.record_input_tick(message.end_tick);
                         // TODO: UPDATE THIS
                         for (target, start, diffs) in &message.diffs {
                             match target {
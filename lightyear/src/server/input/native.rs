@@ -11,7 +11,7 @@ use crate::serialize::reader::Reader;
 use crate::server::connection::ConnectionManager;
 use crate::server::events::InputEvent;
 use crate::shared::replication::network_target::NetworkTarget;
-use crate::shared::sets::{InternalMainSet, ServerMarker};
+use crate::shared::sets::{InternalMainSet, NetworkingSchedules, ServerMarker};
 
 pub struct InputPlugin<A> {
     _marker: std::marker::PhantomData<A>,
@@ -20,14 +20,86 @@ pub struct InputPlugin<A> {
 #[derive(Resource, Debug)]
 pub struct InputBuffers<A> {
     /// The first element stores the last input we have received from the client.
-    /// In case we are missing the client input for a tick, we will fallback to using this.
+    /// In case we are missing the client input for a tick, we will fallback to using this
+    /// (see [`MissingInputPolicy`]).
     buffers: HashMap<ClientId, (Option<A>, InputBuffer<A>)>,
+    stats: HashMap<ClientId, InputBufferStats>,
 }
 
 impl<A> Default for InputBuffers<A> {
     fn default() -> Self {
         Self {
             buffers: HashMap::default(),
+            stats: HashMap::default(),
+        }
+    }
+}
+
+impl<A> InputBuffers<A> {
+    /// Statistics about the input buffer of a client.
+    ///
+    /// Can be used to tune the depth of the jitter buffer with
+    /// [`InputConfig::jitter_buffer_ticks`](crate::client::input::native::InputConfig::jitter_buffer_ticks)
+    pub fn stats(&self, client_id: ClientId) -> Option<&InputBufferStats> {
+        self.stats.get(&client_id)
+    }
+}
+
+/// Statistics about how often the input buffer of a client ran dry
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct InputBufferStats {
+    /// Number of ticks for which we looked for an input of the client
+    pub ticks: u32,
+    /// Number of ticks for which the input had not been received yet, so the previous input was used instead
+    pub missing_inputs: u32,
+    /// Number of ticks of inputs that were still buffered after the input of the latest tick was consumed.
+    ///
+    /// This is the margin with which the latest inputs arrived; a value that is often 0 means that the
+    /// jitter buffer is too small.
+    pub buffered_ticks: u16,
+}
+
+impl InputBufferStats {
+    /// Fraction of ticks for which the input was missing
+    pub fn missing_ratio(&self) -> f32 {
+        if self.ticks == 0 {
+            return 0.0;
+        }
+        self.missing_inputs as f32 / self.ticks as f32
+    }
+}
+
+/// How the server fills the input of a tick for which the input of the client was not received
+/// (for example because of packet loss, or because it arrived too late).
+///
+/// This is a per-input type [`Resource`]; you can insert it in the server app to override the default
+/// policy for a given input type:
+/// ```rust,ignore
+/// // use a neutral input instead of the missing input
+/// app.insert_resource(MissingInputPolicy::<MyInput>::Custom(|_| Some(MyInput::default())));
+/// ```
+///
+/// The missing inputs are rare if the client sends redundant inputs
+/// (see [`InputConfig::packet_redundancy`](crate::client::input::native::InputConfig::packet_redundancy)),
+/// since a single lost packet does not create a gap in the server's input buffer.
+#[derive(Resource, Default)]
+pub enum MissingInputPolicy<A> {
+    /// Repeat the last input received from the client, so that a player doesn't stop for one tick
+    #[default]
+    RepeatLast,
+    /// Emit the [`InputEvent`] without any input (this is the same as the client not pressing anything)
+    Absent,
+    /// Compute the input from the last input received from the client
+    Custom(fn(last_input: Option<&A>) -> Option<A>),
+}
+
+impl<A: Clone> MissingInputPolicy<A> {
+    /// Input to use for a tick where the input of the client is missing
+    fn fill(&self, last_input: Option<&A>) -> Option<A> {
+        match self {
+            MissingInputPolicy::RepeatLast => last_input.cloned(),
+            MissingInputPolicy::Absent => None,
+            MissingInputPolicy::Custom(f) => f(last_input),
         }
     }
 }
@@ -52,13 +124,15 @@ pub enum InputSystemSet {
 
 impl<A: UserAction> Plugin for InputPlugin<A> {
     fn build(&self, app: &mut App) {
+        let schedules = NetworkingSchedules::of(app);
         // RESOURCES
         app.init_resource::<InputBuffers<A>>();
+        app.init_resource::<MissingInputPolicy<A>>();
         // EVENTS
         app.add_event::<InputEvent<A>>();
         // SETS
         app.configure_sets(
-            PreUpdate,
+            schedules.receive,
             InputSystemSet::ReceiveInputMessage
                 .in_set(InternalMainSet::<ServerMarker>::EmitEvents)
                 .run_if(is_started),
@@ -73,7 +147,7 @@ impl<A: UserAction> Plugin for InputPlugin<A> {
         );
 
         app.add_systems(
-            PreUpdate,
+            schedules.receive,
             receive_input_message::<A>.in_set(InputSystemSet::ReceiveInputMessage),
         );
         app.add_systems(
@@ -94,6 +168,7 @@ fn handle_client_disconnect<A: UserAction>(
     mut input_buffers: ResMut<InputBuffers<A>>,
 ) {
     input_buffers.buffers.remove(&trigger.event().client_id);
+    input_buffers.stats.remove(&trigger.event().client_id);
 }
 
 /// Read the message received from the client and emit the MessageEvent event
@@ -123,6 +198,7 @@ fn receive_input_message<A: UserAction>(
                 ) {
                     Ok(message) => {
                         debug!("Received input message: {:?}", message);
+                        connection.record_input_tick(message.end_tick);
                         input_buffers
                             .buffers
                             .entry(*client_id)
@@ -153,22 +229,36 @@ fn receive_input_message<A: UserAction>(
 // Do it in this system because we want an input for every tick
 fn write_input_event<A: UserAction>(
     tick_manager: Res<TickManager>,
+    missing_input_policy: Res<MissingInputPolicy<A>>,
     mut input_buffers: ResMut<InputBuffers<A>>,
     mut input_events: EventWriter<InputEvent<A>>,
 ) {
     let tick = tick_manager.tick();
-    input_buffers
-        .buffers
+    let InputBuffers { buffers, stats } = input_buffers.as_mut();
+    buffers
         .iter_mut()
         .for_each(move |(client_id, (last_input, input_buffer))| {
             debug!(?input_buffer, ?tick, ?client_id, "input buffer for client");
+            // the buffer ran dry if we haven't received the input for this tick yet
+            let end_tick = input_buffer.end_tick();
+            let stats = stats.entry(*client_id).or_default();
+            stats.ticks += 1;
+            match end_tick {
+                Some(end_tick) if end_tick >= tick => {
+                    stats.buffered_ticks = (end_tick - tick) as u16;
+                }
+                _ => {
+                    stats.missing_inputs += 1;
+                    stats.buffered_ticks = 0;
+                }
+            }
             let received_input = input_buffer.pop(tick);
             let fallback = received_input.is_none();
 
-            // NOTE: if there is no input for this tick, we should use the last input that we have
-            //  as a best-effort fallback.
+            // NOTE: if there is no input for this tick, we use the last input that we have
+            //  as a best-effort fallback (depending on the MissingInputPolicy)
             let input = match received_input {
-                None => last_input.clone(),
+                None => missing_input_policy.fill(last_input.as_ref()),
                 Some(i) => {
                     *last_input = Some(i.clone());
                     Some(i)
@@ -1,16 +1,26 @@
 use super::*;
 use crate::prelude::CompressionConfig;
 use crate::server::io::transport::{ServerTransportBuilder, ServerTransportBuilderEnum};
+use crate::shared::time_source::RealTimeSource;
 use crate::transport::channels::Channels;
-use crate::transport::config::SharedIoConfig;
+use crate::transport::config::{SharedIoConfig, SocketConfig};
+use crate::transport::custom::{CustomTransport, CustomTransportBuilder};
 use crate::transport::dummy::DummyIo;
-use crate::transport::io::IoStats;
+use crate::transport::io::IoCounters;
+use crate::transport::middleware::bandwidth::BandwidthLimitedSender;
+use crate::transport::middleware::checksum::{ChecksumReceiver, ChecksumSender};
+#[cfg(any(feature = "zstd", feature = "lz4"))]
+use crate::transport::middleware::compression::packet::{
+    CompressedPacketReceiver, CompressedPacketSender,
+};
 #[cfg(feature = "zstd")]
 use crate::transport::middleware::compression::zstd::compression::ZstdCompressor;
 #[cfg(feature = "zstd")]
 use crate::transport::middleware::compression::zstd::decompression::ZstdDecompressor;
-use crate::transport::middleware::conditioner::LinkConditioner;
-use crate::transport::middleware::PacketReceiverWrapper;
+use crate::transport::middleware::conditioner::{
+    AddrConditionedPacketReceiver, AddrConditionerConfigs, ConditionedPacketSender, LinkConditioner,
+};
+use crate::transport::middleware::{apply_receiver_middleware, apply_sender_middleware};
 use crate::transport::udp::UdpSocketBuilder;
 #[cfg(all(feature = "websocket", not(target_family = "wasm")))]
 use crate::transport::websocket::server::WebSocketServerSocketBuilder;
@@ -20,6 +30,7 @@ use crate::transport::BoxedReceiver;
 use crate::transport::Transport;
 use bevy::prelude::TypePath;
 use std::net::IpAddr;
+use std::sync::Arc;
 #[cfg(all(feature = "webtransport", not(target_family = "wasm")))]
 use wtransport::Identity;
 
@@ -46,6 +57,8 @@ pub enum ServerTransport {
             Sender<Vec<u8>>,
         )>,
     },
+    /// Use a transport provided by the user
+    Custom(Arc<dyn CustomTransport>),
     /// Dummy transport if the connection handles its own io (for example steam sockets)
     Dummy,
 }
@@ -75,16 +88,20 @@ impl Clone for ServerTransport {
             ServerTransport::Channels { channels: __self_0 } => ServerTransport::Channels {
                 channels: Clone::clone(__self_0),
             },
+            ServerTransport::Custom(__self_0) => ServerTransport::Custom(Clone::clone(__self_0)),
             ServerTransport::Dummy => ServerTransport::Dummy,
         }
     }
 }
 
 impl ServerTransport {
-    fn build(self) -> ServerTransportBuilderEnum {
+    fn build(self, socket_config: SocketConfig) -> ServerTransportBuilderEnum {
         match self {
             ServerTransport::UdpSocket(addr) => {
-                ServerTransportBuilderEnum::UdpSocket(UdpSocketBuilder { local_addr: addr })
+                ServerTransportBuilderEnum::UdpSocket(UdpSocketBuilder {
+                    local_addr: addr,
+                    socket_config,
+                })
             }
             #[cfg(all(feature = "webtransport", not(target_family = "wasm")))]
             ServerTransport::WebTransportServer {
@@ -98,11 +115,15 @@ impl ServerTransport {
             ServerTransport::WebSocketServer { server_addr } => {
                 ServerTransportBuilderEnum::WebSocketServer(WebSocketServerSocketBuilder {
                     server_addr,
+                    tcp_nodelay: socket_config.tcp_nodelay,
                 })
             }
             ServerTransport::Channels { channels } => {
                 ServerTransportBuilderEnum::Channels(Channels::new(channels))
             }
+            ServerTransport::Custom(transport) => {
+                ServerTransportBuilderEnum::Custom(CustomTransportBuilder(transport))
+            }
             ServerTransport::Dummy => ServerTransportBuilderEnum::Dummy(DummyIo),
         }
     }
@@ -116,36 +137,75 @@ impl Default for ServerTransport {
 
 impl SharedIoConfig<ServerTransport> {
     pub fn start(self) -> Result<Io> {
-        let (transport, state, io_rx, network_tx) = self.transport.build().start()?;
+        let (transport, state, io_rx, network_tx) = self.transport.build(self.socket).start()?;
         let local_addr = transport.local_addr();
-        #[allow(unused_mut)]
-        let (mut sender, receiver) = transport.split();
-        #[allow(unused_mut)]
-        let mut receiver: BoxedReceiver = if let Some(conditioner_config) = self.conditioner {
-            let conditioner = LinkConditioner::new(conditioner_config);
-            Box::new(conditioner.wrap(receiver))
-        } else {
-            Box::new(receiver)
-        };
+        let (sender, receiver) = transport.split();
+        let mut sender = apply_sender_middleware(sender, &self.sender_middleware);
+        if let Some(conditioner_config) = self.outgoing_conditioner {
+            let mut conditioner = LinkConditioner::new(conditioner_config);
+            if let Some(time_source) = &self.time_source {
+                conditioner = conditioner.with_time_source(time_source.clone());
+            }
+            sender = Box::new(ConditionedPacketSender::new(sender, conditioner));
+        }
+        if let Some(max_bandwidth) = self.max_send_bandwidth {
+            let time_source = self
+                .time_source
+                .clone()
+                .unwrap_or_else(|| Arc::new(RealTimeSource));
+            sender = Box::new(BandwidthLimitedSender::new(
+                sender,
+                max_bandwidth,
+                time_source,
+            ));
+        }
+        let receiver = apply_receiver_middleware(receiver, &self.receiver_middleware);
+        // the server can override the conditioner for specific clients at runtime
+        let conditioner_configs = AddrConditionerConfigs::default();
+        let mut receiver = AddrConditionedPacketReceiver::new(
+            receiver,
+            self.incoming_conditioner,
+            conditioner_configs.clone(),
+        );
+        if let Some(time_source) = &self.time_source {
+            receiver = receiver.with_time_source(time_source.clone());
+        }
+        let mut receiver: BoxedReceiver = Box::new(receiver);
+        let stats = Arc::new(IoCounters::new(
+            self.bandwidth_window,
+            self.time_source.clone(),
+        ));
+        if self.checksum {
+            sender = Box::new(ChecksumSender::new(sender));
+            receiver = Box::new(ChecksumReceiver::new(receiver, stats.clone()));
+        }
         match self.compression {
             CompressionConfig::None => {}
             #[cfg(feature = "zstd")]
             CompressionConfig::Zstd { level } => {
-                use crate::transport::middleware::PacketSenderWrapper;
-                let compressor = ZstdCompressor::new(level);
-                sender = Box::new(compressor.wrap(sender));
-                let decompressor = ZstdDecompressor::new();
-                receiver = Box::new(decompressor.wrap(receiver));
+                let dictionary = self.compression_dictionary.as_deref();
+                sender = Box::new(CompressedPacketSender::new(
+                    sender,
+                    ZstdCompressor::new(level, dictionary),
+                    self.compression_min_size,
+                ));
+                receiver = Box::new(CompressedPacketReceiver::new(
+                    receiver,
+                    ZstdDecompressor::new(dictionary),
+                ));
             }
             #[cfg(feature = "lz4")]
             CompressionConfig::Lz4 => {
-                use crate::transport::middleware::PacketSenderWrapper;
-                let compressor =
-                    crate::transport::middleware::compression::lz4::Compressor::default();
-                sender = Box::new(compressor.wrap(sender));
-                let decompressor =
-                    crate::transport::middleware::compression::lz4::Decompressor::default();
-                receiver = Box::new(decompressor.wrap(receiver));
+                use crate::transport::middleware::compression::lz4::{Compressor, Decompressor};
+                sender = Box::new(CompressedPacketSender::new(
+                    sender,
+                    Compressor::default(),
+                    self.compression_min_size,
+                ));
+                receiver = Box::new(CompressedPacketReceiver::new(
+                    receiver,
+                    Decompressor::default(),
+                ));
             }
         }
         Ok(BaseIo {
@@ -153,10 +213,11 @@ impl SharedIoConfig<ServerTransport> {
             sender,
             receiver,
             state,
-            stats: IoStats::default(),
+            stats,
             context: IoContext {
                 event_sender: network_tx,
                 event_receiver: io_rx,
+                conditioner_configs,
             },
         })
     }
@@ -5,6 +5,7 @@ pub(crate) mod transport;
 
 use crate::transport::error::{Error, Result};
 use crate::transport::io::{BaseIo, IoState};
+use crate::transport::middleware::conditioner::{AddrConditionerConfigs, LinkConditionerConfig};
 use bevy::prelude::{Deref, DerefMut};
 use crossbeam_channel::Sender;
 use std::net::SocketAddr;
@@ -12,14 +13,18 @@ use std::net::SocketAddr;
 pub struct IoContext {
     pub(crate) event_sender: Option<ServerNetworkEventSender>,
     pub(crate) event_receiver: Option<ServerIoEventReceiver>,
+    /// Link conditioner configs that apply to specific remote addresses
+    pub(crate) conditioner_configs: AddrConditionerConfigs,
 }
 
 /// Server IO
 pub type Io = BaseIo<IoContext>;
 
 impl Io {
+    /// Close the io: the transport is released immediately, even if the io is not dropped
     pub fn close(&mut self) -> Result<()> {
         self.state = IoState::Disconnected;
+        self.close_transport();
         if let Some(event_sender) = self.context.event_sender.as_mut() {
             event_sender
                 .try_send(ServerIoEvent::ServerDisconnected(
@@ -29,6 +34,14 @@ impl Io {
         }
         Ok(())
     }
+
+    /// Simulate specific network conditions for the packets received from the remote address `addr`,
+    /// instead of using the [`LinkConditionerConfig`] of the [`IoConfig`](crate::prelude::server::IoConfig).
+    ///
+    /// If `config` is None, the packets from `addr` use the default conditioner again.
+    pub fn set_conditioner(&mut self, addr: SocketAddr, config: Option<LinkConditionerConfig>) {
+        self.context.conditioner_configs.set(addr, config);
+    }
 }
 
 #[derive(Deref, DerefMut, Clone)]
@@ -39,6 +52,8 @@ pub(crate) enum ServerIoEvent {
     ServerConnected,
     ServerDisconnected(Error),
     ClientDisconnected(SocketAddr),
+    /// The socket became invalid and was bound again to this local address
+    Rebound(SocketAddr),
 }
 
 /// Events that will be sent from the main thread to the io thread
@@ -1,5 +1,6 @@
 use crate::server::io::{ServerIoEventReceiver, ServerNetworkEventSender};
 use crate::transport::channels::Channels;
+use crate::transport::custom::{CustomTransportBuilder, CustomTransportSocket};
 use crate::transport::dummy::DummyIo;
 use crate::transport::error::Result;
 use crate::transport::io::IoState;
@@ -33,6 +34,7 @@ pub(crate) enum ServerTransportBuilderEnum {
     #[cfg(all(feature = "websocket", not(target_family = "wasm")))]
     WebSocketServer(WebSocketServerSocketBuilder),
     Channels(Channels),
+    Custom(CustomTransportBuilder),
     Dummy(DummyIo),
 }
 
@@ -45,5 +47,6 @@ pub(crate) enum ServerTransportEnum {
     #[cfg(all(feature = "websocket", not(target_family = "wasm")))]
     WebSocketServer(WebSocketServerSocket),
     Channels(Channels),
+    Custom(CustomTransportSocket),
     Dummy(DummyIo),
 }
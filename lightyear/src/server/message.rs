@@ -1,14 +1,16 @@
 use std::ops::DerefMut;
 
-use crate::prelude::{server::is_started, Message};
+use crate::prelude::{server::is_started, ClientId, Message};
 use crate::protocol::message::{MessageKind, MessageRegistry};
 use crate::serialize::reader::Reader;
 use crate::server::connection::ConnectionManager;
 use crate::server::events::MessageEvent;
+use crate::shared::events::components::RawMessage;
 use crate::shared::replication::network_target::NetworkTarget;
-use crate::shared::sets::{InternalMainSet, ServerMarker};
-use bevy::app::{App, PreUpdate};
-use bevy::prelude::{EventWriter, IntoSystemConfigs, Res, ResMut};
+use crate::shared::sets::{InternalMainSet, NetworkingSchedules, ServerMarker};
+use bevy::app::App;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::{EventReader, EventWriter, IntoSystemConfigs, Res, ResMut};
 use tracing::{error, trace};
 
 /// Read the messages received from the clients and emit the MessageEvent event
@@ -39,15 +41,20 @@ fn read_message<M: Message>(
                         .remote_to_local,
                 ) {
                     Ok(message) => {
+                        // NOTE: this is O(1), it just increments the reference count
+                        let bytes = reader.consume();
                         // rebroadcast
                         if target != NetworkTarget::None {
                             connection.messages_to_rebroadcast.push((
-                                reader.consume(),
+                                bytes.clone(),
                                 target,
                                 channel_kind,
                             ));
                         }
-                        event.send(MessageEvent::new(message, *client_id));
+                        event.send(MessageEvent::new(message, *client_id).with_raw(RawMessage {
+                            bytes,
+                            channel: channel_kind,
+                        }));
                         trace!("Received message: {:?}", std::any::type_name::<M>());
                     }
                     Err(e) => {
@@ -63,11 +70,37 @@ fn read_message<M: Message>(
     }
 }
 
+/// [`SystemParam`] to read the messages of type `M` received from the clients.
+///
+/// This is a typed wrapper around `EventReader<MessageEvent<M>>`:
+/// ```rust,ignore
+/// fn handle_chat(mut receiver: MessageReceiver<ChatMessage>) {
+///     for (client_id, message) in receiver.receive_from() {
+///         // do something with the message
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct MessageReceiver<'w, 's, M: Message> {
+    events: EventReader<'w, 's, MessageEvent<M>>,
+}
+
+impl<'w, 's, M: Message> MessageReceiver<'w, 's, M> {
+    /// Iterate through the messages received since the last time this system ran,
+    /// along with the id of the client that sent them
+    pub fn receive_from(&mut self) -> impl Iterator<Item = (ClientId, &M)> + '_ {
+        self.events
+            .read()
+            .map(|event| (*event.context(), event.message()))
+    }
+}
+
 /// Register a message that can be sent from client to server
 pub(crate) fn add_server_receive_message_from_client<M: Message>(app: &mut App) {
+    let schedules = NetworkingSchedules::of(app);
     app.add_event::<MessageEvent<M>>();
     app.add_systems(
-        PreUpdate,
+        schedules.receive,
         read_message::<M>
             .in_set(InternalMainSet::<ServerMarker>::EmitEvents)
             .run_if(is_started),
@@ -234,4 +267,138 @@ mod tests {
         // verify that the other client received the message
         assert_eq!(stepper.client_app.world().resource::<Counter>().0, 1);
     }
+
+    /// Sending a message to a client that is not connected returns an error
+    #[test]
+    fn server_send_message_to_unknown_client() {
+        use crate::prelude::server::{ConnectionManager, ServerError};
+        use crate::prelude::ClientId;
+        use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+
+        let mut stepper = BevyStepper::default();
+        let mut manager = stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ConnectionManager>();
+        let connected = ClientId::Netcode(TEST_CLIENT_ID);
+        let unknown = ClientId::Netcode(TEST_CLIENT_ID + 1);
+        assert!(manager.is_connected(connected));
+        assert!(!manager.is_connected(unknown));
+        assert!(manager
+            .send_message::<Channel1, StringMessage>(connected, &mut StringMessage("a".to_string()))
+            .is_ok());
+        assert!(matches!(
+            manager.send_message::<Channel1, StringMessage>(
+                unknown,
+                &mut StringMessage("a".to_string())
+            ),
+            Err(ServerError::ClientIdNotFound(client_id)) if client_id == unknown
+        ));
+    }
+
+    #[derive(Resource, Default)]
+    struct Received(Vec<(crate::prelude::ClientId, String)>);
+
+    fn receive_messages(
+        mut received: ResMut<Received>,
+        mut receiver: super::MessageReceiver<StringMessage>,
+    ) {
+        received.0.extend(
+            receiver
+                .receive_from()
+                .map(|(client_id, message)| (client_id, message.0.clone())),
+        );
+    }
+
+    #[test]
+    fn server_message_receiver() {
+        use crate::prelude::ClientId;
+        use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+
+        let mut stepper = BevyStepper::default();
+        stepper.server_app.init_resource::<Received>();
+        stepper.server_app.add_systems(Update, receive_messages);
+
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<crate::prelude::client::ConnectionManager>()
+            .send_message::<Channel1, StringMessage>(&mut StringMessage("a".to_string()))
+            .unwrap();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        assert_eq!(
+            stepper.server_app.world().resource::<Received>().0,
+            vec![(ClientId::Netcode(TEST_CLIENT_ID), "a".to_string())]
+        );
+    }
+
+    fn relay_messages(
+        mut manager: ResMut<crate::prelude::server::ConnectionManager>,
+        mut events: EventReader<crate::prelude::server::MessageEvent<StringMessage>>,
+    ) {
+        for event in events.read() {
+            manager.relay(event, NetworkTarget::All).unwrap();
+        }
+    }
+
+    fn relay_stepper() -> crate::tests::multi_stepper::MultiBevyStepper {
+        let mut stepper = crate::tests::multi_stepper::MultiBevyStepper::default();
+        stepper.client_app_1.init_resource::<Counter>();
+        stepper.client_app_2.init_resource::<Counter>();
+        stepper.client_app_1.add_systems(Update, count_messages);
+        stepper.client_app_2.add_systems(Update, count_messages);
+        stepper
+    }
+
+    /// The server relays the raw message received from a client to the other clients
+    #[test]
+    fn server_relay_message() {
+        let mut stepper = relay_stepper();
+        stepper.server_app.add_systems(Update, relay_messages);
+        stepper
+            .client_app_1
+            .world_mut()
+            .resource_mut::<crate::prelude::client::ConnectionManager>()
+            .send_message::<Channel1, StringMessage>(&mut StringMessage("a".to_string()))
+            .unwrap();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        // the message is not sent back to the sender
+        assert_eq!(stepper.client_app_1.world().resource::<Counter>().0, 0);
+        assert_eq!(stepper.client_app_2.world().resource::<Counter>().0, 1);
+    }
+
+    /// The server relays a typed message on behalf of a client
+    #[test]
+    fn server_relay_typed_message() {
+        use crate::prelude::server::{ConnectionManager, MessageEvent, ServerError};
+        use crate::prelude::ClientId;
+        use crate::tests::multi_stepper::TEST_CLIENT_ID_1;
+
+        let mut stepper = relay_stepper();
+        let mut manager = stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ConnectionManager>();
+        let from = ClientId::Netcode(TEST_CLIENT_ID_1);
+        manager
+            .relay_message::<Channel1, _>(from, &StringMessage("a".to_string()), NetworkTarget::All)
+            .unwrap();
+        // a message that was not received from a client cannot be relayed as-is
+        assert!(matches!(
+            manager.relay(
+                &MessageEvent::new(StringMessage("a".to_string()), from),
+                NetworkTarget::All
+            ),
+            Err(ServerError::MessageNotRelayable)
+        ));
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert_eq!(stepper.client_app_1.world().resource::<Counter>().0, 0);
+        assert_eq!(stepper.client_app_2.world().resource::<Counter>().0, 1);
+    }
 }
@@ -11,6 +11,8 @@ pub mod error;
 
 pub mod events;
 
+pub mod initial_sync;
+
 pub mod input;
 
 pub(crate) mod io;
@@ -20,6 +22,8 @@ pub mod plugin;
 pub(crate) mod message;
 pub(crate) mod prediction;
 
+pub mod rate_limit;
+
 pub mod clients;
 pub(crate) mod networking;
 pub mod relevance;
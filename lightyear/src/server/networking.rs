@@ -1,8 +1,11 @@
 //! Defines the server bevy systems and run conditions
-use crate::connection::server::{IoConfig, NetServer, ServerConnection, ServerConnections};
+use crate::connection::server::{
+    DisconnectReason, IoConfig, NetServer, ServerConnection, ServerConnections,
+};
+use crate::packet::error::PacketError;
 use crate::prelude::{
-    is_host_server, server::is_started, ChannelRegistry, MainSet, MessageRegistry, TickManager,
-    TimeManager,
+    is_host_server, server::is_started, ChannelRegistry, ClientId, MainSet, MessageRegistry,
+    TickManager, TimeManager,
 };
 use crate::protocol::component::ComponentRegistry;
 use crate::serialize::reader::Reader;
@@ -10,11 +13,15 @@ use crate::server::clients::ControlledEntities;
 use crate::server::config::ServerConfig;
 use crate::server::connection::ConnectionManager;
 use crate::server::error::ServerError;
+use crate::server::events::ReconnectEvent;
 use crate::server::io::ServerIoEvent;
-use crate::shared::sets::{InternalMainSet, ServerMarker};
+use crate::shared::sets::{InternalMainSet, NetworkingSchedules, ServerMarker};
+use crate::transport::udp::TransportRebindEvent;
+use crate::transport::PacketSender;
 use async_channel::TryRecvError;
 use bevy::ecs::system::{RunSystemOnce, SystemChangeTick};
 use bevy::prelude::*;
+use bevy::utils::HashSet;
 use tracing::{debug, error, trace};
 
 /// Plugin handling the server networking systems: sending/receiving packets to clients
@@ -29,6 +36,7 @@ pub(crate) struct ServerNetworkingPlugin;
 
 impl Plugin for ServerNetworkingPlugin {
     fn build(&self, app: &mut App) {
+        let schedules = NetworkingSchedules::of(app);
         app
             // REFLECTION
             .register_type::<IoConfig>()
@@ -36,7 +44,7 @@ impl Plugin for ServerNetworkingPlugin {
             .init_state::<NetworkingState>()
             // SYSTEM SETS
             .configure_sets(
-                PreUpdate,
+                schedules.receive,
                 (
                     InternalMainSet::<ServerMarker>::Receive.in_set(MainSet::Receive),
                     InternalMainSet::<ServerMarker>::EmitEvents.in_set(MainSet::EmitEvents),
@@ -45,18 +53,22 @@ impl Plugin for ServerNetworkingPlugin {
                     .run_if(is_started),
             )
             .configure_sets(
-                PostUpdate,
+                schedules.receive,
+                MainSet::Simulation.after(MainSet::EmitEvents),
+            )
+            .configure_sets(
+                schedules.send,
                 InternalMainSet::<ServerMarker>::Send.in_set(MainSet::Send),
             )
             // SYSTEMS //
             .add_systems(
-                PreUpdate,
+                schedules.receive,
                 (receive_packets, receive)
                     .chain()
                     .in_set(InternalMainSet::<ServerMarker>::Receive),
             )
             .add_systems(
-                PostUpdate,
+                schedules.send,
                 (send, send_host_server.run_if(is_host_server))
                     .in_set(InternalMainSet::<ServerMarker>::Send),
             );
@@ -90,6 +102,7 @@ pub(crate) fn receive_packets(
     component_registry: Res<ComponentRegistry>,
     message_registry: Res<MessageRegistry>,
     system_change_tick: SystemChangeTick,
+    mut rebind_events: EventWriter<TransportRebindEvent>,
 ) {
     trace!("Receive client packets");
     let delta = virtual_time.delta();
@@ -117,6 +130,11 @@ pub(crate) fn receive_packets(
                                 error!("Disconnect server because of io error: {:?}", e);
                                 networking_state.set(NetworkingState::Stopped);
                             }
+                            ServerIoEvent::Rebound(local_addr) => {
+                                let event = TransportRebindEvent { local_addr };
+                                rebind_events.send(event);
+                                commands.trigger(event);
+                            }
                             _ => {}
                         }
                     }
@@ -126,16 +144,37 @@ pub(crate) fn receive_packets(
             }
         }
 
+        // the max_clients limit is shared between all the servers
+        let other_clients = netservers
+            .client_server_map
+            .values()
+            .filter(|&&idx| idx != server_idx)
+            .count();
+        netserver.set_max_clients(netservers.max_clients.saturating_sub(other_clients));
         let _ = netserver
             .try_update(delta.as_secs_f64())
             .map_err(|e| error!("Error updating netcode server: {:?}", e));
+        let new_reconnections = netserver.new_reconnections();
         for client_id in netserver.new_connections().iter().copied() {
             netservers.client_server_map.insert(client_id, server_idx);
             // spawn an entity for the client
             let client_entity = commands
                 .spawn((ControlledEntities::default(), Name::new("Client")))
                 .id();
-            connection_manager.add(client_id, client_entity);
+            let connect_payload = netserver
+                .connect_payload(client_id)
+                .map(<[u8]>::to_vec)
+                .unwrap_or_default();
+            let addr = netserver.client_addr(client_id);
+            connection_manager.add(client_id, client_entity, connect_payload, addr);
+            if new_reconnections.contains(&client_id) {
+                connection_manager
+                    .events
+                    .add_reconnect_event(ReconnectEvent {
+                        client_id,
+                        entity: client_entity,
+                    });
+            }
         }
         // handle disconnections
 
@@ -150,9 +189,9 @@ pub(crate) fn receive_packets(
             })
         }
         // disconnects because we received a disconnect message
-        for client_id in netserver.new_disconnections().iter().copied() {
+        for (client_id, reason) in netserver.new_disconnections().iter().copied() {
             if netservers.client_server_map.remove(&client_id).is_some() {
-                connection_manager.remove(client_id);
+                connection_manager.remove(client_id, reason);
                 // NOTE: we don't despawn the entity right away to let the user react to
                 // the disconnect event
                 // TODO: use observers/component_hooks to react automatically on the client despawn?
@@ -173,12 +212,17 @@ pub(crate) fn receive_packets(
     // RECV_PACKETS: buffer packets into message managers
     // enable split borrows on connection manager
     let connection_manager = &mut *connection_manager;
+    let mut protocol_mismatches = HashSet::new();
     for (server_idx, netserver) in netservers.servers.iter_mut().enumerate() {
         while let Some((payload, client_id)) = netserver.recv() {
             // Note: the client_id might not be present in the connection_manager if we receive
             // packets from a client
             // TODO: use connection to apply on BOTH message manager and replication manager
             if let Some(connection) = connection_manager.connections.get_mut(&client_id) {
+                // the client might have migrated to a new address
+                if let Some(addr) = netserver.client_addr(client_id) {
+                    connection.addr = Some(addr);
+                }
                 if let Err(err) = connection.recv_packet(
                     payload,
                     tick_manager.as_ref(),
@@ -186,11 +230,19 @@ pub(crate) fn receive_packets(
                     &mut connection_manager.delta_manager,
                 ) {
                     warn!("Could not receive packet: {err:?}");
+                    // the client is using a protocol that doesn't match ours
+                    if matches!(err, ServerError::Packet(PacketError::ChannelNotFound)) {
+                        protocol_mismatches.insert(client_id);
+                    }
                 }
             } else {
                 // it's still possible to receive some packets from a client that just disconnected.
                 // (multiple packets arrived at the same time from that client)
-                if netserver.new_disconnections().contains(&client_id) {
+                if netserver
+                    .new_disconnections()
+                    .iter()
+                    .any(|(id, _)| *id == client_id)
+                {
                     trace!("received packet from client that just got disconnected. Ignoring.");
                     // we ignore packets from disconnected clients
                     // this is not an error
@@ -201,6 +253,10 @@ pub(crate) fn receive_packets(
             }
         }
     }
+    for client_id in protocol_mismatches {
+        error!("Disconnecting client {client_id:?} because of a protocol mismatch");
+        let _ = netservers.disconnect_with_reason(client_id, DisconnectReason::ProtocolMismatch);
+    }
 }
 
 /// Read from internal buffers and apply the changes to the world
@@ -234,6 +290,24 @@ pub(crate) fn receive(
         .unwrap_or_else(|e| {
             error!("Error during receive: {}", e);
         });
+
+    // disconnect the clients that sent messages well above the rate limits
+    let rate_limited: Vec<ClientId> = world
+        .resource_mut::<ConnectionManager>()
+        .connections
+        .iter_mut()
+        .filter_map(|(client_id, connection)| {
+            std::mem::take(&mut connection.rate_limit_exceeded).then_some(*client_id)
+        })
+        .collect();
+    if !rate_limited.is_empty() {
+        let mut netservers = world.resource_mut::<ServerConnections>();
+        for client_id in rate_limited {
+            error!("Disconnecting client {client_id:?} because it exceeded the rate limits");
+            let _ =
+                netservers.disconnect_with_reason(client_id, DisconnectReason::RateLimitExceeded);
+        }
+    }
 }
 
 // or do additional send stuff here
@@ -262,14 +336,32 @@ pub(crate) fn send(
                 .servers
                 .get_mut(netserver_idx)
                 .ok_or(ServerError::ServerConnectionNotFound)?;
-            for packet_byte in connection.send_packets(&time_manager, &tick_manager)? {
-                netserver.send(packet_byte.as_slice(), *client_id)?;
+            let payloads = connection.send_packets(&time_manager, &tick_manager)?;
+            for packet_byte in payloads.iter() {
+                if let Err(e) = netserver.send(packet_byte.as_slice(), *client_id) {
+                    // recoverable errors (for example when the bandwidth limit is exceeded) can happen
+                    // every frame: skip the remaining packets of this client, but keep sending to the other clients
+                    if e.is_recoverable() {
+                        debug!("Could not send packet: {}", e);
+                        break;
+                    }
+                    return Err(e.into());
+                }
             }
+            connection.recycle_payloads(payloads);
             Ok(())
         })
         .unwrap_or_else(|e: ServerError| {
             error!("Error sending packets: {}", e);
         });
+    // send the packets that were buffered by the io (for example with UDP GSO)
+    for netserver in netservers.servers.iter_mut() {
+        if let Some(io) = netserver.io_mut() {
+            let _ = io
+                .flush()
+                .inspect_err(|e| error!("Error flushing io: {}", e));
+        }
+    }
 }
 
 /// When running in host-server mode, we also need to send messages to the local client.
@@ -329,7 +421,7 @@ fn rebuild_server_connections(world: &mut World) {
     world.insert_resource(connection_manager);
 
     // rebuild the server connections and insert them
-    let server_connections = ServerConnections::new(server_config.net);
+    let server_connections = ServerConnections::new(server_config.net, server_config.max_clients);
     world.insert_resource(server_connections);
 }
 
@@ -373,3 +465,501 @@ impl ServerCommands for Commands<'_, '_> {
         self.insert_resource(NextState::Pending(NetworkingState::Stopped));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::client::config::ClientConfig;
+    use crate::client::events::DisconnectEvent as ClientDisconnectEvent;
+    use crate::client::networking::{ClientCommands, NetworkingState as ClientNetworkingState};
+    use crate::connection::client::DisconnectReason as ClientDisconnectReason;
+    use crate::connection::client::{ClientConnection, ConnectionState, NetClient, NetConfig};
+    use crate::connection::server::ServerConnection;
+    use crate::prelude::client::{self, ClientTransport};
+    use crate::prelude::server::{
+        ConnectEvent, ConnectionManager, DisconnectEvent, DisconnectReason, DuplicateConnectPolicy,
+        ReconnectEvent, ServerCommands, ServerConfig, ServerConnections,
+    };
+    use crate::prelude::server::{Replicate, ServerTransport};
+    use crate::prelude::{ClientId, SharedConfig, TickConfig};
+    use crate::server::io::{ServerIoEvent, ServerNetworkEventSender};
+    use crate::tests::protocol::ComponentSyncModeFull;
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+    use crate::transport::LOCAL_SOCKET;
+    use bevy::prelude::*;
+    use bevy::utils::Duration;
+    use std::net::SocketAddr;
+
+    #[derive(Resource, Default)]
+    struct ServerDisconnects(Vec<DisconnectReason>);
+
+    #[derive(Resource, Default)]
+    struct ClientDisconnects(Vec<Option<DisconnectReason>>);
+
+    #[test]
+    fn test_kick_client_with_reason() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .server_app
+            .init_resource::<ServerDisconnects>()
+            .add_systems(
+                Update,
+                |mut reader: EventReader<DisconnectEvent>, mut res: ResMut<ServerDisconnects>| {
+                    res.0.extend(reader.read().map(|event| event.reason));
+                },
+            );
+        stepper
+            .client_app
+            .init_resource::<ClientDisconnects>()
+            .add_systems(
+                Update,
+                |mut reader: EventReader<ClientDisconnectEvent>,
+                 mut res: ResMut<ClientDisconnects>| {
+                    res.0.extend(reader.read().map(|event| match event.reason {
+                        Some(ClientDisconnectReason::Server(reason)) => Some(reason),
+                        _ => None,
+                    }));
+                },
+            );
+
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConnections>()
+            .kick(ClientId::Netcode(TEST_CLIENT_ID), 5)
+            .unwrap();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+
+        assert_eq!(
+            stepper.server_app.world().resource::<ServerDisconnects>().0,
+            vec![DisconnectReason::ServerKicked(5)]
+        );
+        assert_eq!(
+            stepper.client_app.world().resource::<ClientDisconnects>().0,
+            vec![Some(DisconnectReason::ServerKicked(5))]
+        );
+    }
+
+    #[test]
+    fn test_stop_server_reason() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .client_app
+            .init_resource::<ClientDisconnects>()
+            .add_systems(
+                Update,
+                |mut reader: EventReader<ClientDisconnectEvent>,
+                 mut res: ResMut<ClientDisconnects>| {
+                    res.0.extend(reader.read().map(|event| match event.reason {
+                        Some(ClientDisconnectReason::Server(reason)) => Some(reason),
+                        _ => None,
+                    }));
+                },
+            );
+
+        stepper.server_app.world_mut().commands().stop_server();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+
+        assert_eq!(
+            stepper.client_app.world().resource::<ClientDisconnects>().0,
+            vec![Some(DisconnectReason::ServerStopped)]
+        );
+    }
+
+    /// Number of connected clients seen by the systems that read the disconnect events
+    #[derive(Resource, Default)]
+    struct ClientCountOnDisconnect(Vec<usize>);
+
+    #[test]
+    fn test_connected_clients() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .server_app
+            .init_resource::<ClientCountOnDisconnect>()
+            .add_systems(
+                Update,
+                |mut reader: EventReader<DisconnectEvent>,
+                 manager: Res<ConnectionManager>,
+                 mut res: ResMut<ClientCountOnDisconnect>| {
+                    for _ in reader.read() {
+                        res.0.push(manager.client_count());
+                    }
+                },
+            );
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        let manager = stepper.server_app.world().resource::<ConnectionManager>();
+        assert_eq!(
+            manager.connected_clients().collect::<Vec<_>>(),
+            vec![client_id]
+        );
+        assert_eq!(manager.client_count(), 1);
+
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConnections>()
+            .disconnect(client_id)
+            .unwrap();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        // the disconnection is already reflected in the frame where the event is emitted
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .resource::<ClientCountOnDisconnect>()
+                .0,
+            vec![0]
+        );
+        let manager = stepper.server_app.world().resource::<ConnectionManager>();
+        assert_eq!(manager.connected_clients().count(), 0);
+        assert_eq!(manager.client_count(), 0);
+    }
+
+    #[derive(Resource, Default)]
+    struct ConnectPayloads(Vec<(ClientId, Vec<u8>)>);
+
+    #[test]
+    fn test_connect_payload() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..Default::default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, ClientConfig::default(), frame_duration);
+        if let NetConfig::Netcode { config, .. } = &mut stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ClientConfig>()
+            .net
+        {
+            config.connect_payload = b"player_name".to_vec();
+        }
+        stepper
+            .server_app
+            .init_resource::<ConnectPayloads>()
+            .add_systems(
+                Update,
+                |mut reader: EventReader<ConnectEvent>, mut res: ResMut<ConnectPayloads>| {
+                    res.0.extend(
+                        reader
+                            .read()
+                            .map(|event| (event.client_id, event.connect_payload.clone())),
+                    );
+                },
+            );
+        stepper.init();
+
+        assert_eq!(
+            stepper.server_app.world().resource::<ConnectPayloads>().0,
+            vec![(ClientId::Netcode(TEST_CLIENT_ID), b"player_name".to_vec())]
+        );
+    }
+
+    #[derive(Resource, Default)]
+    struct EventAddrs(Vec<Option<SocketAddr>>);
+
+    /// The address of the client is available in the connect and disconnect events
+    #[test]
+    fn test_connect_disconnect_addr() {
+        let mut stepper = BevyStepper::new(
+            SharedConfig::default(),
+            ClientConfig::default(),
+            Duration::from_millis(10),
+        );
+        stepper
+            .server_app
+            .init_resource::<EventAddrs>()
+            .add_systems(
+                Update,
+                |mut connects: EventReader<ConnectEvent>,
+                 mut disconnects: EventReader<DisconnectEvent>,
+                 mut res: ResMut<EventAddrs>| {
+                    res.0.extend(connects.read().map(|event| event.addr));
+                    res.0.extend(disconnects.read().map(|event| event.addr));
+                },
+            );
+        stepper.init();
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .resource::<ConnectionManager>()
+                .connection(client_id)
+                .unwrap()
+                .addr(),
+            Some(LOCAL_SOCKET)
+        );
+
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConnections>()
+            .kick(client_id, 1)
+            .unwrap();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper.server_app.world().resource::<EventAddrs>().0,
+            vec![Some(LOCAL_SOCKET), Some(LOCAL_SOCKET)]
+        );
+    }
+
+    /// The server migrates the client to its new address after the client switched its transport
+    #[test]
+    fn test_client_transport_migration() {
+        let mut stepper = BevyStepper::new(
+            SharedConfig::default(),
+            ClientConfig::default(),
+            Duration::from_millis(10),
+        );
+        let new_addr = SocketAddr::from(([127, 0, 0, 1], 1234));
+        let (from_server_send, from_server_recv) = crossbeam_channel::unbounded();
+        let (to_server_send, to_server_recv) = crossbeam_channel::unbounded();
+        #[allow(irrefutable_let_patterns)]
+        if let crate::connection::server::NetConfig::Netcode { io, .. } = &mut stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .net[0]
+        {
+            if let ServerTransport::Channels { channels } = &mut io.transport {
+                channels.push((new_addr, to_server_recv, from_server_send));
+            }
+        }
+        stepper.init();
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+
+        // NOTE: the previous transport channels are kept alive by the ClientConfig
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ClientConnection>()
+            .client
+            .io_mut()
+            .unwrap()
+            .set_transport(&client::IoConfig::from_transport(
+                ClientTransport::LocalChannel {
+                    send: to_server_send,
+                    recv: from_server_recv,
+                },
+            ))
+            .unwrap();
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+
+        assert!(matches!(
+            stepper
+                .client_app
+                .world()
+                .resource::<ClientConnection>()
+                .state(),
+            ConnectionState::Connected
+        ));
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .resource::<ConnectionManager>()
+                .connection(client_id)
+                .unwrap()
+                .addr(),
+            Some(new_addr)
+        );
+
+        // the server can still send messages to the client
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((Replicate::default(), ComponentSyncModeFull(1.0)))
+            .id();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .is_some());
+    }
+
+    #[derive(Resource, Default)]
+    struct Reconnects(Vec<ClientId>);
+
+    /// Connect the client again while it is still connected to the server,
+    /// as if the client had restarted without notifying the server.
+    ///
+    /// If `new_addr` is provided, the client connects again from this address.
+    /// Returns the stepper and a receiver of the events that the netcode server sent to the io.
+    fn duplicate_connect(
+        policy: DuplicateConnectPolicy,
+        new_addr: Option<SocketAddr>,
+    ) -> (BevyStepper, async_channel::Receiver<ServerIoEvent>) {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..Default::default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, ClientConfig::default(), frame_duration);
+        let (from_server_send, from_server_recv) = crossbeam_channel::unbounded();
+        let (to_server_send, to_server_recv) = crossbeam_channel::unbounded();
+        for net in stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .net
+            .iter_mut()
+        {
+            #[allow(irrefutable_let_patterns)]
+            if let crate::connection::server::NetConfig::Netcode { config, io } = net {
+                config.duplicate_connect_policy = policy;
+                if let (Some(new_addr), ServerTransport::Channels { channels }) =
+                    (new_addr, &mut io.transport)
+                {
+                    channels.push((new_addr, to_server_recv.clone(), from_server_send.clone()));
+                }
+            }
+        }
+        stepper
+            .server_app
+            .init_resource::<ServerDisconnects>()
+            .init_resource::<Reconnects>()
+            .add_systems(
+                Update,
+                (
+                    |mut reader: EventReader<DisconnectEvent>,
+                     mut res: ResMut<ServerDisconnects>| {
+                        res.0.extend(reader.read().map(|event| event.reason));
+                    },
+                    |mut reader: EventReader<ReconnectEvent>, mut res: ResMut<Reconnects>| {
+                        res.0.extend(reader.read().map(|event| event.client_id));
+                    },
+                ),
+            );
+        stepper.init();
+
+        let (io_event_send, io_event_recv) = async_channel::unbounded();
+        #[allow(irrefutable_let_patterns)]
+        if let ServerConnection::Netcode(server) = &mut stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConnections>()
+            .servers[0]
+        {
+            server.set_io_event_sender(ServerNetworkEventSender(io_event_send));
+        }
+        #[allow(irrefutable_let_patterns)]
+        if let (Some(_), NetConfig::Netcode { io, .. }) = (
+            new_addr,
+            &mut stepper
+                .client_app
+                .world_mut()
+                .resource_mut::<ClientConfig>()
+                .net,
+        ) {
+            io.transport = ClientTransport::LocalChannel {
+                send: to_server_send,
+                recv: from_server_recv,
+            };
+        }
+
+        let mut commands = stepper.client_app.world_mut().commands();
+        commands.connect_client();
+        stepper.client_app.world_mut().flush();
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        (stepper, io_event_recv)
+    }
+
+    #[test]
+    fn test_duplicate_connect_rejected() {
+        let (stepper, _) = duplicate_connect(DuplicateConnectPolicy::Reject, None);
+        // the existing session is kept
+        assert!(stepper
+            .server_app
+            .world()
+            .resource::<ServerDisconnects>()
+            .0
+            .is_empty());
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .resource::<ConnectionManager>()
+                .connected_clients()
+                .collect::<Vec<_>>(),
+            vec![ClientId::Netcode(TEST_CLIENT_ID)]
+        );
+        // the new connection is denied
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<State<ClientNetworkingState>>()
+                .get(),
+            &ClientNetworkingState::Disconnected
+        );
+    }
+
+    #[test]
+    fn test_duplicate_connect_reconnect() {
+        let (stepper, io_events) = duplicate_connect(DuplicateConnectPolicy::Reconnect, None);
+        // the new connection uses the same address, so the io must keep it open
+        assert!(io_events.is_empty());
+        assert_eq!(
+            stepper.server_app.world().resource::<ServerDisconnects>().0,
+            vec![DisconnectReason::Reconnected]
+        );
+        assert_eq!(
+            stepper.server_app.world().resource::<Reconnects>().0,
+            vec![ClientId::Netcode(TEST_CLIENT_ID)]
+        );
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<State<ClientNetworkingState>>()
+                .get(),
+            &ClientNetworkingState::Connected
+        );
+    }
+
+    /// If the client connects again from another address, the io is notified that the
+    /// address of the replaced session is not used anymore
+    #[test]
+    fn test_duplicate_connect_reconnect_new_addr() {
+        let new_addr = SocketAddr::from(([127, 0, 0, 1], 1234));
+        let (stepper, io_events) =
+            duplicate_connect(DuplicateConnectPolicy::Reconnect, Some(new_addr));
+        assert_eq!(
+            stepper.server_app.world().resource::<Reconnects>().0,
+            vec![ClientId::Netcode(TEST_CLIENT_ID)]
+        );
+        assert!(matches!(
+            io_events.try_recv(),
+            Ok(ServerIoEvent::ClientDisconnected(addr)) if addr == LOCAL_SOCKET
+        ));
+        assert!(io_events.is_empty());
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .resource::<ConnectionManager>()
+                .connection(ClientId::Netcode(TEST_CLIENT_ID))
+                .unwrap()
+                .addr(),
+            Some(new_addr)
+        );
+    }
+}
@@ -14,6 +14,7 @@ use bevy::prelude::*;
 
 use crate::server::events::ServerEventsPlugin;
 use crate::server::networking::ServerNetworkingPlugin;
+use crate::server::relevance::distance::DistanceUpdateRatePlugin;
 use crate::server::relevance::immediate::NetworkRelevancePlugin;
 use crate::server::relevance::room::RoomPlugin;
 use crate::server::replication::{
@@ -31,6 +32,7 @@ use super::config::ServerConfig;
 /// - [`ServerNetworkingPlugin`]: Handles the network state (starting/stopping the server, sending/receiving packets)
 /// - [`NetworkRelevancePlugin`]: Handles the network relevance systems. This can be disabled if you don't need fine-grained interest management.
 /// - [`RoomPlugin`]: Handles the room system, which is an addition to the visibility system. This can be disabled if you don't need rooms.
+/// - [`DistanceUpdateRatePlugin`]: Adjusts the rate at which entities are updated based on their distance to each client.
 /// - [`ServerReplicationReceivePlugin`]: Handles the replication of entities and resources from clients to the server. This can be
 ///   disabled if you don't need client to server replication.
 /// - [`ServerReplicationSendPlugin`]: Handles the replication of entities and resources from the server to the client. This can be
@@ -41,10 +43,13 @@ pub struct ServerPlugins {
 
 impl ServerPlugins {
     pub fn new(config: ServerConfig) -> Self {
-        if config.shared.server_replication_send_interval != config.replication.send_interval {
+        let send_interval = config
+            .replication
+            .effective_send_interval(config.shared.tick.tick_duration);
+        if config.shared.server_replication_send_interval != send_interval {
             error!(
-                "The config.shared.server_replication_send_interval {:?} is different from the config.replication.send_interval {:?}. This can cause issues. They should be set to the same value",
-                config.shared.server_replication_send_interval, config.replication.send_interval
+                "The config.shared.server_replication_send_interval {:?} is different from the replication send interval {:?}. This can cause issues. They should be set to the same value",
+                config.shared.server_replication_send_interval, send_interval
             );
         }
         Self { config }
@@ -63,6 +68,7 @@ impl PluginGroup for ServerPlugins {
             .add(ServerNetworkingPlugin)
             .add(NetworkRelevancePlugin)
             .add(RoomPlugin)
+            .add(DistanceUpdateRatePlugin)
             .add(ClientsMetadataPlugin)
             .add(ServerReplicationReceivePlugin { tick_interval })
             .add(ServerReplicationSendPlugin { tick_interval })
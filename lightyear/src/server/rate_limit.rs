@@ -0,0 +1,452 @@
+//! Limit the rate of messages that the server accepts from each client
+//!
+//! A malicious or buggy client could flood the server with messages. With a [`RateLimitConfig`],
+//! the server counts the messages and bytes received from each client on each channel, over windows of
+//! [`RateLimitConfig::window`]:
+//! - the messages received above the [`ChannelRateLimit`] of the channel are dropped
+//! - if the client sends more than [`RateLimitConfig::disconnect_threshold`] times the limit,
+//!   it is disconnected with [`DisconnectReason::RateLimitExceeded`](crate::connection::server::DisconnectReason::RateLimitExceeded)
+//!
+//! The messages of the internal channels of lightyear (replication, pings, inputs, authority, ...) are never dropped,
+//! because the messages are dropped after the packet was acked: a dropped replication message would never be
+//! sent again, and the replication group would stall. The internal channels are only limited if they have a
+//! specific limit in [`RateLimitConfig::channel_limits`], and a client that exceeds it is disconnected.
+//!
+//! The rates of the previous window are available with [`Connection::received_rates`](crate::server::connection::Connection::received_rates)
+//! for monitoring, even if no limit is configured.
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+use tracing::{trace, warn};
+
+use crate::channel::builder::Channel;
+use crate::protocol::channel::ChannelKind;
+
+/// Maximum rate at which a client can send messages on a channel
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChannelRateLimit {
+    pub messages_per_second: f32,
+    pub bytes_per_second: f32,
+}
+
+impl ChannelRateLimit {
+    pub fn new(messages_per_second: f32, bytes_per_second: f32) -> Self {
+        Self {
+            messages_per_second,
+            bytes_per_second,
+        }
+    }
+}
+
+/// Configuration of the rate limits applied to the messages received from each client
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateLimitConfig {
+    /// Limit applied to the channels registered by the user that don't have a specific limit.
+    ///
+    /// If None, only the channels in `channel_limits` are limited.
+    /// This does not apply to the internal channels of lightyear.
+    pub default_limit: Option<ChannelRateLimit>,
+    /// Limits for specific channels
+    pub channel_limits: HashMap<ChannelKind, ChannelRateLimit>,
+    /// A client that sends more than `disconnect_threshold` times the limit of a channel
+    /// during a window is disconnected
+    pub disconnect_threshold: f32,
+    /// Duration of the window over which the rates are measured
+    pub window: Duration,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            default_limit: None,
+            channel_limits: HashMap::default(),
+            disconnect_threshold: 4.0,
+            window: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RateLimitConfig {
+    pub fn with_default_limit(mut self, limit: ChannelRateLimit) -> Self {
+        self.default_limit = Some(limit);
+        self
+    }
+
+    /// Set the limit of the channel `C`
+    pub fn with_channel_limit<C: Channel>(mut self, limit: ChannelRateLimit) -> Self {
+        self.channel_limits.insert(ChannelKind::of::<C>(), limit);
+        self
+    }
+
+    pub fn with_disconnect_threshold(mut self, disconnect_threshold: f32) -> Self {
+        self.disconnect_threshold = disconnect_threshold;
+        self
+    }
+
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    fn limit(&self, channel: &ChannelKind, internal: bool) -> Option<&ChannelRateLimit> {
+        let default_limit = if internal {
+            None
+        } else {
+            self.default_limit.as_ref()
+        };
+        self.channel_limits.get(channel).or(default_limit)
+    }
+}
+
+/// Rates at which a client sent messages on a channel, measured over the previous window
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ChannelRates {
+    /// Messages received per second, including the dropped messages
+    pub messages_per_second: f32,
+    /// Bytes received per second, including the dropped messages
+    pub bytes_per_second: f32,
+    /// Number of messages dropped because they exceeded the limit
+    pub dropped_messages: u32,
+}
+
+/// What to do with a message received from a client
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RateLimitDecision {
+    Accept,
+    Drop,
+    Disconnect,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct ChannelCounter {
+    messages: u32,
+    bytes: usize,
+    dropped: u32,
+}
+
+/// Per-connection tracker of the rates of received messages
+#[derive(Debug, Default)]
+pub(crate) struct RateLimiter {
+    config: Option<RateLimitConfig>,
+    elapsed: Duration,
+    counters: HashMap<ChannelKind, ChannelCounter>,
+    rates: HashMap<ChannelKind, ChannelRates>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(config: Option<RateLimitConfig>) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    fn window(&self) -> Duration {
+        self.config
+            .as_ref()
+            .map_or(Duration::from_secs(1), |config| config.window)
+    }
+
+    /// Advance the current window; the rates are computed at the end of each window
+    pub(crate) fn update(&mut self, delta: Duration) {
+        self.elapsed += delta;
+        let window = self.window();
+        if self.elapsed < window {
+            return;
+        }
+        let secs = self.elapsed.as_secs_f32();
+        // keep the channels in the map, so that they show up in the rates even if no messages
+        // were received during the window
+        self.rates = self
+            .counters
+            .iter_mut()
+            .map(|(channel, counter)| {
+                let counter = std::mem::take(counter);
+                (
+                    *channel,
+                    ChannelRates {
+                        messages_per_second: counter.messages as f32 / secs,
+                        bytes_per_second: counter.bytes as f32 / secs,
+                        dropped_messages: counter.dropped,
+                    },
+                )
+            })
+            .collect();
+        self.elapsed = Duration::default();
+    }
+
+    /// Record a message received on `channel` and decide if it should be processed
+    ///
+    /// The messages of `internal` channels are never dropped, see the [module docs](self)
+    pub(crate) fn check(
+        &mut self,
+        channel: ChannelKind,
+        bytes: usize,
+        internal: bool,
+    ) -> RateLimitDecision {
+        let counter = self.counters.entry(channel).or_default();
+        counter.messages += 1;
+        counter.bytes += bytes;
+        let Some(config) = &self.config else {
+            return RateLimitDecision::Accept;
+        };
+        let Some(limit) = config.limit(&channel, internal) else {
+            return RateLimitDecision::Accept;
+        };
+        let window_secs = config.window.as_secs_f32();
+        let max_messages = limit.messages_per_second * window_secs;
+        let max_bytes = limit.bytes_per_second * window_secs;
+        let messages = counter.messages as f32;
+        let bytes = counter.bytes as f32;
+        if messages > max_messages * config.disconnect_threshold
+            || bytes > max_bytes * config.disconnect_threshold
+        {
+            warn!(?channel, "client exceeded the hard rate limit");
+            return RateLimitDecision::Disconnect;
+        }
+        if !internal && (messages > max_messages || bytes > max_bytes) {
+            trace!(?channel, "dropping message above the rate limit");
+            counter.dropped += 1;
+            return RateLimitDecision::Drop;
+        }
+        RateLimitDecision::Accept
+    }
+
+    /// Rates measured over the previous window
+    pub(crate) fn rates(&self) -> &HashMap<ChannelKind, ChannelRates> {
+        &self.rates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{default, Events};
+
+    use crate::channel::builder::EntityActionsChannel;
+    use crate::connection::server::DisconnectReason;
+    use crate::prelude::server::{ConnectionManager, DisconnectEvent, MessageEvent, ServerConfig};
+    use crate::prelude::{client, ClientId, SharedConfig, TickConfig};
+    use crate::tests::protocol::{Channel1, Channel2, ComponentSyncModeFull, StringMessage};
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter() {
+        let channel = ChannelKind::of::<Channel1>();
+        let other_channel = ChannelKind::of::<Channel2>();
+        let mut limiter = RateLimiter::new(Some(
+            RateLimitConfig::default()
+                .with_channel_limit::<Channel1>(ChannelRateLimit::new(2.0, 100.0))
+                .with_disconnect_threshold(2.0),
+        ));
+        assert_eq!(limiter.check(channel, 10, false), RateLimitDecision::Accept);
+        assert_eq!(limiter.check(channel, 10, false), RateLimitDecision::Accept);
+        // above the limit
+        assert_eq!(limiter.check(channel, 10, false), RateLimitDecision::Drop);
+        assert_eq!(limiter.check(channel, 10, false), RateLimitDecision::Drop);
+        // channels without a limit are not affected
+        assert_eq!(
+            limiter.check(other_channel, 1000, false),
+            RateLimitDecision::Accept
+        );
+        // above the hard limit
+        assert_eq!(
+            limiter.check(channel, 10, false),
+            RateLimitDecision::Disconnect
+        );
+
+        limiter.update(Duration::from_secs(1));
+        assert_eq!(
+            limiter.rates().get(&channel),
+            Some(&ChannelRates {
+                messages_per_second: 5.0,
+                bytes_per_second: 50.0,
+                dropped_messages: 2,
+            })
+        );
+        // the counters are reset for the new window
+        assert_eq!(limiter.check(channel, 120, false), RateLimitDecision::Drop);
+    }
+
+    #[test]
+    fn test_rate_limiter_internal_channel() {
+        let internal_channel = ChannelKind::of::<EntityActionsChannel>();
+        let limit = ChannelRateLimit::new(1.0, 100.0);
+        // the default limit does not apply to the internal channels
+        let mut limiter =
+            RateLimiter::new(Some(RateLimitConfig::default().with_default_limit(limit)));
+        for _ in 0..10 {
+            assert_eq!(
+                limiter.check(internal_channel, 10, true),
+                RateLimitDecision::Accept
+            );
+        }
+
+        // a specific limit never drops the messages of an internal channel, but can disconnect the client
+        let mut limiter = RateLimiter::new(Some(
+            RateLimitConfig::default()
+                .with_channel_limit::<EntityActionsChannel>(limit)
+                .with_disconnect_threshold(2.0),
+        ));
+        assert_eq!(
+            limiter.check(internal_channel, 10, true),
+            RateLimitDecision::Accept
+        );
+        assert_eq!(
+            limiter.check(internal_channel, 10, true),
+            RateLimitDecision::Accept
+        );
+        assert_eq!(
+            limiter.check(internal_channel, 10, true),
+            RateLimitDecision::Disconnect
+        );
+    }
+
+    /// A client that sends many replication actions above the default limit keeps replicating its entities
+    #[test]
+    fn test_rate_limit_replication_group_progresses() {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, default(), tick_duration);
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .packet
+            .rate_limit = Some(
+            RateLimitConfig::default().with_default_limit(ChannelRateLimit::new(1.0, 10000.0)),
+        );
+        stepper.init();
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+
+        let client_entity = stepper
+            .client_app
+            .world_mut()
+            .spawn(client::Replicate::default())
+            .id();
+        // flood the replication group with actions, well above the default limit
+        for i in 0..20 {
+            let mut entity_mut = stepper.client_app.world_mut().entity_mut(client_entity);
+            if i % 2 == 0 {
+                entity_mut.insert(ComponentSyncModeFull(i as f32));
+            } else {
+                entity_mut.remove::<ComponentSyncModeFull>();
+            }
+            stepper.frame_step();
+        }
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(client_entity)
+            .insert(ComponentSyncModeFull(100.0));
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+
+        let connection = stepper
+            .server_app
+            .world()
+            .resource::<ConnectionManager>()
+            .connection(client_id)
+            .expect("the client should still be connected");
+        let server_entity = connection
+            .replication_receiver
+            .remote_entity_map
+            .get_local(client_entity)
+            .expect("entity was not replicated to the server");
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .get::<ComponentSyncModeFull>(server_entity),
+            Some(&ComponentSyncModeFull(100.0))
+        );
+    }
+
+    fn send_messages(stepper: &mut BevyStepper, count: usize) {
+        let mut manager = stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<client::ConnectionManager>();
+        for _ in 0..count {
+            manager
+                .send_message::<Channel1, StringMessage>(&mut StringMessage("a".to_string()))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_rate_limit_client() {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, default(), tick_duration);
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .packet
+            .rate_limit = Some(
+            RateLimitConfig::default()
+                .with_channel_limit::<Channel1>(ChannelRateLimit::new(5.0, 10000.0)),
+        );
+        stepper.init();
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+
+        // the messages above the limit are dropped
+        send_messages(&mut stepper, 8);
+        stepper.frame_step();
+        stepper.frame_step();
+        let received = stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<Events<MessageEvent<StringMessage>>>()
+            .drain()
+            .count();
+        assert_eq!(received, 5);
+
+        // the rates are available at the end of the window
+        let mut rates = None;
+        for _ in 0..150 {
+            stepper.frame_step();
+            rates = stepper
+                .server_app
+                .world()
+                .resource::<ConnectionManager>()
+                .connection(client_id)
+                .unwrap()
+                .received_rates()
+                .get(&ChannelKind::of::<Channel1>())
+                .copied();
+            if rates.is_some() {
+                break;
+            }
+        }
+        assert_eq!(rates.unwrap().dropped_messages, 3);
+
+        // the client is disconnected above the hard limit
+        send_messages(&mut stepper, 30);
+        let mut disconnect = None;
+        for _ in 0..10 {
+            stepper.frame_step();
+            disconnect = stepper
+                .server_app
+                .world_mut()
+                .resource_mut::<Events<DisconnectEvent>>()
+                .drain()
+                .next();
+            if disconnect.is_some() {
+                break;
+            }
+        }
+        let disconnect = disconnect.unwrap();
+        assert_eq!(disconnect.client_id, client_id);
+        assert_eq!(disconnect.reason, DisconnectReason::RateLimitExceeded);
+    }
+}
@@ -0,0 +1,296 @@
+/*! Update entities at a rate that depends on their distance to each client
+
+Network relevance decides *if* an entity is replicated to a client. [`DistanceUpdateRate`] refines this by
+deciding *how often* the updates of a relevant entity are sent: entities close to the [`ReplicationFocus`] of a client
+can be updated on every send, while entities that are far away are only updated every few sends.
+This saves bandwidth without the pop-in caused by a hard relevance cutoff.
+
+The distance is computed between the [`Transform`] of the replicated entity and the [`ReplicationFocus`] of the client,
+which must be inserted on the client entity (the `entity` of the [`ConnectEvent`](crate::server::events::ConnectEvent)).
+Entities without a [`DistanceUpdateRate`], and clients without a [`ReplicationFocus`], are updated on every send.
+
+```rust
+use bevy::prelude::*;
+use lightyear::prelude::server::*;
+
+fn spawn_entity(mut commands: Commands) {
+    commands.spawn((
+        Replicate::default(),
+        Transform::default(),
+        // update on every send within 10 units, every 2 sends within 50 units, and every 8 sends further away
+        DistanceUpdateRate::new(8).with_tier(10.0, 1).with_tier(50.0, 2),
+    ));
+}
+
+fn add_focus(mut commands: Commands, mut events: EventReader<ConnectEvent>) {
+    for event in events.read() {
+        commands.entity(event.entity).insert(ReplicationFocus::new(Vec3::ZERO));
+    }
+}
+```
+
+The rate is applied per [`ReplicationGroup`]: a group is updated at the rate of its closest entity.
+When an entity moves to a slower tier, its update interval grows by one send at a time, so that its updates
+fade out progressively instead of freezing abruptly. Moving to a faster tier takes effect immediately.
+*/
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::prelude::server::is_started;
+use crate::prelude::ReplicationGroup;
+use crate::server::connection::ConnectionManager;
+use crate::shared::replication::components::ReplicationGroupId;
+use crate::shared::sets::{InternalReplicationSet, NetworkingSchedules, ServerMarker};
+
+/// Maps the distance between an entity and the [`ReplicationFocus`] of a client to the number of sends
+/// between two updates of the entity
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct DistanceUpdateRate {
+    /// `(max_distance, interval)` pairs, sorted by increasing distance
+    tiers: Vec<(f32, u32)>,
+    /// Interval used for the entities that are further than all the tiers
+    pub far_interval: u32,
+}
+
+impl DistanceUpdateRate {
+    pub fn new(far_interval: u32) -> Self {
+        Self {
+            tiers: Vec::new(),
+            far_interval,
+        }
+    }
+
+    /// Entities closer than `max_distance` (and not in a closer tier) are updated every `interval` sends
+    pub fn with_tier(mut self, max_distance: f32, interval: u32) -> Self {
+        self.tiers.push((max_distance, interval));
+        self.tiers.sort_by(|a, b| a.0.total_cmp(&b.0));
+        self
+    }
+
+    /// Number of sends between two updates of an entity at this distance
+    pub fn interval(&self, distance: f32) -> u32 {
+        self.tiers
+            .iter()
+            .find(|(max_distance, _)| distance <= *max_distance)
+            .map_or(self.far_interval, |(_, interval)| *interval)
+            .max(1)
+    }
+}
+
+/// Position from which a client observes the world, used to compute the [`DistanceUpdateRate`] of the entities
+///
+/// Must be inserted on the client entity.
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct ReplicationFocus {
+    pub position: Vec3,
+    /// If set, replaces the [`DistanceUpdateRate`] of the entities for this client
+    pub update_rate: Option<DistanceUpdateRate>,
+}
+
+impl ReplicationFocus {
+    pub fn new(position: Vec3) -> Self {
+        Self {
+            position,
+            update_rate: None,
+        }
+    }
+
+    pub fn with_update_rate(mut self, update_rate: DistanceUpdateRate) -> Self {
+        self.update_rate = Some(update_rate);
+        self
+    }
+}
+
+/// Plugin that updates the rate at which groups are replicated to each client based on [`DistanceUpdateRate`]
+#[derive(Default)]
+pub(crate) struct DistanceUpdateRatePlugin;
+
+impl Plugin for DistanceUpdateRatePlugin {
+    fn build(&self, app: &mut App) {
+        let schedules = NetworkingSchedules::of(app);
+        app.add_systems(
+            schedules.send,
+            systems::update_intervals
+                .run_if(is_started)
+                .in_set(InternalReplicationSet::<ServerMarker>::BeforeBuffer),
+        );
+    }
+}
+
+pub(super) mod systems {
+    use super::*;
+
+    pub(super) fn update_intervals(
+        mut connection_manager: ResMut<ConnectionManager>,
+        focuses: Query<&ReplicationFocus>,
+        entities: Query<(Entity, &Transform, &ReplicationGroup, &DistanceUpdateRate)>,
+        groups: Query<&ReplicationGroup>,
+        mut removed: RemovedComponents<DistanceUpdateRate>,
+        mut intervals: Local<HashMap<ReplicationGroupId, u32>>,
+    ) {
+        // entities that stopped using a distance-based rate are updated on every send again
+        let removed_groups: Vec<ReplicationGroupId> = removed
+            .read()
+            .filter_map(|entity| groups.get(entity).ok().map(|g| g.group_id(Some(entity))))
+            .collect();
+        for connection in connection_manager.connections.values_mut() {
+            let focus = focuses.get(connection.entity()).ok();
+            for (entity, transform, group, rate) in entities.iter() {
+                let interval = focus.map_or(1, |focus| {
+                    focus
+                        .update_rate
+                        .as_ref()
+                        .unwrap_or(rate)
+                        .interval(transform.translation.distance(focus.position))
+                });
+                // the group is updated at the rate of its closest entity
+                intervals
+                    .entry(group.group_id(Some(entity)))
+                    .and_modify(|group_interval| *group_interval = interval.min(*group_interval))
+                    .or_insert(interval);
+            }
+            for group_id in removed_groups.iter() {
+                intervals.entry(*group_id).or_insert(1);
+            }
+            for (group_id, interval) in intervals.drain() {
+                connection
+                    .replication_sender
+                    .set_update_interval(group_id, interval);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::server::Replicate;
+    use crate::prelude::{client, ClientId};
+    use crate::tests::protocol::ComponentSyncModeFull;
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+
+    use super::*;
+
+    #[test]
+    fn test_interval() {
+        let rate = DistanceUpdateRate::new(8)
+            .with_tier(50.0, 2)
+            .with_tier(10.0, 1);
+        assert_eq!(rate.interval(0.0), 1);
+        assert_eq!(rate.interval(10.0), 1);
+        assert_eq!(rate.interval(20.0), 2);
+        assert_eq!(rate.interval(100.0), 8);
+        assert_eq!(DistanceUpdateRate::new(0).interval(100.0), 1);
+    }
+
+    #[test]
+    fn test_distance_update_rate() {
+        let mut stepper = BevyStepper::default();
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        let client_entity = stepper
+            .server_app
+            .world()
+            .resource::<ConnectionManager>()
+            .client_entity(client_id)
+            .unwrap();
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(client_entity)
+            .insert(ReplicationFocus::new(Vec3::ZERO));
+        let rate = DistanceUpdateRate::new(4).with_tier(10.0, 1);
+        let near = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                Replicate::default(),
+                Transform::default(),
+                rate.clone(),
+                ComponentSyncModeFull(0.0),
+            ))
+            .id();
+        let far = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                Replicate::default(),
+                Transform::from_xyz(100.0, 0.0, 0.0),
+                rate,
+                ComponentSyncModeFull(0.0),
+            ))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let update_interval = |stepper: &BevyStepper, entity: Entity| {
+            stepper
+                .server_app
+                .world()
+                .resource::<ConnectionManager>()
+                .connection(client_id)
+                .unwrap()
+                .replication_sender
+                .group_channels
+                .get(&ReplicationGroupId(entity.to_bits()))
+                .unwrap()
+                .update_interval
+        };
+        let client_value = |stepper: &BevyStepper, entity: Entity| {
+            let client_entity = stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(entity)
+                .unwrap();
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(client_entity)
+                .unwrap()
+                .0
+        };
+
+        let mut near_updates = 0;
+        let mut far_updates = 0;
+        let mut far_intervals = vec![];
+        let mut last = (0.0, 0.0);
+        for i in 1..=30 {
+            for entity in [near, far] {
+                stepper
+                    .server_app
+                    .world_mut()
+                    .get_mut::<ComponentSyncModeFull>(entity)
+                    .unwrap()
+                    .0 = i as f32;
+            }
+            stepper.frame_step();
+            let values = (client_value(&stepper, near), client_value(&stepper, far));
+            if values.0 != last.0 {
+                near_updates += 1;
+            }
+            if values.1 != last.1 {
+                far_updates += 1;
+            }
+            last = values;
+            far_intervals.push(update_interval(&stepper, far));
+        }
+        assert_eq!(update_interval(&stepper, near), 1);
+        assert!(near_updates >= 28);
+        assert!(far_updates < near_updates / 2);
+        // the interval of the far entity grows progressively
+        assert_eq!(far_intervals[0], 2);
+        assert!(far_intervals.contains(&3));
+        assert_eq!(*far_intervals.last().unwrap(), 4);
+
+        // moving closer applies the faster rate immediately
+        stepper
+            .server_app
+            .world_mut()
+            .get_mut::<Transform>(far)
+            .unwrap()
+            .translation = Vec3::ZERO;
+        stepper.frame_step();
+        assert_eq!(update_interval(&stepper, far), 1);
+    }
+}
@@ -24,7 +24,7 @@ fn my_system(
 ```
 */
 use crate::prelude::{server::is_started, ClientId};
-use crate::shared::sets::{InternalReplicationSet, ServerMarker};
+use crate::shared::sets::{InternalReplicationSet, NetworkingSchedules, ServerMarker};
 use bevy::ecs::entity::EntityHashSet;
 use bevy::prelude::*;
 use bevy::utils::HashMap;
@@ -228,11 +228,12 @@ pub(crate) struct NetworkRelevancePlugin;
 
 impl Plugin for NetworkRelevancePlugin {
     fn build(&self, app: &mut App) {
+        let schedules = NetworkingSchedules::of(app);
         // RESOURCES
         app.init_resource::<RelevanceManager>();
         // SETS
         app.configure_sets(
-            PostUpdate,
+            schedules.send,
             (
                 (
                     // update replication caches must happen before replication, but after we add CachedNetworkRelevance
@@ -258,7 +259,7 @@ impl Plugin for NetworkRelevancePlugin {
         //     systems::handle_client_disconnect.after(InternalMainSet::<ServerMarker>::EmitEvents),
         // );
         app.add_systems(
-            PostUpdate,
+            schedules.send,
             (
                 systems::add_cached_network_relevance
                     .in_set(InternalReplicationSet::<ServerMarker>::BeforeBuffer),
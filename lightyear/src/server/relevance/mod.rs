@@ -1,4 +1,5 @@
 pub mod immediate;
 
+pub mod distance;
 pub mod error;
 pub mod room;
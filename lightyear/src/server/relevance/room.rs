@@ -50,7 +50,7 @@ use crate::connection::id::ClientId;
 use crate::prelude::server::is_started;
 
 use crate::server::relevance::immediate::{NetworkRelevanceSet, RelevanceManager};
-use crate::shared::sets::{InternalReplicationSet, ServerMarker};
+use crate::shared::sets::{InternalReplicationSet, NetworkingSchedules, ServerMarker};
 
 type EntityHashMap<K, V> = hashbrown::HashMap<K, V, EntityHash>;
 type EntityHashSet<K> = hashbrown::HashSet<K, EntityHash>;
@@ -137,11 +137,12 @@ pub enum RoomSystemSets {
 
 impl Plugin for RoomPlugin {
     fn build(&self, app: &mut App) {
+        let schedules = NetworkingSchedules::of(app);
         // RESOURCES
         app.init_resource::<RoomManager>();
         // SETS
         app.configure_sets(
-            PostUpdate,
+            schedules.send,
             (
                 (
                     // the room events must be processed before the relevance events
@@ -157,7 +158,7 @@ impl Plugin for RoomPlugin {
         );
         // SYSTEMS
         app.add_systems(
-            PostUpdate,
+            schedules.send,
             (
                 systems::buffer_room_relevance_events
                     .in_set(RoomSystemSets::UpdateReplicationCaches),
@@ -25,6 +25,10 @@ pub type ReplicationSet = InternalReplicationSet<ServerMarker>;
 
 pub(crate) mod receive {
     use super::*;
+    use crate::prelude::server::MessageEvent;
+    use crate::prelude::ComponentRegistry;
+    use crate::shared::replication::applied::{ComponentApplied, ComponentAppliedEvent};
+    use crate::shared::sets::NetworkingSchedules;
 
     #[derive(Default)]
     pub struct ServerReplicationReceivePlugin {
@@ -33,6 +37,7 @@ pub(crate) mod receive {
 
     impl Plugin for ServerReplicationReceivePlugin {
         fn build(&self, app: &mut App) {
+            let schedules = NetworkingSchedules::of(app);
             app
                 // PLUGIN
                 .add_plugins(ReplicationReceivePlugin::<ConnectionManager>::new(
@@ -40,17 +45,48 @@ pub(crate) mod receive {
                 ))
                 // SETS
                 .configure_sets(
-                    PreUpdate,
+                    schedules.receive,
                     ServerReplicationSet::ClientReplication
                         .run_if(is_started)
                         .after(InternalMainSet::<ServerMarker>::EmitEvents),
+                )
+                // EVENTS
+                .add_event::<ComponentAppliedEvent>()
+                // SYSTEMS
+                .add_systems(
+                    schedules.receive,
+                    handle_component_applied.after(InternalMainSet::<ServerMarker>::EmitEvents),
                 );
         }
     }
+
+    /// Convert the application acks sent by the clients into [`ComponentAppliedEvent`]s
+    fn handle_component_applied(
+        component_registry: Res<ComponentRegistry>,
+        mut messages: ResMut<Events<MessageEvent<ComponentApplied>>>,
+        mut events: EventWriter<ComponentAppliedEvent>,
+    ) {
+        for message in messages.drain() {
+            let Some(component) = component_registry
+                .kind_map
+                .kind(message.message.component)
+                .copied()
+            else {
+                continue;
+            };
+            events.send(ComponentAppliedEvent {
+                client_id: message.context,
+                entity: message.message.entity,
+                component,
+                tick: message.message.tick,
+            });
+        }
+    }
 }
 
 pub(crate) mod send {
     use super::*;
+    use crate::channel::builder::InitialSyncChannel;
     use crate::prelude::{
         is_host_server, ClientId, ComponentRegistry, DisabledComponent, NetworkRelevanceMode,
         OverrideTargetComponent, ReplicateHierarchy, Replicated, ReplicationGroup,
@@ -58,18 +94,20 @@ pub(crate) mod send {
     };
     use crate::protocol::component::ComponentKind;
     use crate::server::error::ServerError;
+    use crate::server::initial_sync::{InitialSync, InitialSyncComplete};
     use crate::server::prediction::handle_pre_predicted;
+    use crate::server::relevance::distance::ReplicationFocus;
     use crate::server::relevance::immediate::{CachedNetworkRelevance, ClientRelevance};
     use crate::shared::replication::archetypes::{
         get_erased_component, ServerReplicatedArchetypes,
     };
     use crate::shared::replication::authority::{AuthorityPeer, HasAuthority};
     use crate::shared::replication::components::{
-        Cached, Controlled, Replicating, ReplicationGroupId, ReplicationTarget,
-        ShouldBeInterpolated,
+        Cached, ComponentClientFilterFn, Controlled, DontReplicate, Replicating,
+        ReplicationGroupId, ReplicationTarget, ShouldBeInterpolated,
     };
     use crate::shared::replication::network_target::NetworkTarget;
-    use crate::shared::replication::ReplicationSend;
+    use crate::shared::sets::NetworkingSchedules;
     use bevy::ecs::component::ComponentTicks;
     use bevy::ecs::system::SystemChangeTick;
     use bevy::ptr::Ptr;
@@ -81,11 +119,8 @@ pub(crate) mod send {
 
     impl Plugin for ServerReplicationSendPlugin {
         fn build(&self, app: &mut App) {
-            let send_interval = app
-                .world()
-                .resource::<ServerConfig>()
-                .replication
-                .send_interval;
+            let schedules = NetworkingSchedules::of(app);
+            let replication_config = app.world().resource::<ServerConfig>().replication;
 
             app
                 // REFLECTION
@@ -93,28 +128,29 @@ pub(crate) mod send {
                 // PLUGIN
                 .add_plugins(ReplicationSendPlugin::<ConnectionManager>::new(
                     self.tick_interval,
-                    send_interval,
+                    replication_config.send_interval,
+                    replication_config.send_interval_ticks,
                 ))
                 // SYSTEM SETS
                 .configure_sets(
-                    PostUpdate,
+                    schedules.send,
                     // on server: we need to set the hash value before replicating the component
                     InternalReplicationSet::<ServerMarker>::SetPreSpawnedHash
                         .before(InternalReplicationSet::<ServerMarker>::BufferComponentUpdates)
                         .in_set(InternalReplicationSet::<ServerMarker>::All),
                 )
                 .configure_sets(
-                    PostUpdate,
+                    schedules.send,
                     InternalReplicationSet::<ServerMarker>::All.run_if(is_started),
                 )
                 // SYSTEMS
                 .add_systems(
-                    PostUpdate,
+                    schedules.send,
                     compute_hash.in_set(InternalReplicationSet::<ServerMarker>::SetPreSpawnedHash),
                 );
             // SYSTEMS
             app.add_systems(
-                PostUpdate,
+                schedules.send,
                 (
                     // TODO: putting it here means we might miss entities that are spawned and despawned within the send_interval? bug or feature?
                     //  be careful that newly_connected_client is cleared every send_interval, not every frame.
@@ -130,7 +166,7 @@ pub(crate) mod send {
             );
             // HOST-SERVER
             app.add_systems(
-                PostUpdate,
+                schedules.send,
                 add_prediction_interpolation_components
                     // .after(InternalMainSet::<ServerMarker>::SendMessages)
                     .run_if(is_host_server),
@@ -257,6 +293,39 @@ pub(crate) mod send {
         //  should be sent with the same frequency!
         // clear the list of newly connected clients
         connection_manager.new_clients.clear();
+        let finished_syncs: Vec<_> = connection_manager
+            .initial_syncs
+            .iter()
+            .filter(|(_, sync)| sync.is_finished())
+            .map(|(client_id, _)| *client_id)
+            .collect();
+        for client_id in finished_syncs {
+            let sync = connection_manager.initial_syncs.remove(&client_id).unwrap();
+            let Ok(connection) = connection_manager.connection(client_id) else {
+                continue;
+            };
+            // groups that were despawned before their spawn was sent are not part of the sync anymore
+            let groups = sync
+                .groups()
+                .iter()
+                .filter(|group_id| {
+                    connection
+                        .replication_sender
+                        .group_channels
+                        .contains_key(*group_id)
+                })
+                .copied()
+                .collect();
+            if let Err(e) = connection_manager.send_message::<InitialSyncChannel, _>(
+                client_id,
+                &mut InitialSyncComplete { groups },
+            ) {
+                error!(
+                    ?client_id,
+                    "could not send the end of the initial sync: {e}"
+                );
+            }
+        }
     }
 
     /// In HostServer mode, we will add the Predicted/Interpolated components to the server entities
@@ -386,6 +455,15 @@ pub(crate) mod send {
         let mut sender = std::mem::take(&mut *set.p1());
         let world = set.p0();
 
+        // spread the replication of the world to the new clients over multiple sends
+        if let Some(max_entities) = sender.replication_config.initial_sync_entities_per_tick {
+            start_initial_syncs(world, &replicated_archetypes, &mut sender);
+            sender
+                .initial_syncs
+                .values_mut()
+                .for_each(|sync| sync.next_batch(max_entities));
+        }
+
         // 2. go through all the archetypes that should be replicated
         for replicated_archetype in replicated_archetypes.archetypes.iter() {
             // SAFETY: update() makes sure that we have a valid archetype
@@ -498,6 +576,13 @@ pub(crate) mod send {
                             // the OverrideTarget<C> component has the same memory layout as NetworkTarget
                             .map(|ptr| unsafe { ptr.deref::<NetworkTarget>() })
                     });
+                    let client_filter = replicated_component.client_filter.and_then(|id| {
+                        entity_ref
+                            .get_by_id(id)
+                            // SAFETY: we know the archetype has the ComponentClientFilter<C> component
+                            // the ComponentClientFilter<C> component has the same memory layout as ComponentClientFilterFn
+                            .map(|ptr| unsafe { ptr.deref::<ComponentClientFilterFn>() })
+                    });
 
                     replicate_component_updates(
                         tick_manager.tick(),
@@ -514,6 +599,7 @@ pub(crate) mod send {
                         replicated_component.delta_compression,
                         replicated_component.replicate_once,
                         override_target,
+                        client_filter,
                         &system_ticks,
                         &mut sender,
                     );
@@ -526,6 +612,51 @@ pub(crate) mod send {
         *set.p1() = sender;
     }
 
+    /// Start an [`InitialSync`] for each newly connected client, with all the entities that are replicated to it
+    fn start_initial_syncs(
+        world: &World,
+        replicated_archetypes: &ServerReplicatedArchetypes,
+        sender: &mut ConnectionManager,
+    ) {
+        for client_id in sender.new_clients.clone() {
+            let focus = sender
+                .connection(client_id)
+                .ok()
+                .and_then(|connection| world.get::<ReplicationFocus>(connection.entity()))
+                .map(|focus| focus.position);
+            let entities = replicated_archetypes
+                .archetypes
+                .iter()
+                .filter_map(|replicated_archetype| world.archetypes().get(replicated_archetype.id))
+                .flat_map(|archetype| archetype.entities())
+                .filter_map(|entity| {
+                    let entity_ref = world.entity(entity.id());
+                    let replication_target = entity_ref.get::<ReplicationTarget>()?;
+                    if !replication_target.target.targets(&client_id)
+                        || entity_ref
+                            .get::<Replicated>()
+                            .is_some_and(|r| r.from == Some(client_id))
+                        || entity_ref
+                            .get::<AuthorityPeer>()
+                            .is_some_and(|a| *a == AuthorityPeer::Client(client_id))
+                    {
+                        return None;
+                    }
+                    let group = entity_ref.get::<ReplicationGroup>();
+                    let group_id = group.map_or(ReplicationGroupId::default(), |g| {
+                        g.group_id(Some(entity.id()))
+                    });
+                    let distance = focus
+                        .zip(entity_ref.get::<Transform>())
+                        .map(|(focus, transform)| transform.translation.distance(focus));
+                    Some((group_id, group.map_or(1.0, |g| g.priority()), distance))
+                });
+            let sync = InitialSync::new(entities);
+            debug!(?client_id, percent = ?sync.percent(), "Starting initial sync");
+            sender.initial_syncs.insert(client_id, sync);
+        }
+    }
+
     /// Send entity spawn replication messages to clients
     /// Also handles:
     /// - newly_connected_clients should receive the entity spawn message even if the entity was not just spawned
@@ -567,8 +698,16 @@ pub(crate) mod send {
                                 }
                                 ClientRelevance::Lost => {}
                                 ClientRelevance::Maintained => {
-                                    // only try to replicate if the replicate component was just added
-                                    if replication_target.is_added() {
+                                    // only try to replicate if the replicate component was just added,
+                                    // if the client is being resynced, or if the client was just added
+                                    // to the replication target
+                                    if replication_target.is_added()
+                                        || sender.is_initial_sync_target(*client_id, group_id)
+                                        || (replication_target.is_changed()
+                                            && !cached_replication_target.is_some_and(|cached| {
+                                                cached.value.target.targets(client_id)
+                                            }))
+                                    {
                                         trace!(
                                             ?entity,
                                             ?client_id,
@@ -601,7 +740,7 @@ pub(crate) mod send {
                 }
 
                 // also replicate to the newly connected clients that match the target
-                let new_connected_clients = sender.new_connected_clients();
+                let new_connected_clients = sender.initial_sync_targets(group_id);
                 if !new_connected_clients.is_empty() {
                     // replicate to the newly connected clients that match our target
                     let mut new_connected_target = NetworkTarget::Only(new_connected_clients);
@@ -620,6 +759,8 @@ pub(crate) mod send {
         if let Some(client_id) = replicated.and_then(|r| r.from) {
             target.exclude(&NetworkTarget::Single(client_id));
         };
+        // the spawn will be sent with the initial sync of the client
+        sender.exclude_initial_sync_pending(group_id, &mut target);
         if target.is_empty() {
             return;
         }
@@ -629,6 +770,16 @@ pub(crate) mod send {
         let _ = sender
             .connected_targets(target)
             .try_for_each(|client_id| {
+                let remote_entity_map = &mut sender
+                    .connection_mut(client_id)?
+                    .replication_receiver
+                    .remote_entity_map;
+                // the client already received the entity from the server that exported the replication state
+                if remote_entity_map.is_aliased(entity) {
+                    return Ok(());
+                }
+                // the entity might be known to the client as one of the entities of the exporting server
+                let network_entity = remote_entity_map.reserve_network_entity(entity);
                 // TODO: we don't want to convert because this is a spawn! we need to provide the local entity
                 //  so that the receiver can do the mapping
                 // // convert the entity to a network entity
@@ -673,12 +824,12 @@ pub(crate) mod send {
                     sender
                         .connection_mut(client_id)?
                         .replication_sender
-                        .prepare_entity_spawn_reuse(entity, group_id, *remote_entity);
+                        .prepare_entity_spawn_reuse(network_entity, group_id, *remote_entity);
                 } else {
                     sender
                         .connection_mut(client_id)?
                         .replication_sender
-                        .prepare_entity_spawn(entity, group_id);
+                        .prepare_entity_spawn(network_entity, group_id);
                 }
 
                 // also set the priority for the group when we spawn it
@@ -704,13 +855,14 @@ pub(crate) mod send {
                 &ReplicationTarget,
                 Option<&CachedNetworkRelevance>,
             ),
-            With<Replicating>,
+            (With<Replicating>, Without<DontReplicate>),
         >,
         // TODO: should we use Option<ResMut> so that this observer doesn't trigger
         //  when we are not connected?
         mut sender: ResMut<ConnectionManager>,
     ) {
         let entity = trigger.entity();
+        sender.hidden_components.remove(&entity);
         if let Ok((replication_group, network_target, cached_relevance)) = query.get(entity) {
             trace!(?entity, "Replicate entity despawn");
             // only send the despawn to clients who were in the target of the entity
@@ -744,37 +896,50 @@ pub(crate) mod send {
         visibility: Option<&CachedNetworkRelevance>,
         sender: &mut ConnectionManager,
     ) {
-        // 1. send despawn for clients that lost visibility
+        // clients that were targeted before the replication target changed, but not anymore
+        let removed_from_target = |client_id: &ClientId| {
+            replication_target.is_changed()
+                && !replication_target.is_added()
+                && !replication_target.target.targets(client_id)
+                && cached_replication_target
+                    .is_some_and(|cached| cached.value.target.targets(client_id))
+        };
         let mut target: NetworkTarget = match visibility {
-            Some(visibility) => {
-                visibility
-                    .clients_cache
-                    .iter()
-                    .filter_map(|(client_id, visibility)| {
-                        if replication_target.target.targets(client_id)
-                            && matches!(visibility, ClientRelevance::Lost) {
-                            debug!(
-                                "sending entity despawn for entity: {:?} because ClientVisibility::Lost",
-                                entity
-                            );
-                            return Some(*client_id);
+            // 1. send despawn for clients that lost visibility, or that had visibility but were
+            // removed from the replication target
+            Some(visibility) => visibility
+                .clients_cache
+                .iter()
+                .filter_map(|(client_id, visibility)| {
+                    let despawn = match visibility {
+                        ClientRelevance::Lost => {
+                            replication_target.target.targets(client_id)
+                                || removed_from_target(client_id)
                         }
-                        None
-                    }).collect()
-            }
+                        ClientRelevance::Maintained => removed_from_target(client_id),
+                        ClientRelevance::Gained => false,
+                    };
+                    if despawn {
+                        debug!(?entity, ?client_id, ?visibility, "sending entity despawn");
+                        return Some(*client_id);
+                    }
+                    None
+                })
+                .collect(),
+            // 2. if the replication target changed, find the clients that were removed in the new replication target
             None => {
-                NetworkTarget::None
+                let mut target = NetworkTarget::None;
+                if replication_target.is_changed() && !replication_target.is_added() {
+                    if let Some(cached_target) = cached_replication_target {
+                        // get targets that we had before but not anymore
+                        let mut new_despawn = cached_target.value.target.clone();
+                        new_despawn.exclude(&replication_target.target);
+                        target.union(&new_despawn);
+                    }
+                }
+                target
             }
         };
-        // 2. if the replication target changed, find the clients that were removed in the new replication target
-        if replication_target.is_changed() && !replication_target.is_added() {
-            if let Some(cached_target) = cached_replication_target {
-                // get targets that we had before but not anymore
-                let mut new_despawn = cached_target.value.target.clone();
-                new_despawn.exclude(&replication_target.target);
-                target.union(&new_despawn);
-            }
-        }
         // 3. we don't send messages to the client that has authority
         if let Some(AuthorityPeer::Client(c)) = authority_peer {
             target.exclude(&NetworkTarget::Single(*c));
@@ -816,6 +981,7 @@ pub(crate) mod send {
         delta_compression: bool,
         replicate_once: bool,
         override_target: Option<&NetworkTarget>,
+        client_filter: Option<&ComponentClientFilterFn>,
         system_ticks: &SystemChangeTick,
         sender: &mut ConnectionManager,
     ) {
@@ -844,10 +1010,12 @@ pub(crate) mod send {
                                     ClientRelevance::Lost => {}
                                     ClientRelevance::Maintained => {
                                         // send a component_insert for components that were newly added
+                                        // (or for all components if the client is being resynced)
                                         if component_ticks.is_added(
                                             system_ticks.last_run(),
                                             system_ticks.this_run(),
                                         ) || force_insert
+                                            || sender.is_initial_sync_target(*client_id, group_id)
                                         {
                                             insert_clients.push(*client_id);
                                         } else {
@@ -898,7 +1066,7 @@ pub(crate) mod send {
                         update_target.union(target);
                     }
 
-                    let new_connected_clients = sender.new_connected_clients();
+                    let new_connected_clients = sender.initial_sync_targets(group_id);
                     // replicate all components to newly connected clients
                     if !new_connected_clients.is_empty() {
                         // replicate to the newly connected clients that match our target
@@ -919,6 +1087,22 @@ pub(crate) mod send {
 
         // do not send a component as both update and insert
         update_target.exclude(&insert_target);
+        // the component will be sent with the initial sync of the client
+        sender.exclude_initial_sync_pending(group_id, &mut insert_target);
+        sender.exclude_initial_sync_pending(group_id, &mut update_target);
+
+        if let Some(client_filter) = client_filter {
+            apply_component_client_filter(
+                component_registry,
+                entity,
+                component_kind,
+                group_id,
+                client_filter,
+                &mut insert_target,
+                &mut update_target,
+                sender,
+            );
+        }
 
         if !insert_target.is_empty() || !update_target.is_empty() {
             if !insert_target.is_empty() {
@@ -959,6 +1143,69 @@ pub(crate) mod send {
         }
     }
 
+    /// Remove the clients that should not see the component from the insert/update targets.
+    ///
+    /// - if the component was visible to a client and becomes hidden, we remove the component on that client
+    /// - if the component was hidden from a client and becomes visible, we send an insert with the
+    ///   current value of the component
+    #[allow(clippy::too_many_arguments)]
+    fn apply_component_client_filter(
+        component_registry: &ComponentRegistry,
+        entity: Entity,
+        component_kind: ComponentKind,
+        group_id: ReplicationGroupId,
+        client_filter: &ComponentClientFilterFn,
+        insert_target: &mut NetworkTarget,
+        update_target: &mut NetworkTarget,
+        sender: &mut ConnectionManager,
+    ) {
+        let candidates = sender
+            .connected_targets(insert_target.clone())
+            .map(|client_id| (client_id, false))
+            .chain(
+                sender
+                    .connected_targets(update_target.clone())
+                    .map(|client_id| (client_id, true)),
+            )
+            .collect::<Vec<_>>();
+        let hidden = sender
+            .hidden_components
+            .entry(entity)
+            .or_default()
+            .entry(component_kind)
+            .or_default();
+        let mut remove_clients = vec![];
+        for (client_id, is_update) in candidates {
+            let client = NetworkTarget::Single(client_id);
+            if !client_filter(client_id, entity) {
+                insert_target.exclude(&client);
+                update_target.exclude(&client);
+                // the client might have received the component before, remove it
+                if hidden.insert(client_id) && is_update {
+                    remove_clients.push(client_id);
+                }
+            } else if hidden.remove(&client_id) && is_update {
+                // the component is revealed: send its current value
+                update_target.exclude(&client);
+                insert_target.union(&client);
+            }
+        }
+        if !remove_clients.is_empty() {
+            let net_id = *component_registry.kind_map.net_id(&component_kind).unwrap();
+            trace!(?entity, ?component_kind, clients = ?remove_clients, "Component is hidden by the ComponentClientFilter");
+            let _ = sender
+                .prepare_component_remove(
+                    entity,
+                    net_id,
+                    group_id,
+                    NetworkTarget::from(remove_clients),
+                )
+                .inspect_err(|e| {
+                    error!("error sending component remove: {:?}", e);
+                });
+        }
+    }
+
     /// This system sends updates for all components that were removed
     pub(crate) fn send_component_removed<C: Component>(
         registry: Res<ComponentRegistry>,
@@ -972,7 +1219,7 @@ pub(crate) mod send {
                 Has<DisabledComponent<C>>,
                 Option<&OverrideTargetComponent<C>>,
             ),
-            With<Replicating>,
+            (With<Replicating>, Without<DontReplicate>),
         >,
         mut removed: RemovedComponents<C>,
         mut sender: ResMut<ConnectionManager>,
@@ -1023,19 +1270,28 @@ pub(crate) mod send {
                 if let Some(AuthorityPeer::Client(c)) = authority_peer {
                     target.exclude(&NetworkTarget::Single(*c));
                 }
+                // clients from which the component was hidden by a ComponentClientFilter don't have the component
+                if let Some(hidden) = sender
+                    .hidden_components
+                    .get_mut(&entity)
+                    .and_then(|components| components.remove(&ComponentKind::of::<C>()))
+                {
+                    target.exclude(&NetworkTarget::Only(hidden.into_iter().collect()));
+                }
                 if target.is_empty() {
                     return;
                 }
                 let group_id = group.group_id(Some(entity));
                 debug!(?entity, ?kind, "Sending RemoveComponent");
-                let _ = sender.prepare_component_remove(entity, kind, group, target);
+                let _ = sender.prepare_component_remove(entity, kind, group_id, target);
             }
         })
     }
 
     pub(crate) fn register_replicate_component_send<C: Component>(app: &mut App) {
+        let schedules = NetworkingSchedules::of(app);
         app.add_systems(
-            PostUpdate,
+            schedules.send,
             (
                 // NOTE: we need to run `send_component_removed` once per frame (and not once per send_interval)
                 //  because the RemovedComponents Events are present only for 1 frame and we might miss them if we don't run this every frame
@@ -1057,8 +1313,8 @@ pub(crate) mod send {
         use crate::prelude::client::Confirmed;
         use crate::prelude::server::{ControlledBy, NetConfig, RelevanceManager, Replicate};
         use crate::prelude::{
-            client, server, DeltaCompression, LinkConditionerConfig, ReplicateOnceComponent,
-            Replicated,
+            client, server, ComponentClientFilter, DeltaCompression, LinkConditionerConfig,
+            ReplicateOnceComponent, Replicated,
         };
         use crate::server::replication::send::SyncTarget;
         use crate::shared::replication::components::{Controlled, ReplicationGroupId};
@@ -1070,9 +1326,36 @@ pub(crate) mod send {
         use bevy::ecs::system::RunSystemOnce;
         use bevy::prelude::{default, EventReader, Resource, Update};
         use bevy::utils::HashSet;
+        use std::sync::Arc;
 
         // TODO: test entity spawn newly connected client
 
+        #[test]
+        fn test_entity_spawn_dont_replicate() {
+            let mut stepper = BevyStepper::default();
+
+            // the entity has replicated components and Replicate, but opted out of replication
+            let server_entity = stepper
+                .server_app
+                .world_mut()
+                .spawn((
+                    ComponentSyncModeFull(1.0),
+                    Replicate::default(),
+                    DontReplicate,
+                ))
+                .id();
+            stepper.frame_step();
+            stepper.frame_step();
+            assert!(stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .is_none());
+        }
+
         #[test]
         fn test_entity_spawn() {
             let mut stepper = BevyStepper::default();
@@ -1532,6 +1815,129 @@ pub(crate) mod send {
                 .is_none());
         }
 
+        /// Swap the replication target between different subsets of clients
+        fn check_replication_target_swap(relevance_mode: NetworkRelevanceMode) {
+            let mut stepper = MultiBevyStepper::default();
+            let client_1 = ClientId::Netcode(TEST_CLIENT_ID_1);
+            let client_2 = ClientId::Netcode(TEST_CLIENT_ID_2);
+
+            // spawn an entity on server that is only replicated to client 1
+            let server_entity = stepper
+                .server_app
+                .world_mut()
+                .spawn((
+                    Replicate {
+                        target: ReplicationTarget {
+                            target: NetworkTarget::Only(vec![client_1]),
+                        },
+                        relevance_mode,
+                        ..default()
+                    },
+                    ComponentSyncModeFull(1.0),
+                ))
+                .id();
+            if relevance_mode == NetworkRelevanceMode::InterestManagement {
+                let mut manager = stepper
+                    .server_app
+                    .world_mut()
+                    .resource_mut::<RelevanceManager>();
+                manager.gain_relevance(client_1, server_entity);
+                manager.gain_relevance(client_2, server_entity);
+            }
+            stepper.frame_step();
+            stepper.frame_step();
+
+            let client_entity_1 = stepper
+                .client_app_1
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .expect("entity was not replicated to client 1");
+            assert!(stepper
+                .client_app_2
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .is_none());
+
+            // swap the replication target
+            stepper
+                .server_app
+                .world_mut()
+                .get_mut::<ReplicationTarget>(server_entity)
+                .unwrap()
+                .target = NetworkTarget::AllExcept(vec![client_1]);
+            stepper.frame_step();
+            stepper.frame_step();
+
+            // the entity is despawned on client 1 and spawned with its components on client 2
+            assert!(stepper
+                .client_app_1
+                .world()
+                .get_entity(client_entity_1)
+                .is_none());
+            let client_entity_2 = stepper
+                .client_app_2
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .expect("entity was not replicated to client 2");
+            assert_eq!(
+                stepper
+                    .client_app_2
+                    .world()
+                    .get::<ComponentSyncModeFull>(client_entity_2),
+                Some(&ComponentSyncModeFull(1.0))
+            );
+
+            // replicate to all clients again
+            stepper
+                .server_app
+                .world_mut()
+                .get_mut::<ReplicationTarget>(server_entity)
+                .unwrap()
+                .target = NetworkTarget::All;
+            stepper.frame_step();
+            stepper.frame_step();
+            let client_entity_1 = stepper
+                .client_app_1
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .expect("entity was not replicated to client 1");
+            assert_eq!(
+                stepper
+                    .client_app_1
+                    .world()
+                    .get::<ComponentSyncModeFull>(client_entity_1),
+                Some(&ComponentSyncModeFull(1.0))
+            );
+            // client 2 still has the same entity
+            assert!(stepper
+                .client_app_2
+                .world()
+                .get_entity(client_entity_2)
+                .is_some());
+        }
+
+        #[test]
+        fn test_replication_target_swap() {
+            check_replication_target_swap(NetworkRelevanceMode::All);
+        }
+
+        #[test]
+        fn test_replication_target_swap_visibility() {
+            check_replication_target_swap(NetworkRelevanceMode::InterestManagement);
+        }
+
         #[test]
         fn test_component_insert() {
             let mut stepper = BevyStepper::default();
@@ -1864,6 +2270,108 @@ pub(crate) mod send {
                 .is_none());
         }
 
+        /// Check that a component can be hidden from some clients with a ComponentClientFilter,
+        /// and that it is sent with its current value when it gets revealed
+        #[test]
+        fn test_component_client_filter() {
+            let mut stepper = MultiBevyStepper::default();
+            let visible_to = Arc::new(parking_lot::RwLock::new(vec![ClientId::Netcode(
+                TEST_CLIENT_ID_1,
+            )]));
+            let filter_visible_to = visible_to.clone();
+
+            // spawn an entity on server, with the component only visible to client 1
+            let server_entity = stepper
+                .server_app
+                .world_mut()
+                .spawn((
+                    Replicate::default(),
+                    ComponentSyncModeFull(1.0),
+                    ComponentClientFilter::<ComponentSyncModeFull>::new(move |client_id, _| {
+                        filter_visible_to.read().contains(&client_id)
+                    }),
+                ))
+                .id();
+            stepper.frame_step();
+            stepper.frame_step();
+            let client_entity_1 = stepper
+                .client_app_1
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .expect("entity was not replicated to client");
+            let client_entity_2 = stepper
+                .client_app_2
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .expect("entity was not replicated to client");
+            assert_eq!(
+                stepper
+                    .client_app_1
+                    .world()
+                    .entity(client_entity_1)
+                    .get::<ComponentSyncModeFull>()
+                    .expect("component missing"),
+                &ComponentSyncModeFull(1.0)
+            );
+            assert!(stepper
+                .client_app_2
+                .world()
+                .entity(client_entity_2)
+                .get::<ComponentSyncModeFull>()
+                .is_none());
+
+            // updates are not sent to client 2 either
+            stepper
+                .server_app
+                .world_mut()
+                .entity_mut(server_entity)
+                .insert(ComponentSyncModeFull(2.0));
+            stepper.frame_step();
+            stepper.frame_step();
+            assert_eq!(
+                stepper
+                    .client_app_1
+                    .world()
+                    .entity(client_entity_1)
+                    .get::<ComponentSyncModeFull>()
+                    .expect("component missing"),
+                &ComponentSyncModeFull(2.0)
+            );
+            assert!(stepper
+                .client_app_2
+                .world()
+                .entity(client_entity_2)
+                .get::<ComponentSyncModeFull>()
+                .is_none());
+
+            // reveal the component to client 2 and hide it from client 1, without modifying it:
+            // client 2 receives the current value and the component is removed on client 1
+            *visible_to.write() = vec![ClientId::Netcode(TEST_CLIENT_ID_2)];
+            stepper.frame_step();
+            stepper.frame_step();
+            assert!(stepper
+                .client_app_1
+                .world()
+                .entity(client_entity_1)
+                .get::<ComponentSyncModeFull>()
+                .is_none());
+            assert_eq!(
+                stepper
+                    .client_app_2
+                    .world()
+                    .entity(client_entity_2)
+                    .get::<ComponentSyncModeFull>()
+                    .expect("component missing"),
+                &ComponentSyncModeFull(2.0)
+            );
+        }
+
         /// Check that override target works even if the entity uses interest management
         /// We still use visibility, but we use `override_target` instead of `replication_target`
         #[test]
@@ -1973,6 +2481,266 @@ pub(crate) mod send {
             );
         }
 
+        /// Test that resyncing a client re-sends the full component state, even if the
+        /// component did not change on the server
+        #[test]
+        fn test_resync_client() {
+            let mut stepper = BevyStepper::default();
+
+            // spawn an entity on server
+            let server_entity = stepper
+                .server_app
+                .world_mut()
+                .spawn((Replicate::default(), ComponentSyncModeFull(1.0)))
+                .id();
+            stepper.frame_step();
+            stepper.frame_step();
+            let client_entity = stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .expect("entity was not replicated to client");
+
+            // the client state diverges from the server state
+            stepper
+                .client_app
+                .world_mut()
+                .entity_mut(client_entity)
+                .insert(ComponentSyncModeFull(5.0));
+            stepper.frame_step();
+            stepper.frame_step();
+            assert_eq!(
+                stepper
+                    .client_app
+                    .world()
+                    .entity(client_entity)
+                    .get::<ComponentSyncModeFull>()
+                    .expect("component missing"),
+                &ComponentSyncModeFull(5.0)
+            );
+
+            // resync the client
+            stepper
+                .server_app
+                .world_mut()
+                .resource_mut::<ConnectionManager>()
+                .resync(ClientId::Netcode(TEST_CLIENT_ID))
+                .unwrap();
+            stepper.frame_step();
+            stepper.frame_step();
+
+            // check that the server state was re-sent, without spawning a new entity
+            assert_eq!(
+                stepper
+                    .client_app
+                    .world()
+                    .resource::<client::ConnectionManager>()
+                    .replication_receiver
+                    .remote_entity_map
+                    .get_local(server_entity),
+                Some(client_entity)
+            );
+            assert_eq!(
+                stepper
+                    .client_app
+                    .world()
+                    .entity(client_entity)
+                    .get::<ComponentSyncModeFull>()
+                    .expect("component missing"),
+                &ComponentSyncModeFull(1.0)
+            );
+        }
+
+        /// Test that resyncing a client does not spawn new predicted or interpolated entities
+        /// for the entities that the client already knows about
+        #[test]
+        fn test_resync_client_predicted() {
+            let mut stepper = BevyStepper::default();
+
+            let server_entity = stepper
+                .server_app
+                .world_mut()
+                .spawn((
+                    Replicate {
+                        sync: SyncTarget {
+                            prediction: NetworkTarget::All,
+                            interpolation: NetworkTarget::All,
+                        },
+                        ..default()
+                    },
+                    ComponentSyncModeFull(1.0),
+                ))
+                .id();
+            stepper.frame_step();
+            stepper.frame_step();
+            let client_entity = stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .expect("entity was not replicated to client");
+            let confirmed = stepper
+                .client_app
+                .world()
+                .entity(client_entity)
+                .get::<Confirmed>()
+                .expect("Confirmed component missing");
+            let (predicted, interpolated) = (confirmed.predicted, confirmed.interpolated);
+            assert!(predicted.is_some());
+            assert!(interpolated.is_some());
+
+            stepper
+                .server_app
+                .world_mut()
+                .resource_mut::<ConnectionManager>()
+                .resync(ClientId::Netcode(TEST_CLIENT_ID))
+                .unwrap();
+            stepper.frame_step();
+            stepper.frame_step();
+
+            // the existing predicted and interpolated entities are kept
+            let resynced = stepper
+                .client_app
+                .world()
+                .entity(client_entity)
+                .get::<Confirmed>()
+                .expect("Confirmed component missing");
+            assert_eq!(resynced.predicted, predicted);
+            assert_eq!(resynced.interpolated, interpolated);
+            assert_eq!(
+                stepper
+                    .client_app
+                    .world_mut()
+                    .query::<&Predicted>()
+                    .iter(stepper.client_app.world())
+                    .count(),
+                1
+            );
+            assert_eq!(
+                stepper
+                    .client_app
+                    .world_mut()
+                    .query::<&Interpolated>()
+                    .iter(stepper.client_app.world())
+                    .count(),
+                1
+            );
+        }
+
+        #[test]
+        fn test_pause_replication() {
+            let mut stepper = BevyStepper::default();
+            let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+            let get_local = |stepper: &BevyStepper, server_entity| {
+                stepper
+                    .client_app
+                    .world()
+                    .resource::<client::ConnectionManager>()
+                    .replication_receiver
+                    .remote_entity_map
+                    .get_local(server_entity)
+            };
+
+            let server_entity_a = stepper
+                .server_app
+                .world_mut()
+                .spawn((
+                    Replicate::default(),
+                    ComponentSyncModeFull(1.0),
+                    ComponentSyncModeSimple(1.0),
+                ))
+                .id();
+            let server_entity_b = stepper
+                .server_app
+                .world_mut()
+                .spawn(Replicate::default())
+                .id();
+            stepper.frame_step();
+            stepper.frame_step();
+            let client_entity_a = get_local(&stepper, server_entity_a).unwrap();
+            let client_entity_b = get_local(&stepper, server_entity_b).unwrap();
+
+            stepper
+                .server_app
+                .world_mut()
+                .resource_mut::<ConnectionManager>()
+                .set_replication_paused(client_id, true)
+                .unwrap();
+            stepper
+                .server_app
+                .world_mut()
+                .entity_mut(server_entity_a)
+                .insert(ComponentSyncModeFull(2.0))
+                .remove::<ComponentSyncModeSimple>();
+            stepper.server_app.world_mut().despawn(server_entity_b);
+            let server_entity_c = stepper
+                .server_app
+                .world_mut()
+                .spawn(Replicate::default())
+                .id();
+            // an entity spawned and despawned while the replication is paused
+            let server_entity_d = stepper
+                .server_app
+                .world_mut()
+                .spawn(Replicate::default())
+                .id();
+            stepper.frame_step();
+            stepper.server_app.world_mut().despawn(server_entity_d);
+            stepper.frame_step();
+            stepper.frame_step();
+
+            // only the despawn was replicated
+            assert_eq!(
+                stepper
+                    .client_app
+                    .world()
+                    .get::<ComponentSyncModeFull>(client_entity_a),
+                Some(&ComponentSyncModeFull(1.0))
+            );
+            assert!(stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeSimple>(client_entity_a)
+                .is_some());
+            assert!(stepper
+                .client_app
+                .world()
+                .get_entity(client_entity_b)
+                .is_none());
+            assert!(get_local(&stepper, server_entity_c).is_none());
+
+            // resuming the replication sends the current state
+            stepper
+                .server_app
+                .world_mut()
+                .resource_mut::<ConnectionManager>()
+                .set_replication_paused(client_id, false)
+                .unwrap();
+            stepper.frame_step();
+            stepper.frame_step();
+            assert_eq!(get_local(&stepper, server_entity_a), Some(client_entity_a));
+            assert_eq!(
+                stepper
+                    .client_app
+                    .world()
+                    .get::<ComponentSyncModeFull>(client_entity_a),
+                Some(&ComponentSyncModeFull(2.0))
+            );
+            // the removal is sent when the replication is resumed
+            assert!(stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeSimple>(client_entity_a)
+                .is_none());
+            assert!(get_local(&stepper, server_entity_c).is_some());
+            assert!(get_local(&stepper, server_entity_d).is_none());
+        }
+
         /// Test that replicating updates works even if the update happens after tick wrapping
         #[test]
         fn test_component_update_after_tick_wrap() {
@@ -2422,11 +3190,13 @@ pub(crate) mod send {
                 .first_mut()
                 .unwrap()
             {
-                io.conditioner = Some(LinkConditionerConfig {
+                io.incoming_conditioner = Some(LinkConditionerConfig {
                     // the server receives client packets after 3 ticks
                     incoming_latency: Duration::from_millis(30),
                     incoming_jitter: Default::default(),
                     incoming_loss: 0.0,
+                    duplicate_chance: 0.0,
+                    reorder_chance: 0.0,
                 })
             }
             stepper.start();
@@ -2594,11 +3364,13 @@ pub(crate) mod send {
                 .first_mut()
                 .unwrap()
             {
-                io.conditioner = Some(LinkConditionerConfig {
+                io.incoming_conditioner = Some(LinkConditionerConfig {
                     // the server receives client packets after 3 ticks
                     incoming_latency: Duration::from_millis(30),
                     incoming_jitter: Default::default(),
                     incoming_loss: 0.0,
+                    duplicate_chance: 0.0,
+                    reorder_chance: 0.0,
                 })
             }
             stepper.start();
@@ -2933,6 +3705,15 @@ pub(crate) mod send {
                 .entity(client_entity)
                 .get::<ComponentSyncModeFull>()
                 .is_none());
+            // the removal is surfaced as an event on the client
+            let events: Vec<_> = stepper
+                .client_app
+                .world_mut()
+                .resource_mut::<Events<client::ComponentRemoveEvent<ComponentSyncModeFull>>>()
+                .drain()
+                .map(|event| event.entity())
+                .collect();
+            assert_eq!(events, vec![client_entity]);
         }
 
         /// Check that if we switch the visibility mode, the entity gets spawned
@@ -3092,10 +3873,33 @@ pub(crate) mod send {
 
 pub(crate) mod commands {
     use crate::channel::builder::AuthorityChannel;
-    use crate::prelude::{Replicating, ServerConnectionManager};
+    use crate::prelude::{ClientId, Replicating, ServerConnectionManager};
     use crate::shared::replication::authority::{AuthorityChange, AuthorityPeer, HasAuthority};
     use bevy::ecs::system::EntityCommands;
     use bevy::prelude::{Entity, World};
+    use tracing::warn;
+
+    /// Notify a client that it gained or lost the authority over an entity
+    fn send_authority_change(
+        world: &mut World,
+        client_id: ClientId,
+        entity: Entity,
+        gain_authority: bool,
+    ) {
+        // the client could have disconnected in the meantime
+        if let Err(e) = world
+            .resource_mut::<ServerConnectionManager>()
+            .send_message::<AuthorityChannel, _>(
+                client_id,
+                &mut AuthorityChange {
+                    entity,
+                    gain_authority,
+                },
+            )
+        {
+            warn!(?client_id, ?entity, "could not send authority change: {e}");
+        }
+    }
 
     pub trait AuthorityCommandExt {
         /// This command is used to transfer the authority of an entity to a different peer.
@@ -3127,16 +3931,7 @@ pub(crate) mod commands {
                     }
                     (AuthorityPeer::None, AuthorityPeer::Client(c)) => {
                         world.entity_mut(entity).insert(AuthorityPeer::Client(c));
-                        world
-                            .resource_mut::<ServerConnectionManager>()
-                            .send_message::<AuthorityChannel, _>(
-                                c,
-                                &mut AuthorityChange {
-                                    entity,
-                                    gain_authority: true,
-                                },
-                            )
-                            .expect("could not send message");
+                        send_authority_change(world, c, entity, true);
                     }
                     (AuthorityPeer::Server, AuthorityPeer::None) => {
                         world
@@ -3146,16 +3941,7 @@ pub(crate) mod commands {
                     }
                     (AuthorityPeer::Client(c), AuthorityPeer::None) => {
                         world.entity_mut(entity).insert(AuthorityPeer::None);
-                        world
-                            .resource_mut::<ServerConnectionManager>()
-                            .send_message::<AuthorityChannel, _>(
-                                c,
-                                &mut AuthorityChange {
-                                    entity,
-                                    gain_authority: false,
-                                },
-                            )
-                            .expect("could not send message");
+                        send_authority_change(world, c, entity, false);
                     }
                     (AuthorityPeer::Client(c), AuthorityPeer::Server) => {
                         // TODO: only gain the authority when we have received an ack
@@ -3163,55 +3949,19 @@ pub(crate) mod commands {
                         world
                             .entity_mut(entity)
                             .insert((HasAuthority, AuthorityPeer::Server));
-                        world
-                            .resource_mut::<ServerConnectionManager>()
-                            .send_message::<AuthorityChannel, _>(
-                                c,
-                                &mut AuthorityChange {
-                                    entity,
-                                    gain_authority: false,
-                                },
-                            )
-                            .expect("could not send message");
+                        send_authority_change(world, c, entity, false);
                     }
                     (AuthorityPeer::Server, AuthorityPeer::Client(c)) => {
                         world
                             .entity_mut(entity)
                             .remove::<HasAuthority>()
                             .insert(AuthorityPeer::Client(c));
-                        world
-                            .resource_mut::<ServerConnectionManager>()
-                            .send_message::<AuthorityChannel, _>(
-                                c,
-                                &mut AuthorityChange {
-                                    entity,
-                                    gain_authority: true,
-                                },
-                            )
-                            .expect("could not send message");
+                        send_authority_change(world, c, entity, true);
                     }
                     (AuthorityPeer::Client(c1), AuthorityPeer::Client(c2)) => {
                         world.entity_mut(entity).insert(AuthorityPeer::Client(c2));
-                        world
-                            .resource_mut::<ServerConnectionManager>()
-                            .send_message::<AuthorityChannel, _>(
-                                c1,
-                                &mut AuthorityChange {
-                                    entity,
-                                    gain_authority: false,
-                                },
-                            )
-                            .expect("could not send message");
-                        world
-                            .resource_mut::<ServerConnectionManager>()
-                            .send_message::<AuthorityChannel, _>(
-                                c2,
-                                &mut AuthorityChange {
-                                    entity,
-                                    gain_authority: true,
-                                },
-                            )
-                            .expect("could not send message");
+                        send_authority_change(world, c1, entity, false);
+                        send_authority_change(world, c2, entity, true);
                     }
                     _ => unreachable!(),
                 }
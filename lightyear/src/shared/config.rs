@@ -13,6 +13,11 @@ pub struct SharedConfig {
     /// configuration for the [`FixedUpdate`](bevy::prelude::FixedUpdate) schedule
     pub tick: TickConfig,
     pub mode: Mode,
+    /// Seed of the [`DeterministicRng`](crate::prelude::DeterministicRng).
+    ///
+    /// Only the value set on the server is used: it is sent to the clients when they connect.
+    /// If it is not set, the server picks a random seed.
+    pub rng_seed: Option<u64>,
 }
 
 // TODO: maybe the modes should just be
@@ -42,6 +47,16 @@ impl Default for SharedConfig {
             server_replication_send_interval: Duration::from_millis(0),
             tick: TickConfig::new(Duration::from_millis(16)),
             mode: Mode::default(),
+            rng_seed: None,
         }
     }
 }
+
+impl SharedConfig {
+    /// Duration of a tick, i.e. the timestep of the [`FixedUpdate`](bevy::prelude::FixedUpdate) schedule
+    ///
+    /// The current tick can be read with the [`TickManager`](crate::prelude::TickManager) resource.
+    pub fn tick_duration(&self) -> Duration {
+        self.tick.tick_duration
+    }
+}
@@ -0,0 +1,71 @@
+//! Helpers to make sure that all the pending network messages have been delivered.
+//!
+//! This is mostly useful at the end of a deterministic test, to make sure that all the reliable messages
+//! and replication updates have been sent and received before running the assertions.
+use bevy::prelude::{App, World};
+
+use crate::client::connection::ConnectionManager as ClientConnectionManager;
+use crate::server::connection::ConnectionManager as ServerConnectionManager;
+
+/// Returns true if the client or the server running in this [`World`] still has messages to send,
+/// or reliable messages that were not acked yet.
+pub fn has_pending_messages(world: &World) -> bool {
+    world
+        .get_resource::<ClientConnectionManager>()
+        .is_some_and(|connection| connection.has_pending_messages())
+        || world
+            .get_resource::<ServerConnectionManager>()
+            .is_some_and(|connection| connection.has_pending_messages())
+}
+
+/// Update all the `apps` until none of them has pending messages, or until `max_updates` updates have been run.
+///
+/// The apps are updated one more time once the messages are drained, so that the last messages sent
+/// are received by the remote peers.
+///
+/// Returns false if the apps still had pending messages after `max_updates` updates.
+pub fn drain_network(apps: &mut [&mut App], max_updates: usize) -> bool {
+    for _ in 0..max_updates {
+        apps.iter_mut().for_each(|app| app.update());
+        if !apps.iter().any(|app| has_pending_messages(app.world())) {
+            apps.iter_mut().for_each(|app| app.update());
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::server;
+    use crate::tests::stepper::BevyStepper;
+
+    use super::*;
+
+    #[test]
+    fn test_drain_network_apps() {
+        let mut stepper = BevyStepper::default();
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(server::Replicate::default())
+            .id();
+        stepper.server_app.update();
+        assert!(has_pending_messages(stepper.server_app.world()));
+
+        assert!(drain_network(
+            &mut [&mut stepper.client_app, &mut stepper.server_app],
+            10
+        ));
+        assert!(!has_pending_messages(stepper.server_app.world()));
+        assert!(!has_pending_messages(stepper.client_app.world()));
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<ClientConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .is_some());
+    }
+}
@@ -3,19 +3,45 @@
 use std::marker::PhantomData;
 
 use bevy::prelude::{Component, Entity, Event};
+use bytes::Bytes;
 
 use crate::packet::message::Message;
+use crate::packet::message_ack::MessageHandle;
+use crate::protocol::channel::ChannelKind;
 
 /// This event is emitted whenever we receive a message from the remote
 #[derive(Event, Debug)]
 pub struct MessageEvent<M: Message, Ctx = ()> {
     pub message: M,
     pub context: Ctx,
+    /// The message as it was received, so that it can be relayed without being serialized again
+    raw: Option<RawMessage>,
+}
+
+/// Serialized bytes of a received message, along with the channel it was received on
+#[derive(Debug, Clone)]
+pub(crate) struct RawMessage {
+    pub(crate) bytes: Bytes,
+    pub(crate) channel: ChannelKind,
 }
 
 impl<M: Message, Ctx> MessageEvent<M, Ctx> {
     pub fn new(message: M, context: Ctx) -> Self {
-        Self { message, context }
+        Self {
+            message,
+            context,
+            raw: None,
+        }
+    }
+
+    pub(crate) fn with_raw(mut self, raw: RawMessage) -> Self {
+        self.raw = Some(raw);
+        self
+    }
+
+    /// The message as it was received, if it can be relayed
+    pub(crate) fn raw(&self) -> Option<&RawMessage> {
+        self.raw.as_ref()
     }
 
     pub fn message(&self) -> &M {
@@ -27,6 +53,24 @@ impl<M: Message, Ctx> MessageEvent<M, Ctx> {
     }
 }
 
+/// Event emitted when the remote peer acked a message sent with `send_message_with_ack`
+///
+/// See [`message_ack`](crate::packet::message_ack) for more details.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageAckEvent<Ctx = ()> {
+    pub handle: MessageHandle,
+    pub context: Ctx,
+}
+
+/// Event emitted when a message sent with `send_message_with_ack` is considered lost, and won't be sent again
+///
+/// See [`message_ack`](crate::packet::message_ack) for more details.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageTimeoutEvent<Ctx = ()> {
+    pub handle: MessageHandle,
+    pub context: Ctx,
+}
+
 #[derive(Event)]
 /// Event emitted on server every time we receive an event
 pub struct InputEvent<I: crate::inputs::native::UserAction, Ctx = ()> {
@@ -30,6 +30,9 @@ pub struct ConnectionEvents {
     //  let's just start with the kind...
     //  also, normally the updates are sequenced
     pub component_updates: HashMap<ComponentNetId, Vec<Entity>>,
+    /// Component updates that were applied to the world, for components that were registered with
+    /// [`add_application_ack`](crate::protocol::component::ComponentRegistration::add_application_ack)
+    pub(crate) applied_components: Vec<(Entity, ComponentNetId, Tick)>,
     // // TODO: what happens if we receive on the same frame an Update for tick 4 and update for tick 10?
     // //  can we just discard the older one? what about for inserts/removes?
     // pub component_updates: EntityHashMap<Entity, HashMap<P::ComponentKinds, Tick>>,
@@ -50,6 +53,7 @@ impl ClearEvents for ConnectionEvents {
         self.component_inserts.clear();
         self.component_removes.clear();
         self.component_updates.clear();
+        self.applied_components.clear();
         self.empty = true;
     }
 }
@@ -69,6 +73,7 @@ impl ConnectionEvents {
             component_inserts: Default::default(),
             component_removes: Default::default(),
             component_updates: Default::default(),
+            applied_components: Default::default(),
             // bookkeeping
             empty: true,
         }
@@ -130,6 +135,19 @@ impl ConnectionEvents {
         self.empty = false;
     }
 
+    /// The component update for `tick` was applied to the entity.
+    ///
+    /// These are not emitted as bevy events, but are sent back to the remote
+    pub(crate) fn push_applied_component(
+        &mut self,
+        entity: Entity,
+        kind: ComponentNetId,
+        tick: Tick,
+    ) {
+        trace!(?entity, ?kind, ?tick, "Applied component");
+        self.applied_components.push((entity, kind, tick));
+    }
+
     // TODO: how do distinguish between multiple updates for the same component/entity? add ticks?
     pub(crate) fn push_update_component(
         &mut self,
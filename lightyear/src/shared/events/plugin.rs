@@ -1,12 +1,12 @@
 //! Create the bevy [`Plugin`]
 
-use bevy::app::{App, PreUpdate};
+use bevy::app::App;
 use bevy::prelude::{IntoSystemConfigs, Plugin};
 
 use crate::shared::events::components::{EntityDespawnEvent, EntitySpawnEvent};
 use crate::shared::events::systems::{clear_events, push_entity_events};
 use crate::shared::replication::ReplicationReceive;
-use crate::shared::sets::InternalMainSet;
+use crate::shared::sets::{InternalMainSet, NetworkingSchedules};
 
 pub struct EventsPlugin<R> {
     marker: std::marker::PhantomData<R>,
@@ -22,16 +22,17 @@ impl<R> Default for EventsPlugin<R> {
 
 impl<R: ReplicationReceive> Plugin for EventsPlugin<R> {
     fn build(&self, app: &mut App) {
+        let schedules = NetworkingSchedules::of(app);
         // EVENTS
         app.add_event::<EntitySpawnEvent<R::EventContext>>()
             .add_event::<EntityDespawnEvent<R::EventContext>>();
         // SYSTEMS
         app.add_systems(
-            PreUpdate,
+            schedules.receive,
             push_entity_events::<R>.in_set(InternalMainSet::<R::SetMarker>::EmitEvents),
         );
         app.add_systems(
-            PreUpdate,
+            schedules.receive,
             clear_events::<R>.after(InternalMainSet::<R::SetMarker>::EmitEvents),
         );
     }
@@ -2,6 +2,8 @@
 
 pub mod config;
 
+pub mod drain;
+
 pub mod events;
 
 pub mod log;
@@ -12,6 +14,8 @@ pub mod plugin;
 
 pub mod replication;
 
+pub mod rng;
+
 pub mod sets;
 
 pub mod tick_manager;
@@ -20,3 +24,4 @@ pub mod input;
 pub(crate) mod message;
 pub mod run_conditions;
 pub mod time_manager;
+pub mod time_source;
@@ -2,23 +2,61 @@
 use bevy::reflect::Reflect;
 use bevy::time::Stopwatch;
 use bevy::utils::Duration;
+use std::collections::VecDeque;
 use tracing::{error, trace};
 
-use crate::shared::ping::message::{Ping, Pong};
+use crate::shared::ping::message::{Ping, Pong, TimeSync};
 use crate::shared::ping::store::{PingId, PingStore};
+use crate::shared::tick_manager::TickManager;
 use crate::shared::time_manager::{TimeManager, WrappedTime};
 use crate::utils::ready_buffer::ReadyBuffer;
 
 /// Config for the ping manager, which sends regular pings to the remote machine in order
-/// to compute network statistics (RTT, jitter)
+/// to compute network statistics (RTT, jitter, packet loss)
 #[derive(Clone, Copy, Debug, Reflect)]
 pub struct PingConfig {
     /// The duration to wait before sending a ping message to the remote host,
     /// in order to estimate RTT time
     pub ping_interval: Duration,
-    /// Duration of the rolling buffer of stats to compute RTT/jitter
+    /// Duration of the rolling buffer of stats to compute RTT/jitter/packet loss
     /// NOTE: this must be high enough to have received enough pongs to sync
     pub stats_buffer_duration: Duration,
+    /// Algorithm used to estimate the RTT/jitter from the RTT samples
+    pub rtt_estimator: RttEstimator,
+    /// How often the server sends a [`TimeSync`](crate::shared::ping::message::TimeSync) stamp
+    /// that the client uses to correct its estimate of the server time.
+    ///
+    /// Set to `None` to disable the stamps; the client will then only use the server tick
+    /// contained in each packet header. This is unused on the client.
+    pub time_sync_interval: Option<Duration>,
+    /// How often the server emits a [`ConnectionHealthEvent`](crate::server::events::ConnectionHealthEvent)
+    /// for each connected client.
+    ///
+    /// Set to `None` to disable the events. This is unused on the client.
+    pub health_event_interval: Option<Duration>,
+}
+
+/// Algorithm used to compute the RTT and jitter estimates from the RTT samples received via pongs.
+///
+/// The RTT estimate is used for clock sync, so this lets you choose between a responsive estimate (that
+/// quickly follows changes in network conditions) and a stable one (that is less sensitive to outliers).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Reflect)]
+pub enum RttEstimator {
+    /// Mean of the samples in the stats buffer, ignoring the samples that are more than one standard deviation
+    /// away from the mean
+    #[default]
+    PrunedMean,
+    /// Simple moving average of the samples in the stats buffer
+    MovingAverage,
+    /// Exponentially weighted moving average: each new sample `s` updates the estimate with
+    /// `rtt = (1 - alpha) * rtt + alpha * s`.
+    ///
+    /// A higher `alpha` (between 0.0 and 1.0) makes the estimate react faster to changes in RTT.
+    /// The samples leaving the stats buffer are not taken into account.
+    Ewma { alpha: f32 },
+    /// Minimum RTT of the samples in the stats buffer. This is the RTT without queuing delays;
+    /// the jitter is still computed from the standard deviation of the samples.
+    MinRtt,
 }
 
 impl Default for PingConfig {
@@ -26,10 +64,30 @@ impl Default for PingConfig {
         PingConfig {
             ping_interval: Duration::from_millis(100),
             stats_buffer_duration: Duration::from_secs(4),
+            rtt_estimator: RttEstimator::default(),
+            time_sync_interval: Some(Duration::from_millis(100)),
+            health_event_interval: None,
         }
     }
 }
 
+impl PingConfig {
+    pub fn with_rtt_estimator(mut self, rtt_estimator: RttEstimator) -> Self {
+        self.rtt_estimator = rtt_estimator;
+        self
+    }
+
+    pub fn with_time_sync_interval(mut self, time_sync_interval: Option<Duration>) -> Self {
+        self.time_sync_interval = time_sync_interval;
+        self
+    }
+
+    pub fn with_health_event_interval(mut self, health_event_interval: Duration) -> Self {
+        self.health_event_interval = Some(health_event_interval);
+        self
+    }
+}
+
 /// The [`PingManager`] is responsible for sending regular pings to the remote machine,
 /// and monitor pongs in order to estimate statistics (rtt, jitter) about the connection.
 #[derive(Debug)]
@@ -37,6 +95,10 @@ pub struct PingManager {
     config: PingConfig,
     /// Timer to send regular pings to the remote
     ping_timer: Stopwatch,
+    /// Timer to send regular time sync stamps to the remote
+    time_sync_timer: Stopwatch,
+    /// Timer to report the health of the connection regularly
+    health_timer: Stopwatch,
     /// ping store to track which pings we sent
     ping_store: PingStore,
     /// ping id corresponding to the most recent pong received
@@ -51,10 +113,16 @@ pub struct PingManager {
     pub(crate) sync_stats: SyncStatsBuffer,
     /// Current best estimates of various networking statistics
     pub final_stats: FinalStats,
+    /// True if the [`RttEstimator::Ewma`] estimate has been initialized from a first sample
+    ewma_initialized: bool,
     /// The number of pings we have sent
     pub(crate) pings_sent: u32,
     /// The number of pongs we have received
     pub(crate) pongs_recv: u32,
+    /// Pings sent during the last `stats_buffer_duration`, with true if we received their pong
+    recent_pings: VecDeque<(WrappedTime, PingId, bool)>,
+    /// Time of the latest update
+    current_time: WrappedTime,
 }
 
 /// Connection stats aggregated over several [`SyncStats`]
@@ -88,14 +156,19 @@ impl PingManager {
             config,
             // pings
             ping_timer: Stopwatch::new(),
+            time_sync_timer: Stopwatch::new(),
+            health_timer: Stopwatch::new(),
             ping_store: PingStore::new(),
             most_recent_received_ping: PingId(u16::MAX - 1),
             pongs_to_send: vec![],
             // sync
             sync_stats: SyncStatsBuffer::new(),
             final_stats: FinalStats::default(),
+            ewma_initialized: false,
             pings_sent: 0,
             pongs_recv: 0,
+            recent_pings: VecDeque::new(),
+            current_time: WrappedTime::default(),
         }
     }
 
@@ -109,12 +182,56 @@ impl PingManager {
         self.final_stats.jitter
     }
 
+    /// Return the fraction of pings (between 0.0 and 1.0) sent during the last
+    /// [`PingConfig::stats_buffer_duration`] that did not get a pong.
+    ///
+    /// A ping is considered lost if we didn't receive its pong after twice the RTT plus the ping interval.
+    pub fn packet_loss(&self) -> f32 {
+        let timeout = self.rtt() * 2 + self.config.ping_interval;
+        let (mut pings, mut lost) = (0, 0);
+        for (sent_time, _, received) in &self.recent_pings {
+            if *received {
+                pings += 1;
+            } else if *sent_time + timeout < self.current_time {
+                pings += 1;
+                lost += 1;
+            }
+        }
+        if pings == 0 {
+            return 0.0;
+        }
+        lost as f32 / pings as f32
+    }
+
+    /// Return the algorithm currently used to estimate the rtt/jitter
+    pub fn rtt_estimator(&self) -> RttEstimator {
+        self.config.rtt_estimator
+    }
+
+    /// Return the raw rtt samples currently in the stats buffer, in no particular order
+    pub fn rtt_samples(&self) -> impl Iterator<Item = Duration> + '_ {
+        self.sync_stats
+            .heap
+            .iter()
+            .map(|stat| stat.item.round_trip_delay)
+    }
+
     /// Update the ping manager after a delta update
     pub(crate) fn update(&mut self, time_manager: &TimeManager) {
         self.ping_timer.tick(time_manager.delta());
+        self.time_sync_timer.tick(time_manager.delta());
+        self.health_timer.tick(time_manager.delta());
 
         // clear stats that are older than a threshold, such as 2 seconds
+        self.current_time = time_manager.current_time();
         let oldest_time = time_manager.current_time() - self.config.stats_buffer_duration;
+        while self
+            .recent_pings
+            .front()
+            .is_some_and(|(sent_time, _, _)| *sent_time < oldest_time)
+        {
+            self.recent_pings.pop_front();
+        }
         let old_len = self.sync_stats.len();
         self.sync_stats.pop_until(&oldest_time);
         let new_len = self.sync_stats.len();
@@ -140,16 +257,131 @@ impl PingManager {
 
             let ping_id = self.ping_store.push_new(time_manager.current_time());
             self.pings_sent += 1;
+            self.recent_pings
+                .push_back((time_manager.current_time(), ping_id, false));
             return Some(Ping { id: ping_id });
         }
         None
     }
 
+    /// Check if we are ready to send a time sync stamp to the remote
+    pub(crate) fn maybe_prepare_time_sync(
+        &mut self,
+        time_manager: &TimeManager,
+        tick_manager: &TickManager,
+    ) -> Option<TimeSync> {
+        let interval = self.config.time_sync_interval?;
+        if self.time_sync_timer.elapsed() >= interval {
+            self.time_sync_timer.reset();
+            return Some(TimeSync {
+                tick: tick_manager.tick(),
+                overstep: time_manager.raw_overstep(),
+            });
+        }
+        None
+    }
+
+    /// Check if we should report the health of the connection
+    pub(crate) fn maybe_report_health(&mut self) -> bool {
+        let Some(interval) = self.config.health_event_interval else {
+            return false;
+        };
+        if self.health_timer.elapsed() >= interval {
+            self.health_timer.reset();
+            return true;
+        }
+        false
+    }
+
     // TODO: optimization
     //  - for efficiency, we want to use a rolling mean/std algorithm
     //  - every N seconds (for example 2 seconds), we clear the buffer for stats older than 2 seconds and recompute mean/std from the remaining elements
-    /// Compute the stats (offset, rtt, jitter) from the stats present in the buffer
+    /// Compute the stats (rtt, jitter) from the stats present in the buffer, using the
+    /// configured [`RttEstimator`]
     pub fn compute_stats(&mut self) {
+        match self.config.rtt_estimator {
+            RttEstimator::PrunedMean => self.compute_pruned_mean_stats(),
+            RttEstimator::MovingAverage => {
+                if let Some((mean, stdv)) = self.rtt_mean_and_stdv() {
+                    self.final_stats = FinalStats {
+                        rtt: Duration::from_secs_f64(mean),
+                        jitter: Duration::from_secs_f64(stdv / 2.0),
+                    };
+                }
+            }
+            RttEstimator::MinRtt => {
+                let Some((_, stdv)) = self.rtt_mean_and_stdv() else {
+                    return;
+                };
+                let min = self.rtt_samples().min().unwrap_or_default();
+                self.final_stats = FinalStats {
+                    rtt: min,
+                    jitter: Duration::from_secs_f64(stdv / 2.0),
+                };
+            }
+            // the EWMA is updated incrementally every time we receive a new sample
+            RttEstimator::Ewma { .. } => {}
+        }
+        trace!(
+            rtt = ?self.final_stats.rtt,
+            jitter = ?self.final_stats.jitter,
+            "Computed stats!"
+        );
+    }
+
+    /// Mean and standard deviation (in seconds) of the rtt samples in the buffer.
+    /// Returns None if the buffer is empty
+    fn rtt_mean_and_stdv(&self) -> Option<(f64, f64)> {
+        if self.sync_stats.is_empty() {
+            return None;
+        }
+        let sample_count = self.sync_stats.len() as f64;
+        let mean = self.rtt_samples().map(|rtt| rtt.as_secs_f64()).sum::<f64>() / sample_count;
+        let variance = self
+            .rtt_samples()
+            .map(|rtt| (rtt.as_secs_f64() - mean).powi(2))
+            .sum::<f64>()
+            / sample_count;
+        Some((mean, variance.sqrt()))
+    }
+
+    /// Update the [`RttEstimator::Ewma`] estimate with a new sample
+    fn update_ewma(&mut self, alpha: f32, sample: Duration) {
+        let sample = sample.as_secs_f64();
+        if !self.ewma_initialized {
+            self.ewma_initialized = true;
+            self.final_stats = FinalStats {
+                rtt: Duration::from_secs_f64(sample),
+                jitter: Duration::from_secs_f64(sample / 4.0),
+            };
+            return;
+        }
+        let alpha = alpha.clamp(0.0, 1.0) as f64;
+        let rtt = self.final_stats.rtt.as_secs_f64();
+        // the jitter is a one-way delay, so we track half of the rtt deviation
+        let deviation = (sample - rtt).abs() / 2.0;
+        let jitter = self.final_stats.jitter.as_secs_f64();
+        self.final_stats = FinalStats {
+            rtt: Duration::from_secs_f64((1.0 - alpha) * rtt + alpha * sample),
+            jitter: Duration::from_secs_f64((1.0 - alpha) * jitter + alpha * deviation),
+        };
+    }
+
+    /// Add a new rtt sample to the stats buffer and update the estimates
+    pub(crate) fn record_rtt_sample(
+        &mut self,
+        received_time: WrappedTime,
+        round_trip_delay: Duration,
+    ) {
+        self.sync_stats
+            .push(received_time, SyncStats { round_trip_delay });
+        if let RttEstimator::Ewma { alpha } = self.config.rtt_estimator {
+            self.update_ewma(alpha, round_trip_delay);
+        }
+        self.compute_stats();
+    }
+
+    fn compute_pruned_mean_stats(&mut self) {
         let sample_count = self.sync_stats.len() as f64;
 
         // Find the Mean
@@ -211,11 +443,6 @@ impl PingManager {
             // jitter is based on one-way delay, so we divide by 2
             jitter: Duration::from_secs_f64(final_rtt_stdv / 2.0),
         };
-        trace!(
-            rtt = ?self.final_stats.rtt,
-            jitter = ?self.final_stats.jitter,
-            "Computed stats!"
-        );
     }
 
     /// Received a pong: update
@@ -229,6 +456,13 @@ impl PingManager {
             error!("Received a ping that is not present in the ping-store anymore");
             return;
         };
+        if let Some(ping) = self
+            .recent_pings
+            .iter_mut()
+            .find(|(_, id, _)| *id == pong.ping_id)
+        {
+            ping.2 = true;
+        }
 
         // only update values for the most recent pongs received
         if pong.ping_id > self.most_recent_received_ping {
@@ -243,12 +477,8 @@ impl PingManager {
             trace!(?rtt, ?received_time, ?ping_sent_time, ?server_process_time, ?pong.pong_sent_time, ?pong.ping_received_time, "process pong");
             let round_trip_delay = (rtt - server_process_time).to_std().unwrap_or_default();
 
-            // update stats buffer
-            self.sync_stats
-                .push(received_time, SyncStats { round_trip_delay });
-
-            // recompute stats whenever we get a new pong
-            self.compute_stats();
+            // update stats buffer and recompute stats whenever we get a new pong
+            self.record_rtt_sample(received_time, round_trip_delay);
         }
     }
 
@@ -282,6 +512,9 @@ mod tests {
         let config = PingConfig {
             ping_interval: Duration::from_millis(100),
             stats_buffer_duration: Duration::from_secs(4),
+            rtt_estimator: RttEstimator::default(),
+            time_sync_interval: None,
+            health_event_interval: None,
         };
         let mut ping_manager = PingManager::new(config);
         let mut time_manager = TimeManager::default();
@@ -339,6 +572,109 @@ mod tests {
         // TODO
     }
 
+    /// Feed the same synthetic rtt sequence (a stable rtt of 100ms with some noise, then
+    /// a jump to 200ms) to a ping manager using the given estimator, and return the rtt estimates
+    /// (before the jump, after the jump)
+    fn estimate_synthetic_rtt(rtt_estimator: RttEstimator) -> (Duration, Duration) {
+        let config = PingConfig::default().with_rtt_estimator(rtt_estimator);
+        let mut ping_manager = PingManager::new(config);
+        let mut time_manager = TimeManager::default();
+        let delta = Duration::from_millis(100);
+        let noise = [0, 10, 0, 5, 0, 15, 0, 10];
+        let mut feed = |ping_manager: &mut PingManager, base: u64, count: usize| {
+            for i in 0..count {
+                time_manager.update(delta);
+                ping_manager.update(&time_manager);
+                let sample = Duration::from_millis(base + noise[i % noise.len()]);
+                ping_manager.record_rtt_sample(time_manager.current_time(), sample);
+            }
+        };
+        // 40 samples at 100ms: the whole stats buffer
+        feed(&mut ping_manager, 100, 40);
+        let before = ping_manager.rtt();
+        // 10 samples at 200ms
+        feed(&mut ping_manager, 200, 10);
+        let after = ping_manager.rtt();
+        (before, after)
+    }
+
+    #[test]
+    fn test_rtt_estimators_convergence() {
+        let (pruned_before, pruned_after) = estimate_synthetic_rtt(RttEstimator::PrunedMean);
+        let (mean_before, mean_after) = estimate_synthetic_rtt(RttEstimator::MovingAverage);
+        let (fast_before, fast_after) = estimate_synthetic_rtt(RttEstimator::Ewma { alpha: 0.5 });
+        let (slow_before, slow_after) = estimate_synthetic_rtt(RttEstimator::Ewma { alpha: 0.05 });
+        let (min_before, min_after) = estimate_synthetic_rtt(RttEstimator::MinRtt);
+
+        let ms = Duration::from_millis;
+        // on the stable part, all estimators are close to the true rtt
+        for before in [pruned_before, mean_before, fast_before, slow_before] {
+            assert!(before >= ms(100) && before <= ms(110), "{before:?}");
+        }
+        // the pruned mean ignores the outliers
+        assert!(pruned_before < mean_before);
+        // the min-rtt estimator ignores the noise entirely
+        assert_eq!(min_before, ms(100));
+
+        // after the jump, the buffer still contains mostly old samples
+        assert!(mean_after > mean_before && mean_after < ms(150));
+        assert!(pruned_after < ms(150));
+        assert_eq!(min_after, ms(100));
+        // a high alpha converges quickly, a low alpha is more stable
+        assert!(fast_after > ms(195), "{fast_after:?}");
+        assert!(
+            slow_after > slow_before && slow_after < ms(170),
+            "{slow_after:?}"
+        );
+    }
+
+    #[test]
+    fn test_packet_loss() {
+        let mut ping_manager = PingManager::new(PingConfig::default());
+        let mut time_manager = TimeManager::default();
+        let delta = Duration::from_millis(100);
+        let mut pings = vec![];
+        for _ in 0..4 {
+            time_manager.update(delta);
+            ping_manager.update(&time_manager);
+            pings.push(ping_manager.maybe_prepare_ping(&time_manager).unwrap());
+        }
+        // the pong of the last ping is lost
+        for ping in &pings[..3] {
+            let pong = Pong {
+                ping_id: ping.id,
+                ping_received_time: WrappedTime::default(),
+                pong_sent_time: WrappedTime::default(),
+            };
+            ping_manager.process_pong(&pong, time_manager.current_time());
+        }
+        // the last ping could still get a pong
+        assert_eq!(ping_manager.packet_loss(), 0.0);
+
+        time_manager.update(Duration::from_secs(1));
+        ping_manager.update(&time_manager);
+        assert_eq!(ping_manager.packet_loss(), 0.25);
+
+        // the pings leave the stats buffer
+        time_manager.update(PingConfig::default().stats_buffer_duration);
+        ping_manager.update(&time_manager);
+        assert_eq!(ping_manager.packet_loss(), 0.0);
+    }
+
+    #[test]
+    fn test_rtt_samples() {
+        let mut ping_manager = PingManager::new(PingConfig::default());
+        ping_manager.record_rtt_sample(WrappedTime::new(0), Duration::from_millis(10));
+        ping_manager.record_rtt_sample(WrappedTime::new(100), Duration::from_millis(20));
+        let mut samples = ping_manager.rtt_samples().collect::<Vec<_>>();
+        samples.sort();
+        assert_eq!(
+            samples,
+            vec![Duration::from_millis(10), Duration::from_millis(20)]
+        );
+        assert_eq!(ping_manager.rtt_estimator(), RttEstimator::PrunedMean);
+    }
+
     // #[test]
     // fn test_ping_manager() {
     //     let ping_config = PingConfig {
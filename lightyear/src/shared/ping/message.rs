@@ -2,8 +2,9 @@
 use crate::serialize::reader::Reader;
 use crate::serialize::{SerializationError, ToBytes};
 use crate::shared::ping::store::PingId;
+use crate::shared::tick_manager::Tick;
 use crate::shared::time_manager::WrappedTime;
-use byteorder::WriteBytesExt;
+use byteorder::{NetworkEndian, ReadBytesExt, WriteBytesExt};
 
 // TODO: do we need the ping ids? we could just re-use the message id ?
 /// Ping message; the remote should respond immediately with a pong
@@ -64,3 +65,39 @@ impl ToBytes for Pong {
         })
     }
 }
+
+/// Message periodically sent by the server to stamp the exact server time at which the packet was sent.
+///
+/// The packet header only contains the server tick, so the client uses this stamp to also know
+/// how far into the tick the server was when it sent the packet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeSync {
+    /// server tick at which the message was sent
+    pub tick: Tick,
+    /// fraction of the tick duration that had elapsed on the server since the start of `tick`, between 0.0 and 1.0
+    pub overstep: f32,
+}
+
+impl ToBytes for TimeSync {
+    fn len(&self) -> usize {
+        4
+    }
+
+    fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
+        self.tick.to_bytes(buffer)?;
+        // the overstep is quantized to a u16
+        let overstep = (self.overstep.clamp(0.0, 1.0) * u16::MAX as f32) as u16;
+        buffer.write_u16::<NetworkEndian>(overstep)?;
+        Ok(())
+    }
+
+    fn from_bytes(buffer: &mut Reader) -> Result<Self, SerializationError>
+    where
+        Self: Sized,
+    {
+        Ok(TimeSync {
+            tick: Tick::from_bytes(buffer)?,
+            overstep: buffer.read_u16::<NetworkEndian>()? as f32 / u16::MAX as f32,
+        })
+    }
+}
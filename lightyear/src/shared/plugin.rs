@@ -9,14 +9,19 @@ use crate::prelude::client::ComponentSyncMode;
 use crate::prelude::{
     AppComponentExt, AppMessageExt, ChannelDirection, ChannelRegistry, ComponentRegistry,
     LinkConditionerConfig, MessageRegistry, Mode, ParentSync, PingConfig, PrePredicted,
-    PreSpawnedPlayerObject, ShouldBePredicted, TickConfig,
+    PreSpawnedPlayerObject, RttEstimator, ShouldBePredicted, TickConfig,
 };
+use crate::server::config::ServerConfig;
+use crate::server::initial_sync::InitialSyncComplete;
 use crate::shared::config::SharedConfig;
+use crate::shared::replication::applied::ComponentApplied;
 use crate::shared::replication::authority::AuthorityChange;
-use crate::shared::replication::components::{Controlled, ShouldBeInterpolated};
+use crate::shared::replication::components::{Controlled, InterpolationSnap, ShouldBeInterpolated};
+use crate::shared::rng::{receive_rng_seed, send_rng_seed, DeterministicRng, RngSeed};
+use crate::shared::sets::{ClientMarker, InternalMainSet, NetworkingSchedules, ServerMarker};
 use crate::shared::tick_manager::TickManagerPlugin;
 use crate::shared::time_manager::TimePlugin;
-use crate::transport::io::{IoState, IoStats};
+use crate::transport::io::{BandwidthStats, IoState, IoStats};
 use crate::transport::middleware::compression::CompressionConfig;
 
 #[derive(Default, Debug)]
@@ -100,7 +105,9 @@ impl Plugin for SharedPlugin {
             .register_type::<SharedConfig>()
             .register_type::<TickConfig>()
             .register_type::<PingConfig>()
+            .register_type::<RttEstimator>()
             .register_type::<IoStats>()
+            .register_type::<BandwidthStats>()
             .register_type::<IoState>()
             .register_type::<LinkConditionerConfig>()
             .register_type::<CompressionConfig>();
@@ -110,6 +117,8 @@ impl Plugin for SharedPlugin {
         app.add_plugins(crate::utils::avian2d::Avian2dPlugin);
         #[cfg(feature = "avian3d")]
         app.add_plugins(crate::utils::avian3d::Avian3dPlugin);
+        #[cfg(feature = "serialization_stats")]
+        app.add_plugins(crate::protocol::serialization_stats::SerializationStatsPlugin);
 
         // RESOURCES
         // the SharedPlugin is called after the ClientConfig is inserted
@@ -124,6 +133,8 @@ impl Plugin for SharedPlugin {
         app.insert_resource(ChannelRegistry::new(input_send_interval));
         app.insert_resource(ComponentRegistry::default());
         app.insert_resource(MessageRegistry::default());
+        // keep the schedules provided by the user, if any
+        app.init_resource::<NetworkingSchedules>();
         // NOTE: this tick duration must be the same as any previous existing fixed timesteps
         app.insert_resource(Time::<Fixed>::from_seconds(
             self.config.tick.tick_duration.as_secs_f64(),
@@ -148,6 +159,7 @@ impl Plugin for SharedPlugin {
         app.register_component::<PrePredicted>(ChannelDirection::Bidirectional);
         app.register_component::<ShouldBePredicted>(ChannelDirection::ServerToClient);
         app.register_component::<ShouldBeInterpolated>(ChannelDirection::ServerToClient);
+        app.register_component::<InterpolationSnap>(ChannelDirection::ServerToClient);
         app.register_component::<ParentSync>(ChannelDirection::Bidirectional)
             .add_map_entities();
         app.register_component::<Controlled>(ChannelDirection::ServerToClient)
@@ -156,6 +168,28 @@ impl Plugin for SharedPlugin {
 
         app.register_message::<AuthorityChange>(ChannelDirection::ServerToClient)
             .add_map_entities();
+        app.register_message::<ComponentApplied>(ChannelDirection::ClientToServer)
+            .add_map_entities();
+        app.register_message::<InitialSyncComplete>(ChannelDirection::ServerToClient);
+        app.register_message::<RngSeed>(ChannelDirection::ServerToClient);
+
+        // RNG
+        // the server picks the seed and sends it to the clients
+        let schedules = NetworkingSchedules::of(app);
+        if let Some(server_config) = app.world().get_resource::<ServerConfig>() {
+            let seed = server_config.shared.rng_seed.unwrap_or_else(rand::random);
+            app.insert_resource(DeterministicRng::new(seed));
+            app.add_systems(
+                schedules.receive,
+                send_rng_seed.after(InternalMainSet::<ServerMarker>::EmitEvents),
+            );
+        }
+        if app.world().get_resource::<ClientConfig>().is_some() {
+            app.add_systems(
+                schedules.receive,
+                receive_rng_seed.after(InternalMainSet::<ClientMarker>::EmitEvents),
+            );
+        }
 
         // check that the protocol was built correctly
         app.world().resource::<ComponentRegistry>().check();
@@ -0,0 +1,117 @@
+//! Application-level acks of component updates
+//!
+//! Packet acks only tell the sender that the bytes of an update arrived; the receiver could still discard
+//! the update (for example because it is older than the current state, or because the entity was not spawned yet).
+//!
+//! For components registered with [`add_application_ack`](crate::protocol::component::ComponentRegistration::add_application_ack),
+//! the client sends a [`ComponentApplied`] message every time it applies an update of the component to its world,
+//! which is surfaced on the server as a [`ComponentAppliedEvent`].
+use bevy::ecs::entity::MapEntities;
+use bevy::prelude::{Component, Entity, EntityMapper, Event};
+use serde::{Deserialize, Serialize};
+
+use crate::connection::id::ClientId;
+use crate::prelude::Tick;
+use crate::protocol::component::{ComponentKind, ComponentNetId};
+
+/// Message sent by the client when it applied the update of a component
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ComponentApplied {
+    pub entity: Entity,
+    pub component: ComponentNetId,
+    /// Server tick of the update that was applied
+    pub tick: Tick,
+}
+
+impl MapEntities for ComponentApplied {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.entity = entity_mapper.map_entity(self.entity);
+    }
+}
+
+/// Event emitted on the server when a client confirms that it applied a component update
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComponentAppliedEvent {
+    pub client_id: ClientId,
+    /// The server entity
+    pub entity: Entity,
+    pub component: ComponentKind,
+    /// Tick of the update that was applied
+    pub tick: Tick,
+}
+
+impl ComponentAppliedEvent {
+    /// Returns true if the applied update is an update of the component `C`
+    pub fn is<C: Component>(&self) -> bool {
+        self.component == ComponentKind::of::<C>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Events;
+
+    use crate::prelude::server::Replicate;
+    use crate::prelude::{client, ClientId};
+    use crate::tests::protocol::ComponentSyncModeSimple;
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+
+    use super::*;
+
+    fn applied_events(stepper: &mut BevyStepper) -> Vec<ComponentAppliedEvent> {
+        let mut events = vec![];
+        for _ in 0..5 {
+            stepper.frame_step();
+            events.extend(
+                stepper
+                    .server_app
+                    .world_mut()
+                    .resource_mut::<Events<ComponentAppliedEvent>>()
+                    .drain(),
+            );
+        }
+        events
+    }
+
+    #[test]
+    fn test_component_applied_event() {
+        let mut stepper = BevyStepper::default();
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((Replicate::default(), ComponentSyncModeSimple(1.0)))
+            .id();
+        let events = applied_events(&mut stepper);
+        assert_eq!(events.len(), 1);
+        let event = events[0];
+        assert_eq!(event.client_id, ClientId::Netcode(TEST_CLIENT_ID));
+        assert_eq!(event.entity, server_entity);
+        assert!(event.is::<ComponentSyncModeSimple>());
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .unwrap();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeSimple>(client_entity),
+            Some(&ComponentSyncModeSimple(1.0))
+        );
+
+        // updates are also confirmed, with the tick of the update
+        stepper
+            .server_app
+            .world_mut()
+            .get_mut::<ComponentSyncModeSimple>(server_entity)
+            .unwrap()
+            .0 = 2.0;
+        let events = applied_events(&mut stepper);
+        assert_eq!(events.len(), 1);
+        assert!(events[0].tick > event.tick);
+    }
+}
@@ -2,7 +2,7 @@
 use std::mem;
 
 use crate::client::replication::send::ReplicateToServer;
-use crate::prelude::{ComponentRegistry, Replicating, ReplicationTarget};
+use crate::prelude::{ComponentRegistry, DontReplicate, Replicating, ReplicationTarget};
 use crate::protocol::component::ComponentKind;
 use crate::shared::replication::authority::HasAuthority;
 use bevy::ecs::archetype::ArchetypeEntity;
@@ -33,6 +33,8 @@ pub(crate) struct ReplicatedArchetypes<C: Component> {
     /// ID of the [`Replicating`] component, which indicates that the entity is being replicated.
     /// If this component is not present, we pause all replication (inserts/updates/spawns)
     replicating_component_id: ComponentId,
+    /// ID of the [`DontReplicate`] component, which excludes the entity from replication.
+    dont_replicate_component_id: ComponentId,
     /// ID of the [`HasAuthority`] component, which indicates that the current peer has authority over the entity.
     /// On the client, we only send replication updates if we have authority.
     /// On the server, we still send replication updates even if we don't have authority, because
@@ -66,6 +68,7 @@ impl<C: Component> ReplicatedArchetypes<C> {
         Self {
             replication_component_id: world.init_component::<ReplicateToServer>(),
             replicating_component_id: world.init_component::<Replicating>(),
+            dont_replicate_component_id: world.init_component::<DontReplicate>(),
             has_authority_component_id: Some(world.init_component::<HasAuthority>()),
             generation: ArchetypeGeneration::initial(),
             archetypes: Vec::new(),
@@ -77,6 +80,7 @@ impl<C: Component> ReplicatedArchetypes<C> {
         Self {
             replication_component_id: world.init_component::<ReplicationTarget>(),
             replicating_component_id: world.init_component::<Replicating>(),
+            dont_replicate_component_id: world.init_component::<DontReplicate>(),
             has_authority_component_id: None,
             generation: ArchetypeGeneration::initial(),
             archetypes: Vec::new(),
@@ -95,6 +99,7 @@ pub(crate) struct ReplicatedComponent {
     pub(crate) delta_compression: bool,
     pub(crate) replicate_once: bool,
     pub(crate) override_target: Option<ComponentId>,
+    pub(crate) client_filter: Option<ComponentId>,
     pub(crate) id: ComponentId,
     pub(crate) kind: ComponentKind,
     pub(crate) storage_type: StorageType,
@@ -141,6 +146,7 @@ impl<C: Component> ReplicatedArchetypes<C> {
             .filter(|archetype| {
                 archetype.contains(self.replication_component_id)
                     && archetype.contains(self.replicating_component_id)
+                    && !archetype.contains(self.dont_replicate_component_id)
                     // on the client, we only replicate if we have authority
                     && self
                         .has_authority_component_id
@@ -188,6 +194,10 @@ impl<C: Component> ReplicatedArchetypes<C> {
                         .components()
                         .any(|c| c == replication_metadata.override_target_id)
                         .then_some(replication_metadata.override_target_id);
+                    let client_filter = archetype
+                        .components()
+                        .any(|c| c == replication_metadata.client_filter_id)
+                        .then_some(replication_metadata.client_filter_id);
 
                     let disabled = archetype
                         .components()
@@ -199,6 +209,7 @@ impl<C: Component> ReplicatedArchetypes<C> {
                         delta_compression,
                         replicate_once,
                         override_target,
+                        client_filter,
                         id: component,
                         kind,
                         storage_type,
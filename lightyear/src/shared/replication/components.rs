@@ -1,4 +1,6 @@
 //! Components used for replication
+use std::sync::Arc;
+
 use bevy::ecs::reflect::ReflectComponent;
 use bevy::prelude::{Component, Entity, Reflect};
 use bevy::time::{Timer, TimerMode};
@@ -39,7 +41,27 @@ pub struct Controlled;
 #[reflect(Component)]
 pub struct Replicating;
 
+/// Marker component to indicate that the entity should never be replicated.
+///
+/// Replication is opt-in: only entities that have the `Replicate` bundle are replicated, but some entities can
+/// still get replicated implicitly (for example children of a replicated entity if [`ReplicateHierarchy`] is recursive).
+/// Add this component to make sure that an entity (for example an editor-only or server-internal entity)
+/// is never replicated, even if it has the `Replicate` bundle.
+///
+/// Adding this component to an entity that is already being replicated has the same effect as removing [`Replicating`]:
+/// the replication is paused, but the entity is not despawned on the remote.
+/// Similarly, removing this component does not spawn the entity on the remote; to start replicating an entity later on,
+/// add the `Replicate` bundle at that point instead.
+#[derive(Component, Clone, Copy, Default, PartialEq, Debug, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct DontReplicate;
+
 /// Component that indicates which clients the entity should be replicated to.
+///
+/// The target can be modified at runtime: the entity will be spawned (with all its replicated components)
+/// on the clients that were added to the target, and despawned on the clients that were removed from it.
+/// This is combined with the [`NetworkRelevanceMode`]: the entity is only replicated to clients that are targeted
+/// and for which the entity is relevant.
 #[derive(Component, Clone, Debug, PartialEq, Reflect)]
 #[reflect(Component)]
 pub struct ReplicationTarget {
@@ -172,6 +194,47 @@ impl<C> OverrideTargetComponent<C> {
     }
 }
 
+/// Function used by [`ComponentClientFilter`] to decide if the component of an entity should be replicated to a client
+pub type ComponentClientFilterFn = Arc<dyn Fn(ClientId, Entity) -> bool + Send + Sync>;
+
+/// This component lets you hide a specific component of a replicated entity from some clients,
+/// while the rest of the entity is still replicated to them. (for example a player's hand of cards
+/// should only be visible to that player)
+///
+/// The filter is evaluated on the server every time the entity is replicated, for each client
+/// that would receive the component:
+/// - if it returns false, the component is not sent to that client. If the client already had the component,
+///   it will be removed on the client.
+/// - when a hidden component becomes visible again, its current value is sent to the client.
+///
+/// This is different from [`NetworkRelevanceMode`], which controls the visibility of the whole entity.
+#[derive(Component, Clone)]
+#[repr(transparent)]
+pub struct ComponentClientFilter<C> {
+    filter: ComponentClientFilterFn,
+    _marker: std::marker::PhantomData<C>,
+}
+
+impl<C> ComponentClientFilter<C> {
+    pub fn new(filter: impl Fn(ClientId, Entity) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            filter: Arc::new(filter),
+            _marker: Default::default(),
+        }
+    }
+
+    /// Returns true if the component of `entity` should be replicated to `client_id`
+    pub fn is_visible(&self, client_id: ClientId, entity: Entity) -> bool {
+        (self.filter)(client_id, entity)
+    }
+}
+
+impl<C> std::fmt::Debug for ComponentClientFilter<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ComponentClientFilter").finish()
+    }
+}
+
 #[derive(Debug, Default, Copy, Clone, PartialEq, Reflect)]
 pub enum ReplicationGroupIdBuilder {
     // the group id is the entity id
@@ -325,6 +388,24 @@ pub enum NetworkRelevanceMode {
 #[reflect(Component)]
 pub struct ShouldBeInterpolated;
 
+/// Component that lets the server mark an update of an entity as discontinuous (for example a teleport or a respawn).
+///
+/// Every time this component changes (or is inserted), the interpolated components of the entity that were updated
+/// in the same tick are not interpolated: the `Interpolated` entity keeps its previous value and then jumps directly
+/// to the new value, instead of sliding across the map.
+///
+/// Call [`InterpolationSnap::snap`] on the server when teleporting the entity.
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct InterpolationSnap(u32);
+
+impl InterpolationSnap {
+    /// Mark the updates sent in the current tick as discontinuous
+    pub fn snap(&mut self) {
+        self.0 = self.0.wrapping_add(1);
+    }
+}
+
 /// Indicates that an entity was pre-predicted
 // NOTE: we do not map entities for this component, we want to receive the entities as is
 //  because we already do the mapping at other steps
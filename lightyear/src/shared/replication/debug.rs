@@ -0,0 +1,143 @@
+//! Dump the replication state of a connection, to help debug replication issues
+//!
+//! The dump is a read-only view of the per-client replication state that the server keeps:
+//! - the entities that are currently replicated to the client
+//! - for each replication group, the acked baselines and the updates that are still waiting for an ack
+//! - the number of messages still buffered in each channel
+//!
+//! It implements `Serialize`, so that it can be attached to a bug report.
+//! With the `json` feature, `ReplicationDebugInfo::to_json` serializes it to JSON.
+use bevy::prelude::Entity;
+use serde::{Deserialize, Serialize};
+
+use crate::connection::id::ClientId;
+use crate::packet::message::MessageId;
+use crate::prelude::Tick;
+use crate::shared::replication::components::ReplicationGroupId;
+use crate::shared::replication::send::SpawnAckState;
+
+/// Replication state of a client, as seen by the server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplicationDebugInfo {
+    pub client_id: ClientId,
+    /// Entities that are currently replicated to the client
+    pub visible_entities: Vec<EntityDebugInfo>,
+    /// State of the replication groups that are sent to the client
+    pub groups: Vec<GroupDebugInfo>,
+    /// Number of messages that were not sent (or not acked, for reliable channels) in each channel
+    pub channels: Vec<ChannelDebugInfo>,
+    /// Most recent tick that was acked by the client, across all replication groups
+    pub last_acked_tick: Option<Tick>,
+}
+
+/// An entity that is replicated to the client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntityDebugInfo {
+    pub entity: Entity,
+    pub group_id: ReplicationGroupId,
+    /// Only tracked if [`ReplicationConfig::wait_for_spawn_ack`](crate::prelude::ReplicationConfig::wait_for_spawn_ack) is enabled
+    pub spawn_ack_state: Option<SpawnAckState>,
+}
+
+/// Send-side state of a replication group
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupDebugInfo {
+    pub group_id: ReplicationGroupId,
+    /// Tick of the last update message that was acked by the client.
+    ///
+    /// This is the baseline used for delta-compression.
+    pub ack_tick: Option<Tick>,
+    /// Tick of the last actions message sent for this group
+    pub last_action_tick: Option<Tick>,
+    /// Message id of the next actions message for this group
+    pub actions_next_send_message_id: MessageId,
+    /// Ticks of the update messages that were sent but not acked yet
+    pub unacked_update_ticks: Vec<Tick>,
+    /// Number of entities with actions buffered but not sent yet
+    pub pending_actions: usize,
+    /// Number of entities with updates buffered but not sent yet
+    pub pending_updates: usize,
+    pub accumulated_priority: f32,
+}
+
+/// Number of messages buffered in a channel
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelDebugInfo {
+    pub name: String,
+    pub pending_messages: usize,
+}
+
+impl ReplicationDebugInfo {
+    /// Serialize the dump to a pretty-printed JSON string
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::server::Replicate;
+    use crate::prelude::ReplicationGroup;
+    use crate::server::connection::ConnectionManager;
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+
+    use super::*;
+
+    #[test]
+    fn test_debug_dump() {
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        let mut stepper = BevyStepper::default();
+        let entity = stepper
+            .server_app
+            .world_mut()
+            .spawn(Replicate {
+                group: ReplicationGroup::new_id(3),
+                ..Default::default()
+            })
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let world = stepper.server_app.world();
+        let info = world
+            .resource::<ConnectionManager>()
+            .debug_dump(client_id, world)
+            .unwrap();
+        assert_eq!(info.client_id, client_id);
+        assert_eq!(
+            info.visible_entities,
+            vec![EntityDebugInfo {
+                entity,
+                group_id: ReplicationGroupId(3),
+                spawn_ack_state: None,
+            }]
+        );
+        let group = info
+            .groups
+            .iter()
+            .find(|group| group.group_id == ReplicationGroupId(3))
+            .unwrap();
+        // the spawn action has been sent
+        assert_eq!(group.actions_next_send_message_id, MessageId(1));
+        assert!(group.last_action_tick.is_some());
+        assert!(!info.channels.is_empty());
+
+        let json = serde_json::to_string(&info).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ReplicationDebugInfo>(&json).unwrap(),
+            info
+        );
+        #[cfg(feature = "json")]
+        assert_eq!(
+            info.to_json().unwrap(),
+            serde_json::to_string_pretty(&info).unwrap()
+        );
+
+        // dumping the state of an unknown client fails
+        assert!(world
+            .resource::<ConnectionManager>()
+            .debug_dump(ClientId::Netcode(TEST_CLIENT_ID + 1), world)
+            .is_err());
+    }
+}
@@ -2,9 +2,93 @@
 use bevy::ecs::entity::{EntityHashMap, EntityMapper};
 use bevy::prelude::{Deref, DerefMut, Entity, EntityWorldMut, World};
 use bevy::reflect::Reflect;
+use bevy::utils::HashMap;
 
+/// Bit used to store the [`EntityNamespace`] of a [`NetworkEntityId`]. Bevy never sets this bit
+/// in practice because it is part of the entity generation.
 const MARKED: u64 = 1 << 62;
 
+/// Largest entity index that is reserved for the entities that would collide with an alias.
+///
+/// It is the largest index that is still serialized as a 4-byte varint.
+const MAX_RESERVED_INDEX: u32 = (1 << 30) - 1;
+
+/// Id space in which the entity of a [`NetworkEntityId`] is valid
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect)]
+pub enum EntityNamespace {
+    /// The entity is the sender's local entity; the receiver needs to map it to one of its own entities
+    /// using its [`RemoteEntityMap`]
+    Sender,
+    /// The sender already mapped the entity to the receiver's local entity, so the receiver must
+    /// use it as is (for example when a client sends a message about an entity that was replicated from the server)
+    Receiver,
+}
+
+/// Identity of an entity when it is sent over the network.
+///
+/// A Bevy [`Entity`] is only meaningful in the [`World`] that spawned it: the entity `3v1` on the server
+/// and the entity `3v1` on the client are unrelated, and a predicted entity could alias an entity that
+/// the server maps in later. A [`NetworkEntityId`] makes explicit which peer's id space the entity belongs to,
+/// so that the receiver never confuses one of its own entities with one of the sender's entities.
+///
+/// # Migration
+/// The `u64` representation of a [`NetworkEntityId`] is identical to the bits of the [`Entity`] that is serialized
+/// in replication messages and in components that implement `MapEntities`, so no protocol change is needed.
+/// Code that used the raw entities received from the network can convert them with [`NetworkEntityId::from_bits`]
+/// (or `From<Entity>`), and look them up with [`RemoteEntityMap::get_local_by_network_id`].
+///
+/// The [`RemoteEntityMap`] stores the entities of the remote as [`NetworkEntityId`]s; the local entities
+/// are plain [`Entity`]s.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect)]
+pub struct NetworkEntityId(u64);
+
+impl NetworkEntityId {
+    /// Create a new network id for an entity that belongs to the given namespace
+    pub const fn new(entity: Entity, namespace: EntityNamespace) -> Self {
+        let bits = entity.to_bits() & !MARKED;
+        match namespace {
+            EntityNamespace::Sender => Self(bits),
+            EntityNamespace::Receiver => Self(bits | MARKED),
+        }
+    }
+
+    /// Create a network id from its `u64` representation
+    pub const fn from_bits(bits: u64) -> Self {
+        Self(bits)
+    }
+
+    /// Get the `u64` representation of the network id, as it is sent over the network
+    pub const fn to_bits(self) -> u64 {
+        self.0
+    }
+
+    /// The id space in which [`NetworkEntityId::entity`] is valid
+    pub const fn namespace(self) -> EntityNamespace {
+        if self.0 & MARKED != 0 {
+            EntityNamespace::Receiver
+        } else {
+            EntityNamespace::Sender
+        }
+    }
+
+    /// The entity, in the id space given by [`NetworkEntityId::namespace`]
+    pub const fn entity(self) -> Entity {
+        Entity::from_bits(self.0 & !MARKED)
+    }
+
+    /// The [`Entity`] that is serialized over the network for this id
+    pub(crate) const fn to_network_entity(self) -> Entity {
+        Entity::from_bits(self.0)
+    }
+}
+
+impl From<Entity> for NetworkEntityId {
+    /// Convert an entity received from the network
+    fn from(entity: Entity) -> Self {
+        Self::from_bits(entity.to_bits())
+    }
+}
+
 #[derive(Default, Debug, Reflect, Deref, DerefMut)]
 pub struct EntityMap(pub(crate) EntityHashMap<Entity>);
 
@@ -15,32 +99,89 @@ impl EntityMapper for EntityMap {
     }
 }
 
-#[derive(Default, Debug, Reflect, Deref, DerefMut)]
-pub struct SendEntityMap(pub(crate) EntityHashMap<Entity>);
+#[derive(Debug, Reflect, Deref, DerefMut)]
+pub struct SendEntityMap {
+    #[deref]
+    pub(crate) map: EntityHashMap<Entity>,
+    /// Local entities that the remote knows under a different entity of the sender.
+    ///
+    /// This happens when the replication state is imported from another server: the client keeps
+    /// referring to the entities of the exporting server.
+    /// See [`snapshot`](crate::shared::replication::snapshot).
+    pub(crate) aliases: EntityHashMap<Entity>,
+    /// Local entities that are sent with a reserved entity, because their own entity is also
+    /// the alias of another local entity
+    pub(crate) reserved: EntityHashMap<Entity>,
+    /// Next entity index that can be reserved. The indices are allocated downwards from [`MAX_RESERVED_INDEX`],
+    /// which Bevy never reaches in practice, so they cannot collide with the entities of a server.
+    next_reserved_index: u32,
+}
+
+impl Default for SendEntityMap {
+    fn default() -> Self {
+        Self {
+            map: EntityHashMap::default(),
+            aliases: EntityHashMap::default(),
+            reserved: EntityHashMap::default(),
+            next_reserved_index: MAX_RESERVED_INDEX,
+        }
+    }
+}
+
+impl SendEntityMap {
+    /// Entity of the sender namespace under which the remote knows `local_entity`
+    fn network_entity(&self, local_entity: Entity) -> Entity {
+        self.aliases
+            .get(&local_entity)
+            .or_else(|| self.reserved.get(&local_entity))
+            .copied()
+            .unwrap_or(local_entity)
+    }
+}
 
 impl EntityMapper for SendEntityMap {
     /// Try to map the entity using the map, or return the initial entity if it doesn't work
     fn map_entity(&mut self, entity: Entity) -> Entity {
         // if the entity was mapped, mark it as mapped so we don't map it again on the receive side
-        if let Some(mapped) = self.0.get(&entity) {
+        if let Some(mapped) = self.map.get(&entity) {
             RemoteEntityMap::mark_mapped(*mapped)
         } else {
-            entity
+            self.network_entity(entity)
         }
     }
 }
 
+/// Map from the entities of the remote (in the [`EntityNamespace::Sender`] namespace) to our local entities
+///
+/// The remote entities are stored as [`NetworkEntityId`]s so that they can never be confused with our
+/// own entities.
 #[derive(Default, Debug, Reflect, Deref, DerefMut)]
-pub struct ReceiveEntityMap(pub(crate) EntityHashMap<Entity>);
+pub struct ReceiveEntityMap {
+    #[deref]
+    pub(crate) map: HashMap<NetworkEntityId, Entity>,
+    /// The entities under which the remote knows some of our local entities (the reverse of the
+    /// aliases and reserved entities of the [`SendEntityMap`])
+    pub(crate) aliases: EntityHashMap<Entity>,
+}
+
+impl ReceiveEntityMap {
+    /// Local entity for an entity that the remote received from us
+    fn local_entity(&self, network_entity: Entity) -> Entity {
+        self.aliases
+            .get(&network_entity)
+            .copied()
+            .unwrap_or(network_entity)
+    }
+}
 
 impl EntityMapper for ReceiveEntityMap {
     /// Try to map the entity using the map, or return the initial entity if it doesn't work
     fn map_entity(&mut self, entity: Entity) -> Entity {
-        // if the entity was already mapped on the send side, we don't need to map it again
-        if RemoteEntityMap::is_mapped(entity) {
-            RemoteEntityMap::mark_unmapped(entity)
-        } else {
-            self.0.get(&entity).copied().unwrap_or(entity)
+        let network_id = NetworkEntityId::from(entity);
+        match network_id.namespace() {
+            // if the entity was already mapped on the send side, we don't need to map it again
+            EntityNamespace::Receiver => self.local_entity(network_id.entity()),
+            EntityNamespace::Sender => self.map.get(&network_id).copied().unwrap_or(entity),
         }
     }
 }
@@ -70,7 +211,10 @@ impl RemoteEntityMap {
     /// Insert a new mapping between a remote entity and a local entity
     #[inline]
     pub fn insert(&mut self, remote_entity: Entity, local_entity: Entity) {
-        self.remote_to_local.insert(remote_entity, local_entity);
+        self.remote_to_local.insert(
+            NetworkEntityId::new(remote_entity, EntityNamespace::Sender),
+            local_entity,
+        );
         self.local_to_remote.insert(local_entity, remote_entity);
     }
 
@@ -89,12 +233,80 @@ impl RemoteEntityMap {
     /// in which case we don't want to map it again
     #[inline]
     pub(crate) fn get_local(&self, remote_entity: Entity) -> Option<Entity> {
-        // the remote_entity is actually local, because it has already been mapped!
-        let unmapped = Self::mark_unmapped(remote_entity);
-        if Self::is_mapped(remote_entity) {
-            return Some(unmapped);
+        self.get_local_by_network_id(NetworkEntityId::from(remote_entity))
+    }
+
+    /// Get the local entity corresponding to an entity id received from the network
+    pub fn get_local_by_network_id(&self, network_id: NetworkEntityId) -> Option<Entity> {
+        match network_id.namespace() {
+            // the entity is actually local, because it has already been mapped by the sender!
+            EntityNamespace::Receiver => {
+                Some(self.remote_to_local.local_entity(network_id.entity()))
+            }
+            EntityNamespace::Sender => self.remote_to_local.get(&network_id).copied(),
+        }
+    }
+
+    /// Get the id to use to refer to a local entity when sending it to the remote.
+    ///
+    /// If the entity was replicated from the remote, we use the remote's entity directly.
+    pub fn to_network_id(&self, local_entity: Entity) -> NetworkEntityId {
+        match self.local_to_remote.get(&local_entity) {
+            Some(remote_entity) => NetworkEntityId::new(*remote_entity, EntityNamespace::Receiver),
+            None => NetworkEntityId::new(
+                self.local_to_remote.network_entity(local_entity),
+                EntityNamespace::Sender,
+            ),
+        }
+    }
+
+    /// Make the remote refer to `local_entity` as `alias`, an entity of another sender that the remote
+    /// already knows about
+    pub(crate) fn insert_alias(&mut self, local_entity: Entity, alias: Entity) {
+        self.local_to_remote.aliases.insert(local_entity, alias);
+        self.remote_to_local.aliases.insert(alias, local_entity);
+    }
+
+    /// Get the entity to use when spawning `local_entity` on the remote.
+    ///
+    /// If the entity of `local_entity` is already the alias of another local entity, the remote would
+    /// confuse the two, so `local_entity` is sent with a reserved entity instead.
+    pub(crate) fn reserve_network_entity(&mut self, local_entity: Entity) -> Entity {
+        let network_entity = self.local_to_remote.network_entity(local_entity);
+        if network_entity != local_entity
+            || !self.remote_to_local.aliases.contains_key(&local_entity)
+        {
+            return network_entity;
+        }
+        let reserved = loop {
+            let candidate = Entity::from_raw(self.local_to_remote.next_reserved_index);
+            self.local_to_remote.next_reserved_index -= 1;
+            if !self.remote_to_local.aliases.contains_key(&candidate) {
+                break candidate;
+            }
         };
-        self.remote_to_local.get(&unmapped).copied()
+        self.local_to_remote.reserved.insert(local_entity, reserved);
+        self.remote_to_local.aliases.insert(reserved, local_entity);
+        reserved
+    }
+
+    /// Returns true if the remote knows `local_entity` under an alias
+    pub(crate) fn is_aliased(&self, local_entity: Entity) -> bool {
+        self.local_to_remote.aliases.contains_key(&local_entity)
+    }
+
+    /// Stop using an alias (or a reserved entity) for `local_entity`, for example because the entity
+    /// was despawned on the remote
+    pub(crate) fn remove_alias(&mut self, local_entity: Entity) {
+        for network_entity in [
+            self.local_to_remote.aliases.remove(&local_entity),
+            self.local_to_remote.reserved.remove(&local_entity),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            self.remote_to_local.aliases.remove(&network_entity);
+        }
     }
 
     /// We want to map entities in two situations:
@@ -106,31 +318,28 @@ impl RemoteEntityMap {
     ///
     /// So we use a dead bit on the entity to mark it as mapped. If an entity is already marked as mapped, the receiver won't try
     /// to map it again
+    ///
+    /// The marked bit is the namespace of the corresponding [`NetworkEntityId`].
     pub(crate) const fn mark_mapped(entity: Entity) -> Entity {
-        let mut bits = entity.to_bits();
-        bits |= MARKED;
-        Entity::from_bits(bits)
+        NetworkEntityId::new(entity, EntityNamespace::Receiver).to_network_entity()
     }
 
     pub(crate) const fn mark_unmapped(entity: Entity) -> Entity {
-        let mut bits = entity.to_bits();
-        bits &= !MARKED;
-        Entity::from_bits(bits)
+        NetworkEntityId::from_bits(entity.to_bits()).entity()
     }
 
     /// Returns true if the entity already has been mapped
     pub(crate) const fn is_mapped(entity: Entity) -> bool {
-        entity.to_bits() & MARKED != 0
+        matches!(
+            NetworkEntityId::from_bits(entity.to_bits()).namespace(),
+            EntityNamespace::Receiver
+        )
     }
 
     /// Convert a local entity to a network entity that we can send
     /// We will try to map it to a remote entity if we can
     pub(crate) fn to_remote(&self, local_entity: Entity) -> Entity {
-        if let Some(remote_entity) = self.local_to_remote.get(&local_entity) {
-            Self::mark_mapped(*remote_entity)
-        } else {
-            local_entity
-        }
+        self.to_network_id(local_entity).to_network_entity()
     }
 
     /// Get the remote entity corresponding to the local entity in the entity map
@@ -151,18 +360,22 @@ impl RemoteEntityMap {
 
     /// Remove the entity from our mapping and return the local entity
     pub(super) fn remove_by_remote(&mut self, remote_entity: Entity) -> Option<Entity> {
-        // the entity is actually local, because it has already been mapped!
-        if Self::is_mapped(remote_entity) {
-            let local = Self::mark_unmapped(remote_entity);
-            if let Some(remote) = self.local_to_remote.remove(&local) {
-                self.remote_to_local.remove(&remote);
-                return Some(local);
+        let network_id = NetworkEntityId::from(remote_entity);
+        match network_id.namespace() {
+            // the entity is actually local, because it has already been mapped!
+            EntityNamespace::Receiver => {
+                let local = self.remote_to_local.local_entity(network_id.entity());
+                let remote = self.local_to_remote.remove(&local)?;
+                self.remote_to_local
+                    .remove(&NetworkEntityId::new(remote, EntityNamespace::Sender));
+                Some(local)
+            }
+            EntityNamespace::Sender => {
+                let local = self.remote_to_local.remove(&network_id)?;
+                self.local_to_remote.remove(&local);
+                Some(local)
             }
-        } else if let Some(local) = self.remote_to_local.remove(&remote_entity) {
-            self.local_to_remote.remove(&local);
-            return Some(local);
         }
-        None
     }
 
     pub(crate) fn is_empty(&self) -> bool {
@@ -171,7 +384,10 @@ impl RemoteEntityMap {
 
     fn clear(&mut self) {
         self.local_to_remote.clear();
+        self.local_to_remote.aliases.clear();
+        self.local_to_remote.reserved.clear();
         self.remote_to_local.clear();
+        self.remote_to_local.aliases.clear();
     }
 }
 
@@ -192,6 +408,44 @@ mod tests {
         assert!(RemoteEntityMap::is_mapped(entity));
     }
 
+    /// Test that the network ids use the same representation as the marked entities,
+    /// and that they are resolved in the correct namespace
+    #[test]
+    fn test_network_entity_id() {
+        let local = Entity::from_raw(1);
+        let remote = Entity::from_raw(2);
+        let mut entity_map = RemoteEntityMap::default();
+        entity_map.insert(remote, local);
+
+        let sender_id = NetworkEntityId::new(remote, EntityNamespace::Sender);
+        assert_eq!(sender_id.namespace(), EntityNamespace::Sender);
+        assert_eq!(sender_id.entity(), remote);
+        assert_eq!(sender_id.to_bits(), remote.to_bits());
+        assert_eq!(entity_map.get_local_by_network_id(sender_id), Some(local));
+        // the remote entities are keyed by their network id
+        assert_eq!(entity_map.remote_to_local.get(&sender_id), Some(&local));
+
+        // an entity that we replicated from the remote is sent back in the remote's namespace
+        let receiver_id = entity_map.to_network_id(local);
+        assert_eq!(receiver_id.namespace(), EntityNamespace::Receiver);
+        assert_eq!(receiver_id.entity(), remote);
+        assert_eq!(
+            receiver_id.to_bits(),
+            RemoteEntityMap::mark_mapped(remote).to_bits()
+        );
+        // ... and the remote resolves it to its own entity without mapping it
+        let remote_map = RemoteEntityMap::default();
+        assert_eq!(
+            remote_map.get_local_by_network_id(receiver_id),
+            Some(remote)
+        );
+
+        // an entity that is unknown to the remote is not aliased to one of its own entities
+        let unknown_id = entity_map.to_network_id(Entity::from_raw(3));
+        assert_eq!(unknown_id.namespace(), EntityNamespace::Sender);
+        assert_eq!(remote_map.get_local_by_network_id(unknown_id), None);
+    }
+
     // An entity gets replicated from server to client,
     // then a component gets removed from that entity on server,
     // that component should also removed on client as well.
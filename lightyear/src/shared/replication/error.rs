@@ -14,4 +14,6 @@ pub enum ReplicationError {
     MessageProtocolError(#[from] crate::protocol::message::MessageError),
     #[error(transparent)]
     ComponentProtocolError(#[from] crate::protocol::component::ComponentError),
+    #[error("replication snapshot has version {found}, expected {expected}")]
+    SnapshotVersionMismatch { expected: u16, found: u16 },
 }
@@ -6,13 +6,14 @@ use serde::{Deserialize, Serialize};
 
 use crate::prelude::server::ControlledBy;
 use crate::prelude::{
-    MainSet, NetworkRelevanceMode, PrePredicted, Replicated, Replicating, ReplicationGroup,
+    DontReplicate, MainSet, NetworkRelevanceMode, PrePredicted, Replicated, Replicating,
+    ReplicationGroup,
 };
 use crate::server::replication::send::SyncTarget;
 use crate::shared::replication::authority::{AuthorityPeer, HasAuthority};
 use crate::shared::replication::components::{ReplicateHierarchy, ReplicationTarget};
 use crate::shared::replication::{ReplicationPeer, ReplicationSend};
-use crate::shared::sets::{InternalMainSet, InternalReplicationSet};
+use crate::shared::sets::{InternalMainSet, InternalReplicationSet, NetworkingSchedules};
 
 /// This component can be added to an entity to replicate the entity's hierarchy to the remote world.
 /// The `ParentSync` component will be updated automatically when the `Parent` component changes,
@@ -81,6 +82,8 @@ impl<R: ReplicationSend> HierarchySendPlugin<R> {
         >,
         children_query: Query<&Children>,
         child_query: Query<(), (With<ParentSync>, With<Replicating>)>,
+        dont_replicate_query: Query<(), With<DontReplicate>>,
+        parents: Query<&Parent>,
     ) {
         for (
             parent_entity,
@@ -105,6 +108,13 @@ impl<R: ReplicationSend> HierarchySendPlugin<R> {
                     if child_query.get(child).is_ok() {
                         continue;
                     }
+                    // the child (or one of its ancestors below the root) explicitly opted out of replication
+                    if std::iter::successors(Some(child), |e| parents.get(*e).ok().map(Parent::get))
+                        .take_while(|e| *e != parent_entity)
+                        .any(|e| dont_replicate_query.get(e).is_ok())
+                    {
+                        continue;
+                    }
                     trace!("Propagate Replicate through hierarchy: adding Replicate on child: {child:?}");
                     // no need to set the correct parent as it will be set later in the `update_parent_sync` system
                     commands.entity(child).insert((
@@ -193,9 +203,10 @@ impl<R: ReplicationSend> HierarchySendPlugin<R> {
 
 impl<R: ReplicationSend> Plugin for HierarchySendPlugin<R> {
     fn build(&self, app: &mut App) {
+        let schedules = NetworkingSchedules::of(app);
         app.observe(Self::handle_parent_remove);
         app.add_systems(
-            PostUpdate,
+            schedules.send,
             (Self::propagate_replicate, Self::update_parent_sync)
                 .chain()
                 // we don't need to run these every frame, only every send_interval
@@ -249,13 +260,14 @@ impl<R> HierarchyReceivePlugin<R> {
 
 impl<R: ReplicationPeer> Plugin for HierarchyReceivePlugin<R> {
     fn build(&self, app: &mut App) {
+        let schedules = NetworkingSchedules::of(app);
         // REFLECTION
         app.register_type::<ParentSync>();
 
         // TODO: does this work for client replication? (client replicating to other clients via the server?)
         // when we receive a ParentSync update from the remote, update the hierarchy
         app.add_systems(
-            PreUpdate,
+            schedules.receive,
             Self::update_parent
                 .after(InternalMainSet::<R::SetMarker>::Receive)
                 // NOTE: we're putting this in MainSet::Receive so that users can order
@@ -275,7 +287,7 @@ mod tests {
     use crate::prelude::client;
     use crate::prelude::server::Replicate;
     use crate::prelude::ReplicationGroup;
-    use crate::shared::replication::components::ReplicateHierarchy;
+    use crate::shared::replication::components::{DontReplicate, ReplicateHierarchy, Replicating};
     use crate::shared::replication::hierarchy::ParentSync;
     use crate::tests::protocol::*;
     use crate::tests::stepper::BevyStepper;
@@ -513,4 +525,53 @@ mod tests {
             &ParentSync(Some(server_parent))
         );
     }
+
+    #[test]
+    fn test_propagate_hierarchy_dont_replicate() {
+        let (mut stepper, grandparent, parent, child) = setup_hierarchy();
+
+        // the parent opted out of replication, so neither it nor its child should be replicated
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(parent)
+            .insert(DontReplicate);
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(grandparent)
+            .insert(Replicate::default());
+
+        stepper.frame_step();
+        stepper.frame_step();
+
+        assert!(stepper
+            .server_app
+            .world()
+            .get::<Replicating>(parent)
+            .is_none());
+        assert!(stepper
+            .server_app
+            .world()
+            .get::<Replicating>(child)
+            .is_none());
+        assert!(stepper
+            .client_app
+            .world_mut()
+            .query_filtered::<Entity, With<ComponentSyncModeFull>>()
+            .get_single(stepper.client_app.world())
+            .is_ok());
+        assert!(stepper
+            .client_app
+            .world_mut()
+            .query_filtered::<Entity, With<ComponentSyncModeSimple>>()
+            .get_single(stepper.client_app.world())
+            .is_err());
+        assert!(stepper
+            .client_app
+            .world_mut()
+            .query_filtered::<Entity, With<ComponentSyncModeOnce>>()
+            .get_single(stepper.client_app.world())
+            .is_err());
+    }
 }
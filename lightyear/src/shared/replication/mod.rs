@@ -25,8 +25,10 @@ use crate::shared::replication::components::ReplicationGroupId;
 
 pub mod components;
 
+pub(crate) mod applied;
 pub(crate) mod archetypes;
 pub(crate) mod authority;
+pub mod debug;
 pub mod delta;
 pub mod entity_map;
 pub mod error;
@@ -37,6 +39,7 @@ pub(crate) mod prespawn;
 pub(crate) mod receive;
 pub(crate) mod resources;
 pub(crate) mod send;
+pub mod snapshot;
 pub(crate) mod systems;
 
 /// Serialize Entity as two varints for the index and generation (because they will probably be low).
@@ -195,7 +198,7 @@ impl ToBytes for SendEntityActionsMessage {
 #[derive(Clone, PartialEq, Debug)]
 pub struct EntityActionsMessage {
     sequence_id: MessageId,
-    group_id: ReplicationGroupId,
+    pub(crate) group_id: ReplicationGroupId,
     // TODO: for better compression, we should use columnar storage
     // we use vec but the order of entities should not matter
     pub(crate) actions: Vec<(Entity, EntityActions)>,
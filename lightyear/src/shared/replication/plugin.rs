@@ -19,7 +19,72 @@ pub struct ReplicationConfig {
     /// How often we send replication updates.
     ///
     /// Set to `Duration::default()` to send updates every frame.
+    ///
+    /// If the interval is longer than the tick duration, only the latest value of each component is sent:
+    /// the intermediate values between two sends are not replicated.
+    ///
+    /// This is ignored if [`send_interval_ticks`](Self::send_interval_ticks) is set.
+    ///
+    /// On the server, [`SharedConfig::server_replication_send_interval`](crate::prelude::SharedConfig::server_replication_send_interval)
+    /// should be set to the same interval (see [`effective_send_interval`](Self::effective_send_interval)).
     pub send_interval: Duration,
+    /// If set, replication updates are sent once every `n` ticks instead of using [`send_interval`](Self::send_interval).
+    ///
+    /// A time-based interval that is not a multiple of the tick duration sends an uneven number of
+    /// ticks of updates on each send; with a tick-based interval the sends stay aligned with the simulation.
+    /// The updates are sent on the first frame where at least `n` ticks have elapsed since the last send.
+    pub send_interval_ticks: Option<u16>,
+    /// If true, we don't send any updates for a replication group until the remote has acknowledged
+    /// the spawn of every entity in the group.
+    ///
+    /// The spawn is sent reliably, so it will be retransmitted until it is acked; this guarantees that
+    /// updates only arrive for entities that already exist on the remote.
+    pub wait_for_spawn_ack: bool,
+    /// How the receiver orders the component updates, which are sent unreliably and can arrive out of order.
+    pub updates_ordering: UpdatesOrdering,
+    /// If true, the sender reuses the replication metadata (buffers, spawn tracking) of despawned entities
+    /// that were in their own replication group for the entities that get spawned later, instead of keeping it
+    /// forever and allocating new metadata for every spawned entity.
+    ///
+    /// This reduces allocations when entities are spawned and despawned at a high rate (for example projectiles).
+    /// The entities themselves are still despawned and spawned on the remote: Bevy already recycles
+    /// the despawned entity slots with a new generation.
+    pub entity_pooling: bool,
+    /// Maximum number of entities whose updates are sent to each remote on a single send.
+    ///
+    /// The replication groups with the highest accumulated priority are sent first; the updates of the
+    /// other groups are deferred to the next sends, and their priority keeps accumulating so that they
+    /// are eventually sent. A group is never split: a group that is bigger than the limit is sent on its own.
+    ///
+    /// This bounds the cost of a send and the size of the burst of packets when many entities change at once.
+    /// If None, all the updates are sent.
+    pub max_entities_per_tick: Option<usize>,
+    /// Maximum number of entities that are spawned on a newly connected client on a single send.
+    ///
+    /// When a client connects, the whole replicated world is sent to it. With a limit, the replication groups
+    /// are sent in batches over multiple sends, highest priority first, instead of in a single burst.
+    /// See [`initial_sync`](crate::server::initial_sync) for more details.
+    ///
+    /// Only used by the server. If None, the whole world is sent at once.
+    pub initial_sync_entities_per_tick: Option<usize>,
+}
+
+/// Ordering guarantee applied by the receiver to component updates.
+///
+/// Every update is stamped with the remote tick at which it was sent; updates that are older than
+/// the last update applied are discarded, so that a stale packet cannot overwrite a more recent state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Reflect)]
+pub enum UpdatesOrdering {
+    /// Discard an update message if we have already applied an update message with a more recent tick
+    /// for the same replication group.
+    PerGroup,
+    /// Discard a component update if we have already applied a more recent value for that component
+    /// of the entity.
+    ///
+    /// A stale update for one component (e.g. `Health`) does not prevent the other components of the message
+    /// (e.g. `Transform`) from being applied if they are more recent.
+    #[default]
+    PerComponent,
 }
 
 #[derive(Clone, Copy, Debug, Reflect)]
@@ -42,11 +107,25 @@ pub enum SendUpdatesMode {
     SinceLastSend,
 }
 
+impl ReplicationConfig {
+    /// Duration between two sends of replication updates
+    pub fn effective_send_interval(&self, tick_duration: Duration) -> Duration {
+        self.send_interval_ticks
+            .map_or(self.send_interval, |ticks| tick_duration * ticks as u32)
+    }
+}
+
 impl Default for ReplicationConfig {
     fn default() -> Self {
         Self {
             send_updates_mode: SendUpdatesMode::SinceLastAck,
             send_interval: Duration::default(),
+            send_interval_ticks: None,
+            wait_for_spawn_ack: false,
+            updates_ordering: UpdatesOrdering::default(),
+            entity_pooling: false,
+            max_entities_per_tick: None,
+            initial_sync_entities_per_tick: None,
         }
     }
 }
@@ -88,10 +167,12 @@ pub(crate) mod receive {
 
 pub(crate) mod send {
     use super::*;
-    use crate::prelude::{Replicating, ReplicationGroup, TimeManager};
+    use crate::prelude::{Replicating, ReplicationGroup, Tick, TickManager, TimeManager};
+    use crate::shared::sets::NetworkingSchedules;
 
     pub(crate) struct ReplicationSendPlugin<R> {
         send_interval: Duration,
+        send_interval_ticks: Option<u16>,
         clean_interval: Duration,
         _marker: std::marker::PhantomData<R>,
     }
@@ -99,13 +180,34 @@ pub(crate) mod send {
     #[derive(Resource, Debug)]
     pub(crate) struct SendIntervalTimer<R: Send + Sync + 'static> {
         pub(crate) timer: Option<Timer>,
+        /// If set, we send every `n` ticks instead of using the timer
+        send_interval_ticks: Option<u16>,
+        /// Tick of the last frame where we sent replication updates
+        pub(crate) last_send_tick: Option<Tick>,
+        /// True if the tick-based interval has elapsed during the current frame
+        ticks_elapsed: bool,
         _marker: std::marker::PhantomData<R>,
     }
 
+    impl<R: Send + Sync + 'static> SendIntervalTimer<R> {
+        /// Returns true if we should send replication updates during the current frame
+        pub(crate) fn should_send(&self) -> bool {
+            if self.send_interval_ticks.is_some() {
+                return self.ticks_elapsed;
+            }
+            self.timer.as_ref().map_or(true, |timer| timer.finished())
+        }
+    }
+
     impl<R: Send + Sync + 'static> ReplicationSendPlugin<R> {
-        pub(crate) fn new(tick_interval: Duration, send_interval: Duration) -> Self {
+        pub(crate) fn new(
+            tick_interval: Duration,
+            send_interval: Duration,
+            send_interval_ticks: Option<u16>,
+        ) -> Self {
             Self {
                 send_interval,
+                send_interval_ticks,
                 // TODO: find a better constant for the clean interval?
                 clean_interval: tick_interval * (i16::MAX as u32 / 3),
                 _marker: std::marker::PhantomData,
@@ -115,9 +217,18 @@ pub(crate) mod send {
         /// Tick the timer that controls when we buffer replication updates
         fn tick_send_interval_timer(
             time_manager: Res<TimeManager>,
+            tick_manager: Res<TickManager>,
             mut timer: ResMut<SendIntervalTimer<R>>,
         ) {
-            if let Some(timer) = &mut timer.timer {
+            if let Some(interval) = timer.send_interval_ticks {
+                let tick = tick_manager.tick();
+                timer.ticks_elapsed = timer
+                    .last_send_tick
+                    .map_or(true, |last| tick - last >= interval as i16);
+                if timer.ticks_elapsed {
+                    timer.last_send_tick = Some(tick);
+                }
+            } else if let Some(timer) = &mut timer.timer {
                 timer.tick(time_manager.delta());
             }
         }
@@ -152,6 +263,7 @@ pub(crate) mod send {
 
     impl<R: ReplicationSend> Plugin for ReplicationSendPlugin<R> {
         fn build(&self, app: &mut App) {
+            let schedules = NetworkingSchedules::of(app);
             // PLUGINS
             if !app.is_plugin_added::<shared::SharedPlugin>() {
                 app.add_plugins(shared::SharedPlugin);
@@ -166,23 +278,23 @@ pub(crate) mod send {
                 } else {
                     Some(Timer::new(self.send_interval, TimerMode::Repeating))
                 },
+                send_interval_ticks: self.send_interval_ticks,
+                last_send_tick: None,
+                ticks_elapsed: false,
                 _marker: std::marker::PhantomData,
             });
 
             // SETS
             app.configure_sets(
-                PostUpdate,
+                schedules.send,
+                MainSet::Simulation.before(InternalReplicationSet::<R::SetMarker>::All),
+            );
+            app.configure_sets(
+                schedules.send,
                 (
                     // only send messages if the timer has finished
-                    InternalReplicationSet::<R::SetMarker>::SendMessages.run_if(
-                        |timer: Res<SendIntervalTimer<R>>| {
-                            if let Some(timer) = &timer.timer {
-                                timer.finished()
-                            } else {
-                                true
-                            }
-                        },
-                    ),
+                    InternalReplicationSet::<R::SetMarker>::SendMessages
+                        .run_if(|timer: Res<SendIntervalTimer<R>>| timer.should_send()),
                     (
                         InternalReplicationSet::<R::SetMarker>::BeforeBuffer,
                         InternalReplicationSet::<R::SetMarker>::BufferResourceUpdates,
@@ -224,11 +336,11 @@ pub(crate) mod send {
             );
             // SYSTEMS
             app.add_systems(
-                PreUpdate,
+                schedules.receive,
                 ReplicationSendPlugin::<R>::tick_send_interval_timer.after(MainSet::Receive),
             );
             app.add_systems(
-                PostUpdate,
+                schedules.send,
                 (
                     ReplicationSendPlugin::<R>::tick_replication_group_timers
                         .in_set(InternalReplicationSet::<R::SetMarker>::BeforeBuffer),
@@ -248,13 +360,14 @@ pub(crate) mod send {
 pub(crate) mod shared {
     use crate::client::replication::send::ReplicateToServer;
     use crate::prelude::{
-        NetworkRelevanceMode, PrePredicted, RemoteEntityMap, ReplicateHierarchy, Replicated,
-        ReplicationConfig, ReplicationGroup, ReplicationTarget, ShouldBePredicted, TargetEntity,
+        EntityNamespace, NetworkEntityId, NetworkRelevanceMode, PrePredicted, RemoteEntityMap,
+        ReplicateHierarchy, Replicated, ReplicationConfig, ReplicationGroup, ReplicationTarget,
+        ShouldBePredicted, TargetEntity, UpdatesOrdering,
     };
     use crate::shared::replication::authority::{AuthorityPeer, HasAuthority};
     use crate::shared::replication::components::{
-        Controlled, Replicating, ReplicationGroupId, ReplicationGroupIdBuilder,
-        ShouldBeInterpolated,
+        Controlled, DontReplicate, InterpolationSnap, Replicating, ReplicationGroupId,
+        ReplicationGroupIdBuilder, ShouldBeInterpolated,
     };
     use crate::shared::replication::entity_map::{InterpolatedEntityMap, PredictedEntityMap};
     use crate::shared::replication::network_target::NetworkTarget;
@@ -269,19 +382,24 @@ pub(crate) mod shared {
                 .register_type::<Replicated>()
                 .register_type::<Controlled>()
                 .register_type::<Replicating>()
+                .register_type::<DontReplicate>()
                 .register_type::<ReplicationTarget>()
                 .register_type::<ReplicateToServer>()
                 .register_type::<ReplicateHierarchy>()
                 .register_type::<ReplicationGroupIdBuilder>()
                 .register_type::<ReplicationGroup>()
                 .register_type::<ReplicationConfig>()
+                .register_type::<UpdatesOrdering>()
                 .register_type::<ReplicationGroupId>()
                 .register_type::<NetworkRelevanceMode>()
                 .register_type::<NetworkTarget>()
                 .register_type::<ShouldBeInterpolated>()
+                .register_type::<InterpolationSnap>()
                 .register_type::<PrePredicted>()
                 .register_type::<ShouldBePredicted>()
                 .register_type::<RemoteEntityMap>()
+                .register_type::<NetworkEntityId>()
+                .register_type::<EntityNamespace>()
                 .register_type::<PredictedEntityMap>()
                 .register_type::<HasAuthority>()
                 .register_type::<AuthorityPeer>()
@@ -289,3 +407,46 @@ pub(crate) mod shared {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::default;
+
+    use crate::client::config::ClientConfig;
+    use crate::client::connection::ConnectionManager;
+    use crate::prelude::{SharedConfig, TickConfig};
+    use crate::tests::stepper::BevyStepper;
+
+    use super::*;
+
+    #[test]
+    fn test_send_interval_ticks() {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let mut client_config = ClientConfig::default();
+        client_config.replication.send_interval_ticks = Some(3);
+        // a frame is not a multiple of the tick duration
+        let mut stepper = BevyStepper::new(shared_config, client_config, Duration::from_millis(12));
+        stepper.init();
+
+        let mut sends = vec![];
+        for _ in 0..50 {
+            stepper.frame_step();
+            let timer = stepper
+                .client_app
+                .world()
+                .resource::<send::SendIntervalTimer<ConnectionManager>>();
+            if timer.should_send() {
+                sends.push(timer.last_send_tick.unwrap());
+            }
+        }
+        // the updates are sent once every 3 ticks
+        assert!(sends.len() >= 10);
+        for window in sends.windows(2) {
+            assert_eq!(window[1] - window[0], 3);
+        }
+    }
+}
@@ -6,16 +6,20 @@ use super::{EntityActionsMessage, EntityUpdatesMessage, SpawnAction};
 use crate::packet::message::MessageId;
 use crate::prelude::client::Confirmed;
 use crate::prelude::{ClientConnectionManager, ClientId, ServerConnectionManager, Tick};
-use crate::protocol::component::ComponentRegistry;
+use crate::protocol::component::{ComponentNetId, ComponentRegistry};
 use crate::serialize::reader::Reader;
+use crate::serialize::ToBytes;
 use crate::shared::events::connection::ConnectionEvents;
 use crate::shared::replication::authority::{AuthorityPeer, HasAuthority};
 use crate::shared::replication::components::{Replicated, ReplicationGroupId};
+use crate::shared::replication::plugin::UpdatesOrdering;
+use crate::shared::replication::snapshot::ReceiveGroupSnapshot;
 #[cfg(test)]
 use crate::utils::captures::Captures;
 use bevy::ecs::entity::EntityHash;
 use bevy::prelude::{DespawnRecursiveExt, Entity, EntityWorldMut, World};
-use bevy::utils::HashSet;
+use bevy::utils::{HashMap, HashSet};
+use bytes::Bytes;
 use tracing::{debug, error, info, trace, warn};
 #[cfg(feature = "trace")]
 use tracing::{instrument, Level};
@@ -35,6 +39,9 @@ pub struct ReplicationReceiver {
     // BOTH
     /// Buffer to so that we have an ordered receiver per group
     pub(crate) group_channels: EntityHashMap<ReplicationGroupId, GroupChannel>,
+
+    /// How we discard component updates that arrive out of order
+    pub(crate) updates_ordering: UpdatesOrdering,
 }
 
 /// Get `ConnectionEvents` depending on whether we receive from a client or a server
@@ -66,6 +73,63 @@ impl ReplicationReceiver {
             remote_entity_to_group: Default::default(),
             // BOTH
             group_channels: Default::default(),
+            updates_ordering: UpdatesOrdering::default(),
+        }
+    }
+
+    /// Set the ordering guarantee used to discard stale component updates
+    pub(crate) fn with_updates_ordering(mut self, updates_ordering: UpdatesOrdering) -> Self {
+        self.updates_ordering = updates_ordering;
+        self
+    }
+
+    /// Export the state of the replication groups and the entity mapping, to restore it on another server
+    pub(crate) fn snapshot(&self) -> (Vec<ReceiveGroupSnapshot>, Vec<(Entity, Entity)>) {
+        let groups = self
+            .group_channels
+            .iter()
+            .map(|(group_id, channel)| ReceiveGroupSnapshot {
+                group_id: *group_id,
+                actions_pending_recv_message_id: channel.actions_pending_recv_message_id,
+                latest_tick: channel.latest_tick,
+                remote_entities: channel.remote_entities.iter().copied().collect(),
+            })
+            .collect();
+        let entity_map = self
+            .remote_entity_map
+            .remote_to_local
+            .iter()
+            .map(|(remote, local)| (remote.entity(), *local))
+            .collect();
+        (groups, entity_map)
+    }
+
+    /// Restore the state exported by [`Self::snapshot`].
+    ///
+    /// `map_local_entity` converts the local entities of the exporting server to the local entities of
+    /// this server; mappings for which it returns `None` are dropped.
+    pub(crate) fn restore(
+        &mut self,
+        groups: Vec<ReceiveGroupSnapshot>,
+        entity_map: Vec<(Entity, Entity)>,
+        mut map_local_entity: impl FnMut(Entity) -> Option<Entity>,
+    ) {
+        for group in groups {
+            let channel = self.group_channels.entry(group.group_id).or_default();
+            channel.actions_pending_recv_message_id = group.actions_pending_recv_message_id;
+            channel.latest_tick = group.latest_tick;
+            channel.latest_update_tick = group.latest_tick;
+            channel.latest_reliable_update_tick = group.latest_tick;
+            for remote_entity in group.remote_entities {
+                channel.remote_entities.insert(remote_entity);
+                self.remote_entity_to_group
+                    .insert(remote_entity, group.group_id);
+            }
+        }
+        for (remote_entity, local_entity) in entity_map {
+            if let Some(local_entity) = map_local_entity(local_entity) {
+                self.remote_entity_map.insert(remote_entity, local_entity);
+            }
         }
     }
 
@@ -147,6 +211,28 @@ impl ReplicationReceiver {
         trace!(?channel, "group channel after buffering");
     }
 
+    /// Buffer a received [`EntityUpdatesMessage`] from the [`EntityReliableUpdatesChannel`](crate::channel::builder::EntityReliableUpdatesChannel).
+    ///
+    /// Reliable updates can be retransmitted after more recent actions or updates were applied for the group,
+    /// so they are not discarded based on the group's latest tick: stale values are discarded per component
+    /// when the update is applied.
+    #[cfg_attr(feature = "trace", instrument(level = Level::INFO, skip_all))]
+    pub(crate) fn recv_reliable_updates(
+        &mut self,
+        updates: EntityUpdatesMessage,
+        remote_tick: Tick,
+    ) {
+        trace!(
+            ?updates,
+            ?remote_tick,
+            "Received reliable replication message"
+        );
+        let channel = self.group_channels.entry(updates.group_id).or_default();
+        channel
+            .buffered_reliable_updates
+            .insert(updates, remote_tick);
+    }
+
     /// Return all the [`EntityActionsMessage`] from our internal buffer that are ready to be read.
     /// For each [`ReplicationGroup`], we return the actions in order.
     ///
@@ -223,6 +309,23 @@ impl ReplicationReceiver {
                     group_channel.latest_tick = Some(tick);
                 }
             }
+            if group_channel
+                .latest_update_tick
+                .is_some_and(|latest_update_tick| tick - latest_update_tick > (i16::MAX / 2))
+            {
+                group_channel.latest_update_tick = None;
+            }
+            if group_channel
+                .latest_reliable_update_tick
+                .is_some_and(|latest_update_tick| tick - latest_update_tick > (i16::MAX / 2))
+            {
+                group_channel.latest_reliable_update_tick = None;
+            }
+            // forget the component ticks that are too old to be compared without wrapping issues
+            group_channel.component_ticks.retain(|_, ticks| {
+                ticks.retain(|_, component_tick| tick - *component_tick <= (i16::MAX / 2));
+                !ticks.is_empty()
+            });
         }
     }
 }
@@ -298,7 +401,7 @@ impl ReplicationReceiver {
             }
         }
 
-        for (entity, actions) in message.actions.into_iter() {
+        for (entity, mut actions) in message.actions.into_iter() {
             debug!(remote_entity = ?entity, "Received entity actions");
 
             // despawn
@@ -334,6 +437,8 @@ impl ReplicationReceiver {
             // inserts
             // TODO: remove updates that are duplicate for the same component
             debug!(remote_entity = ?entity, "Received InsertComponent");
+            // apply the components in the order declared in the protocol
+            component_registry.sort_by_apply_order(&mut actions.insert);
             for component in actions.insert {
                 // TODO: we allocate a new vector for each component but we should
                 //  be able to re-use the same reader
@@ -369,6 +474,7 @@ impl ReplicationReceiver {
 
             // updates
             debug!(remote_entity = ?entity, "Received UpdateComponent");
+            component_registry.sort_by_apply_order(&mut actions.updates);
             for component in actions.updates {
                 // TODO: re-use buffers via pool?
                 let mut reader = Reader::from(component);
@@ -405,7 +511,7 @@ impl ReplicationReceiver {
         if is_history {
             return;
         }
-        for (entity, components) in message.updates.into_iter() {
+        for (entity, mut components) in message.updates.into_iter() {
             debug!(?components, remote_entity = ?entity, "Received UpdateComponent");
 
             // update the entity only if it exists
@@ -417,6 +523,7 @@ impl ReplicationReceiver {
                 debug!("update for entity that doesn't exist: {:?}", entity);
                 continue;
             };
+            component_registry.sort_by_apply_order(&mut components);
             for component in components {
                 let mut reader = Reader::from(component);
                 let _ = component_registry
@@ -553,6 +660,7 @@ impl ReplicationReceiver {
                     &mut self.remote_entity_map,
                     &mut self.remote_entity_to_group,
                     events,
+                    self.updates_ordering,
                 );
             });
 
@@ -567,27 +675,51 @@ impl ReplicationReceiver {
                 // (max_readable_tick is the only one we want to actually apply to the world, because the other
                 //  older updates are redundant. The older ticks are included so that we can have a comprehensive
                 //  confirmed history, for example to have a better interpolation)
-                let Some(max_applicable_idx) = channel
+                if let Some(max_applicable_idx) = channel
                     .buffered_updates
                     .max_index_to_apply(channel.latest_tick)
-                else {
-                    return;
-                };
+                {
+                    // pop the oldest until we reach the max applicable index
+                    while channel.buffered_updates.len() > max_applicable_idx {
+                        let (remote_tick, message) = channel.buffered_updates.pop_oldest().unwrap();
+                        let is_history = channel.buffered_updates.len() != max_applicable_idx;
+                        channel.apply_updates_message(
+                            world,
+                            remote,
+                            component_registry,
+                            remote_tick,
+                            is_history,
+                            false,
+                            message,
+                            events,
+                            &mut self.remote_entity_map,
+                            self.updates_ordering,
+                        );
+                    }
+                }
 
-                // pop the oldest until we reach the max applicable index
-                while channel.buffered_updates.len() > max_applicable_idx {
-                    let (remote_tick, message) = channel.buffered_updates.pop_oldest().unwrap();
-                    let is_history = channel.buffered_updates.len() != max_applicable_idx;
-                    channel.apply_updates_message(
-                        world,
-                        remote,
-                        component_registry,
-                        remote_tick,
-                        is_history,
-                        message,
-                        events,
-                        &mut self.remote_entity_map,
-                    );
+                // reliable updates are not redundant with each other (they can contain different components),
+                // so we apply all of them, and discard stale values per component
+                if let Some(max_applicable_idx) = channel
+                    .buffered_reliable_updates
+                    .max_index_to_apply(channel.latest_tick)
+                {
+                    while channel.buffered_reliable_updates.len() > max_applicable_idx {
+                        let (remote_tick, message) =
+                            channel.buffered_reliable_updates.pop_oldest().unwrap();
+                        channel.apply_updates_message(
+                            world,
+                            remote,
+                            component_registry,
+                            remote_tick,
+                            false,
+                            true,
+                            message,
+                            events,
+                            &mut self.remote_entity_map,
+                            UpdatesOrdering::PerComponent,
+                        );
+                    }
                 }
             })
     }
@@ -604,8 +736,20 @@ pub struct GroupChannel {
     pub(crate) actions_recv_message_buffer: BTreeMap<MessageId, (Tick, EntityActionsMessage)>,
     // updates
     pub(crate) buffered_updates: UpdatesBuffer,
+    /// Updates received on the [`EntityReliableUpdatesChannel`](crate::channel::builder::EntityReliableUpdatesChannel)
+    pub(crate) buffered_reliable_updates: UpdatesBuffer,
     /// remote tick of the latest update/action that we applied to the local group
     pub latest_tick: Option<Tick>,
+    /// remote tick of the most recent actions or updates message that we applied to the local group.
+    /// Used to discard stale updates with [`UpdatesOrdering::PerGroup`]
+    pub(crate) latest_update_tick: Option<Tick>,
+    /// remote tick of the most recent message received on the
+    /// [`EntityReliableUpdatesChannel`](crate::channel::builder::EntityReliableUpdatesChannel)
+    /// that we applied to the local group
+    pub(crate) latest_reliable_update_tick: Option<Tick>,
+    /// remote tick of the latest value that we applied for each component of each remote entity.
+    /// Used to discard stale updates with [`UpdatesOrdering::PerComponent`]
+    pub(crate) component_ticks: EntityHashMap<Entity, HashMap<ComponentNetId, Tick>>,
 }
 
 impl Default for GroupChannel {
@@ -615,7 +759,11 @@ impl Default for GroupChannel {
             actions_pending_recv_message_id: MessageId(0),
             actions_recv_message_buffer: BTreeMap::new(),
             buffered_updates: UpdatesBuffer::default(),
+            buffered_reliable_updates: UpdatesBuffer::default(),
             latest_tick: None,
+            latest_update_tick: None,
+            latest_reliable_update_tick: None,
+            component_ticks: Default::default(),
         }
     }
 }
@@ -816,6 +964,7 @@ impl GroupChannel {
         remote_entity_map: &mut RemoteEntityMap,
         remote_entity_to_group: &mut EntityHashMap<Entity, ReplicationGroupId>,
         events: &mut ConnectionEvents,
+        updates_ordering: UpdatesOrdering,
     ) {
         let group_id = message.group_id;
         debug!(?remote_tick, ?message, "Received replication actions");
@@ -833,11 +982,15 @@ impl GroupChannel {
 
                     if let Some(local_entity) = remote_entity_map.get_local(*remote_entity) {
                         if world.get_entity(local_entity).is_some() {
-                            warn!(
+                            // the sender is resyncing the entity (for example after `ConnectionManager::resync`
+                            // on the server): keep the existing entity and apply the inserts as updates.
+                            // The per-component ticks are reset since the sender reset its baselines
+                            debug!(
                                 ?remote_entity,
                                 ?local_entity,
-                                "Received spawn for an entity that already exists"
+                                "Received spawn for an entity that already exists, resyncing it"
                             );
+                            self.component_ticks.remove(remote_entity);
                             continue;
                         }
                         warn!("Received spawn for an entity that is already in our entity mapping! Not spawning");
@@ -888,7 +1041,7 @@ impl GroupChannel {
             }
         }
 
-        for (entity, actions) in message.actions.into_iter() {
+        for (entity, mut actions) in message.actions.into_iter() {
             debug!(remote_entity = ?entity, "Received entity actions");
 
             // despawn
@@ -896,6 +1049,7 @@ impl GroupChannel {
                 debug!(remote_entity = ?entity, "Received entity despawn");
                 if let Some(local_entity) = remote_entity_map.remove_by_remote(entity) {
                     self.remote_entities.remove(&entity);
+                    self.component_ticks.remove(&entity);
                     // TODO: we despawn all children as well right now, but that might not be what we want?
                     if let Some(entity_mut) = world.get_entity_mut(local_entity) {
                         entity_mut.despawn_recursive();
@@ -925,7 +1079,12 @@ impl GroupChannel {
             // inserts
             // TODO: remove updates that are duplicate for the same component
             debug!(remote_entity = ?entity, "Received InsertComponent");
+            // apply the components in the order declared in the protocol
+            component_registry.sort_by_apply_order(&mut actions.insert);
             for component in actions.insert {
+                if updates_ordering == UpdatesOrdering::PerComponent {
+                    self.record_component_tick(entity, &component, remote_tick);
+                }
                 // TODO: reuse a single reader that reads through the entire message
                 let mut reader = Reader::from(component);
                 let _ = component_registry
@@ -953,13 +1112,24 @@ impl GroupChannel {
             // removals
             trace!(remote_entity = ?entity, ?actions.remove, "Received RemoveComponent");
             for kind in actions.remove {
+                if updates_ordering == UpdatesOrdering::PerComponent {
+                    // make sure that a stale update does not re-insert the removed component
+                    self.component_ticks
+                        .entry(entity)
+                        .or_default()
+                        .insert(kind, remote_tick);
+                }
                 events.push_remove_component(local_entity_mut.id(), kind, Tick(0));
                 component_registry.raw_remove(kind, &mut local_entity_mut);
             }
 
             // updates
             debug!(remote_entity = ?entity, "Received UpdateComponent");
+            component_registry.sort_by_apply_order(&mut actions.updates);
             for component in actions.updates {
+                if updates_ordering == UpdatesOrdering::PerComponent {
+                    self.record_component_tick(entity, &component, remote_tick);
+                }
                 let mut reader = Reader::from(component);
                 let _ = component_registry
                     .raw_write(
@@ -974,9 +1144,36 @@ impl GroupChannel {
                     });
             }
         }
+        if self.latest_update_tick.map_or(true, |t| t < remote_tick) {
+            self.latest_update_tick = Some(remote_tick);
+        }
         self.update_confirmed_tick(world, group_id, remote_tick, remote_entity_map);
     }
 
+    /// Returns true if the component update from `remote_tick` is more recent than the latest
+    /// value we applied for this component of the entity, and records `remote_tick` as the latest tick.
+    ///
+    /// The component bytes start with the [`ComponentNetId`], which we peek without consuming the bytes.
+    fn record_component_tick(
+        &mut self,
+        remote_entity: Entity,
+        component: &Bytes,
+        remote_tick: Tick,
+    ) -> bool {
+        let Ok(net_id) = ComponentNetId::from_bytes(&mut Reader::from(component.clone())) else {
+            // let the component registry report the error when writing the component
+            return true;
+        };
+        let ticks = self.component_ticks.entry(remote_entity).or_default();
+        match ticks.get(&net_id) {
+            Some(latest_tick) if *latest_tick >= remote_tick => false,
+            _ => {
+                ticks.insert(net_id, remote_tick);
+                true
+            }
+        }
+    }
+
     // TODO: should we accept updates from the client that lost authority if they are from a
     //  tick before the moment where we changed authority? seems like we should?
     /// Check if we can accept updates for this entity, based on the authority
@@ -1001,9 +1198,11 @@ impl GroupChannel {
         component_registry: &ComponentRegistry,
         remote_tick: Tick,
         is_history: bool,
+        reliable: bool,
         message: EntityUpdatesMessage,
         events: &mut ConnectionEvents,
         remote_entity_map: &mut RemoteEntityMap,
+        updates_ordering: UpdatesOrdering,
     ) {
         let group_id = message.group_id;
         debug!(?remote_tick, ?message, "Received replication updates");
@@ -1012,7 +1211,24 @@ impl GroupChannel {
         if is_history {
             return;
         }
-        for (entity, components) in message.updates.into_iter() {
+        // the reliable updates contain other components than the unreliable updates, so each stream
+        // tracks its own latest tick: a reliable update must not make the older unreliable updates stale
+        let latest_update_tick = if reliable {
+            self.latest_reliable_update_tick
+        } else {
+            self.latest_update_tick
+        };
+        // updates are sent unreliably, so we could receive a message after a more recent one has been applied
+        let is_most_recent = latest_update_tick.map_or(true, |t| t <= remote_tick);
+        if updates_ordering == UpdatesOrdering::PerGroup && !is_most_recent {
+            trace!(
+                ?remote_tick,
+                ?latest_update_tick,
+                "discard stale updates message"
+            );
+            return;
+        }
+        for (entity, mut components) in message.updates.into_iter() {
             debug!(?components, remote_entity = ?entity, "Received UpdateComponent");
             let Some(mut local_entity_mut) = remote_entity_map.get_by_remote(world, entity) else {
                 // we can get a few buffered updates after the entity has been despawned
@@ -1029,7 +1245,14 @@ impl GroupChannel {
                 debug!("authority check failed for entity: {:?}", entity);
                 continue;
             }
+            component_registry.sort_by_apply_order(&mut components);
             for component in components {
+                if updates_ordering == UpdatesOrdering::PerComponent
+                    && !self.record_component_tick(entity, &component, remote_tick)
+                {
+                    trace!(remote_entity = ?entity, ?remote_tick, "discard stale component update");
+                    continue;
+                }
                 let mut reader = Reader::from(component);
                 let _ = component_registry
                     .raw_write(
@@ -1044,7 +1267,22 @@ impl GroupChannel {
                     });
             }
         }
-        self.update_confirmed_tick(world, group_id, remote_tick, remote_entity_map);
+        if !is_most_recent {
+            return;
+        }
+        // the confirmed tick should never go back in time
+        let is_confirmed_tick = [self.latest_update_tick, self.latest_reliable_update_tick]
+            .into_iter()
+            .flatten()
+            .all(|t| t <= remote_tick);
+        if reliable {
+            self.latest_reliable_update_tick = Some(remote_tick);
+        } else {
+            self.latest_update_tick = Some(remote_tick);
+        }
+        if is_confirmed_tick {
+            self.update_confirmed_tick(world, group_id, remote_tick, remote_entity_map);
+        }
     }
 
     /// Update the Confirmed tick for all entities in the replication group
@@ -1425,4 +1663,242 @@ mod tests {
             local_entity
         );
     }
+
+    fn serialize_component<C: 'static>(
+        component_registry: &ComponentRegistry,
+        mut component: C,
+    ) -> Bytes {
+        let mut writer = crate::serialize::writer::Writer::default();
+        component_registry
+            .serialize(&mut component, &mut writer, None)
+            .unwrap();
+        writer.to_bytes()
+    }
+
+    /// Apply an updates message that is older than an updates message that was already applied,
+    /// and check which component updates are discarded depending on the [`UpdatesOrdering`]
+    fn apply_stale_updates(updates_ordering: UpdatesOrdering) -> World {
+        use crate::tests::protocol::*;
+        use crate::tests::stepper::BevyStepper;
+
+        let stepper = BevyStepper::default();
+        let component_registry = stepper.client_app.world().resource::<ComponentRegistry>();
+        let mut world = World::new();
+        let mut manager = ReplicationReceiver::new().with_updates_ordering(updates_ordering);
+        let mut events = ConnectionEvents::default();
+        let mut channel = GroupChannel::default();
+        let group_id = ReplicationGroupId(0);
+        let remote_entity = Entity::from_raw(1000);
+
+        channel.apply_actions_message(
+            &mut world,
+            None,
+            component_registry,
+            Tick(1),
+            EntityActionsMessage {
+                group_id,
+                sequence_id: MessageId(0),
+                actions: vec![(
+                    remote_entity,
+                    EntityActions {
+                        spawn: SpawnAction::Spawn,
+                        insert: vec![serialize_component(
+                            component_registry,
+                            ComponentSyncModeSimple(1.0),
+                        )],
+                        remove: Default::default(),
+                        updates: vec![],
+                    },
+                )],
+            },
+            &mut manager.remote_entity_map,
+            &mut manager.remote_entity_to_group,
+            &mut events,
+            manager.updates_ordering,
+        );
+        // the most recent update is applied first
+        channel.apply_updates_message(
+            &mut world,
+            None,
+            component_registry,
+            Tick(5),
+            false,
+            false,
+            EntityUpdatesMessage {
+                group_id,
+                last_action_tick: Some(Tick(1)),
+                updates: vec![(
+                    remote_entity,
+                    vec![serialize_component(
+                        component_registry,
+                        ComponentSyncModeSimple(5.0),
+                    )],
+                )],
+            },
+            &mut events,
+            &mut manager.remote_entity_map,
+            manager.updates_ordering,
+        );
+        // then we receive an older update, which also contains a component that was not updated since
+        channel.apply_updates_message(
+            &mut world,
+            None,
+            component_registry,
+            Tick(3),
+            false,
+            false,
+            EntityUpdatesMessage {
+                group_id,
+                last_action_tick: Some(Tick(1)),
+                updates: vec![(
+                    remote_entity,
+                    vec![
+                        serialize_component(component_registry, ComponentSyncModeSimple(3.0)),
+                        serialize_component(component_registry, ComponentSyncModeFull2(3.0)),
+                    ],
+                )],
+            },
+            &mut events,
+            &mut manager.remote_entity_map,
+            manager.updates_ordering,
+        );
+        assert_eq!(channel.latest_update_tick, Some(Tick(5)));
+        world
+    }
+
+    /// With per-component ordering, a stale update for a component is discarded
+    /// but the other components of the message are still applied
+    #[test]
+    fn test_updates_ordering_per_component() {
+        use crate::tests::protocol::*;
+
+        let mut world = apply_stale_updates(UpdatesOrdering::PerComponent);
+        let (simple, full) = world
+            .query::<(&ComponentSyncModeSimple, Option<&ComponentSyncModeFull2>)>()
+            .single(&world);
+        assert_eq!(simple, &ComponentSyncModeSimple(5.0));
+        assert_eq!(full, Some(&ComponentSyncModeFull2(3.0)));
+    }
+
+    /// With per-group ordering, the entire stale message is discarded
+    #[test]
+    fn test_updates_ordering_per_group() {
+        use crate::tests::protocol::*;
+
+        let mut world = apply_stale_updates(UpdatesOrdering::PerGroup);
+        let (simple, full) = world
+            .query::<(&ComponentSyncModeSimple, Option<&ComponentSyncModeFull2>)>()
+            .single(&world);
+        assert_eq!(simple, &ComponentSyncModeSimple(5.0));
+        assert_eq!(full, None);
+    }
+
+    /// A reliable update does not make the older unreliable updates stale
+    #[test]
+    fn test_reliable_updates_do_not_discard_unreliable_updates() {
+        use crate::tests::protocol::*;
+        use crate::tests::stepper::BevyStepper;
+
+        let stepper = BevyStepper::default();
+        let component_registry = stepper.client_app.world().resource::<ComponentRegistry>();
+        let mut world = World::new();
+        let mut manager = ReplicationReceiver::new();
+        let mut events = ConnectionEvents::default();
+        let mut channel = GroupChannel::default();
+        let group_id = ReplicationGroupId(0);
+        let remote_entity = Entity::from_raw(1000);
+
+        channel.apply_actions_message(
+            &mut world,
+            None,
+            component_registry,
+            Tick(1),
+            EntityActionsMessage {
+                group_id,
+                sequence_id: MessageId(0),
+                actions: vec![(
+                    remote_entity,
+                    EntityActions {
+                        spawn: SpawnAction::Spawn,
+                        insert: vec![serialize_component(
+                            component_registry,
+                            ComponentSyncModeSimple(1.0),
+                        )],
+                        remove: Default::default(),
+                        updates: vec![],
+                    },
+                )],
+            },
+            &mut manager.remote_entity_map,
+            &mut manager.remote_entity_to_group,
+            &mut events,
+            UpdatesOrdering::PerGroup,
+        );
+        let updates = |value: f32| EntityUpdatesMessage {
+            group_id,
+            last_action_tick: Some(Tick(1)),
+            updates: vec![(
+                remote_entity,
+                vec![serialize_component(
+                    component_registry,
+                    ComponentSyncModeSimple(value),
+                )],
+            )],
+        };
+        channel.apply_updates_message(
+            &mut world,
+            None,
+            component_registry,
+            Tick(5),
+            false,
+            true,
+            EntityUpdatesMessage {
+                group_id,
+                last_action_tick: Some(Tick(1)),
+                updates: vec![(
+                    remote_entity,
+                    vec![serialize_component(
+                        component_registry,
+                        ComponentSyncModeFull2(5.0),
+                    )],
+                )],
+            },
+            &mut events,
+            &mut manager.remote_entity_map,
+            UpdatesOrdering::PerComponent,
+        );
+        channel.apply_updates_message(
+            &mut world,
+            None,
+            component_registry,
+            Tick(3),
+            false,
+            false,
+            updates(3.0),
+            &mut events,
+            &mut manager.remote_entity_map,
+            UpdatesOrdering::PerGroup,
+        );
+        // an older unreliable update is still discarded
+        channel.apply_updates_message(
+            &mut world,
+            None,
+            component_registry,
+            Tick(2),
+            false,
+            false,
+            updates(2.0),
+            &mut events,
+            &mut manager.remote_entity_map,
+            UpdatesOrdering::PerGroup,
+        );
+
+        let (simple, full) = world
+            .query::<(&ComponentSyncModeSimple, &ComponentSyncModeFull2)>()
+            .single(&world);
+        assert_eq!(simple, &ComponentSyncModeSimple(3.0));
+        assert_eq!(full, &ComponentSyncModeFull2(5.0));
+        assert_eq!(channel.latest_update_tick, Some(Tick(3)));
+        assert_eq!(channel.latest_reliable_update_tick, Some(Tick(5)));
+    }
 }
@@ -4,8 +4,7 @@ use std::marker::PhantomData;
 
 use bevy::app::App;
 use bevy::prelude::{
-    Commands, DetectChanges, IntoSystemConfigs, IntoSystemSetConfigs, Plugin, PostUpdate,
-    PreUpdate, Res, ResMut, Resource,
+    Commands, DetectChanges, IntoSystemConfigs, IntoSystemSetConfigs, Plugin, Res, ResMut, Resource,
 };
 pub use command::{ReplicateResourceExt, StopReplicateResourceExt};
 use serde::{Deserialize, Serialize};
@@ -80,6 +79,7 @@ pub(crate) mod send {
 
     use crate::connection::client::{ClientConnection, NetClient};
     use crate::shared::message::MessageSend;
+    use crate::shared::sets::NetworkingSchedules;
     use bevy::prelude::resource_removed;
     use tracing::trace;
 
@@ -105,8 +105,9 @@ pub(crate) mod send {
     >(
         app: &mut App,
     ) {
+        let schedules = NetworkingSchedules::of(app);
         app.add_systems(
-            PostUpdate,
+            schedules.send,
             (
                 send_resource_removal::<R, S>.run_if(resource_removed::<R>()),
                 send_resource_update::<R, S>,
@@ -190,6 +191,7 @@ pub(crate) mod receive {
     use tracing::trace;
 
     use super::*;
+    use crate::shared::sets::NetworkingSchedules;
 
     pub(crate) struct ResourceReceivePlugin<R> {
         _marker: PhantomData<R>,
@@ -205,8 +207,9 @@ pub(crate) mod receive {
 
     impl<R: ReplicationPeer> Plugin for ResourceReceivePlugin<R> {
         fn build(&self, app: &mut App) {
+            let schedules = NetworkingSchedules::of(app);
             app.configure_sets(
-                PreUpdate,
+                schedules.receive,
                 InternalReplicationSet::<R::SetMarker>::ReceiveResourceUpdates
                     .after(InternalMainSet::<R::SetMarker>::EmitEvents),
             );
@@ -223,17 +226,18 @@ pub(crate) mod receive {
         app: &mut App,
         is_bidirectional: bool,
     ) {
+        let schedules = NetworkingSchedules::of(app);
         // If `is_bidirectional` is  true, that means that the resource can be replicated in both directions.
         // In that case, we need to disable change detection or we would get an infinite loop of updates.
         if is_bidirectional {
             app.add_systems(
-                PreUpdate,
+                schedules.receive,
                 handle_resource_message_bidirectional::<R, S::EventContext>
                     .in_set(InternalReplicationSet::<S::SetMarker>::ReceiveResourceUpdates),
             );
         } else {
             app.add_systems(
-                PreUpdate,
+                schedules.receive,
                 handle_resource_message::<R, S::EventContext>
                     .in_set(InternalReplicationSet::<S::SetMarker>::ReceiveResourceUpdates),
             );
@@ -1,7 +1,9 @@
 //! General struct handling replication
 use std::iter::Extend;
 
-use crate::channel::builder::{EntityActionsChannel, EntityUpdatesChannel};
+use crate::channel::builder::{
+    EntityActionsChannel, EntityReliableUpdatesChannel, EntityUpdatesChannel,
+};
 use bevy::ecs::component::Tick as BevyTick;
 use bevy::ecs::entity::EntityHash;
 use bevy::prelude::Entity;
@@ -9,6 +11,7 @@ use bevy::ptr::Ptr;
 use bevy::utils::{hashbrown, HashMap};
 use bytes::Bytes;
 use crossbeam_channel::Receiver;
+use serde::{Deserialize, Serialize};
 use tracing::{debug, error, trace};
 #[cfg(feature = "trace")]
 use tracing::{instrument, Level};
@@ -23,9 +26,11 @@ use crate::protocol::component::{ComponentKind, ComponentNetId};
 use crate::serialize::writer::Writer;
 use crate::serialize::{SerializationError, ToBytes};
 use crate::shared::replication::components::ReplicationGroupId;
+use crate::shared::replication::debug::GroupDebugInfo;
 use crate::shared::replication::delta::DeltaManager;
 use crate::shared::replication::error::ReplicationError;
 use crate::shared::replication::plugin::{ReplicationConfig, SendUpdatesMode};
+use crate::shared::replication::snapshot::SendGroupSnapshot;
 #[cfg(test)]
 use {
     super::{EntityActionsMessage, EntityUpdatesMessage},
@@ -49,6 +54,18 @@ pub(crate) struct UpdateMessageMetadata {
     tick: Tick,
 }
 
+/// Whether the remote has acknowledged the spawn of a replicated entity.
+///
+/// Only tracked if [`ReplicationConfig::wait_for_spawn_ack`] is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpawnAckState {
+    /// The spawn has been sent but the remote hasn't acknowledged it yet.
+    /// No updates will be sent for the entity's replication group.
+    Pending,
+    /// The remote has acknowledged the spawn
+    Acked,
+}
+
 #[derive(Debug)]
 pub(crate) struct ReplicationSender {
     /// Get notified whenever a message-id that was sent has been received by the remote
@@ -60,6 +77,10 @@ pub(crate) struct ReplicationSender {
     /// when we buffered the message. (so that when it's acked, we know we only need to include updates that happened after that tick,
     /// for that replication group)
     pub(crate) updates_message_id_to_group_id: HashMap<MessageId, UpdateMessageMetadata>,
+    /// Get notified whenever an entity-actions message that was sent has been received by the remote
+    pub(crate) actions_ack_receiver: Receiver<MessageId>,
+    /// Map from the message-id of an entity-actions message to the entities that were spawned in that message
+    pub(crate) spawn_message_id_to_entities: HashMap<MessageId, (ReplicationGroupId, Vec<Entity>)>,
     /// Group channels that have at least 1 replication update or action buffered
     pub group_with_actions: EntityHashSet<ReplicationGroupId>,
     pub group_with_updates: EntityHashSet<ReplicationGroupId>,
@@ -75,12 +96,19 @@ pub(crate) struct ReplicationSender {
 
     replication_config: ReplicationConfig,
     bandwidth_cap_enabled: bool,
+    /// Number of entities whose updates were deferred during the last send because of
+    /// [`ReplicationConfig::max_entities_per_tick`]
+    pub(crate) deferred_entities: usize,
+    /// Channels of replication groups that can never be used again, which are reused for new groups
+    /// (only if [`ReplicationConfig::entity_pooling`] is enabled)
+    group_channel_pool: Vec<GroupChannel>,
 }
 
 impl ReplicationSender {
     pub(crate) fn new(
         updates_ack_receiver: Receiver<MessageId>,
         updates_nack_receiver: Receiver<MessageId>,
+        actions_ack_receiver: Receiver<MessageId>,
         message_send_receiver: Receiver<MessageId>,
         replication_config: ReplicationConfig,
         bandwidth_cap_enabled: bool,
@@ -90,6 +118,8 @@ impl ReplicationSender {
             updates_ack_receiver,
             updates_nack_receiver,
             updates_message_id_to_group_id: Default::default(),
+            actions_ack_receiver,
+            spawn_message_id_to_entities: Default::default(),
             group_with_actions: EntityHashSet::default(),
             group_with_updates: EntityHashSet::default(),
             // pending_unique_components: EntityHashMap::default(),
@@ -98,6 +128,87 @@ impl ReplicationSender {
             // PRIORITY
             message_send_receiver,
             bandwidth_cap_enabled,
+            deferred_entities: 0,
+            group_channel_pool: Vec::new(),
+        }
+    }
+
+    /// Export the state of the replication groups, to restore it on another server
+    pub(crate) fn snapshot(&self) -> Vec<SendGroupSnapshot> {
+        self.group_channels
+            .iter()
+            .map(|(group_id, channel)| {
+                let mut entities = channel.spawned_entities.iter().copied().collect::<Vec<_>>();
+                entities.sort();
+                SendGroupSnapshot {
+                    group_id: *group_id,
+                    actions_next_send_message_id: channel.actions_next_send_message_id,
+                    last_action_tick: channel.last_action_tick,
+                    entities,
+                }
+            })
+            .collect()
+    }
+
+    /// Inspect the state of the replication groups, for debugging
+    pub(crate) fn debug_groups(&self) -> Vec<GroupDebugInfo> {
+        self.group_channels
+            .iter()
+            .map(|(group_id, channel)| {
+                let mut unacked_update_ticks = self
+                    .updates_message_id_to_group_id
+                    .values()
+                    .filter(|metadata| metadata.group_id == *group_id)
+                    .map(|metadata| metadata.tick)
+                    .collect::<Vec<_>>();
+                unacked_update_ticks.sort();
+                GroupDebugInfo {
+                    group_id: *group_id,
+                    ack_tick: channel.ack_tick,
+                    last_action_tick: channel.last_action_tick,
+                    actions_next_send_message_id: channel.actions_next_send_message_id,
+                    unacked_update_ticks,
+                    pending_actions: channel.pending_actions.len(),
+                    pending_updates: channel.pending_updates.len(),
+                    accumulated_priority: channel.accumulated_priority,
+                }
+            })
+            .collect()
+    }
+
+    /// Restore the state of the replication groups exported by [`Self::snapshot`].
+    ///
+    /// The bevy ticks are reset, so that all the component values are sent again.
+    ///
+    /// The entities that the remote already received are aliased in `remote_entity_map`, so that they keep
+    /// being sent with the entity of the exporting server and are not spawned again.
+    pub(crate) fn restore(
+        &mut self,
+        groups: Vec<SendGroupSnapshot>,
+        remote_entity_map: &mut RemoteEntityMap,
+        mut map_local_entity: impl FnMut(Entity) -> Option<Entity>,
+    ) {
+        for group in groups {
+            let channel = self.group_channels.entry(group.group_id).or_default();
+            for exported_entity in group.entities {
+                let Some(local_entity) = map_local_entity(exported_entity) else {
+                    continue;
+                };
+                remote_entity_map.insert_alias(local_entity, exported_entity);
+                channel.spawned_entities.insert(local_entity);
+                // the actions channel is reliable, so the spawns that were not acked yet will still be received
+                if self.replication_config.wait_for_spawn_ack {
+                    channel
+                        .spawn_ack_states
+                        .insert(local_entity, SpawnAckState::Acked);
+                }
+            }
+            channel.actions_next_send_message_id = group.actions_next_send_message_id;
+            channel.last_action_tick = group.last_action_tick;
+            channel.send_tick = None;
+            channel.reliable_send_tick = None;
+            channel.ack_bevy_tick = None;
+            channel.ack_tick = None;
         }
     }
 
@@ -140,6 +251,29 @@ impl ReplicationSender {
         })
     }
 
+    /// Get the [`SpawnAckState`] of an entity, if we are waiting for spawn acks
+    /// and we sent a spawn for that entity.
+    pub(crate) fn spawn_ack_state(&self, entity: Entity) -> Option<SpawnAckState> {
+        self.group_channels
+            .values()
+            .find_map(|channel| channel.spawn_ack_states.get(&entity).copied())
+    }
+
+    /// Reset the send and ack ticks of every replication group, so that the next replication messages
+    /// contain the full state of the groups (without computing diffs from previously acked values).
+    ///
+    /// We also forget about the update messages that are in flight, so that a late ack cannot
+    /// bring back a delta-compression baseline from before the reset.
+    pub(crate) fn reset_group_channels(&mut self) {
+        self.updates_message_id_to_group_id.clear();
+        self.group_channels.values_mut().for_each(|channel| {
+            channel.send_tick = None;
+            channel.reliable_send_tick = None;
+            channel.ack_bevy_tick = None;
+            channel.ack_tick = None;
+        });
+    }
+
     /// Internal bookkeeping:
     /// 1. handle all nack update messages
     pub(crate) fn update(&mut self, world_tick: BevyTick) {
@@ -171,7 +305,8 @@ impl ReplicationSender {
 
                         // TODO: if all clients lost a given message, than we can immediately drop the delta-compression data
                         //  for that tick
-                    } else {
+                    } else if !self.replication_config.entity_pooling {
+                        // (with entity pooling, the channels of despawned groups are removed)
                         error!("Received an update message-id nack but the corresponding group channel does not exist");
                     }
                 }
@@ -211,7 +346,7 @@ impl ReplicationSender {
                     );
                     channel.send_tick = Some(*bevy_tick);
                     channel.accumulated_priority = 0.0;
-                } else {
+                } else if !self.replication_config.entity_pooling {
                     error!(?message_id, ?group_id, "Received a send message-id notification but the corresponding group channel does not exist");
                 }
             } else {
@@ -230,6 +365,20 @@ impl ReplicationSender {
         component_registry: &ComponentRegistry,
         delta_manager: &mut DeltaManager,
     ) {
+        while let Ok(message_id) = self.actions_ack_receiver.try_recv() {
+            if let Some((group_id, entities)) =
+                self.spawn_message_id_to_entities.remove(&message_id)
+            {
+                if let Some(channel) = self.group_channels.get_mut(&group_id) {
+                    for entity in entities {
+                        if let Some(state) = channel.spawn_ack_states.get_mut(&entity) {
+                            debug!(?group_id, ?entity, "Entity spawn acked");
+                            *state = SpawnAckState::Acked;
+                        }
+                    }
+                }
+            }
+        }
         // TODO: handle errors that are not channel::isEmpty
         while let Ok(message_id) = self.updates_ack_receiver.try_recv() {
             // remember to remove the entry from the map to avoid memory leakage
@@ -247,7 +396,7 @@ impl ReplicationSender {
 
                     // update the acks for the delta manager
                     delta_manager.receive_ack(tick, group_id, component_registry);
-                } else {
+                } else if !self.replication_config.entity_pooling {
                     error!("Received an update message-id ack but the corresponding group channel does not exist");
                 }
             } else {
@@ -289,12 +438,32 @@ impl ReplicationSender {
 ///
 /// - all component inserts/removes/updates for an entity to be grouped together in a single message
 impl ReplicationSender {
-    /// Update the base priority for a given group
-    pub(crate) fn update_base_priority(&mut self, group_id: ReplicationGroupId, priority: f32) {
+    /// Get the channel of a replication group, reusing a pooled channel if the group doesn't have one yet
+    fn group_channel_mut(&mut self, group_id: ReplicationGroupId) -> &mut GroupChannel {
         self.group_channels
             .entry(group_id)
-            .or_default()
-            .base_priority = priority;
+            .or_insert_with(|| self.group_channel_pool.pop().unwrap_or_default())
+    }
+
+    /// Update the base priority for a given group
+    pub(crate) fn update_base_priority(&mut self, group_id: ReplicationGroupId, priority: f32) {
+        self.group_channel_mut(group_id).base_priority = priority;
+    }
+
+    /// Only send updates for the group once every `interval` sends.
+    ///
+    /// A shorter interval is applied immediately, but a longer interval is reached progressively
+    /// (the interval grows by one after each update message), so that the updates of an entity
+    /// that moves away slow down gradually instead of freezing abruptly.
+    pub(crate) fn set_update_interval(&mut self, group_id: ReplicationGroupId, interval: u32) {
+        let Some(channel) = self.group_channels.get_mut(&group_id) else {
+            return;
+        };
+        let interval = interval.max(1);
+        channel.target_update_interval = interval;
+        if interval < channel.update_interval {
+            channel.update_interval = interval;
+        }
     }
 
     // TODO: how can I emit metrics here that contain the channel kind?
@@ -306,9 +475,7 @@ impl ReplicationSender {
     // #[cfg_attr(feature = "trace", instrument(level = Level::INFO, skip_all))]
     pub(crate) fn prepare_entity_spawn(&mut self, entity: Entity, group_id: ReplicationGroupId) {
         self.group_with_actions.insert(group_id);
-        self.group_channels
-            .entry(group_id)
-            .or_default()
+        self.group_channel_mut(group_id)
             .pending_actions
             .entry(entity)
             .or_default()
@@ -325,9 +492,7 @@ impl ReplicationSender {
         remote_entity: Entity,
     ) {
         self.group_with_actions.insert(group_id);
-        self.group_channels
-            .entry(group_id)
-            .or_default()
+        self.group_channel_mut(group_id)
             .pending_actions
             .entry(local_entity)
             .or_default()
@@ -337,9 +502,7 @@ impl ReplicationSender {
     #[cfg_attr(feature = "trace", instrument(level = Level::INFO, skip_all))]
     pub(crate) fn prepare_entity_despawn(&mut self, entity: Entity, group_id: ReplicationGroupId) {
         self.group_with_actions.insert(group_id);
-        self.group_channels
-            .entry(group_id)
-            .or_default()
+        self.group_channel_mut(group_id)
             .pending_actions
             .entry(entity)
             .or_default()
@@ -357,9 +520,7 @@ impl ReplicationSender {
         component: Bytes,
     ) {
         self.group_with_actions.insert(group_id);
-        self.group_channels
-            .entry(group_id)
-            .or_default()
+        self.group_channel_mut(group_id)
             .pending_actions
             .entry(entity)
             .or_default()
@@ -375,9 +536,7 @@ impl ReplicationSender {
         kind: ComponentNetId,
     ) {
         self.group_with_actions.insert(group_id);
-        self.group_channels
-            .entry(group_id)
-            .or_default()
+        self.group_channel_mut(group_id)
             .pending_actions
             .entry(entity)
             .or_default()
@@ -393,15 +552,30 @@ impl ReplicationSender {
         raw_data: Bytes,
     ) {
         self.group_with_updates.insert(group_id);
-        self.group_channels
-            .entry(group_id)
-            .or_default()
+        self.group_channel_mut(group_id)
             .pending_updates
             .entry(entity)
             .or_default()
             .push(raw_data);
     }
 
+    /// Buffer the update of a component with [`ReplicationMode::Reliable`](crate::prelude::ReplicationMode::Reliable),
+    /// which will be sent on the [`EntityReliableUpdatesChannel`]
+    #[cfg_attr(feature = "trace", instrument(level = Level::INFO, skip_all))]
+    pub(crate) fn prepare_reliable_component_update(
+        &mut self,
+        entity: Entity,
+        group_id: ReplicationGroupId,
+        raw_data: Bytes,
+    ) {
+        self.group_with_updates.insert(group_id);
+        self.group_channel_mut(group_id)
+            .pending_reliable_updates
+            .entry(entity)
+            .or_default()
+            .push(raw_data);
+    }
+
     /// Create a component update.
     #[cfg_attr(feature = "trace", instrument(level = Level::INFO, skip_all))]
     #[allow(clippy::too_many_arguments)]
@@ -417,7 +591,7 @@ impl ReplicationSender {
         tick: Tick,
         remote_entity_map: &mut RemoteEntityMap,
     ) -> Result<(), ReplicationError> {
-        let group_channel = self.group_channels.entry(group_id).or_default();
+        let group_channel = self.group_channel_mut(group_id);
         // Get the latest acked tick for this entity/component
         let raw_data = group_channel
             .ack_tick
@@ -488,7 +662,11 @@ impl ReplicationSender {
                 let mut actions = std::mem::take(&mut channel.pending_actions);
                 // add any updates for that group
                 if self.group_with_updates.remove(&group_id) {
-                    for (entity, components) in channel.pending_updates.drain() {
+                    for (entity, components) in channel
+                        .pending_updates
+                        .drain()
+                        .chain(channel.pending_reliable_updates.drain())
+                    {
                         actions
                             .entry(entity)
                             .or_default()
@@ -502,6 +680,7 @@ impl ReplicationSender {
                 // This is ok to do even if we don't get an actual send notification because EntityActions messages are
                 // guaranteed to be sent at some point. (since the actions channel is reliable)
                 channel.send_tick = Some(bevy_tick);
+                channel.reliable_send_tick = Some(bevy_tick);
                 channel.ack_tick = Some(tick);
                 let priority = channel.accumulated_priority;
                 let message_id = channel.actions_next_send_message_id;
@@ -567,7 +746,11 @@ impl ReplicationSender {
             // add any updates for that group
             if self.group_with_updates.remove(&group_id) {
                 // drain so that we keep the allocated memory
-                for (entity, components) in channel.pending_updates.drain() {
+                for (entity, components) in channel
+                    .pending_updates
+                    .drain()
+                    .chain(channel.pending_reliable_updates.drain())
+                {
                     actions
                         .entry(entity)
                         .or_default()
@@ -582,6 +765,7 @@ impl ReplicationSender {
             // This is ok to do even if we don't get an actual send notification because EntityActions messages are
             // guaranteed to be sent at some point. (since the actions channel is reliable)
             channel.send_tick = Some(bevy_tick);
+            channel.reliable_send_tick = Some(bevy_tick);
             //  We can consider that we received an ack for the current tick because the message is sent reliably,
             //  so we know that we should eventually receive an ack.
             //  Updates after this insert only get read if the insert was received, so this doesn't introduce any bad behaviour.
@@ -595,6 +779,37 @@ impl ReplicationSender {
             let message_id = channel.actions_next_send_message_id;
             channel.actions_next_send_message_id += 1;
             channel.last_action_tick = Some(tick);
+            let mut spawned_entities = vec![];
+            let mut group_despawned = false;
+            for (entity, action) in actions.iter() {
+                match action.spawn {
+                    SpawnAction::Spawn | SpawnAction::Reuse(_) => {
+                        channel.spawned_entities.insert(*entity);
+                    }
+                    SpawnAction::Despawn => {
+                        channel.spawned_entities.remove(entity);
+                        // the group id is built from the entity, so it will never be used again
+                        group_despawned |= ReplicationGroupId(entity.to_bits()) == group_id;
+                    }
+                    SpawnAction::None => {}
+                }
+            }
+            if self.replication_config.wait_for_spawn_ack {
+                for (entity, action) in actions.iter() {
+                    match action.spawn {
+                        SpawnAction::Spawn | SpawnAction::Reuse(_) => {
+                            channel
+                                .spawn_ack_states
+                                .insert(*entity, SpawnAckState::Pending);
+                            spawned_entities.push(*entity);
+                        }
+                        SpawnAction::Despawn => {
+                            channel.spawn_ack_states.remove(entity);
+                        }
+                        SpawnAction::None => {}
+                    }
+                }
+            }
             // we use SendEntityActionsMessage so that we don't have to convert the hashmap into a vec
             let message = SendEntityActionsMessage {
                 sequence_id: message_id,
@@ -620,11 +835,29 @@ impl ReplicationSender {
                     priority,
                 )?
                 .expect("The entity actions channels should always return a message_id");
+            if !spawned_entities.is_empty() {
+                self.spawn_message_id_to_entities
+                    .insert(message_id, (group_id, spawned_entities));
+            }
 
             // restore the hashmap that we took out, so that we can reuse the allocated memory
             channel.pending_actions = message.actions;
             channel.pending_actions.clear();
 
+            if self.replication_config.entity_pooling
+                && group_despawned
+                && channel.spawned_entities.is_empty()
+            {
+                // SAFETY: we know that the group_channel exists
+                let mut channel = self.group_channels.remove(&group_id).unwrap();
+                channel.clear();
+                trace!(
+                    ?group_id,
+                    "Pooled the channel of a despawned replication group"
+                );
+                self.group_channel_pool.push(channel);
+            }
+
             Ok::<(), PacketError>(())
         })
     }
@@ -667,11 +900,106 @@ impl ReplicationSender {
         writer: &mut Writer,
         message_manager: &mut MessageManager,
     ) -> Result<(), PacketError> {
-        self.group_with_updates.drain().try_for_each(|group_id| {
+        let priority_threshold = message_manager.updates_priority_threshold();
+        let max_entities = self.replication_config.max_entities_per_tick;
+        let mut groups: Vec<_> = self.group_with_updates.drain().collect();
+        if max_entities.is_some() {
+            // send the groups with the highest priority first
+            groups.sort_by(|a, b| {
+                let priority = |group_id| {
+                    self.group_channels
+                        .get(group_id)
+                        .map_or(0.0, |channel| channel.accumulated_priority)
+                };
+                priority(b).total_cmp(&priority(a)).then(a.0.cmp(&b.0))
+            });
+        }
+        let mut sent_entities = 0;
+        self.deferred_entities = 0;
+        groups.into_iter().try_for_each(|group_id| {
             let channel = self.group_channels.get_mut(&group_id).unwrap();
+            // don't send updates until the remote has acked the spawn of all the entities in the group.
+            // We don't update the `send_tick`, so the updates will be collected again on the next send.
+            if channel
+                .spawn_ack_states
+                .values()
+                .any(|state| *state == SpawnAckState::Pending)
+            {
+                trace!(?group_id, "waiting for spawn ack before sending updates");
+                channel.pending_updates.clear();
+                channel.pending_reliable_updates.clear();
+                return Ok(());
+            }
+            // if the connection is congested, we coalesce the updates of low-priority groups.
+            // We don't update the `send_tick`, so the changes will be collected again on the next send.
+            if priority_threshold.is_some_and(|threshold| channel.base_priority < threshold) {
+                trace!(?group_id, "connection is congested, delaying updates");
+                channel.pending_updates.clear();
+                channel.pending_reliable_updates.clear();
+                return Ok(());
+            }
+            // the group is only updated once every `update_interval` sends.
+            // Same as above, the changes will be collected again on the next send.
+            channel.skipped_sends += 1;
+            if channel.skipped_sends < channel.update_interval {
+                trace!(?group_id, "delaying updates until the next update interval");
+                channel.pending_updates.clear();
+                channel.pending_reliable_updates.clear();
+                return Ok(());
+            }
+            // only send the updates of `max_entities_per_tick` entities; the other groups keep their
+            // accumulated priority and are sent on the next sends.
+            if let Some(max_entities) = max_entities {
+                let num_entities = channel.pending_updates.len()
+                    + channel
+                        .pending_reliable_updates
+                        .keys()
+                        .filter(|entity| !channel.pending_updates.contains_key(*entity))
+                        .count();
+                if sent_entities > 0 && sent_entities + num_entities > max_entities {
+                    trace!(?group_id, "too many entities sent, deferring updates");
+                    self.deferred_entities += num_entities;
+                    channel.pending_updates.clear();
+                    channel.pending_reliable_updates.clear();
+                    return Ok(());
+                }
+                sent_entities += num_entities;
+            }
+            channel.skipped_sends = 0;
+            if channel.update_interval < channel.target_update_interval {
+                channel.update_interval += 1;
+            }
+            let priority = channel.accumulated_priority;
+            // the priority is only reset on send notifications if there is a bandwidth cap,
+            // so that the deferred groups are sent first on the next send
+            if max_entities.is_some() && !self.bandwidth_cap_enabled {
+                channel.accumulated_priority = 0.0;
+            }
+            if !channel.pending_reliable_updates.is_empty() {
+                let message = SendEntityUpdatesMessage {
+                    group_id,
+                    last_action_tick: channel.last_action_tick,
+                    updates: std::mem::take(&mut channel.pending_reliable_updates),
+                };
+                message.to_bytes(writer)?;
+                // the reliable channel takes care of retransmitting the message until it is acked,
+                // so we don't need to track the message_id
+                message_manager.buffer_send_with_priority(
+                    writer.split(),
+                    ChannelKind::of::<EntityReliableUpdatesChannel>(),
+                    priority,
+                )?;
+                trace!(?group_id, ?bevy_tick, "Send reliable replication update");
+                channel.reliable_send_tick = Some(bevy_tick);
+                // restore the hashmap that we took out, so that we can reuse the allocated memory
+                channel.pending_reliable_updates = message.updates;
+                channel.pending_reliable_updates.clear();
+            }
+            if channel.pending_updates.is_empty() {
+                return Ok(());
+            }
             let updates = std::mem::take(&mut channel.pending_updates);
             trace!(?group_id, "pending updates: {:?}", updates);
-            let priority = channel.accumulated_priority;
             let message = SendEntityUpdatesMessage {
                 group_id,
                 // TODO: as an optimization (to avoid 1 byte for the Option), we can use `last_action_tick = tick`
@@ -737,6 +1065,8 @@ pub struct GroupChannel {
     /// to collect new replication messages
     pub pending_actions: EntityHashMap<Entity, EntityActions>,
     pub pending_updates: EntityHashMap<Entity, Vec<Bytes>>,
+    /// Updates of the components with [`ReplicationMode::Reliable`](crate::prelude::ReplicationMode::Reliable)
+    pub pending_reliable_updates: EntityHashMap<Entity, Vec<Bytes>>,
     pub actions_next_send_message_id: MessageId,
 
     // TODO: maybe also keep track of which Tick this bevy-tick corresponds to? (will enable doing diff-compression)
@@ -747,6 +1077,11 @@ pub struct GroupChannel {
     ///
     /// at the start, it's `None` (meaning that we send any changes)
     pub send_tick: Option<BevyTick>,
+    /// Bevy Tick when we last sent a reliable update for this group.
+    ///
+    /// Reliable updates are retransmitted by their channel until they are acked, so we only need to send
+    /// the changes that happened after this tick, regardless of update acks.
+    pub reliable_send_tick: Option<BevyTick>,
     /// Bevy Tick when we last received an ack for an update message for this group.
     ///
     /// If a message is acked, we bump the ack_tick to the `send_tick` at which we sent the update.
@@ -761,25 +1096,66 @@ pub struct GroupChannel {
     /// process Updates if they have processed all Actions that happened before them.
     pub last_action_tick: Option<Tick>,
 
+    /// Spawn-ack state of the entities of this group (only tracked if
+    /// [`ReplicationConfig::wait_for_spawn_ack`] is enabled)
+    pub spawn_ack_states: EntityHashMap<Entity, SpawnAckState>,
+    /// Entities of this group whose spawn was sent to the remote, and that were not despawned since
+    pub spawned_entities: EntityHashSet<Entity>,
+
     /// The priority to send the replication group.
     /// This will be reset to base_priority every time we send network updates, unless we couldn't send a message
     /// for this group because of the bandwidth cap, in which case it will be accumulated.
     pub accumulated_priority: f32,
     pub base_priority: f32,
+
+    /// Updates for this group are only sent once every `update_interval` sends that contain changes for the group
+    /// (see [`DistanceUpdateRate`](crate::server::relevance::distance::DistanceUpdateRate))
+    pub update_interval: u32,
+    /// Interval that `update_interval` is fading towards
+    pub(crate) target_update_interval: u32,
+    /// Number of sends since the last update message for this group
+    pub(crate) skipped_sends: u32,
+}
+
+impl GroupChannel {
+    /// Reset the channel to its default state, but keep the memory allocated by its buffers
+    fn clear(&mut self) {
+        let mut channel = GroupChannel {
+            pending_actions: std::mem::take(&mut self.pending_actions),
+            pending_updates: std::mem::take(&mut self.pending_updates),
+            pending_reliable_updates: std::mem::take(&mut self.pending_reliable_updates),
+            spawn_ack_states: std::mem::take(&mut self.spawn_ack_states),
+            spawned_entities: std::mem::take(&mut self.spawned_entities),
+            ..Default::default()
+        };
+        channel.pending_actions.clear();
+        channel.pending_updates.clear();
+        channel.pending_reliable_updates.clear();
+        channel.spawn_ack_states.clear();
+        channel.spawned_entities.clear();
+        *self = channel;
+    }
 }
 
 impl Default for GroupChannel {
     fn default() -> Self {
         Self {
             pending_updates: EntityHashMap::default(),
+            pending_reliable_updates: EntityHashMap::default(),
             pending_actions: EntityHashMap::default(),
             actions_next_send_message_id: MessageId(0),
             send_tick: None,
+            reliable_send_tick: None,
             ack_bevy_tick: None,
             ack_tick: None,
             last_action_tick: None,
+            spawn_ack_states: EntityHashMap::default(),
+            spawned_entities: EntityHashSet::default(),
             accumulated_priority: 0.0,
             base_priority: 1.0,
+            update_interval: 1,
+            target_update_interval: 1,
+            skipped_sends: 0,
         }
     }
 }
@@ -788,6 +1164,7 @@ impl Default for GroupChannel {
 mod tests {
     use crate::prelude::server::Replicate;
     use crate::prelude::ClientId;
+    use crate::prelude::ReplicationGroup;
     use crate::server::connection::ConnectionManager;
 
     use crate::tests::protocol::ComponentSyncModeFull;
@@ -860,6 +1237,167 @@ mod tests {
         // receive a lost notification for the third tick
     }
 
+    /// Test that updates are not sent until the remote has acked the spawn of the entity
+    #[test]
+    fn test_wait_for_spawn_ack() {
+        let mut stepper = BevyStepper::default();
+        macro_rules! sender {
+            () => {
+                stepper
+                    .server_app
+                    .world_mut()
+                    .resource_mut::<ConnectionManager>()
+                    .connections
+                    .get_mut(&ClientId::Netcode(TEST_CLIENT_ID))
+                    .unwrap()
+                    .replication_sender
+            };
+        }
+        sender!().replication_config.wait_for_spawn_ack = true;
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((ComponentSyncModeFull(1.0), Replicate::default()))
+            .id();
+        stepper.frame_step();
+        assert_eq!(
+            sender!().spawn_ack_state(server_entity),
+            Some(SpawnAckState::Pending)
+        );
+
+        // update the component before the spawn is acked: the update is not sent
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(server_entity)
+            .get_mut::<ComponentSyncModeFull>()
+            .unwrap()
+            .0 = 2.0;
+        // only update the server, so that the client doesn't get a chance to ack the spawn
+        stepper.advance_time(stepper.frame_duration);
+        stepper.server_app.update();
+        assert_eq!(
+            sender!().spawn_ack_state(server_entity),
+            Some(SpawnAckState::Pending)
+        );
+        assert!(sender!().updates_message_id_to_group_id.is_empty());
+
+        // once the spawn is acked, the update gets sent
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            sender!().spawn_ack_state(server_entity),
+            Some(SpawnAckState::Acked)
+        );
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<crate::client::connection::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .unwrap();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(client_entity)
+                .unwrap(),
+            &ComponentSyncModeFull(2.0)
+        );
+    }
+
+    /// Test that with entity pooling, the sender reuses the group channel of a despawned entity
+    #[test]
+    fn test_entity_pooling() {
+        let mut stepper = BevyStepper::default();
+        macro_rules! sender {
+            () => {
+                stepper
+                    .server_app
+                    .world_mut()
+                    .resource_mut::<ConnectionManager>()
+                    .connections
+                    .get_mut(&ClientId::Netcode(TEST_CLIENT_ID))
+                    .unwrap()
+                    .replication_sender
+            };
+        }
+        macro_rules! client_entity {
+            ($server_entity:expr) => {
+                stepper
+                    .client_app
+                    .world()
+                    .resource::<crate::client::connection::ConnectionManager>()
+                    .replication_receiver
+                    .remote_entity_map
+                    .get_local($server_entity)
+            };
+        }
+        sender!().replication_config.entity_pooling = true;
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((ComponentSyncModeFull(1.0), Replicate::default()))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = client_entity!(server_entity).unwrap();
+        let group_id = ReplicationGroupId(server_entity.to_bits());
+        assert!(sender!().group_channels.contains_key(&group_id));
+
+        // the despawn is replicated, and the channel of the group is pooled
+        stepper.server_app.world_mut().despawn(server_entity);
+        stepper.frame_step();
+        stepper.frame_step();
+        assert!(client_entity!(server_entity).is_none());
+        assert!(stepper
+            .client_app
+            .world()
+            .get_entity(client_entity)
+            .is_none());
+        assert!(!sender!().group_channels.contains_key(&group_id));
+        assert_eq!(sender!().group_channel_pool.len(), 1);
+
+        // a new spawn reuses the pooled channel
+        let new_server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((ComponentSyncModeFull(2.0), Replicate::default()))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        assert!(sender!().group_channel_pool.is_empty());
+        let new_client_entity = client_entity!(new_server_entity).unwrap();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(new_client_entity)
+                .unwrap(),
+            &ComponentSyncModeFull(2.0)
+        );
+
+        // updates are still replicated with the reused channel
+        stepper
+            .server_app
+            .world_mut()
+            .get_mut::<ComponentSyncModeFull>(new_server_entity)
+            .unwrap()
+            .0 = 3.0;
+        stepper.frame_step();
+        stepper.frame_step();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(new_client_entity)
+                .unwrap(),
+            &ComponentSyncModeFull(3.0)
+        );
+    }
+
     /// Test that if we receive a nack, we bump the send_tick down to the ack tick
     #[test]
     fn test_integration_send_tick_updates_on_packet_nack() {
@@ -929,6 +1467,7 @@ mod tests {
         let mut sender = ReplicationSender::new(
             rx_ack,
             rx_nack,
+            crossbeam_channel::unbounded().1,
             rx_send,
             ReplicationConfig {
                 send_updates_mode: SendUpdatesMode::SinceLastSend,
@@ -1028,8 +1567,14 @@ mod tests {
         let (tx_ack, rx_ack) = crossbeam_channel::unbounded();
         let (tx_nack, rx_nack) = crossbeam_channel::unbounded();
         let (tx_send, rx_send) = crossbeam_channel::unbounded();
-        let mut sender =
-            ReplicationSender::new(rx_ack, rx_nack, rx_send, ReplicationConfig::default(), true);
+        let mut sender = ReplicationSender::new(
+            rx_ack,
+            rx_nack,
+            crossbeam_channel::unbounded().1,
+            rx_send,
+            ReplicationConfig::default(),
+            true,
+        );
         let group_1 = ReplicationGroupId(0);
         sender
             .group_channels
@@ -1079,6 +1624,7 @@ mod tests {
         let mut manager = ReplicationSender::new(
             rx_ack,
             rx_nack,
+            crossbeam_channel::unbounded().1,
             rx_send,
             ReplicationConfig::default(),
             false,
@@ -1184,4 +1730,197 @@ mod tests {
             Some(Tick(2))
         );
     }
+
+    /// Test that a lost reliable component update is retransmitted, without delaying the
+    /// unreliable component updates of the same entity
+    #[test]
+    fn test_reliable_component_update_retransmit() {
+        use crate::connection::server::ServerConnections;
+        use crate::prelude::{client, LinkConditionerConfig};
+        use crate::tests::protocol::ComponentReliable;
+        use std::time::Duration;
+
+        let mut stepper = BevyStepper::default();
+        let client_entity = stepper
+            .client_app
+            .world_mut()
+            .spawn((
+                client::Replicate::default(),
+                ComponentSyncModeFull(1.0),
+                ComponentReliable("a".to_string()),
+            ))
+            .id();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let server_entity = stepper
+            .server_app
+            .world()
+            .resource::<ConnectionManager>()
+            .connection(ClientId::Netcode(TEST_CLIENT_ID))
+            .unwrap()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(client_entity)
+            .expect("entity was not replicated to server");
+        let set_loss = |stepper: &mut BevyStepper, loss: f32| {
+            stepper
+                .server_app
+                .world_mut()
+                .resource_mut::<ServerConnections>()
+                .set_client_conditioner(
+                    ClientId::Netcode(TEST_CLIENT_ID),
+                    Some(LinkConditionerConfig::new(
+                        Duration::default(),
+                        Duration::default(),
+                        loss,
+                    )),
+                )
+                .unwrap();
+        };
+
+        // the packet containing both updates is dropped
+        set_loss(&mut stepper, 1.0);
+        let mut client_entity_mut = stepper.client_app.world_mut().entity_mut(client_entity);
+        client_entity_mut
+            .get_mut::<ComponentSyncModeFull>()
+            .unwrap()
+            .0 = 2.0;
+        client_entity_mut.get_mut::<ComponentReliable>().unwrap().0 = "b".to_string();
+        stepper.frame_step();
+        set_loss(&mut stepper, 0.0);
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .get::<ComponentReliable>(server_entity),
+            Some(&ComponentReliable("a".to_string()))
+        );
+
+        // the positions keep flowing, and the name eventually gets retransmitted
+        let mut name_received = false;
+        for i in 0..20 {
+            stepper
+                .client_app
+                .world_mut()
+                .get_mut::<ComponentSyncModeFull>(client_entity)
+                .unwrap()
+                .0 = 3.0 + i as f32;
+            stepper.frame_step();
+            let server_world = stepper.server_app.world();
+            assert_eq!(
+                server_world.get::<ComponentSyncModeFull>(server_entity),
+                Some(&ComponentSyncModeFull(3.0 + i as f32))
+            );
+            name_received |= server_world.get::<ComponentReliable>(server_entity)
+                == Some(&ComponentReliable("b".to_string()));
+        }
+        assert!(name_received);
+        // the name is only sent once on the reliable channel: the update is not collected again
+        // by the unreliable updates after the packet loss
+        let sender = &stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_sender;
+        let group_id = ReplicationGroupId(client_entity.to_bits());
+        assert!(sender.group_channels[&group_id]
+            .pending_reliable_updates
+            .is_empty());
+    }
+
+    /// Test that with `max_entities_per_tick`, the updates of the other groups are deferred to the next sends
+    #[test]
+    fn test_max_entities_per_tick() {
+        let mut stepper = BevyStepper::default();
+        macro_rules! sender {
+            () => {
+                stepper
+                    .server_app
+                    .world_mut()
+                    .resource_mut::<ConnectionManager>()
+                    .connections
+                    .get_mut(&ClientId::Netcode(TEST_CLIENT_ID))
+                    .unwrap()
+                    .replication_sender
+            };
+        }
+        sender!().replication_config.max_entities_per_tick = Some(2);
+        let server_entities: Vec<_> = (0..5)
+            .map(|i| {
+                stepper
+                    .server_app
+                    .world_mut()
+                    .spawn((
+                        ComponentSyncModeFull(0.0),
+                        Replicate {
+                            group: ReplicationGroup::new_id(i),
+                            ..default()
+                        },
+                    ))
+                    .id()
+            })
+            .collect();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        let client_entities: Vec<_> = server_entities
+            .iter()
+            .map(|server_entity| {
+                stepper
+                    .client_app
+                    .world()
+                    .resource::<crate::client::connection::ConnectionManager>()
+                    .replication_receiver
+                    .remote_entity_map
+                    .get_local(*server_entity)
+                    .unwrap()
+            })
+            .collect();
+        let updated = |stepper: &BevyStepper| {
+            client_entities
+                .iter()
+                .filter(|client_entity| {
+                    stepper
+                        .client_app
+                        .world()
+                        .get::<ComponentSyncModeFull>(**client_entity)
+                        .unwrap()
+                        .0
+                        == 1.0
+                })
+                .count()
+        };
+
+        // update all the entities: only 2 of them are sent on the first send
+        for server_entity in &server_entities {
+            stepper
+                .server_app
+                .world_mut()
+                .get_mut::<ComponentSyncModeFull>(*server_entity)
+                .unwrap()
+                .0 = 1.0;
+        }
+        stepper.frame_step();
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .resource::<ConnectionManager>()
+                .connection(ClientId::Netcode(TEST_CLIENT_ID))
+                .unwrap()
+                .deferred_entities(),
+            3
+        );
+        stepper.frame_step();
+        assert_eq!(updated(&stepper), 2);
+        assert_eq!(sender!().deferred_entities, 1);
+
+        // the deferred updates are sent on the next sends
+        stepper.frame_step();
+        assert_eq!(updated(&stepper), 4);
+        stepper.frame_step();
+        assert_eq!(updated(&stepper), 5);
+        assert_eq!(sender!().deferred_entities, 0);
+    }
 }
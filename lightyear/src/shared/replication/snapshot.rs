@@ -0,0 +1,479 @@
+//! Export the replication state of a connection, so that it can be restored on another server
+//!
+//! This is useful when a client is handed off between multiple server instances (for example when
+//! sharding the world): the new server can keep using the replication message ids and ticks
+//! that the client expects, so that the client sees a continuous entity state.
+//!
+//! Only the state that is meaningful across processes is exported:
+//! - the per-group message ids and ticks used to order replication messages
+//! - the entities that were already spawned on the client. When importing, they are mapped to the entities
+//!   of the importing server, which keeps sending them with the entity of the exporting server: the client
+//!   receives updates for the entities it already has instead of new spawns. The entities of the importing
+//!   server whose id is already used by one of these aliases are spawned on the client with a reserved id
+//! - the mapping between the client's entities and the server's entities
+//!
+//! Change-detection ticks and delta-compression baselines are local to a server's `World`, so they
+//! are not exported; the first update sent by the importing server will contain the full component values.
+//!
+//! Replication groups are identified by their [`ReplicationGroupId`], so entities that are handed off
+//! should use an explicit group id (see [`ReplicationGroup::new_id`](crate::prelude::ReplicationGroup::new_id))
+//! that is the same on every server.
+//!
+//! The snapshot only contains the replication state: the client's transport session (and the message ids
+//! of its channels) must be kept across the handoff, for example by a proxy that routes the client's
+//! packets to the new server.
+use bevy::prelude::Entity;
+use byteorder::{NetworkEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::packet::message::MessageId;
+use crate::prelude::Tick;
+use crate::serialize::reader::Reader;
+use crate::serialize::{SerializationError, ToBytes};
+use crate::shared::replication::components::ReplicationGroupId;
+use crate::shared::replication::error::ReplicationError;
+
+/// Version of the snapshot format.
+///
+/// It is bumped every time the format changes; importing a snapshot with a different version fails
+/// with [`ReplicationError::SnapshotVersionMismatch`].
+pub const REPLICATION_SNAPSHOT_VERSION: u16 = 2;
+
+/// Replication state of a connection.
+///
+/// The snapshot can be serialized with [`ReplicationSnapshot::serialize`] to be sent to another server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplicationSnapshot {
+    pub version: u16,
+    /// State of the replication groups that we send to the remote
+    pub send_groups: Vec<SendGroupSnapshot>,
+    /// State of the replication groups that we receive from the remote
+    pub receive_groups: Vec<ReceiveGroupSnapshot>,
+    /// Mapping between the remote entities and the local entities, as `(remote, local)` pairs
+    pub entity_map: Vec<(Entity, Entity)>,
+}
+
+/// Send-side state of a replication group
+#[derive(Debug, Clone, PartialEq)]
+pub struct SendGroupSnapshot {
+    pub group_id: ReplicationGroupId,
+    /// Message id of the next actions message for this group
+    pub actions_next_send_message_id: MessageId,
+    /// Tick of the last actions message sent for this group
+    pub last_action_tick: Option<Tick>,
+    /// Local entities of the group that were spawned on the remote
+    pub entities: Vec<Entity>,
+}
+
+/// Receive-side state of a replication group
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReceiveGroupSnapshot {
+    pub group_id: ReplicationGroupId,
+    /// Message id of the next actions message that we expect for this group
+    pub actions_pending_recv_message_id: MessageId,
+    /// Remote tick of the latest message applied for this group
+    pub latest_tick: Option<Tick>,
+    /// Remote entities that are part of the group
+    pub remote_entities: Vec<Entity>,
+}
+
+impl ReplicationSnapshot {
+    /// Serialize the snapshot, prefixed with its version
+    pub fn serialize(&self) -> Result<Vec<u8>, SerializationError> {
+        let mut buffer = Vec::with_capacity(self.len());
+        self.to_bytes(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Deserialize a snapshot.
+    ///
+    /// Fails if the snapshot was produced with a different [`REPLICATION_SNAPSHOT_VERSION`].
+    pub fn deserialize(bytes: impl Into<bytes::Bytes>) -> Result<Self, ReplicationError> {
+        let mut reader = Reader::from(bytes.into());
+        let version = reader
+            .read_u16::<NetworkEndian>()
+            .map_err(SerializationError::from)?;
+        if version != REPLICATION_SNAPSHOT_VERSION {
+            return Err(ReplicationError::SnapshotVersionMismatch {
+                expected: REPLICATION_SNAPSHOT_VERSION,
+                found: version,
+            });
+        }
+        Ok(Self {
+            version,
+            send_groups: Vec::from_bytes(&mut reader)?,
+            receive_groups: Vec::from_bytes(&mut reader)?,
+            entity_map: Vec::from_bytes(&mut reader)?,
+        })
+    }
+}
+
+impl ToBytes for ReplicationSnapshot {
+    fn len(&self) -> usize {
+        2 + ToBytes::len(&self.send_groups)
+            + ToBytes::len(&self.receive_groups)
+            + ToBytes::len(&self.entity_map)
+    }
+
+    fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
+        buffer.write_u16::<NetworkEndian>(self.version)?;
+        self.send_groups.to_bytes(buffer)?;
+        self.receive_groups.to_bytes(buffer)?;
+        self.entity_map.to_bytes(buffer)?;
+        Ok(())
+    }
+
+    fn from_bytes(buffer: &mut Reader) -> Result<Self, SerializationError>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            version: buffer.read_u16::<NetworkEndian>()?,
+            send_groups: Vec::from_bytes(buffer)?,
+            receive_groups: Vec::from_bytes(buffer)?,
+            entity_map: Vec::from_bytes(buffer)?,
+        })
+    }
+}
+
+impl ToBytes for SendGroupSnapshot {
+    fn len(&self) -> usize {
+        self.group_id.len()
+            + self.actions_next_send_message_id.len()
+            + self.last_action_tick.len()
+            + ToBytes::len(&self.entities)
+    }
+
+    fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
+        self.group_id.to_bytes(buffer)?;
+        self.actions_next_send_message_id.to_bytes(buffer)?;
+        self.last_action_tick.to_bytes(buffer)?;
+        self.entities.to_bytes(buffer)?;
+        Ok(())
+    }
+
+    fn from_bytes(buffer: &mut Reader) -> Result<Self, SerializationError>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            group_id: ReplicationGroupId::from_bytes(buffer)?,
+            actions_next_send_message_id: MessageId::from_bytes(buffer)?,
+            last_action_tick: Option::from_bytes(buffer)?,
+            entities: Vec::from_bytes(buffer)?,
+        })
+    }
+}
+
+impl ToBytes for ReceiveGroupSnapshot {
+    fn len(&self) -> usize {
+        self.group_id.len()
+            + self.actions_pending_recv_message_id.len()
+            + self.latest_tick.len()
+            + ToBytes::len(&self.remote_entities)
+    }
+
+    fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
+        self.group_id.to_bytes(buffer)?;
+        self.actions_pending_recv_message_id.to_bytes(buffer)?;
+        self.latest_tick.to_bytes(buffer)?;
+        self.remote_entities.to_bytes(buffer)?;
+        Ok(())
+    }
+
+    fn from_bytes(buffer: &mut Reader) -> Result<Self, SerializationError>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            group_id: ReplicationGroupId::from_bytes(buffer)?,
+            actions_pending_recv_message_id: MessageId::from_bytes(buffer)?,
+            latest_tick: Option::from_bytes(buffer)?,
+            remote_entities: Vec::from_bytes(buffer)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::server::Replicate;
+    use crate::prelude::{ClientId, Replicated, Replicating, ReplicationGroup};
+    use crate::server::connection::ConnectionManager;
+    use crate::shared::replication::entity_map::{EntityNamespace, NetworkEntityId};
+    use crate::tests::protocol::ComponentSyncModeFull;
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+    use bevy::prelude::With;
+
+    use super::*;
+
+    #[test]
+    fn test_snapshot_serialization() {
+        let snapshot = ReplicationSnapshot {
+            version: REPLICATION_SNAPSHOT_VERSION,
+            send_groups: vec![SendGroupSnapshot {
+                group_id: ReplicationGroupId(3),
+                actions_next_send_message_id: MessageId(2),
+                last_action_tick: Some(Tick(10)),
+                entities: vec![Entity::from_raw(5)],
+            }],
+            receive_groups: vec![ReceiveGroupSnapshot {
+                group_id: ReplicationGroupId(4),
+                actions_pending_recv_message_id: MessageId(1),
+                latest_tick: None,
+                remote_entities: vec![Entity::from_raw(1)],
+            }],
+            entity_map: vec![(Entity::from_raw(1), Entity::from_raw(2))],
+        };
+        let bytes = snapshot.serialize().unwrap();
+        assert_eq!(
+            ReplicationSnapshot::deserialize(bytes.clone()).unwrap(),
+            snapshot
+        );
+
+        // snapshots from a different version are rejected
+        let mut bytes = bytes;
+        bytes[1] += 1;
+        assert!(matches!(
+            ReplicationSnapshot::deserialize(bytes),
+            Err(ReplicationError::SnapshotVersionMismatch { .. })
+        ));
+    }
+
+    /// Export the replication state from one server and import it on another server
+    #[test]
+    fn test_export_import_replication_state() {
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        let group = ReplicationGroup::new_id(7);
+        let mut stepper = BevyStepper::default();
+        stepper.server_app.world_mut().spawn(Replicate {
+            group,
+            ..Default::default()
+        });
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let snapshot = stepper
+            .server_app
+            .world()
+            .resource::<ConnectionManager>()
+            .export_replication_state(client_id)
+            .unwrap();
+        let snapshot_group = snapshot
+            .send_groups
+            .iter()
+            .find(|g| g.group_id == ReplicationGroupId(7))
+            .unwrap();
+        // the spawn action has been sent
+        assert_eq!(snapshot_group.actions_next_send_message_id, MessageId(1));
+        let bytes = snapshot.serialize().unwrap();
+
+        let mut other_stepper = BevyStepper::default();
+        other_stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ConnectionManager>()
+            .import_replication_state(
+                client_id,
+                ReplicationSnapshot::deserialize(bytes).unwrap(),
+                Some,
+            )
+            .unwrap();
+        let channel = other_stepper
+            .server_app
+            .world()
+            .resource::<ConnectionManager>()
+            .connection(client_id)
+            .unwrap()
+            .replication_sender
+            .group_channels
+            .get(&ReplicationGroupId(7))
+            .unwrap();
+        assert_eq!(channel.actions_next_send_message_id, MessageId(1));
+        assert_eq!(channel.send_tick, None);
+    }
+
+    /// The client is handed off to a server that has its own entity for the replicated object:
+    /// the client must keep a single entity, updated by the new server
+    #[test]
+    fn test_handoff_does_not_duplicate_entities() {
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        let group = ReplicationGroup::new_id(7);
+        let mut stepper = BevyStepper::default();
+        let exported_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                Replicate {
+                    group: group.clone(),
+                    ..Default::default()
+                },
+                ComponentSyncModeFull(1.0),
+            ))
+            .id();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        let snapshot = stepper
+            .server_app
+            .world()
+            .resource::<ConnectionManager>()
+            .export_replication_state(client_id)
+            .unwrap();
+        assert_eq!(snapshot.send_groups[0].entities, vec![exported_entity]);
+
+        // simulate the new server: the object is a different entity, and the replication state of
+        // the client is fresh (the transport session is kept)
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(exported_entity)
+            .remove::<Replicating>();
+        stepper.server_app.world_mut().despawn(exported_entity);
+        let local_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                Replicate {
+                    group,
+                    ..Default::default()
+                },
+                ComponentSyncModeFull(2.0),
+            ))
+            .id();
+        let mut manager = stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ConnectionManager>();
+        manager
+            .connection_mut(client_id)
+            .unwrap()
+            .replication_sender
+            .group_channels
+            .clear();
+        manager.new_clients.push(client_id);
+        manager
+            .import_replication_state(client_id, snapshot, |entity| {
+                (entity == exported_entity).then_some(local_entity)
+            })
+            .unwrap();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+
+        let client_values = stepper
+            .client_app
+            .world_mut()
+            .query_filtered::<Option<&ComponentSyncModeFull>, With<Replicated>>()
+            .iter(stepper.client_app.world())
+            .map(|value| value.cloned())
+            .collect::<Vec<_>>();
+        assert_eq!(client_values, vec![Some(ComponentSyncModeFull(2.0))]);
+    }
+
+    /// The importing server has a native entity with the same id as the entity of the exporting server
+    /// that the client already knows: the two must stay separate entities on the client
+    #[test]
+    fn test_handoff_alias_collides_with_native_entity() {
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        let mut stepper = BevyStepper::default();
+        let exported_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                Replicate {
+                    group: ReplicationGroup::new_id(7),
+                    ..Default::default()
+                },
+                ComponentSyncModeFull(1.0),
+            ))
+            .id();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        let snapshot = stepper
+            .server_app
+            .world()
+            .resource::<ConnectionManager>()
+            .export_replication_state(client_id)
+            .unwrap();
+
+        // simulate the new server: the object is a different entity, and the entity with the id of the
+        // exported entity is an unrelated native entity of the new server
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(exported_entity)
+            .remove::<Replicating>()
+            .remove::<Replicate>();
+        let local_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                Replicate {
+                    group: ReplicationGroup::new_id(7),
+                    ..Default::default()
+                },
+                ComponentSyncModeFull(2.0),
+            ))
+            .id();
+        let mut manager = stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ConnectionManager>();
+        manager
+            .connection_mut(client_id)
+            .unwrap()
+            .replication_sender
+            .group_channels
+            .clear();
+        manager.new_clients.push(client_id);
+        manager
+            .import_replication_state(client_id, snapshot, |entity| {
+                (entity == exported_entity).then_some(local_entity)
+            })
+            .unwrap();
+        let native_entity = exported_entity;
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(native_entity)
+            .insert((
+                Replicate {
+                    group: ReplicationGroup::new_id(8),
+                    ..Default::default()
+                },
+                ComponentSyncModeFull(3.0),
+            ));
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+
+        let mut client_values = stepper
+            .client_app
+            .world_mut()
+            .query_filtered::<&ComponentSyncModeFull, With<Replicated>>()
+            .iter(stepper.client_app.world())
+            .map(|value| value.0)
+            .collect::<Vec<_>>();
+        client_values.sort_by(f32::total_cmp);
+        assert_eq!(client_values, vec![2.0, 3.0]);
+
+        // the entities that the client sends back are resolved to the correct local entities
+        let entity_map = &stepper
+            .server_app
+            .world()
+            .resource::<ConnectionManager>()
+            .connection(client_id)
+            .unwrap()
+            .replication_receiver
+            .remote_entity_map;
+        for entity in [local_entity, native_entity] {
+            let network_id = entity_map.to_network_id(entity);
+            let client_network_id =
+                NetworkEntityId::new(network_id.entity(), EntityNamespace::Receiver);
+            assert_eq!(
+                entity_map.get_local_by_network_id(client_network_id),
+                Some(entity)
+            );
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Deterministic random numbers that are identical on the client and on the server
+//!
+//! The server picks the seed ([`SharedConfig::rng_seed`](crate::prelude::SharedConfig::rng_seed), or a random seed
+//! if it is not set) and sends it to each client when it connects. The [`DeterministicRng`] resource is then
+//! available on the server, and on the client once the seed has been received.
+//!
+//! The random numbers are drawn from an rng created for a specific [`Tick`]: the client prediction and the server
+//! simulation of the same tick draw the same sequence, and re-running a tick during a rollback draws it again.
+//!
+//! ```rust,ignore
+//! fn spread(rng: Res<DeterministicRng>, tick_manager: Res<TickManager>) {
+//!     let mut rng = rng.at_tick(tick_manager.tick());
+//!     let angle: f32 = rng.gen_range(-0.1..0.1);
+//! }
+//! ```
+//!
+//! The sequences are only identical if the client and the server use the same version of lightyear.
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::channel::builder::RngSeedChannel;
+use crate::client::events::MessageEvent;
+use crate::server::connection::ConnectionManager;
+use crate::server::events::ConnectEvent;
+use crate::shared::tick_manager::Tick;
+
+/// Seed shared between the client and the server, used to create an rng for each tick
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeterministicRng {
+    seed: u64,
+}
+
+impl DeterministicRng {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Create the rng of a tick. The same tick always returns an rng that draws the same sequence.
+    pub fn at_tick(&self, tick: Tick) -> StdRng {
+        // spread the ticks over the whole u64 range so that neighbouring ticks get unrelated seeds
+        StdRng::seed_from_u64(self.seed ^ u64::from(tick.0).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+    }
+}
+
+/// Message sent by the server to a client when it connects
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RngSeed {
+    pub seed: u64,
+}
+
+/// Send the seed to the clients that just connected
+pub(crate) fn send_rng_seed(
+    rng: Res<DeterministicRng>,
+    mut connect_events: EventReader<ConnectEvent>,
+    mut connection_manager: ResMut<ConnectionManager>,
+) {
+    for event in connect_events.read() {
+        if let Err(e) = connection_manager
+            .send_message::<RngSeedChannel, _>(event.client_id, &mut RngSeed { seed: rng.seed })
+        {
+            error!(?e, client_id = ?event.client_id, "could not send the rng seed");
+        }
+    }
+}
+
+/// Insert the [`DeterministicRng`] when the seed is received from the server
+pub(crate) fn receive_rng_seed(
+    mut commands: Commands,
+    mut messages: ResMut<Events<MessageEvent<RngSeed>>>,
+) {
+    for message in messages.drain() {
+        commands.insert_resource(DeterministicRng::new(message.message.seed));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::default;
+    use bevy::utils::Duration;
+    use rand::Rng;
+
+    use crate::prelude::{SharedConfig, TickConfig};
+    use crate::server::config::ServerConfig;
+    use crate::tests::stepper::BevyStepper;
+
+    use super::*;
+
+    #[test]
+    fn test_rng_at_tick() {
+        let rng = DeterministicRng::new(1);
+        let draw = |rng: &DeterministicRng, tick| rng.at_tick(Tick(tick)).gen::<u64>();
+        assert_eq!(draw(&rng, 3), draw(&rng, 3));
+        assert_ne!(draw(&rng, 3), draw(&rng, 4));
+        assert_ne!(draw(&rng, 3), draw(&DeterministicRng::new(2), 3));
+    }
+
+    #[test]
+    fn test_seed_sent_on_connect() {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, default(), tick_duration);
+        // only the server sets the seed
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .shared
+            .rng_seed = Some(7);
+        stepper.init();
+        assert_eq!(
+            stepper.server_app.world().resource::<DeterministicRng>(),
+            &DeterministicRng::new(7)
+        );
+        assert_eq!(
+            stepper.client_app.world().resource::<DeterministicRng>(),
+            &DeterministicRng::new(7)
+        );
+    }
+}
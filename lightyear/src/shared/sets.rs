@@ -1,5 +1,6 @@
 //! Bevy [`SystemSet`] that are shared between the server and client
-use bevy::prelude::SystemSet;
+use bevy::ecs::schedule::{InternedScheduleLabel, ScheduleLabel};
+use bevy::prelude::{App, PostUpdate, PreUpdate, Resource, SystemSet};
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
 pub struct ClientMarker;
@@ -64,27 +65,178 @@ pub(crate) enum InternalMainSet<M> {
     _Marker(std::marker::PhantomData<M>),
 }
 
+/// Public SystemSets used by lightyear to receive and send data
+///
+/// Every frame, the sets run in this order:
+/// 1. [`MainSet::Receive`]: the packets are received and the replicated data is applied to the world
+/// 2. [`MainSet::EmitEvents`]: the networking events (messages, connections, replication events) are emitted
+/// 3. on the client, the prediction systems (spawning the predicted entities, rollback)
+/// 4. [`MainSet::Simulation`] in the receive schedule, then the `Update` and `FixedMain` schedules,
+///    then [`MainSet::Simulation`] in the send schedule
+/// 5. the replication updates are buffered
+/// 6. [`MainSet::Send`]: the packets are sent
+///
+/// Systems that run in `Update` or in the `FixedMain` schedules (`FixedUpdate`, etc.) always run between
+/// the receive and the send. Systems that run in the same schedules as the networking systems (`PreUpdate`
+/// and `PostUpdate` by default) should be added to [`MainSet::Simulation`] to be ordered correctly.
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone, Copy)]
 pub enum MainSet {
     /// Systems that receive data (buffer any data received from transport, and read
     /// data from the buffers)
     ///
-    /// Runs in `PreUpdate`.
+    /// Runs in [`NetworkingSchedules::receive`] (`PreUpdate` by default).
     Receive,
     /// Systems that emit networking-related events
-    /// Runs in `PreUpdate`, after `Receive`
+    /// Runs in [`NetworkingSchedules::receive`], after `Receive`
     EmitEvents,
 
+    /// Set for the user systems that read the replicated state or modify the state that will be replicated.
+    ///
+    /// In [`NetworkingSchedules::receive`], it runs after all the data received this frame has been applied
+    /// (including the client prediction and rollback). In [`NetworkingSchedules::send`], it runs before the
+    /// replication updates are buffered, so that the changes it makes are sent during the same frame.
+    ///
+    /// The set can also be used in other schedules, where it has no ordering constraints.
+    Simulation,
+
     /// SystemSet where we actually send packets over the network.
     /// Runs every frame.
     ///
-    /// Runs in `PostUpdate`
+    /// Runs in [`NetworkingSchedules::send`] (`PostUpdate` by default)
     Send,
 }
 
+/// Schedules in which the networking systems run
+///
+/// By default, data is received in [`PreUpdate`] and sent in [`PostUpdate`]. To drive networking from
+/// your own schedules (for example to step a headless app manually), insert this resource
+/// before adding the `ClientPlugins` or `ServerPlugins`:
+/// ```rust,ignore
+/// app.insert_resource(NetworkingSchedules {
+///     receive: MyReceive.intern(),
+///     send: MySend.intern(),
+/// });
+/// app.add_plugins(ServerPlugins::new(config));
+/// ```
+///
+/// The schedules must then be run in this order every frame:
+/// 1. the `receive` schedule: [`MainSet::Receive`] reads the packets from the transport,
+///    then [`MainSet::EmitEvents`] emits the networking events and applies the replication updates
+/// 2. [`RunFixedMainLoop`](bevy::app::RunFixedMainLoop), which runs the [`FixedMain`](bevy::app::FixedMain)
+///    schedules where the tick is incremented and the game logic runs
+/// 3. the `send` schedule: the replication systems buffer the updates, then [`MainSet::Send`] sends the packets
+///
+/// The visual systems (visual interpolation, prediction correction) keep running in [`PostUpdate`].
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NetworkingSchedules {
+    /// Schedule where packets are received and networking events are emitted
+    pub receive: InternedScheduleLabel,
+    /// Schedule where replication updates are buffered and packets are sent
+    pub send: InternedScheduleLabel,
+}
+
+impl Default for NetworkingSchedules {
+    fn default() -> Self {
+        Self {
+            receive: PreUpdate.intern(),
+            send: PostUpdate.intern(),
+        }
+    }
+}
+
+impl NetworkingSchedules {
+    /// Get the schedules that were configured for the app
+    pub(crate) fn of(app: &App) -> Self {
+        app.world()
+            .get_resource::<Self>()
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
 /// SystemSet that run during the FixedUpdate schedule
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone, Copy)]
 pub enum FixedUpdateSet {
     /// System that runs in the FixedFirst schedule to increment the ticks
     TickUpdate,
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use bevy::state::app::StatesPlugin;
+
+    use crate::prelude::server::{Replicate, ServerConfig, ServerPlugins};
+    use crate::tests::protocol::{ComponentSyncModeFull, ProtocolPlugin};
+    use crate::tests::stepper::BevyStepper;
+
+    use super::*;
+
+    #[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+    struct CustomReceive;
+
+    #[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+    struct CustomSend;
+
+    fn has_system(app: &App, label: impl ScheduleLabel, name: &str) -> bool {
+        app.get_schedule(label).is_some_and(|schedule| {
+            schedule
+                .graph()
+                .systems()
+                .any(|(_, system, _)| system.name().ends_with(name))
+        })
+    }
+
+    #[test]
+    fn test_custom_schedules() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin));
+        app.insert_resource(NetworkingSchedules {
+            receive: CustomReceive.intern(),
+            send: CustomSend.intern(),
+        });
+        app.add_plugins((ServerPlugins::new(ServerConfig::default()), ProtocolPlugin));
+
+        let receive = "server::networking::receive";
+        let send = "server::networking::send";
+        assert!(has_system(&app, CustomReceive, receive));
+        assert!(has_system(&app, CustomSend, send));
+        assert!(!has_system(&app, PreUpdate, receive));
+        assert!(!has_system(&app, PostUpdate, send));
+    }
+
+    /// Number of replicated entities seen by the system at each frame
+    #[derive(Resource, Default)]
+    struct Seen(Vec<usize>);
+
+    #[test]
+    fn test_simulation_set_sees_replicated_state() {
+        let mut stepper = BevyStepper::default();
+        stepper.client_app.init_resource::<Seen>().add_systems(
+            PreUpdate,
+            (|query: Query<&ComponentSyncModeFull>, mut seen: ResMut<Seen>| {
+                seen.0.push(query.iter().count())
+            })
+            .in_set(MainSet::Simulation),
+        );
+        stepper
+            .server_app
+            .world_mut()
+            .spawn((Replicate::default(), ComponentSyncModeFull(1.0)));
+        let mut spawned = vec![];
+        for _ in 0..5 {
+            stepper.frame_step();
+            spawned.push(
+                stepper
+                    .client_app
+                    .world_mut()
+                    .query::<&ComponentSyncModeFull>()
+                    .iter(stepper.client_app.world())
+                    .count(),
+            );
+        }
+        // the system sees the entity during the frame where it was replicated
+        assert!(spawned.contains(&1));
+        assert_eq!(stepper.client_app.world().resource::<Seen>().0, spawned);
+    }
+}
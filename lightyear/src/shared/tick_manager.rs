@@ -54,6 +54,11 @@ impl TickConfig {
     pub fn new(tick_duration: Duration) -> Self {
         Self { tick_duration }
     }
+
+    /// Number of ticks per second
+    pub fn tick_rate(&self) -> f64 {
+        1.0 / self.tick_duration.as_secs_f64()
+    }
 }
 
 /// Manages the tick for the host system. Ticks are incremented by one every time
@@ -64,6 +69,8 @@ pub struct TickManager {
     pub config: TickConfig,
     /// Current tick (sequence number of the FixedUpdate schedule)
     tick: Tick,
+    /// Fraction of a tick that has elapsed since the last FixedUpdate run
+    overstep: f32,
 }
 
 impl TickManager {
@@ -71,6 +78,7 @@ impl TickManager {
         Self {
             config,
             tick: Tick(0),
+            overstep: 0.0,
         }
     }
 
@@ -95,8 +103,59 @@ impl TickManager {
         self.tick
     }
 
+    /// Duration of a tick
+    pub fn tick_duration(&self) -> Duration {
+        self.config.tick_duration
+    }
+
+    /// Get the fraction of the tick duration that has elapsed since the last FixedUpdate run.
+    ///
+    /// This is the interpolation alpha between the previous tick and the current tick, and can be used
+    /// to interpolate visual transforms in render systems. It is computed via
+    /// [`TimeManager::overstep`](crate::prelude::TimeManager::overstep), so it takes into account the
+    /// [`OverstepMapping`](crate::shared::time_manager::OverstepMapping)
+    pub fn overstep(&self) -> f32 {
+        self.overstep
+    }
+
+    pub(crate) fn update_overstep(&mut self, overstep: f32) {
+        self.overstep = overstep;
+    }
+
     /// Get the current tick of the app; works even if we are in rollback
     pub fn tick_or_rollback_tick(&self, rollback_state: &Rollback) -> Tick {
         rollback_state.get_rollback_tick().unwrap_or(self.tick)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use crate::prelude::SharedConfig;
+    use crate::tests::stepper::BevyStepper;
+
+    use super::*;
+
+    #[test]
+    fn test_tick_readable_from_systems() {
+        let config = SharedConfig {
+            tick: TickConfig::new(Duration::from_millis(20)),
+            ..default()
+        };
+        assert_eq!(config.tick_duration(), Duration::from_millis(20));
+        assert_eq!(config.tick.tick_rate(), 50.0);
+
+        let mut stepper = BevyStepper::default();
+        let tick = stepper.client_tick();
+        let (tick_duration, system_tick) =
+            stepper
+                .client_app
+                .world_mut()
+                .run_system_once(|tick_manager: Res<TickManager>| {
+                    (tick_manager.tick_duration(), tick_manager.tick())
+                });
+        assert_eq!(tick_duration, stepper.tick_duration);
+        assert_eq!(system_tick, tick);
+    }
+}
@@ -11,6 +11,7 @@ This module contains some helper functions to compute the difference between two
 */
 use std::fmt::Formatter;
 use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
+use std::sync::Arc;
 
 use bevy::app::{App, RunFixedMainLoop};
 use bevy::prelude::{IntoSystemConfigs, Plugin, Res, ResMut, Resource, Time};
@@ -21,7 +22,7 @@ use chrono::Duration as ChronoDuration;
 
 pub use wrapped_time::WrappedTime;
 
-use crate::prelude::Tick;
+use crate::prelude::{Tick, TickManager};
 
 /// Plugin that will centralize information about the various times (real, virtual, fixed)
 /// as well as track when we should send updates to the remote
@@ -39,10 +40,28 @@ impl Plugin for TimePlugin {
     }
 }
 
-fn update_overstep(mut time_manager: ResMut<TimeManager>, fixed_time: Res<Time<Fixed>>) {
+fn update_overstep(
+    mut time_manager: ResMut<TimeManager>,
+    tick_manager: Option<ResMut<TickManager>>,
+    fixed_time: Res<Time<Fixed>>,
+) {
     time_manager.update_overstep(fixed_time.overstep_fraction());
+    if let Some(mut tick_manager) = tick_manager {
+        tick_manager.update_overstep(time_manager.overstep());
+    }
 }
 
+/// Function that maps the raw overstep of the [`Fixed`] timestep (the fraction of a tick that has elapsed
+/// since the last [`FixedUpdate`](bevy::prelude::FixedUpdate) run) to the value returned by [`TimeManager::overstep`].
+///
+/// The default mapping is the identity. You can provide your own mapping for custom render-time effects
+/// (for example to slow down the visual progression between two ticks during a slow-motion effect).
+/// The returned value should be between 0.0 and 1.0
+///
+/// The mapping can capture state, so that the factor can be changed at runtime
+/// (for example by reading a shared atomic value).
+pub type OverstepMapping = Arc<dyn Fn(f32) -> f32 + Send + Sync>;
+
 #[derive(Resource)]
 pub struct TimeManager {
     /// The virtual time
@@ -51,6 +70,10 @@ pub struct TimeManager {
     real_time: WrappedTime,
     /// The remaining time after running the fixed-update steps, as a fraction of the tick time
     overstep: f32,
+    /// The overstep before the [`OverstepMapping`] is applied
+    raw_overstep: f32,
+    /// Mapping applied to the raw overstep
+    overstep_mapping: OverstepMapping,
     /// The time since the last frame; gets update by bevy's Time resource at the start of the frame
     delta: Duration,
     /// The relative speed set by the client.
@@ -76,6 +99,8 @@ impl TimeManager {
             wrapped_time: WrappedTime::new(0),
             real_time: WrappedTime::new(0),
             overstep: 0.0,
+            raw_overstep: 0.0,
+            overstep_mapping: Arc::new(|overstep| overstep),
             delta: Duration::default(),
             base_relative_speed: 1.0,
             sync_relative_speed: 1.0,
@@ -88,11 +113,25 @@ impl TimeManager {
     }
 
     /// Get the overstep (remaining time after running the fixed-update steps)
-    /// as a fraction of the tick time
+    /// as a fraction of the tick time, after the [`OverstepMapping`] has been applied.
+    ///
+    /// This can be used as the interpolation alpha between the previous and the current tick
+    /// when rendering.
     pub fn overstep(&self) -> f32 {
         self.overstep
     }
 
+    /// Get the overstep as computed by bevy's [`Time<Fixed>`], without applying the [`OverstepMapping`]
+    pub fn raw_overstep(&self) -> f32 {
+        self.raw_overstep
+    }
+
+    /// Set the [`OverstepMapping`] used to compute [`TimeManager::overstep`] from the raw overstep
+    pub fn set_overstep_mapping(&mut self, mapping: impl Fn(f32) -> f32 + Send + Sync + 'static) {
+        self.overstep_mapping = Arc::new(mapping);
+        self.overstep = (self.overstep_mapping)(self.raw_overstep);
+    }
+
     /// Get the relative speed at which the simulation should be running
     pub fn get_relative_speed(&self) -> f32 {
         self.base_relative_speed * self.sync_relative_speed
@@ -118,7 +157,8 @@ impl TimeManager {
 
     /// Update the overstep (right after the overstep was computed, after RunFixedUpdateLoop)
     pub(crate) fn update_overstep(&mut self, overstep: f32) {
-        self.overstep = overstep;
+        self.raw_overstep = overstep;
+        self.overstep = (self.overstep_mapping)(overstep);
     }
 
     fn update_real(&mut self, real_delta: Duration) {
@@ -377,6 +417,39 @@ mod wrapped_time {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_overstep_mapping() {
+        let mut time_manager = TimeManager::default();
+        time_manager.update_overstep(0.5);
+        assert_eq!(time_manager.overstep(), 0.5);
+
+        time_manager.set_overstep_mapping(|overstep| overstep * 0.5);
+        assert_eq!(time_manager.overstep(), 0.25);
+        assert_eq!(time_manager.raw_overstep(), 0.5);
+
+        time_manager.update_overstep(1.0);
+        assert_eq!(time_manager.overstep(), 0.5);
+    }
+
+    #[test]
+    fn test_overstep_mapping_dynamic_factor() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let factor = Arc::new(AtomicU32::new(1.0f32.to_bits()));
+        let mut time_manager = TimeManager::default();
+        let mapping_factor = factor.clone();
+        time_manager.set_overstep_mapping(move |overstep| {
+            overstep * f32::from_bits(mapping_factor.load(Ordering::Relaxed))
+        });
+        time_manager.update_overstep(0.5);
+        assert_eq!(time_manager.overstep(), 0.5);
+
+        // change the time dilation factor without setting a new mapping
+        factor.store(0.5f32.to_bits(), Ordering::Relaxed);
+        time_manager.update_overstep(0.5);
+        assert_eq!(time_manager.overstep(), 0.25);
+    }
+
     #[test]
     fn test_mul() {
         let a = WrappedTime::new(u32::MAX);
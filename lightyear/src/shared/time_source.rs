@@ -0,0 +1,97 @@
+/*! Module to abstract over the clock used to read the current time
+
+Most of lightyear is driven by bevy's [`Time`](bevy::prelude::Time) (the ping manager, the netcode keep-alives and timeouts, etc.),
+which can already be controlled in tests via [`TimeUpdateStrategy`](bevy::time::TimeUpdateStrategy).
+
+Some subsystems need to read the current time directly (for example the [`LinkConditioner`](crate::transport::middleware::conditioner::LinkConditioner)
+which delays packets). They read it from a [`TimeSource`], which is the real clock by default but can be replaced
+by a [`MockTimeSource`] that only advances when asked to.
+*/
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use bevy::utils::Duration;
+use cfg_if::cfg_if;
+use parking_lot::RwLock;
+
+cfg_if! {
+    if #[cfg(test)] {
+        use mock_instant::global::Instant;
+    } else {
+        use bevy::utils::Instant;
+    }
+}
+
+/// A clock that can be queried for the current time
+pub trait TimeSource: Debug + Send + Sync + 'static {
+    /// Get the current time
+    fn now(&self) -> Instant;
+}
+
+/// [`TimeSource`] that uses the real clock
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RealTimeSource;
+
+impl TimeSource for RealTimeSource {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// [`TimeSource`] that only advances when [`MockTimeSource::advance`] is called.
+///
+/// Clones share the same clock, so a clone can be kept to advance the time of a
+/// [`TimeSource`] that was provided to lightyear.
+#[derive(Debug, Clone)]
+pub struct MockTimeSource {
+    start: Instant,
+    elapsed: Arc<RwLock<Duration>>,
+}
+
+impl Default for MockTimeSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockTimeSource {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed: Arc::new(RwLock::new(Duration::default())),
+        }
+    }
+
+    /// Advance the clock by `duration`
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.write() += duration;
+    }
+
+    /// Time elapsed since the creation of the clock
+    pub fn elapsed(&self) -> Duration {
+        *self.elapsed.read()
+    }
+}
+
+impl TimeSource for MockTimeSource {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_time_source() {
+        let time_source = MockTimeSource::new();
+        let start = time_source.now();
+        assert_eq!(time_source.now(), start);
+
+        // clones share the same clock
+        time_source.clone().advance(Duration::from_secs(5));
+        assert_eq!(time_source.now() - start, Duration::from_secs(5));
+        assert_eq!(time_source.elapsed(), Duration::from_secs(5));
+    }
+}
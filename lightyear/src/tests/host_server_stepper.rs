@@ -82,9 +82,9 @@ impl HostServerStepper {
         let NetConfig::Netcode { io, .. } = client_config.net.clone() else {
             panic!("Only Netcode transport is supported in tests");
         };
-        if let Some(conditioner) = io.conditioner {
-            server_io = server_io.with_conditioner(conditioner.clone());
-            client_io = client_io.with_conditioner(conditioner.clone());
+        if let Some(conditioner) = io.incoming_conditioner {
+            server_io = server_io.with_incoming_conditioner(conditioner.clone());
+            client_io = client_io.with_incoming_conditioner(conditioner.clone());
         }
 
         // Shared config
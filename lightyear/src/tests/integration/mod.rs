@@ -1,2 +1,3 @@
 mod multi_transport;
+mod reliable_ordering;
 mod tick_wrapping;
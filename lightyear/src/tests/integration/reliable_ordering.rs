@@ -0,0 +1,172 @@
+//! Check that reliable ordered channels deliver every message exactly once, and in order,
+//! even when the link is lossy and reorders or duplicates packets.
+use crate::channel::builder::ReliableSettings;
+use crate::packet::message_manager::MessageManager;
+use crate::packet::packet::FRAGMENT_SIZE;
+use crate::packet::packet_builder::Payload;
+use crate::packet::priority_manager::PriorityConfig;
+use crate::prelude::*;
+use crate::shared::ping::manager::{PingConfig, PingManager};
+use crate::shared::tick_manager::TickManager;
+use crate::shared::time_manager::TimeManager;
+use crate::tests::protocol::*;
+use bevy::prelude::default;
+use bevy::utils::Duration;
+use bytes::Bytes;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const STEP: Duration = Duration::from_millis(10);
+const LOSS: f64 = 0.3;
+const REORDER: f64 = 0.2;
+const DUPLICATION: f64 = 0.1;
+
+/// One direction of a simulated network link.
+///
+/// Every packet is delayed by one step; packets can also be dropped, duplicated
+/// or delayed for a few more steps (which reorders them compared to the packets sent after them).
+struct Link {
+    rng: StdRng,
+    /// Packets in flight along with the step at which they will be delivered
+    in_flight: Vec<(usize, Payload)>,
+}
+
+impl Link {
+    fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            in_flight: vec![],
+        }
+    }
+
+    fn send(&mut self, step: usize, payload: Payload) {
+        if self.rng.gen_bool(LOSS) {
+            return;
+        }
+        let copies = if self.rng.gen_bool(DUPLICATION) { 2 } else { 1 };
+        for _ in 0..copies {
+            let delay = if self.rng.gen_bool(REORDER) {
+                self.rng.gen_range(2..6)
+            } else {
+                1
+            };
+            self.in_flight.push((step + delay, payload.clone()));
+        }
+    }
+
+    /// Return the packets that reach the other end of the link at this step
+    fn deliver(&mut self, step: usize) -> Vec<Payload> {
+        let (ready, in_flight) = std::mem::take(&mut self.in_flight)
+            .into_iter()
+            .partition(|(delivery_step, _)| *delivery_step <= step);
+        self.in_flight = in_flight;
+        ready.into_iter().map(|(_, payload)| payload).collect()
+    }
+}
+
+fn setup() -> (MessageManager, MessageManager) {
+    let mut channel_registry = ChannelRegistry::default();
+    channel_registry.add_channel::<Channel1>(ChannelSettings {
+        mode: ChannelMode::UnorderedUnreliable,
+        ..default()
+    });
+    channel_registry.add_channel::<Channel2>(ChannelSettings {
+        mode: ChannelMode::OrderedReliable(ReliableSettings {
+            rtt_resend_factor: 1.5,
+            rtt_resend_min_delay: STEP * 5,
+            max_send_attempts: None,
+        }),
+        ..default()
+    });
+    let sender = MessageManager::new(&channel_registry, 1.5, PriorityConfig::default());
+    let receiver = MessageManager::new(&channel_registry, 1.5, PriorityConfig::default());
+    (sender, receiver)
+}
+
+/// Message containing its index, padded to `size` bytes
+fn message(index: u32, size: usize) -> Bytes {
+    let mut bytes = index.to_le_bytes().to_vec();
+    bytes.resize(size.max(4), index as u8);
+    bytes.into()
+}
+
+/// Send the messages over a reliable ordered channel through a lossy link (in both directions,
+/// so that acks can also be lost), and return the messages read by the receiver
+fn send_through_lossy_link(messages: &[Bytes], seed: u64) -> Vec<Bytes> {
+    let (mut sender, mut receiver) = setup();
+    let mut time_manager = TimeManager::default();
+    let tick_manager = TickManager::from_config(TickConfig::new(STEP));
+    let ping_manager = PingManager::new(PingConfig::default());
+    let mut to_receiver = Link::new(seed);
+    let mut to_sender = Link::new(seed.wrapping_add(1));
+    let mut received = vec![];
+
+    for message in messages {
+        sender
+            .buffer_send(message.clone(), ChannelKind::of::<Channel2>())
+            .unwrap();
+    }
+    for step in 0..2000 {
+        time_manager.update(STEP);
+        let tick = Tick(step as u16);
+        sender.update(&time_manager, &ping_manager, &tick_manager);
+        receiver.update(&time_manager, &ping_manager, &tick_manager);
+
+        for payload in sender.send_packets(tick).unwrap() {
+            to_receiver.send(step, payload);
+        }
+        for payload in to_receiver.deliver(step) {
+            receiver.recv_packet(payload.into()).unwrap();
+        }
+        received.extend(
+            receiver
+                .read_messages()
+                .filter(|(channel_kind, _)| *channel_kind == ChannelKind::of::<Channel2>())
+                .map(|(_, (_, bytes))| bytes),
+        );
+
+        // the receiver sends a packet every step so that the sender receives acks
+        receiver
+            .buffer_send(vec![0].into(), ChannelKind::of::<Channel1>())
+            .unwrap();
+        for payload in receiver.send_packets(tick).unwrap() {
+            to_sender.send(step, payload);
+        }
+        for payload in to_sender.deliver(step) {
+            sender.recv_packet(payload.into()).unwrap();
+        }
+        if received.len() >= messages.len() && step > 100 {
+            break;
+        }
+    }
+    received
+}
+
+#[test]
+fn test_reliable_ordered_lossy_link() {
+    let messages: Vec<Bytes> = (0..200).map(|i| message(i, 20)).collect();
+    for seed in 0..5 {
+        let received = send_through_lossy_link(&messages, seed);
+        assert_eq!(received, messages, "seed {seed}");
+    }
+}
+
+/// Same as above, but some messages are big enough to be split into fragments, which
+/// can each be lost, duplicated or reordered independently
+#[test]
+fn test_reliable_ordered_lossy_link_fragments() {
+    let messages: Vec<Bytes> = (0..50)
+        .map(|i| {
+            let size = if i % 3 == 0 {
+                (FRAGMENT_SIZE as f32 * 2.5) as usize
+            } else {
+                50
+            };
+            message(i, size)
+        })
+        .collect();
+    for seed in 0..5 {
+        let received = send_through_lossy_link(&messages, seed);
+        assert_eq!(received, messages, "seed {seed}");
+    }
+}
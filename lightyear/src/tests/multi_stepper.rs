@@ -165,7 +165,7 @@ impl MultiBevyStepper {
                 shared: shared_config,
                 net: net_config,
                 sync: sync_config,
-                prediction: prediction_config,
+                prediction: prediction_config.clone(),
                 interpolation: interpolation_config,
                 ..default()
             };
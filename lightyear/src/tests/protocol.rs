@@ -146,6 +146,14 @@ impl Diffable for ComponentDeltaCompression2 {
 #[derive(Component, Clone, Debug, PartialEq, Reflect)]
 pub struct ComponentRollback(pub f32);
 
+/// Component whose updates are replicated on the reliable updates channel
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+pub struct ComponentReliable(pub String);
+
+/// Component whose values received from the clients must be positive
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+pub struct ComponentValidated(pub f32);
+
 // Resources
 #[derive(Resource, Serialize, Deserialize, Debug, PartialEq, Clone, Reflect)]
 pub struct Resource1(pub f32);
@@ -220,7 +228,8 @@ impl Plugin for ProtocolPlugin {
                 serialize_map_entities: None,
             },
         )
-        .add_prediction(ComponentSyncMode::Simple);
+        .add_prediction(ComponentSyncMode::Simple)
+        .add_application_ack();
 
         app.register_component::<ComponentSyncModeOnce>(ChannelDirection::ServerToClient)
             .add_prediction(ComponentSyncMode::Once);
@@ -240,6 +249,12 @@ impl Plugin for ProtocolPlugin {
         app.register_component::<ComponentDeltaCompression2>(ChannelDirection::ServerToClient)
             .add_delta_compression();
 
+        app.register_component::<ComponentReliable>(ChannelDirection::Bidirectional)
+            .with_replication_mode(ReplicationMode::Reliable);
+
+        app.register_component::<ComponentValidated>(ChannelDirection::Bidirectional)
+            .add_validation(|c| c.0 >= 0.0);
+
         app.add_rollback::<ComponentRollback>();
 
         // resources
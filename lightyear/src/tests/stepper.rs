@@ -75,9 +75,9 @@ impl BevyStepper {
         let NetConfig::Netcode { io, .. } = client_config.net else {
             panic!("Only Netcode transport is supported in tests");
         };
-        if let Some(conditioner) = io.conditioner {
-            server_io = server_io.with_conditioner(conditioner.clone());
-            client_io = client_io.with_conditioner(conditioner.clone());
+        if let Some(conditioner) = io.incoming_conditioner {
+            server_io = server_io.with_incoming_conditioner(conditioner.clone());
+            client_io = client_io.with_incoming_conditioner(conditioner.clone());
         }
 
         // Shared config
@@ -291,6 +291,24 @@ impl BevyStepper {
         self.server_app.update();
     }
 
+    /// Advance the world until the client and the server have no more pending messages
+    /// (all buffered messages were sent, and all reliable messages were acked).
+    ///
+    /// Panics if the network is still not drained after `max_frames` frames
+    pub(crate) fn drain_network(&mut self, max_frames: usize) {
+        for _ in 0..max_frames {
+            self.frame_step();
+            if !has_pending_messages(self.client_app.world())
+                && !has_pending_messages(self.server_app.world())
+            {
+                // the last messages sent by the server are received by the client on the next frame
+                self.frame_step();
+                return;
+            }
+        }
+        panic!("the network was not drained after {max_frames} frames");
+    }
+
     pub(crate) fn tick_step(&mut self) {
         self.advance_time(self.tick_duration);
         self.client_app.update();
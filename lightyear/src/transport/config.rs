@@ -1,26 +1,190 @@
+use std::sync::Arc;
+
+use bevy::utils::Duration;
+
+use crate::shared::time_source::TimeSource;
 use crate::transport::middleware::compression::CompressionConfig;
 use crate::transport::middleware::conditioner::LinkConditionerConfig;
+use crate::transport::middleware::{ReceiverMiddleware, SenderMiddleware};
 use bevy::prelude::Reflect;
 
-#[derive(Clone, Debug, Default, Reflect)]
+/// Maximum payload of a UDP datagram (over IPv6; it is 65507 bytes over IPv4)
+pub(crate) const MAX_UDP_PAYLOAD: usize = 65527;
+
+/// Tuning options for the OS sockets used by the transports
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct SocketConfig {
+    /// Set `TCP_NODELAY` on the TCP streams (used by the WebSocket transport), which disables
+    /// Nagle's algorithm.
+    ///
+    /// Nagle's algorithm delays small writes to coalesce them, which adds latency; it is
+    /// disabled by default.
+    pub tcp_nodelay: bool,
+    /// Use UDP generic segmentation offload (GSO) for the UDP transport.
+    ///
+    /// Consecutive packets sent to the same address are batched and handed to the kernel in a
+    /// single `sendmsg` call, which reduces the syscall overhead on servers that send a lot of packets.
+    /// The batch is sent when the sender is flushed at the end of the frame.
+    ///
+    /// This is only supported on Linux (kernel 4.18+); on other platforms, or if the kernel or the
+    /// network interface do not support GSO, packets are sent individually.
+    pub udp_gso: bool,
+    /// Number of times we try to bind the UDP socket again when it becomes invalid
+    /// (for example after a suspend/resume or a network change), before disconnecting the io.
+    ///
+    /// 0 disables the rebind attempts.
+    pub rebind_attempts: u32,
+    /// Delay before the second rebind attempt; the delay doubles after each failed attempt.
+    pub rebind_backoff: Duration,
+    /// If the UDP socket is bound to an IPv6 address (for example `[::]:5000`), also accept IPv4
+    /// traffic on it, as IPv4-mapped IPv6 addresses.
+    ///
+    /// This lets a server accept both IPv4 and IPv6 clients with a single socket.
+    pub udp_dual_stack: bool,
+    /// Size (in bytes) of the buffer in which the UDP transport receives the datagrams.
+    ///
+    /// Datagrams bigger than this are truncated by the OS: they are detected and dropped
+    /// (with a warning, and the `transport.truncated_packets` counter if the `metrics` feature is enabled).
+    /// The default is the maximum payload of a UDP datagram, so that no datagram is ever truncated.
+    pub udp_recv_buffer_size: usize,
+}
+
+impl Default for SocketConfig {
+    fn default() -> Self {
+        Self {
+            tcp_nodelay: true,
+            udp_gso: false,
+            rebind_attempts: 5,
+            rebind_backoff: Duration::from_millis(500),
+            udp_dual_stack: false,
+            udp_recv_buffer_size: MAX_UDP_PAYLOAD,
+        }
+    }
+}
+
+impl SocketConfig {
+    pub fn with_tcp_nodelay(mut self, tcp_nodelay: bool) -> Self {
+        self.tcp_nodelay = tcp_nodelay;
+        self
+    }
+
+    pub fn with_udp_gso(mut self, udp_gso: bool) -> Self {
+        self.udp_gso = udp_gso;
+        self
+    }
+
+    pub fn with_udp_dual_stack(mut self, udp_dual_stack: bool) -> Self {
+        self.udp_dual_stack = udp_dual_stack;
+        self
+    }
+
+    pub fn with_udp_recv_buffer_size(mut self, size: usize) -> Self {
+        self.udp_recv_buffer_size = size;
+        self
+    }
+
+    pub fn with_rebind(mut self, attempts: u32, backoff: Duration) -> Self {
+        self.rebind_attempts = attempts;
+        self.rebind_backoff = backoff;
+        self
+    }
+}
+
+#[derive(Clone, Debug, Reflect)]
 #[reflect(from_reflect = false)]
 pub struct SharedIoConfig<T> {
     #[reflect(ignore)]
     pub transport: T,
-    pub conditioner: Option<LinkConditionerConfig>,
+    /// Conditioner applied to the received packets (the downlink)
+    pub incoming_conditioner: Option<LinkConditionerConfig>,
+    /// Conditioner applied to the sent packets (the uplink).
+    ///
+    /// The delayed packets are sent when the io is sent to or flushed later, which happens every frame.
+    /// Unlike [`SharedIoConfig::incoming_conditioner`], this affects the packets received by the remote peer.
+    pub outgoing_conditioner: Option<LinkConditionerConfig>,
     pub compression: CompressionConfig,
+    /// Packets smaller than this size (in bytes) are sent without compression.
+    ///
+    /// Packets that would be bigger after the compression are also sent without compression.
+    pub compression_min_size: usize,
+    /// Dictionary used by the packet compression (only supported by [`CompressionConfig::Zstd`]).
+    ///
+    /// The client and the server must use the same dictionary.
+    #[reflect(ignore)]
+    pub compression_dictionary: Option<Arc<[u8]>>,
+    pub socket: SocketConfig,
+    /// Append a CRC32 checksum to every packet, and drop the received packets with an invalid checksum.
+    ///
+    /// This is useful for transports that don't guarantee the integrity of the packets.
+    /// The checksum is computed on the compressed packets. The client and the server must use the same setting.
+    pub checksum: bool,
+    /// Custom middleware applied to the received packets, in order.
+    ///
+    /// They are applied directly on the packets received by the transport, before
+    /// the conditioner and the decompression.
+    #[reflect(ignore)]
+    pub receiver_middleware: Vec<Arc<dyn ReceiverMiddleware>>,
+    /// Custom middleware applied to the sent packets, in order.
+    ///
+    /// They are applied after the compression, right before the packets are sent by the transport.
+    #[reflect(ignore)]
+    pub sender_middleware: Vec<Arc<dyn SenderMiddleware>>,
+    /// Clock used by the io middleware that need to read the current time (for example the conditioner).
+    ///
+    /// Uses the real clock if `None`.
+    #[reflect(ignore)]
+    pub time_source: Option<Arc<dyn TimeSource>>,
+    /// Duration of the rolling window used to compute the [`BandwidthStats`](crate::transport::io::BandwidthStats)
+    /// of the io
+    pub bandwidth_window: Duration,
+    /// Maximum number of bytes sent per second by the io.
+    ///
+    /// When the limit is exceeded, sending a packet returns
+    /// [`Error::BandwidthExceeded`](crate::transport::error::Error::BandwidthExceeded) instead of sending it.
+    /// There is no limit if `None`.
+    pub max_send_bandwidth: Option<usize>,
+}
+
+impl<T: Default> Default for SharedIoConfig<T> {
+    fn default() -> Self {
+        Self::from_transport(T::default())
+    }
 }
 
 impl<T> SharedIoConfig<T> {
     pub fn from_transport(transport: T) -> Self {
         Self {
             transport,
-            conditioner: None,
+            incoming_conditioner: None,
+            outgoing_conditioner: None,
             compression: CompressionConfig::default(),
+            compression_min_size: 0,
+            compression_dictionary: None,
+            socket: SocketConfig::default(),
+            checksum: false,
+            receiver_middleware: vec![],
+            sender_middleware: vec![],
+            time_source: None,
+            bandwidth_window: Duration::from_secs(1),
+            max_send_bandwidth: None,
         }
     }
-    pub fn with_conditioner(mut self, conditioner_config: LinkConditionerConfig) -> Self {
-        self.conditioner = Some(conditioner_config);
+    /// Condition both the received and the sent packets with the same config.
+    ///
+    /// The latency is added in both directions: use [`SharedIoConfig::with_incoming_conditioner`]
+    /// to only condition the received packets.
+    pub fn with_conditioner(self, conditioner_config: LinkConditionerConfig) -> Self {
+        self.with_incoming_conditioner(conditioner_config.clone())
+            .with_outgoing_conditioner(conditioner_config)
+    }
+
+    pub fn with_incoming_conditioner(mut self, conditioner_config: LinkConditionerConfig) -> Self {
+        self.incoming_conditioner = Some(conditioner_config);
+        self
+    }
+
+    pub fn with_outgoing_conditioner(mut self, conditioner_config: LinkConditionerConfig) -> Self {
+        self.outgoing_conditioner = Some(conditioner_config);
         self
     }
 
@@ -28,4 +192,54 @@ impl<T> SharedIoConfig<T> {
         self.compression = compression_config;
         self
     }
+
+    pub fn with_compression_min_size(mut self, min_size: usize) -> Self {
+        self.compression_min_size = min_size;
+        self
+    }
+
+    /// Use a dictionary for the packet compression, for example one trained with a
+    /// `DictionarySampler`
+    pub fn with_compression_dictionary(mut self, dictionary: impl Into<Arc<[u8]>>) -> Self {
+        self.compression_dictionary = Some(dictionary.into());
+        self
+    }
+
+    pub fn with_socket_config(mut self, socket_config: SocketConfig) -> Self {
+        self.socket = socket_config;
+        self
+    }
+
+    pub fn with_checksum(mut self, checksum: bool) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// Use a custom [`TimeSource`] instead of the real clock
+    pub fn with_time_source(mut self, time_source: impl TimeSource) -> Self {
+        self.time_source = Some(Arc::new(time_source));
+        self
+    }
+
+    pub fn with_bandwidth_window(mut self, window: Duration) -> Self {
+        self.bandwidth_window = window;
+        self
+    }
+
+    pub fn with_max_send_bandwidth(mut self, bytes_per_second: usize) -> Self {
+        self.max_send_bandwidth = Some(bytes_per_second);
+        self
+    }
+
+    /// Add a middleware at the end of the list of receiver middleware
+    pub fn with_receiver_middleware(mut self, middleware: impl ReceiverMiddleware) -> Self {
+        self.receiver_middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Add a middleware at the end of the list of sender middleware
+    pub fn with_sender_middleware(mut self, middleware: impl SenderMiddleware) -> Self {
+        self.sender_middleware.push(Arc::new(middleware));
+        self
+    }
 }
@@ -0,0 +1,259 @@
+//! Transport provided by the user, for transports that are not supported by lightyear
+//! (for example a relay)
+//!
+//! The packets still go through the middleware, the conditioner and the compression of the io.
+//!
+//! The io and the netcode connection identify the peers with a [`SocketAddr`]. If the transport uses
+//! other identities (for example the peer ids of a relay), the [`PeerAddrMap`] can be used to give each peer
+//! a synthetic address.
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::net::{Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+
+use bevy::utils::HashMap;
+
+use crate::client::io::transport::{ClientTransportBuilder, ClientTransportEnum};
+use crate::client::io::{ClientIoEventReceiver, ClientNetworkEventSender};
+use crate::server::io::transport::{ServerTransportBuilder, ServerTransportEnum};
+use crate::server::io::{ServerIoEventReceiver, ServerNetworkEventSender};
+use crate::transport::io::IoState;
+use crate::transport::{BoxedReceiver, BoxedSender, Transport};
+
+use super::error::Result;
+
+/// Custom transport that can be used with `ClientTransport::Custom` or `ServerTransport::Custom`
+///
+/// The transport is used as a factory: `build` is called every time the `Io` is built from the config,
+/// i.e. when the client connects or when the server starts.
+pub trait CustomTransport: Debug + Send + Sync + 'static {
+    /// Create the sender and the receiver of the transport, and return the local address it is bound to
+    fn build(&self) -> Result<(BoxedSender, BoxedReceiver, SocketAddr)>;
+}
+
+/// Maps the identities of the peers of a transport that doesn't use IP addresses (for example the peer ids
+/// of a relay) to synthetic [`SocketAddr`]s, so that the transport can be used as a [`CustomTransport`]
+///
+/// The sender of the transport translates the address of each packet back to the peer with [`peer`](Self::peer),
+/// and the receiver uses [`addr`](Self::addr) as the address of the packets received from a peer.
+///
+/// The synthetic addresses are in the IPv6 unique local range (`fd00::/8`) and are never re-used,
+/// so they cannot collide with each other.
+#[derive(Debug, Clone)]
+pub struct PeerAddrMap<K> {
+    addrs: HashMap<K, SocketAddr>,
+    peers: HashMap<SocketAddr, K>,
+    next_id: u64,
+}
+
+impl<K> Default for PeerAddrMap<K> {
+    fn default() -> Self {
+        Self {
+            addrs: HashMap::default(),
+            peers: HashMap::default(),
+            next_id: 0,
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone> PeerAddrMap<K> {
+    /// Returns the address of the peer, and assigns a new address to the peer if it doesn't have one yet
+    pub fn addr(&mut self, peer: &K) -> SocketAddr {
+        if let Some(addr) = self.addrs.get(peer) {
+            return *addr;
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let ip = Ipv6Addr::new(
+            0xfd00,
+            0,
+            0,
+            0,
+            (id >> 48) as u16,
+            (id >> 32) as u16,
+            (id >> 16) as u16,
+            id as u16,
+        );
+        let addr = SocketAddr::new(ip.into(), 0);
+        self.addrs.insert(peer.clone(), addr);
+        self.peers.insert(addr, peer.clone());
+        addr
+    }
+
+    /// Returns the peer that has this address, if any
+    pub fn peer(&self, addr: &SocketAddr) -> Option<&K> {
+        self.peers.get(addr)
+    }
+
+    /// Remove the peer (for example when it leaves the relay), and return its address
+    pub fn remove(&mut self, peer: &K) -> Option<SocketAddr> {
+        let addr = self.addrs.remove(peer)?;
+        self.peers.remove(&addr);
+        Some(addr)
+    }
+}
+
+pub(crate) struct CustomTransportBuilder(pub(crate) Arc<dyn CustomTransport>);
+
+impl CustomTransportBuilder {
+    fn build(self) -> Result<CustomTransportSocket> {
+        let (sender, receiver, local_addr) = self.0.build()?;
+        Ok(CustomTransportSocket {
+            local_addr,
+            sender,
+            receiver,
+        })
+    }
+}
+
+impl ClientTransportBuilder for CustomTransportBuilder {
+    fn connect(
+        self,
+    ) -> Result<(
+        ClientTransportEnum,
+        IoState,
+        Option<ClientIoEventReceiver>,
+        Option<ClientNetworkEventSender>,
+    )> {
+        Ok((
+            ClientTransportEnum::Custom(self.build()?),
+            IoState::Connected,
+            None,
+            None,
+        ))
+    }
+}
+
+impl ServerTransportBuilder for CustomTransportBuilder {
+    fn start(
+        self,
+    ) -> Result<(
+        ServerTransportEnum,
+        IoState,
+        Option<ServerIoEventReceiver>,
+        Option<ServerNetworkEventSender>,
+    )> {
+        Ok((
+            ServerTransportEnum::Custom(self.build()?),
+            IoState::Connected,
+            None,
+            None,
+        ))
+    }
+}
+
+pub(crate) struct CustomTransportSocket {
+    local_addr: SocketAddr,
+    sender: BoxedSender,
+    receiver: BoxedReceiver,
+}
+
+impl Transport for CustomTransportSocket {
+    fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    fn split(self) -> (BoxedSender, BoxedReceiver) {
+        (self.sender, self.receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossbeam_channel::{Receiver, Sender};
+
+    use crate::client::io::config::ClientTransport;
+    use crate::prelude::server::ServerTransport;
+    use crate::transport::config::SharedIoConfig;
+    use crate::transport::middleware::conditioner::LinkConditionerConfig;
+    use crate::transport::{PacketReceiver, PacketSender, LOCAL_SOCKET};
+
+    use super::*;
+
+    /// Transport that sends the packets to itself
+    #[derive(Debug)]
+    struct Loopback;
+
+    struct LoopbackSender(Sender<Vec<u8>>);
+
+    impl PacketSender for LoopbackSender {
+        fn send(&mut self, payload: &[u8], _: &SocketAddr) -> Result<()> {
+            self.0.send(payload.to_vec()).unwrap();
+            Ok(())
+        }
+    }
+
+    struct LoopbackReceiver {
+        recv: Receiver<Vec<u8>>,
+        buffer: Vec<u8>,
+    }
+
+    impl PacketReceiver for LoopbackReceiver {
+        fn recv(&mut self) -> Result<Option<(&mut [u8], SocketAddr)>> {
+            Ok(self.recv.try_recv().ok().map(|data| {
+                self.buffer = data;
+                (self.buffer.as_mut_slice(), LOCAL_SOCKET)
+            }))
+        }
+    }
+
+    impl CustomTransport for Loopback {
+        fn build(&self) -> Result<(BoxedSender, BoxedReceiver, SocketAddr)> {
+            let (send, recv) = crossbeam_channel::unbounded();
+            Ok((
+                Box::new(LoopbackSender(send)),
+                Box::new(LoopbackReceiver {
+                    recv,
+                    buffer: vec![],
+                }),
+                "127.0.0.1:1234".parse().unwrap(),
+            ))
+        }
+    }
+
+    #[test]
+    fn test_custom_transport_client() {
+        let mut io = SharedIoConfig::from_transport(ClientTransport::Custom(Arc::new(Loopback)))
+            .connect()
+            .unwrap();
+        assert_eq!(io.local_addr(), "127.0.0.1:1234".parse().unwrap());
+        let msg = b"hello world".as_slice();
+        io.send(msg, &LOCAL_SOCKET).unwrap();
+        let (data, _) = io.recv().unwrap().unwrap();
+        assert_eq!(data, msg);
+        assert_eq!(io.stats().packets_received, 1);
+    }
+
+    #[test]
+    fn test_custom_transport_server_conditioner() {
+        let mut io = SharedIoConfig::from_transport(ServerTransport::Custom(Arc::new(Loopback)))
+            // all packets are lost
+            .with_conditioner(LinkConditionerConfig {
+                incoming_latency: Default::default(),
+                incoming_jitter: Default::default(),
+                incoming_loss: 1.0,
+                duplicate_chance: 0.0,
+                reorder_chance: 0.0,
+            })
+            .start()
+            .unwrap();
+        io.sender.send(b"hello world", &LOCAL_SOCKET).unwrap();
+        assert!(io.receiver.recv().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_peer_addr_map() {
+        let mut map = PeerAddrMap::default();
+        let a = map.addr(&"a");
+        let b = map.addr(&"b");
+        assert_ne!(a, b);
+        assert_eq!(map.addr(&"a"), a);
+        assert_eq!(map.peer(&a), Some(&"a"));
+        assert_eq!(map.peer(&b), Some(&"b"));
+
+        // addresses are not re-used
+        assert_eq!(map.remove(&"a"), Some(a));
+        assert_eq!(map.peer(&a), None);
+        assert_ne!(map.addr(&"a"), a);
+    }
+}
@@ -1,5 +1,13 @@
+//! Errors returned by the transports ([`PacketSender`](crate::transport::PacketSender),
+//! [`PacketReceiver`](crate::transport::PacketReceiver)) and by the [`Io`](crate::transport::io::Io)
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Alias of the transport [`Error`]
+pub type TransportError = Error;
+
+/// Error returned by a transport
+///
+/// Use [`is_recoverable`](Error::is_recoverable) to know if the transport can still be used after the error.
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("transport is not connected. Did you call connect()?")]
@@ -16,6 +24,8 @@ pub enum Error {
     Channel(String),
     #[error("requested by user")]
     UserRequest,
+    #[error("the packet was not sent because the send bandwidth limit is exceeded")]
+    BandwidthExceeded,
     #[cfg(feature = "lz4")]
     #[error("lz4 compression error")]
     CompressError(#[from] lz4_flex::block::CompressError),
@@ -24,6 +34,26 @@ pub enum Error {
     DecompressError(#[from] lz4_flex::block::DecompressError),
 }
 
+impl Error {
+    /// Returns true if the error only affects the current packet, and the transport can still be used.
+    ///
+    /// The other errors (for example a closed connection) are fatal: the transport must be reconnected.
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            Error::Io(e) => matches!(
+                e.kind(),
+                std::io::ErrorKind::WouldBlock
+                    | std::io::ErrorKind::Interrupted
+                    | std::io::ErrorKind::TimedOut
+            ),
+            Error::BandwidthExceeded => true,
+            #[cfg(feature = "lz4")]
+            Error::CompressError(_) | Error::DecompressError(_) => true,
+            _ => false,
+        }
+    }
+}
+
 #[allow(unused_qualifications)]
 impl<T> ::core::convert::From<async_channel::SendError<T>> for Error {
     #[allow(deprecated)]
@@ -55,3 +85,18 @@ impl<T> ::core::convert::From<tokio::sync::mpsc::error::SendError<T>> for Error
         Error::Channel(source.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_recoverable() {
+        let io = |kind| Error::from(std::io::Error::from(kind));
+        assert!(io(std::io::ErrorKind::WouldBlock).is_recoverable());
+        assert!(!io(std::io::ErrorKind::ConnectionAborted).is_recoverable());
+        assert!(Error::BandwidthExceeded.is_recoverable());
+        assert!(!Error::NotConnected.is_recoverable());
+        assert!(!Error::from(crossbeam_channel::SendError(())).is_recoverable());
+    }
+}
@@ -1,18 +1,31 @@
 //! Wrapper around a transport, that can perform additional transformations such as
 //! bandwidth monitoring or compression
+use std::collections::VecDeque;
 use std::fmt::{Debug, Formatter};
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use bevy::diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
 use bevy::prelude::*;
-#[cfg(feature = "metrics")]
-use metrics;
+use bevy::utils::Duration;
+use cfg_if::cfg_if;
+use parking_lot::Mutex;
 
+use crate::shared::time_source::{RealTimeSource, TimeSource};
 use crate::transport::{PacketReceiver, PacketSender};
 
-use super::error::Result;
+use super::error::{Error, Result};
 use super::{BoxedReceiver, BoxedSender};
 
+cfg_if! {
+    if #[cfg(test)] {
+        use mock_instant::global::Instant;
+    } else {
+        use bevy::utils::Instant;
+    }
+}
+
 /// Connected io layer that can send/receive bytes
 #[derive(Resource)]
 pub struct BaseIo<T: Send + Sync> {
@@ -20,17 +33,228 @@ pub struct BaseIo<T: Send + Sync> {
     pub(crate) sender: BoxedSender,
     pub(crate) receiver: BoxedReceiver,
     pub(crate) state: IoState,
-    pub(crate) stats: IoStats,
+    pub(crate) stats: Arc<IoCounters>,
     pub(crate) context: T,
 }
 
-// TODO: add stats/compression to middleware
-#[derive(Default, Debug, Reflect)]
+/// Total number of bytes and packets sent and received since the [`BaseIo`] was created.
+///
+/// These counters are always recorded; the `metrics` feature additionally exports them
+/// to the `metrics` registry.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Reflect)]
 pub struct IoStats {
     pub bytes_sent: usize,
     pub bytes_received: usize,
     pub packets_sent: usize,
     pub packets_received: usize,
+    /// Number of received packets that were dropped because their checksum was invalid
+    /// (only if [`SharedIoConfig::checksum`](crate::transport::config::SharedIoConfig::checksum) is enabled)
+    pub corrupt_packets: usize,
+}
+
+impl IoStats {
+    /// Stats accumulated since the `last` snapshot
+    fn since(&self, last: &IoStats) -> IoStats {
+        // the stats are reset if the io is re-created (for example when the client reconnects),
+        // in which case all the current stats were accumulated since the reset
+        let reset = self.bytes_sent < last.bytes_sent
+            || self.bytes_received < last.bytes_received
+            || self.packets_sent < last.packets_sent
+            || self.packets_received < last.packets_received
+            || self.corrupt_packets < last.corrupt_packets;
+        if reset {
+            return *self;
+        }
+        IoStats {
+            bytes_sent: self.bytes_sent - last.bytes_sent,
+            bytes_received: self.bytes_received - last.bytes_received,
+            packets_sent: self.packets_sent - last.packets_sent,
+            packets_received: self.packets_received - last.packets_received,
+            corrupt_packets: self.corrupt_packets - last.corrupt_packets,
+        }
+    }
+}
+
+/// Number of bytes and packets sent and received during the last
+/// [`SharedIoConfig::bandwidth_window`](crate::transport::config::SharedIoConfig::bandwidth_window).
+///
+/// Unlike the `metrics` feature, these are always available, for example to display a bandwidth HUD.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct BandwidthStats {
+    /// Duration of the rolling window
+    pub window: Duration,
+    pub bytes_sent: usize,
+    pub bytes_received: usize,
+    pub packets_sent: usize,
+    pub packets_received: usize,
+}
+
+impl BandwidthStats {
+    /// Average number of bytes sent per second over the window
+    pub fn bytes_sent_per_second(&self) -> f32 {
+        self.per_second(self.bytes_sent)
+    }
+
+    /// Average number of bytes received per second over the window
+    pub fn bytes_received_per_second(&self) -> f32 {
+        self.per_second(self.bytes_received)
+    }
+
+    fn per_second(&self, value: usize) -> f32 {
+        if self.window.is_zero() {
+            return 0.0;
+        }
+        value as f32 / self.window.as_secs_f32()
+    }
+}
+
+/// Size and time of the packets sent and received during the rolling window
+#[derive(Debug, Default)]
+struct BandwidthWindow {
+    sent: VecDeque<(Instant, usize)>,
+    received: VecDeque<(Instant, usize)>,
+}
+
+impl BandwidthWindow {
+    /// Remove the packets that are older than `window`
+    fn prune(&mut self, now: Instant, window: Duration) {
+        for packets in [&mut self.sent, &mut self.received] {
+            while packets
+                .front()
+                .is_some_and(|(time, _)| now.duration_since(*time) > window)
+            {
+                packets.pop_front();
+            }
+        }
+    }
+}
+
+/// Lightweight counters used to compute the [`IoStats`] and the [`BandwidthStats`].
+///
+/// They use atomics so that they can be updated from both halves of [`BaseIo::split`].
+#[derive(Debug)]
+pub(crate) struct IoCounters {
+    bytes_sent: AtomicUsize,
+    bytes_received: AtomicUsize,
+    packets_sent: AtomicUsize,
+    packets_received: AtomicUsize,
+    corrupt_packets: AtomicUsize,
+    bandwidth_window: Duration,
+    bandwidth: Mutex<BandwidthWindow>,
+    time_source: Arc<dyn TimeSource>,
+}
+
+impl IoCounters {
+    pub(crate) fn new(
+        bandwidth_window: Duration,
+        time_source: Option<Arc<dyn TimeSource>>,
+    ) -> Self {
+        Self {
+            bytes_sent: AtomicUsize::default(),
+            bytes_received: AtomicUsize::default(),
+            packets_sent: AtomicUsize::default(),
+            packets_received: AtomicUsize::default(),
+            corrupt_packets: AtomicUsize::default(),
+            bandwidth_window,
+            bandwidth: Mutex::default(),
+            time_source: time_source.unwrap_or_else(|| Arc::new(RealTimeSource)),
+        }
+    }
+
+    fn record_sent(&self, num_bytes: usize) {
+        #[cfg(feature = "metrics")]
+        {
+            metrics::counter!("transport.packets_sent").increment(1);
+            metrics::gauge!("transport.bytes_sent").increment(num_bytes as f64);
+        }
+        self.bytes_sent.fetch_add(num_bytes, Ordering::Relaxed);
+        self.packets_sent.fetch_add(1, Ordering::Relaxed);
+        let now = self.time_source.now();
+        let mut bandwidth = self.bandwidth.lock();
+        bandwidth.prune(now, self.bandwidth_window);
+        bandwidth.sent.push_back((now, num_bytes));
+    }
+
+    fn record_received(&self, num_bytes: usize) {
+        #[cfg(feature = "metrics")]
+        {
+            metrics::counter!("transport.packets_received").increment(1);
+            metrics::gauge!("transport.bytes_received").increment(num_bytes as f64);
+        }
+        self.bytes_received.fetch_add(num_bytes, Ordering::Relaxed);
+        self.packets_received.fetch_add(1, Ordering::Relaxed);
+        let now = self.time_source.now();
+        let mut bandwidth = self.bandwidth.lock();
+        bandwidth.prune(now, self.bandwidth_window);
+        bandwidth.received.push_back((now, num_bytes));
+    }
+
+    pub(crate) fn record_corrupt(&self) {
+        #[cfg(feature = "metrics")]
+        metrics::counter!("transport.corrupt_packets").increment(1);
+        self.corrupt_packets.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> IoStats {
+        IoStats {
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            packets_sent: self.packets_sent.load(Ordering::Relaxed),
+            packets_received: self.packets_received.load(Ordering::Relaxed),
+            corrupt_packets: self.corrupt_packets.load(Ordering::Relaxed),
+        }
+    }
+
+    fn bandwidth(&self) -> BandwidthStats {
+        let mut bandwidth = self.bandwidth.lock();
+        bandwidth.prune(self.time_source.now(), self.bandwidth_window);
+        BandwidthStats {
+            window: self.bandwidth_window,
+            bytes_sent: bandwidth.sent.iter().map(|(_, bytes)| bytes).sum(),
+            bytes_received: bandwidth.received.iter().map(|(_, bytes)| bytes).sum(),
+            packets_sent: bandwidth.sent.len(),
+            packets_received: bandwidth.received.len(),
+        }
+    }
+
+    fn reset_bandwidth(&self) {
+        *self.bandwidth.lock() = BandwidthWindow::default();
+    }
+}
+
+/// Sender half of [`BaseIo::split`], which records the [`IoStats`]
+pub struct IoSender<'a> {
+    sender: &'a mut BoxedSender,
+    stats: &'a IoCounters,
+}
+
+impl PacketSender for IoSender<'_> {
+    fn send(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
+        self.sender.as_mut().send(payload, address)?;
+        self.stats.record_sent(payload.len());
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.sender.as_mut().flush()
+    }
+}
+
+/// Receiver half of [`BaseIo::split`], which records the [`IoStats`]
+pub struct IoReceiver<'a> {
+    receiver: &'a mut BoxedReceiver,
+    stats: &'a IoCounters,
+}
+
+impl PacketReceiver for IoReceiver<'_> {
+    fn recv(&mut self) -> Result<Option<(&mut [u8], SocketAddr)>> {
+        let stats = self.stats;
+        self.receiver.as_mut().recv().inspect(|x| {
+            if let Some((buffer, _)) = x {
+                stats.record_received(buffer.len());
+            }
+        })
+    }
 }
 
 impl<T: Send + Sync> BaseIo<T> {
@@ -38,13 +262,64 @@ impl<T: Send + Sync> BaseIo<T> {
         self.local_addr
     }
 
-    // TODO: no stats are being computed here!
-    pub fn split(&mut self) -> (&mut impl PacketSender, &mut impl PacketReceiver) {
-        (&mut self.sender, &mut self.receiver)
+    /// Split the io into a sender and a receiver that can be used at the same time
+    pub fn split(&mut self) -> (IoSender<'_>, IoReceiver<'_>) {
+        (
+            IoSender {
+                sender: &mut self.sender,
+                stats: &self.stats,
+            },
+            IoReceiver {
+                receiver: &mut self.receiver,
+                stats: &self.stats,
+            },
+        )
     }
 
-    pub fn stats(&self) -> &IoStats {
-        &self.stats
+    /// Total number of bytes and packets sent and received by this io.
+    ///
+    /// This is available even if the `metrics` feature is disabled.
+    pub fn stats(&self) -> IoStats {
+        self.stats.snapshot()
+    }
+
+    /// Number of bytes and packets sent and received during the last
+    /// [`SharedIoConfig::bandwidth_window`](crate::transport::config::SharedIoConfig::bandwidth_window).
+    ///
+    /// This is available even if the `metrics` feature is disabled.
+    pub fn bandwidth(&self) -> BandwidthStats {
+        self.stats.bandwidth()
+    }
+
+    /// Drop the sender and the receiver of the transport, which releases the underlying socket or channel
+    /// (for example so that the port of a UDP socket can be bound again right away).
+    ///
+    /// The buffered packets are flushed first. Sending or receiving on the io afterwards returns
+    /// [`Error::NotConnected`].
+    pub(crate) fn close_transport(&mut self) {
+        let _ = self.sender.flush();
+        self.sender = Box::new(ClosedTransport);
+        self.receiver = Box::new(ClosedTransport);
+    }
+
+    /// Clear the packets recorded in the bandwidth window (the totals of [`BaseIo::stats`] are not reset)
+    pub fn reset_bandwidth(&self) {
+        self.stats.reset_bandwidth()
+    }
+}
+
+/// Sender and receiver of an io whose transport was closed
+struct ClosedTransport;
+
+impl PacketSender for ClosedTransport {
+    fn send(&mut self, _: &[u8], _: &SocketAddr) -> Result<()> {
+        Err(Error::NotConnected)
+    }
+}
+
+impl PacketReceiver for ClosedTransport {
+    fn recv(&mut self) -> Result<Option<(&mut [u8], SocketAddr)>> {
+        Err(Error::NotConnected)
     }
 }
 
@@ -56,33 +331,24 @@ impl<T: Send + Sync> Debug for BaseIo<T> {
 
 impl<T: Send + Sync> PacketReceiver for BaseIo<T> {
     fn recv(&mut self) -> Result<Option<(&mut [u8], SocketAddr)>> {
-        // todo: bandwidth monitoring
-        self.receiver.as_mut().recv().map(|x| {
-            if let Some((ref buffer, _)) = x {
-                #[cfg(feature = "metrics")]
-                {
-                    metrics::counter!("transport.packets_received").increment(1);
-                    metrics::gauge!("transport.bytes_received").increment(buffer.len() as f64);
-                }
-                self.stats.bytes_received += buffer.len();
-                self.stats.packets_received += 1;
+        let stats = &self.stats;
+        self.receiver.as_mut().recv().inspect(|x| {
+            if let Some((buffer, _)) = x {
+                stats.record_received(buffer.len());
             }
-            x
         })
     }
 }
 
 impl<T: Send + Sync> PacketSender for BaseIo<T> {
     fn send(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
-        // todo: bandwidth monitoring
-        #[cfg(feature = "metrics")]
-        {
-            metrics::counter!("transport.packets_sent").increment(1);
-            metrics::gauge!("transport.bytes_sent").increment(payload.len() as f64);
-        }
-        self.stats.bytes_sent += payload.len();
-        self.stats.packets_sent += 1;
-        self.sender.as_mut().send(payload, address)
+        self.sender.as_mut().send(payload, address)?;
+        self.stats.record_sent(payload.len());
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.sender.as_mut().flush()
     }
 }
 
@@ -102,8 +368,11 @@ impl IoDiagnosticsPlugin {
     /// Max diagnostic history length.
     pub const DIAGNOSTIC_HISTORY_LEN: usize = 60;
 
+    /// Add measurements for the stats accumulated since the `last_stats` snapshot,
+    /// and update the snapshot
     pub(crate) fn update_diagnostics(
-        stats: &mut IoStats,
+        stats: &IoStats,
+        last_stats: &mut IoStats,
         time: &Res<Time<Real>>,
         diagnostics: &mut Diagnostics,
     ) {
@@ -111,19 +380,18 @@ impl IoDiagnosticsPlugin {
         if delta_seconds == 0.0 {
             return;
         }
+        let delta = stats.since(last_stats);
+        let bytes_received = delta.bytes_received as f64;
+        let bytes_sent = delta.bytes_sent as f64;
+        let packets_received = delta.packets_received as f64;
+        let packets_sent = delta.packets_sent as f64;
         diagnostics.add_measurement(&Self::BYTES_IN, || {
-            (stats.bytes_received as f64 / 1000.0) / delta_seconds
-        });
-        diagnostics.add_measurement(&Self::BYTES_OUT, || {
-            (stats.bytes_sent as f64 / 1000.0) / delta_seconds
+            (bytes_received / 1000.0) / delta_seconds
         });
-        diagnostics.add_measurement(&Self::PACKETS_IN, || {
-            stats.packets_received as f64 / delta_seconds
-        });
-        diagnostics.add_measurement(&Self::PACKETS_OUT, || {
-            stats.packets_sent as f64 / delta_seconds
-        });
-        *stats = IoStats::default()
+        diagnostics.add_measurement(&Self::BYTES_OUT, || (bytes_sent / 1000.0) / delta_seconds);
+        diagnostics.add_measurement(&Self::PACKETS_IN, || packets_received / delta_seconds);
+        diagnostics.add_measurement(&Self::PACKETS_OUT, || packets_sent / delta_seconds);
+        *last_stats = *stats;
     }
 }
 
@@ -155,3 +423,128 @@ pub(crate) enum IoState {
     Connected,
     Disconnected,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::connection::client::{ClientConnection, NetClient};
+    use crate::connection::server::{NetServer, ServerConnections};
+    use crate::tests::stepper::BevyStepper;
+
+    use super::*;
+
+    fn stats(stepper: &BevyStepper) -> (IoStats, IoStats) {
+        let client_stats = stepper
+            .client_app
+            .world()
+            .resource::<ClientConnection>()
+            .io()
+            .unwrap()
+            .stats();
+        let server_stats = stepper
+            .server_app
+            .world()
+            .resource::<ServerConnections>()
+            .servers[0]
+            .io()
+            .unwrap()
+            .stats();
+        (client_stats, server_stats)
+    }
+
+    /// Check that the io stats are recorded even without the `metrics` feature,
+    /// and that they accumulate over frames
+    #[test]
+    fn test_io_stats() {
+        let mut stepper = BevyStepper::default();
+        let (client_before, server_before) = stats(&stepper);
+        assert!(client_before.packets_sent > 0);
+        assert!(server_before.packets_sent > 0);
+
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let (client_after, server_after) = stats(&stepper);
+        assert!(client_after.packets_sent > client_before.packets_sent);
+        assert!(client_after.bytes_received > client_before.bytes_received);
+        assert!(server_after.packets_received > server_before.packets_received);
+        // the local channels are lossless: everything the client sent was received by the server
+        assert_eq!(client_after.bytes_sent, server_after.bytes_received);
+        assert_eq!(client_after.packets_sent, server_after.packets_received);
+    }
+
+    #[test]
+    fn test_bandwidth_window() {
+        use crate::client::io::config::ClientTransport;
+        use crate::shared::time_source::MockTimeSource;
+        use crate::transport::config::SharedIoConfig;
+        use crate::transport::LOCAL_SOCKET;
+
+        let time_source = MockTimeSource::new();
+        let (send, recv) = crossbeam_channel::unbounded();
+        let mut io = SharedIoConfig::from_transport(ClientTransport::LocalChannel { send, recv })
+            .with_bandwidth_window(Duration::from_secs(2))
+            .with_time_source(time_source.clone())
+            .connect()
+            .unwrap();
+        io.send(&[0; 10], &LOCAL_SOCKET).unwrap();
+        time_source.advance(Duration::from_secs(1));
+        io.send(&[0; 20], &LOCAL_SOCKET).unwrap();
+        io.recv().unwrap().unwrap();
+        let bandwidth = io.bandwidth();
+        assert_eq!(bandwidth.bytes_sent, 30);
+        assert_eq!(bandwidth.packets_sent, 2);
+        assert_eq!(bandwidth.bytes_received, 10);
+        assert_eq!(bandwidth.bytes_sent_per_second(), 15.0);
+
+        // the first packet is now outside the window
+        time_source.advance(Duration::from_millis(1500));
+        let bandwidth = io.bandwidth();
+        assert_eq!(bandwidth.bytes_sent, 20);
+        assert_eq!(bandwidth.packets_sent, 1);
+        assert_eq!(bandwidth.bytes_received, 10);
+
+        // the reset doesn't affect the totals
+        io.reset_bandwidth();
+        assert_eq!(io.bandwidth().bytes_sent, 0);
+        assert_eq!(io.stats().bytes_sent, 30);
+    }
+
+    /// Check that the stats accumulated since the last snapshot don't go negative when the io is re-created
+    #[test]
+    fn test_io_stats_since_reset() {
+        let last = IoStats {
+            bytes_sent: 100,
+            bytes_received: 200,
+            packets_sent: 10,
+            packets_received: 20,
+            corrupt_packets: 0,
+        };
+        let current = IoStats {
+            bytes_sent: 150,
+            bytes_received: 260,
+            packets_sent: 12,
+            packets_received: 25,
+            corrupt_packets: 0,
+        };
+        assert_eq!(
+            current.since(&last),
+            IoStats {
+                bytes_sent: 50,
+                bytes_received: 60,
+                packets_sent: 2,
+                packets_received: 5,
+                corrupt_packets: 0,
+            }
+        );
+
+        // the io was re-created: only the new stats count
+        let reset = IoStats {
+            bytes_sent: 30,
+            bytes_received: 300,
+            packets_sent: 3,
+            packets_received: 30,
+            corrupt_packets: 0,
+        };
+        assert_eq!(reset.since(&current), reset);
+    }
+}
@@ -0,0 +1,112 @@
+//! Limit the number of bytes sent per second by the io with a token bucket
+//!
+//! The bucket holds up to one second of bandwidth and is refilled continuously.
+//! A packet can be sent as long as the bucket is not empty (the bucket can go below zero
+//! so that packets bigger than the remaining tokens are not blocked forever);
+//! otherwise [`Error::BandwidthExceeded`] is returned and the packet is not sent, so that the caller can back off.
+//!
+//! The limit applies to the bytes that are actually sent, so after the compression and the checksum.
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use cfg_if::cfg_if;
+
+use crate::shared::time_source::TimeSource;
+use crate::transport::error::{Error, Result};
+use crate::transport::PacketSender;
+
+cfg_if! {
+    if #[cfg(test)] {
+        use mock_instant::global::Instant;
+    } else {
+        use bevy::utils::Instant;
+    }
+}
+
+pub(crate) struct BandwidthLimitedSender<T: PacketSender> {
+    inner: T,
+    /// Maximum number of bytes sent per second
+    max_bandwidth: usize,
+    /// Number of bytes that can be sent
+    tokens: f64,
+    last_refill: Instant,
+    time_source: Arc<dyn TimeSource>,
+}
+
+impl<T: PacketSender> BandwidthLimitedSender<T> {
+    pub(crate) fn new(inner: T, max_bandwidth: usize, time_source: Arc<dyn TimeSource>) -> Self {
+        Self {
+            inner,
+            max_bandwidth,
+            tokens: max_bandwidth as f64,
+            last_refill: time_source.now(),
+            time_source,
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = self.time_source.now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.last_refill = now;
+        self.tokens =
+            (self.tokens + elapsed * self.max_bandwidth as f64).min(self.max_bandwidth as f64);
+    }
+}
+
+impl<T: PacketSender> PacketSender for BandwidthLimitedSender<T> {
+    fn send(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
+        self.refill();
+        if self.tokens <= 0.0 {
+            return Err(Error::BandwidthExceeded);
+        }
+        self.inner.send(payload, address)?;
+        self.tokens -= payload.len() as f64;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::utils::Duration;
+
+    use crate::client::io::config::ClientTransport;
+    use crate::shared::time_source::MockTimeSource;
+    use crate::transport::config::SharedIoConfig;
+    use crate::transport::{PacketReceiver, PacketSender, LOCAL_SOCKET};
+
+    #[test]
+    fn test_bandwidth_limit() {
+        let time_source = MockTimeSource::new();
+        let (send, recv) = crossbeam_channel::unbounded();
+        let mut io = SharedIoConfig::from_transport(ClientTransport::LocalChannel { send, recv })
+            .with_max_send_bandwidth(100)
+            .with_time_source(time_source.clone())
+            .connect()
+            .unwrap();
+        io.send(&[0; 60], &LOCAL_SOCKET).unwrap();
+        // the bucket can go below zero
+        io.send(&[0; 60], &LOCAL_SOCKET).unwrap();
+        // the bucket is empty
+        assert!(matches!(
+            io.send(&[0; 10], &LOCAL_SOCKET),
+            Err(crate::transport::error::Error::BandwidthExceeded)
+        ));
+        assert_eq!(io.stats().packets_sent, 2);
+
+        // the bucket is refilled over time
+        time_source.advance(Duration::from_millis(100));
+        assert!(io.send(&[0; 10], &LOCAL_SOCKET).is_err());
+        time_source.advance(Duration::from_millis(200));
+        io.send(&[0; 10], &LOCAL_SOCKET).unwrap();
+        assert_eq!(io.stats().packets_sent, 3);
+        let mut received = 0;
+        while io.recv().unwrap().is_some() {
+            received += 1;
+        }
+        assert_eq!(received, 3);
+    }
+}
@@ -0,0 +1,182 @@
+//! Append a CRC32 checksum to every packet, and drop the received packets that don't match their checksum
+//!
+//! Some transports (for example relays, or exotic transports) don't guarantee the integrity of the packets.
+//! The checksum only detects accidental corruption; it is not a replacement for the encryption and
+//! authentication of the netcode connection.
+//!
+//! The checksum is computed on the bytes that are actually sent, so after the compression.
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use tracing::trace;
+
+use crate::transport::error::Result;
+use crate::transport::io::IoCounters;
+use crate::transport::{PacketReceiver, PacketSender};
+
+/// Number of bytes added at the end of each packet
+pub(crate) const CHECKSUM_SIZE: usize = 4;
+
+pub(crate) struct ChecksumSender<T: PacketSender> {
+    inner: T,
+    buffer: Vec<u8>,
+}
+
+impl<T: PacketSender> ChecksumSender<T> {
+    pub(crate) fn new(inner: T) -> Self {
+        Self {
+            inner,
+            buffer: vec![],
+        }
+    }
+}
+
+impl<T: PacketSender> PacketSender for ChecksumSender<T> {
+    fn send(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
+        self.buffer.clear();
+        self.buffer.extend_from_slice(payload);
+        self.buffer
+            .extend_from_slice(&crc32fast::hash(payload).to_le_bytes());
+        self.inner.send(&self.buffer, address)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+pub(crate) struct ChecksumReceiver<T: PacketReceiver> {
+    inner: T,
+    buffer: Vec<u8>,
+    /// Used to count the packets that were dropped because of an invalid checksum
+    stats: Arc<IoCounters>,
+}
+
+impl<T: PacketReceiver> ChecksumReceiver<T> {
+    pub(crate) fn new(inner: T, stats: Arc<IoCounters>) -> Self {
+        Self {
+            inner,
+            buffer: vec![],
+            stats,
+        }
+    }
+}
+
+/// Return the payload of the packet if its checksum is valid
+fn verify(packet: &[u8]) -> Option<&[u8]> {
+    let split = packet.len().checked_sub(CHECKSUM_SIZE)?;
+    let (payload, checksum) = packet.split_at(split);
+    (crc32fast::hash(payload).to_le_bytes() == checksum).then_some(payload)
+}
+
+impl<T: PacketReceiver> PacketReceiver for ChecksumReceiver<T> {
+    fn recv(&mut self) -> Result<Option<(&mut [u8], SocketAddr)>> {
+        loop {
+            let Some((data, addr)) = self.inner.recv()? else {
+                return Ok(None);
+            };
+            if let Some(payload) = verify(data) {
+                self.buffer.clear();
+                self.buffer.extend_from_slice(payload);
+                return Ok(Some((self.buffer.as_mut_slice(), addr)));
+            }
+            trace!(?addr, "dropping packet with an invalid checksum");
+            self.stats.record_corrupt();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::io::config::ClientTransport;
+    use crate::transport::config::SharedIoConfig;
+    use crate::transport::middleware::SenderMiddleware;
+    use crate::transport::{BoxedSender, LOCAL_SOCKET};
+
+    use super::*;
+
+    /// Middleware that flips a bit of the first byte of every packet
+    #[derive(Debug)]
+    struct Corrupt;
+
+    struct CorruptSender {
+        inner: BoxedSender,
+        buffer: Vec<u8>,
+    }
+
+    impl PacketSender for CorruptSender {
+        fn send(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
+            self.buffer.clear();
+            self.buffer.extend_from_slice(payload);
+            self.buffer[0] ^= 1;
+            self.inner.send(&self.buffer, address)
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            self.inner.flush()
+        }
+    }
+
+    impl SenderMiddleware for Corrupt {
+        fn wrap_sender(&self, sender: BoxedSender) -> BoxedSender {
+            Box::new(CorruptSender {
+                inner: sender,
+                buffer: vec![],
+            })
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let mut packet = b"hello world".to_vec();
+        packet.extend_from_slice(&crc32fast::hash(b"hello world").to_le_bytes());
+        assert_eq!(verify(&packet), Some(b"hello world".as_slice()));
+        packet[1] ^= 1;
+        assert_eq!(verify(&packet), None);
+        assert_eq!(verify(&[0, 1]), None);
+    }
+
+    #[test]
+    fn test_checksum() {
+        let (send, recv) = crossbeam_channel::unbounded();
+        let mut io = SharedIoConfig::from_transport(ClientTransport::LocalChannel { send, recv })
+            .with_checksum(true)
+            .connect()
+            .unwrap();
+        let msg = b"hello world".as_slice();
+        io.sender.send(msg, &LOCAL_SOCKET).unwrap();
+        let (data, _) = io.receiver.recv().unwrap().unwrap();
+        assert_eq!(data, msg);
+        assert_eq!(io.stats().corrupt_packets, 0);
+    }
+
+    #[test]
+    fn test_checksum_corrupt_packet() {
+        let (send, recv) = crossbeam_channel::unbounded();
+        let mut io = SharedIoConfig::from_transport(ClientTransport::LocalChannel { send, recv })
+            .with_checksum(true)
+            .with_sender_middleware(Corrupt)
+            .connect()
+            .unwrap();
+        io.sender.send(b"hello world", &LOCAL_SOCKET).unwrap();
+        assert!(io.receiver.recv().unwrap().is_none());
+        assert_eq!(io.stats().corrupt_packets, 1);
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn test_checksum_with_compression() {
+        use crate::transport::middleware::compression::CompressionConfig;
+
+        let (send, recv) = crossbeam_channel::unbounded();
+        let mut io = SharedIoConfig::from_transport(ClientTransport::LocalChannel { send, recv })
+            .with_checksum(true)
+            .with_compression(CompressionConfig::Lz4)
+            .connect()
+            .unwrap();
+        let msg = b"hello hello hello hello world".as_slice();
+        io.sender.send(msg, &LOCAL_SOCKET).unwrap();
+        let (data, _) = io.receiver.recv().unwrap().unwrap();
+        assert_eq!(data, msg);
+    }
+}
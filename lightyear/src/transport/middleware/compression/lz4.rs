@@ -1,18 +1,15 @@
-//! Zstd compression
+//! Lz4 compression
 
 use crate::connection::netcode::MAX_PKT_BUF_SIZE;
-use crate::transport::error::{Error, Result};
-use std::net::SocketAddr;
+use crate::transport::error::Result;
 
 pub(crate) use compression::Compressor;
 pub(crate) use decompression::Decompressor;
 
 pub(crate) mod compression {
     use super::*;
-    use crate::transport::middleware::PacketSenderWrapper;
-    use crate::transport::PacketSender;
+    use crate::transport::middleware::compression::PacketCompressor;
     use lz4_flex::block::compress_into;
-    use tracing::error;
 
     pub(crate) struct Compressor {
         result: Vec<u8>,
@@ -29,43 +26,21 @@ pub(crate) mod compression {
 
     impl Compressor {
         pub fn compress(&mut self, data: &[u8]) -> Result<&[u8]> {
-            // let res = compress(data);
-            // error!(
-            //     "input size: {:?}, compressed size: {:?}",
-            //     data.len(),
-            //     res.len()
-            // );
             let size = compress_into(data, &mut self.result)?;
             Ok(&self.result[..size])
         }
     }
 
-    struct Lz4PacketSender<T: PacketSender> {
-        inner: T,
-        compressor: Compressor,
-    }
-
-    impl<T: PacketSender> PacketSender for Lz4PacketSender<T> {
-        fn send(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
-            let compressed = self.compressor.compress(payload)?;
-            self.inner.send(compressed, address)
-        }
-    }
-
-    impl<T: PacketSender> PacketSenderWrapper<T> for Compressor {
-        fn wrap(self, sender: T) -> impl PacketSender {
-            Lz4PacketSender {
-                inner: sender,
-                compressor: self,
-            }
+    impl PacketCompressor for Compressor {
+        fn compress_packet(&mut self, data: &[u8]) -> Result<&[u8]> {
+            self.compress(data)
         }
     }
 }
 
 pub(crate) mod decompression {
     use super::*;
-    use crate::transport::middleware::PacketReceiverWrapper;
-    use crate::transport::PacketReceiver;
+    use crate::transport::middleware::compression::PacketDecompressor;
     use lz4_flex::block::decompress_into;
 
     pub(crate) struct Decompressor {
@@ -83,33 +58,18 @@ pub(crate) mod decompression {
 
     impl Decompressor {
         pub fn decompress(&mut self, data: &[u8]) -> Result<&mut [u8]> {
-            let size = decompress_into(data, &mut self.result)?;
-            Ok(&mut self.result[..size])
+            let size = self.decompress_packet(data)?;
+            Ok(self.decompressed(size))
         }
     }
 
-    struct Lz4PacketReceiver<T: PacketReceiver> {
-        inner: T,
-        decompressor: Decompressor,
-    }
-
-    impl<T: PacketReceiver> PacketReceiver for Lz4PacketReceiver<T> {
-        fn recv(&mut self) -> Result<Option<(&mut [u8], SocketAddr)>> {
-            if let Some((buf, addr)) = self.inner.recv()? {
-                let decompressed = self.decompressor.decompress(buf)?;
-                Ok(Some((decompressed, addr)))
-            } else {
-                Ok(None)
-            }
+    impl PacketDecompressor for Decompressor {
+        fn decompress_packet(&mut self, data: &[u8]) -> Result<usize> {
+            Ok(decompress_into(data, &mut self.result)?)
         }
-    }
 
-    impl<T: PacketReceiver> PacketReceiverWrapper<T> for Decompressor {
-        fn wrap(self, receiver: T) -> impl PacketReceiver {
-            Lz4PacketReceiver {
-                inner: receiver,
-                decompressor: self,
-            }
+        fn decompressed(&mut self, size: usize) -> &mut [u8] {
+            &mut self.result[..size]
         }
     }
 }
@@ -128,8 +88,9 @@ mod tests {
         let config = ClientTransport::LocalChannel { send, recv };
         let io_config = SharedIoConfig::<ClientTransport> {
             transport: config,
-            conditioner: None,
+            incoming_conditioner: None,
             compression: CompressionConfig::Lz4,
+            ..Default::default()
         };
         let mut io = io_config.connect().unwrap();
         let msg = b"hello world".as_slice();
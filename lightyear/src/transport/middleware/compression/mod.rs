@@ -1,13 +1,21 @@
+use crate::packet::packet::FRAGMENT_SIZE;
+use crate::serialize::SerializationError;
 use bevy::prelude::Reflect;
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "zstd")]
 pub(crate) mod zstd;
+#[cfg(feature = "zstd")]
+pub use zstd::{train_dictionary, DictionarySampler};
 
 #[cfg(feature = "lz4")]
 pub(crate) mod lz4;
 
-#[derive(Clone, Copy, Debug, Default, Reflect, Serialize, Deserialize)]
+#[cfg_attr(not(any(feature = "zstd", feature = "lz4")), allow(dead_code))]
+pub(crate) mod packet;
+pub(crate) use packet::{PacketCompressor, PacketDecompressor};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Reflect, Serialize, Deserialize)]
 pub enum CompressionConfig {
     #[default]
     None,
@@ -16,3 +24,93 @@ pub enum CompressionConfig {
     #[cfg(feature = "lz4")]
     Lz4,
 }
+
+/// Compression settings for a single message or component type, specified when registering the type.
+///
+/// This applies on top of the packet-level compression of the [`SharedIoConfig`](crate::transport::config::SharedIoConfig).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TypeCompressionConfig {
+    pub compression: CompressionConfig,
+    /// Serialized values that are smaller than this size (in bytes) are sent without compression
+    pub min_size: usize,
+}
+
+impl TypeCompressionConfig {
+    pub fn new(compression: CompressionConfig) -> Self {
+        Self {
+            compression,
+            min_size: 0,
+        }
+    }
+
+    pub fn with_min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+}
+
+/// Maximum size of a decompressed value.
+///
+/// This is the size of the largest message that can be sent (a message is split in at most
+/// `u8::MAX` fragments), so that a malicious peer cannot make us allocate an unbounded buffer.
+pub(crate) const MAX_DECOMPRESSED_SIZE: usize = u8::MAX as usize * FRAGMENT_SIZE;
+
+#[cfg(any(feature = "zstd", feature = "lz4"))]
+fn compression_error(
+    error: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+) -> SerializationError {
+    SerializationError::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+}
+
+/// Check the size of the decompressed value that is announced by the sender
+#[cfg(any(feature = "zstd", feature = "lz4"))]
+fn check_decompressed_size(size: usize) -> Result<usize, SerializationError> {
+    if size > MAX_DECOMPRESSED_SIZE {
+        return Err(compression_error(format!(
+            "the decompressed value is too large ({size} bytes)"
+        )));
+    }
+    Ok(size)
+}
+
+/// Compress a serialized value
+pub(crate) fn compress(
+    compression: CompressionConfig,
+    data: &[u8],
+) -> Result<Vec<u8>, SerializationError> {
+    match compression {
+        CompressionConfig::None => Ok(data.to_vec()),
+        #[cfg(feature = "zstd")]
+        CompressionConfig::Zstd { level } => Ok(::zstd::bulk::compress(data, level)?),
+        #[cfg(feature = "lz4")]
+        CompressionConfig::Lz4 => Ok(lz4_flex::block::compress_prepend_size(data)),
+    }
+}
+
+/// Decompress a value that was compressed with [`compress`]
+///
+/// Returns an error if the decompressed value would be larger than [`MAX_DECOMPRESSED_SIZE`]
+pub(crate) fn decompress(
+    compression: CompressionConfig,
+    data: &[u8],
+) -> Result<Vec<u8>, SerializationError> {
+    match compression {
+        CompressionConfig::None => Ok(data.to_vec()),
+        #[cfg(feature = "zstd")]
+        CompressionConfig::Zstd { .. } => {
+            // the compressor writes the content size in the frame header
+            let size = ::zstd::zstd_safe::get_frame_content_size(data)
+                .map_err(|e| compression_error(e.to_string()))?
+                .map_or(MAX_DECOMPRESSED_SIZE, |size| size as usize);
+            let size = check_decompressed_size(size)?;
+            Ok(::zstd::bulk::decompress(data, size)?)
+        }
+        #[cfg(feature = "lz4")]
+        CompressionConfig::Lz4 => {
+            let (size, data) =
+                lz4_flex::block::uncompressed_size(data).map_err(compression_error)?;
+            let size = check_decompressed_size(size)?;
+            lz4_flex::block::decompress(data, size).map_err(compression_error)
+        }
+    }
+}
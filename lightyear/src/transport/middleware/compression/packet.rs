@@ -0,0 +1,246 @@
+//! Compression of whole packets in the io
+//!
+//! Each compressed packet starts with a byte that indicates if the rest of the packet is compressed:
+//! - packets smaller than [`SharedIoConfig::compression_min_size`](crate::transport::config::SharedIoConfig::compression_min_size)
+//!   are sent without compression
+//! - packets that would be bigger after the compression are also sent without compression
+//! - packets that could not be compressed are also sent without compression
+//!
+//! Received packets that cannot be decompressed are dropped.
+use std::net::SocketAddr;
+
+use tracing::trace;
+
+use crate::transport::error::Result;
+use crate::transport::{PacketReceiver, PacketSender};
+
+/// Number of bytes added at the start of each packet
+pub(crate) const FLAG_SIZE: usize = 1;
+
+/// The rest of the packet is not compressed
+const UNCOMPRESSED: u8 = 0;
+/// The rest of the packet is compressed
+const COMPRESSED: u8 = 1;
+
+/// Compression algorithm used by the [`CompressedPacketSender`]
+pub(crate) trait PacketCompressor: Send + Sync {
+    fn compress_packet(&mut self, data: &[u8]) -> Result<&[u8]>;
+}
+
+/// Decompression algorithm used by the [`CompressedPacketReceiver`]
+pub(crate) trait PacketDecompressor: Send + Sync {
+    /// Decompress the data in an internal buffer, and return the size of the decompressed data
+    fn decompress_packet(&mut self, data: &[u8]) -> Result<usize>;
+
+    /// The first `size` bytes of the internal buffer
+    fn decompressed(&mut self, size: usize) -> &mut [u8];
+}
+
+pub(crate) struct CompressedPacketSender<T: PacketSender, C: PacketCompressor> {
+    inner: T,
+    compressor: C,
+    min_size: usize,
+    buffer: Vec<u8>,
+}
+
+impl<T: PacketSender, C: PacketCompressor> CompressedPacketSender<T, C> {
+    pub(crate) fn new(inner: T, compressor: C, min_size: usize) -> Self {
+        Self {
+            inner,
+            compressor,
+            min_size,
+            buffer: vec![],
+        }
+    }
+}
+
+impl<T: PacketSender, C: PacketCompressor> PacketSender for CompressedPacketSender<T, C> {
+    fn send(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
+        self.buffer.clear();
+        if payload.len() >= self.min_size {
+            match self.compressor.compress_packet(payload) {
+                Ok(compressed) if compressed.len() < payload.len() => {
+                    self.buffer.push(COMPRESSED);
+                    self.buffer.extend_from_slice(compressed);
+                    return self.inner.send(&self.buffer, address);
+                }
+                Ok(_) => {}
+                Err(e) => trace!(
+                    ?address,
+                    ?e,
+                    "sending packet uncompressed because the compression failed"
+                ),
+            }
+        }
+        self.buffer.push(UNCOMPRESSED);
+        self.buffer.extend_from_slice(payload);
+        self.inner.send(&self.buffer, address)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+pub(crate) struct CompressedPacketReceiver<T: PacketReceiver, D: PacketDecompressor> {
+    inner: T,
+    decompressor: D,
+    buffer: Vec<u8>,
+}
+
+impl<T: PacketReceiver, D: PacketDecompressor> CompressedPacketReceiver<T, D> {
+    pub(crate) fn new(inner: T, decompressor: D) -> Self {
+        Self {
+            inner,
+            decompressor,
+            buffer: vec![],
+        }
+    }
+}
+
+impl<T: PacketReceiver, D: PacketDecompressor> PacketReceiver for CompressedPacketReceiver<T, D> {
+    fn recv(&mut self) -> Result<Option<(&mut [u8], SocketAddr)>> {
+        loop {
+            let Some((data, addr)) = self.inner.recv()? else {
+                return Ok(None);
+            };
+            match data.split_first() {
+                Some((&UNCOMPRESSED, payload)) => {
+                    self.buffer.clear();
+                    self.buffer.extend_from_slice(payload);
+                    return Ok(Some((self.buffer.as_mut_slice(), addr)));
+                }
+                Some((&COMPRESSED, payload)) => {
+                    match self.decompressor.decompress_packet(payload) {
+                        Ok(size) => return Ok(Some((self.decompressor.decompressed(size), addr))),
+                        Err(e) => {
+                            trace!(?addr, ?e, "dropping packet that could not be decompressed")
+                        }
+                    }
+                }
+                _ => trace!(?addr, "dropping packet with an invalid compression header"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossbeam_channel::{Receiver, Sender};
+
+    use crate::transport::LOCAL_SOCKET;
+
+    use super::*;
+
+    /// Compresses the packets that contain a single repeated byte
+    struct RepeatCompressor(Vec<u8>);
+
+    impl PacketCompressor for RepeatCompressor {
+        fn compress_packet(&mut self, data: &[u8]) -> Result<&[u8]> {
+            self.0.clear();
+            if data.len() > u8::MAX as usize {
+                return Err(std::io::Error::other("packet too big").into());
+            }
+            if data.iter().all(|b| *b == data[0]) {
+                self.0.extend_from_slice(&[data.len() as u8, data[0]]);
+            } else {
+                self.0.extend_from_slice(data);
+                self.0.push(0);
+            }
+            Ok(&self.0)
+        }
+    }
+
+    struct RepeatDecompressor(Vec<u8>);
+
+    impl PacketDecompressor for RepeatDecompressor {
+        fn decompress_packet(&mut self, data: &[u8]) -> Result<usize> {
+            let [len, byte] = data else {
+                return Err(std::io::Error::other("invalid data").into());
+            };
+            self.0 = vec![*byte; *len as usize];
+            Ok(self.0.len())
+        }
+
+        fn decompressed(&mut self, size: usize) -> &mut [u8] {
+            &mut self.0[..size]
+        }
+    }
+
+    struct ChannelSender(Sender<Vec<u8>>);
+
+    impl PacketSender for ChannelSender {
+        fn send(&mut self, payload: &[u8], _: &SocketAddr) -> Result<()> {
+            self.0.send(payload.to_vec()).unwrap();
+            Ok(())
+        }
+    }
+
+    struct ChannelReceiver(Receiver<Vec<u8>>, Vec<u8>);
+
+    impl PacketReceiver for ChannelReceiver {
+        fn recv(&mut self) -> Result<Option<(&mut [u8], SocketAddr)>> {
+            Ok(self.0.try_recv().ok().map(|data| {
+                self.1 = data;
+                (self.1.as_mut_slice(), LOCAL_SOCKET)
+            }))
+        }
+    }
+
+    #[test]
+    fn test_compressed_packets() {
+        let (send, recv) = crossbeam_channel::unbounded();
+        let mut sender =
+            CompressedPacketSender::new(ChannelSender(send), RepeatCompressor(vec![]), 4);
+        sender.send(&[1; 10], &LOCAL_SOCKET).unwrap();
+        // smaller than the threshold
+        sender.send(&[1; 3], &LOCAL_SOCKET).unwrap();
+        // the compression would make the packet bigger
+        sender.send(&[1, 2, 3, 4], &LOCAL_SOCKET).unwrap();
+        // the compression fails
+        sender.send(&[1; 300], &LOCAL_SOCKET).unwrap();
+        let packets: Vec<_> = recv.try_iter().collect();
+        assert_eq!(
+            packets,
+            vec![
+                vec![COMPRESSED, 10, 1],
+                vec![UNCOMPRESSED, 1, 1, 1],
+                vec![UNCOMPRESSED, 1, 2, 3, 4],
+                [vec![UNCOMPRESSED], vec![1; 300]].concat(),
+            ]
+        );
+
+        let (send, recv) = crossbeam_channel::unbounded();
+        packets
+            .into_iter()
+            .for_each(|packet| send.send(packet).unwrap());
+        let mut receiver = CompressedPacketReceiver::new(
+            ChannelReceiver(recv, vec![]),
+            RepeatDecompressor(vec![]),
+        );
+        assert_eq!(receiver.recv().unwrap().unwrap().0, &[1; 10]);
+        assert_eq!(receiver.recv().unwrap().unwrap().0, &[1; 3]);
+        assert_eq!(receiver.recv().unwrap().unwrap().0, &[1, 2, 3, 4]);
+        assert_eq!(receiver.recv().unwrap().unwrap().0, &[1; 300]);
+        assert!(receiver.recv().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_drop_malformed_packets() {
+        let (send, recv) = crossbeam_channel::unbounded();
+        let mut receiver = CompressedPacketReceiver::new(
+            ChannelReceiver(recv, vec![]),
+            RepeatDecompressor(vec![]),
+        );
+        // empty packet
+        send.send(vec![]).unwrap();
+        // invalid header
+        send.send(vec![5, 1, 2]).unwrap();
+        // invalid compressed data
+        send.send(vec![COMPRESSED, 1, 2, 3]).unwrap();
+        send.send(vec![COMPRESSED, 2, 7]).unwrap();
+        // the malformed packets are skipped
+        assert_eq!(receiver.recv().unwrap().unwrap().0, &[7, 7]);
+        assert!(receiver.recv().unwrap().is_none());
+    }
+}
@@ -1,13 +1,32 @@
 //! Zstd compression
+//!
+//! Game packets are small, so compressing each packet on its own cannot exploit the redundancy between
+//! packets (the same entity ids, component layouts and headers are sent over and over).
+//! A zstd dictionary trained on representative traffic fixes this: set it with
+//! [`SharedIoConfig::with_compression_dictionary`](crate::transport::config::SharedIoConfig::with_compression_dictionary)
+//! on both the client and the server.
+//!
+//! To train a dictionary, record some traffic with a [`DictionarySampler`] and call [`DictionarySampler::train`]:
+//! ```rust,ignore
+//! let sampler = DictionarySampler::new(10_000);
+//! // packets must be recorded without packet compression
+//! let io = server::IoConfig::from_transport(transport).with_sender_middleware(sampler.clone());
+//! // ... run the game for a while ...
+//! let dictionary = sampler.train(16 * 1024)?;
+//! std::fs::write("packets.dict", dictionary)?;
+//! ```
+use std::fmt::{Debug, Formatter};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
 
 use crate::connection::netcode::MAX_PKT_BUF_SIZE;
 use crate::transport::error::{Error, Result};
-use std::net::SocketAddr;
+use crate::transport::middleware::SenderMiddleware;
+use crate::transport::{BoxedSender, PacketSender};
 
 pub(crate) mod compression {
     use super::*;
-    use crate::transport::middleware::PacketSenderWrapper;
-    use crate::transport::PacketSender;
+    use crate::transport::middleware::compression::PacketCompressor;
     use zstd::bulk::Compressor;
 
     pub(crate) struct ZstdCompressor {
@@ -16,10 +35,15 @@ pub(crate) mod compression {
     }
 
     impl ZstdCompressor {
-        pub fn new(level: i32) -> Self {
+        /// The same `dictionary` must be used by the decompressor
+        pub fn new(level: i32, dictionary: Option<&[u8]>) -> Self {
+            let compressor = match dictionary {
+                Some(dictionary) => Compressor::with_dictionary(level, dictionary),
+                None => Compressor::new(level),
+            };
             ZstdCompressor {
                 result: Vec::with_capacity(MAX_PKT_BUF_SIZE),
-                compressor: Compressor::new(level).unwrap(),
+                compressor: compressor.unwrap(),
             }
         }
 
@@ -31,32 +55,16 @@ pub(crate) mod compression {
         }
     }
 
-    struct ZstdPacketSender<T: PacketSender> {
-        inner: T,
-        compressor: ZstdCompressor,
-    }
-
-    impl<T: PacketSender> PacketSender for ZstdPacketSender<T> {
-        fn send(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
-            let compressed = self.compressor.compress(payload)?;
-            self.inner.send(compressed, address)
-        }
-    }
-
-    impl<T: PacketSender> PacketSenderWrapper<T> for ZstdCompressor {
-        fn wrap(self, sender: T) -> impl PacketSender {
-            ZstdPacketSender {
-                inner: sender,
-                compressor: self,
-            }
+    impl PacketCompressor for ZstdCompressor {
+        fn compress_packet(&mut self, data: &[u8]) -> Result<&[u8]> {
+            self.compress(data)
         }
     }
 }
 
 pub(crate) mod decompression {
     use super::*;
-    use crate::transport::middleware::PacketReceiverWrapper;
-    use crate::transport::PacketReceiver;
+    use crate::transport::middleware::compression::PacketDecompressor;
     use zstd::bulk::Decompressor;
 
     pub(crate) struct ZstdDecompressor {
@@ -65,70 +73,174 @@ pub(crate) mod decompression {
     }
 
     impl ZstdDecompressor {
-        pub fn new() -> Self {
+        pub fn new(dictionary: Option<&[u8]>) -> Self {
+            let decompressor = match dictionary {
+                Some(dictionary) => Decompressor::with_dictionary(dictionary),
+                None => Decompressor::new(),
+            };
             ZstdDecompressor {
                 result: Vec::with_capacity(MAX_PKT_BUF_SIZE),
-                decompressor: Decompressor::new().unwrap(),
+                decompressor: decompressor.unwrap(),
             }
         }
 
         pub fn decompress(&mut self, data: &[u8]) -> Result<&mut [u8]> {
+            let size = self.decompress_packet(data)?;
+            Ok(self.decompressed(size))
+        }
+    }
+
+    impl PacketDecompressor for ZstdDecompressor {
+        fn decompress_packet(&mut self, data: &[u8]) -> Result<usize> {
             self.decompressor
                 .decompress_to_buffer(data, &mut self.result)
-                .map_err(|e| Error::Io(e))?;
-            Ok(&mut self.result)
+                .map_err(|e| Error::Io(e))
+        }
+
+        fn decompressed(&mut self, size: usize) -> &mut [u8] {
+            &mut self.result[..size]
         }
     }
+}
+
+/// Train a zstd dictionary of at most `max_size` bytes from sample packets
+pub fn train_dictionary(samples: &[impl AsRef<[u8]>], max_size: usize) -> std::io::Result<Vec<u8>> {
+    zstd::dict::from_samples(samples, max_size)
+}
 
-    struct ZstdPacketReceiver<T: PacketReceiver> {
-        inner: T,
-        decompressor: ZstdDecompressor,
+/// [`SenderMiddleware`] that records the packets that are sent, to train a compression dictionary
+///
+/// The sender middleware are applied after the compression, so the packets must be recorded
+/// with the packet compression disabled.
+#[derive(Clone, Default)]
+pub struct DictionarySampler {
+    samples: Arc<Mutex<Vec<Vec<u8>>>>,
+    max_samples: usize,
+}
+
+impl Debug for DictionarySampler {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DictionarySampler")
+            .field("samples", &self.samples.lock().unwrap().len())
+            .field("max_samples", &self.max_samples)
+            .finish()
     }
+}
 
-    impl<T: PacketReceiver> PacketReceiver for ZstdPacketReceiver<T> {
-        fn recv(&mut self) -> Result<Option<(&mut [u8], SocketAddr)>> {
-            if let Some((buf, addr)) = self.inner.recv()? {
-                let decompressed = self.decompressor.decompress(buf)?;
-                Ok(Some((decompressed, addr)))
-            } else {
-                Ok(None)
-            }
+impl DictionarySampler {
+    /// Record at most `max_samples` packets
+    pub fn new(max_samples: usize) -> Self {
+        Self {
+            samples: Arc::default(),
+            max_samples,
         }
     }
 
-    impl<T: PacketReceiver> PacketReceiverWrapper<T> for ZstdDecompressor {
-        fn wrap(self, receiver: T) -> impl PacketReceiver {
-            ZstdPacketReceiver {
-                inner: receiver,
-                decompressor: self,
-            }
+    /// Packets recorded so far
+    pub fn samples(&self) -> Vec<Vec<u8>> {
+        self.samples.lock().unwrap().clone()
+    }
+
+    /// Train a dictionary of at most `max_size` bytes from the recorded packets
+    pub fn train(&self, max_size: usize) -> std::io::Result<Vec<u8>> {
+        train_dictionary(&self.samples.lock().unwrap(), max_size)
+    }
+}
+
+struct SamplingPacketSender {
+    inner: BoxedSender,
+    sampler: DictionarySampler,
+}
+
+impl PacketSender for SamplingPacketSender {
+    fn send(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
+        let mut samples = self.sampler.samples.lock().unwrap();
+        if samples.len() < self.sampler.max_samples {
+            samples.push(payload.to_vec());
         }
+        drop(samples);
+        self.inner.send(payload, address)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl SenderMiddleware for DictionarySampler {
+    fn wrap_sender(&self, sender: BoxedSender) -> BoxedSender {
+        Box::new(SamplingPacketSender {
+            inner: sender,
+            sampler: self.clone(),
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::prelude::{SharedIoConfig, TransportConfig};
-    use crate::transport::middleware::compression::CompressionConfig;
+    use super::compression::ZstdCompressor;
+    use super::decompression::ZstdDecompressor;
+    use super::*;
     use crate::transport::LOCAL_SOCKET;
 
+    struct DiscardSender;
+
+    impl PacketSender for DiscardSender {
+        fn send(&mut self, _: &[u8], _: &SocketAddr) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Packets that look like replication updates: a common header, an entity id and a position
+    fn packets(count: u32) -> Vec<Vec<u8>> {
+        (0..count)
+            .map(|i| {
+                let mut packet = b"header:update;group=".to_vec();
+                packet.extend_from_slice(&(i % 16).to_le_bytes());
+                packet.extend_from_slice(b";component=Position;x=");
+                packet.extend_from_slice(&(i as f32).to_le_bytes());
+                packet
+            })
+            .collect()
+    }
+
     #[test]
     fn test_compression() {
-        let (send, recv) = crossbeam_channel::unbounded();
-
-        let config = TransportConfig::LocalChannel { send, recv };
-        let io_config = SharedIoConfig {
-            transport: config,
-            conditioner: None,
-            compression: CompressionConfig::Zstd { level: 0 },
-        };
-        let mut io = io_config.connect().unwrap();
+        let mut compressor = ZstdCompressor::new(0, None);
+        let mut decompressor = ZstdDecompressor::new(None);
         let msg = b"hello world".as_slice();
-        // send data
-        io.sender.send(&msg, &LOCAL_SOCKET).unwrap();
+        let compressed = compressor.compress(msg).unwrap().to_vec();
+        assert_eq!(decompressor.decompress(&compressed).unwrap(), msg);
+    }
 
-        // receive data
-        let (data, addr) = io.receiver.recv().unwrap().unwrap();
-        assert_eq!(data.as_ref(), msg);
+    #[test]
+    fn test_compression_dictionary() {
+        let sampler = DictionarySampler::new(500);
+        let mut sender = sampler.wrap_sender(Box::new(DiscardSender));
+        for packet in packets(1000) {
+            sender.send(&packet, &LOCAL_SOCKET).unwrap();
+        }
+        assert_eq!(sampler.samples().len(), 500);
+        let dictionary = sampler.train(1024).unwrap();
+
+        let packet = &packets(1001)[1000];
+        let mut compressor = ZstdCompressor::new(3, None);
+        let size = compressor.compress(packet).unwrap().len();
+        let mut compressor = ZstdCompressor::new(3, Some(&dictionary));
+        let compressed = compressor.compress(packet).unwrap().to_vec();
+        assert!(
+            compressed.len() < size / 2,
+            "with dictionary: {}, without: {size}",
+            compressed.len()
+        );
+
+        let mut decompressor = ZstdDecompressor::new(Some(&dictionary));
+        assert_eq!(
+            decompressor.decompress(&compressed).unwrap(),
+            packet.as_slice()
+        );
+        // the receiver must use the same dictionary
+        let mut decompressor = ZstdDecompressor::new(None);
+        assert!(decompressor.decompress(&compressed).is_err());
     }
 }
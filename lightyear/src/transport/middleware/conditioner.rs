@@ -1,15 +1,26 @@
 //! Contains the `LinkConditioner` struct which can be used to simulate network conditions
+//!
+//! The conditioner can be applied on both paths of the io:
+//! - on the receive path ([`ConditionedPacketReceiver`]), the received packets are delayed, dropped, duplicated or reordered
+//!   before being returned by `recv`. This only affects the packets received by this peer.
+//! - on the send path ([`ConditionedPacketSender`]), the sent packets are delayed, dropped, duplicated or reordered before being
+//!   handed to the transport. The delayed packets are sent by the later calls to `send` or `flush`
+//!   (the io is flushed every frame), so the sender never blocks. This affects the packets that the remote peer
+//!   receives, which is useful to simulate asymmetric links from a single peer.
 use bevy::reflect::Reflect;
 use std::net::SocketAddr;
+use std::sync::Arc;
 
-use bevy::utils::Duration;
+use bevy::utils::{Duration, HashMap};
 use cfg_if::cfg_if;
+use parking_lot::RwLock;
 use rand;
 use rand::{thread_rng, Rng};
 
+use crate::shared::time_source::{RealTimeSource, TimeSource};
 use crate::transport::error::Result;
 use crate::transport::middleware::PacketReceiverWrapper;
-use crate::transport::PacketReceiver;
+use crate::transport::{PacketReceiver, PacketSender};
 use crate::utils::ready_buffer::ReadyBuffer;
 
 cfg_if! {
@@ -32,6 +43,17 @@ pub struct LinkConditionerConfig {
     /// The % chance that an incoming packet will be dropped.
     /// Represented as a value between 0 and 1
     pub incoming_loss: f32,
+    /// The % chance that an incoming packet will be received twice.
+    /// Represented as a value between 0 and 1
+    ///
+    /// The duplicate gets its own jitter, so it can be received before or after the original packet.
+    pub duplicate_chance: f32,
+    /// The % chance that a packet that is ready to be received is swapped with the next ready packet.
+    /// Represented as a value between 0 and 1
+    ///
+    /// This is independent of the latency and jitter: only the packets that are ready at the same time
+    /// can be swapped. (On the server, the per-address conditioner uses the reorder chance of the default config)
+    pub reorder_chance: f32,
 }
 
 pub(crate) type PacketLinkConditioner = LinkConditioner<(SocketAddr, Box<[u8]>)>;
@@ -40,28 +62,54 @@ pub(crate) struct LinkConditioner<P: Eq> {
     config: LinkConditionerConfig,
     pub time_queue: ReadyBuffer<Instant, P>,
     last_packet: Option<P>,
+    /// Packet that was swapped with the next ready packet, and is returned by the next `pop_packet`
+    swapped_packet: Option<P>,
+    time_source: Arc<dyn TimeSource>,
 }
 
-impl<P: Eq> LinkConditioner<P> {
+impl<P: Eq + Clone> LinkConditioner<P> {
     pub fn new(config: LinkConditionerConfig) -> Self {
         LinkConditioner {
             config,
             time_queue: ReadyBuffer::new(),
             last_packet: None,
+            swapped_packet: None,
+            time_source: Arc::new(RealTimeSource),
         }
     }
 
-    /// Add latency/jitter/loss to a packet
+    /// Use a custom [`TimeSource`] to compute when packets are ready to be received
+    pub fn with_time_source(mut self, time_source: Arc<dyn TimeSource>) -> Self {
+        self.time_source = time_source;
+        self
+    }
+
+    /// Add latency/jitter/loss/duplication to a packet
     fn condition_packet(&mut self, packet: P) {
+        let config = self.config.clone();
+        self.condition_packet_with(&config, packet);
+    }
+
+    /// Add latency/jitter/loss/duplication to a packet, using a different config than the conditioner's config
+    fn condition_packet_with(&mut self, config: &LinkConditionerConfig, packet: P) {
         let mut rng = thread_rng();
-        if rng.gen_range(0.0..1.0) <= self.config.incoming_loss {
+        if config.incoming_loss > 0.0 && rng.gen_range(0.0..1.0) <= config.incoming_loss {
             return;
         }
-        let mut latency: i32 = self.config.incoming_latency.as_millis() as i32;
+        if config.duplicate_chance > 0.0 && rng.gen_range(0.0..1.0) <= config.duplicate_chance {
+            self.delay_packet(config, packet.clone());
+        }
+        self.delay_packet(config, packet);
+    }
+
+    /// Add latency/jitter to a packet
+    fn delay_packet(&mut self, config: &LinkConditionerConfig, packet: P) {
+        let mut rng = thread_rng();
+        let mut latency: i32 = config.incoming_latency.as_millis() as i32;
         // TODO: how can i use the virtual time here?
-        let mut packet_timestamp = Instant::now();
-        if self.config.incoming_jitter > Duration::default() {
-            let jitter: i32 = self.config.incoming_jitter.as_millis() as i32;
+        let mut packet_timestamp = self.time_source.now();
+        if config.incoming_jitter > Duration::default() {
+            let jitter: i32 = config.incoming_jitter.as_millis() as i32;
             latency += rng.gen_range(-jitter..jitter);
         }
         if latency > 0 {
@@ -72,9 +120,20 @@ impl<P: Eq> LinkConditioner<P> {
 
     /// Check if a packet is ready to be returned
     fn pop_packet(&mut self) -> Option<P> {
-        self.time_queue
-            .pop_item(&Instant::now())
-            .map(|(_, packet)| packet)
+        if let Some(packet) = self.swapped_packet.take() {
+            return Some(packet);
+        }
+        let now = self.time_source.now();
+        let (_, packet) = self.time_queue.pop_item(&now)?;
+        if self.config.reorder_chance > 0.0
+            && thread_rng().gen_range(0.0..1.0) <= self.config.reorder_chance
+        {
+            if let Some((_, next)) = self.time_queue.pop_item(&now) {
+                self.swapped_packet = Some(packet);
+                return Some(next);
+            }
+        }
+        Some(packet)
     }
 }
 
@@ -122,6 +181,150 @@ impl<T: PacketReceiver> PacketReceiver for ConditionedPacketReceiver<T, (SocketA
     }
 }
 
+/// A wrapper around a packet sender that simulates network conditions
+/// by adding latency, jitter and packet loss to outgoing packets.
+pub struct ConditionedPacketSender<T: PacketSender> {
+    packet_sender: T,
+    conditioner: PacketLinkConditioner,
+}
+
+impl<T: PacketSender> ConditionedPacketSender<T> {
+    pub(crate) fn new(packet_sender: T, conditioner: PacketLinkConditioner) -> Self {
+        Self {
+            packet_sender,
+            conditioner,
+        }
+    }
+
+    /// Send the delayed packets that are ready to be sent
+    fn send_ready_packets(&mut self) -> Result<()> {
+        while let Some((addr, data)) = self.conditioner.pop_packet() {
+            self.packet_sender.send(&data, &addr)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: PacketSender> PacketSender for ConditionedPacketSender<T> {
+    fn send(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
+        self.conditioner
+            .condition_packet((*address, payload.to_vec().into_boxed_slice()));
+        self.send_ready_packets()
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.send_ready_packets()?;
+        self.packet_sender.flush()
+    }
+}
+
+/// [`LinkConditionerConfig`]s that apply to the packets received from specific remote addresses.
+///
+/// This is a shared handle, so that the configs can be modified at runtime while the receiver is in use.
+#[derive(Clone, Default)]
+pub(crate) struct AddrConditionerConfigs(Arc<RwLock<HashMap<SocketAddr, LinkConditionerConfig>>>);
+
+impl AddrConditionerConfigs {
+    /// Set the config used for packets received from `addr`.
+    ///
+    /// If `config` is None, the default config of the receiver is used again.
+    pub(crate) fn set(&self, addr: SocketAddr, config: Option<LinkConditionerConfig>) {
+        let mut configs = self.0.write();
+        match config {
+            Some(config) => configs.insert(addr, config),
+            None => configs.remove(&addr),
+        };
+    }
+
+    pub(crate) fn get(&self, addr: &SocketAddr) -> Option<LinkConditionerConfig> {
+        self.0.read().get(addr).cloned()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.read().is_empty()
+    }
+}
+
+/// A wrapper around a packet receiver that receives packets from multiple remote addresses (i.e. on the server),
+/// and simulates different network conditions depending on the address of the remote.
+///
+/// The packets from remotes that have no specific config are conditioned using the default config, if any.
+pub(crate) struct AddrConditionedPacketReceiver<T: PacketReceiver> {
+    packet_receiver: T,
+    conditioner: PacketLinkConditioner,
+    default_config: Option<LinkConditionerConfig>,
+    configs: AddrConditionerConfigs,
+}
+
+impl<T: PacketReceiver> AddrConditionedPacketReceiver<T> {
+    pub(crate) fn new(
+        packet_receiver: T,
+        default_config: Option<LinkConditionerConfig>,
+        configs: AddrConditionerConfigs,
+    ) -> Self {
+        Self {
+            packet_receiver,
+            conditioner: LinkConditioner::new(default_config.clone().unwrap_or(
+                LinkConditionerConfig::new(Duration::default(), Duration::default(), 0.0),
+            )),
+            default_config,
+            configs,
+        }
+    }
+
+    /// Use a custom [`TimeSource`] to compute when packets are ready to be received
+    pub(crate) fn with_time_source(mut self, time_source: Arc<dyn TimeSource>) -> Self {
+        self.conditioner = self.conditioner.with_time_source(time_source);
+        self
+    }
+}
+
+impl<T: PacketReceiver> PacketReceiver for AddrConditionedPacketReceiver<T> {
+    fn recv(&mut self) -> Result<Option<(&mut [u8], SocketAddr)>> {
+        // no conditioning at all: avoid copying the packets
+        if self.default_config.is_none()
+            && self.conditioner.time_queue.is_empty()
+            && self.configs.is_empty()
+        {
+            return self.packet_receiver.recv();
+        }
+        loop {
+            // keep trying to receive packets from the inner packet receiver
+            let option = self.packet_receiver.recv()?;
+            match option {
+                None => break,
+                Some((data, addr)) => {
+                    let packet = (addr, data.to_vec().into_boxed_slice());
+                    match self
+                        .configs
+                        .get(&addr)
+                        .or_else(|| self.default_config.clone())
+                    {
+                        Some(config) => self.conditioner.condition_packet_with(&config, packet),
+                        // no conditioning for this remote, the packet will be ready immediately
+                        None => self
+                            .conditioner
+                            .time_queue
+                            .push(self.conditioner.time_source.now(), packet),
+                    }
+                }
+            }
+        }
+        // only return a packet if it is ready to be returned
+        match self.conditioner.pop_packet() {
+            Some((addr, data)) => {
+                // we use `last_packet` to get ownership of the data
+                self.conditioner.last_packet = Some((addr, data));
+                Ok(Some((
+                    self.conditioner.last_packet.as_mut().unwrap().1.as_mut(),
+                    addr,
+                )))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
 impl LinkConditionerConfig {
     /// Creates a new LinkConditionerConfig
     pub fn new(incoming_latency: Duration, incoming_jitter: Duration, incoming_loss: f32) -> Self {
@@ -129,9 +332,23 @@ impl LinkConditionerConfig {
             incoming_latency,
             incoming_jitter,
             incoming_loss,
+            duplicate_chance: 0.0,
+            reorder_chance: 0.0,
         }
     }
 
+    /// Set the % chance that an incoming packet will be received twice
+    pub fn with_duplicate_chance(mut self, duplicate_chance: f32) -> Self {
+        self.duplicate_chance = duplicate_chance;
+        self
+    }
+
+    /// Set the % chance that a ready packet is swapped with the next ready packet
+    pub fn with_reorder_chance(mut self, reorder_chance: f32) -> Self {
+        self.reorder_chance = reorder_chance;
+        self
+    }
+
     /// Creates a new LinkConditioner that simulates a connection which is in a
     /// good condition
     pub fn good_condition() -> Self {
@@ -139,6 +356,8 @@ impl LinkConditionerConfig {
             incoming_latency: Duration::from_millis(40),
             incoming_jitter: Duration::from_millis(6),
             incoming_loss: 0.002,
+            duplicate_chance: 0.0,
+            reorder_chance: 0.0,
         }
     }
 
@@ -149,6 +368,8 @@ impl LinkConditionerConfig {
             incoming_latency: Duration::from_millis(170),
             incoming_jitter: Duration::from_millis(45),
             incoming_loss: 0.02,
+            duplicate_chance: 0.0,
+            reorder_chance: 0.0,
         }
     }
 
@@ -159,6 +380,211 @@ impl LinkConditionerConfig {
             incoming_latency: Duration::from_millis(300),
             incoming_jitter: Duration::from_millis(84),
             incoming_loss: 0.04,
+            duplicate_chance: 0.0,
+            reorder_chance: 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shared::time_source::MockTimeSource;
+
+    use super::*;
+
+    #[test]
+    fn test_conditioner_with_mock_time_source() {
+        let time_source = MockTimeSource::new();
+        let mut conditioner = LinkConditioner::new(LinkConditionerConfig {
+            incoming_latency: Duration::from_millis(100),
+            incoming_jitter: Duration::default(),
+            incoming_loss: 0.0,
+            duplicate_chance: 0.0,
+            reorder_chance: 0.0,
+        })
+        .with_time_source(Arc::new(time_source.clone()));
+
+        conditioner.condition_packet(1);
+        assert_eq!(conditioner.pop_packet(), None);
+
+        time_source.advance(Duration::from_millis(50));
+        assert_eq!(conditioner.pop_packet(), None);
+
+        time_source.advance(Duration::from_millis(50));
+        assert_eq!(conditioner.pop_packet(), Some(1));
+    }
+
+    /// Receiver that returns the packets pushed to a shared queue
+    #[derive(Default, Clone)]
+    struct QueueReceiver {
+        queue: Arc<RwLock<Vec<(SocketAddr, Vec<u8>)>>>,
+        buffer: Vec<u8>,
+    }
+
+    impl PacketReceiver for QueueReceiver {
+        fn recv(&mut self) -> Result<Option<(&mut [u8], SocketAddr)>> {
+            let mut queue = self.queue.write();
+            if queue.is_empty() {
+                return Ok(None);
+            }
+            let (addr, data) = queue.remove(0);
+            self.buffer = data;
+            Ok(Some((self.buffer.as_mut_slice(), addr)))
+        }
+    }
+
+    /// Sender that pushes the packets to a shared queue
+    #[derive(Default, Clone)]
+    struct QueueSender {
+        queue: Arc<RwLock<Vec<(SocketAddr, Vec<u8>)>>>,
+    }
+
+    impl PacketSender for QueueSender {
+        fn send(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
+            self.queue.write().push((*address, payload.to_vec()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_duplicate_packets() {
+        let time_source = MockTimeSource::new();
+        let addr: SocketAddr = "127.0.0.1:1001".parse().unwrap();
+        let inner = QueueReceiver::default();
+        let conditioner = LinkConditioner::new(
+            LinkConditionerConfig::new(Duration::from_millis(100), Duration::default(), 0.0)
+                .with_duplicate_chance(1.0),
+        )
+        .with_time_source(Arc::new(time_source.clone()));
+        let mut receiver = conditioner.wrap(inner.clone());
+
+        inner.queue.write().push((addr, vec![1]));
+        assert!(receiver.recv().unwrap().is_none());
+
+        // the duplicate is also delayed
+        time_source.advance(Duration::from_millis(100));
+        assert_eq!(receiver.recv().unwrap().unwrap().0, &[1]);
+        assert_eq!(receiver.recv().unwrap().unwrap().0, &[1]);
+        assert!(receiver.recv().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_reorder_packets() {
+        let time_source = MockTimeSource::new();
+        let mut conditioner = LinkConditioner::new(
+            LinkConditionerConfig::new(Duration::default(), Duration::default(), 0.0)
+                .with_reorder_chance(1.0),
+        )
+        .with_time_source(Arc::new(time_source.clone()));
+        for packet in 1..=5 {
+            conditioner.condition_packet(packet);
+            time_source.advance(Duration::from_millis(1));
         }
+        // adjacent packets are swapped; the last packet has no packet to be swapped with
+        let packets: Vec<_> = std::iter::from_fn(|| conditioner.pop_packet()).collect();
+        assert_eq!(packets, vec![2, 1, 4, 3, 5]);
+    }
+
+    #[test]
+    fn test_conditioned_sender() {
+        let time_source = MockTimeSource::new();
+        let addr: SocketAddr = "127.0.0.1:1001".parse().unwrap();
+        let inner = QueueSender::default();
+        let conditioner = LinkConditioner::new(LinkConditionerConfig::new(
+            Duration::from_millis(100),
+            Duration::default(),
+            0.0,
+        ))
+        .with_time_source(Arc::new(time_source.clone()));
+        let mut sender = ConditionedPacketSender::new(inner.clone(), conditioner);
+
+        sender.send(&[1], &addr).unwrap();
+        assert!(inner.queue.read().is_empty());
+
+        // the delayed packet is sent by a later send
+        time_source.advance(Duration::from_millis(50));
+        sender.send(&[2], &addr).unwrap();
+        time_source.advance(Duration::from_millis(50));
+        sender.send(&[3], &addr).unwrap();
+        assert_eq!(*inner.queue.read(), vec![(addr, vec![1])]);
+
+        // or by a flush
+        time_source.advance(Duration::from_millis(50));
+        sender.flush().unwrap();
+        assert_eq!(*inner.queue.read(), vec![(addr, vec![1]), (addr, vec![2])]);
+    }
+
+    #[test]
+    fn test_addr_conditioner() {
+        let time_source = MockTimeSource::new();
+        let addr_1: SocketAddr = "127.0.0.1:1001".parse().unwrap();
+        let addr_2: SocketAddr = "127.0.0.1:1002".parse().unwrap();
+        let inner = QueueReceiver::default();
+        let configs = AddrConditionerConfigs::default();
+        let mut receiver = AddrConditionedPacketReceiver::new(inner.clone(), None, configs.clone())
+            .with_time_source(Arc::new(time_source.clone()));
+
+        // only the packets from addr_1 are delayed
+        configs.set(
+            addr_1,
+            Some(LinkConditionerConfig::new(
+                Duration::from_millis(100),
+                Duration::default(),
+                0.0,
+            )),
+        );
+        inner.queue.write().push((addr_1, vec![1]));
+        inner.queue.write().push((addr_2, vec![2]));
+        let (data, addr) = receiver.recv().unwrap().unwrap();
+        assert_eq!((data.to_vec(), addr), (vec![2], addr_2));
+        assert!(receiver.recv().unwrap().is_none());
+
+        time_source.advance(Duration::from_millis(100));
+        let (data, addr) = receiver.recv().unwrap().unwrap();
+        assert_eq!((data.to_vec(), addr), (vec![1], addr_1));
+
+        // removing the override stops conditioning the packets from addr_1
+        configs.set(addr_1, None);
+        inner.queue.write().push((addr_1, vec![3]));
+        let (data, addr) = receiver.recv().unwrap().unwrap();
+        assert_eq!((data.to_vec(), addr), (vec![3], addr_1));
+    }
+
+    /// The downlink loses all the packets, but not the uplink
+    #[test]
+    fn test_per_direction_conditioner() {
+        use crate::client::io::config::ClientTransport;
+        use crate::transport::config::SharedIoConfig;
+        use crate::transport::LOCAL_SOCKET;
+
+        let (a_send, b_recv) = crossbeam_channel::unbounded();
+        let (b_send, a_recv) = crossbeam_channel::unbounded();
+        let mut a = SharedIoConfig::from_transport(ClientTransport::LocalChannel {
+            send: a_send,
+            recv: a_recv,
+        })
+        .with_incoming_conditioner(LinkConditionerConfig::new(
+            Duration::default(),
+            Duration::default(),
+            1.0,
+        ))
+        .with_outgoing_conditioner(LinkConditionerConfig::new(
+            Duration::default(),
+            Duration::default(),
+            0.0,
+        ))
+        .connect()
+        .unwrap();
+        let mut b = SharedIoConfig::from_transport(ClientTransport::LocalChannel {
+            send: b_send,
+            recv: b_recv,
+        })
+        .connect()
+        .unwrap();
+
+        a.send(&[1], &LOCAL_SOCKET).unwrap();
+        assert_eq!(b.recv().unwrap().unwrap().0, &[1]);
+        b.send(&[2], &LOCAL_SOCKET).unwrap();
+        assert!(a.recv().unwrap().is_none());
     }
 }
@@ -1,7 +1,10 @@
 //! Module defining 'wrappers' that modify the behaviour of an existing [`PacketReceiver`] or [`PacketSender`].
 //!
 //! Wrappers are used to add additional functionality to an existing transport, such as encryption, compression, metrics, etc.
-use crate::transport::{PacketReceiver, PacketSender};
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::transport::{BoxedReceiver, BoxedSender, PacketReceiver, PacketSender};
 
 /// A conditioner is used to simulate network conditions such as latency, jitter and packet loss.
 pub(crate) mod conditioner;
@@ -9,6 +12,16 @@ pub(crate) mod conditioner;
 /// Middleware that compresses packets before sending them.
 pub(crate) mod compression;
 
+/// Middleware that appends a checksum to the packets to detect corrupt packets.
+pub(crate) mod checksum;
+
+/// Middleware that limits the number of bytes sent per second.
+pub(crate) mod bandwidth;
+
+/// Maximum number of bytes that the built-in middleware (compression and checksum) add to each packet
+pub(crate) const MIDDLEWARE_OVERHEAD: usize =
+    compression::packet::FLAG_SIZE + checksum::CHECKSUM_SIZE;
+
 pub trait PacketReceiverWrapper<T: PacketReceiver> {
     fn wrap(self, receiver: T) -> impl PacketReceiver;
 }
@@ -16,3 +29,134 @@ pub trait PacketReceiverWrapper<T: PacketReceiver> {
 pub trait PacketSenderWrapper<T: PacketSender> {
     fn wrap(self, sender: T) -> impl PacketSender;
 }
+
+/// Custom middleware that can be added to the [`IoConfig`](crate::transport::config::SharedIoConfig)
+/// to transform the packets received by the transport (for example to decrypt them, or to record them).
+///
+/// The middleware is used as a factory: `wrap_receiver` is called every time the `Io` is built from the config.
+pub trait ReceiverMiddleware: Debug + Send + Sync + 'static {
+    fn wrap_receiver(&self, receiver: BoxedReceiver) -> BoxedReceiver;
+}
+
+/// Custom middleware that can be added to the [`IoConfig`](crate::transport::config::SharedIoConfig)
+/// to transform the packets sent by the transport (for example to encrypt them, or to record them).
+///
+/// The middleware is used as a factory: `wrap_sender` is called every time the `Io` is built from the config.
+pub trait SenderMiddleware: Debug + Send + Sync + 'static {
+    fn wrap_sender(&self, sender: BoxedSender) -> BoxedSender;
+}
+
+/// Wrap the receiver with each middleware in order: the first middleware of the list is the first one
+/// to process the packets received from the transport.
+pub(crate) fn apply_receiver_middleware(
+    receiver: BoxedReceiver,
+    middleware: &[Arc<dyn ReceiverMiddleware>],
+) -> BoxedReceiver {
+    middleware.iter().fold(receiver, |receiver, middleware| {
+        middleware.wrap_receiver(receiver)
+    })
+}
+
+/// Wrap the sender with each middleware in order: the last middleware of the list is the last one
+/// to process the packets before they are sent by the transport.
+pub(crate) fn apply_sender_middleware(
+    sender: BoxedSender,
+    middleware: &[Arc<dyn SenderMiddleware>],
+) -> BoxedSender {
+    middleware
+        .iter()
+        .rev()
+        .fold(sender, |sender, middleware| middleware.wrap_sender(sender))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use super::*;
+    use crate::client::io::config::ClientTransport;
+    use crate::transport::config::SharedIoConfig;
+    use crate::transport::error::Result;
+    use crate::transport::LOCAL_SOCKET;
+
+    /// Middleware that appends a byte to every packet
+    #[derive(Debug)]
+    struct Append(u8);
+
+    struct AppendSender {
+        inner: BoxedSender,
+        byte: u8,
+        buffer: Vec<u8>,
+    }
+
+    impl PacketSender for AppendSender {
+        fn send(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
+            self.buffer.clear();
+            self.buffer.extend_from_slice(payload);
+            self.buffer.push(self.byte);
+            self.inner.send(&self.buffer, address)
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            self.inner.flush()
+        }
+    }
+
+    impl SenderMiddleware for Append {
+        fn wrap_sender(&self, sender: BoxedSender) -> BoxedSender {
+            Box::new(AppendSender {
+                inner: sender,
+                byte: self.0,
+                buffer: vec![],
+            })
+        }
+    }
+
+    /// Middleware that checks that the last byte of every packet is the expected one, and strips it
+    #[derive(Debug)]
+    struct Strip(u8);
+
+    struct StripReceiver {
+        inner: BoxedReceiver,
+        byte: u8,
+    }
+
+    impl PacketReceiver for StripReceiver {
+        fn recv(&mut self) -> Result<Option<(&mut [u8], SocketAddr)>> {
+            let byte = self.byte;
+            Ok(self.inner.recv()?.map(|(data, addr)| {
+                let (last, rest) = data.split_last_mut().unwrap();
+                assert_eq!(*last, byte);
+                (rest, addr)
+            }))
+        }
+    }
+
+    impl ReceiverMiddleware for Strip {
+        fn wrap_receiver(&self, receiver: BoxedReceiver) -> BoxedReceiver {
+            Box::new(StripReceiver {
+                inner: receiver,
+                byte: self.0,
+            })
+        }
+    }
+
+    #[test]
+    fn test_middleware_order() {
+        let (send, recv) = crossbeam_channel::unbounded();
+        let io_config =
+            SharedIoConfig::from_transport(ClientTransport::LocalChannel { send, recv })
+                // the packet goes through 1 then 2 before being sent
+                .with_sender_middleware(Append(1))
+                .with_sender_middleware(Append(2))
+                // the received packet goes through 2 then 1
+                .with_receiver_middleware(Strip(2))
+                .with_receiver_middleware(Strip(1));
+        let mut io = io_config.connect().unwrap();
+        let msg = b"hello world".as_slice();
+        io.sender.send(msg, &LOCAL_SOCKET).unwrap();
+
+        let (data, _) = io.receiver.recv().unwrap().unwrap();
+        assert_eq!(data, msg);
+    }
+}
@@ -11,6 +11,7 @@ use error::Result;
 use crate::client::io::transport::ClientTransportEnum;
 use crate::server::io::transport::ServerTransportEnum;
 use crate::transport::channels::Channels;
+use crate::transport::custom::CustomTransportSocket;
 use crate::transport::dummy::DummyIo;
 use crate::transport::local::LocalChannel;
 use crate::transport::udp::UdpSocket;
@@ -44,11 +45,13 @@ pub(crate) mod channels;
 #[cfg(feature = "webtransport")]
 pub(crate) mod webtransport;
 
-pub(crate) mod middleware;
+pub mod middleware;
 
 pub mod config;
+/// The transport is provided by the user
+pub(crate) mod custom;
 pub(crate) mod dummy;
-pub(crate) mod error;
+pub mod error;
 #[cfg_attr(docsrs, doc(cfg(feature = "websocket")))]
 #[cfg(feature = "websocket")]
 pub(crate) mod websocket;
@@ -66,8 +69,8 @@ pub(crate) const MTU: usize = 1472;
 /// the discovery will start from.
 pub(crate) const MIN_MTU: usize = 1300;
 
-pub(crate) type BoxedSender = Box<dyn PacketSender + Send + Sync>;
-pub(crate) type BoxedReceiver = Box<dyn PacketReceiver + Send + Sync>;
+pub type BoxedSender = Box<dyn PacketSender + Send + Sync>;
+pub type BoxedReceiver = Box<dyn PacketReceiver + Send + Sync>;
 
 #[enum_dispatch]
 pub(crate) trait Transport {
@@ -81,18 +84,35 @@ pub(crate) trait Transport {
 }
 
 /// Send data to a remote address
+///
+/// The remote peers are always identified by a [`SocketAddr`], because the netcode connection
+/// also uses it to identify the clients. Transports whose peers are not IP addresses (for example a relay)
+/// must map their peers to synthetic addresses with a [`PeerAddrMap`](crate::prelude::PeerAddrMap).
 pub trait PacketSender: Send + Sync {
     /// Send data on the socket to the remote address
     fn send(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()>;
+
+    /// Send the packets that were buffered by the sender, if any.
+    ///
+    /// This is called once per frame, after all the packets of the frame have been sent.
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
 }
 
 impl PacketSender for BoxedSender {
     fn send(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
         (**self).send(payload, address)
     }
+
+    fn flush(&mut self) -> Result<()> {
+        (**self).flush()
+    }
 }
 
 /// Receive data from a remote address
+///
+/// See [`PacketSender`] for how the remote peers are identified.
 pub trait PacketReceiver: Send + Sync {
     /// Receive a packet from the socket. Returns the data read and the origin.
     ///
@@ -1,31 +1,98 @@
 //! The transport is a UDP socket
+//!
+//! After a suspend/resume or a network change, the OS can invalidate the socket: every receive then
+//! fails with an error. In that case the socket is bound again to the same local address (see
+//! [`SocketConfig::rebind_attempts`]), and a [`TransportRebindEvent`] is emitted if it succeeds.
+//! If all the attempts fail, the io is disconnected.
+//!
+//! The socket can be bound to an IPv4 or an IPv6 address. With [`SocketConfig::udp_dual_stack`], a socket bound
+//! to an IPv6 address also accepts IPv4 traffic.
 use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 
+use bevy::prelude::Event;
+use bevy::utils::{Duration, Instant};
+use tracing::{error, info, warn};
+
 use crate::client::io::transport::{ClientTransportBuilder, ClientTransportEnum};
-use crate::client::io::{ClientIoEventReceiver, ClientNetworkEventSender};
+use crate::client::io::{ClientIoEvent, ClientIoEventReceiver, ClientNetworkEventSender};
 use crate::server::io::transport::{ServerTransportBuilder, ServerTransportEnum};
-use crate::server::io::{ServerIoEventReceiver, ServerNetworkEventSender};
+use crate::server::io::{ServerIoEvent, ServerIoEventReceiver, ServerNetworkEventSender};
+use crate::transport::config::SocketConfig;
 use crate::transport::io::IoState;
-use crate::transport::{BoxedReceiver, BoxedSender, PacketReceiver, PacketSender, Transport, MTU};
+use crate::transport::{BoxedReceiver, BoxedSender, PacketReceiver, PacketSender, Transport};
 
 use super::error::Result;
 
 pub struct UdpSocketBuilder {
     pub(crate) local_addr: SocketAddr,
+    pub(crate) socket_config: SocketConfig,
+}
+
+/// Bevy [`Event`] emitted on the client or the server when the UDP socket became invalid
+/// (for example after the computer woke up from sleep) and was bound again successfully
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransportRebindEvent {
+    /// Local address of the new socket
+    pub local_addr: SocketAddr,
 }
 
 impl UdpSocketBuilder {
-    fn build(self) -> Result<UdpSocket> {
-        let udp_socket = std::net::UdpSocket::bind(self.local_addr)?;
+    fn bind(local_addr: SocketAddr, dual_stack: bool) -> std::io::Result<std::net::UdpSocket> {
+        let udp_socket = if dual_stack && local_addr.is_ipv6() {
+            Self::bind_dual_stack(local_addr)?
+        } else {
+            std::net::UdpSocket::bind(local_addr)?
+        };
+        udp_socket.set_nonblocking(true)?;
+        Ok(udp_socket)
+    }
+
+    /// Bind an IPv6 socket that also accepts IPv4 traffic
+    #[cfg(not(target_family = "wasm"))]
+    fn bind_dual_stack(local_addr: SocketAddr) -> std::io::Result<std::net::UdpSocket> {
+        use socket2::{Domain, Protocol, Socket, Type};
+
+        let socket = Socket::new(Domain::IPV6, Type::DGRAM, Some(Protocol::UDP))?;
+        socket.set_only_v6(false)?;
+        socket.bind(&local_addr.into())?;
+        Ok(socket.into())
+    }
+
+    #[cfg(target_family = "wasm")]
+    fn bind_dual_stack(local_addr: SocketAddr) -> std::io::Result<std::net::UdpSocket> {
+        std::net::UdpSocket::bind(local_addr)
+    }
+
+    fn build(self, notifier: RebindNotifier) -> Result<UdpSocket> {
+        let dual_stack = self.socket_config.udp_dual_stack;
+        let udp_socket = Self::bind(self.local_addr, dual_stack)?;
         let local_addr = udp_socket.local_addr()?;
-        let socket = Arc::new(Mutex::new(udp_socket));
-        socket.as_ref().lock().unwrap().set_nonblocking(true)?;
-        let sender = UdpSocketBuffer {
+        let gso = self.socket_config.udp_gso && gso::is_supported(&udp_socket);
+        let socket = Arc::new(Mutex::new(Some(udp_socket)));
+        let receiver = UdpSocketBuffer {
             socket: socket.clone(),
-            buffer: [0; MTU],
+            // one extra byte to detect the datagrams that don't fit in the buffer
+            buffer: vec![0; self.socket_config.udp_recv_buffer_size + 1],
+            gso_batch: None,
+            rebind: Some(Rebind {
+                // bind to the same port, even if the OS assigned it
+                local_addr,
+                dual_stack,
+                max_attempts: self.socket_config.rebind_attempts,
+                backoff: self.socket_config.rebind_backoff,
+                attempts: 0,
+                next_attempt: None,
+                failed: false,
+                notifier,
+            }),
+        };
+        let sender = UdpSocketBuffer {
+            socket,
+            buffer: vec![],
+            gso_batch: gso.then(gso::GsoBatch::default),
+            rebind: None,
         };
-        let receiver = sender.clone();
         Ok(UdpSocket {
             local_addr,
             sender,
@@ -44,10 +111,11 @@ impl ClientTransportBuilder for UdpSocketBuilder {
         Option<ClientIoEventReceiver>,
         Option<ClientNetworkEventSender>,
     )> {
+        let (event_tx, event_rx) = async_channel::unbounded();
         Ok((
-            ClientTransportEnum::UdpSocket(self.build()?),
+            ClientTransportEnum::UdpSocket(self.build(RebindNotifier::Client(event_tx))?),
             IoState::Connected,
-            None,
+            Some(ClientIoEventReceiver(event_rx)),
             None,
         ))
     }
@@ -62,10 +130,11 @@ impl ServerTransportBuilder for UdpSocketBuilder {
         Option<ServerIoEventReceiver>,
         Option<ServerNetworkEventSender>,
     )> {
+        let (event_tx, event_rx) = async_channel::unbounded();
         Ok((
-            ServerTransportEnum::UdpSocket(self.build()?),
+            ServerTransportEnum::UdpSocket(self.build(RebindNotifier::Server(event_tx))?),
             IoState::Connected,
-            None,
+            Some(ServerIoEventReceiver(event_rx)),
             None,
         ))
     }
@@ -91,39 +160,431 @@ impl Transport for UdpSocket {
 #[derive(Clone)]
 pub struct UdpSocketBuffer {
     /// The underlying UDP Socket. This is wrapped in an Arc<Mutex<>> so that it
-    /// can be shared between threads
-    socket: Arc<Mutex<std::net::UdpSocket>>,
-    buffer: [u8; MTU],
+    /// can be shared between threads.
+    ///
+    /// It is None while the socket is being bound again after a fatal error.
+    socket: Arc<Mutex<Option<std::net::UdpSocket>>>,
+    /// Buffer in which the datagrams are received, only used by the receiver
+    buffer: Vec<u8>,
+    /// Packets waiting to be sent with a single GSO `sendmsg` call, if GSO is enabled
+    gso_batch: Option<gso::GsoBatch>,
+    /// State of the rebind attempts, only used by the receiver
+    rebind: Option<Rebind>,
+}
+
+/// Used to notify the main thread of the result of the rebind attempts
+#[derive(Clone)]
+enum RebindNotifier {
+    Client(async_channel::Sender<ClientIoEvent>),
+    Server(async_channel::Sender<ServerIoEvent>),
+}
+
+impl RebindNotifier {
+    fn rebound(&self, local_addr: SocketAddr) {
+        let _ = match self {
+            RebindNotifier::Client(sender) => sender
+                .try_send(ClientIoEvent::Rebound(local_addr))
+                .map_err(|_| ()),
+            RebindNotifier::Server(sender) => sender
+                .try_send(ServerIoEvent::Rebound(local_addr))
+                .map_err(|_| ()),
+        };
+    }
+
+    fn failed(&self, e: std::io::Error) {
+        let _ = match self {
+            RebindNotifier::Client(sender) => sender
+                .try_send(ClientIoEvent::Disconnected(e.into()))
+                .map_err(|_| ()),
+            RebindNotifier::Server(sender) => sender
+                .try_send(ServerIoEvent::ServerDisconnected(e.into()))
+                .map_err(|_| ()),
+        };
+    }
+}
+
+#[derive(Clone)]
+struct Rebind {
+    local_addr: SocketAddr,
+    dual_stack: bool,
+    max_attempts: u32,
+    backoff: Duration,
+    /// Number of failed attempts since the socket became invalid
+    attempts: u32,
+    /// Time of the next attempt, if the socket is invalid
+    next_attempt: Option<Instant>,
+    /// True if we gave up and notified the io that the socket is invalid
+    failed: bool,
+    notifier: RebindNotifier,
+}
+
+/// Returns true if the error means that the datagram was bigger than the buffer
+/// (Windows returns `WSAEMSGSIZE` instead of truncating the datagram silently)
+fn is_truncation_error(e: &std::io::Error) -> bool {
+    cfg!(windows) && e.raw_os_error() == Some(10040)
+}
+
+/// Errors that don't mean that the socket is invalid
+fn is_transient_error(e: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+    matches!(
+        e.kind(),
+        ErrorKind::WouldBlock
+            | ErrorKind::Interrupted
+            | ErrorKind::TimedOut
+            // returned on some platforms when a previous packet could not be delivered (ICMP port unreachable)
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionRefused
+    )
+}
+
+impl UdpSocketBuffer {
+    /// Try to bind the socket again after a fatal error.
+    ///
+    /// Returns the error if all the attempts failed.
+    fn handle_fatal_error(&mut self, e: std::io::Error) -> Result<()> {
+        let Some(rebind) = self.rebind.as_mut() else {
+            return Err(e.into());
+        };
+        if rebind.attempts >= rebind.max_attempts {
+            // the last rebind succeeded but the socket is still invalid
+            if rebind.max_attempts > 0 && !rebind.failed {
+                error!(?e, "UDP socket is still invalid after binding it again");
+                rebind.failed = true;
+                rebind
+                    .notifier
+                    .failed(std::io::Error::new(e.kind(), e.to_string()));
+            }
+            return Err(e.into());
+        }
+        let now = Instant::now();
+        match rebind.next_attempt {
+            None => {
+                warn!(?e, "UDP socket error, trying to bind the socket again");
+            }
+            Some(next_attempt) if now < next_attempt => return Ok(()),
+            Some(_) => {}
+        }
+        // the attempts are only reset when a packet is received, in case the new socket is also invalid
+        rebind.attempts += 1;
+        // exponential backoff
+        let backoff = rebind.backoff * 2u32.saturating_pow(rebind.attempts - 1);
+        rebind.next_attempt = Some(now + backoff);
+        let mut socket = self.socket.lock().unwrap();
+        // the previous socket still owns the port, so we need to close it before binding again
+        socket.take();
+        match UdpSocketBuilder::bind(rebind.local_addr, rebind.dual_stack) {
+            Ok(new_socket) => {
+                info!(local_addr = ?rebind.local_addr, "UDP socket bound again");
+                *socket = Some(new_socket);
+                rebind.notifier.rebound(rebind.local_addr);
+                Ok(())
+            }
+            Err(bind_error) if rebind.attempts >= rebind.max_attempts => {
+                error!(?bind_error, "could not bind the UDP socket again");
+                rebind.failed = true;
+                rebind.notifier.failed(bind_error);
+                Err(e.into())
+            }
+            Err(bind_error) => {
+                warn!(
+                    ?bind_error,
+                    ?backoff,
+                    "could not bind the UDP socket again, retrying"
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+fn socket_rebinding_error() -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::NotConnected,
+        "the UDP socket is being bound again",
+    )
 }
 
 impl PacketSender for UdpSocketBuffer {
     fn send(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
-        self.socket
-            .as_ref()
-            .lock()
-            .unwrap()
-            .send_to(payload, address)?;
+        let socket = self.socket.as_ref().lock().unwrap();
+        let socket = socket.as_ref().ok_or_else(socket_rebinding_error)?;
+        if let Some(batch) = self.gso_batch.as_mut() {
+            if !batch.can_append(payload, address) {
+                batch.send(socket)?;
+            }
+            batch.append(payload, address);
+            return Ok(());
+        }
+        socket.send_to(payload, address)?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if let Some(batch) = self.gso_batch.as_mut() {
+            let socket = self.socket.as_ref().lock().unwrap();
+            batch.send(socket.as_ref().ok_or_else(socket_rebinding_error)?)?;
+        }
         Ok(())
     }
 }
 
 impl PacketReceiver for UdpSocketBuffer {
     /// Receives a packet from the socket, and stores the results in the provided buffer
+    ///
+    /// The datagrams that are bigger than [`SocketConfig::udp_recv_buffer_size`] are dropped.
     fn recv(&mut self) -> Result<Option<(&mut [u8], SocketAddr)>> {
-        match self
-            .socket
-            .as_ref()
-            .lock()
-            .unwrap()
-            .recv_from(&mut self.buffer)
-        {
-            Ok((recv_len, address)) => Ok(Some((&mut self.buffer[..recv_len], address))),
-            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                // Nothing to receive on the socket
-                Ok(None)
+        // the buffer has one extra byte: if it is filled, the datagram was too big
+        let max_size = self.buffer.len() - 1;
+        loop {
+            let result = match self.socket.as_ref().lock().unwrap().as_ref() {
+                Some(socket) => socket.recv_from(&mut self.buffer),
+                // the previous rebind attempt failed
+                None => Err(socket_rebinding_error()),
+            };
+            match result {
+                Ok((recv_len, address)) => {
+                    if let Some(rebind) = self.rebind.as_mut() {
+                        rebind.attempts = 0;
+                        rebind.next_attempt = None;
+                    }
+                    if recv_len > max_size {
+                        warn!(
+                            ?address,
+                            max_size, "dropping a datagram bigger than the receive buffer"
+                        );
+                        #[cfg(feature = "metrics")]
+                        metrics::counter!("transport.truncated_packets").increment(1);
+                        continue;
+                    }
+                    return Ok(Some((&mut self.buffer[..recv_len], address)));
+                }
+                Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    // Nothing to receive on the socket
+                    return Ok(None);
+                }
+                Err(ref e) if is_truncation_error(e) => {
+                    warn!(
+                        max_size,
+                        "dropping a datagram bigger than the receive buffer"
+                    );
+                    #[cfg(feature = "metrics")]
+                    metrics::counter!("transport.truncated_packets").increment(1);
+                }
+                Err(e) if is_transient_error(&e) => return Err(e.into()),
+                Err(e) => {
+                    self.handle_fatal_error(e)?;
+                    return Ok(None);
+                }
             }
-            // Err(e) => Err(anyhow!("error receiving packet")),
-            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Batch the packets sent to the same address so that they can be sent with
+/// [UDP generic segmentation offload](https://www.kernel.org/doc/html/latest/networking/segmentation-offloads.html).
+///
+/// The kernel splits the buffer into segments of `segment_size` bytes (the last segment can be smaller),
+/// so only packets of the same size can be batched together.
+mod gso {
+    use std::net::SocketAddr;
+
+    use tracing::warn;
+
+    /// Maximum number of segments that can be sent in a single GSO call
+    const MAX_SEGMENTS: usize = 64;
+    /// Maximum size of the payload of a UDP datagram
+    const MAX_BUFFER_SIZE: usize = 65507;
+
+    #[derive(Clone, Default)]
+    pub(super) struct GsoBatch {
+        address: Option<SocketAddr>,
+        segment_size: usize,
+        segments: usize,
+        buffer: Vec<u8>,
+        /// The GSO call failed once, so fall back to sending the packets individually
+        unsupported: bool,
+    }
+
+    impl GsoBatch {
+        pub(super) fn can_append(&self, payload: &[u8], address: &SocketAddr) -> bool {
+            // the batch is empty
+            let Some(batch_address) = self.address else {
+                return true;
+            };
+            batch_address == *address
+                // only the last segment can be smaller than the segment size
+                && self.buffer.len() == self.segments * self.segment_size
+                && payload.len() <= self.segment_size
+                && self.segments < MAX_SEGMENTS
+                && self.buffer.len() + payload.len() <= MAX_BUFFER_SIZE
+        }
+
+        pub(super) fn append(&mut self, payload: &[u8], address: &SocketAddr) {
+            if self.address.is_none() {
+                self.address = Some(*address);
+                self.segment_size = payload.len();
+            }
+            self.buffer.extend_from_slice(payload);
+            self.segments += 1;
+        }
+
+        /// Send the buffered packets and clear the batch
+        pub(super) fn send(&mut self, socket: &std::net::UdpSocket) -> std::io::Result<()> {
+            let Some(address) = self.address.take() else {
+                return Ok(());
+            };
+            let result = if self.segments == 1 || self.unsupported {
+                send_individually(socket, &self.buffer, self.segment_size, &address)
+            } else {
+                send_segments(socket, &self.buffer, self.segment_size, &address).or_else(|e| {
+                    if is_unsupported_error(&e) {
+                        warn!("UDP GSO is not supported by the network interface, falling back to individual packets: {e:?}");
+                        self.unsupported = true;
+                        send_individually(socket, &self.buffer, self.segment_size, &address)
+                    } else {
+                        Err(e)
+                    }
+                })
+            };
+            self.buffer.clear();
+            self.segments = 0;
+            result
+        }
+    }
+
+    fn send_individually(
+        socket: &std::net::UdpSocket,
+        buffer: &[u8],
+        segment_size: usize,
+        address: &SocketAddr,
+    ) -> std::io::Result<()> {
+        for segment in buffer.chunks(segment_size.max(1)) {
+            socket.send_to(segment, address)?;
+        }
+        Ok(())
+    }
+
+    /// EIO is returned if the network interface does not support checksum offload,
+    /// EINVAL if the segment size is not supported
+    #[cfg(target_os = "linux")]
+    fn is_unsupported_error(e: &std::io::Error) -> bool {
+        matches!(e.raw_os_error(), Some(libc::EIO) | Some(libc::EINVAL))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn is_unsupported_error(_: &std::io::Error) -> bool {
+        true
+    }
+
+    /// Returns true if the socket supports UDP GSO
+    #[cfg(target_os = "linux")]
+    pub(super) fn is_supported(socket: &std::net::UdpSocket) -> bool {
+        use std::os::fd::AsRawFd;
+
+        let mut value: libc::c_int = 0;
+        let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+        // SAFETY: the option value buffer is a valid c_int
+        let ret = unsafe {
+            libc::getsockopt(
+                socket.as_raw_fd(),
+                libc::SOL_UDP,
+                libc::UDP_SEGMENT,
+                &mut value as *mut libc::c_int as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        if ret != 0 {
+            warn!(
+                "UDP GSO is not supported by the kernel, falling back to individual packets: {:?}",
+                std::io::Error::last_os_error()
+            );
+        }
+        ret == 0
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(super) fn is_supported(_: &std::net::UdpSocket) -> bool {
+        warn!("UDP GSO is only supported on Linux, falling back to individual packets");
+        false
+    }
+
+    #[cfg(target_os = "linux")]
+    fn send_segments(
+        socket: &std::net::UdpSocket,
+        buffer: &[u8],
+        segment_size: usize,
+        address: &SocketAddr,
+    ) -> std::io::Result<()> {
+        use std::os::fd::AsRawFd;
+
+        // SAFETY: all the pointers in the msghdr point to buffers that outlive the sendmsg call,
+        // and the control buffer is large enough (and aligned) for a single u16 control message
+        unsafe {
+            let (mut name, name_len) = raw_address(address);
+            let mut iov = libc::iovec {
+                iov_base: buffer.as_ptr() as *mut libc::c_void,
+                iov_len: buffer.len(),
+            };
+            let mut control = [0u64; 4];
+            let mut msg: libc::msghdr = std::mem::zeroed();
+            msg.msg_name = &mut name as *mut libc::sockaddr_storage as *mut libc::c_void;
+            msg.msg_namelen = name_len;
+            msg.msg_iov = &mut iov;
+            msg.msg_iovlen = 1;
+            msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+            msg.msg_controllen = libc::CMSG_SPACE(std::mem::size_of::<u16>() as u32) as _;
+            let cmsg = libc::CMSG_FIRSTHDR(&msg);
+            (*cmsg).cmsg_level = libc::SOL_UDP;
+            (*cmsg).cmsg_type = libc::UDP_SEGMENT;
+            (*cmsg).cmsg_len = libc::CMSG_LEN(std::mem::size_of::<u16>() as u32) as _;
+            std::ptr::write_unaligned(libc::CMSG_DATA(cmsg) as *mut u16, segment_size as u16);
+            if libc::sendmsg(socket.as_raw_fd(), &msg, 0) < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn send_segments(
+        socket: &std::net::UdpSocket,
+        buffer: &[u8],
+        segment_size: usize,
+        address: &SocketAddr,
+    ) -> std::io::Result<()> {
+        send_individually(socket, buffer, segment_size, address)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn raw_address(address: &SocketAddr) -> (libc::sockaddr_storage, libc::socklen_t) {
+        // SAFETY: sockaddr_storage is large enough (and aligned) for both sockaddr_in and sockaddr_in6
+        unsafe {
+            let mut storage: libc::sockaddr_storage = std::mem::zeroed();
+            let len = match address {
+                SocketAddr::V4(address) => {
+                    let raw = &mut storage as *mut libc::sockaddr_storage as *mut libc::sockaddr_in;
+                    (*raw).sin_family = libc::AF_INET as libc::sa_family_t;
+                    (*raw).sin_port = address.port().to_be();
+                    (*raw).sin_addr = libc::in_addr {
+                        s_addr: u32::from_ne_bytes(address.ip().octets()),
+                    };
+                    std::mem::size_of::<libc::sockaddr_in>()
+                }
+                SocketAddr::V6(address) => {
+                    let raw =
+                        &mut storage as *mut libc::sockaddr_storage as *mut libc::sockaddr_in6;
+                    (*raw).sin6_family = libc::AF_INET6 as libc::sa_family_t;
+                    (*raw).sin6_port = address.port().to_be();
+                    (*raw).sin6_flowinfo = address.flowinfo();
+                    (*raw).sin6_addr = libc::in6_addr {
+                        s6_addr: address.ip().octets(),
+                    };
+                    (*raw).sin6_scope_id = address.scope_id();
+                    std::mem::size_of::<libc::sockaddr_in6>()
+                }
+            };
+            (storage, len as libc::socklen_t)
         }
     }
 }
@@ -131,13 +592,16 @@ impl PacketReceiver for UdpSocketBuffer {
 #[cfg(not(target_family = "wasm"))]
 #[cfg(test)]
 mod tests {
-    use std::net::SocketAddr;
+    use std::net::{Ipv4Addr, SocketAddr};
     use std::str::FromStr;
 
     use crate::client::io::transport::ClientTransportBuilder;
     use crate::server::io::transport::ServerTransportBuilder;
     use bevy::utils::Duration;
 
+    use crate::client::io::transport::ClientTransportEnum;
+    use crate::client::io::ClientIoEvent;
+    use crate::transport::config::SocketConfig;
     use crate::transport::middleware::conditioner::{LinkConditioner, LinkConditionerConfig};
     use crate::transport::middleware::PacketReceiverWrapper;
     use crate::transport::udp::UdpSocketBuilder;
@@ -147,15 +611,21 @@ mod tests {
     fn test_udp_socket() {
         // let the OS assign a port
         let local_addr = SocketAddr::from_str("127.0.0.1:0").unwrap();
-        let (client_socket, _, _, _) = UdpSocketBuilder { local_addr }
-            .connect()
-            .expect("could not connect to socket");
+        let (client_socket, _, _, _) = UdpSocketBuilder {
+            local_addr,
+            socket_config: SocketConfig::default(),
+        }
+        .connect()
+        .expect("could not connect to socket");
         let client_addr = client_socket.local_addr();
         let (mut client_sender, _) = client_socket.split();
 
-        let (server_socket, _, _, _) = UdpSocketBuilder { local_addr }
-            .start()
-            .expect("could not connect to socket");
+        let (server_socket, _, _, _) = UdpSocketBuilder {
+            local_addr,
+            socket_config: SocketConfig::default(),
+        }
+        .start()
+        .expect("could not connect to socket");
         let server_addr = server_socket.local_addr();
         let (_, mut server_receiver) = server_socket.split();
 
@@ -172,6 +642,145 @@ mod tests {
         assert_eq!(recv_msg, msg);
     }
 
+    #[test]
+    fn test_udp_socket_max_size_datagram() {
+        let local_addr = SocketAddr::from_str("127.0.0.1:0").unwrap();
+        let (client_socket, _, _, _) = UdpSocketBuilder {
+            local_addr,
+            socket_config: SocketConfig::default(),
+        }
+        .connect()
+        .expect("could not connect to socket");
+        let (mut client_sender, _) = client_socket.split();
+        let (server_socket, _, _, _) = UdpSocketBuilder {
+            local_addr,
+            socket_config: SocketConfig::default(),
+        }
+        .start()
+        .expect("could not connect to socket");
+        let server_addr = server_socket.local_addr();
+        let (_, mut server_receiver) = server_socket.split();
+
+        // maximum payload over IPv4
+        let msg: Vec<u8> = (0..65507).map(|i| i as u8).collect();
+        client_sender.send(&msg, &server_addr).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+
+        let Some((recv_msg, _)) = server_receiver.recv().unwrap() else {
+            panic!("expected to receive a packet");
+        };
+        assert_eq!(recv_msg, msg.as_slice());
+    }
+
+    #[test]
+    fn test_udp_socket_drop_truncated_datagram() {
+        let local_addr = SocketAddr::from_str("127.0.0.1:0").unwrap();
+        let (client_socket, _, _, _) = UdpSocketBuilder {
+            local_addr,
+            socket_config: SocketConfig::default(),
+        }
+        .connect()
+        .expect("could not connect to socket");
+        let (mut client_sender, _) = client_socket.split();
+        let (server_socket, _, _, _) = UdpSocketBuilder {
+            local_addr,
+            socket_config: SocketConfig::default().with_udp_recv_buffer_size(100),
+        }
+        .start()
+        .expect("could not connect to socket");
+        let server_addr = server_socket.local_addr();
+        let (_, mut server_receiver) = server_socket.split();
+
+        client_sender.send(&[1; 101], &server_addr).unwrap();
+        client_sender.send(&[2; 100], &server_addr).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+
+        // the datagram that doesn't fit in the buffer is dropped instead of being truncated
+        let Some((recv_msg, _)) = server_receiver.recv().unwrap() else {
+            panic!("expected to receive a packet");
+        };
+        assert_eq!(recv_msg, &[2; 100]);
+        assert!(server_receiver.recv().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_udp_socket_ipv6() {
+        let local_addr = SocketAddr::from_str("[::1]:0").unwrap();
+        let (client_socket, _, _, _) = UdpSocketBuilder {
+            local_addr,
+            socket_config: SocketConfig::default(),
+        }
+        .connect()
+        .expect("could not connect to socket");
+        let client_addr = client_socket.local_addr();
+        assert!(client_addr.is_ipv6());
+        let (mut client_sender, _) = client_socket.split();
+
+        let (server_socket, _, _, _) = UdpSocketBuilder {
+            local_addr,
+            socket_config: SocketConfig::default(),
+        }
+        .start()
+        .expect("could not connect to socket");
+        let server_addr = server_socket.local_addr();
+        let (_, mut server_receiver) = server_socket.split();
+
+        let msg = b"hello world";
+        client_sender.send(msg, &server_addr).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+
+        let Some((recv_msg, address)) = server_receiver.recv().unwrap() else {
+            panic!("expected to receive a packet");
+        };
+        assert_eq!(address, client_addr);
+        assert_eq!(recv_msg, msg);
+    }
+
+    #[test]
+    fn test_udp_socket_dual_stack() {
+        let (server_socket, _, _, _) = UdpSocketBuilder {
+            local_addr: SocketAddr::from_str("[::]:0").unwrap(),
+            socket_config: SocketConfig::default().with_udp_dual_stack(true),
+        }
+        .start()
+        .expect("could not connect to socket");
+        let server_port = server_socket.local_addr().port();
+        let (mut server_sender, mut server_receiver) = server_socket.split();
+
+        // an IPv4 client
+        let (client_socket, _, _, _) = UdpSocketBuilder {
+            local_addr: SocketAddr::from_str("127.0.0.1:0").unwrap(),
+            socket_config: SocketConfig::default(),
+        }
+        .connect()
+        .expect("could not connect to socket");
+        let client_addr = client_socket.local_addr();
+        let (mut client_sender, mut client_receiver) = client_socket.split();
+
+        let server_addr = SocketAddr::from_str(&format!("127.0.0.1:{server_port}")).unwrap();
+        client_sender.send(b"hello", &server_addr).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+
+        // the server sees the IPv4-mapped address of the client
+        let Some((recv_msg, address)) = server_receiver.recv().unwrap() else {
+            panic!("expected to receive a packet");
+        };
+        assert_eq!(recv_msg, b"hello");
+        let SocketAddr::V6(mapped) = address else {
+            panic!("expected an IPv6 address");
+        };
+        assert_eq!(mapped.ip().to_ipv4_mapped(), Some(Ipv4Addr::LOCALHOST));
+        assert_eq!(mapped.port(), client_addr.port());
+
+        // and can answer to it
+        server_sender.send(b"world", &address).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+        let Some((recv_msg, _)) = client_receiver.recv().unwrap() else {
+            panic!("expected to receive a packet");
+        };
+        assert_eq!(recv_msg, b"world");
+    }
+
     #[test]
     fn test_udp_socket_with_conditioner() {
         use mock_instant::global::MockClock;
@@ -179,15 +788,21 @@ mod tests {
         // let the OS assign a port
         let local_addr = SocketAddr::from_str("127.0.0.1:0").unwrap();
 
-        let (client_socket, _, _, _) = UdpSocketBuilder { local_addr }
-            .connect()
-            .expect("could not connect to socket");
+        let (client_socket, _, _, _) = UdpSocketBuilder {
+            local_addr,
+            socket_config: SocketConfig::default(),
+        }
+        .connect()
+        .expect("could not connect to socket");
         let client_addr = client_socket.local_addr();
         let (mut client_sender, _) = client_socket.split();
 
-        let (server_socket, _, _, _) = UdpSocketBuilder { local_addr }
-            .start()
-            .expect("could not connect to socket");
+        let (server_socket, _, _, _) = UdpSocketBuilder {
+            local_addr,
+            socket_config: SocketConfig::default(),
+        }
+        .start()
+        .expect("could not connect to socket");
         let server_addr = server_socket.local_addr();
         let (_, server_receiver) = server_socket.split();
 
@@ -195,6 +810,8 @@ mod tests {
             incoming_latency: Duration::from_millis(100),
             incoming_jitter: Duration::from_millis(0),
             incoming_loss: 0.0,
+            duplicate_chance: 0.0,
+            reorder_chance: 0.0,
         })
         .wrap(server_receiver);
 
@@ -225,4 +842,178 @@ mod tests {
         assert_eq!(address, client_addr);
         assert_eq!(recv_msg, msg);
     }
+
+    #[test]
+    fn test_udp_socket_gso() {
+        // let the OS assign a port
+        let local_addr = SocketAddr::from_str("127.0.0.1:0").unwrap();
+        let (client_socket, _, _, _) = UdpSocketBuilder {
+            local_addr,
+            socket_config: SocketConfig::default(),
+        }
+        .connect()
+        .expect("could not connect to socket");
+        let client_addr = client_socket.local_addr();
+        let (_, mut client_receiver) = client_socket.split();
+
+        let (server_socket, _, _, _) = UdpSocketBuilder {
+            local_addr,
+            socket_config: SocketConfig::default().with_udp_gso(true),
+        }
+        .start()
+        .expect("could not connect to socket");
+        let (mut server_sender, _) = server_socket.split();
+
+        // packets of the same size are batched, the last packet can be smaller
+        let msgs = [b"hello".as_slice(), b"world", b"bye", b"again!"];
+        for msg in msgs {
+            server_sender.send(msg, &client_addr).unwrap();
+        }
+        server_sender.flush().unwrap();
+
+        // sleep a little to give time to the message to arrive in the socket
+        std::thread::sleep(Duration::from_millis(10));
+
+        // the client receives the individual packets
+        for msg in msgs {
+            let Some((recv_msg, _)) = client_receiver.recv().unwrap() else {
+                panic!("expected to receive a packet");
+            };
+            assert_eq!(recv_msg, msg);
+        }
+        assert!(client_receiver.recv().unwrap().is_none());
+    }
+
+    /// Make the socket invalid by replacing its file descriptor with a file, and
+    /// return the socket and the receiver that is notified of the rebind attempts
+    #[cfg(target_os = "linux")]
+    fn invalid_socket(
+        socket_config: SocketConfig,
+    ) -> (
+        SocketAddr,
+        crate::transport::BoxedReceiver,
+        crate::client::io::ClientIoEventReceiver,
+    ) {
+        use std::os::fd::AsRawFd;
+
+        let (transport, _, events, _) = UdpSocketBuilder {
+            local_addr: SocketAddr::from_str("127.0.0.1:0").unwrap(),
+            socket_config,
+        }
+        .connect()
+        .unwrap();
+        let ClientTransportEnum::UdpSocket(socket) = transport else {
+            unreachable!()
+        };
+        let local_addr = socket.local_addr();
+        let file = std::fs::File::open("/dev/null").unwrap();
+        // SAFETY: both file descriptors are valid
+        unsafe {
+            let udp_socket = socket.receiver.socket.lock().unwrap();
+            let udp_socket = udp_socket.as_ref().unwrap();
+            assert!(libc::dup2(file.as_raw_fd(), udp_socket.as_raw_fd()) >= 0);
+        }
+        let (_, receiver) = socket.split();
+        (local_addr, receiver, events.unwrap())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_udp_socket_rebind() {
+        let (local_addr, mut receiver, events) = invalid_socket(SocketConfig::default());
+        // the socket is bound again to the same address
+        assert!(receiver.recv().unwrap().is_none());
+        let Ok(ClientIoEvent::Rebound(rebound_addr)) = events.try_recv() else {
+            panic!("expected a rebind event");
+        };
+        assert_eq!(rebound_addr, local_addr);
+
+        let (transport, _, _, _) = UdpSocketBuilder {
+            local_addr: SocketAddr::from_str("127.0.0.1:0").unwrap(),
+            socket_config: SocketConfig::default(),
+        }
+        .connect()
+        .unwrap();
+        let (mut sender, _) = transport.split();
+        let msg = b"hello world";
+        sender.send(msg, &local_addr).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+        let Some((recv_msg, _)) = receiver.recv().unwrap() else {
+            panic!("expected to receive a packet");
+        };
+        assert_eq!(recv_msg, msg);
+    }
+
+    /// The socket is bound again to its port while the previous socket still owns it
+    #[test]
+    fn test_udp_socket_rebind_same_port() {
+        let (transport, _, events, _) = UdpSocketBuilder {
+            local_addr: SocketAddr::from_str("127.0.0.1:0").unwrap(),
+            socket_config: SocketConfig::default(),
+        }
+        .connect()
+        .unwrap();
+        let ClientTransportEnum::UdpSocket(mut socket) = transport else {
+            unreachable!()
+        };
+        let local_addr = socket.local_addr();
+        socket
+            .receiver
+            .handle_fatal_error(std::io::Error::other("socket error"))
+            .unwrap();
+        let Ok(ClientIoEvent::Rebound(rebound_addr)) = events.unwrap().try_recv() else {
+            panic!("expected a rebind event");
+        };
+        assert_eq!(rebound_addr, local_addr);
+
+        let (_, mut receiver) = socket.split();
+        let sender = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let msg = b"hello world";
+        sender.send_to(msg, local_addr).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+        let Some((recv_msg, _)) = receiver.recv().unwrap() else {
+            panic!("expected to receive a packet");
+        };
+        assert_eq!(recv_msg, msg);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_udp_socket_rebind_failed() {
+        let (local_addr, mut receiver, events) =
+            invalid_socket(SocketConfig::default().with_rebind(2, Duration::ZERO));
+        // another socket took the port
+        let _other = std::net::UdpSocket::bind(local_addr).unwrap();
+        assert!(receiver.recv().unwrap().is_none());
+        assert!(events.try_recv().is_err());
+        // after the last attempt, the io is disconnected
+        assert!(receiver.recv().is_err());
+        let Ok(ClientIoEvent::Disconnected(_)) = events.try_recv() else {
+            panic!("expected a disconnect event");
+        };
+    }
+
+    #[test]
+    fn test_udp_socket_close() {
+        use crate::client::io::config::ClientTransport;
+        use crate::transport::config::SharedIoConfig;
+        use crate::transport::error::Error;
+
+        let mut io = SharedIoConfig::from_transport(ClientTransport::UdpSocket(
+            SocketAddr::from_str("127.0.0.1:0").unwrap(),
+        ))
+        .connect()
+        .unwrap();
+        let local_addr = io.local_addr();
+        // the port is still in use
+        assert!(std::net::UdpSocket::bind(local_addr).is_err());
+
+        io.close().unwrap();
+        assert!(matches!(
+            io.send(b"hello", &local_addr),
+            Err(Error::NotConnected)
+        ));
+        // the port is released even though the io is not dropped
+        assert!(std::net::UdpSocket::bind(local_addr).is_ok());
+    }
 }
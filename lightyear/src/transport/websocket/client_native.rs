@@ -37,6 +37,8 @@ use crate::transport::{
 
 pub(crate) struct WebSocketClientSocketBuilder {
     pub(crate) server_addr: SocketAddr,
+    /// Disable Nagle's algorithm on the underlying TCP stream
+    pub(crate) tcp_nodelay: bool,
 }
 
 impl ClientTransportBuilder for WebSocketClientSocketBuilder {
@@ -66,7 +68,7 @@ impl ClientTransportBuilder for WebSocketClientSocketBuilder {
                 let ws_stream = match connect_async_with_config(
                     format!("ws://{}/", self.server_addr),
                     None,
-                    true,
+                    self.tcp_nodelay,
                 )
                 .await
                 {
@@ -201,6 +203,10 @@ impl PacketReceiver for WebSocketClientSocketReceiver {
         match self.clientbound_rx.try_recv() {
             Ok(msg) => match msg {
                 Message::Binary(buf) => {
+                    if buf.len() > MTU {
+                        debug!(len = ?buf.len(), "dropping received packet larger than the MTU");
+                        return Ok(None);
+                    }
                     self.buffer[..buf.len()].copy_from_slice(&buf);
                     Ok(Some((&mut self.buffer[..buf.len()], self.server_addr)))
                 }
@@ -160,6 +160,10 @@ impl PacketReceiver for WebSocketClientSocketReceiver {
     fn recv(&mut self) -> Result<Option<(&mut [u8], SocketAddr)>> {
         match self.clientbound_rx.try_recv() {
             Ok(msg) => {
+                if msg.len() > MTU {
+                    debug!(len = ?msg.len(), "dropping received packet larger than the MTU");
+                    return Ok(None);
+                }
                 self.buffer[..msg.len()].copy_from_slice(&msg);
                 Ok(Some((&mut self.buffer[..msg.len()], self.server_addr)))
             }
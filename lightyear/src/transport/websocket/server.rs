@@ -28,6 +28,8 @@ use crate::transport::{BoxedReceiver, BoxedSender, PacketReceiver, PacketSender,
 
 pub(crate) struct WebSocketServerSocketBuilder {
     pub(crate) server_addr: SocketAddr,
+    /// Disable Nagle's algorithm on the TCP streams of the clients
+    pub(crate) tcp_nodelay: bool,
 }
 
 impl ServerTransportBuilder for WebSocketServerSocketBuilder {
@@ -94,6 +96,9 @@ impl ServerTransportBuilder for WebSocketServerSocketBuilder {
                             }
                         }
                         Ok((stream, addr)) = listener.accept() => {
+                            if let Err(e) = stream.set_nodelay(self.tcp_nodelay) {
+                                error!("Could not set TCP_NODELAY on the stream of client {addr:?}: {e:?}");
+                            }
                             let clientbound_tx_map = clientbound_tx_map.clone();
                             let serverbound_tx = serverbound_tx.clone();
                             let task = IoTaskPool::get().spawn(Compat::new(
@@ -260,6 +265,10 @@ impl PacketReceiver for WebSocketServerSocketReceiver {
         match self.serverbound_rx.try_recv() {
             Ok((addr, msg)) => match msg {
                 Message::Binary(buf) => {
+                    if buf.len() > MTU {
+                        debug!(len = ?buf.len(), "dropping received packet larger than the MTU");
+                        return Ok(None);
+                    }
                     self.buffer[..buf.len()].copy_from_slice(&buf);
                     Ok(Some((&mut self.buffer[..buf.len()], addr)))
                 }
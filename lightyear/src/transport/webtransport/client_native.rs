@@ -207,6 +207,10 @@ impl PacketReceiver for WebTransportClientPacketReceiver {
     fn recv(&mut self) -> Result<Option<(&mut [u8], SocketAddr)>> {
         match self.from_server_receiver.try_recv() {
             Ok(data) => {
+                if data.len() > MTU {
+                    debug!(len = ?data.len(), "dropping received packet larger than the MTU");
+                    return Ok(None);
+                }
                 // convert from datagram to payload via xwt
                 self.buffer[..data.len()].copy_from_slice(data.payload().as_ref());
                 Ok(Some((&mut self.buffer[..data.len()], self.server_addr)))
@@ -229,6 +229,10 @@ impl PacketReceiver for WebTransportClientPacketReceiver {
             Ok(datagram) => {
                 // convert from datagram to payload via xwt
                 let data = datagram.as_slice();
+                if data.len() > MTU {
+                    debug!(len = ?data.len(), "dropping received packet larger than the MTU");
+                    return Ok(None);
+                }
                 self.buffer[..data.len()].copy_from_slice(data);
                 Ok(Some((&mut self.buffer[..data.len()], self.server_addr)))
             }
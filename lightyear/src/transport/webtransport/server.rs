@@ -267,6 +267,10 @@ impl PacketReceiver for WebTransportServerSocketReceiver {
     fn recv(&mut self) -> Result<Option<(&mut [u8], SocketAddr)>> {
         match self.from_client_receiver.try_recv() {
             Ok((data, addr)) => {
+                if data.len() > MTU {
+                    debug!(len = ?data.len(), "dropping received packet larger than the MTU");
+                    return Ok(None);
+                }
                 self.buffer[..data.len()].copy_from_slice(data.payload().as_ref());
                 Ok(Some((&mut self.buffer[..data.len()], addr)))
             }